@@ -0,0 +1,9 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="solid" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+ <image source="../tilesheet.png" width="32" height="32"/>
+ <tile id="0">
+  <objectgroup draworder="index" id="2">
+   <object id="1" template="solid.tx" x="0" y="0"/>
+  </objectgroup>
+ </tile>
+</tileset>
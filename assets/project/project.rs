@@ -0,0 +1,140 @@
+// Generated from the custom types of a Tiled project. Changes will be overwritten.
+
+/// The `Kind` enum of the project.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Kind {
+    /// The `Walker` value.
+    #[default]
+    Walker,
+    /// The `Flyer` value.
+    Flyer,
+}
+
+impl tiled::FromPropertyValue for Kind {
+    fn from_property_value(value: &tiled::PropertyValue) -> tiled::Result<Self> {
+        match value {
+            tiled::PropertyValue::StringValue(value) => match value.as_str() {
+                "Walker" => Ok(Kind::Walker),
+                "Flyer" => Ok(Kind::Flyer),
+                _ => Err(tiled::Error::InvalidPropertyValue {
+                    description: format!("unknown value `{}` of enum `{}`", value, "Kind"),
+                }),
+            },
+            tiled::PropertyValue::IntValue(0) => Ok(Kind::Walker),
+            tiled::PropertyValue::IntValue(1) => Ok(Kind::Flyer),
+            _ => Err(tiled::Error::InvalidPropertyValue {
+                description: "expected a value of enum `Kind`".to_owned(),
+            }),
+        }
+    }
+}
+
+/// The `Layers` enum of the project, whose values are flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Layers(pub u32);
+
+impl Layers {
+    /// The `Ground` flag.
+    pub const GROUND: Layers = Layers(0x1);
+    /// The `Air` flag.
+    pub const AIR: Layers = Layers(0x2);
+    /// The `Water` flag.
+    pub const WATER: Layers = Layers(0x4);
+
+    /// Returns whether all the flags of `other` are set.
+    pub fn contains(&self, other: Layers) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl tiled::FromPropertyValue for Layers {
+    fn from_property_value(value: &tiled::PropertyValue) -> tiled::Result<Self> {
+        match value {
+            tiled::PropertyValue::StringValue(value) => value
+                .split(',')
+                .filter(|value| !value.is_empty())
+                .try_fold(Layers(0), |flags, value| {
+                    let flag = match value {
+                        "Ground" => Layers::GROUND,
+                        "Air" => Layers::AIR,
+                        "Water" => Layers::WATER,
+                        _ => {
+                            return Err(tiled::Error::InvalidPropertyValue {
+                                description: format!("unknown value `{}` of enum `{}`", value, "Layers"),
+                            })
+                        }
+                    };
+                    Ok(Layers(flags.0 | flag.0))
+                }),
+            tiled::PropertyValue::IntValue(value) => Ok(Layers(*value as u32)),
+            _ => Err(tiled::Error::InvalidPropertyValue {
+                description: "expected a value of enum `Layers`".to_owned(),
+            }),
+        }
+    }
+}
+
+/// The `Stats` class of the project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub armor: f32,
+    pub health: i32,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for Stats {
+    fn default() -> Self {
+        Stats {
+            armor: 0.0,
+            health: 10,
+        }
+    }
+}
+
+impl tiled::FromProperties for Stats {
+    fn update_from_properties(&mut self, properties: &tiled::Properties) -> tiled::Result<()> {
+        tiled::FromPropertyValue::update_from_property(&mut self.armor, properties, "armor")?;
+        tiled::FromPropertyValue::update_from_property(&mut self.health, properties, "health")?;
+        Ok(())
+    }
+}
+
+/// The `Spawn` class of the project.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spawn {
+    pub count: i32,
+    pub kind: Kind,
+    pub layers: Layers,
+    pub sound: String,
+    pub stats: Stats,
+    pub target: u32,
+    pub tint: tiled::Color,
+}
+
+#[allow(clippy::derivable_impls)]
+impl Default for Spawn {
+    fn default() -> Self {
+        Spawn {
+            count: 1,
+            kind: Kind::Walker,
+            layers: Layers(3),
+            sound: "spawn.ogg".to_owned(),
+            stats: Stats { health: 50, ..Default::default() },
+            target: 0,
+            tint: tiled::Color::from_argb_u32(0x00000000),
+        }
+    }
+}
+
+impl tiled::FromProperties for Spawn {
+    fn update_from_properties(&mut self, properties: &tiled::Properties) -> tiled::Result<()> {
+        tiled::FromPropertyValue::update_from_property(&mut self.count, properties, "count")?;
+        tiled::FromPropertyValue::update_from_property(&mut self.kind, properties, "kind")?;
+        tiled::FromPropertyValue::update_from_property(&mut self.layers, properties, "layers")?;
+        tiled::FromPropertyValue::update_from_property(&mut self.sound, properties, "sound")?;
+        tiled::FromProperties::update_from_class_property(&mut self.stats, properties, "stats")?;
+        tiled::FromPropertyValue::update_from_property(&mut self.target, properties, "target")?;
+        tiled::FromPropertyValue::update_from_property(&mut self.tint, properties, "tint")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,5 @@
+<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" tiledversion="1.10.2" name="wide" tilewidth="32" tileheight="16" tilecount="4" columns="2" tilerendersize="grid" fillmode="preserve-aspect-fit">
+ <tileoffset x="1" y="2"/>
+ <image source="tilesheet.png" width="64" height="32"/>
+</tileset>
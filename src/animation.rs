@@ -12,6 +12,7 @@ use crate::{
 /// [frame]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-frame
 /// [TMX tile animation]: https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#animation
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct Frame {
     /// The local ID of a tile within the parent tileset.
     pub tile_id: u32,
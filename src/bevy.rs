@@ -0,0 +1,225 @@
+//! Integration with the [Bevy](https://bevyengine.org) asset system. Requires the `bevy` feature.
+//!
+//! Adding [`TiledPlugin`] to an app allows loading `.tmx` files through the `AssetServer` as
+//! [`TiledMap`] assets. The tilesets and templates used by a map are read through Bevy's asset
+//! sources as well, and are tracked as dependencies of the map, so that changing any of them
+//! reloads the map when hot reloading is enabled.
+//!
+//! With the `world` feature too, `.world` files are loaded as [`TiledWorld`] assets, which load
+//! the maps listed in them as dependencies.
+//!
+//! Images are not loaded by this crate; Use the paths in [`Image::source`](crate::Image::source)
+//! to load them with the `AssetServer` instead.
+//!
+//! ## Example
+//! ```no_run
+//! use bevy_app::App;
+//! use bevy_asset::{AssetServer, Handle};
+//! use tiled::bevy::{TiledMap, TiledPlugin};
+//!
+//! # fn add_plugin(app: &mut App) {
+//! app.add_plugins(TiledPlugin);
+//! # }
+//! # fn load(asset_server: &AssetServer) {
+//! let map: Handle<TiledMap> = asset_server.load("maps/level.tmx");
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::Cursor,
+    ops::Deref,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use bevy_app::{App, Plugin};
+#[cfg(feature = "world")]
+use bevy_asset::Handle;
+use bevy_asset::{io::Reader, Asset, AssetApp, AssetLoader, LoadContext};
+use bevy_reflect::TypePath;
+
+use crate::{
+    util::normalize_path, DefaultResourceCache, Error, Loader, Map, ResourceReader, Result,
+};
+
+#[cfg(feature = "world")]
+use crate::World;
+
+/// A plugin that registers [`TiledMap`] as an asset, along with [`TmxLoader`], and with the
+/// `world` feature, [`TiledWorld`] along with [`WorldLoader`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TiledPlugin;
+
+impl Plugin for TiledPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TiledMap>()
+            .register_asset_loader(TmxLoader);
+        #[cfg(feature = "world")]
+        app.init_asset::<TiledWorld>()
+            .register_asset_loader(WorldLoader);
+    }
+}
+
+/// A [`Map`] loaded as a Bevy asset.
+#[derive(Asset, TypePath, Debug)]
+pub struct TiledMap(pub Map);
+
+impl Deref for TiledMap {
+    type Target = Map;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// An [`AssetLoader`] for `.tmx` files, which produces [`TiledMap`]s.
+#[derive(TypePath, Debug, Clone, Copy, Default)]
+pub struct TmxLoader;
+
+impl AssetLoader for TmxLoader {
+    type Asset = TiledMap;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<TiledMap> {
+        let path = load_context.path().path().to_owned();
+        let bytes = read_all(reader, &path).await?;
+
+        let mut reader = PreloadedReader::default();
+        reader.files.insert(path.clone(), bytes.into());
+        let mut cache = DefaultResourceCache::new();
+
+        // The files a map depends on are only known while parsing it, but they can't be read from
+        // the asset sources synchronously. So each time the parser asks for a file that hasn't been
+        // read yet, it is read and parsing starts over, reusing the tilesets and templates that
+        // were already cached.
+        loop {
+            let mut loader = Loader::with_cache_and_reader(cache, reader);
+            let result = loader.load_tmx_map(&path);
+            (cache, reader) = loader.into_inner();
+
+            let missing = match reader.missing.take() {
+                Some(missing) if result.is_err() && !reader.files.contains_key(&missing) => missing,
+                _ => return result.map(TiledMap),
+            };
+            // Asset paths can't contain `.` and `..` components
+            let bytes = load_context
+                .read_asset_bytes(normalize_path(&missing))
+                .await
+                .map_err(|err| Error::ResourceLoadingError {
+                    path: missing.clone(),
+                    err: Box::new(err),
+                })?;
+            reader.files.insert(missing, bytes.into());
+        }
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx"]
+    }
+}
+
+/// A [`World`] loaded as a Bevy asset, along with the maps listed in it. Requires the `world`
+/// feature.
+#[cfg(feature = "world")]
+#[derive(Asset, TypePath, Debug)]
+pub struct TiledWorld {
+    /// The world.
+    pub world: World,
+    /// The maps of [`World::maps`], in the same order, which are dependencies of the world.
+    ///
+    /// Maps placed by the [patterns](World::patterns) of the world aren't loaded, since asset
+    /// sources can't be searched for them; Load the ones needed with the `AssetServer`, using
+    /// [`World::match_path()`] to place them.
+    #[dependency]
+    pub maps: Vec<Handle<TiledMap>>,
+}
+
+#[cfg(feature = "world")]
+impl Deref for TiledWorld {
+    type Target = World;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.world
+    }
+}
+
+/// An [`AssetLoader`] for `.world` files, which produces [`TiledWorld`]s. Requires the `world`
+/// feature.
+#[cfg(feature = "world")]
+#[derive(TypePath, Debug, Clone, Copy, Default)]
+pub struct WorldLoader;
+
+#[cfg(feature = "world")]
+impl AssetLoader for WorldLoader {
+    type Asset = TiledWorld;
+    type Settings = ();
+    type Error = Error;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<TiledWorld> {
+        let path = load_context.path().path().to_owned();
+        let bytes = read_all(reader, &path).await?;
+        let world = World::parse(&path, &bytes)?;
+        let maps = world
+            .maps
+            .iter()
+            .map(|map| load_context.load(normalize_path(&world.directory().join(&map.filename))))
+            .collect();
+        Ok(TiledWorld { world, maps })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["world"]
+    }
+}
+
+async fn read_all(reader: &mut dyn Reader, path: &Path) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    reader
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    Ok(bytes)
+}
+
+/// A [`ResourceReader`] that only provides files that have already been read, remembering the
+/// path of the last one that wasn't.
+#[derive(Default)]
+struct PreloadedReader {
+    files: HashMap<PathBuf, Arc<[u8]>>,
+    missing: Option<PathBuf>,
+}
+
+impl ResourceReader for PreloadedReader {
+    type Resource = Cursor<Arc<[u8]>>;
+    type Error = std::io::Error;
+
+    fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        match self.files.get(path) {
+            Some(bytes) => Ok(Cursor::new(bytes.clone())),
+            None => {
+                self.missing = Some(path.to_owned());
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "file has not been read from the asset source yet",
+                ))
+            }
+        }
+    }
+}
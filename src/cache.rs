@@ -1,10 +1,13 @@
 use std::{
     collections::HashMap,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
 };
 
-use crate::{Template, Tileset};
+use crate::{util::normalize_path, Template, Tileset};
 
 /// A reference type that is used to refer to a resource. For the owned variant, see [`ResourcePathBuf`].
 pub type ResourcePath = Path;
@@ -47,41 +50,226 @@ pub trait ResourceCache {
     fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>>;
     /// Insert a new template into the cache.
     fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>);
+
+    /// Iterates over the cached tilesets along with their path, in no particular order, e.g. to
+    /// create the textures of every tileset loaded so far.
+    ///
+    /// The default implementation iterates over nothing, which suits caches that don't keep
+    /// resources.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, ResourceCache};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
+    ///
+    /// for (path, tileset) in loader.cache().tilesets() {
+    ///     println!("{} has {} tiles", path.display(), tileset.tilecount);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn tilesets(&self) -> CachedIter<'_, Tileset> {
+        Box::new(std::iter::empty())
+    }
+
+    /// Iterates over the cached templates along with their path, in no particular order.
+    ///
+    /// The default implementation iterates over nothing, which suits caches that don't keep
+    /// resources.
+    fn templates(&self) -> CachedIter<'_, Template> {
+        Box::new(std::iter::empty())
+    }
+
+    /// Returns how many lookups found what they looked for so far, and how many didn't.
+    ///
+    /// The default implementation doesn't count lookups and always returns zeros.
+    fn stats(&self) -> CacheStats {
+        CacheStats::default()
+    }
+
+    /// Removes the tileset or template cached at `path`, if any, so that it gets loaded again
+    /// the next time a map uses it, e.g. after it changed on disk. Templates that use a tileset
+    /// removed this way are removed as well, since they refer to the old one.
+    ///
+    /// The default implementation does nothing, which suits caches that don't keep resources.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, ResourceCache};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_some());
+    ///
+    /// loader.cache_mut().invalidate("assets/tilesheet.tsx");
+    /// assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn invalidate(&mut self, _path: impl AsRef<ResourcePath>) {}
+
+    /// Only keeps the cached resources for which `keep` returns true, given their path.
+    ///
+    /// Unlike [`Self::invalidate()`], templates are kept even if the tileset they use isn't.
+    /// The default implementation does nothing, which suits caches that don't keep resources.
+    fn retain(&mut self, _keep: impl FnMut(&ResourcePath, CachedResource<'_>) -> bool) {}
+}
+
+/// An iterator over the resources of one type held by a [`ResourceCache`], along with their
+/// path, as returned by [`ResourceCache::tilesets()`] and [`ResourceCache::templates()`].
+pub type CachedIter<'a, T> = Box<dyn Iterator<Item = (&'a ResourcePath, &'a Arc<T>)> + 'a>;
+
+/// A resource held by a [`ResourceCache`], given to [`ResourceCache::retain()`].
+#[derive(Debug, Clone, Copy)]
+pub enum CachedResource<'a> {
+    /// A tileset.
+    Tileset(&'a Arc<Tileset>),
+    /// A template.
+    Template(&'a Arc<Template>),
+}
+
+/// The number of lookups of a [`ResourceCache`] that found what they looked for, and of those
+/// that didn't, as returned by [`ResourceCache::stats()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CacheStats {
+    /// The lookups that found a cached resource.
+    pub hits: usize,
+    /// The lookups that found nothing, after which the resource is usually loaded.
+    pub misses: usize,
 }
 
 /// A cache that identifies resources by their path, storing them in a [`HashMap`].
+///
+/// Paths are normalized before being used as keys, by removing `.` components and `..` ones along
+/// with the directory they go up from, so that resources inserted manually are found whichever
+/// way maps refer to them. For instance, a tileset generated at runtime and inserted at
+/// `generated/atlas.tsx` is the one used by a map at `maps/level.tmx` referring to
+/// `../generated/atlas.tsx`, even though no such file exists:
+///
+/// ```
+/// use std::io::Cursor;
+/// use std::path::Path;
+/// use std::sync::Arc;
+/// use tiled::{Image, Loader, ResourceCache, TilesetBuilder};
+///
+/// # fn main() -> tiled::Result<()> {
+/// let reader = |path: &Path| -> std::io::Result<_> {
+///     match path.to_str() {
+///         Some("maps/level.tmx") => Ok(Cursor::new(
+///             r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+///                  <tileset firstgid="1" source="../generated/atlas.tsx"/>
+///                  <layer id="1" name="ground" width="1" height="1"><data encoding="csv">1</data></layer>
+///                </map>"#,
+///         )),
+///         _ => Err(std::io::ErrorKind::NotFound.into()),
+///     }
+/// };
+/// let image = Image {
+///     source: "generated/atlas.png".into(),
+///     width: 64,
+///     height: 32,
+///     transparent_colour: None,
+/// };
+/// let atlas = TilesetBuilder::new("atlas", image, 32, 32)
+///     .source("generated/atlas.tsx")
+///     .build()?;
+///
+/// let mut loader = Loader::with_reader(reader);
+/// loader
+///     .cache_mut()
+///     .insert_tileset("generated/atlas.tsx", Arc::new(atlas));
+/// let map = loader.load_tmx_map("maps/level.tmx")?;
+/// assert_eq!(map.tilesets()[0].name, "atlas");
+/// # Ok(())
+/// # }
+/// ```
 #[derive(Debug, Default)]
 pub struct DefaultResourceCache {
     /// The tilesets cached until now.
     pub tilesets: HashMap<ResourcePathBuf, Arc<Tileset>>,
     /// The templates cached until now.
     pub templates: HashMap<ResourcePathBuf, Arc<Template>>,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
 }
 
 impl DefaultResourceCache {
     /// Creates an empty [`DefaultResourceCache`].
     pub fn new() -> Self {
-        Self {
-            tilesets: HashMap::new(),
-            templates: HashMap::new(),
-        }
+        Self::default()
+    }
+
+    fn count<T>(&self, resource: Option<&Arc<T>>) -> Option<Arc<T>> {
+        let counter = match resource {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        resource.cloned()
     }
 }
 
 impl ResourceCache for DefaultResourceCache {
     fn get_tileset(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Tileset>> {
-        self.tilesets.get(path.as_ref()).map(Clone::clone)
+        self.count(self.tilesets.get(&normalize_path(path.as_ref())))
     }
 
     fn insert_tileset(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Tileset>) {
-        self.tilesets.insert(path.as_ref().to_path_buf(), tileset);
+        self.tilesets.insert(normalize_path(path.as_ref()), tileset);
     }
 
     fn get_template(&self, path: impl AsRef<ResourcePath>) -> Option<Arc<Template>> {
-        self.templates.get(path.as_ref()).map(Clone::clone)
+        self.count(self.templates.get(&normalize_path(path.as_ref())))
     }
 
     fn insert_template(&mut self, path: impl AsRef<ResourcePath>, tileset: Arc<Template>) {
-        self.templates.insert(path.as_ref().to_path_buf(), tileset);
+        self.templates
+            .insert(normalize_path(path.as_ref()), tileset);
+    }
+
+    fn tilesets(&self) -> CachedIter<'_, Tileset> {
+        Box::new(
+            self.tilesets
+                .iter()
+                .map(|(path, tileset)| (path.as_path(), tileset)),
+        )
+    }
+
+    fn templates(&self) -> CachedIter<'_, Template> {
+        Box::new(
+            self.templates
+                .iter()
+                .map(|(path, template)| (path.as_path(), template)),
+        )
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+
+    fn invalidate(&mut self, path: impl AsRef<ResourcePath>) {
+        let path = normalize_path(path.as_ref());
+        self.tilesets.remove(&path);
+        self.templates.remove(&path);
+        self.templates.retain(|_, template| {
+            template
+                .tileset
+                .as_ref()
+                .is_none_or(|tileset| normalize_path(&tileset.source) != path)
+        });
+    }
+
+    fn retain(&mut self, mut keep: impl FnMut(&ResourcePath, CachedResource<'_>) -> bool) {
+        self.tilesets
+            .retain(|path, tileset| keep(path, CachedResource::Tileset(tileset)));
+        self.templates
+            .retain(|path, template| keep(path, CachedResource::Template(template)));
     }
 }
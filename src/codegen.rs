@@ -0,0 +1,592 @@
+//! Generation of Rust types from the custom types of a [`Project`]. Requires the `project`
+//! feature.
+//!
+//! [`generate_rust()`] turns every class of a project into a struct implementing
+//! [`FromProperties`](crate::FromProperties), and every enum into a Rust enum, or a set of bit
+//! flags for enums whose values are flags, implementing
+//! [`FromPropertyValue`](crate::FromPropertyValue). The default values of the members of classes
+//! become the [`Default`] implementations of the structs, so that reading properties with
+//! [`FromProperties::from_properties()`](crate::FromProperties::from_properties) gives the values
+//! Tiled shows.
+//!
+//! Names are converted to Rust identifiers: types and enum values to `UpperCamelCase` and class
+//! members to `snake_case`. Members of classes the project doesn't define, and members that
+//! would make a class contain itself, are left out.
+//!
+//! ## Example
+//! The types are meant to be generated by a build script, which keeps them in sync with the
+//! project whenever it changes:
+//! ```no_run
+//! // In the main function of build.rs
+//! let project = tiled::Loader::new()
+//!     .load_project("assets/game.tiled-project")
+//!     .unwrap();
+//! let out_dir = std::env::var("OUT_DIR").unwrap();
+//! std::fs::write(
+//!     std::path::Path::new(&out_dir).join("tiled_types.rs"),
+//!     tiled::codegen::generate_rust(&project),
+//! )
+//! .unwrap();
+//! println!("cargo:rerun-if-changed=assets/game.tiled-project");
+//! ```
+//! The game then includes them with
+//! `include!(concat!(env!("OUT_DIR"), "/tiled_types.rs"));`.
+
+use std::{collections::HashSet, fmt::Write};
+
+use crate::{
+    ClassMember, ClassType, EnumStorageType, EnumType, Project, PropertyType, PropertyValue,
+};
+
+/// Returns the Rust source of the types generated from the custom types of `project`, in the
+/// order the project defines them. See the [module documentation](self).
+pub fn generate_rust(project: &Project) -> String {
+    let mut out = String::from(
+        "// Generated from the custom types of a Tiled project. Changes will be overwritten.\n",
+    );
+    for property_type in &project.property_types {
+        out.push('\n');
+        match property_type {
+            PropertyType::Class(class) => write_class(project, class, &mut out),
+            // Enums without values can't have a default value either, so they are given as
+            // empty sets of flags
+            PropertyType::Enum(enum_type)
+                if enum_type.values_as_flags || enum_type.values.is_empty() =>
+            {
+                write_flags(enum_type, &mut out)
+            }
+            PropertyType::Enum(enum_type) => write_enum(enum_type, &mut out),
+        }
+    }
+    out
+}
+
+/// The Rust type a class member is read into.
+enum FieldType<'a> {
+    Bool,
+    Int,
+    Float,
+    String,
+    Color,
+    Object,
+    Enum(&'a EnumType),
+    Class(&'a ClassType),
+}
+
+/// A class member along with the field it becomes in the generated struct.
+struct Field<'a> {
+    member: &'a ClassMember,
+    name: String,
+    field_type: FieldType<'a>,
+}
+
+fn write_class(project: &Project, class: &ClassType, out: &mut String) {
+    let name = type_name(&class.name);
+    let fields = fields(project, class);
+
+    writeln!(out, "/// The `{}` class of the project.", class.name).unwrap();
+    writeln!(out, "#[derive(Debug, Clone, PartialEq)]").unwrap();
+    if fields.is_empty() {
+        writeln!(out, "pub struct {} {{}}", name).unwrap();
+    } else {
+        writeln!(out, "pub struct {} {{", name).unwrap();
+        for field in &fields {
+            writeln!(
+                out,
+                "    pub {}: {},",
+                field.name,
+                rust_type(&field.field_type)
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+    }
+
+    // The defaults of members may all be the defaults of their types
+    writeln!(out, "\n#[allow(clippy::derivable_impls)]").unwrap();
+    writeln!(out, "impl Default for {} {{", name).unwrap();
+    writeln!(out, "    fn default() -> Self {{").unwrap();
+    if fields.is_empty() {
+        writeln!(out, "        {} {{}}", name).unwrap();
+    } else {
+        writeln!(out, "        {} {{", name).unwrap();
+        for field in &fields {
+            writeln!(
+                out,
+                "            {}: {},",
+                field.name,
+                value_expr(project, &field.field_type, &field.member.value)
+            )
+            .unwrap();
+        }
+        writeln!(out, "        }}").unwrap();
+    }
+    writeln!(out, "    }}\n}}").unwrap();
+
+    writeln!(out, "\nimpl tiled::FromProperties for {} {{", name).unwrap();
+    let properties = if fields.is_empty() {
+        "_properties"
+    } else {
+        "properties"
+    };
+    writeln!(
+        out,
+        "    fn update_from_properties(&mut self, {}: &tiled::Properties) -> tiled::Result<()> {{",
+        properties
+    )
+    .unwrap();
+    for field in &fields {
+        let update = match field.field_type {
+            FieldType::Class(_) => "tiled::FromProperties::update_from_class_property",
+            _ => "tiled::FromPropertyValue::update_from_property",
+        };
+        writeln!(
+            out,
+            "        {}(&mut self.{}, properties, {:?})?;",
+            update, field.name, &*field.member.name
+        )
+        .unwrap();
+    }
+    writeln!(out, "        Ok(())\n    }}\n}}").unwrap();
+}
+
+fn write_enum(enum_type: &EnumType, out: &mut String) {
+    let name = type_name(&enum_type.name);
+    let mut used = HashSet::new();
+    let variants: Vec<String> = enum_type
+        .values
+        .iter()
+        .map(|value| unique(type_name(value), &mut used))
+        .collect();
+
+    writeln!(out, "/// The `{}` enum of the project.", enum_type.name).unwrap();
+    writeln!(
+        out,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]"
+    )
+    .unwrap();
+    writeln!(out, "pub enum {} {{", name).unwrap();
+    for (index, (variant, value)) in variants.iter().zip(&enum_type.values).enumerate() {
+        writeln!(out, "    /// The `{}` value.", value).unwrap();
+        if index == 0 {
+            writeln!(out, "    #[default]").unwrap();
+        }
+        writeln!(out, "    {},", variant).unwrap();
+    }
+    writeln!(out, "}}").unwrap();
+
+    writeln!(out, "\nimpl tiled::FromPropertyValue for {} {{", name).unwrap();
+    writeln!(
+        out,
+        "    fn from_property_value(value: &tiled::PropertyValue) -> tiled::Result<Self> {{"
+    )
+    .unwrap();
+    writeln!(out, "        match value {{").unwrap();
+    writeln!(
+        out,
+        "            tiled::PropertyValue::StringValue(value) => match value.as_str() {{"
+    )
+    .unwrap();
+    for (variant, value) in variants.iter().zip(&enum_type.values) {
+        writeln!(
+            out,
+            "                {:?} => Ok({}::{}),",
+            value, name, variant
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "                _ => Err(tiled::Error::InvalidPropertyValue {{\n                    \
+         description: format!(\"unknown value `{{}}` of enum `{{}}`\", value, {:?}),\n                }}),",
+        enum_type.name
+    )
+    .unwrap();
+    writeln!(out, "            }},").unwrap();
+    for (index, variant) in variants.iter().enumerate() {
+        writeln!(
+            out,
+            "            tiled::PropertyValue::IntValue({}) => Ok({}::{}),",
+            index, name, variant
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "            _ => Err(tiled::Error::InvalidPropertyValue {{\n                \
+         description: {:?}.to_owned(),\n            }}),",
+        format!("expected a value of enum `{}`", enum_type.name)
+    )
+    .unwrap();
+    writeln!(out, "        }}\n    }}\n}}").unwrap();
+}
+
+fn write_flags(enum_type: &EnumType, out: &mut String) {
+    let name = type_name(&enum_type.name);
+    let mut used = HashSet::new();
+    let flags: Vec<(String, &String)> = enum_type
+        .values
+        .iter()
+        .take(32)
+        .map(|value| (unique(constant_name(value), &mut used), value))
+        .collect();
+
+    writeln!(
+        out,
+        "/// The `{}` enum of the project, whose values are flags.",
+        enum_type.name
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]"
+    )
+    .unwrap();
+    writeln!(out, "pub struct {}(pub u32);", name).unwrap();
+
+    writeln!(out, "\nimpl {} {{", name).unwrap();
+    for (index, (constant, value)) in flags.iter().enumerate() {
+        writeln!(out, "    /// The `{}` flag.", value).unwrap();
+        writeln!(
+            out,
+            "    pub const {}: {} = {}({:#x});",
+            constant,
+            name,
+            name,
+            1u32 << index
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "\n    /// Returns whether all the flags of `other` are set."
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "    pub fn contains(&self, other: {}) -> bool {{\n        self.0 & other.0 == other.0\n    }}",
+        name
+    )
+    .unwrap();
+    writeln!(out, "}}").unwrap();
+
+    writeln!(out, "\nimpl tiled::FromPropertyValue for {} {{", name).unwrap();
+    writeln!(
+        out,
+        "    fn from_property_value(value: &tiled::PropertyValue) -> tiled::Result<Self> {{"
+    )
+    .unwrap();
+    writeln!(out, "        match value {{").unwrap();
+    writeln!(
+        out,
+        "            tiled::PropertyValue::StringValue(value) => value\n                \
+         .split(',')\n                .filter(|value| !value.is_empty())\n                \
+         .try_fold({}(0), |flags, value| {{\n                    \
+         let flag = match value {{",
+        name
+    )
+    .unwrap();
+    for (constant, value) in &flags {
+        writeln!(
+            out,
+            "                        {:?} => {}::{},",
+            value, name, constant
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "                        _ => {{\n                            \
+         return Err(tiled::Error::InvalidPropertyValue {{\n                                \
+         description: format!(\"unknown value `{{}}` of enum `{{}}`\", value, {:?}),\n                            \
+         }})\n                        }}\n                    }};\n                    \
+         Ok({}(flags.0 | flag.0))\n                }}),",
+        enum_type.name, name
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "            tiled::PropertyValue::IntValue(value) => Ok({}(*value as u32)),",
+        name
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "            _ => Err(tiled::Error::InvalidPropertyValue {{\n                \
+         description: {:?}.to_owned(),\n            }}),",
+        format!("expected a value of enum `{}`", enum_type.name)
+    )
+    .unwrap();
+    writeln!(out, "        }}\n    }}\n}}").unwrap();
+}
+
+/// Returns the fields of the struct generated for `class`.
+fn fields<'a>(project: &'a Project, class: &'a ClassType) -> Vec<Field<'a>> {
+    let mut used = HashSet::new();
+    class
+        .members
+        .iter()
+        .filter_map(|member| {
+            let field_type = field_type(project, member)?;
+            if let FieldType::Class(member_class) = field_type {
+                if contains_class(project, member_class, &class.name, &mut Vec::new()) {
+                    return None;
+                }
+            }
+            Some(Field {
+                member,
+                name: unique(field_name(&member.name), &mut used),
+                field_type,
+            })
+        })
+        .collect()
+}
+
+/// Returns the type a member is read into, or [`None`] if it is a class the project doesn't
+/// define.
+fn field_type<'a>(project: &'a Project, member: &ClassMember) -> Option<FieldType<'a>> {
+    let enum_type = member
+        .property_type
+        .as_deref()
+        .and_then(|name| project.enum_type(name));
+    Some(match &member.value {
+        PropertyValue::BoolValue(_) => FieldType::Bool,
+        PropertyValue::IntValue(_) | PropertyValue::StringValue(_) if enum_type.is_some() => {
+            FieldType::Enum(enum_type?)
+        }
+        PropertyValue::IntValue(_) => FieldType::Int,
+        PropertyValue::FloatValue(_) => FieldType::Float,
+        PropertyValue::StringValue(_) | PropertyValue::FileValue(_) => FieldType::String,
+        PropertyValue::ColorValue(_) => FieldType::Color,
+        PropertyValue::ObjectValue(_) => FieldType::Object,
+        PropertyValue::ClassValue { property_type, .. } => {
+            FieldType::Class(project.class(property_type)?)
+        }
+        PropertyValue::Custom(_) => FieldType::String,
+    })
+}
+
+/// Returns whether `class` is or contains the class named `name`, directly or through the
+/// members of the classes it contains.
+fn contains_class<'a>(
+    project: &'a Project,
+    class: &'a ClassType,
+    name: &str,
+    visited: &mut Vec<&'a str>,
+) -> bool {
+    if class.name == name {
+        return true;
+    }
+    if visited.contains(&class.name.as_str()) {
+        return false;
+    }
+    visited.push(&class.name);
+    class.members.iter().any(|member| match &member.value {
+        PropertyValue::ClassValue { property_type, .. } => project
+            .class(property_type)
+            .is_some_and(|member_class| contains_class(project, member_class, name, visited)),
+        _ => false,
+    })
+}
+
+fn rust_type(field_type: &FieldType) -> String {
+    match field_type {
+        FieldType::Bool => "bool".to_owned(),
+        FieldType::Int => "i32".to_owned(),
+        FieldType::Float => "f32".to_owned(),
+        FieldType::String => "String".to_owned(),
+        FieldType::Color => "tiled::Color".to_owned(),
+        FieldType::Object => "u32".to_owned(),
+        FieldType::Enum(enum_type) => type_name(&enum_type.name),
+        FieldType::Class(class) => type_name(&class.name),
+    }
+}
+
+/// Returns the expression of a value of a field, such as its default.
+fn value_expr(project: &Project, field_type: &FieldType, value: &PropertyValue) -> String {
+    match (field_type, value) {
+        (FieldType::Bool, PropertyValue::BoolValue(value)) => value.to_string(),
+        (FieldType::Int, PropertyValue::IntValue(value)) => value.to_string(),
+        (FieldType::Float, PropertyValue::FloatValue(value)) => float_expr(*value),
+        (
+            FieldType::String,
+            PropertyValue::StringValue(value) | PropertyValue::FileValue(value),
+        ) => {
+            format!("{:?}.to_owned()", value)
+        }
+        (FieldType::Color, PropertyValue::ColorValue(color)) => {
+            format!("tiled::Color::from_argb_u32({:#010x})", color.to_argb_u32())
+        }
+        (FieldType::Object, PropertyValue::ObjectValue(id)) => id.to_string(),
+        (FieldType::Enum(enum_type), value) => enum_expr(enum_type, value),
+        (FieldType::Class(class), PropertyValue::ClassValue { properties, .. }) => {
+            let name = type_name(&class.name);
+            let fields = fields(project, class);
+            let set: Vec<String> = fields
+                .iter()
+                .filter_map(|field| {
+                    let value = properties.get(&field.member.name)?;
+                    Some(format!(
+                        "{}: {}",
+                        field.name,
+                        value_expr(project, &field.field_type, value)
+                    ))
+                })
+                .collect();
+            if set.is_empty() {
+                format!("{}::default()", name)
+            } else if set.len() == fields.len() {
+                format!("{} {{ {} }}", name, set.join(", "))
+            } else {
+                format!("{} {{ {}, ..Default::default() }}", name, set.join(", "))
+            }
+        }
+        _ => "Default::default()".to_owned(),
+    }
+}
+
+fn float_expr(value: f32) -> String {
+    if value.is_nan() {
+        "f32::NAN".to_owned()
+    } else if value.is_infinite() {
+        if value > 0. {
+            "f32::INFINITY"
+        } else {
+            "f32::NEG_INFINITY"
+        }
+        .to_owned()
+    } else {
+        format!("{:?}", value)
+    }
+}
+
+fn enum_expr(enum_type: &EnumType, value: &PropertyValue) -> String {
+    let name = type_name(&enum_type.name);
+    if enum_type.values_as_flags {
+        let bits = match value {
+            PropertyValue::IntValue(bits) => *bits as u32,
+            PropertyValue::StringValue(values) => values
+                .split(',')
+                .filter_map(|value| enum_type.values.iter().position(|v| v == value))
+                .filter(|index| *index < 32)
+                .fold(0, |bits, index| bits | 1 << index),
+            _ => 0,
+        };
+        return format!("{}({})", name, bits);
+    }
+    let index = match (value, enum_type.storage_type) {
+        (PropertyValue::StringValue(value), _) => enum_type.values.iter().position(|v| v == value),
+        (PropertyValue::IntValue(index), EnumStorageType::Int) => {
+            Some(*index as usize).filter(|index| *index < enum_type.values.len())
+        }
+        _ => None,
+    };
+    match index {
+        Some(index) => {
+            let mut used = HashSet::new();
+            let variant = enum_type
+                .values
+                .iter()
+                .map(|value| unique(type_name(value), &mut used))
+                .nth(index)
+                .unwrap_or_default();
+            format!("{}::{}", name, variant)
+        }
+        None => "Default::default()".to_owned(),
+    }
+}
+
+/// Splits a name into the words it is made of, at non-alphanumeric characters and at the
+/// transitions from lowercase letters or digits to uppercase letters.
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            words.extend((!word.is_empty()).then(|| std::mem::take(&mut word)));
+            previous_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && previous_lower {
+            words.push(std::mem::take(&mut word));
+        }
+        previous_lower = c.is_lowercase() || c.is_numeric();
+        word.push(c);
+    }
+    words.extend((!word.is_empty()).then_some(word));
+    words
+}
+
+/// Converts a name to an `UpperCamelCase` identifier.
+fn type_name(name: &str) -> String {
+    let mut identifier: String = words(name)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_numeric()) {
+        identifier.insert(0, 'T');
+    }
+    if identifier == "Self" {
+        identifier.push('_');
+    }
+    identifier
+}
+
+/// Converts a name to a `snake_case` identifier.
+fn field_name(name: &str) -> String {
+    let identifier = words(name)
+        .iter()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    match identifier.as_str() {
+        "" => "field".to_owned(),
+        "self" | "super" | "crate" => format!("{}_", identifier),
+        identifier if KEYWORDS.contains(&identifier) => format!("r#{}", identifier),
+        identifier if identifier.starts_with(|c: char| c.is_numeric()) => {
+            format!("_{}", identifier)
+        }
+        identifier => identifier.to_owned(),
+    }
+}
+
+/// Converts a name to a `SCREAMING_SNAKE_CASE` identifier.
+fn constant_name(name: &str) -> String {
+    let identifier = words(name)
+        .iter()
+        .map(|word| word.to_uppercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_numeric()) {
+        format!("_{}", identifier)
+    } else {
+        identifier
+    }
+}
+
+/// Appends a number to `identifier` if it was already used.
+fn unique(identifier: String, used: &mut HashSet<String>) -> String {
+    let mut candidate = identifier.clone();
+    let mut suffix = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{}{}", identifier, suffix);
+        suffix += 1;
+    }
+    candidate
+}
+
+/// The keywords that can be used as raw identifiers.
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
@@ -0,0 +1,74 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::Tileset;
+
+/// What a file a map depends on contains, part of a [`Dependency`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum DependencyKind {
+    /// An external tileset, used by the map or by one of its templates.
+    Tileset,
+    /// An object template.
+    Template,
+    /// An image used by a tileset or an image layer.
+    Image,
+}
+
+/// A file referenced by a map, directly or through the files it references, as returned by
+/// [`Loader::dependencies()`](crate::Loader::dependencies).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Dependency {
+    /// The path of the file, relative to the working directory if the path of the map was.
+    pub path: PathBuf,
+    /// What the file contains.
+    pub kind: DependencyKind,
+}
+
+/// The files referenced while loading a map, recorded by
+/// [`ParseContext`](crate::parse::ParseContext).
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Dependencies {
+    files: BTreeMap<PathBuf, DependencyKind>,
+}
+
+impl Dependencies {
+    pub fn add(&mut self, path: &Path, kind: DependencyKind) {
+        self.files.entry(path.to_owned()).or_insert(kind);
+    }
+
+    /// Records an external tileset loaded from `path` along with the images it uses.
+    pub fn add_tileset(&mut self, path: &Path, tileset: &Tileset) {
+        self.add(path, DependencyKind::Tileset);
+        self.add_tileset_images(tileset);
+    }
+
+    /// Records the images used by a tileset, be it external or embedded.
+    pub fn add_tileset_images(&mut self, tileset: &Tileset) {
+        if let Some(image) = &tileset.image {
+            self.add(&image.source, DependencyKind::Image);
+        }
+        for (_, tile) in tileset.tiles() {
+            if let Some(image) = &tile.image {
+                self.add(&image.source, DependencyKind::Image);
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "watch"), allow(dead_code))]
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, DependencyKind)> {
+        self.files
+            .iter()
+            .map(|(path, kind)| (path.as_path(), *kind))
+    }
+
+    pub fn into_vec(self) -> Vec<Dependency> {
+        self.files
+            .into_iter()
+            .map(|(path, kind)| Dependency { path, kind })
+            .collect()
+    }
+}
@@ -1,6 +1,10 @@
 use crate::InvalidTilesetError::InvalidTileDimensions;
+use crate::TileId;
 use std::num::ParseIntError;
-use std::{fmt, path::PathBuf};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
 
 /// Errors that can occur while decoding csv data.
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -41,6 +45,217 @@ impl fmt::Display for InvalidTilesetError {
 
 impl std::error::Error for InvalidTilesetError {}
 
+/// Errors that can occur when editing the layer hierarchy of a [`Map`](crate::Map).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LayerEditError {
+    /// No layer with the given ID exists in the map.
+    LayerNotFound(u32),
+    /// The layer with the given ID is not a group layer, and as such cannot contain other layers.
+    NotAGroup(u32),
+    /// The index given is past the end of the list of layers it refers to.
+    IndexOutOfBounds(usize),
+    /// The group layer with the given ID cannot be moved inside itself or one of its children.
+    MoveIntoItself(u32),
+}
+
+impl fmt::Display for LayerEditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LayerEditError::LayerNotFound(id) => write!(f, "No layer with ID {} exists", id),
+            LayerEditError::NotAGroup(id) => write!(f, "Layer with ID {} is not a group layer", id),
+            LayerEditError::IndexOutOfBounds(index) => {
+                write!(f, "Layer index {} is out of bounds", index)
+            }
+            LayerEditError::MoveIntoItself(id) => write!(
+                f,
+                "Group layer with ID {} cannot be moved inside itself or its children",
+                id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LayerEditError {}
+
+/// Errors that can occur when replacing a tileset of a map with [`Map::replace_tileset()`] or
+/// [`Map::replace_tileset_remapped()`].
+///
+/// [`Map::replace_tileset()`]: crate::Map::replace_tileset
+/// [`Map::replace_tileset_remapped()`]: crate::Map::replace_tileset_remapped
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ReplaceTilesetError {
+    /// The map has no tileset at the given index.
+    TilesetNotFound(usize),
+    /// A tile of the map uses a tile ID that doesn't exist in the new tileset.
+    MissingTile {
+        /// The ID of the tile in the tileset being replaced.
+        id: TileId,
+        /// The ID the tile would have in the new tileset.
+        new_id: TileId,
+    },
+}
+
+impl fmt::Display for ReplaceTilesetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplaceTilesetError::TilesetNotFound(index) => {
+                write!(f, "No tileset exists at index {}", index)
+            }
+            ReplaceTilesetError::MissingTile { id, new_id } if id == new_id => {
+                write!(f, "Tile {} is used but is missing from the new tileset", id)
+            }
+            ReplaceTilesetError::MissingTile { id, new_id } => write!(
+                f,
+                "Tile {} is used but tile {} it maps to is missing from the new tileset",
+                id, new_id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReplaceTilesetError {}
+
+/// Errors that can occur when editing a [`Map`](crate::Map) with an
+/// [`EditTransaction`](crate::EditTransaction), or when undoing or redoing the edits it recorded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EditError {
+    /// No layer with the given ID exists in the map.
+    LayerNotFound(u32),
+    /// The layer with the given ID is not of the type the edit requires, e.g. a tile was placed
+    /// on an object layer.
+    WrongLayerType(u32),
+    /// The position given is outside of the finite tile layer with the given ID.
+    OutOfBounds {
+        /// The ID of the layer.
+        layer: u32,
+        /// The X coordinate given.
+        x: i32,
+        /// The Y coordinate given.
+        y: i32,
+    },
+    /// The map has no tileset at the given index.
+    TilesetNotFound(usize),
+    /// The tileset at the given index has no tile with the given ID.
+    TileNotFound {
+        /// The index of the tileset within the map.
+        tileset_index: usize,
+        /// The local ID of the tile.
+        id: TileId,
+    },
+    /// No object with the given ID exists in the map.
+    ObjectNotFound(u32),
+    /// An object with the given ID already exists in the map.
+    DuplicateObject(u32),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::LayerNotFound(id) => write!(f, "No layer with ID {} exists", id),
+            EditError::WrongLayerType(id) => {
+                write!(f, "Layer with ID {} cannot be edited this way", id)
+            }
+            EditError::OutOfBounds { layer, x, y } => write!(
+                f,
+                "Position ({}, {}) is outside of the layer with ID {}",
+                x, y, layer
+            ),
+            EditError::TilesetNotFound(index) => {
+                write!(f, "No tileset exists at index {}", index)
+            }
+            EditError::TileNotFound { tileset_index, id } => write!(
+                f,
+                "The tileset at index {} has no tile with ID {}",
+                tileset_index, id
+            ),
+            EditError::ObjectNotFound(id) => write!(f, "No object with ID {} exists", id),
+            EditError::DuplicateObject(id) => {
+                write!(f, "An object with ID {} already exists", id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+/// Errors that can occur when changing whether a tileset is embedded in a map with
+/// [`Map::embed_tileset()`](crate::Map::embed_tileset) or
+/// [`Map::export_tileset()`](crate::Map::export_tileset).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum EmbedTilesetError {
+    /// The map has no tileset at the given index.
+    TilesetNotFound(usize),
+    /// The tileset at the given index is already embedded in the map.
+    AlreadyEmbedded(usize),
+    /// The tileset at the given index is already stored in a file of its own.
+    AlreadyExternal(usize),
+    /// The path the tileset was to be exported to is the path of the map itself.
+    PathIsMap(PathBuf),
+}
+
+impl fmt::Display for EmbedTilesetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmbedTilesetError::TilesetNotFound(index) => {
+                write!(f, "No tileset exists at index {}", index)
+            }
+            EmbedTilesetError::AlreadyEmbedded(index) => {
+                write!(f, "Tileset {} is already embedded in the map", index)
+            }
+            EmbedTilesetError::AlreadyExternal(index) => {
+                write!(f, "Tileset {} is already stored in its own file", index)
+            }
+            EmbedTilesetError::PathIsMap(path) => write!(
+                f,
+                "Cannot export a tileset to {}, which is the path of the map",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for EmbedTilesetError {}
+
+/// Errors that can occur when reading a snapshot made with
+/// [`Map::to_snapshot()`](crate::Map::to_snapshot).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SnapshotError {
+    /// The data does not start like a snapshot does.
+    InvalidHeader,
+    /// The snapshot was made by a version of this crate that uses a different snapshot format.
+    UnsupportedVersion {
+        /// The format version found in the snapshot.
+        found: u32,
+        /// The format version this version of the crate reads and writes.
+        supported: u32,
+    },
+    /// The contents of the snapshot could not be decoded.
+    Decoding(String),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidHeader => write!(f, "The data given is not a map snapshot"),
+            SnapshotError::UnsupportedVersion { found, supported } => write!(
+                f,
+                "Snapshot has format version {}, but only version {} is supported",
+                found, supported
+            ),
+            SnapshotError::Decoding(description) => {
+                write!(f, "Could not decode snapshot: {}", description)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
 /// Errors which occurred when parsing the file
 #[derive(Debug)]
 #[non_exhaustive]
@@ -107,6 +322,277 @@ pub enum Error {
     },
     /// There was an invalid tileset in the map parsed.
     InvalidTileset(InvalidTilesetError),
+    /// A map snapshot could not be read.
+    InvalidSnapshot(SnapshotError),
+    /// A world file could not be parsed.
+    InvalidWorld {
+        /// A description of the error that occurred.
+        description: String,
+    },
+    /// A project file could not be parsed.
+    InvalidProject {
+        /// A description of the error that occurred.
+        description: String,
+    },
+    /// An error occurred while parsing a file, at the given position within it.
+    ///
+    /// Errors returned while loading files are wrapped in this variant; Use [`Error::inner()`] to
+    /// obtain the underlying error.
+    Located {
+        /// The path to the file being parsed.
+        path: PathBuf,
+        /// The line the parser was at when the error occurred, starting from 1.
+        line: u64,
+        /// The column the parser was at when the error occurred, starting from 1.
+        column: u64,
+        /// The error that occurred.
+        err: Box<Error>,
+    },
+    /// Several errors were found while loading a file with
+    /// [`LoaderOptions::collect_errors`](crate::LoaderOptions::collect_errors) set, in the order
+    /// they were found.
+    Multiple(Vec<Error>),
+    /// Loading was stopped because the flag given to
+    /// [`Loader::set_cancellation_flag`](crate::Loader::set_cancellation_flag) was set.
+    Cancelled,
+    /// A file references itself through the tilesets and templates it references, e.g. a tileset
+    /// whose tiles have collision objects using a template that uses the same tileset.
+    CircularReference {
+        /// The files forming the cycle, starting and ending with the file referenced again, each
+        /// one referencing the next.
+        cycle: Vec<PathBuf>,
+    },
+    /// An element that isn't part of the TMX or TSX formats was found while loading a file in
+    /// [strict mode](crate::LoaderOptions::strict).
+    UnknownElement {
+        /// The name of the element.
+        name: String,
+        /// The name of the element containing it.
+        parent: String,
+    },
+    /// An attribute that isn't part of the TMX or TSX formats was found while loading a file in
+    /// [strict mode](crate::LoaderOptions::strict).
+    UnknownAttribute {
+        /// The name of the attribute.
+        name: String,
+        /// The name of the element it was found on.
+        element: String,
+    },
+    /// A part of the formats that Tiled has deprecated was found while loading a file in
+    /// [strict mode](crate::LoaderOptions::strict).
+    DeprecatedFeature {
+        /// A description of the deprecated feature.
+        description: String,
+    },
+    /// The tile data of a file decompresses to more bytes than
+    /// [`LoaderOptions::max_decompressed_bytes`](crate::LoaderOptions::max_decompressed_bytes).
+    DecompressedSizeExceeded {
+        /// The limit that was exceeded, in bytes.
+        limit: usize,
+    },
+    /// A map, tile layer or chunk is wider or higher than
+    /// [`LoaderOptions::max_map_dimensions`](crate::LoaderOptions::max_map_dimensions).
+    MapTooLarge {
+        /// The width found, in tiles.
+        width: u32,
+        /// The height found, in tiles.
+        height: u32,
+        /// The limit that was exceeded, in tiles.
+        limit: u32,
+    },
+    /// A file contains more layers than
+    /// [`LoaderOptions::max_layers`](crate::LoaderOptions::max_layers).
+    TooManyLayers {
+        /// The limit that was exceeded.
+        limit: usize,
+    },
+    /// A file contains more objects than
+    /// [`LoaderOptions::max_objects`](crate::LoaderOptions::max_objects).
+    TooManyObjects {
+        /// The limit that was exceeded.
+        limit: usize,
+    },
+    /// A file referenced a file outside of the directory it is allowed to read from, since
+    /// [`PathPolicy::allow_escape`](crate::PathPolicy::allow_escape) is unset.
+    PathEscapesRoot {
+        /// The path of the referenced file.
+        path: PathBuf,
+        /// The directory the file should have been in.
+        root: PathBuf,
+    },
+}
+
+impl Error {
+    /// Returns the underlying error, skipping over the [`Error::Located`] wrapper if present.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Error, Loader};
+    ///
+    /// # fn main() {
+    /// let reader = |_: &std::path::Path| -> std::io::Result<_> {
+    ///     Ok(std::io::Cursor::new("<map version=\"1.10\">\n  <layer/>\n</map>"))
+    /// };
+    /// let err = Loader::with_reader(reader).load_tmx_map("map.tmx").unwrap_err();
+    ///
+    /// assert!(matches!(err, Error::Located { line: 1, .. }));
+    /// assert!(matches!(err.inner(), Error::MalformedAttributes(_)));
+    /// # }
+    /// ```
+    pub fn inner(&self) -> &Error {
+        match self {
+            Error::Located { err, .. } => err.inner(),
+            err => err,
+        }
+    }
+
+    /// Returns the path, line and column of the place where the error occurred, if known.
+    pub fn location(&self) -> Option<(&Path, u64, u64)> {
+        match self {
+            Error::Located {
+                path, line, column, ..
+            } => Some((path, *line, *column)),
+            _ => None,
+        }
+    }
+
+    /// Returns the category of the error, to handle or report errors of a kind together. For
+    /// [`Error::Located`], this is the category of the underlying error, and for
+    /// [`Error::Multiple`] the one of the first error.
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::ResourceLoadingError { .. }
+            | Error::PathIsNotFile
+            | Error::PathEscapesRoot { .. }
+            | Error::Cancelled => ErrorCategory::Io,
+            Error::MalformedAttributes(_)
+            | Error::DecompressingError(_)
+            | Error::Base64DecodingError(_)
+            | Error::CsvDecodingError(_)
+            | Error::XmlDecodingError(_)
+            | Error::PrematureEnd(_)
+            | Error::InvalidPropertyValue { .. }
+            | Error::InvalidWangIdEncoding { .. }
+            | Error::InvalidSnapshot(_)
+            | Error::InvalidWorld { .. }
+            | Error::InvalidProject { .. } => ErrorCategory::Syntax,
+            Error::InvalidTileFound
+            | Error::TemplateHasNoObject
+            | Error::InvalidObjectData { .. }
+            | Error::InvalidTileset(_)
+            | Error::CircularReference { .. }
+            | Error::DecompressedSizeExceeded { .. }
+            | Error::MapTooLarge { .. }
+            | Error::TooManyLayers { .. }
+            | Error::TooManyObjects { .. } => ErrorCategory::Semantics,
+            Error::InvalidEncodingFormat { .. }
+            | Error::UnknownPropertyType { .. }
+            | Error::UnknownElement { .. }
+            | Error::UnknownAttribute { .. }
+            | Error::DeprecatedFeature { .. } => ErrorCategory::Unsupported,
+            Error::Located { err, .. } => err.category(),
+            Error::Multiple(errors) => errors
+                .first()
+                .map_or(ErrorCategory::Semantics, Error::category),
+        }
+    }
+
+    /// Returns a code identifying the kind of error, which stays the same across versions of the
+    /// crate so that tools can refer to it, e.g. to suppress some errors. The first digit is the
+    /// one of the [category](Error::category): `E1xx` for [`ErrorCategory::Io`], `E2xx` for
+    /// [`ErrorCategory::Syntax`], `E3xx` for [`ErrorCategory::Semantics`] and `E4xx` for
+    /// [`ErrorCategory::Unsupported`].
+    ///
+    /// Like [`Error::category()`], this looks through [`Error::Located`], and gives the code of
+    /// the first error of [`Error::Multiple`], or `E000` if there are none.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{ErrorCategory, Loader};
+    ///
+    /// let err = Loader::new().load_tmx_map("assets/missing.tmx").unwrap_err();
+    /// assert_eq!(err.category(), ErrorCategory::Io);
+    /// assert_eq!(err.code(), "E101");
+    /// ```
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::ResourceLoadingError { .. } => "E101",
+            Error::PathIsNotFile => "E102",
+            Error::PathEscapesRoot { .. } => "E103",
+            Error::Cancelled => "E104",
+            Error::MalformedAttributes(_) => "E201",
+            Error::DecompressingError(_) => "E202",
+            Error::Base64DecodingError(_) => "E203",
+            Error::CsvDecodingError(_) => "E204",
+            Error::XmlDecodingError(_) => "E205",
+            Error::PrematureEnd(_) => "E206",
+            Error::InvalidPropertyValue { .. } => "E207",
+            Error::InvalidWangIdEncoding { .. } => "E208",
+            Error::InvalidSnapshot(_) => "E209",
+            Error::InvalidWorld { .. } => "E210",
+            Error::InvalidProject { .. } => "E211",
+            Error::InvalidTileFound => "E301",
+            Error::TemplateHasNoObject => "E302",
+            Error::InvalidObjectData { .. } => "E303",
+            Error::InvalidTileset(_) => "E304",
+            Error::CircularReference { .. } => "E305",
+            Error::DecompressedSizeExceeded { .. } => "E306",
+            Error::MapTooLarge { .. } => "E307",
+            Error::TooManyLayers { .. } => "E308",
+            Error::TooManyObjects { .. } => "E309",
+            Error::InvalidEncodingFormat { .. } => "E401",
+            Error::UnknownPropertyType { .. } => "E402",
+            Error::UnknownElement { .. } => "E403",
+            Error::UnknownAttribute { .. } => "E404",
+            Error::DeprecatedFeature { .. } => "E405",
+            Error::Located { err, .. } => err.code(),
+            Error::Multiple(errors) => errors.first().map_or("E000", Error::code),
+        }
+    }
+
+    /// Returns the path of the file the error is about: The file being parsed for
+    /// [`Error::Located`], or the file that couldn't be read or was referenced for the errors
+    /// that hold a path. For [`Error::Multiple`], this is the path of the first error.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::Located { path, .. }
+            | Error::ResourceLoadingError { path, .. }
+            | Error::PathEscapesRoot { path, .. } => Some(path),
+            Error::CircularReference { cycle } => cycle.first().map(PathBuf::as_path),
+            Error::Multiple(errors) => errors.first().and_then(Error::path),
+            _ => None,
+        }
+    }
+}
+
+/// The categories of [`Error`]s, returned by [`Error::category()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorCategory {
+    /// A file could not be read, or loading was stopped before it could finish.
+    Io,
+    /// A file is not well-formed: An XML, JSON or snapshot file could not be decoded, or an
+    /// attribute, property or tile data has an invalid value.
+    Syntax,
+    /// A file is well-formed but its contents don't make sense, such as a tileset with tiles of
+    /// size 0 or files referencing each other in a cycle, or it exceeds the limits of the
+    /// [`LoaderOptions`](crate::LoaderOptions).
+    Semantics,
+    /// A file uses something this crate doesn't support, such as an unknown property type or
+    /// encoding, or elements and attributes rejected in
+    /// [strict mode](crate::LoaderOptions::strict).
+    Unsupported,
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            ErrorCategory::Io => "I/O error",
+            ErrorCategory::Syntax => "syntax error",
+            ErrorCategory::Semantics => "semantic error",
+            ErrorCategory::Unsupported => "unsupported feature",
+        })
+    }
 }
 
 /// A result with an error variant of [`crate::Error`].
@@ -158,6 +644,61 @@ impl fmt::Display for Error {
             Error::InvalidObjectData{description} =>
                 write!(fmt, "Invalid object data: {}", description),
             Error::InvalidTileset(e) => write!(fmt, "{}", e),
+            Error::InvalidSnapshot(e) => write!(fmt, "{}", e),
+            Error::InvalidWorld { description } => write!(fmt, "Invalid world: {}", description),
+            Error::InvalidProject { description } =>
+                write!(fmt, "Invalid project: {}", description),
+            Error::Located { path, line, column, err } =>
+                write!(fmt, "{}:{}:{}: {}", path.display(), line, column, err),
+            Error::Multiple(errors) => {
+                write!(fmt, "{} errors found", errors.len())?;
+                for err in errors {
+                    write!(fmt, "\n{}", err)?;
+                }
+                Ok(())
+            }
+            Error::Cancelled => write!(fmt, "Loading was cancelled"),
+            Error::UnknownElement { name, parent } => {
+                write!(fmt, "Unknown element {} found in {}", name, parent)
+            }
+            Error::UnknownAttribute { name, element } => {
+                write!(fmt, "Unknown attribute {} found in {}", name, element)
+            }
+            Error::DeprecatedFeature { description } => {
+                write!(fmt, "Deprecated feature used: {}", description)
+            }
+            Error::DecompressedSizeExceeded { limit } => write!(
+                fmt,
+                "Tile data decompresses to more than the limit of {} bytes",
+                limit
+            ),
+            Error::MapTooLarge { width, height, limit } => write!(
+                fmt,
+                "Size of {}x{} tiles exceeds the limit of {} tiles",
+                width, height, limit
+            ),
+            Error::TooManyLayers { limit } => {
+                write!(fmt, "More layers than the limit of {} were found", limit)
+            }
+            Error::TooManyObjects { limit } => {
+                write!(fmt, "More objects than the limit of {} were found", limit)
+            }
+            Error::CircularReference { cycle } => {
+                write!(fmt, "Circular reference: ")?;
+                for (i, path) in cycle.iter().enumerate() {
+                    if i > 0 {
+                        write!(fmt, " -> ")?;
+                    }
+                    write!(fmt, "{}", path.display())?;
+                }
+                Ok(())
+            }
+            Error::PathEscapesRoot { path, root } => write!(
+                fmt,
+                "Path {} is outside of the directory {}",
+                path.display(),
+                root.display()
+            ),
         }
     }
 }
@@ -169,7 +710,155 @@ impl std::error::Error for Error {
             Error::Base64DecodingError(e) => Some(e as &dyn std::error::Error),
             Error::XmlDecodingError(e) => Some(e as &dyn std::error::Error),
             Error::ResourceLoadingError { err, .. } => Some(err.as_ref()),
+            Error::Located { err, .. } => Some(err.as_ref()),
             _ => None,
         }
     }
 }
+
+/// A problem found while loading a file in [lenient mode](crate::LoaderOptions::lenient), which
+/// did not prevent it from loading.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParseWarning {
+    /// The path of the file the problem was found in.
+    pub path: PathBuf,
+    /// The problem found.
+    pub kind: ParseWarningKind,
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.kind)
+    }
+}
+
+/// A problem found by [`Loader::try_load_tmx_map()`](crate::Loader::try_load_tmx_map), either an
+/// error that made part of the map fail to load or a warning about a part that loaded anyway.
+#[derive(Debug)]
+pub enum Diagnostic {
+    /// A part of the map, such as a layer or a tileset, failed to load and was left out, or the
+    /// whole map did.
+    Error(Error),
+    /// A problem that didn't prevent the map from loading, as reported in
+    /// [lenient mode](crate::LoaderOptions::lenient).
+    Warning(ParseWarning),
+}
+
+impl Diagnostic {
+    /// Returns true if this is an [error](Diagnostic::Error), which means that something is
+    /// missing from the map.
+    #[inline]
+    pub fn is_error(&self) -> bool {
+        matches!(self, Diagnostic::Error(_))
+    }
+
+    /// Returns the path of the file the problem was found in, if known.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Diagnostic::Error(err) => err.path(),
+            Diagnostic::Warning(warning) => Some(&warning.path),
+        }
+    }
+
+    /// Returns the position of the problem within its file as a line and a column, both
+    /// starting from 1, if known. Only errors have a position.
+    pub fn position(&self) -> Option<(u64, u64)> {
+        match self {
+            Diagnostic::Error(err) => err.location().map(|(_, line, column)| (line, column)),
+            Diagnostic::Warning(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Diagnostic::Error(err) => write!(f, "error: {}", err),
+            Diagnostic::Warning(warning) => write!(f, "warning: {}", warning),
+        }
+    }
+}
+
+/// The kinds of problems that are reported as a [`ParseWarning`] in lenient mode, rather than as
+/// an [`Error`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub enum ParseWarningKind {
+    /// A property has a type that isn't recognized by the crate. It has been loaded as a
+    /// [`PropertyValue::StringValue`](crate::PropertyValue::StringValue) instead.
+    UnknownPropertyType {
+        /// The name of the property.
+        name: String,
+        /// The name of the type that isn't recognized.
+        type_name: String,
+    },
+    /// The value of a property could not be parsed. The property has been left out.
+    InvalidPropertyValue {
+        /// The name of the property.
+        name: String,
+        /// A description of the error that occurred.
+        description: String,
+    },
+    /// An element that isn't part of the TMX or TSX formats was found. It has been skipped along
+    /// with its contents.
+    UnknownElement {
+        /// The name of the element.
+        name: String,
+        /// The name of the element containing it.
+        parent: String,
+    },
+    /// A chunk of an infinite tile layer contains less tiles than its size implies. The missing
+    /// tiles have been left empty.
+    IncompleteChunk {
+        /// The X coordinate of the chunk's top-left tile.
+        x: i32,
+        /// The Y coordinate of the chunk's top-left tile.
+        y: i32,
+    },
+    /// An element has both a `type` attribute, which Tiled 1.9 renamed to `class`, and a `class`
+    /// attribute with a different value. The value of `class` has been used, like Tiled does.
+    ConflictingClass {
+        /// The name of the element.
+        element: String,
+        /// The value of the `type` attribute, which has been ignored.
+        user_type: String,
+        /// The value of the `class` attribute.
+        class: String,
+    },
+}
+
+impl fmt::Display for ParseWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseWarningKind::UnknownPropertyType { name, type_name } => write!(
+                f,
+                "Property '{}' has unknown type '{}', loaded as a string",
+                name, type_name
+            ),
+            ParseWarningKind::InvalidPropertyValue { name, description } => write!(
+                f,
+                "Invalid value for property '{}', skipped: {}",
+                name, description
+            ),
+            ParseWarningKind::UnknownElement { name, parent } => {
+                write!(f, "Unknown element <{}> in <{}>, skipped", name, parent)
+            }
+            ParseWarningKind::IncompleteChunk { x, y } => write!(
+                f,
+                "Chunk at ({}, {}) is missing tiles, left them empty",
+                x, y
+            ),
+            ParseWarningKind::ConflictingClass {
+                element,
+                user_type,
+                class,
+            } => write!(
+                f,
+                "<{}> has type '{}' and class '{}', used the class",
+                element, user_type, class
+            ),
+        }
+    }
+}
@@ -0,0 +1,859 @@
+//! Conversion of maps to [Godot 4](https://godotengine.org) scenes. Requires the `godot` feature.
+//!
+//! [`to_godot()`] turns a map into the text of a `.tscn` scene, which is meant to be run as part of
+//! a build step so that maps edited in Tiled can be used by a Godot project directly:
+//!
+//! - The tilesets of the map become a single `TileSet` resource, with one atlas source for each
+//!   tileset, or for each tile of image collections. Their collision shapes are converted to
+//!   polygons of the first physics layer.
+//! - Tile layers become `TileMapLayer` nodes using that resource, which requires Godot 4.3 or
+//!   later. Flipped tiles use the transform flags of alternative tiles.
+//! - Image layers become `Sprite2D` nodes, and group layers `Node2D` nodes containing the nodes of
+//!   their children.
+//! - Objects become `Node2D` nodes, or `Sprite2D` nodes if they display a tile, whose metadata
+//!   holds their ID, class, shape and custom properties.
+//!
+//! The `TileSet` is embedded in the scene unless [`GodotOptions::tile_set_path`] is set, in which
+//! case it is returned as a separate `.tres` resource in [`GodotExport::tile_set`].
+//!
+//! ## Example
+//! ```
+//! use tiled::{
+//!     export::godot::{to_godot, GodotOptions},
+//!     Loader,
+//! };
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+//!
+//! let mut options = GodotOptions::default();
+//! options.tile_set_path = Some("res://assets/tilesheet.tres".to_owned());
+//! let export = to_godot(&map, &options);
+//! for warning in &export.warnings {
+//!     eprintln!("{}", warning);
+//! }
+//! # assert!(export.scene.contains("TileMapLayer"));
+//! # assert!(export.tile_set.is_some());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    util::normalize_path, Color, Layer, LayerTileData, LayerType, Map, Object, ObjectShape,
+    Orientation, Properties, PropertyValue, TileId, TileLayer, Tileset,
+};
+
+/// The flags Godot adds to alternative tile IDs to transform tiles.
+const TRANSFORM_FLIP_H: u16 = 1 << 12;
+const TRANSFORM_FLIP_V: u16 = 1 << 13;
+const TRANSFORM_TRANSPOSE: u16 = 1 << 14;
+
+/// Options for [`to_godot()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GodotOptions {
+    /// The directory of the Godot project, i.e. the one `res://` refers to. Image paths are made
+    /// relative to it, and must be relative to the same directory as it, generally the working
+    /// directory maps are loaded from.
+    ///
+    /// Empty by default, i.e. the working directory is the project directory.
+    pub project_dir: PathBuf,
+    /// If set, the `TileSet` is returned as a separate resource, which the scene loads from this
+    /// path, e.g. `res://tilesets/level.tres`. Otherwise, it is embedded in the scene.
+    ///
+    /// Unset by default.
+    pub tile_set_path: Option<String>,
+}
+
+/// The result of converting a [`Map`] with [`to_godot()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct GodotExport {
+    /// The text of the `.tscn` scene.
+    pub scene: String,
+    /// The text of the `.tres` `TileSet` resource, if [`GodotOptions::tile_set_path`] was set.
+    pub tile_set: Option<String>,
+    /// The parts of the map that Godot can't represent, and as such were changed or left out.
+    pub warnings: Vec<GodotWarning>,
+}
+
+/// Something in a [`Map`] that could not be converted exactly by [`to_godot()`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum GodotWarning {
+    /// The map is staggered or hexagonal, and was converted as if it was orthogonal.
+    UnsupportedOrientation(Orientation),
+    /// An image is not inside [`GodotOptions::project_dir`], so its path was written as is.
+    OutsideProject {
+        /// The path of the image.
+        path: PathBuf,
+    },
+    /// A collision shape of a tile is a point, a polyline or a text, and was left out.
+    UnsupportedCollisionShape {
+        /// The index of the tileset within the map's tilesets.
+        tileset_index: usize,
+        /// The ID of the tile within its tileset.
+        tile_id: TileId,
+    },
+    /// A custom property could not be stored as metadata because Godot doesn't support its name,
+    /// even after replacing invalid characters, and was left out.
+    UnsupportedProperty {
+        /// The name of the property.
+        name: String,
+    },
+}
+
+impl fmt::Display for GodotWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GodotWarning::UnsupportedOrientation(orientation) => write!(
+                f,
+                "Map is {}, which was converted as orthogonal",
+                orientation
+            ),
+            GodotWarning::OutsideProject { path } => write!(
+                f,
+                "Image {} is not inside the project directory",
+                path.display()
+            ),
+            GodotWarning::UnsupportedCollisionShape {
+                tileset_index,
+                tile_id,
+            } => write!(
+                f,
+                "A collision shape of tile {} of tileset {} was left out",
+                tile_id, tileset_index
+            ),
+            GodotWarning::UnsupportedProperty { name } => {
+                write!(f, "Property '{}' was left out", name)
+            }
+        }
+    }
+}
+
+/// Converts a map to a Godot 4 scene. See the [module documentation](self) for what is converted
+/// and how.
+pub fn to_godot(map: &Map, options: &GodotOptions) -> GodotExport {
+    let mut warnings = Vec::new();
+    match map.orientation {
+        Orientation::Orthogonal | Orientation::Isometric => {}
+        orientation => warnings.push(GodotWarning::UnsupportedOrientation(orientation)),
+    }
+
+    let mut tile_set_resources = Resources::new(options);
+    let tile_set = TileSetBuilder::build(map, &mut tile_set_resources);
+    let (tile_set_text, mut resources, tile_set_ref) = match &options.tile_set_path {
+        Some(path) => {
+            let text = tile_set_resources.file(
+                "[gd_resource type=\"TileSet\" load_steps={} format=3]",
+                &format!("[resource]\n{}", tile_set.body),
+            );
+            warnings.append(&mut tile_set_resources.warnings);
+            let mut resources = Resources::new(options);
+            let id = resources.ext_resource("TileSet", path.clone());
+            (Some(text), resources, format!("ExtResource(\"{}\")", id))
+        }
+        None => {
+            let id = tile_set_resources.sub_resource("TileSet", tile_set.body.clone());
+            (None, tile_set_resources, format!("SubResource(\"{}\")", id))
+        }
+    };
+
+    let mut scene = SceneBuilder {
+        tile_set: &tile_set,
+        tile_set_ref,
+        resources: &mut resources,
+        nodes: String::new(),
+    };
+    let root_name = map
+        .source
+        .file_stem()
+        .map(|stem| node_name(&stem.to_string_lossy()))
+        .unwrap_or_else(|| "Map".to_owned());
+    writeln!(scene.nodes, "[node name=\"{}\" type=\"Node2D\"]", root_name).unwrap();
+    scene.write_metadata(map.user_type.as_deref(), &[], &map.properties);
+    scene.write_layers(map.layers(), ".");
+
+    let nodes = std::mem::take(&mut scene.nodes);
+    let scene = resources.file("[gd_scene load_steps={} format=3]", &nodes);
+    warnings.append(&mut resources.warnings);
+
+    GodotExport {
+        scene,
+        tile_set: tile_set_text,
+        warnings,
+    }
+}
+
+/// The external and internal resources of a `.tscn` or `.tres` file.
+struct Resources<'a> {
+    options: &'a GodotOptions,
+    warnings: Vec<GodotWarning>,
+    ext_resources: Vec<String>,
+    textures: HashMap<PathBuf, String>,
+    sub_resources: Vec<String>,
+}
+
+impl<'a> Resources<'a> {
+    fn new(options: &'a GodotOptions) -> Self {
+        Self {
+            options,
+            warnings: Vec::new(),
+            ext_resources: Vec::new(),
+            textures: HashMap::new(),
+            sub_resources: Vec::new(),
+        }
+    }
+
+    /// Adds an external resource and returns its ID.
+    fn ext_resource(&mut self, ty: &str, path: String) -> String {
+        let id = format!("{}_{}", self.ext_resources.len() + 1, ty.to_lowercase());
+        self.ext_resources.push(format!(
+            "[ext_resource type=\"{}\" path={} id=\"{}\"]\n",
+            ty,
+            string(&path),
+            id
+        ));
+        id
+    }
+
+    /// Returns the ID of the external resource of an image, adding it if needed.
+    fn texture(&mut self, path: &Path) -> String {
+        if let Some(id) = self.textures.get(path) {
+            return id.clone();
+        }
+
+        let normalized = normalize_path(path);
+        let res_path = match normalized.strip_prefix(normalize_path(&self.options.project_dir)) {
+            Ok(relative) if relative.is_relative() => {
+                format!("res://{}", relative.to_string_lossy().replace('\\', "/"))
+            }
+            _ => {
+                self.warnings.push(GodotWarning::OutsideProject {
+                    path: path.to_owned(),
+                });
+                path.to_string_lossy().replace('\\', "/")
+            }
+        };
+        let id = self.ext_resource("Texture2D", res_path);
+        self.textures.insert(path.to_owned(), id.clone());
+        id
+    }
+
+    /// Adds an internal resource and returns its ID.
+    fn sub_resource(&mut self, ty: &str, body: String) -> String {
+        let id = format!("{}_{}", ty, self.sub_resources.len() + 1);
+        self.sub_resources.push(format!(
+            "[sub_resource type=\"{}\" id=\"{}\"]\n{}",
+            ty, id, body
+        ));
+        id
+    }
+
+    /// Writes a whole file, `header` having a `{}` placeholder for the number of resources to
+    /// load.
+    fn file(&self, header: &str, main: &str) -> String {
+        let load_steps = self.ext_resources.len() + self.sub_resources.len() + 1;
+        let mut text = header.replacen("{}", &load_steps.to_string(), 1);
+        text.push_str("\n\n");
+        for resource in &self.ext_resources {
+            text.push_str(resource);
+        }
+        if !self.ext_resources.is_empty() {
+            text.push('\n');
+        }
+        for resource in &self.sub_resources {
+            text.push_str(resource);
+            text.push('\n');
+        }
+        text.push_str(main);
+        text
+    }
+}
+
+/// Where the tiles of a tileset are found within the `TileSet`.
+enum TileSource {
+    /// The whole tileset is the atlas source with this ID.
+    Atlas { source_id: u16, columns: u32 },
+    /// Each tile of an image collection is the only tile of an atlas source.
+    Collection(HashMap<TileId, u16>),
+    /// The tileset has no image.
+    None,
+}
+
+/// The `TileSet` resource built from the tilesets of a map.
+struct TileSetBuilder {
+    body: String,
+    sources: Vec<TileSource>,
+}
+
+impl TileSetBuilder {
+    fn build(map: &Map, resources: &mut Resources) -> Self {
+        let mut body = String::new();
+        if map.orientation == Orientation::Isometric {
+            // TILE_SHAPE_ISOMETRIC and TILE_LAYOUT_DIAMOND_DOWN
+            body.push_str("tile_shape = 1\ntile_layout = 5\n");
+        }
+        writeln!(
+            body,
+            "tile_size = Vector2i({}, {})",
+            map.tile_width, map.tile_height
+        )
+        .unwrap();
+        body.push_str("physics_layer_0/collision_layer = 1\n");
+
+        let mut next_source_id = 0;
+        let mut sources = Vec::new();
+        for (tileset_index, tileset) in map.tilesets().iter().enumerate() {
+            let source = match &tileset.image {
+                Some(image) => {
+                    let texture = resources.texture(&image.source);
+                    let mut atlas = String::new();
+                    write_atlas_header(
+                        &mut atlas,
+                        &texture,
+                        (tileset.tile_width, tileset.tile_height),
+                        tileset.margin,
+                        tileset.spacing,
+                    );
+                    let columns = tileset.columns.max(1);
+                    for id in 0..tileset.tilecount {
+                        write_atlas_tile(
+                            &mut atlas,
+                            (id % columns, id / columns),
+                            tileset,
+                            tileset_index,
+                            id,
+                            (tileset.tile_width, tileset.tile_height),
+                            &mut resources.warnings,
+                        );
+                    }
+                    let source_id = next_source_id;
+                    next_source_id += 1;
+                    let atlas = resources.sub_resource("TileSetAtlasSource", atlas);
+                    writeln!(body, "sources/{} = SubResource(\"{}\")", source_id, atlas).unwrap();
+                    TileSource::Atlas { source_id, columns }
+                }
+                None if tileset.tiles().len() > 0 => {
+                    let mut ids: Vec<TileId> = tileset.tiles().map(|(id, _)| id).collect();
+                    ids.sort_unstable();
+                    let mut tiles = HashMap::new();
+                    for id in ids {
+                        let image = match tileset.get_tile(id).and_then(|tile| tile.image.clone()) {
+                            Some(image) => image,
+                            None => continue,
+                        };
+                        let texture = resources.texture(&image.source);
+                        let mut atlas = String::new();
+                        let size = (image.width.max(0) as u32, image.height.max(0) as u32);
+                        write_atlas_header(&mut atlas, &texture, size, 0, 0);
+                        write_atlas_tile(
+                            &mut atlas,
+                            (0, 0),
+                            tileset,
+                            tileset_index,
+                            id,
+                            size,
+                            &mut resources.warnings,
+                        );
+                        let source_id = next_source_id;
+                        next_source_id += 1;
+                        let atlas = resources.sub_resource("TileSetAtlasSource", atlas);
+                        writeln!(body, "sources/{} = SubResource(\"{}\")", source_id, atlas)
+                            .unwrap();
+                        tiles.insert(id, source_id);
+                    }
+                    TileSource::Collection(tiles)
+                }
+                None => TileSource::None,
+            };
+            sources.push(source);
+        }
+
+        Self { body, sources }
+    }
+
+    /// Returns the source ID and atlas coordinates of a tile of the map.
+    fn locate(&self, tileset_index: usize, id: TileId) -> Option<(u16, u16, u16)> {
+        match self.sources.get(tileset_index)? {
+            TileSource::Atlas { source_id, columns } => {
+                Some((*source_id, (id % columns) as u16, (id / columns) as u16))
+            }
+            TileSource::Collection(tiles) => Some((*tiles.get(&id)?, 0, 0)),
+            TileSource::None => None,
+        }
+    }
+}
+
+fn write_atlas_header(
+    atlas: &mut String,
+    texture: &str,
+    tile_size: (u32, u32),
+    margin: u32,
+    spacing: u32,
+) {
+    writeln!(atlas, "texture = ExtResource(\"{}\")", texture).unwrap();
+    writeln!(atlas, "margins = Vector2i({}, {})", margin, margin).unwrap();
+    writeln!(atlas, "separation = Vector2i({}, {})", spacing, spacing).unwrap();
+    writeln!(
+        atlas,
+        "texture_region_size = Vector2i({}, {})",
+        tile_size.0, tile_size.1
+    )
+    .unwrap();
+}
+
+/// Writes the properties of a tile of an atlas source, including its collision polygons.
+fn write_atlas_tile(
+    atlas: &mut String,
+    (x, y): (u32, u32),
+    tileset: &Tileset,
+    tileset_index: usize,
+    id: TileId,
+    tile_size: (u32, u32),
+    warnings: &mut Vec<GodotWarning>,
+) {
+    writeln!(atlas, "{}:{}/0 = 0", x, y).unwrap();
+    let collision = match tileset.get_tile(id).and_then(|tile| tile.collision.clone()) {
+        Some(collision) => collision,
+        None => return,
+    };
+
+    // Godot positions collision shapes relative to the center of tiles
+    let center = (tile_size.0 as f32 / 2.0, tile_size.1 as f32 / 2.0);
+    let mut polygon_index = 0;
+    for object in collision.object_data() {
+        let outline = match object.outline().filter(|outline| outline.closed) {
+            Some(outline) => outline,
+            None => {
+                warnings.push(GodotWarning::UnsupportedCollisionShape {
+                    tileset_index,
+                    tile_id: id,
+                });
+                continue;
+            }
+        };
+        let outline = outline.map(|(px, py)| (px - center.0, py - center.1));
+        writeln!(
+            atlas,
+            "{}:{}/0/physics_layer_0/polygon_{}/points = {}",
+            x,
+            y,
+            polygon_index,
+            vector2_array(&outline.points)
+        )
+        .unwrap();
+        polygon_index += 1;
+    }
+}
+
+struct SceneBuilder<'r, 'a> {
+    tile_set: &'r TileSetBuilder,
+    tile_set_ref: String,
+    resources: &'r mut Resources<'a>,
+    nodes: String,
+}
+
+impl SceneBuilder<'_, '_> {
+    fn write_layers<'map>(&mut self, layers: impl Iterator<Item = Layer<'map>>, parent: &str) {
+        let mut names = NodeNames::default();
+        for layer in layers {
+            let name = names.unique(&layer.name);
+            let ty = match layer.layer_type() {
+                LayerType::Tiles(_) => "TileMapLayer",
+                LayerType::Objects(_) | LayerType::Group(_) => "Node2D",
+                LayerType::Image(_) => "Sprite2D",
+            };
+            self.write_node_header(&name, ty, parent);
+            if layer.offset_x != 0.0 || layer.offset_y != 0.0 {
+                writeln!(
+                    self.nodes,
+                    "position = Vector2({:?}, {:?})",
+                    layer.offset_x, layer.offset_y
+                )
+                .unwrap();
+            }
+            if !layer.visible {
+                self.nodes.push_str("visible = false\n");
+            }
+            if layer.opacity != 1.0 || layer.tint_color.is_some() {
+                let tint = layer.tint_color.unwrap_or(Color {
+                    red: 255,
+                    green: 255,
+                    blue: 255,
+                    alpha: 255,
+                });
+                let alpha = tint.alpha as f32 / 255.0 * layer.opacity;
+                writeln!(self.nodes, "modulate = {}", color_with_alpha(tint, alpha)).unwrap();
+            }
+
+            let path = if parent == "." {
+                name
+            } else {
+                format!("{}/{}", parent, name)
+            };
+            match layer.layer_type() {
+                LayerType::Tiles(tiles) => {
+                    writeln!(self.nodes, "tile_set = {}", self.tile_set_ref).unwrap();
+                    writeln!(
+                        self.nodes,
+                        "tile_map_data = PackedByteArray({})",
+                        self.tile_map_data(&tiles)
+                    )
+                    .unwrap();
+                    self.write_metadata(layer.user_type.as_deref(), &[], &layer.properties);
+                }
+                LayerType::Image(image) => {
+                    if let Some(image) = &image.image {
+                        let texture = self.resources.texture(&image.source);
+                        writeln!(self.nodes, "texture = ExtResource(\"{}\")", texture).unwrap();
+                    }
+                    self.nodes.push_str("centered = false\n");
+                    self.write_metadata(layer.user_type.as_deref(), &[], &layer.properties);
+                }
+                LayerType::Objects(objects) => {
+                    self.write_metadata(layer.user_type.as_deref(), &[], &layer.properties);
+                    let mut names = NodeNames::default();
+                    for object in objects.objects() {
+                        self.write_object(&object, &mut names, &path);
+                    }
+                }
+                LayerType::Group(group) => {
+                    self.write_metadata(layer.user_type.as_deref(), &[], &layer.properties);
+                    self.write_layers(group.layers(), &path);
+                }
+            }
+        }
+    }
+
+    fn write_object(&mut self, object: &Object, names: &mut NodeNames, parent: &str) {
+        let name = if object.name.is_empty() {
+            format!("Object{}", object.id())
+        } else {
+            object.name.clone()
+        };
+        let name = names.unique(&name);
+        let tile = object.get_tile();
+        self.write_node_header(
+            &name,
+            if tile.is_some() { "Sprite2D" } else { "Node2D" },
+            parent,
+        );
+        writeln!(
+            self.nodes,
+            "position = Vector2({:?}, {:?})",
+            object.x, object.y
+        )
+        .unwrap();
+        if object.rotation != 0.0 {
+            writeln!(self.nodes, "rotation = {:?}", object.rotation.to_radians()).unwrap();
+        }
+        if !object.visible {
+            self.nodes.push_str("visible = false\n");
+        }
+
+        let mut metadata = vec![("tiled_id", object.id().to_string())];
+        match &object.shape {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => {
+                let shape = match &object.shape {
+                    ObjectShape::Rect { .. } => "rectangle",
+                    ObjectShape::Ellipse { .. } => "ellipse",
+                    _ => "text",
+                };
+                metadata.push(("tiled_shape", string(shape)));
+                metadata.push(("tiled_size", format!("Vector2({:?}, {:?})", width, height)));
+                if let ObjectShape::Text { text, .. } = &object.shape {
+                    metadata.push(("tiled_text", string(text)));
+                }
+
+                if let Some(tile) = &tile {
+                    let tileset = tile.get_tileset();
+                    if let (Some(image), Some(rect)) = (
+                        tileset
+                            .image
+                            .as_ref()
+                            .or_else(|| tile.get_tile().and_then(|tile| tile.data.image.as_ref())),
+                        tileset.tile_rect(tile.id()),
+                    ) {
+                        // Tile objects are positioned by their bottom left corner
+                        let texture = self.resources.texture(&image.source);
+                        writeln!(self.nodes, "texture = ExtResource(\"{}\")", texture).unwrap();
+                        self.nodes.push_str("centered = false\n");
+                        writeln!(self.nodes, "offset = Vector2(0, {})", -(rect.height as i64))
+                            .unwrap();
+                        writeln!(
+                            self.nodes,
+                            "scale = Vector2({:?}, {:?})",
+                            width / rect.width.max(1) as f32,
+                            height / rect.height.max(1) as f32
+                        )
+                        .unwrap();
+                        if tileset.image.is_some() {
+                            self.nodes.push_str("region_enabled = true\n");
+                            writeln!(
+                                self.nodes,
+                                "region_rect = Rect2({}, {}, {}, {})",
+                                rect.x, rect.y, rect.width, rect.height
+                            )
+                            .unwrap();
+                        }
+                        if let Some(data) = object.tile_data() {
+                            if data.flip_h {
+                                self.nodes.push_str("flip_h = true\n");
+                            }
+                            if data.flip_v {
+                                self.nodes.push_str("flip_v = true\n");
+                            }
+                        }
+                    }
+                }
+            }
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                let shape = match &object.shape {
+                    ObjectShape::Polyline { .. } => "polyline",
+                    _ => "polygon",
+                };
+                metadata.push(("tiled_shape", string(shape)));
+                metadata.push(("tiled_points", vector2_array(points)));
+            }
+            ObjectShape::Point(..) => {
+                metadata.push(("tiled_shape", string("point")));
+            }
+        }
+
+        let class = if object.user_type.is_empty() {
+            None
+        } else {
+            Some(object.user_type.as_str())
+        };
+        self.write_metadata(class, &metadata, &object.properties);
+    }
+
+    fn write_node_header(&mut self, name: &str, ty: &str, parent: &str) {
+        writeln!(
+            self.nodes,
+            "\n[node name=\"{}\" type=\"{}\" parent=\"{}\"]",
+            name, ty, parent
+        )
+        .unwrap();
+    }
+
+    /// Writes the metadata of the current node, made of its class, of the entries given and of
+    /// its custom properties.
+    fn write_metadata(
+        &mut self,
+        class: Option<&str>,
+        entries: &[(&str, String)],
+        properties: &Properties,
+    ) {
+        if let Some(class) = class {
+            writeln!(self.nodes, "metadata/tiled_class = {}", string(class)).unwrap();
+        }
+        for (key, value) in entries {
+            writeln!(self.nodes, "metadata/{} = {}", key, value).unwrap();
+        }
+
+        let mut names: Vec<&Arc<str>> = properties.keys().collect();
+        names.sort();
+        for name in names {
+            match metadata_key(name) {
+                Some(key) => writeln!(
+                    self.nodes,
+                    "metadata/{} = {}",
+                    key,
+                    variant(&properties[name])
+                )
+                .unwrap(),
+                None => self
+                    .resources
+                    .warnings
+                    .push(GodotWarning::UnsupportedProperty {
+                        name: name.to_string(),
+                    }),
+            }
+        }
+    }
+
+    /// Encodes the cells of a tile layer the way `TileMapLayer` stores them.
+    fn tile_map_data(&self, tiles: &TileLayer) -> String {
+        // The version of the format
+        let mut data: Vec<u8> = 0u16.to_le_bytes().to_vec();
+        let mut push_cell = |x: i32, y: i32, tile: &LayerTileData| {
+            let (source_id, atlas_x, atlas_y) =
+                match self.tile_set.locate(tile.tileset_index(), tile.id()) {
+                    Some(location) => location,
+                    None => return,
+                };
+            let mut alternative = 0;
+            if tile.flip_h {
+                alternative |= TRANSFORM_FLIP_H;
+            }
+            if tile.flip_v {
+                alternative |= TRANSFORM_FLIP_V;
+            }
+            if tile.flip_d {
+                alternative |= TRANSFORM_TRANSPOSE;
+            }
+            for value in [
+                x as i16 as u16,
+                y as i16 as u16,
+                source_id,
+                atlas_x,
+                atlas_y,
+                alternative,
+            ] {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        };
+
+        match tiles {
+            TileLayer::Finite(tiles) => {
+                for y in 0..tiles.height() as i32 {
+                    for x in 0..tiles.width() as i32 {
+                        if let Some(tile) = tiles.get_tile_data(x, y) {
+                            push_cell(x, y, &tile);
+                        }
+                    }
+                }
+            }
+            TileLayer::Infinite(tiles) => {
+                let mut chunks: Vec<_> = tiles.chunks().collect();
+                chunks.sort_by_key(|((x, y), _)| (*y, *x));
+                for ((chunk_x, chunk_y), chunk) in chunks {
+                    for y in 0..crate::ChunkData::HEIGHT as i32 {
+                        for x in 0..crate::ChunkData::WIDTH as i32 {
+                            if let Some(tile) = chunk.get_tile_data(x, y) {
+                                push_cell(
+                                    chunk_x * crate::ChunkData::WIDTH as i32 + x,
+                                    chunk_y * crate::ChunkData::HEIGHT as i32 + y,
+                                    &tile,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut text = String::new();
+        for (i, byte) in data.iter().enumerate() {
+            if i > 0 {
+                text.push_str(", ");
+            }
+            write!(text, "{}", byte).unwrap();
+        }
+        text
+    }
+}
+
+/// Gives sibling nodes unique names, which Godot requires.
+#[derive(Default)]
+struct NodeNames {
+    used: HashMap<String, u32>,
+}
+
+impl NodeNames {
+    fn unique(&mut self, name: &str) -> String {
+        let name = node_name(name);
+        let count = self.used.entry(name.clone()).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            name
+        } else {
+            format!("{}{}", name, count)
+        }
+    }
+}
+
+/// Replaces the characters that Godot doesn't allow in node names.
+fn node_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '.' | ':' | '@' | '/' | '"' | '%' => '_',
+            c => c,
+        })
+        .collect();
+    if name.is_empty() {
+        "Node".to_owned()
+    } else {
+        name
+    }
+}
+
+/// Turns a property name into a metadata key, which must be a valid identifier.
+fn metadata_key(name: &str) -> Option<String> {
+    let key: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if key.is_empty() || key.starts_with(|c: char| c.is_ascii_digit()) {
+        None
+    } else {
+        Some(key)
+    }
+}
+
+/// Formats a property value as a Godot variant.
+fn variant(value: &PropertyValue) -> String {
+    match value {
+        PropertyValue::BoolValue(value) => value.to_string(),
+        PropertyValue::FloatValue(value) => format!("{:?}", value),
+        PropertyValue::IntValue(value) => value.to_string(),
+        PropertyValue::ObjectValue(value) => value.to_string(),
+        PropertyValue::ColorValue(color) => color_with_alpha(*color, color.alpha as f32 / 255.0),
+        PropertyValue::StringValue(value) | PropertyValue::FileValue(value) => string(value),
+        PropertyValue::Custom(custom) => string(&custom.value),
+        PropertyValue::ClassValue { properties, .. } => {
+            let mut names: Vec<&Arc<str>> = properties.keys().collect();
+            names.sort();
+            let entries: Vec<String> = names
+                .into_iter()
+                .map(|name| format!("{}: {}", string(name), variant(&properties[name])))
+                .collect();
+            format!("{{{}}}", entries.join(", "))
+        }
+    }
+}
+
+fn color_with_alpha(color: Color, alpha: f32) -> String {
+    format!(
+        "Color({:?}, {:?}, {:?}, {:?})",
+        color.red as f32 / 255.0,
+        color.green as f32 / 255.0,
+        color.blue as f32 / 255.0,
+        alpha
+    )
+}
+
+fn vector2_array(points: &[(f32, f32)]) -> String {
+    let coordinates: Vec<String> = points
+        .iter()
+        .map(|(x, y)| format!("{:?}, {:?}", x, y))
+        .collect();
+    format!("PackedVector2Array({})", coordinates.join(", "))
+}
+
+/// Formats a string literal.
+fn string(value: &str) -> String {
+    let mut literal = String::with_capacity(value.len() + 2);
+    literal.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
@@ -0,0 +1,837 @@
+//! Conversion of maps to [LDtk](https://ldtk.io) projects. Requires the `ldtk` feature.
+//!
+//! LDtk and Tiled model levels differently, so only what both editors share is converted:
+//!
+//! - The map becomes a project with a single level. Infinite maps are cropped to the area their
+//!   chunks cover.
+//! - Each tileset with a single image becomes an LDtk tileset. LDtk only supports square tiles, so
+//!   the tile width is used as their size.
+//! - Tile layers become `Tiles` layers. LDtk layers may only use one tileset, so a layer using
+//!   several of them is split into one layer per tileset.
+//! - Objects become entities, one entity definition being created for each object class.
+//!   Polylines and polygons are converted to the rectangle bounding them, and points to 1 pixel
+//!   wide entities.
+//! - Custom properties of the map and of objects become fields of the level and of the entities.
+//! - Group layers are flattened, their name being prepended to the name of their children.
+//!
+//! Anything else is left out, which is reported as an [`LdtkWarning`].
+//!
+//! ## Example
+//! ```
+//! use tiled::{export::ldtk, Loader};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+//! let project = ldtk::to_ldtk(&map);
+//! for warning in &project.warnings {
+//!     eprintln!("{}", warning);
+//! }
+//! let json = serde_json::to_string_pretty(&project.json)?;
+//! # assert!(json.contains("\"layerInstances\""));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{collections::HashMap, fmt, path::Path, sync::Arc};
+
+use serde_json::{json, Map as JsonObject, Value};
+
+use crate::{
+    Color, Layer, LayerTileData, LayerType, Map, Object, ObjectShape, Orientation, Properties,
+    PropertyValue, TileLayer, Tileset,
+};
+
+/// The version of the LDtk JSON format produced.
+const JSON_VERSION: &str = "1.5.3";
+
+/// The result of converting a [`Map`] with [`to_ldtk()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LdtkProject {
+    /// The LDtk project, which can be written to a `.ldtk` file as is.
+    pub json: Value,
+    /// The parts of the map that LDtk can't represent, and as such were changed or left out.
+    pub warnings: Vec<LdtkWarning>,
+}
+
+/// Something in a [`Map`] that could not be converted exactly by [`to_ldtk()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LdtkWarning {
+    /// The map is not orthogonal, but its tiles and objects were placed on an orthogonal grid.
+    NotOrthogonal,
+    /// The tiles of a tileset are not square. Its tile width was used as their size.
+    NonSquareTiles {
+        /// The index of the tileset within the map's tilesets.
+        tileset_index: usize,
+    },
+    /// A tileset is an image collection, which LDtk doesn't support. Its tiles were left out.
+    ImageCollection {
+        /// The index of the tileset within the map's tilesets.
+        tileset_index: usize,
+    },
+    /// An image layer was left out.
+    ImageLayer {
+        /// The ID of the layer.
+        layer_id: u32,
+    },
+    /// A layer has custom properties, which LDtk layers can't have. They were left out.
+    LayerProperties {
+        /// The ID of the layer.
+        layer_id: u32,
+    },
+    /// A tile layer contains tiles that are flipped diagonally, which LDtk doesn't support. They
+    /// were only flipped horizontally and vertically.
+    RotatedTiles {
+        /// The ID of the layer.
+        layer_id: u32,
+    },
+    /// A custom property has a class type, or a different type than a property with the same name
+    /// found before, and was left out.
+    UnsupportedProperty {
+        /// The name of the property.
+        name: String,
+    },
+}
+
+impl fmt::Display for LdtkWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LdtkWarning::NotOrthogonal => write!(f, "Map is not orthogonal"),
+            LdtkWarning::NonSquareTiles { tileset_index } => {
+                write!(f, "Tileset {} does not have square tiles", tileset_index)
+            }
+            LdtkWarning::ImageCollection { tileset_index } => write!(
+                f,
+                "Tileset {} is an image collection and was left out",
+                tileset_index
+            ),
+            LdtkWarning::ImageLayer { layer_id } => {
+                write!(f, "Image layer {} was left out", layer_id)
+            }
+            LdtkWarning::LayerProperties { layer_id } => {
+                write!(f, "Properties of layer {} were left out", layer_id)
+            }
+            LdtkWarning::RotatedTiles { layer_id } => write!(
+                f,
+                "Layer {} contains diagonally flipped tiles, which were not rotated",
+                layer_id
+            ),
+            LdtkWarning::UnsupportedProperty { name } => {
+                write!(f, "Property '{}' was left out", name)
+            }
+        }
+    }
+}
+
+/// Converts a map to an LDtk project containing a single level. See the
+/// [module documentation](self) for what is converted and how.
+///
+/// Paths to images are written as they are stored in the map's [`Image`](crate::Image)s, i.e.
+/// relative to the working directory the map was loaded from, so they may need to be adjusted
+/// depending on where the project is saved.
+pub fn to_ldtk(map: &Map) -> LdtkProject {
+    Converter::new(map).convert()
+}
+
+/// The fields of an LDtk definition, i.e. of the level or of an entity, indexed by name.
+#[derive(Default)]
+struct FieldDefs {
+    defs: Vec<Value>,
+    types: HashMap<String, (i64, &'static str)>,
+}
+
+struct EntityDef {
+    uid: i64,
+    identifier: String,
+    width: u32,
+    height: u32,
+    fields: FieldDefs,
+}
+
+struct Converter<'map> {
+    map: &'map Map,
+    warnings: Vec<LdtkWarning>,
+    next_uid: i64,
+    /// The LDtk tileset UID of each of the map's tilesets, if it could be converted.
+    tileset_uids: Vec<Option<i64>>,
+    layer_defs: Vec<Value>,
+    layer_instances: Vec<Value>,
+    layer_identifiers: HashMap<String, usize>,
+    entity_defs: Vec<EntityDef>,
+    level_fields: FieldDefs,
+    /// The position, in tiles, of the top left corner of the level within the map.
+    origin: (i32, i32),
+    /// The size of the level, in tiles.
+    size: (u32, u32),
+}
+
+impl<'map> Converter<'map> {
+    fn new(map: &'map Map) -> Self {
+        let (origin, size) = map.tile_bounds();
+        Self {
+            map,
+            warnings: Vec::new(),
+            next_uid: 1,
+            tileset_uids: Vec::new(),
+            layer_defs: Vec::new(),
+            layer_instances: Vec::new(),
+            layer_identifiers: HashMap::new(),
+            entity_defs: Vec::new(),
+            level_fields: FieldDefs::default(),
+            origin,
+            size,
+        }
+    }
+
+    fn convert(mut self) -> LdtkProject {
+        let map = self.map;
+        if map.orientation != Orientation::Orthogonal {
+            self.warnings.push(LdtkWarning::NotOrthogonal);
+        }
+
+        let tilesets: Vec<Value> = map
+            .tilesets()
+            .iter()
+            .enumerate()
+            .filter_map(|(index, tileset)| self.convert_tileset(index, tileset))
+            .collect();
+
+        self.convert_layers(map.layers(), "", 1.0, (0.0, 0.0), true);
+        // LDtk lists layers from top to bottom
+        self.layer_defs.reverse();
+        self.layer_instances.reverse();
+
+        let level_uid = self.uid();
+        let level_field_instances = self.field_instances(&map.properties, None);
+        let level_name = map
+            .source
+            .file_stem()
+            .map(|stem| identifier(&stem.to_string_lossy()))
+            .unwrap_or_else(|| "Level_0".to_owned());
+        let (width, height) = self.pixel_size();
+
+        let entity_defs: Vec<Value> = self
+            .entity_defs
+            .iter()
+            .map(|def| {
+                json!({
+                    "identifier": def.identifier,
+                    "uid": def.uid,
+                    "tags": [],
+                    "exportToToc": false,
+                    "allowOutOfBounds": false,
+                    "doc": null,
+                    "width": def.width,
+                    "height": def.height,
+                    "resizableX": true,
+                    "resizableY": true,
+                    "keepAspectRatio": false,
+                    "tileOpacity": 1,
+                    "fillOpacity": 0.08,
+                    "lineOpacity": 0,
+                    "hollow": false,
+                    "color": "#94D9B3",
+                    "renderMode": "Rectangle",
+                    "showName": true,
+                    "tilesetId": null,
+                    "tileRenderMode": "FitInside",
+                    "tileRect": null,
+                    "uiTileRect": null,
+                    "nineSliceBorders": [],
+                    "maxCount": 0,
+                    "limitScope": "PerLevel",
+                    "limitBehavior": "MoveLastOne",
+                    "pivotX": 0,
+                    "pivotY": 0,
+                    "fieldDefs": def.fields.defs,
+                })
+            })
+            .collect();
+
+        let level = json!({
+            "identifier": level_name,
+            "iid": iid(level_uid),
+            "uid": level_uid,
+            "worldX": 0,
+            "worldY": 0,
+            "worldDepth": 0,
+            "pxWid": width,
+            "pxHei": height,
+            "__bgColor": map.background_color.map_or_else(|| "#696A79".to_owned(), hex_color),
+            "bgColor": map.background_color.map(hex_color),
+            "useAutoIdentifier": false,
+            "bgRelPath": null,
+            "bgPos": null,
+            "bgPivotX": 0.5,
+            "bgPivotY": 0.5,
+            "__smartColor": "#ADADB5",
+            "__bgPos": null,
+            "externalRelPath": null,
+            "fieldInstances": level_field_instances,
+            "layerInstances": self.layer_instances,
+            "__neighbours": [],
+        });
+
+        let project_uid = self.uid();
+        let json = json!({
+            "__header__": {
+                "fileType": "LDtk Project JSON",
+                "app": "LDtk",
+                "doc": "https://ldtk.io/json",
+                "schema": "https://ldtk.io/files/JSON_SCHEMA.json",
+                "appAuthor": "Sebastien 'deepnight' Benard",
+                "appVersion": JSON_VERSION,
+                "url": "https://ldtk.io",
+            },
+            "iid": iid(project_uid),
+            "jsonVersion": JSON_VERSION,
+            "appBuildId": 0,
+            "nextUid": self.next_uid,
+            "identifierStyle": "Free",
+            "toc": [],
+            "worldLayout": "Free",
+            "worldGridWidth": width,
+            "worldGridHeight": height,
+            "defaultLevelWidth": width,
+            "defaultLevelHeight": height,
+            "defaultPivotX": 0,
+            "defaultPivotY": 0,
+            "defaultGridSize": map.tile_width,
+            "defaultEntityWidth": map.tile_width,
+            "defaultEntityHeight": map.tile_height,
+            "bgColor": "#40465B",
+            "defaultLevelBgColor": "#696A79",
+            "minifyJson": false,
+            "externalLevels": false,
+            "exportTiled": false,
+            "simplifiedExport": false,
+            "imageExportMode": "None",
+            "exportLevelBg": true,
+            "pngFilePattern": null,
+            "backupOnSave": false,
+            "backupLimit": 10,
+            "backupRelPath": null,
+            "levelNamePattern": "Level_%idx",
+            "tutorialDesc": null,
+            "customCommands": [],
+            "flags": [],
+            "defs": {
+                "layers": self.layer_defs,
+                "entities": entity_defs,
+                "tilesets": tilesets,
+                "enums": [],
+                "externalEnums": [],
+                "levelFields": self.level_fields.defs,
+            },
+            "levels": [level],
+            "worlds": [],
+            "dummyWorldIid": iid(project_uid + 1),
+        });
+
+        LdtkProject {
+            json,
+            warnings: self.warnings,
+        }
+    }
+
+    fn uid(&mut self) -> i64 {
+        let uid = self.next_uid;
+        self.next_uid += 1;
+        uid
+    }
+
+    fn pixel_size(&self) -> (u32, u32) {
+        (
+            self.size.0 * self.map.tile_width,
+            self.size.1 * self.map.tile_height,
+        )
+    }
+
+    fn convert_tileset(&mut self, index: usize, tileset: &Tileset) -> Option<Value> {
+        let image = match &tileset.image {
+            Some(image) => image,
+            None => {
+                self.warnings.push(LdtkWarning::ImageCollection {
+                    tileset_index: index,
+                });
+                self.tileset_uids.push(None);
+                return None;
+            }
+        };
+        if tileset.tile_width != tileset.tile_height {
+            self.warnings.push(LdtkWarning::NonSquareTiles {
+                tileset_index: index,
+            });
+        }
+
+        let uid = self.uid();
+        self.tileset_uids.push(Some(uid));
+        let grid_size = tileset.tile_width;
+        Some(json!({
+            "__cWid": tileset.columns,
+            "__cHei": tileset.tilecount.div_ceil(tileset.columns.max(1)),
+            "identifier": identifier(&tileset.name),
+            "uid": uid,
+            "relPath": image_path(&image.source),
+            "embedAtlas": null,
+            "pxWid": image.width,
+            "pxHei": image.height,
+            "tileGridSize": grid_size,
+            "spacing": tileset.spacing,
+            "padding": tileset.margin,
+            "tags": [],
+            "tagsSourceEnumUid": null,
+            "enumTags": [],
+            "customData": [],
+            "savedSelections": [],
+            "cachedPixelData": null,
+        }))
+    }
+
+    fn convert_layers(
+        &mut self,
+        layers: impl Iterator<Item = Layer<'map>>,
+        prefix: &str,
+        opacity: f32,
+        offset: (f32, f32),
+        visible: bool,
+    ) {
+        for layer in layers {
+            let name = format!("{}{}", prefix, layer.name);
+            let opacity = opacity * layer.opacity;
+            let offset = (offset.0 + layer.offset_x, offset.1 + layer.offset_y);
+            let visible = visible && layer.visible;
+
+            if !layer.properties.is_empty() {
+                self.warnings.push(LdtkWarning::LayerProperties {
+                    layer_id: layer.id(),
+                });
+            }
+
+            match layer.layer_type() {
+                LayerType::Tiles(tiles) => {
+                    self.convert_tile_layer(&layer, tiles, &name, opacity, offset, visible)
+                }
+                LayerType::Objects(objects) => {
+                    let entities: Vec<Value> = objects
+                        .objects()
+                        .map(|object| self.convert_object(&object, offset))
+                        .collect();
+                    let (uid, identifier) = self.layer_def(&name, "Entities", None);
+                    let mut instance =
+                        self.layer_instance(uid, identifier, "Entities", None, opacity, visible);
+                    instance.insert("entityInstances".to_owned(), entities.into());
+                    self.layer_instances.push(Value::Object(instance));
+                }
+                LayerType::Image(_) => self.warnings.push(LdtkWarning::ImageLayer {
+                    layer_id: layer.id(),
+                }),
+                LayerType::Group(group) => self.convert_layers(
+                    group.layers(),
+                    &format!("{}_", name),
+                    opacity,
+                    offset,
+                    visible,
+                ),
+            }
+        }
+    }
+
+    fn convert_tile_layer(
+        &mut self,
+        layer: &Layer<'map>,
+        tiles: TileLayer<'map>,
+        name: &str,
+        opacity: f32,
+        offset: (f32, f32),
+        visible: bool,
+    ) {
+        let mut tiles_by_tileset: Vec<Vec<Value>> = vec![Vec::new(); self.map.tilesets().len()];
+        let mut rotated = false;
+        let (columns, rows) = self.size;
+        for y in 0..rows as i32 {
+            for x in 0..columns as i32 {
+                let (map_x, map_y) = (x + self.origin.0, y + self.origin.1);
+                let tile = match &tiles {
+                    TileLayer::Finite(tiles) => tiles.get_tile_data(map_x, map_y),
+                    TileLayer::Infinite(tiles) => tiles.get_tile_data(map_x, map_y),
+                };
+                if let Some(tile) = tile {
+                    rotated |= tile.flip_d;
+                    if let Some(grid_tile) = self.grid_tile(&tile, x, y, offset) {
+                        tiles_by_tileset[tile.tileset_index()].push(grid_tile);
+                    }
+                }
+            }
+        }
+        if rotated {
+            self.warnings.push(LdtkWarning::RotatedTiles {
+                layer_id: layer.id(),
+            });
+        }
+
+        let used: Vec<(usize, Vec<Value>)> = tiles_by_tileset
+            .into_iter()
+            .enumerate()
+            .filter(|(_, tiles)| !tiles.is_empty())
+            .collect();
+        let split = used.len() > 1;
+        for (tileset_index, grid_tiles) in used {
+            let name = if split {
+                format!("{}_{}", name, self.map.tilesets()[tileset_index].name)
+            } else {
+                name.to_owned()
+            };
+            let tileset_uid = self.tileset_uids[tileset_index];
+            let (uid, identifier) = self.layer_def(&name, "Tiles", tileset_uid);
+            let mut instance =
+                self.layer_instance(uid, identifier, "Tiles", tileset_uid, opacity, visible);
+            let rel_path = self.map.tilesets()[tileset_index]
+                .image
+                .as_ref()
+                .map(|image| image_path(&image.source));
+            instance.insert("__tilesetRelPath".to_owned(), rel_path.into());
+            instance.insert("gridTiles".to_owned(), grid_tiles.into());
+            self.layer_instances.push(Value::Object(instance));
+        }
+    }
+
+    fn grid_tile(&self, tile: &LayerTileData, x: i32, y: i32, offset: (f32, f32)) -> Option<Value> {
+        self.tileset_uids[tile.tileset_index()]?;
+        let tileset = &self.map.tilesets()[tile.tileset_index()];
+        let rect = tileset.tile_rect(tile.id())?;
+        let flip = tile.flip_h as u8 | (tile.flip_v as u8) << 1;
+        let grid = self.map.tile_width as i32;
+        Some(json!({
+            "px": [
+                x * grid + offset.0 as i32,
+                y * self.map.tile_height as i32 + offset.1 as i32,
+            ],
+            "src": [rect.x, rect.y],
+            "f": flip,
+            "t": tile.id(),
+            "d": [x + y * self.size.0 as i32],
+            "a": 1,
+        }))
+    }
+
+    fn convert_object(&mut self, object: &Object<'map>, offset: (f32, f32)) -> Value {
+        let (left, top, width, height) = object_bounds(object);
+        let (left, top) = (
+            left + offset.0 - (self.origin.0 * self.map.tile_width as i32) as f32,
+            top + offset.1 - (self.origin.1 * self.map.tile_height as i32) as f32,
+        );
+
+        let class = if object.user_type.is_empty() {
+            "Object"
+        } else {
+            &object.user_type
+        };
+        let identifier = identifier(class);
+        let def_index = match self
+            .entity_defs
+            .iter()
+            .position(|def| def.identifier == identifier)
+        {
+            Some(index) => index,
+            None => {
+                let uid = self.uid();
+                self.entity_defs.push(EntityDef {
+                    uid,
+                    identifier: identifier.clone(),
+                    width: self.map.tile_width,
+                    height: self.map.tile_height,
+                    fields: FieldDefs::default(),
+                });
+                self.entity_defs.len() - 1
+            }
+        };
+        let field_instances = self.field_instances(&object.properties, Some(def_index));
+
+        let tile = object.get_tile().and_then(|tile| {
+            let tileset_index = self
+                .map
+                .tilesets()
+                .iter()
+                .position(|tileset| std::ptr::eq(tileset.as_ref(), tile.get_tileset()))?;
+            let rect = tile.get_tileset().tile_rect(tile.id())?;
+            Some(json!({
+                "tilesetUid": self.tileset_uids[tileset_index]?,
+                "x": rect.x,
+                "y": rect.y,
+                "w": rect.width,
+                "h": rect.height,
+            }))
+        });
+
+        let uid = self.uid();
+        let (px, py) = (left.round() as i32, top.round() as i32);
+        json!({
+            "__identifier": identifier,
+            "__grid": [
+                px.div_euclid(self.map.tile_width as i32),
+                py.div_euclid(self.map.tile_height as i32),
+            ],
+            "__pivot": [0, 0],
+            "__tags": [],
+            "__tile": tile,
+            "__smartColor": "#94D9B3",
+            "__worldX": px,
+            "__worldY": py,
+            "iid": iid(uid),
+            "width": (width.round() as u32).max(1),
+            "height": (height.round() as u32).max(1),
+            "defUid": self.entity_defs[def_index].uid,
+            "px": [px, py],
+            "fieldInstances": field_instances,
+        })
+    }
+
+    /// Returns the UID and identifier of a new layer definition.
+    fn layer_def(&mut self, name: &str, ty: &str, tileset_uid: Option<i64>) -> (i64, String) {
+        let mut identifier = identifier(name);
+        let count = self
+            .layer_identifiers
+            .entry(identifier.clone())
+            .or_insert(0);
+        *count += 1;
+        if *count > 1 {
+            identifier = format!("{}_{}", identifier, count);
+        }
+
+        let uid = self.uid();
+        self.layer_defs.push(json!({
+            "__type": ty,
+            "identifier": identifier,
+            "type": ty,
+            "uid": uid,
+            "doc": null,
+            "uiColor": null,
+            "gridSize": self.map.tile_width,
+            "guideGridWid": 0,
+            "guideGridHei": 0,
+            "displayOpacity": 1,
+            "inactiveOpacity": 1,
+            "hideInList": false,
+            "hideFieldsWhenInactive": true,
+            "canSelectWhenInactive": true,
+            "renderInWorldView": true,
+            "pxOffsetX": 0,
+            "pxOffsetY": 0,
+            "parallaxFactorX": 0,
+            "parallaxFactorY": 0,
+            "parallaxScaling": true,
+            "requiredTags": [],
+            "excludedTags": [],
+            "autoTilesKilledByOtherLayerUid": null,
+            "uiFilterTags": [],
+            "useAsyncRender": false,
+            "intGridValues": [],
+            "intGridValuesGroups": [],
+            "autoRuleGroups": [],
+            "autoSourceLayerDefUid": null,
+            "tilesetDefUid": tileset_uid,
+            "tilePivotX": 0,
+            "tilePivotY": 0,
+            "biomeFieldUid": null,
+        }));
+        (uid, identifier)
+    }
+
+    fn layer_instance(
+        &mut self,
+        def_uid: i64,
+        identifier: String,
+        ty: &str,
+        tileset_uid: Option<i64>,
+        opacity: f32,
+        visible: bool,
+    ) -> JsonObject<String, Value> {
+        let uid = self.uid();
+        let value = json!({
+            "__identifier": identifier,
+            "__type": ty,
+            "__cWid": self.size.0,
+            "__cHei": self.size.1,
+            "__gridSize": self.map.tile_width,
+            "__opacity": opacity,
+            "__pxTotalOffsetX": 0,
+            "__pxTotalOffsetY": 0,
+            "__tilesetDefUid": tileset_uid,
+            "__tilesetRelPath": null,
+            "iid": iid(uid),
+            "levelId": 0,
+            "layerDefUid": def_uid,
+            "pxOffsetX": 0,
+            "pxOffsetY": 0,
+            "visible": visible,
+            "optionalRules": [],
+            "intGridCsv": [],
+            "autoLayerTiles": [],
+            "seed": uid,
+            "overrideTilesetUid": null,
+            "gridTiles": [],
+            "entityInstances": [],
+        });
+        match value {
+            Value::Object(object) => object,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Converts properties into field instances of the level if `entity` is [`None`], or of an
+    /// entity definition otherwise, adding the fields to the definition as needed.
+    fn field_instances(&mut self, properties: &Properties, entity: Option<usize>) -> Vec<Value> {
+        let mut names: Vec<&Arc<str>> = properties.keys().collect();
+        names.sort();
+
+        let mut instances = Vec::new();
+        for name in names {
+            let (ty, value) = match field_value(&properties[name]) {
+                Some(field) => field,
+                None => {
+                    self.warnings.push(LdtkWarning::UnsupportedProperty {
+                        name: name.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let existing = match entity {
+                Some(index) => self.entity_defs[index].fields.types.get(&**name).copied(),
+                None => self.level_fields.types.get(&**name).copied(),
+            };
+            let def_uid = match existing {
+                Some((uid, existing_ty)) if existing_ty == ty => uid,
+                Some(_) => {
+                    self.warnings.push(LdtkWarning::UnsupportedProperty {
+                        name: name.to_string(),
+                    });
+                    continue;
+                }
+                None => {
+                    let uid = self.uid();
+                    let fields = match entity {
+                        Some(index) => &mut self.entity_defs[index].fields,
+                        None => &mut self.level_fields,
+                    };
+                    fields.types.insert(name.to_string(), (uid, ty));
+                    fields.defs.push(json!({
+                        "identifier": identifier(name),
+                        "doc": null,
+                        "__type": ty,
+                        "uid": uid,
+                        "type": format!("F_{}", if ty == "FilePath" { "Path" } else { ty }),
+                        "isArray": false,
+                        "canBeNull": true,
+                        "arrayMinLength": null,
+                        "arrayMaxLength": null,
+                        "editorDisplayMode": "Hidden",
+                        "editorDisplayScale": 1,
+                        "editorDisplayPos": "Above",
+                        "editorLinkStyle": "StraightArrow",
+                        "editorDisplayColor": null,
+                        "editorAlwaysShow": false,
+                        "editorShowInWorld": true,
+                        "editorCutLongValues": true,
+                        "editorTextSuffix": null,
+                        "editorTextPrefix": null,
+                        "useForSmartColor": false,
+                        "exportToToc": false,
+                        "searchable": false,
+                        "min": null,
+                        "max": null,
+                        "regex": null,
+                        "acceptFileTypes": null,
+                        "defaultOverride": null,
+                        "textLanguageMode": null,
+                        "symmetricalRef": false,
+                        "autoChainRef": true,
+                        "allowOutOfLevelRef": true,
+                        "allowedRefs": "OnlySame",
+                        "allowedRefsEntityUid": null,
+                        "allowedRefTags": [],
+                        "tilesetUid": null,
+                    }));
+                    uid
+                }
+            };
+
+            instances.push(json!({
+                "__identifier": identifier(name),
+                "__type": ty,
+                "__value": value,
+                "__tile": null,
+                "defUid": def_uid,
+                "realEditorValues": [],
+            }));
+        }
+        instances
+    }
+}
+
+/// Returns the LDtk type and value of a field holding a property, if LDtk supports it.
+fn field_value(value: &PropertyValue) -> Option<(&'static str, Value)> {
+    Some(match value {
+        PropertyValue::BoolValue(value) => ("Bool", (*value).into()),
+        PropertyValue::FloatValue(value) => ("Float", (*value).into()),
+        PropertyValue::IntValue(value) => ("Int", (*value).into()),
+        PropertyValue::ObjectValue(value) => ("Int", (*value).into()),
+        PropertyValue::ColorValue(value) => ("Color", hex_color(*value).into()),
+        PropertyValue::StringValue(value) => ("String", value.clone().into()),
+        PropertyValue::FileValue(value) => ("FilePath", value.clone().into()),
+        PropertyValue::Custom(custom) => ("String", custom.value.clone().into()),
+        PropertyValue::ClassValue { .. } => return None,
+    })
+}
+
+/// Returns the left, top, width and height of the rectangle bounding an object.
+fn object_bounds(object: &Object) -> (f32, f32, f32, f32) {
+    match &object.shape {
+        ObjectShape::Rect { width, height } if object.get_tile().is_some() => {
+            // Tile objects are positioned by their bottom left corner
+            (object.x, object.y - height, *width, *height)
+        }
+        ObjectShape::Rect { width, height }
+        | ObjectShape::Ellipse { width, height }
+        | ObjectShape::Text { width, height, .. } => (object.x, object.y, *width, *height),
+        ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+            let (mut left, mut top, mut right, mut bottom) = (0f32, 0f32, 0f32, 0f32);
+            for (x, y) in points {
+                left = left.min(*x);
+                top = top.min(*y);
+                right = right.max(*x);
+                bottom = bottom.max(*y);
+            }
+            (object.x + left, object.y + top, right - left, bottom - top)
+        }
+        ObjectShape::Point(x, y) => (*x, *y, 1.0, 1.0),
+    }
+}
+
+/// Turns a name into a valid LDtk identifier, which may only contain ASCII letters, digits and
+/// underscores, and can't start with a digit.
+fn identifier(name: &str) -> String {
+    let mut identifier: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+    identifier
+}
+
+/// Builds a unique instance identifier from a UID, in the UUID format LDtk uses.
+fn iid(uid: i64) -> String {
+    format!("00000000-0000-0000-0000-{:012x}", uid)
+}
+
+/// Formats an image path the way LDtk stores paths, with forward slashes.
+fn image_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+fn hex_color(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.red, color.green, color.blue)
+}
@@ -0,0 +1,8 @@
+//! Converters from the types of this crate to the formats of other level editors and engines.
+//!
+//! Each converter requires the feature of the same name.
+
+#[cfg(feature = "godot")]
+pub mod godot;
+#[cfg(feature = "ldtk")]
+pub mod ldtk;
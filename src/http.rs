@@ -0,0 +1,164 @@
+//! A [`ResourceReader`] downloading the files it reads. Requires the `http` feature.
+
+use std::{
+    fmt,
+    io::Read,
+    path::{Component, Path, PathBuf},
+};
+
+use crate::{util::normalize_path, ResourceReader};
+
+/// Errors that can occur while reading a file with a [`HttpResourceReader`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum HttpError {
+    /// The path can't be turned into a URL below the base URL of the reader, since it is absolute
+    /// or goes up past the base URL with `..`.
+    InvalidPath(PathBuf),
+    /// The server answered with an error status, such as 404 if the file doesn't exist.
+    Status {
+        /// The URL of the file.
+        url: String,
+        /// The HTTP status code returned by the server.
+        code: u16,
+    },
+    /// The request could not be made or the answer could not be received, e.g. because the server
+    /// could not be reached.
+    Transport {
+        /// The URL of the file.
+        url: String,
+        /// The error returned by [`ureq`].
+        err: Box<ureq::Transport>,
+    },
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HttpError::InvalidPath(path) => write!(
+                f,
+                "Path {} is not relative to the base URL of the reader",
+                path.display()
+            ),
+            HttpError::Status { url, code } => {
+                write!(f, "Downloading {} failed with status {}", url, code)
+            }
+            HttpError::Transport { url, err } => write!(f, "Could not download {}: {}", url, err),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HttpError::Transport { err, .. } => Some(err.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// A [`ResourceReader`] that downloads files from a web server, reading each path relative to a
+/// base URL. Requires the `http` feature.
+///
+/// Paths are normalized before being appended to the base URL, and their components are
+/// percent-encoded, so that maps referencing tilesets as `../tilesets/forest tiles.tsx` work as
+/// they do on the filesystem. Requests are blocking.
+///
+/// ## Example
+/// ```no_run
+/// use tiled::{HttpResourceReader, Loader};
+///
+/// # fn main() -> tiled::Result<()> {
+/// let reader = HttpResourceReader::new("https://example.com/game/assets");
+/// let mut loader = Loader::with_reader(reader);
+///
+/// // Downloads https://example.com/game/assets/maps/level1.tmx and the files it references
+/// let map = loader.load_tmx_map("maps/level1.tmx")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct HttpResourceReader {
+    base_url: String,
+    agent: ureq::Agent,
+}
+
+impl HttpResourceReader {
+    /// Creates a reader downloading files relative to `base_url`, using a new [`ureq::Agent`].
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_agent(base_url, ureq::Agent::new())
+    }
+
+    /// Creates a reader downloading files relative to `base_url` with the agent given, e.g. one
+    /// configured with timeouts or a proxy.
+    pub fn with_agent(base_url: impl Into<String>, agent: ureq::Agent) -> Self {
+        let mut base_url = base_url.into();
+        if !base_url.ends_with('/') {
+            base_url.push('/');
+        }
+        Self { base_url, agent }
+    }
+
+    /// The URL paths are relative to, which always ends with a slash.
+    #[inline]
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Returns the URL the file at `path` is downloaded from.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::HttpResourceReader;
+    ///
+    /// let reader = HttpResourceReader::new("https://example.com/assets");
+    /// assert_eq!(
+    ///     reader.url(Path::new("maps/../tilesets/forest tiles.tsx")).unwrap(),
+    ///     "https://example.com/assets/tilesets/forest%20tiles.tsx"
+    /// );
+    /// ```
+    pub fn url(&self, path: &Path) -> Result<String, HttpError> {
+        let mut url = self.base_url.clone();
+        for (index, component) in normalize_path(path).components().enumerate() {
+            match component {
+                Component::Normal(name) => {
+                    if index > 0 {
+                        url.push('/');
+                    }
+                    percent_encode(&name.to_string_lossy(), &mut url);
+                }
+                _ => return Err(HttpError::InvalidPath(path.to_owned())),
+            }
+        }
+        Ok(url)
+    }
+}
+
+impl ResourceReader for HttpResourceReader {
+    type Resource = Box<dyn Read + Send + Sync + 'static>;
+    type Error = HttpError;
+
+    fn read_from(&mut self, path: &Path) -> Result<Self::Resource, Self::Error> {
+        let url = self.url(path)?;
+        match self.agent.get(&url).call() {
+            Ok(response) => Ok(response.into_reader()),
+            Err(ureq::Error::Status(code, _)) => Err(HttpError::Status { url, code }),
+            Err(ureq::Error::Transport(err)) => Err(HttpError::Transport {
+                url,
+                err: Box::new(err),
+            }),
+        }
+    }
+}
+
+/// Appends `segment` to `url`, percent-encoding every byte that isn't an unreserved character.
+fn percent_encode(segment: &str, url: &mut String) {
+    for byte in segment.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+}
@@ -4,12 +4,14 @@ use xml::attribute::OwnedAttribute;
 
 use crate::{
     error::{Error, Result},
+    parse::ParseContext,
     properties::Color,
     util::*,
 };
 
 /// A reference to an image stored somewhere within the filesystem.
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct Image {
     /// The **uncanonicalized** filepath of the image, starting from the path given to load the file
     /// this image is in. See the example for more details.
@@ -72,6 +74,7 @@ impl Image {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         path_relative_to: impl AsRef<Path>,
+        ctx: &ParseContext,
     ) -> Result<Image> {
         let (c, (s, w, h)) = get_attrs!(
             for v in attrs {
@@ -84,8 +87,14 @@ impl Image {
         );
 
         parse_tag!(parser, "image", {});
+        // Skipped images are only parsed for their size, so their path doesn't matter
+        let source = if ctx.skip_images() {
+            PathBuf::from(s)
+        } else {
+            ctx.resolve_path(path_relative_to.as_ref(), &s)?
+        };
         Ok(Image {
-            source: path_relative_to.as_ref().join(s),
+            source,
             width: w,
             height: h,
             transparent_colour: c,
@@ -0,0 +1,192 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    Color, DrawOrder, Encoding, FiniteTileLayerData, GroupLayerData, Image, ImageLayerData,
+    InfiniteTileLayerData, LayerData, LayerDataType, Map, ObjectData, ObjectLayerData, Properties,
+    PropertyValue, TileLayerData, UnknownXml,
+};
+
+#[derive(Debug, Clone)]
+enum LayerBuilderType {
+    Tiles,
+    Objects(Vec<ObjectData>),
+    Image(Option<Image>),
+    Group(Vec<LayerData>),
+}
+
+/// A builder used to create new [`LayerData`] from code.
+///
+/// Layers are given an ID when built, which is reserved from the map they are going to be added
+/// to, so that it is unique within it just like Tiled would do.
+///
+/// ## Example
+/// ```
+/// use tiled::{LayerBuilder, Loader, ObjectBuilder, ObjectShape};
+///
+/// # fn main() -> tiled::Result<()> {
+/// let mut map = Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+///
+/// let door = ObjectBuilder::new(ObjectShape::Rect { width: 32., height: 64. })
+///     .user_type("door")
+///     .build(&mut map);
+/// let triggers = LayerBuilder::objects("triggers", vec![door])
+///     .visible(false)
+///     .build(&mut map);
+/// let terrain = LayerBuilder::tiles("terrain").build(&mut map);
+///
+/// assert_ne!(triggers.id(), terrain.id());
+/// assert_eq!(map.next_layer_id(), terrain.id() + 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct LayerBuilder {
+    name: String,
+    visible: bool,
+    offset: (f32, f32),
+    parallax: (f32, f32),
+    opacity: f32,
+    tint_color: Option<Color>,
+    properties: Properties,
+    user_type: Option<String>,
+    repeat: (bool, bool),
+    draw_order: DrawOrder,
+    layer_type: LayerBuilderType,
+}
+
+impl LayerBuilder {
+    fn new(name: impl Into<String>, layer_type: LayerBuilderType) -> Self {
+        Self {
+            name: name.into(),
+            visible: true,
+            offset: (0., 0.),
+            parallax: (1., 1.),
+            opacity: 1.,
+            tint_color: None,
+            properties: Properties::new(),
+            user_type: None,
+            repeat: (false, false),
+            draw_order: DrawOrder::TopDown,
+            layer_type,
+        }
+    }
+
+    /// Creates a builder for an empty tile layer. The layer will be as big as the map it is built
+    /// for, or infinite if the map is.
+    pub fn tiles(name: impl Into<String>) -> Self {
+        Self::new(name, LayerBuilderType::Tiles)
+    }
+
+    /// Creates a builder for an object layer containing the given objects.
+    pub fn objects(name: impl Into<String>, objects: Vec<ObjectData>) -> Self {
+        Self::new(name, LayerBuilderType::Objects(objects))
+    }
+
+    /// Creates a builder for an image layer displaying the given image, if any.
+    pub fn image(name: impl Into<String>, image: Option<Image>) -> Self {
+        Self::new(name, LayerBuilderType::Image(image))
+    }
+
+    /// Creates a builder for a group layer containing the given layers.
+    pub fn group(name: impl Into<String>, layers: Vec<LayerData>) -> Self {
+        Self::new(name, LayerBuilderType::Group(layers))
+    }
+
+    /// Sets whether the layer is shown or hidden.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Sets the offset of the layer, in pixels.
+    pub fn offset(mut self, x: f32, y: f32) -> Self {
+        self.offset = (x, y);
+        self
+    }
+
+    /// Sets the parallax factors of the layer.
+    pub fn parallax(mut self, x: f32, y: f32) -> Self {
+        self.parallax = (x, y);
+        self
+    }
+
+    /// Sets the opacity of the layer.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+        self
+    }
+
+    /// Sets the tint color of the layer.
+    pub fn tint_color(mut self, tint_color: Color) -> Self {
+        self.tint_color = Some(tint_color);
+        self
+    }
+
+    /// Sets the type (also known as class) of the layer.
+    pub fn user_type(mut self, user_type: impl Into<String>) -> Self {
+        self.user_type = Some(user_type.into());
+        self
+    }
+
+    /// Sets whether the image of an image layer is repeated horizontally and vertically. Ignored
+    /// for other layers.
+    pub fn repeat(mut self, x: bool, y: bool) -> Self {
+        self.repeat = (x, y);
+        self
+    }
+
+    /// Sets the order the objects of an object layer are drawn in. Ignored for other layers.
+    pub fn draw_order(mut self, draw_order: DrawOrder) -> Self {
+        self.draw_order = draw_order;
+        self
+    }
+
+    /// Sets a custom property of the layer.
+    pub fn property(mut self, name: impl Into<Arc<str>>, value: PropertyValue) -> Self {
+        self.properties.insert(name.into(), value);
+        self
+    }
+
+    /// Builds the layer, giving it the next free layer ID of `map`.
+    pub fn build(self, map: &mut Map) -> LayerData {
+        let layer_type = match self.layer_type {
+            LayerBuilderType::Tiles if map.infinite() => {
+                LayerDataType::Tiles(TileLayerData::Infinite(InfiniteTileLayerData {
+                    chunks: HashMap::new(),
+                    encoding: Encoding::default(),
+                }))
+            }
+            LayerBuilderType::Tiles => LayerDataType::Tiles(TileLayerData::Finite(
+                FiniteTileLayerData::empty(map.width, map.height, map),
+            )),
+            LayerBuilderType::Objects(objects) => LayerDataType::Objects(ObjectLayerData {
+                objects,
+                colour: None,
+                draw_order: self.draw_order,
+                name_index: Default::default(),
+            }),
+            LayerBuilderType::Image(image) => LayerDataType::Image(ImageLayerData {
+                image,
+                repeat_x: self.repeat.0,
+                repeat_y: self.repeat.1,
+            }),
+            LayerBuilderType::Group(layers) => LayerDataType::Group(GroupLayerData { layers }),
+        };
+
+        LayerData {
+            name: self.name,
+            id: map.allocate_layer_id(),
+            visible: self.visible,
+            offset_x: self.offset.0,
+            offset_y: self.offset.1,
+            parallax_x: self.parallax.0,
+            parallax_y: self.parallax.1,
+            opacity: self.opacity,
+            tint_color: self.tint_color,
+            properties: self.properties,
+            user_type: self.user_type,
+            layer_type,
+            unknown_xml: UnknownXml::default(),
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Loading only some of the layers of maps, see [`Loader::set_layer_filter()`](crate::Loader::set_layer_filter).
+
+use std::sync::Arc;
+
+/// The kinds of layers, as given to layer filters in [`LayerInfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LayerKind {
+    /// A tile layer.
+    Tiles,
+    /// An object layer.
+    Objects,
+    /// An image layer.
+    Image,
+    /// A group layer.
+    Group,
+}
+
+/// What is known of a layer before its contents are read, given to the function set with
+/// [`Loader::set_layer_filter()`](crate::Loader::set_layer_filter) to decide whether it should be
+/// loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayerInfo<'a> {
+    /// The name of the layer.
+    pub name: &'a str,
+    /// The class of the layer, if any, which older versions of Tiled called its type.
+    pub class: Option<&'a str>,
+    /// The kind of the layer.
+    pub kind: LayerKind,
+}
+
+/// A function deciding which layers are loaded.
+#[derive(Clone)]
+pub(crate) struct LayerFilter(pub Arc<LayerFilterFn>);
+
+type LayerFilterFn = dyn Fn(&LayerInfo) -> bool + Send + Sync;
+
+impl std::fmt::Debug for LayerFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("LayerFilter")
+    }
+}
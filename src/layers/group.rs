@@ -1,20 +1,23 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{path::Path, sync::Arc};
 
 use crate::{
     error::Result,
     layers::{LayerData, LayerTag},
+    parse::ParseContext,
     properties::{parse_properties, Properties},
     util::*,
-    Error, Layer, MapTilesetGid, ResourceCache, ResourceReader, Tileset,
+    Color, Error, Layer, LayerType, MapTilesetGid, ResourceCache, ResourceReader, Tileset,
 };
 
 /// The raw data of a [`GroupLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct GroupLayerData {
-    layers: Vec<LayerData>,
+    pub(crate) layers: Vec<LayerData>,
 }
 
 impl GroupLayerData {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         infinite: bool,
@@ -23,64 +26,73 @@ impl GroupLayerData {
         for_tileset: Option<Arc<Tileset>>,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<(Self, Properties)> {
-        let mut properties = HashMap::new();
+        let mut properties = Properties::new();
         let mut layers = Vec::new();
-        parse_tag!(parser, "group", {
+        parse_tag!(parser, ctx, "group", {
             "layer" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Tiles,
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
-                    cache
+                    for_tileset.as_ref().cloned(),
+                    reader,
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "imagelayer" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Image,
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
-                    cache
+                    for_tileset.as_ref().cloned(),
+                    reader,
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "objectgroup" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Objects,
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
-                    cache
+                    for_tileset.as_ref().cloned(),
+                    reader,
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "group" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Group,
                     infinite,
                     map_path,
                     tilesets,
-                    for_tileset.as_ref().cloned(),reader,
-                    cache
+                    for_tileset.as_ref().cloned(),
+                    reader,
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
         });
@@ -93,7 +105,8 @@ map_wrapper!(
     #[doc = "\nAlso see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#group)."]
     #[doc = "## Note"]
     #[doc = "In Tiled, the properties of the group layer recursively affect child layers.
-    Implementing this behavior is left up to the user of this library."]
+    Use [`GroupLayer::flatten()`] or [`Map::flatten_layers()`](crate::Map::flatten_layers) to
+    combine them with the properties of the layers within the group."]
     GroupLayer => GroupLayerData
 );
 
@@ -135,4 +148,101 @@ impl<'map> GroupLayer<'map> {
             .get(index)
             .map(|data| Layer::new(self.map, data))
     }
+
+    /// Returns an iterator over the layers within this group and its nested groups that aren't
+    /// groups themselves, in display order, each along with the offset, opacity, tint color,
+    /// visibility and parallax factors resulting from the groups it is in, so that renderers can
+    /// ignore the hierarchy entirely.
+    ///
+    /// The properties of this group itself aren't included, since they belong to its [`Layer`];
+    /// use [`Map::flatten_layers()`](crate::Map::flatten_layers) to flatten every layer of a map.
+    pub fn flatten(&self) -> impl Iterator<Item = FlattenedLayer<'map>> + 'map {
+        let mut layers = Vec::new();
+        for layer in self.layers() {
+            FlattenedLayer::root(layer).flatten_into(&mut layers);
+        }
+        layers.into_iter()
+    }
+}
+
+/// A layer that isn't a group, along with its properties combined with those of the groups it is
+/// in, as returned by [`GroupLayer::flatten()`] and
+/// [`Map::flatten_layers()`](crate::Map::flatten_layers).
+///
+/// Properties are combined the way Tiled renders them: offsets are added, opacities and parallax
+/// factors are multiplied, tint colors are multiplied channel by channel and layers are only
+/// visible if all of their groups are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct FlattenedLayer<'map> {
+    /// The layer itself.
+    pub layer: Layer<'map>,
+    /// The offset of the layer in pixels, including the offsets of its groups.
+    pub offset: (f32, f32),
+    /// The opacity of the layer multiplied by the opacities of its groups.
+    pub opacity: f32,
+    /// The tint color of the layer multiplied by the tint colors of its groups, or `None` if none
+    /// of them is tinted.
+    pub tint_color: Option<Color>,
+    /// Whether the layer and all of its groups are visible.
+    pub visible: bool,
+    /// The parallax factors of the layer multiplied by those of its groups.
+    pub parallax: (f32, f32),
+}
+
+impl<'map> FlattenedLayer<'map> {
+    /// A layer with only its own properties, as if it wasn't in any group.
+    pub(crate) fn root(layer: Layer<'map>) -> Self {
+        Self {
+            layer,
+            offset: (layer.offset_x, layer.offset_y),
+            opacity: layer.opacity,
+            tint_color: layer.tint_color,
+            visible: layer.visible,
+            parallax: (layer.parallax_x, layer.parallax_y),
+        }
+    }
+
+    /// Returns `layer` within the group `self` is.
+    fn child(&self, layer: Layer<'map>) -> Self {
+        Self {
+            layer,
+            offset: (
+                self.offset.0 + layer.offset_x,
+                self.offset.1 + layer.offset_y,
+            ),
+            opacity: self.opacity * layer.opacity,
+            tint_color: multiply_tints(self.tint_color, layer.tint_color),
+            visible: self.visible && layer.visible,
+            parallax: (
+                self.parallax.0 * layer.parallax_x,
+                self.parallax.1 * layer.parallax_y,
+            ),
+        }
+    }
+
+    /// Pushes this layer to `layers`, or its flattened children if it is a group.
+    pub(crate) fn flatten_into(self, layers: &mut Vec<FlattenedLayer<'map>>) {
+        match self.layer.layer_type() {
+            LayerType::Group(group) => {
+                for child in group.layers() {
+                    self.child(child).flatten_into(layers);
+                }
+            }
+            _ => layers.push(self),
+        }
+    }
+}
+
+fn multiply_tints(a: Option<Color>, b: Option<Color>) -> Option<Color> {
+    let channel = |a: u8, b: u8| (a as u16 * b as u16 / 255) as u8;
+    match (a, b) {
+        (Some(a), Some(b)) => Some(Color {
+            alpha: channel(a.alpha, b.alpha),
+            red: channel(a.red, b.red),
+            green: channel(a.green, b.green),
+            blue: channel(a.blue, b.blue),
+        }),
+        (tint, None) | (None, tint) => tint,
+    }
 }
@@ -1,40 +1,150 @@
-use std::{collections::HashMap, path::Path};
+use std::{ops::Range, path::Path};
+
+use xml::attribute::OwnedAttribute;
 
 use crate::{
+    dependencies::DependencyKind,
+    parse::ParseContext,
     parse_properties,
-    util::{map_wrapper, parse_tag, XmlEventResult},
+    util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
     Error, Image, Properties, Result,
 };
 
 /// The raw data of an [`ImageLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct ImageLayerData {
     /// The single image this layer contains, if it exists.
     pub image: Option<Image>,
+    /// Whether the image is repeated horizontally to fill the map, e.g. for backgrounds.
+    pub repeat_x: bool,
+    /// Whether the image is repeated vertically to fill the map, e.g. for backgrounds.
+    pub repeat_y: bool,
 }
 
 impl ImageLayerData {
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
+        attrs: Vec<OwnedAttribute>,
         map_path: &Path,
+        ctx: &mut ParseContext,
     ) -> Result<(Self, Properties)> {
+        let (repeat_x, repeat_y) = get_attrs!(
+            for v in attrs {
+                Some("repeatx") => repeat_x ?= v.parse().map(|x: i32| x == 1),
+                Some("repeaty") => repeat_y ?= v.parse().map(|x: i32| x == 1),
+            }
+            (repeat_x, repeat_y)
+        );
         let mut image: Option<Image> = None;
-        let mut properties = HashMap::new();
+        let mut properties = Properties::new();
 
         let path_relative_to = map_path.parent().ok_or(Error::PathIsNotFile)?;
 
-        parse_tag!(parser, "imagelayer", {
+        parse_tag!(parser, ctx, "imagelayer", {
             "image" => |attrs| {
-                image = Some(Image::new(parser, attrs, path_relative_to)?);
+                let layer_image = Image::new(parser, attrs, path_relative_to, ctx)?;
+                if !ctx.skip_images() {
+                    ctx.dependencies_mut().add(&layer_image.source, DependencyKind::Image);
+                    image = Some(layer_image);
+                }
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
         });
-        Ok((ImageLayerData { image }, properties))
+        Ok((
+            ImageLayerData {
+                image,
+                repeat_x: repeat_x.unwrap_or(false),
+                repeat_y: repeat_y.unwrap_or(false),
+            },
+            properties,
+        ))
+    }
+
+    /// Returns the positions to draw the image of the layer at for it to cover the part of the
+    /// map within the viewport given as `(x, y, width, height)` in pixels, taking
+    /// [`repeat_x`](Self::repeat_x) and [`repeat_y`](Self::repeat_y) into account.
+    ///
+    /// `origin` is the position of the image when it isn't repeated, usually the offset of the
+    /// layer, adjusted for parallax if the renderer supports it. Positions are those of the
+    /// top-left corner of each copy of the image and are yielded row by row. Copies that wouldn't
+    /// overlap the viewport are skipped, so an image that isn't repeated yields at most its
+    /// origin, and a layer without an image yields nothing.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Image, ImageLayerData};
+    ///
+    /// // A 100x50 background repeated horizontally
+    /// let layer = ImageLayerData {
+    ///     image: Some(Image {
+    ///         source: "sky.png".into(),
+    ///         width: 100,
+    ///         height: 50,
+    ///         transparent_colour: None,
+    ///     }),
+    ///     repeat_x: true,
+    ///     repeat_y: false,
+    /// };
+    ///
+    /// let viewport = (150.0, 0.0, 200.0, 100.0);
+    /// let positions: Vec<_> = layer.draw_positions((0.0, 0.0), viewport).collect();
+    /// assert_eq!(positions, [(100.0, 0.0), (200.0, 0.0), (300.0, 0.0)]);
+    /// ```
+    pub fn draw_positions(
+        &self,
+        origin: (f32, f32),
+        viewport: (f32, f32, f32, f32),
+    ) -> impl Iterator<Item = (f32, f32)> {
+        let (columns, rows, width, height) = match &self.image {
+            Some(image) => (
+                copies(
+                    origin.0,
+                    image.width as f32,
+                    viewport.0,
+                    viewport.2,
+                    self.repeat_x,
+                ),
+                copies(
+                    origin.1,
+                    image.height as f32,
+                    viewport.1,
+                    viewport.3,
+                    self.repeat_y,
+                ),
+                image.width as f32,
+                image.height as f32,
+            ),
+            None => (0..0, 0..0, 0.0, 0.0),
+        };
+        rows.flat_map(move |row| {
+            columns.clone().map(move |column| {
+                (
+                    origin.0 + column as f32 * width,
+                    origin.1 + row as f32 * height,
+                )
+            })
+        })
+    }
+}
+
+/// Returns the indices of the copies of an image of length `size` starting at `origin` along an
+/// axis that overlap the range of the viewport starting at `start` of length `length`.
+fn copies(origin: f32, size: f32, start: f32, length: f32, repeat: bool) -> Range<i64> {
+    if size <= 0.0 || length <= 0.0 {
+        return 0..0;
+    }
+    if !repeat {
+        let overlaps = origin < start + length && origin + size > start;
+        return if overlaps { 0..1 } else { 0..0 };
     }
+    let first = ((start - origin) / size).floor() as i64;
+    let end = ((start + length - origin) / size).ceil() as i64;
+    first..end
 }
 
 map_wrapper!(
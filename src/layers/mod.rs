@@ -3,10 +3,14 @@ use std::{path::Path, sync::Arc};
 use xml::attribute::OwnedAttribute;
 
 use crate::{
-    error::Result, properties::Properties, util::*, Color, Map, MapTilesetGid, ResourceCache,
-    ResourceReader, Tileset,
+    error::Result, parse::ParseContext, properties::Properties, util::*, Color, Map, MapTilesetGid,
+    PropertiesExt, ResourceCache, ResourceReader, Tileset, UnknownXml,
 };
 
+mod builder;
+pub use builder::*;
+mod filter;
+pub use filter::*;
 mod image;
 pub use image::*;
 mod object;
@@ -17,6 +21,7 @@ mod group;
 pub use group::*;
 
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum LayerDataType {
     Tiles(TileLayerData),
     Objects(ObjectLayerData),
@@ -32,12 +37,34 @@ pub(crate) enum LayerTag {
     Group,
 }
 
+impl LayerTag {
+    /// The name of the element layers of this kind are read from.
+    fn name(&self) -> &'static str {
+        match self {
+            LayerTag::Tiles => "layer",
+            LayerTag::Objects => "objectgroup",
+            LayerTag::Image => "imagelayer",
+            LayerTag::Group => "group",
+        }
+    }
+
+    fn kind(&self) -> LayerKind {
+        match self {
+            LayerTag::Tiles => LayerKind::Tiles,
+            LayerTag::Objects => LayerKind::Objects,
+            LayerTag::Image => LayerKind::Image,
+            LayerTag::Group => LayerKind::Group,
+        }
+    }
+}
+
 /// The raw data of a [`Layer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerData {
     /// The layer's name, set arbitrarily by the user.
     pub name: String,
-    id: u32,
+    pub(crate) id: u32,
     /// Whether this layer should be visible or not.
     pub visible: bool,
     /// The layer's x offset (in pixels).
@@ -56,9 +83,75 @@ pub struct LayerData {
     pub properties: Properties,
     /// The layer's type, which is arbitrarily setby the user.
     pub user_type: Option<String>,
-    layer_type: LayerDataType,
+    pub(crate) layer_type: LayerDataType,
+    pub(crate) unknown_xml: UnknownXml,
 }
 
+/// The attributes of layer elements that are loaded into a [`LayerData`].
+const LAYER_ATTRIBUTES: &[&str] = &[
+    "opacity",
+    "tintcolor",
+    "visible",
+    "offsetx",
+    "offsety",
+    "parallaxx",
+    "parallaxy",
+    "name",
+    "id",
+    "type",
+    "class",
+];
+
+/// [`LAYER_ATTRIBUTES`] plus the ones only image layers have.
+const IMAGE_LAYER_ATTRIBUTES: &[&str] = &[
+    "opacity",
+    "tintcolor",
+    "visible",
+    "offsetx",
+    "offsety",
+    "parallaxx",
+    "parallaxy",
+    "name",
+    "id",
+    "type",
+    "class",
+    "repeatx",
+    "repeaty",
+];
+
+/// [`LAYER_ATTRIBUTES`] plus the ones only object layers have.
+const OBJECT_LAYER_ATTRIBUTES: &[&str] = &[
+    "opacity",
+    "tintcolor",
+    "visible",
+    "offsetx",
+    "offsety",
+    "parallaxx",
+    "parallaxy",
+    "name",
+    "id",
+    "type",
+    "class",
+    "draworder",
+];
+
+/// [`LAYER_ATTRIBUTES`] plus the ones only tile layers have.
+const TILE_LAYER_ATTRIBUTES: &[&str] = &[
+    "opacity",
+    "tintcolor",
+    "visible",
+    "offsetx",
+    "offsety",
+    "parallaxx",
+    "parallaxy",
+    "name",
+    "id",
+    "type",
+    "class",
+    "width",
+    "height",
+];
+
 impl LayerData {
     /// Get the layer's id. Unique within the parent map. Valid only if greater than 0. Defaults to
     /// 0 if the layer was loaded from a file that didn't have the attribute present.
@@ -67,6 +160,61 @@ impl LayerData {
         self.id
     }
 
+    /// The parts of the layer's XML this crate does not model, if loaded with
+    /// [`LoaderOptions::preserve_unknown`](crate::LoaderOptions::preserve_unknown) set.
+    #[inline]
+    pub fn unknown_xml(&self) -> &UnknownXml {
+        &self.unknown_xml
+    }
+
+    /// Returns the highest layer ID found in the given layers and their children, if any.
+    pub(crate) fn max_layer_id(layers: &[LayerData]) -> Option<u32> {
+        layers
+            .iter()
+            .map(|layer| match &layer.layer_type {
+                LayerDataType::Group(group) => {
+                    Self::max_layer_id(&group.layers).map_or(layer.id, |id| id.max(layer.id))
+                }
+                _ => layer.id,
+            })
+            .max()
+    }
+
+    /// Returns the highest object ID found in the given layers and their children, if any.
+    pub(crate) fn max_object_id(layers: &[LayerData]) -> Option<u32> {
+        layers
+            .iter()
+            .filter_map(|layer| match &layer.layer_type {
+                LayerDataType::Objects(objects) => {
+                    objects.objects.iter().map(|object| object.id).max()
+                }
+                LayerDataType::Group(group) => Self::max_object_id(&group.layers),
+                _ => None,
+            })
+            .max()
+    }
+
+    /// Gives the compressed data of the tile layers among `layers` and their children the
+    /// compression level given.
+    pub(crate) fn set_compression_level(layers: &mut [LayerData], level: Option<i32>) {
+        for layer in layers {
+            match &mut layer.layer_type {
+                LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                    data.encoding = data.encoding.with_level(level);
+                }
+                LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                    data.encoding = data.encoding.with_level(level);
+                }
+                LayerDataType::Group(group) => {
+                    Self::set_compression_level(&mut group.layers, level)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses a layer, or skips it and returns [`None`] if the layer filter of the loader rejects
+    /// it.
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
@@ -77,7 +225,9 @@ impl LayerData {
         for_tileset: Option<Arc<Tileset>>,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
-    ) -> Result<Self> {
+        ctx: &mut ParseContext,
+    ) -> Result<Option<Self>> {
+        ctx.check_cancelled()?;
         let (
             opacity,
             tint_color,
@@ -106,10 +256,30 @@ impl LayerData {
             }
             (opacity, tint_color, visible, offset_x, offset_y, parallax_x, parallax_y, name, id, user_type, user_class)
         );
+        let user_type = ctx.class_attribute(tag.name(), user_type, user_class);
+        let info = LayerInfo {
+            name: name.as_deref().unwrap_or_default(),
+            class: user_type.as_deref(),
+            kind: tag.kind(),
+        };
+        if !ctx.filter_layer(&info) {
+            ctx.skip_element(parser)?;
+            return Ok(None);
+        }
+        ctx.count_layer()?;
+
+        let known_attributes = match tag {
+            LayerTag::Tiles => TILE_LAYER_ATTRIBUTES,
+            LayerTag::Image => IMAGE_LAYER_ATTRIBUTES,
+            LayerTag::Objects => OBJECT_LAYER_ATTRIBUTES,
+            _ => LAYER_ATTRIBUTES,
+        };
+        let unknown_attributes = ctx.unknown_attributes(tag.name(), &attrs, known_attributes)?;
+        let depth = ctx.depth();
 
         let (ty, properties) = match tag {
             LayerTag::Tiles => {
-                let (ty, properties) = TileLayerData::new(parser, attrs, infinite, tilesets)?;
+                let (ty, properties) = TileLayerData::new(parser, attrs, infinite, tilesets, ctx)?;
                 (LayerDataType::Tiles(ty), properties)
             }
             LayerTag::Objects => {
@@ -121,11 +291,12 @@ impl LayerData {
                     map_path.parent().ok_or(crate::Error::PathIsNotFile)?,
                     reader,
                     cache,
+                    ctx,
                 )?;
                 (LayerDataType::Objects(ty), properties)
             }
             LayerTag::Image => {
-                let (ty, properties) = ImageLayerData::new(parser, map_path)?;
+                let (ty, properties) = ImageLayerData::new(parser, attrs, map_path, ctx)?;
                 (LayerDataType::Image(ty), properties)
             }
             LayerTag::Group => {
@@ -137,12 +308,14 @@ impl LayerData {
                     for_tileset,
                     reader,
                     cache,
+                    ctx,
                 )?;
                 (LayerDataType::Group(ty), properties)
             }
         };
+        ctx.resource_loaded(map_path);
 
-        Ok(Self {
+        let layer = Self {
             visible: visible.unwrap_or(true),
             offset_x: offset_x.unwrap_or(0.0),
             offset_y: offset_y.unwrap_or(0.0),
@@ -152,10 +325,16 @@ impl LayerData {
             tint_color,
             name: name.unwrap_or_default(),
             id: id.unwrap_or(0),
-            user_type: user_type.or(user_class),
+            user_type,
             properties,
             layer_type: ty,
-        })
+            unknown_xml: UnknownXml {
+                attributes: unknown_attributes,
+                elements: ctx.take_unknown_elements(depth),
+            },
+        };
+        ctx.layer_parsed(&layer);
+        Ok(Some(layer))
     }
 }
 
@@ -238,6 +417,62 @@ impl<'map> Layer<'map> {
             _ => None,
         }
     }
+
+    /// Returns the properties of the layer along with the ones it inherits from the group layers
+    /// it is in, so that a property such as `collision` can be set on a group for all the layers
+    /// within it.
+    ///
+    /// Properties of inner groups take precedence over those of outer ones, and the properties of
+    /// the layer itself over all of them. Class properties are merged member by member, as with
+    /// [`PropertiesExt::merge()`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    /// let group = map.layer_by_path("group-2/group-3").unwrap();
+    /// let layer = map.layer_by_path("group-2/group-3/tile-3").unwrap();
+    ///
+    /// // Both groups set `key`, which the layer overrides
+    /// assert_eq!(group.effective_properties()["key"], group.properties["key"]);
+    /// assert_eq!(layer.effective_properties()["key"], layer.properties["key"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn effective_properties(&self) -> Properties {
+        let mut ancestors = Vec::new();
+        find_ancestors(self.map.layers(), self.data, &mut ancestors);
+
+        let mut properties = Properties::new();
+        for layer in ancestors.into_iter().chain([self.data]) {
+            properties.merge(&layer.properties);
+        }
+        properties
+    }
+}
+
+/// Finds the group layers containing `target` within `layers`, from the outermost one, and
+/// returns whether `target` was found.
+fn find_ancestors<'map>(
+    layers: impl Iterator<Item = Layer<'map>>,
+    target: &LayerData,
+    ancestors: &mut Vec<&'map LayerData>,
+) -> bool {
+    for layer in layers {
+        if std::ptr::eq(layer.data, target) {
+            return true;
+        }
+        if let LayerType::Group(group) = layer.layer_type() {
+            ancestors.push(layer.data);
+            if find_ancestors(group.layers(), target, ancestors) {
+                return true;
+            }
+            ancestors.pop();
+        }
+    }
+    false
 }
 
 /// Represents some kind of map layer.
@@ -1,8 +1,13 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    parse::ParseContext,
     parse_properties,
     util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
     Color, Error, MapTilesetGid, Object, ObjectData, Properties, ResourceCache, ResourceReader,
@@ -11,15 +16,46 @@ use crate::{
 
 /// Raw data referring to a map object layer or tile collision data.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectLayerData {
-    objects: Vec<ObjectData>,
+    pub(crate) objects: Vec<ObjectData>,
     /// The color used in the editor to display objects in this layer.
     pub colour: Option<Color>,
+    /// The order the objects of this layer are drawn in. See
+    /// [`ObjectLayer::objects_in_draw_order()`].
+    pub draw_order: DrawOrder,
+    #[cfg_attr(feature = "snapshot", serde(skip))]
+    pub(crate) name_index: ObjectNameIndex,
+}
+
+/// The order the objects of an [`ObjectLayer`] are drawn in.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub enum DrawOrder {
+    /// Objects are drawn from the top of the layer to the bottom, i.e. sorted by their Y
+    /// coordinate, so that objects lower on the screen are drawn over the ones above them.
+    #[default]
+    TopDown,
+    /// Objects are drawn in the order they appear in the layer.
+    Index,
+}
+
+/// The indices of the objects of a layer by name, built the first time objects are looked up by
+/// name.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ObjectNameIndex(OnceLock<HashMap<String, Vec<usize>>>);
+
+/// The index is a cache, which doesn't make layers any different.
+impl PartialEq for ObjectNameIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 impl ObjectLayerData {
     /// If it is known that there are no objects with tile images in it (i.e. collision data)
     /// then we can pass in [`None`] as the tilesets
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
@@ -29,26 +65,48 @@ impl ObjectLayerData {
         path_relative_to: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<(ObjectLayerData, Properties)> {
-        let c = get_attrs!(
+        let (c, draw_order) = get_attrs!(
             for v in attrs {
                 Some("color") => color ?= v.parse(),
+                Some("draworder") => draw_order = match v.as_str() {
+                    "topdown" => DrawOrder::TopDown,
+                    "index" => DrawOrder::Index,
+                    _ => return Err(Error::MalformedAttributes(
+                        "`draworder` attribute did not contain a valid value of 'topdown' or 'index'"
+                            .to_string(),
+                    )),
+                },
             }
-            color
+            (color, draw_order)
         );
         let mut objects = Vec::new();
-        let mut properties = HashMap::new();
-        parse_tag!(parser, "objectgroup", {
+        let mut properties = Properties::new();
+        parse_tag!(parser, ctx, "objectgroup", {
             "object" => |attrs| {
-                objects.push(ObjectData::new(parser, attrs, tilesets, for_tileset.as_ref().cloned(), path_relative_to, reader, cache)?);
+                let object = ObjectData::new(parser, attrs, tilesets, for_tileset.as_ref().cloned(), path_relative_to, reader, cache, ctx)?;
+                // Collision shapes of tiles are parsed without tilesets
+                if tilesets.is_some() {
+                    ctx.object_parsed(&object);
+                }
+                objects.push(object);
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
         });
-        Ok((ObjectLayerData { objects, colour: c }, properties))
+        Ok((
+            ObjectLayerData {
+                objects,
+                colour: c,
+                draw_order: draw_order.unwrap_or_default(),
+                name_index: ObjectNameIndex::default(),
+            },
+            properties,
+        ))
     }
 
     /// Returns the data belonging to the objects contained within the layer, in the order they were
@@ -57,6 +115,23 @@ impl ObjectLayerData {
     pub fn object_data(&self) -> &[ObjectData] {
         self.objects.as_ref()
     }
+
+    /// Forgets the index of the objects by name, after objects were added, removed or renamed.
+    pub(crate) fn objects_changed(&mut self) {
+        self.name_index = ObjectNameIndex::default();
+    }
+
+    /// Returns the indices of the objects with the name given, in the order of the layer.
+    fn indices_named(&self, name: &str) -> &[usize] {
+        let index = self.name_index.0.get_or_init(|| {
+            let mut index: HashMap<String, Vec<usize>> = HashMap::new();
+            for (i, object) in self.objects.iter().enumerate() {
+                index.entry(object.name.clone()).or_default().push(i);
+            }
+            index
+        });
+        index.get(name).map_or(&[], Vec::as_slice)
+    }
 }
 
 map_wrapper!(
@@ -106,4 +181,70 @@ impl<'map> ObjectLayer<'map> {
             .iter()
             .map(move |object| Object::new(map, object))
     }
+
+    /// Returns the objects present in this layer in the order they should be drawn in according
+    /// to its [draw order](ObjectLayerData::draw_order), from the first one to draw to the last,
+    /// so that sprites are sorted like in Tiled.
+    ///
+    /// With [`DrawOrder::TopDown`], objects are sorted by their Y coordinate, ignoring their
+    /// rotation, and objects at the same height keep the order they were declared in. With
+    /// [`DrawOrder::Index`], that order is kept as is.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+    /// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    ///
+    /// let objects = layer.objects_in_draw_order();
+    /// assert!(objects.windows(2).all(|pair| pair[0].y <= pair[1].y));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn objects_in_draw_order(&self) -> Vec<Object<'map>> {
+        let mut objects: Vec<Object<'map>> = self.objects().collect();
+        if self.data.draw_order == DrawOrder::TopDown {
+            // Sorting is stable, so objects at the same height keep their order
+            objects.sort_by(|a, b| a.y.total_cmp(&b.y));
+        }
+        objects
+    }
+
+    /// Returns the first object of the layer with the name given, if any, e.g. to find a
+    /// well-known object such as `player_start`.
+    ///
+    /// The first lookup by name indexes the objects of the layer by name, so that later ones take
+    /// constant time instead of going through every object.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_property.tmx")?;
+    /// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    ///
+    /// let object = layer.get_object(0).unwrap();
+    /// assert_eq!(layer.object_by_name(&object.name).unwrap().id(), object.id());
+    /// assert!(layer.object_by_name("missing").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn object_by_name(&self, name: &str) -> Option<Object<'map>> {
+        self.objects_by_name(name).next()
+    }
+
+    /// Returns every object of the layer with the name given, in the order they were declared in,
+    /// using the same index as [`ObjectLayer::object_by_name()`].
+    pub fn objects_by_name(
+        &self,
+        name: &str,
+    ) -> impl ExactSizeIterator<Item = Object<'map>> + 'map {
+        let (map, data): (&'map crate::Map, &'map ObjectLayerData) = (self.map, self.data);
+        data.indices_named(name)
+            .iter()
+            .map(move |&index| Object::new(map, &data.objects[index]))
+    }
 }
@@ -0,0 +1,150 @@
+use std::io::Write;
+
+use base64::Engine;
+
+/// How the tiles of a tile layer are stored in a TMX file, along with the compression level used
+/// for compressed data. See [`FiniteTileLayerData::encoding()`](crate::FiniteTileLayerData::encoding)
+/// and [`Map::reencode_layers()`](crate::Map::reencode_layers).
+///
+/// Compression levels are `None` for the default level of the compression method, which is what
+/// a `compressionlevel` of -1 means in a TMX file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Encoding {
+    /// Comma-separated GIDs, the default of Tiled.
+    #[default]
+    Csv,
+    /// Uncompressed base64-encoded GIDs.
+    Base64,
+    /// Base64-encoded GIDs compressed with zlib, using a level from 0 to 9.
+    Base64Zlib {
+        /// The compression level.
+        level: Option<i32>,
+    },
+    /// Base64-encoded GIDs compressed with gzip, using a level from 0 to 9.
+    Base64Gzip {
+        /// The compression level.
+        level: Option<i32>,
+    },
+    /// Base64-encoded GIDs compressed with Zstandard, using a level from 1 to 22. Requires the
+    /// `zstd` feature.
+    #[cfg(feature = "zstd")]
+    Base64Zstd {
+        /// The compression level.
+        level: Option<i32>,
+    },
+}
+
+/// How the text of encoded tile data is laid out, e.g. by [`FiniteTileLayer::encode()`].
+///
+/// Encoding the same tiles with the same format always gives the same text, so that maps kept
+/// under version control only change where their tiles do.
+///
+/// [`FiniteTileLayer::encode()`]: crate::FiniteTileLayer::encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub struct DataFormat {
+    /// The number of tiles on each line of CSV data, or `None` for one line per row of tiles like
+    /// Tiled does.
+    pub csv_tiles_per_line: Option<u32>,
+    /// The number of characters on each line of base64 data, or `None` for a single line like
+    /// Tiled does.
+    pub base64_line_length: Option<usize>,
+}
+
+impl Encoding {
+    /// Returns the encoding described by the `encoding` and `compression` attributes of a `data`
+    /// element, or `None` if they aren't supported.
+    pub(crate) fn from_attributes(
+        encoding: Option<&str>,
+        compression: Option<&str>,
+    ) -> Option<Self> {
+        match (encoding, compression) {
+            (Some("csv"), None) => Some(Encoding::Csv),
+            (Some("base64"), None) => Some(Encoding::Base64),
+            (Some("base64"), Some("zlib")) => Some(Encoding::Base64Zlib { level: None }),
+            (Some("base64"), Some("gzip")) => Some(Encoding::Base64Gzip { level: None }),
+            #[cfg(feature = "zstd")]
+            (Some("base64"), Some("zstd")) => Some(Encoding::Base64Zstd { level: None }),
+            _ => None,
+        }
+    }
+
+    /// The compression level of the encoding, or `None` if it uses the default level or isn't
+    /// compressed.
+    pub fn level(&self) -> Option<i32> {
+        match self {
+            Encoding::Csv | Encoding::Base64 => None,
+            Encoding::Base64Zlib { level } | Encoding::Base64Gzip { level } => *level,
+            #[cfg(feature = "zstd")]
+            Encoding::Base64Zstd { level } => *level,
+        }
+    }
+
+    /// Returns the encoding with its compression level set to `new_level`, if it is compressed.
+    pub(crate) fn with_level(self, new_level: Option<i32>) -> Self {
+        match self {
+            Encoding::Csv | Encoding::Base64 => self,
+            Encoding::Base64Zlib { .. } => Encoding::Base64Zlib { level: new_level },
+            Encoding::Base64Gzip { .. } => Encoding::Base64Gzip { level: new_level },
+            #[cfg(feature = "zstd")]
+            Encoding::Base64Zstd { .. } => Encoding::Base64Zstd { level: new_level },
+        }
+    }
+
+    /// Encodes GIDs along with their flipping bits, arranged in rows of `width` tiles, as the
+    /// contents of a `data` or `chunk` element laid out according to `format`.
+    pub(crate) fn encode(&self, tiles: &[u32], width: u32, format: &DataFormat) -> String {
+        let bytes: Vec<u8> = tiles.iter().flat_map(|bits| bits.to_le_bytes()).collect();
+        let data = match self {
+            Encoding::Csv => {
+                let line_width = format.csv_tiles_per_line.unwrap_or(width).max(1);
+                let lines: Vec<String> = tiles
+                    .chunks(line_width as usize)
+                    .map(|row| row.iter().map(u32::to_string).collect::<Vec<_>>().join(","))
+                    .collect();
+                return format!("\n{}\n", lines.join(",\n"));
+            }
+            Encoding::Base64 => bytes,
+            Encoding::Base64Zlib { level } => {
+                let mut encoder =
+                    flate2::write::ZlibEncoder::new(Vec::new(), flate2_compression(*level));
+                encoder.write_all(&bytes).unwrap();
+                encoder.finish().unwrap()
+            }
+            Encoding::Base64Gzip { level } => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2_compression(*level));
+                encoder.write_all(&bytes).unwrap();
+                encoder.finish().unwrap()
+            }
+            #[cfg(feature = "zstd")]
+            Encoding::Base64Zstd { level } => {
+                // Writing to memory only fails if the compressor can't be allocated
+                zstd::stream::encode_all(&bytes[..], level.unwrap_or(0))
+                    .expect("could not compress tile data")
+            }
+        };
+        let text = base64::engine::general_purpose::STANDARD.encode(data);
+        match format.base64_line_length {
+            Some(length) if length > 0 && text.len() > length => {
+                // Base64 is ASCII, so splitting bytes can't split characters
+                let lines: Vec<&str> = text
+                    .as_bytes()
+                    .chunks(length)
+                    .map(|line| std::str::from_utf8(line).unwrap())
+                    .collect();
+                format!("\n{}\n", lines.join("\n"))
+            }
+            _ => text,
+        }
+    }
+}
+
+fn flate2_compression(level: Option<i32>) -> flate2::Compression {
+    match level {
+        Some(level) => flate2::Compression::new(level.clamp(0, 9) as u32),
+        None => flate2::Compression::default(),
+    }
+}
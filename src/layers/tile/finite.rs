@@ -1,19 +1,30 @@
+use std::sync::Arc;
+
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    parse::ParseContext,
     util::{get_attrs, map_wrapper, XmlEventResult},
-    LayerTile, LayerTileData, MapTilesetGid, Result,
+    DataFormat, Encoding, Gid, LayerTile, LayerTileData, Map, MapTilesetGid, Result, Tileset,
 };
 
-use super::util::parse_data_line;
+use super::util::parse_data_bits;
 
 /// The raw data of a [`FiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
+///
+/// Tiles are stored the same way Tiled does, as one GID with its flipping bits per cell, which
+/// takes 4 bytes per cell regardless of whether it is empty. They are converted to
+/// [`LayerTileData`] when accessed.
 #[derive(PartialEq, Clone, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct FiniteTileLayerData {
-    width: u32,
-    height: u32,
-    /// The tiles are arranged in rows.
-    tiles: Vec<Option<LayerTileData>>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// The first GID of each tileset of the map, in the same order, used to decode `tiles`.
+    pub(crate) first_gids: Vec<u32>,
+    /// The GID of each tile along with its flipping bits, arranged in rows. 0 for empty cells.
+    pub(crate) tiles: Vec<u32>,
+    pub(crate) encoding: Encoding,
 }
 
 impl std::fmt::Debug for FiniteTileLayerData {
@@ -38,12 +49,19 @@ impl FiniteTileLayerData {
         self.height
     }
 
+    /// Get how the tiles of the layer are stored in the TMX file.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         width: u32,
         height: u32,
         tilesets: &[MapTilesetGid],
+        ctx: &mut ParseContext,
     ) -> Result<Self> {
         let (e, c) = get_attrs!(
             for v in attrs {
@@ -53,27 +71,123 @@ impl FiniteTileLayerData {
             (encoding, compression)
         );
 
-        let tiles = parse_data_line(e, c, parser, tilesets)?;
+        let encoding = Encoding::from_attributes(e.as_deref(), c.as_deref()).unwrap_or_default();
+        let tiles = parse_data_bits(e, c, parser, ctx)?;
 
         Ok(Self {
             width,
             height,
+            first_gids: tilesets.iter().map(|tileset| tileset.first_gid.0).collect(),
             tiles,
+            encoding,
         })
     }
 
+    /// Creates an empty layer of the given size for a map, giving its tilesets the GIDs Tiled
+    /// would give them if the map was saved.
+    pub(crate) fn empty(width: u32, height: u32, map: &Map) -> Self {
+        Self {
+            width,
+            height,
+            first_gids: canonical_first_gids(map.tilesets()),
+            tiles: vec![0; width as usize * height as usize],
+            encoding: Encoding::default(),
+        }
+    }
+
+    /// Encodes every tile of the layer again using `first_gids`, replacing each with the result of
+    /// `map_tile` on the way.
+    pub(crate) fn reencode(
+        &mut self,
+        first_gids: Vec<u32>,
+        mut map_tile: impl FnMut(LayerTileData) -> LayerTileData,
+    ) {
+        for index in 0..self.tiles.len() {
+            self.tiles[index] = match self.tile_data_at(index) {
+                Some(tile) => {
+                    let tile = map_tile(tile);
+                    tile.to_bits(Gid(first_gids[tile.tileset_index]))
+                }
+                None => 0,
+            };
+        }
+        self.first_gids = first_gids;
+    }
+
+    /// Returns the GIDs of the tiles along with their flipping bits, encoded with `first_gids`.
+    pub(crate) fn bits(&self, first_gids: &[u32]) -> Vec<u32> {
+        (0..self.tiles.len())
+            .map(|index| match self.tile_data_at(index) {
+                Some(tile) => tile.to_bits(Gid(first_gids[tile.tileset_index])),
+                None => 0,
+            })
+            .collect()
+    }
+
+    /// Replaces the tile at the given index of `tiles`, returning the previous one. If the tile
+    /// can't be encoded with the current first GIDs, e.g. since its tileset was added after the
+    /// map was loaded, the layer is encoded again with the GIDs of a freshly saved map.
+    pub(crate) fn set_tile_data_at(
+        &mut self,
+        index: usize,
+        tile: Option<LayerTileData>,
+        tilesets: &[Arc<Tileset>],
+    ) -> Option<LayerTileData> {
+        let previous = self.tile_data_at(index);
+        self.tiles[index] = 0;
+        if let Some(tile) = tile {
+            if self.first_gids.len() != tilesets.len()
+                || self.decode(tile.to_bits(Gid(self.first_gids[tile.tileset_index]))) != Some(tile)
+            {
+                self.reencode(canonical_first_gids(tilesets), |tile| tile);
+            }
+            self.tiles[index] = tile.to_bits(Gid(self.first_gids[tile.tileset_index]));
+        }
+        previous
+    }
+
     /// Obtains the tile data present at the position given.
     ///
     /// If the position given is invalid or the position is empty, this function will return [`None`].
     ///
     /// If you want to get a [`Tile`](`crate::Tile`) instead, use [`FiniteTileLayer::get_tile()`].
-    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
+    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<LayerTileData> {
         if x < self.width as i32 && y < self.height as i32 && x >= 0 && y >= 0 {
-            self.tiles[x as usize + y as usize * self.width as usize].as_ref()
+            self.tile_data_at(x as usize + y as usize * self.width as usize)
         } else {
             None
         }
     }
+
+    /// Decodes the tile at the given index of `tiles`.
+    pub(crate) fn tile_data_at(&self, index: usize) -> Option<LayerTileData> {
+        self.decode(*self.tiles.get(index)?)
+    }
+
+    /// Decodes a GID plus its flipping bits using the first GIDs of the layer.
+    fn decode(&self, bits: u32) -> Option<LayerTileData> {
+        LayerTileData::from_bits_with(bits, |gid| {
+            let index = self
+                .first_gids
+                .iter()
+                .rposition(|&first_gid| first_gid <= gid.0)?;
+            Some((index, Gid(self.first_gids[index])))
+        })
+    }
+}
+
+/// The first GID Tiled would give each of `tilesets` if they were the tilesets of a saved map.
+pub(crate) fn canonical_first_gids(tilesets: &[Arc<Tileset>]) -> Vec<u32> {
+    tilesets
+        .iter()
+        .scan(1, |next, tileset| {
+            let first_gid = *next;
+            // Image collection tilesets can have tiles with IDs past their tile count
+            let last_id = tileset.tiles().map(|(id, _)| id + 1).max().unwrap_or(0);
+            *next += tileset.tilecount.max(last_id).max(1);
+            Some(first_gid)
+        })
+        .collect()
 }
 
 map_wrapper!(
@@ -90,4 +204,30 @@ impl<'map> FiniteTileLayer<'map> {
             .get_tile_data(x, y)
             .map(|data| LayerTile::new(self.map(), data))
     }
+
+    /// Returns the contents of the `data` element of the layer with its
+    /// [encoding](FiniteTileLayerData::encoding), laid out according to `format`. Tiles are given
+    /// the GIDs Tiled would give them if the map was saved.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{DataFormat, Loader, TileLayer};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+    /// if let Some(TileLayer::Finite(layer)) = map.get_layer(0).unwrap().as_tile_layer() {
+    ///     let mut format = DataFormat::default();
+    ///     format.csv_tiles_per_line = Some(10);
+    ///     let csv = layer.encode(&format);
+    ///     assert_eq!(csv.lines().count(), 1 + 100 * 100 / 10);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode(&self, format: &DataFormat) -> String {
+        let first_gids = canonical_first_gids(self.map.tilesets());
+        self.data
+            .encoding
+            .encode(&self.data.bits(&first_gids), self.data.width, format)
+    }
 }
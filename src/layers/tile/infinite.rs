@@ -3,16 +3,23 @@ use std::collections::HashMap;
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    canonical_first_gids,
+    parse::ParseContext,
     util::{floor_div, get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Error, LayerTile, LayerTileData, MapTilesetGid, Result,
+    DataFormat, Encoding, Error, Gid, LayerTile, LayerTileData, MapTilesetGid, ParseWarningKind,
+    Result,
 };
 
-use super::util::parse_data_line;
+#[cfg(feature = "rayon")]
+use super::util::DecompressionBudget;
+use super::util::EncodedData;
 
 /// The raw data of a [`InfiniteTileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
 #[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct InfiniteTileLayerData {
-    chunks: HashMap<(i32, i32), ChunkData>,
+    pub(crate) chunks: HashMap<(i32, i32), ChunkData>,
+    pub(crate) encoding: Encoding,
 }
 
 impl std::fmt::Debug for InfiniteTileLayerData {
@@ -26,6 +33,7 @@ impl InfiniteTileLayerData {
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         tilesets: &[MapTilesetGid],
+        ctx: &mut ParseContext,
     ) -> Result<Self> {
         let (e, c) = get_attrs!(
             for v in attrs {
@@ -34,31 +42,39 @@ impl InfiniteTileLayerData {
             }
             (encoding, compression)
         );
+        let encoding = Encoding::from_attributes(e.as_deref(), c.as_deref()).unwrap_or_default();
 
         let mut chunks = HashMap::<(i32, i32), ChunkData>::new();
-        parse_tag!(parser, "data", {
+        #[cfg(feature = "rayon")]
+        let mut encoded = Vec::new();
+        parse_tag!(parser, ctx, "data", {
             "chunk" => |attrs| {
-                let chunk = InternalChunk::new(parser, attrs, e.clone(), c.clone(), tilesets)?;
-                for x in chunk.x..chunk.x + chunk.width as i32 {
-                    for y in chunk.y..chunk.y + chunk.height as i32 {
-                        let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
-                        let relative_pos = (x - chunk_pos.0 * ChunkData::WIDTH as i32, y - chunk_pos.1 * ChunkData::HEIGHT as i32);
-                        let chunk_index = (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
-                        let internal_pos = (x - chunk.x, y - chunk.y);
-                        let internal_index = (internal_pos.0 + internal_pos.1 * chunk.width as i32) as usize;
-
-                        if internal_index >= chunk.tiles.len() {
-                            return Err(Error::InvalidTileFound);
-                        }
-
-                        chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] = chunk.tiles[internal_index];
-                    }
+                ctx.check_cancelled()?;
+                let chunk = EncodedChunk::new(parser, attrs, e.clone(), c.clone(), ctx)?;
+                #[cfg(feature = "rayon")]
+                encoded.push(chunk);
+                #[cfg(not(feature = "rayon"))]
+                {
+                    let mut buffers = ctx.take_decode_buffers();
+                    let result = chunk.data.decode_counted(&mut buffers, ctx).and_then(|()| {
+                        insert_chunk(&mut chunks, &chunk, &buffers.tiles, tilesets, ctx)
+                    });
+                    ctx.return_decode_buffers(buffers);
+                    result?;
                 }
                 Ok(())
             }
         });
+        #[cfg(feature = "rayon")]
+        decode_chunks(&mut chunks, encoded, tilesets, ctx)?;
 
-        Ok(Self { chunks })
+        Ok(Self { chunks, encoding })
+    }
+
+    /// Get how the tiles of the layer are stored in the TMX file.
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
     }
 
     /// Obtains the tile data present at the position given.
@@ -66,7 +82,7 @@ impl InfiniteTileLayerData {
     /// If the position given is invalid or the position is empty, this function will return [`None`].
     ///
     /// If you want to get a [`Tile`](`crate::Tile`) instead, use [`InfiniteTileLayer::get_tile()`].
-    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
+    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<LayerTileData> {
         let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
         self.chunks
             .get(&chunk_pos)
@@ -77,11 +93,35 @@ impl InfiniteTileLayerData {
                 );
                 let chunk_index =
                     (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
-                chunk.tiles.get(chunk_index).map(Option::as_ref)
+                chunk.tiles.get(chunk_index).copied()
             })
             .flatten()
     }
 
+    /// Replaces the tile at the position given, returning the previous one. Chunks are created
+    /// when needed, and removed once they are empty.
+    pub(crate) fn set_tile_data(
+        &mut self,
+        x: i32,
+        y: i32,
+        tile: Option<LayerTileData>,
+    ) -> Option<LayerTileData> {
+        let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
+        let chunk_index = (x - chunk_pos.0 * ChunkData::WIDTH as i32
+            + (y - chunk_pos.1 * ChunkData::HEIGHT as i32) * ChunkData::WIDTH as i32)
+            as usize;
+        if tile.is_none() && !self.chunks.contains_key(&chunk_pos) {
+            return None;
+        }
+
+        let chunk = self.chunks.entry(chunk_pos).or_insert_with(ChunkData::new);
+        let previous = std::mem::replace(&mut chunk.tiles[chunk_index], tile);
+        if chunk.tiles.iter().all(Option::is_none) {
+            self.chunks.remove(&chunk_pos);
+        }
+        previous
+    }
+
     /// Returns an iterator over only the data part of the chunks of this tile layer.
     ///
     /// In 99.99% of cases you'll want to use [`InfiniteTileLayer::chunks()`] instead; Using this method is only
@@ -111,8 +151,10 @@ impl InfiniteTileLayerData {
 /// Has only the tile data contained within and not a reference to the map it is part of.
 /// In 99.99% of cases you'll actually want to use [`Chunk`].
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct ChunkData {
-    tiles: Box<[Option<LayerTileData>; Self::TILE_COUNT]>,
+    #[cfg_attr(feature = "snapshot", serde(with = "crate::snapshot::chunk_tiles"))]
+    pub(crate) tiles: Box<[Option<LayerTileData>; Self::TILE_COUNT]>,
 }
 
 impl ChunkData {
@@ -132,14 +174,25 @@ impl ChunkData {
         }
     }
 
+    /// Returns the GIDs of the tiles along with their flipping bits, encoded with `first_gids`.
+    pub(crate) fn bits(&self, first_gids: &[u32]) -> Vec<u32> {
+        self.tiles
+            .iter()
+            .map(|tile| match tile {
+                Some(tile) => tile.to_bits(Gid(first_gids[tile.tileset_index])),
+                None => 0,
+            })
+            .collect()
+    }
+
     /// Obtains the tile data present at the position given relative to the chunk's top-left-most tile.
     ///
     /// If the position given is invalid or the position is empty, this function will return [`None`].
     ///
     /// If you want to get a [`LayerTile`](`crate::LayerTile`) instead, use [`Chunk::get_tile()`].
-    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<&LayerTileData> {
+    pub fn get_tile_data(&self, x: i32, y: i32) -> Option<LayerTileData> {
         if x < Self::WIDTH as i32 && y < Self::HEIGHT as i32 && x >= 0 && y >= 0 {
-            self.tiles[x as usize + y as usize * Self::WIDTH as usize].as_ref()
+            self.tiles[x as usize + y as usize * Self::WIDTH as usize]
         } else {
             None
         }
@@ -170,8 +223,9 @@ impl<'map> Chunk<'map> {
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
-struct InternalChunk {
+/// A chunk of the data of an infinite layer as found in the file, whose tiles haven't been
+/// decoded yet.
+struct EncodedChunk {
     /// The X coordinate of the top-left-most tile in the chunk.
     /// Corresponds to the `x` attribute in the TMX format.
     x: i32,
@@ -180,16 +234,16 @@ struct InternalChunk {
     y: i32,
     width: u32,
     height: u32,
-    tiles: Vec<Option<LayerTileData>>,
+    data: EncodedData,
 }
 
-impl InternalChunk {
-    pub(crate) fn new(
+impl EncodedChunk {
+    fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
         encoding: Option<String>,
         compression: Option<String>,
-        tilesets: &[MapTilesetGid],
+        ctx: &mut ParseContext,
     ) -> Result<Self> {
         let (x, y, width, height) = get_attrs!(
             for v in attrs {
@@ -200,17 +254,99 @@ impl InternalChunk {
             }
             (x, y, width, height)
         );
+        ctx.check_dimensions(width, height)?;
 
-        let tiles = parse_data_line(encoding, compression, parser, tilesets)?;
+        let data = EncodedData::new(encoding, compression, parser, ctx)?;
 
-        Ok(InternalChunk {
+        Ok(EncodedChunk {
             x,
             y,
             width,
             height,
-            tiles,
+            data,
+        })
+    }
+}
+
+/// Copies the tiles of a chunk of the file, decoded to `bits`, into the fixed-size chunks of the
+/// layer.
+fn insert_chunk(
+    chunks: &mut HashMap<(i32, i32), ChunkData>,
+    chunk: &EncodedChunk,
+    bits: &[u32],
+    tilesets: &[MapTilesetGid],
+    ctx: &mut ParseContext,
+) -> Result<()> {
+    let expected_tiles = chunk.width as usize * chunk.height as usize;
+    if bits.len() < expected_tiles {
+        if !ctx.lenient() {
+            return Err(Error::InvalidTileFound);
+        }
+        ctx.warn(ParseWarningKind::IncompleteChunk {
+            x: chunk.x,
+            y: chunk.y,
+        });
+    }
+    for x in chunk.x..chunk.x + chunk.width as i32 {
+        for y in chunk.y..chunk.y + chunk.height as i32 {
+            let chunk_pos = ChunkData::tile_to_chunk_pos(x, y);
+            let relative_pos = (
+                x - chunk_pos.0 * ChunkData::WIDTH as i32,
+                y - chunk_pos.1 * ChunkData::HEIGHT as i32,
+            );
+            let chunk_index = (relative_pos.0 + relative_pos.1 * ChunkData::WIDTH as i32) as usize;
+            let internal_pos = (x - chunk.x, y - chunk.y);
+            let internal_index = (internal_pos.0 + internal_pos.1 * chunk.width as i32) as usize;
+
+            let tile = match bits.get(internal_index) {
+                Some(&bits) => LayerTileData::from_bits(bits, tilesets),
+                // Only reachable in lenient mode
+                None => continue,
+            };
+
+            chunks.entry(chunk_pos).or_insert_with(ChunkData::new).tiles[chunk_index] = tile;
+        }
+    }
+    Ok(())
+}
+
+/// Decodes the data of every chunk in parallel, then adds the chunks in the order of the file,
+/// so that the layer, the warnings and the error returned if any don't depend on scheduling.
+///
+/// The chunks share the remaining decompression budget of `ctx` as they decode, so that they
+/// stop as soon as they have decompressed more than it together. The bytes decompressed are then
+/// counted against `ctx` in the order of the file.
+#[cfg(feature = "rayon")]
+fn decode_chunks(
+    chunks: &mut HashMap<(i32, i32), ChunkData>,
+    encoded: Vec<EncodedChunk>,
+    tilesets: &[MapTilesetGid],
+    ctx: &mut ParseContext,
+) -> Result<()> {
+    use std::sync::atomic::Ordering;
+
+    use rayon::prelude::*;
+
+    use crate::parse::DecodeBuffers;
+
+    let budget = DecompressionBudget::new(ctx);
+    let cancellation_flag = ctx.cancellation_flag();
+    // Stops decoding the remaining chunks as soon as one of them fails or loading is cancelled
+    let decoded: Vec<_> = encoded
+        .par_iter()
+        .map_init(DecodeBuffers::default, |buffers, chunk| {
+            if cancellation_flag.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+                return Err(Error::Cancelled);
+            }
+            let decompressed = chunk.data.decode(budget.as_ref(), buffers)?;
+            Ok((buffers.tiles.clone(), decompressed))
         })
+        .collect::<Result<_>>()?;
+    for (chunk, (bits, decompressed)) in encoded.iter().zip(decoded) {
+        ctx.decompressed(decompressed)?;
+        insert_chunk(chunks, chunk, &bits, tilesets, ctx)?;
     }
+    Ok(())
 }
 
 map_wrapper!(
@@ -284,4 +420,28 @@ impl<'map> InfiniteTileLayer<'map> {
             .get_chunk_data(x, y)
             .map(move |data| Chunk::new(map, data))
     }
+
+    /// Returns the contents of the `chunk` elements of the layer with its
+    /// [encoding](InfiniteTileLayerData::encoding), laid out according to `format`, along with
+    /// the position of their top-left tile. Tiles are given the GIDs Tiled would give them if the
+    /// map was saved.
+    ///
+    /// Chunks are [`ChunkData::WIDTH`] by [`ChunkData::HEIGHT`] tiles and sorted by row, then by
+    /// column, so that the same tiles always give the same result.
+    pub fn encode(&self, format: &DataFormat) -> Vec<((i32, i32), String)> {
+        let first_gids = canonical_first_gids(self.map.tilesets());
+        let mut chunks: Vec<_> = self.data.chunks.iter().collect();
+        chunks.sort_unstable_by_key(|((x, y), _)| (*y, *x));
+        chunks
+            .into_iter()
+            .map(|((x, y), chunk)| {
+                let position = (x * ChunkData::WIDTH as i32, y * ChunkData::HEIGHT as i32);
+                let text =
+                    self.data
+                        .encoding
+                        .encode(&chunk.bits(&first_gids), ChunkData::WIDTH, format);
+                (position, text)
+            })
+            .collect()
+    }
 }
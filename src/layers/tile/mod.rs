@@ -1,29 +1,33 @@
-use std::collections::HashMap;
-
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    parse::ParseContext,
     parse_properties,
-    util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Error, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset,
+    util::{get_attrs, parse_tag, XmlEventResult},
+    Error, Gid, Map, MapTilesetGid, Properties, Result, Tile, TileId, Tileset, TilesetStatus,
 };
 
+mod encoding;
 mod finite;
 mod infinite;
+mod occupancy;
 mod util;
 
+pub use encoding::*;
 pub use finite::*;
 pub use infinite::*;
+pub use occupancy::*;
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct LayerTileData {
     /// The index of the tileset this tile's in, relative to the tile's map. Guaranteed to be a
     /// valid index of the map tileset container, but **isn't guaranteed to actually contain
     /// this tile**.
-    tileset_index: usize,
+    pub(crate) tileset_index: usize,
     /// The local ID of the tile in the tileset it's in.
-    id: TileId,
+    pub(crate) id: TileId,
     /// Whether this tile is flipped on its Y axis (horizontally).
     pub flip_h: bool,
     /// Whether this tile is flipped on its X axis (vertically).
@@ -33,6 +37,21 @@ pub struct LayerTileData {
 }
 
 impl LayerTileData {
+    /// Creates the data of an unflipped tile with local ID `id` in the tileset at `tileset_index`
+    /// within the map, e.g. to place it with [`EditTransaction::set_tile()`].
+    ///
+    /// [`EditTransaction::set_tile()`]: crate::EditTransaction::set_tile
+    #[inline]
+    pub fn new(tileset_index: usize, id: TileId) -> Self {
+        Self {
+            tileset_index,
+            id,
+            flip_h: false,
+            flip_v: false,
+            flip_d: false,
+        }
+    }
+
     /// Get the layer tile's tileset index. Guaranteed to be a
     /// valid index of the map tileset container, but **isn't guaranteed to actually contain
     /// this tile**.
@@ -59,6 +78,18 @@ impl LayerTileData {
 
     /// Creates a new [`LayerTileData`] from a [`Gid`] plus its flipping bits.
     pub(crate) fn from_bits(bits: u32, tilesets: &[MapTilesetGid]) -> Option<Self> {
+        Self::from_bits_with(bits, |gid| {
+            crate::util::get_tileset_for_gid(tilesets, gid)
+                .map(|(index, tileset)| (index, tileset.first_gid))
+        })
+    }
+
+    /// Creates a new [`LayerTileData`] from a [`Gid`] plus its flipping bits, using
+    /// `tileset_for_gid` to find the index and first GID of the tileset a GID belongs to.
+    pub(crate) fn from_bits_with(
+        bits: u32,
+        tileset_for_gid: impl FnOnce(Gid) -> Option<(usize, Gid)>,
+    ) -> Option<Self> {
         let flags = bits & Self::ALL_FLIP_FLAGS;
         let gid = Gid(bits & !Self::ALL_FLIP_FLAGS);
         let flip_d = flags & Self::FLIPPED_DIAGONALLY_FLAG == Self::FLIPPED_DIAGONALLY_FLAG; // Swap x and y axis (anti-diagonally) [flips over y = -x line]
@@ -68,8 +99,8 @@ impl LayerTileData {
         if gid == Gid::EMPTY {
             None
         } else {
-            let (tileset_index, tileset) = crate::util::get_tileset_for_gid(tilesets, gid)?;
-            let id = gid.0 - tileset.first_gid.0;
+            let (tileset_index, first_gid) = tileset_for_gid(gid)?;
+            let id = gid.0 - first_gid.0;
 
             Some(Self {
                 tileset_index,
@@ -80,6 +111,21 @@ impl LayerTileData {
             })
         }
     }
+
+    /// Returns the GID of this tile plus its flipping bits, given the first GID of its tileset.
+    pub(crate) fn to_bits(self, first_gid: Gid) -> u32 {
+        let mut bits = first_gid.0 + self.id;
+        if self.flip_h {
+            bits |= Self::FLIPPED_HORIZONTALLY_FLAG;
+        }
+        if self.flip_v {
+            bits |= Self::FLIPPED_VERTICALLY_FLAG;
+        }
+        if self.flip_d {
+            bits |= Self::FLIPPED_DIAGONALLY_FLAG;
+        }
+        bits
+    }
 }
 
 /// The raw data of a [`TileLayer`]. Does not include a reference to its parent [`Map`](crate::Map).
@@ -88,6 +134,7 @@ impl LayerTileData {
 /// dereference [`TileLayer`] into this structure, and even if we could, it wouldn't make much
 /// sense, since we can already deref from the finite/infinite tile layers themselves.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub(crate) enum TileLayerData {
     Finite(FiniteTileLayerData),
     Infinite(InfiniteTileLayerData),
@@ -99,6 +146,7 @@ impl TileLayerData {
         attrs: Vec<OwnedAttribute>,
         infinite: bool,
         tilesets: &[MapTilesetGid],
+        ctx: &mut ParseContext,
     ) -> Result<(Self, Properties)> {
         let (width, height) = get_attrs!(
             for v in attrs {
@@ -107,19 +155,20 @@ impl TileLayerData {
             }
             (width, height)
         );
+        ctx.check_dimensions(width, height)?;
         let mut result = Self::Finite(Default::default());
-        let mut properties = HashMap::new();
-        parse_tag!(parser, "layer", {
+        let mut properties = Properties::new();
+        parse_tag!(parser, ctx, "layer", {
             "data" => |attrs| {
                 if infinite {
-                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, tilesets)?);
+                    result = Self::Infinite(InfiniteTileLayerData::new(parser, attrs, tilesets, ctx)?);
                 } else {
-                    result = Self::Finite(FiniteTileLayerData::new(parser, attrs, width, height, tilesets)?);
+                    result = Self::Finite(FiniteTileLayerData::new(parser, attrs, width, height, tilesets, ctx)?);
                 }
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
         });
@@ -128,12 +177,34 @@ impl TileLayerData {
     }
 }
 
-map_wrapper!(
-    #[doc = "An instance of a [`Tile`] present in a [`TileLayer`]."]
-    LayerTile => LayerTileData
-);
+/// An instance of a [`Tile`] present in a [`TileLayer`].
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct LayerTile<'map> {
+    pub(crate) map: &'map Map,
+    pub(crate) data: LayerTileData,
+}
+
+impl<'map> std::ops::Deref for LayerTile<'map> {
+    type Target = LayerTileData;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
 
 impl<'map> LayerTile<'map> {
+    #[inline]
+    pub(crate) fn new(map: &'map Map, data: LayerTileData) -> Self {
+        Self { map, data }
+    }
+
+    /// Get the map this object is from.
+    #[inline]
+    pub fn map(&self) -> &'map Map {
+        self.map
+    }
+
     /// Get a reference to the layer tile's referenced tile, if it exists.
     #[inline]
     pub fn get_tile(&self) -> Option<Tile<'map>> {
@@ -145,6 +216,13 @@ impl<'map> LayerTile<'map> {
         // SAFETY: `tileset_index` is guaranteed to be valid
         &self.map.tilesets()[self.data.tileset_index]
     }
+
+    /// Get the layer tile's referenced tileset, or the path it will be loaded from if loading it
+    /// was deferred with [`LoaderOptions::defer_tilesets`](crate::LoaderOptions::defer_tilesets).
+    #[inline]
+    pub fn tileset_status(&self) -> TilesetStatus<'map> {
+        TilesetStatus::new(self.get_tileset())
+    }
 }
 
 /// A map layer containing tiles in some way. May be finite or infinite.
@@ -0,0 +1,224 @@
+//! Compact masks of the cells of tile layers that hold a tile.
+
+use crate::{LayerTile, TileLayer};
+
+use super::ChunkData;
+
+/// The cells of a tile layer that hold a tile, one bit per cell, as returned by
+/// [`TileLayer::occupancy_bitset()`].
+///
+/// The bitset covers a rectangle of the layer, which is the whole layer for finite layers and the
+/// smallest rectangle containing all of its chunks for infinite ones. Bits are stored in rows from
+/// top to bottom. Each row starts at a new `u64` word, with the leftmost cell of a word in its
+/// lowest bit, so that rows can be scanned, combined or compared a word at a time.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OccupancyBitset {
+    origin: (i32, i32),
+    width: u32,
+    height: u32,
+    words: Vec<u64>,
+}
+
+impl OccupancyBitset {
+    fn new(origin: (i32, i32), width: u32, height: u32) -> Self {
+        let words_per_row = (width as usize).div_ceil(64);
+        Self {
+            origin,
+            width,
+            height,
+            words: vec![0; words_per_row * height as usize],
+        }
+    }
+
+    /// The position of the top left cell of the bitset in the layer, which is `(0, 0)` for finite
+    /// layers.
+    #[inline]
+    pub fn origin(&self) -> (i32, i32) {
+        self.origin
+    }
+
+    /// The amount of cells in each row.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The amount of rows.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The amount of `u64` words each row takes.
+    #[inline]
+    pub fn words_per_row(&self) -> usize {
+        (self.width as usize).div_ceil(64)
+    }
+
+    /// Returns all the words of the bitset, row after row.
+    #[inline]
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Returns the words of the row at `y` in the layer, or [`None`] if the bitset doesn't cover
+    /// that row.
+    pub fn row(&self, y: i32) -> Option<&[u64]> {
+        let row = y.checked_sub(self.origin.1)?;
+        if row < 0 || row as u32 >= self.height {
+            return None;
+        }
+        let start = row as usize * self.words_per_row();
+        Some(&self.words[start..start + self.words_per_row()])
+    }
+
+    /// Returns true if the cell at the position given in the layer holds a tile. Positions outside
+    /// the bitset are empty.
+    pub fn is_occupied(&self, x: i32, y: i32) -> bool {
+        match self.index(x, y) {
+            Some((word, bit)) => self.words[word] & (1 << bit) != 0,
+            None => false,
+        }
+    }
+
+    /// The amount of cells holding a tile.
+    pub fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns the positions in the layer of the cells holding a tile, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (i32, i32)> + '_ {
+        let words_per_row = self.words_per_row().max(1);
+        self.words
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, &word)| {
+                let (row, column) = (index / words_per_row, index % words_per_row * 64);
+                (0..64)
+                    .filter(move |bit| word & (1 << bit) != 0)
+                    .map(move |bit| (column + bit, row))
+            })
+            .map(move |(x, y)| (self.origin.0 + x as i32, self.origin.1 + y as i32))
+    }
+
+    fn set(&mut self, x: i32, y: i32) {
+        if let Some((word, bit)) = self.index(x, y) {
+            self.words[word] |= 1 << bit;
+        }
+    }
+
+    /// The index of the word holding the cell at the position given, and of its bit.
+    fn index(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let (column, row) = (x.checked_sub(self.origin.0)?, y.checked_sub(self.origin.1)?);
+        if column < 0 || row < 0 || column as u32 >= self.width || row as u32 >= self.height {
+            return None;
+        }
+        let (column, row) = (column as usize, row as usize);
+        Some((row * self.words_per_row() + column / 64, column % 64))
+    }
+}
+
+impl<'map> TileLayer<'map> {
+    /// Returns which cells of the layer hold a tile, as a bitset that is much smaller and faster
+    /// to go through than the tiles themselves, e.g. for a collision broadphase, fog of war or
+    /// flood fills.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// let occupied = layer.occupancy_bitset();
+    /// assert_eq!((occupied.width(), occupied.height()), (100, 100));
+    /// assert_eq!(occupied.is_occupied(0, 0), layer.get_tile(0, 0).is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn occupancy_bitset(&self) -> OccupancyBitset {
+        self.occupancy_bitset_filtered(|_| true)
+    }
+
+    /// Returns which cells of the layer hold a tile `filter` returns true for, such as the tiles
+    /// of a class or with a property, as a bitset like [`TileLayer::occupancy_bitset()`] does.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    /// let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    ///
+    /// let walls = layer.occupancy_bitset_filtered(|tile| {
+    ///     tile.get_tile()
+    ///         .is_some_and(|tile| tile.user_type.as_deref() == Some("wall"))
+    /// });
+    /// let marked = layer.occupancy_bitset_filtered(|tile| {
+    ///     tile.get_tile()
+    ///         .is_some_and(|tile| tile.properties.contains_key("a tile property"))
+    /// });
+    /// assert_eq!(walls.count(), 0);
+    /// let occupied = layer.occupancy_bitset();
+    /// assert!(marked.iter().all(|(x, y)| occupied.is_occupied(x, y)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn occupancy_bitset_filtered(
+        &self,
+        mut filter: impl FnMut(&LayerTile<'map>) -> bool,
+    ) -> OccupancyBitset {
+        match self {
+            TileLayer::Finite(layer) => {
+                let mut bitset = OccupancyBitset::new((0, 0), layer.width(), layer.height());
+                for y in 0..layer.height() as i32 {
+                    for x in 0..layer.width() as i32 {
+                        if layer.get_tile(x, y).is_some_and(|tile| filter(&tile)) {
+                            bitset.set(x, y);
+                        }
+                    }
+                }
+                bitset
+            }
+            TileLayer::Infinite(layer) => {
+                let (chunk_width, chunk_height) =
+                    (ChunkData::WIDTH as i32, ChunkData::HEIGHT as i32);
+                let bounds = layer.chunks().fold(
+                    None,
+                    |bounds: Option<(i32, i32, i32, i32)>, ((x, y), _)| {
+                        Some(match bounds {
+                            Some((left, top, right, bottom)) => {
+                                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                            }
+                            None => (x, y, x, y),
+                        })
+                    },
+                );
+                let (left, top, right, bottom) = match bounds {
+                    Some(bounds) => bounds,
+                    None => return OccupancyBitset::new((0, 0), 0, 0),
+                };
+                let mut bitset = OccupancyBitset::new(
+                    (left * chunk_width, top * chunk_height),
+                    ((right - left + 1) * chunk_width) as u32,
+                    ((bottom - top + 1) * chunk_height) as u32,
+                );
+                for ((chunk_x, chunk_y), chunk) in layer.chunks() {
+                    for y in 0..chunk_height {
+                        for x in 0..chunk_width {
+                            if chunk.get_tile(x, y).is_some_and(|tile| filter(&tile)) {
+                                bitset.set(chunk_x * chunk_width + x, chunk_y * chunk_height + y);
+                            }
+                        }
+                    }
+                }
+                bitset
+            }
+        }
+    }
+}
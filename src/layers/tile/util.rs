@@ -1,51 +1,211 @@
-use std::{convert::TryInto, io::Read};
+use std::{
+    convert::TryInto,
+    fmt,
+    io::Read,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use base64::Engine;
 use xml::reader::XmlEvent;
 
-use crate::{util::XmlEventResult, CsvDecodingError, Error, LayerTileData, MapTilesetGid, Result};
+use crate::{
+    parse::{DecodeBuffers, ParseContext},
+    trace::{load_span, LoadSpan},
+    util::XmlEventResult,
+    CsvDecodingError, Encoding, Error, Result,
+};
 
-pub(crate) fn parse_data_line(
+/// Decodes the contents of a `data` or `chunk` element into the GIDs of its tiles along with
+/// their flipping bits.
+pub(crate) fn parse_data_bits(
     encoding: Option<String>,
     compression: Option<String>,
     parser: &mut impl Iterator<Item = XmlEventResult>,
-    tilesets: &[MapTilesetGid],
-) -> Result<Vec<Option<LayerTileData>>> {
-    match (encoding.as_deref(), compression.as_deref()) {
-        (Some("csv"), None) => decode_csv(parser, tilesets),
-
-        (Some("base64"), None) => parse_base64(parser).map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("zlib")) => parse_base64(parser)
-            .and_then(|data| process_decoder(Ok(flate2::bufread::ZlibDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        (Some("base64"), Some("gzip")) => parse_base64(parser)
-            .and_then(|data| process_decoder(Ok(flate2::bufread::GzDecoder::new(&data[..]))))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-        #[cfg(feature = "zstd")]
-        (Some("base64"), Some("zstd")) => parse_base64(parser)
-            .and_then(|data| process_decoder(zstd::stream::read::Decoder::with_buffer(&data[..])))
-            .map(|v| convert_to_tiles(&v, tilesets)),
-
-        _ => Err(Error::InvalidEncodingFormat {
+    ctx: &mut ParseContext,
+) -> Result<Vec<u32>> {
+    let data = EncodedData::new(encoding, compression, parser, ctx)?;
+    let mut buffers = ctx.take_decode_buffers();
+    let tiles = data
+        .decode_counted(&mut buffers, ctx)
+        .map(|()| buffers.tiles.clone());
+    ctx.return_decode_buffers(buffers);
+    tiles
+}
+
+/// The contents of a `data` or `chunk` element, read but not decoded yet so that decoding can
+/// happen later, possibly on another thread.
+pub(crate) struct EncodedData {
+    encoding: Encoding,
+    text: String,
+}
+
+impl EncodedData {
+    pub(crate) fn new(
+        encoding: Option<String>,
+        compression: Option<String>,
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+        ctx: &ParseContext,
+    ) -> Result<Self> {
+        let encoding = match Encoding::from_attributes(encoding.as_deref(), compression.as_deref())
+        {
+            Some(encoding) => encoding,
+            None if encoding.is_none() && compression.is_none() && ctx.strict() => {
+                return Err(Error::DeprecatedFeature {
+                    description: "the XML encoding of tile data".to_owned(),
+                })
+            }
+            None => {
+                return Err(Error::InvalidEncodingFormat {
+                    encoding,
+                    compression,
+                })
+            }
+        };
+        Ok(Self {
             encoding,
-            compression,
-        }),
+            text: data_text(parser)?,
+        })
+    }
+
+    /// Decodes the GIDs of the tiles along with their flipping bits into `buffers.tiles`, and
+    /// returns the number of bytes decompressed, which are taken from `budget` as they are read.
+    pub(crate) fn decode(
+        &self,
+        budget: Option<&DecompressionBudget>,
+        buffers: &mut DecodeBuffers,
+    ) -> Result<usize> {
+        let span = load_span!(
+            "decode_tile_data",
+            encoding = ?self.encoding,
+            tiles = tracing::field::Empty
+        );
+        let text = self.text.trim();
+        let tiles = &mut buffers.tiles;
+        let decompressed = match self.encoding {
+            Encoding::Csv => {
+                decode_csv(&self.text, tiles)?;
+                0
+            }
+            Encoding::Base64 => {
+                if decode_base64_bits(text.as_bytes(), tiles).is_some() {
+                    span.record("bytes", tiles.len() * 4);
+                } else {
+                    decode_base64(text, &span, &mut buffers.base64)?;
+                    convert_to_bits(&buffers.base64, tiles);
+                }
+                0
+            }
+            Encoding::Base64Zlib { .. } => {
+                let data = decode_base64(text, &span, &mut buffers.base64)?;
+                let decoder = flate2::bufread::ZlibDecoder::new(data);
+                let data = process_decoder(Ok(decoder), budget, &mut buffers.decompressed)?;
+                convert_to_bits(data, tiles);
+                data.len()
+            }
+            Encoding::Base64Gzip { .. } => {
+                let data = decode_base64(text, &span, &mut buffers.base64)?;
+                let decoder = flate2::bufread::GzDecoder::new(data);
+                let data = process_decoder(Ok(decoder), budget, &mut buffers.decompressed)?;
+                convert_to_bits(data, tiles);
+                data.len()
+            }
+            #[cfg(feature = "zstd")]
+            Encoding::Base64Zstd { .. } => {
+                let data = decode_base64(text, &span, &mut buffers.base64)?;
+                let decoder = zstd::stream::read::Decoder::with_buffer(data);
+                let data = process_decoder(decoder, budget, &mut buffers.decompressed)?;
+                convert_to_bits(data, tiles);
+                data.len()
+            }
+        };
+        span.record("tiles", tiles.len());
+        Ok(decompressed)
+    }
+
+    /// Decodes the tiles like [`Self::decode()`], counting the bytes decompressed against the
+    /// budget of `ctx`.
+    pub(crate) fn decode_counted(
+        &self,
+        buffers: &mut DecodeBuffers,
+        ctx: &mut ParseContext,
+    ) -> Result<()> {
+        let budget = DecompressionBudget::new(ctx);
+        let decompressed = self.decode(budget.as_ref(), buffers)?;
+        ctx.decompressed(decompressed)
+    }
+}
+
+/// The bytes of tile data that may still be decompressed while loading a file, taken from by
+/// every decoder as it reads, including decoders running in parallel, so that the data they hold
+/// together never goes much past
+/// [`LoaderOptions::max_decompressed_bytes`](crate::LoaderOptions::max_decompressed_bytes).
+#[derive(Debug)]
+pub(crate) struct DecompressionBudget {
+    limit: usize,
+    remaining: AtomicUsize,
+}
+
+impl DecompressionBudget {
+    /// The budget left in `ctx`, or [`None`] if decompression isn't limited.
+    pub(crate) fn new(ctx: &ParseContext) -> Option<Self> {
+        Some(Self {
+            limit: ctx.max_decompressed_bytes()?,
+            remaining: AtomicUsize::new(ctx.decompression_budget()?),
+        })
+    }
+
+    /// Takes `bytes` from the budget. If there aren't that many left, empties the budget so that
+    /// every other decoder stops at its next read, and returns false.
+    fn take(&self, bytes: usize) -> bool {
+        let taken = self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |remaining| {
+                remaining.checked_sub(bytes)
+            })
+            .is_ok();
+        if !taken {
+            self.remaining.store(0, Ordering::Relaxed);
+        }
+        taken
     }
 }
 
-fn parse_base64(parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<Vec<u8>> {
+/// A decompressing reader failing with [`BudgetExceeded`] once its [`DecompressionBudget`] is
+/// exhausted.
+struct BudgetedReader<'a, R> {
+    inner: R,
+    budget: &'a DecompressionBudget,
+}
+
+impl<R: Read> Read for BudgetedReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        if self.budget.take(read) {
+            Ok(read)
+        } else {
+            Err(std::io::Error::other(BudgetExceeded))
+        }
+    }
+}
+
+#[derive(Debug)]
+struct BudgetExceeded;
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("decompression budget exceeded")
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Returns the text of a `data` element, which is empty if the element is.
+fn data_text(parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<String> {
     for next in parser {
         match next.map_err(Error::XmlDecodingError)? {
-            XmlEvent::Characters(s) => {
-                return base64::engine::GeneralPurpose::new(
-                    &base64::alphabet::STANDARD,
-                    base64::engine::general_purpose::PAD,
-                )
-                .decode(s.trim().as_bytes())
-                .map_err(Error::Base64DecodingError);
-            }
+            XmlEvent::Characters(s) => return Ok(s),
             XmlEvent::EndElement { name, .. } if name.local_name == "data" => {
-                return Ok(Vec::new());
+                return Ok(String::new());
             }
             _ => {}
         }
@@ -53,50 +213,173 @@ fn parse_base64(parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<Vec
     Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
 }
 
-fn process_decoder(decoder: std::io::Result<impl Read>) -> Result<Vec<u8>> {
+fn decode_base64<'a>(text: &str, span: &LoadSpan, data: &'a mut Vec<u8>) -> Result<&'a [u8]> {
+    data.clear();
+    base64::engine::general_purpose::STANDARD
+        .decode_vec(text.as_bytes(), data)
+        .map_err(Error::Base64DecodingError)?;
+    span.record("bytes", data.len());
+    Ok(data)
+}
+
+const INVALID_BASE64: u8 = 0xff;
+
+/// The value of each base64 character, or [`INVALID_BASE64`] for bytes that aren't one.
+const BASE64_VALUES: [u8; 256] = {
+    let alphabet = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut values = [INVALID_BASE64; 256];
+    let mut i = 0;
+    while i < alphabet.len() {
+        values[alphabet[i] as usize] = i as u8;
+        i += 1;
+    }
+    values
+};
+
+/// Decodes up to four base64 characters into the bits they encode, six per character.
+fn decode_quad(quad: &[u8]) -> Option<u32> {
+    let mut bits = 0;
+    for &c in quad {
+        let value = BASE64_VALUES[c as usize];
+        if value == INVALID_BASE64 {
+            return None;
+        }
+        bits = bits << 6 | value as u32;
+    }
+    Some(bits)
+}
+
+/// Decodes uncompressed base64 tile data straight into the GIDs of its tiles, sixteen characters
+/// (three tiles) at a time.
+///
+/// Returns [`None`] for anything but canonical, padded base64 of a whole number of tiles, which
+/// is left to the general decoder to decode or report.
+fn decode_base64_bits(text: &[u8], tiles: &mut Vec<u32>) -> Option<()> {
+    tiles.clear();
+    if !text.len().is_multiple_of(4) {
+        return None;
+    }
+    let padding = text
+        .iter()
+        .rev()
+        .take(2)
+        .take_while(|&&c| c == b'=')
+        .count();
+    let data = &text[..text.len() - padding];
+
+    tiles.reserve(data.len() * 3 / 16);
+    let mut blocks = data.chunks_exact(16);
+    for block in blocks.by_ref() {
+        let mut bytes = [0; 12];
+        for (quad, out) in block.chunks_exact(4).zip(bytes.chunks_exact_mut(3)) {
+            out.copy_from_slice(&decode_quad(quad)?.to_be_bytes()[1..]);
+        }
+        tiles.extend(
+            bytes
+                .chunks_exact(4)
+                .map(|tile| u32::from_le_bytes(tile.try_into().unwrap())),
+        );
+    }
+
+    // The last tiles, if the tile count isn't a multiple of three
+    let mut bytes = Vec::with_capacity(8);
+    for quad in blocks.remainder().chunks(4) {
+        let bits = decode_quad(quad)?;
+        // Like the general decoder, reject unused bits that aren't zero
+        match quad.len() {
+            4 => bytes.extend_from_slice(&bits.to_be_bytes()[1..]),
+            3 if bits & 0b11 == 0 => bytes.extend_from_slice(&((bits >> 2) as u16).to_be_bytes()),
+            2 if bits & 0b1111 == 0 => bytes.push((bits >> 4) as u8),
+            _ => return None,
+        }
+    }
+    if !bytes.len().is_multiple_of(4) {
+        return None;
+    }
+    tiles.extend(
+        bytes
+            .chunks_exact(4)
+            .map(|tile| u32::from_le_bytes(tile.try_into().unwrap())),
+    );
+    Some(())
+}
+
+fn process_decoder<'a>(
+    decoder: std::io::Result<impl Read>,
+    budget: Option<&DecompressionBudget>,
+    data: &'a mut Vec<u8>,
+) -> Result<&'a [u8]> {
+    data.clear();
     decoder
-        .and_then(|mut decoder| {
-            let mut data = Vec::new();
-            decoder.read_to_end(&mut data)?;
-            Ok(data)
+        .and_then(|decoder| match budget {
+            Some(budget) => BudgetedReader {
+                inner: decoder,
+                budget,
+            }
+            .read_to_end(data),
+            None => {
+                let mut decoder = decoder;
+                decoder.read_to_end(data)
+            }
         })
-        .map_err(Error::DecompressingError)
+        .map_err(|err| match (budget, err.get_ref()) {
+            (Some(budget), Some(inner)) if inner.is::<BudgetExceeded>() => {
+                Error::DecompressedSizeExceeded {
+                    limit: budget.limit,
+                }
+            }
+            _ => Error::DecompressingError(err),
+        })?;
+    Ok(data)
 }
 
-fn decode_csv(
-    parser: &mut impl Iterator<Item = XmlEventResult>,
-    tilesets: &[MapTilesetGid],
-) -> Result<Vec<Option<LayerTileData>>> {
-    for next in parser {
-        match next.map_err(Error::XmlDecodingError)? {
-            XmlEvent::Characters(s) => {
-                let mut tiles = Vec::new();
-                for v in s.split(',') {
-                    match v.trim().parse() {
-                        Ok(bits) => tiles.push(LayerTileData::from_bits(bits, tilesets)),
-                        Err(e) => {
-                            return Err(Error::CsvDecodingError(
-                                CsvDecodingError::TileDataParseError(e),
-                            ))
-                        }
-                    }
-                }
-                return Ok(tiles);
+fn decode_csv(text: &str, tiles: &mut Vec<u32>) -> Result<()> {
+    tiles.clear();
+    if text.is_empty() || decode_csv_fast(text, tiles).is_some() {
+        return Ok(());
+    }
+    tiles.clear();
+    for v in text.split(',') {
+        match v.trim().parse() {
+            Ok(bits) => tiles.push(bits),
+            Err(e) => {
+                return Err(Error::CsvDecodingError(
+                    CsvDecodingError::TileDataParseError(e),
+                ))
             }
-            XmlEvent::EndElement { name, .. } if name.local_name == "data" => {
-                return Ok(Vec::new());
+        }
+    }
+    Ok(())
+}
+
+/// Parses CSV tile data a byte at a time, without going through [`str::parse()`] for each tile.
+///
+/// Returns [`None`] for anything but decimal GIDs separated by commas and ASCII whitespace, which
+/// is left to the general parser to parse or report.
+fn decode_csv_fast(text: &str, tiles: &mut Vec<u32>) -> Option<()> {
+    tiles.reserve(text.bytes().filter(|&b| b == b',').count() + 1);
+    for field in text.as_bytes().split(|&b| b == b',') {
+        let field = field.trim_ascii();
+        if field.is_empty() {
+            return None;
+        }
+        let mut bits: u32 = 0;
+        for &b in field {
+            let digit = b.wrapping_sub(b'0');
+            if digit > 9 {
+                return None;
             }
-            _ => {}
+            bits = bits.checked_mul(10)?.checked_add(digit as u32)?;
         }
+        tiles.push(bits);
     }
-    Err(Error::PrematureEnd("Ran out of XML data".to_owned()))
+    Some(())
 }
 
-fn convert_to_tiles(data: &[u8], tilesets: &[MapTilesetGid]) -> Vec<Option<LayerTileData>> {
-    data.chunks_exact(4)
-        .map(|chunk| {
-            let bits = u32::from_le_bytes(chunk.try_into().unwrap());
-            LayerTileData::from_bits(bits, tilesets)
-        })
-        .collect()
+fn convert_to_bits(data: &[u8], tiles: &mut Vec<u32>) {
+    tiles.clear();
+    tiles.extend(
+        data.chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap())),
+    );
 }
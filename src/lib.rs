@@ -7,30 +7,66 @@
 
 mod animation;
 mod cache;
+mod dependencies;
 mod error;
 mod image;
 mod layers;
 mod loader;
 mod map;
 mod objects;
+mod outline;
 mod parse;
 mod properties;
+mod raw;
 mod reader;
 mod template;
 mod tile;
 mod tileset;
+mod trace;
 mod util;
+mod version;
+
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "project")]
+mod project;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+#[cfg(feature = "world")]
+mod world;
+
+#[cfg(feature = "bevy")]
+pub mod bevy;
+#[cfg(feature = "project")]
+pub mod codegen;
+#[cfg(any(feature = "ldtk", feature = "godot"))]
+pub mod export;
+#[cfg(feature = "lyon")]
+pub mod lyon;
+#[cfg(feature = "rapier")]
+pub mod rapier;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 pub use animation::*;
 pub use cache::*;
+pub use dependencies::*;
 pub use error::*;
+#[cfg(feature = "http")]
+pub use http::*;
 pub use image::*;
 pub use layers::*;
 pub use loader::*;
 pub use map::*;
 pub use objects::*;
+#[cfg(feature = "project")]
+pub use project::*;
 pub use properties::*;
+pub use raw::*;
 pub use reader::*;
 pub use template::*;
 pub use tile::*;
 pub use tileset::*;
+pub use version::*;
+#[cfg(feature = "world")]
+pub use world::*;
@@ -1,10 +1,185 @@
-use std::path::Path;
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{atomic::AtomicBool, Arc},
+};
 
 use crate::{
-    DefaultResourceCache, FilesystemResourceReader, Map, ResourceCache, ResourceReader, Result,
-    Tileset,
+    dependencies::Dependencies,
+    parse::{ParseBuffers, ParseContext, ParseObservers, ProgressCallback},
+    reader::list_directory,
+    DefaultResourceCache, Dependency, Diagnostic, Error, FilesystemResourceReader, LayerData,
+    LayerFilter, LayerInfo, Map, ObjectData, PropertyParsers, PropertyValueParser, ResourceCache,
+    ResourceReader, Result, Template, Tileset,
 };
 
+/// Options that control how a [`Loader`] parses files.
+///
+/// ## Example
+/// ```
+/// use tiled::Loader;
+///
+/// # fn main() -> tiled::Result<()> {
+/// let mut loader = Loader::new();
+/// loader.options_mut().lenient = true;
+///
+/// let map = loader.load_tmx_map("assets/tiled_base64.tmx")?;
+/// for warning in map.warnings() {
+///     eprintln!("{}", warning);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LoaderOptions {
+    /// If set, problems that don't prevent a file from being understood are collected as
+    /// [`ParseWarning`](crate::ParseWarning)s instead of failing the whole load with an
+    /// [`Error`](crate::Error), so that files exported by newer Tiled versions can still be loaded.
+    /// These include properties with unknown types or invalid values and elements this crate
+    /// doesn't know about. Use [`Map::warnings()`] and [`Tileset::warnings()`] to obtain them.
+    ///
+    /// Disabled by default.
+    pub lenient: bool,
+    /// If set, elements that fail to parse are skipped and parsing continues with the rest of the
+    /// file instead of stopping at the first error. If any errors were found, all of them are
+    /// returned at once as [`Error::Multiple`](crate::Error::Multiple), which is useful to report
+    /// every problem in an asset in one pass.
+    ///
+    /// Disabled by default.
+    pub collect_errors: bool,
+    /// If set, the attributes and child elements of maps, tilesets, tiles, layers and objects that
+    /// this crate does not model are kept as [`UnknownXml`](crate::UnknownXml), so that they can be
+    /// written back unchanged. Elements that aren't understood anywhere else are skipped.
+    ///
+    /// Disabled by default.
+    pub preserve_unknown: bool,
+    /// If set, parts of files that this crate can't be sure to understand fully are errors, so
+    /// that assets can be checked before being used: Elements and attributes that aren't part of
+    /// the TMX and TSX formats fail with [`Error::UnknownElement`](crate::Error::UnknownElement)
+    /// and [`Error::UnknownAttribute`](crate::Error::UnknownAttribute), and deprecated features
+    /// such as terrains and the XML encoding of tile data fail with
+    /// [`Error::DeprecatedFeature`](crate::Error::DeprecatedFeature). Takes precedence over
+    /// [`LoaderOptions::lenient`].
+    ///
+    /// Disabled by default.
+    pub strict: bool,
+    /// The most bytes of tile data that may be decompressed while loading a file, counting every
+    /// layer and chunk, past which loading fails with
+    /// [`Error::DecompressedSizeExceeded`](crate::Error::DecompressedSizeExceeded). Protects
+    /// against small files that decompress to huge amounts of data when loading untrusted files.
+    ///
+    /// Unlimited by default.
+    pub max_decompressed_bytes: Option<usize>,
+    /// The largest width and height, in tiles, that maps, tile layers and chunks may have, past
+    /// which loading fails with [`Error::MapTooLarge`](crate::Error::MapTooLarge).
+    ///
+    /// Unlimited by default.
+    pub max_map_dimensions: Option<u32>,
+    /// The most layers a file may contain, including the ones in groups, past which loading fails
+    /// with [`Error::TooManyLayers`](crate::Error::TooManyLayers).
+    ///
+    /// Unlimited by default.
+    pub max_layers: Option<usize>,
+    /// The most objects a file may contain along with the files it references, including the ones
+    /// of templates and tile collision shapes, past which loading fails with
+    /// [`Error::TooManyObjects`](crate::Error::TooManyObjects).
+    ///
+    /// Unlimited by default.
+    pub max_objects: Option<usize>,
+    /// If set, the images of tilesets, tiles and image layers are left out, so that maps can be
+    /// loaded where only their tiles, collision shapes and properties are needed, such as on
+    /// dedicated servers that don't have the art assets. [`Tileset::image`](crate::Tileset::image),
+    /// [`Tile::image`](crate::Tile::image) and
+    /// [`ImageLayer::image`](crate::ImageLayerData::image) are then [`None`], image paths aren't
+    /// checked against the [`PathPolicy`] and images aren't part of the
+    /// [dependencies](Loader::dependencies) of maps.
+    ///
+    /// Tilesets made of a single image still have a tile for each part of the image. Since the
+    /// tilesets loaded while this is set are cached without their images, use another loader to
+    /// load maps with their images.
+    ///
+    /// Disabled by default.
+    pub skip_images: bool,
+    /// If set, the external tilesets of maps that aren't in the [cache](Loader::cache) yet aren't
+    /// loaded along with maps: Their place in [`Map::tilesets()`] is taken by a placeholder for
+    /// which [`Tileset::is_pending()`] is true, until [`Map::resolve_tilesets()`] loads them. This
+    /// is meant for tilesets read from a slow reader, such as one fetching them over the network,
+    /// so that maps can be looked at before their tilesets are needed.
+    ///
+    /// The tiles of layers and objects still refer to their tileset and ID, but have no
+    /// [`Tile`](crate::Tile) until their tileset is resolved; see
+    /// [`LayerTile::tileset_status()`](crate::LayerTile::tileset_status). The tilesets of templates
+    /// are always loaded.
+    ///
+    /// Disabled by default.
+    pub defer_tilesets: bool,
+}
+
+/// Where the relative paths found in files are resolved from, part of a [`PathPolicy`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum PathBase {
+    /// Paths are relative to the directory of the file they are found in, which is how Tiled
+    /// saves them.
+    #[default]
+    File,
+    /// Paths are relative to the given directory, whatever file they are found in. Useful for
+    /// assets written by tools that store paths from the root of the game's assets.
+    AssetRoot(PathBuf),
+}
+
+/// Controls how a [`Loader`] turns the paths found in files, such as the ones of external
+/// tilesets, templates and images, into the paths given to its [`ResourceReader`]. Set with
+/// [`Loader::set_path_policy()`].
+///
+/// The default policy resolves paths like Tiled does.
+///
+/// ## Example
+/// ```
+/// use tiled::{Error, Loader, PathPolicy};
+///
+/// let mut policy = PathPolicy::default();
+/// policy.allow_escape = false;
+/// let mut loader = Loader::new();
+/// loader.set_path_policy(policy);
+///
+/// // The map uses "../tilesheet.png", which is outside of the directory of the map
+/// let err = loader.load_tmx_map("assets/folder/tiled_relative_paths.tmx").unwrap_err();
+/// assert!(matches!(err.inner(), Error::PathEscapesRoot { .. }));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct PathPolicy {
+    /// What relative paths are resolved from.
+    ///
+    /// [`PathBase::File`] by default.
+    pub base: PathBase,
+    /// If unset, paths must stay within the directory of the file being loaded, or within the
+    /// asset root if [`PathPolicy::base`] is [`PathBase::AssetRoot`], once their `.` and `..`
+    /// components are resolved. Other paths, including absolute ones, fail with
+    /// [`Error::PathEscapesRoot`](crate::Error::PathEscapesRoot). Useful to sandbox untrusted
+    /// files.
+    ///
+    /// Enabled by default.
+    pub allow_escape: bool,
+    /// If set, backslashes in paths are treated as separators, so that files written by hand on
+    /// Windows can be loaded everywhere.
+    ///
+    /// Disabled by default.
+    pub normalize_separators: bool,
+}
+
+impl Default for PathPolicy {
+    fn default() -> Self {
+        Self {
+            base: PathBase::File,
+            allow_escape: true,
+            normalize_separators: false,
+        }
+    }
+}
+
 /// A type used for loading [`Map`]s and [`Tileset`]s.
 ///
 /// Internally, it holds a [`ResourceCache`] that, as its name implies, caches intermediate loading
@@ -24,6 +199,16 @@ pub struct Loader<
 > {
     cache: Cache,
     reader: Reader,
+    options: LoaderOptions,
+    /// Strings shared by everything this loader loads, and buffers reused from one load to the
+    /// next.
+    buffers: ParseBuffers,
+    progress: Option<ProgressCallback>,
+    cancellation_flag: Option<Arc<AtomicBool>>,
+    layer_filter: Option<LayerFilter>,
+    observers: ParseObservers,
+    property_parsers: PropertyParsers,
+    path_policy: PathPolicy,
 }
 
 impl Loader {
@@ -33,6 +218,14 @@ impl Loader {
         Self {
             cache: DefaultResourceCache::new(),
             reader: FilesystemResourceReader::new(),
+            options: LoaderOptions::default(),
+            buffers: ParseBuffers::default(),
+            progress: None,
+            cancellation_flag: None,
+            layer_filter: None,
+            observers: ParseObservers::default(),
+            property_parsers: PropertyParsers::default(),
+            path_policy: PathPolicy::default(),
         }
     }
 }
@@ -79,6 +272,14 @@ impl<Reader: ResourceReader> Loader<DefaultResourceCache, Reader> {
         Self {
             cache: DefaultResourceCache::new(),
             reader,
+            options: LoaderOptions::default(),
+            buffers: ParseBuffers::default(),
+            progress: None,
+            cancellation_flag: None,
+            layer_filter: None,
+            observers: ParseObservers::default(),
+            property_parsers: PropertyParsers::default(),
+            path_policy: PathPolicy::default(),
         }
     }
 }
@@ -156,7 +357,18 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     /// # }
     /// ```
     pub fn with_cache_and_reader(cache: Cache, reader: Reader) -> Self {
-        Self { cache, reader }
+        Self {
+            cache,
+            reader,
+            options: LoaderOptions::default(),
+            buffers: ParseBuffers::default(),
+            progress: None,
+            cancellation_flag: None,
+            layer_filter: None,
+            observers: ParseObservers::default(),
+            property_parsers: PropertyParsers::default(),
+            path_policy: PathPolicy::default(),
+        }
     }
 
     /// Parses a file hopefully containing a Tiled map and tries to parse it. All external files
@@ -166,7 +378,199 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     ///
     /// [internal loader cache]: Loader::cache()
     pub fn load_tmx_map(&mut self, path: impl AsRef<Path>) -> Result<Map> {
-        crate::parse::xml::parse_map(path.as_ref(), &mut self.reader, &mut self.cache)
+        self.load_tmx_map_with_dependencies(path.as_ref())
+            .map(|(map, _)| map)
+    }
+
+    /// Loads as much of the map at the given path as possible, e.g. for an editor to display the
+    /// parts of a map that load while pointing out the broken ones.
+    ///
+    /// The map is loaded as if [`LoaderOptions::collect_errors`] were set: Elements that fail to
+    /// load, such as tilesets, objects or the tile data of layers, are left out of the map, and
+    /// the errors they failed with are returned as [`Diagnostic::Error`]s. They are followed by the
+    /// [warnings](Map::warnings) of the map as [`Diagnostic::Warning`]s. If the map can't be
+    /// loaded at all, for instance when its file can't be read, no map is returned and the
+    /// diagnostics explain why.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// let (map, diagnostics) = Loader::new().try_load_tmx_map("assets/tiled_base64.tmx");
+    /// assert!(map.is_some());
+    /// for diagnostic in &diagnostics {
+    ///     eprintln!("{}", diagnostic);
+    /// }
+    /// ```
+    pub fn try_load_tmx_map(&mut self, path: impl AsRef<Path>) -> (Option<Map>, Vec<Diagnostic>) {
+        let collect_errors = std::mem::replace(&mut self.options.collect_errors, true);
+        let mut ctx = self.map_context();
+        self.options.collect_errors = collect_errors;
+        let map = crate::parse::xml::parse_map(
+            path.as_ref(),
+            &mut self.reader,
+            &mut self.cache,
+            &mut ctx,
+        );
+        let mut errors = ctx.take_errors();
+        self.buffers = ctx.into_buffers();
+
+        let map = match map {
+            Ok(map) => Some(map),
+            Err(Error::Multiple(mut failed)) => {
+                errors.append(&mut failed);
+                None
+            }
+            Err(err) => {
+                errors.push(err);
+                None
+            }
+        };
+        let mut diagnostics: Vec<_> = errors.into_iter().map(Diagnostic::Error).collect();
+        if let Some(map) = &map {
+            diagnostics.extend(map.warnings().iter().cloned().map(Diagnostic::Warning));
+        }
+        (map, diagnostics)
+    }
+
+    /// Loads a map like [`Loader::load_tmx_map`], also returning the files it references.
+    pub(crate) fn load_tmx_map_with_dependencies(
+        &mut self,
+        path: &Path,
+    ) -> Result<(Map, Dependencies)> {
+        let mut ctx = self.map_context();
+        let map = crate::parse::xml::parse_map(path, &mut self.reader, &mut self.cache, &mut ctx);
+        let dependencies = ctx.take_dependencies();
+        self.finish_loading(map, ctx).map(|map| (map, dependencies))
+    }
+
+    /// Parses a map from memory, e.g. one received over the network, as if it had been read from
+    /// `path`. The files it references are still read with the loader's [`ResourceReader`],
+    /// relative to `path`, which doesn't need to exist itself.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let data = std::fs::read("assets/tiled_base64_external.tmx").unwrap();
+    ///
+    /// // The external tileset is read from "assets/tilesheet.tsx"
+    /// let map = Loader::new().load_tmx_map_from_slice(&data, "assets/downloaded.tmx")?;
+    /// assert_eq!(map.tilesets()[0].name, "tilesheet");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_tmx_map_from_slice(&mut self, data: &[u8], path: impl AsRef<Path>) -> Result<Map> {
+        let mut ctx = self.map_context();
+        let map = crate::parse::xml::parse_map_from(
+            data,
+            path.as_ref(),
+            &mut self.reader,
+            &mut self.cache,
+            &mut ctx,
+        );
+        self.finish_loading(map, ctx)
+    }
+
+    /// Loads every map found in the directory at `path` and its subdirectories, keyed by their
+    /// path, e.g. to load all the maps of a game server when it starts.
+    ///
+    /// Maps are the files with a `.tmx` extension, found through
+    /// [`ResourceReader::list_directory()`]. They are loaded in the order of their paths, like
+    /// they would be with [`Loader::load_tmx_map`], so the tilesets and templates they share
+    /// are only loaded once and end up in the [internal loader cache]. Loading stops at the first
+    /// map that fails to load.
+    ///
+    /// Fails with [`Error::ResourceLoadingError`] if a directory can't be listed, including when
+    /// the reader of the loader doesn't support listing directories.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let maps = Loader::new().load_all_tmx_in_dir("assets/world")?;
+    ///
+    /// assert!(maps.contains_key(Path::new("assets/world/grid/nested/grid-2-0.tmx")));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn load_all_tmx_in_dir(
+        &mut self,
+        path: impl AsRef<Path>,
+    ) -> Result<BTreeMap<PathBuf, Map>> {
+        let mut map_paths = Vec::new();
+        let mut directories = vec![path.as_ref().to_owned()];
+        while let Some(directory) = directories.pop() {
+            let entries = list_directory(&mut self.reader, &directory)?;
+            for entry in entries {
+                if entry.is_directory {
+                    directories.push(entry.path);
+                } else if entry
+                    .path
+                    .extension()
+                    .is_some_and(|extension| extension.eq_ignore_ascii_case("tmx"))
+                {
+                    map_paths.push(entry.path);
+                }
+            }
+        }
+
+        map_paths.sort_unstable();
+        let mut maps = BTreeMap::new();
+        for path in map_paths {
+            let map = self.load_tmx_map(&path)?;
+            maps.insert(path, map);
+        }
+        Ok(maps)
+    }
+
+    fn map_context(&mut self) -> ParseContext {
+        let mut ctx = ParseContext::new(self.options, std::mem::take(&mut self.buffers));
+        ctx.set_path_policy(self.path_policy.clone());
+        ctx.set_progress_callback(self.progress.clone());
+        ctx.set_cancellation_flag(self.cancellation_flag.clone());
+        ctx.set_layer_filter(self.layer_filter.clone());
+        ctx.set_observers(self.observers.clone());
+        ctx.set_property_parsers(self.property_parsers.clone());
+        ctx
+    }
+
+    /// Returns every file the map at `path` references, directly or through the files it
+    /// references: Its external tilesets, the templates of its objects and the tilesets they use,
+    /// and the images of all of these tilesets and of its image layers. The map itself isn't
+    /// included. Files are sorted by path and appear once each, even if referenced several times.
+    ///
+    /// This is meant for build systems that need to know which assets a map needs. The map is
+    /// loaded in the process, so the tilesets and templates it uses end up in the
+    /// [internal loader cache], while referenced images don't need to exist.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::{DependencyKind, Loader};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let dependencies = Loader::new().dependencies("assets/tiled_base64_external.tmx")?;
+    ///
+    /// let tilesets: Vec<&Path> = dependencies
+    ///     .iter()
+    ///     .filter(|dependency| dependency.kind == DependencyKind::Tileset)
+    ///     .map(|dependency| dependency.path.as_path())
+    ///     .collect();
+    /// assert_eq!(tilesets, [Path::new("assets/tilesheet.tsx")]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn dependencies(&mut self, path: impl AsRef<Path>) -> Result<Vec<Dependency>> {
+        self.load_tmx_map_with_dependencies(path.as_ref())
+            .map(|(_, dependencies)| dependencies.into_vec())
     }
 
     /// Parses a file hopefully containing a Tiled tileset and tries to parse it. All external files
@@ -179,7 +583,83 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
     /// This function will **not** cache the tileset inside the internal [`ResourceCache`], since
     /// in this context it is not an intermediate object.
     pub fn load_tsx_tileset(&mut self, path: impl AsRef<Path>) -> Result<Tileset> {
-        crate::parse::xml::parse_tileset(path.as_ref(), &mut self.reader, &mut self.cache)
+        let mut ctx = self.tileset_context();
+        let tileset = crate::parse::xml::parse_tileset(
+            path.as_ref(),
+            &mut self.reader,
+            &mut self.cache,
+            &mut ctx,
+        );
+        self.finish_loading(tileset, ctx)
+    }
+
+    /// Parses a tileset from memory as if it had been read from `path`, like
+    /// [`Loader::load_tmx_map_from_slice`] does for maps. Like [`Loader::load_tsx_tileset`], the
+    /// tileset is not cached.
+    pub fn load_tsx_tileset_from_slice(
+        &mut self,
+        data: &[u8],
+        path: impl AsRef<Path>,
+    ) -> Result<Tileset> {
+        let mut ctx = self.tileset_context();
+        let tileset = crate::parse::xml::parse_tileset_from(
+            data,
+            path.as_ref(),
+            &mut self.reader,
+            &mut self.cache,
+            &mut ctx,
+        );
+        self.finish_loading(tileset, ctx)
+    }
+
+    /// Loads the template at `path`, or returns it from the internal [`ResourceCache`] if it was
+    /// already loaded, e.g. along with a map using it. Unlike tilesets loaded with
+    /// [`Loader::load_tsx_tileset`], templates are cached, since maps loaded afterwards that use
+    /// them can share them.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let template = Loader::new().load_tx_template("assets/tiled_object_template.tx")?;
+    /// assert_eq!(template.tileset.as_ref().unwrap().name, "tilesheet_template");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_tx_template(&mut self, path: impl AsRef<Path>) -> Result<Arc<Template>> {
+        let path = path.as_ref();
+        if let Some(template) = self.cache.get_template(path) {
+            return Ok(template);
+        }
+        let mut ctx = self.tileset_context();
+        let template = Template::parse_template(path, &mut self.reader, &mut self.cache, &mut ctx);
+        let template = self.finish_loading(template, ctx)?;
+        self.cache.insert_template(path, template.clone());
+        Ok(template)
+    }
+
+    fn tileset_context(&mut self) -> ParseContext {
+        let mut ctx = ParseContext::new(self.options, std::mem::take(&mut self.buffers));
+        ctx.set_path_policy(self.path_policy.clone());
+        ctx.set_cancellation_flag(self.cancellation_flag.clone());
+        ctx.set_observers(self.observers.clone());
+        ctx.set_property_parsers(self.property_parsers.clone());
+        ctx
+    }
+
+    /// Returns the external tileset at `path` from the cache, or loads and caches it like the ones
+    /// referenced by maps.
+    pub(crate) fn load_cached_tileset(&mut self, path: &Path) -> Result<Arc<Tileset>> {
+        if let Some(tileset) = self.cache.get_tileset(path) {
+            return Ok(tileset);
+        }
+        let mut ctx = self.tileset_context();
+        let tileset =
+            crate::parse::xml::parse_tileset(path, &mut self.reader, &mut self.cache, &mut ctx);
+        let tileset = Arc::new(self.finish_loading(tileset, ctx)?);
+        self.cache.insert_tileset(path, tileset.clone());
+        Ok(tileset)
     }
 
     /// Returns a reference to the loader's internal [`ResourceCache`].
@@ -202,6 +682,252 @@ impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
         &mut self.reader
     }
 
+    /// Merges the errors collected while loading with the result of the load, and keeps the
+    /// strings interned and the buffers used during it for the next loads.
+    fn finish_loading<T>(&mut self, result: Result<T>, mut ctx: ParseContext) -> Result<T> {
+        let mut errors = ctx.take_errors();
+        self.buffers = ctx.into_buffers();
+        match result {
+            // Errors found before cancelling don't matter anymore
+            Err(Error::Cancelled) => Err(Error::Cancelled),
+            Ok(value) if errors.is_empty() => Ok(value),
+            Ok(_) => Err(Error::Multiple(errors)),
+            Err(err) if errors.is_empty() => Err(err),
+            Err(err) => {
+                errors.push(err);
+                Err(Error::Multiple(errors))
+            }
+        }
+    }
+
+    /// Sets a function to call each time a tileset, template or layer is loaded while loading a
+    /// map, e.g. to show the progress of loading large maps on a loading screen. Replaces the one
+    /// set before, if any.
+    ///
+    /// The function is given the path of the file the resource was read from, which is the map's
+    /// for embedded tilesets and layers, the index of the resource among the ones loaded so far
+    /// during the current load, starting from 0, and an estimate of the total number of resources
+    /// the map is made of. The estimate always exceeds the index, but may be off by a few
+    /// resources: For instance, it includes the collision shapes of the tiles of embedded
+    /// tilesets, but not the tilesets of templates. Templates count once per object using them.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// loader.on_resource_loaded(|path, index, total_estimate| {
+    ///     println!("{}/{}: {}", index + 1, total_estimate, path.display());
+    /// });
+    /// let map = loader.load_tmx_map("assets/tiled_group_layers.tmx")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_resource_loaded(
+        &mut self,
+        callback: impl Fn(&Path, usize, usize) + Send + Sync + 'static,
+    ) {
+        self.progress = Some(ProgressCallback(Arc::new(callback)));
+    }
+
+    /// Removes the function set with [`Loader::on_resource_loaded`].
+    pub fn clear_resource_loaded(&mut self) {
+        self.progress = None;
+    }
+
+    /// Makes loads stop as soon as possible once `flag` is set, e.g. from another thread, failing
+    /// with [`Error::Cancelled`]. The flag is checked before each file, layer and chunk of tile data
+    /// is parsed. Replaces the flag set before, if any.
+    ///
+    /// The flag isn't reset by the loader, so it needs to be cleared before loading anything else.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    /// use tiled::{Error, Loader};
+    ///
+    /// let cancelled = Arc::new(AtomicBool::new(false));
+    /// let mut loader = Loader::new();
+    /// loader.set_cancellation_flag(cancelled.clone());
+    ///
+    /// // Usually set from another thread, such as the one running a loading screen
+    /// cancelled.store(true, Ordering::Relaxed);
+    /// let result = loader.load_tmx_map("assets/tiled_base64_zlib_infinite.tmx");
+    /// assert!(matches!(result, Err(Error::Cancelled)));
+    /// ```
+    pub fn set_cancellation_flag(&mut self, flag: Arc<AtomicBool>) {
+        self.cancellation_flag = Some(flag);
+    }
+
+    /// Removes the flag set with [`Loader::set_cancellation_flag`].
+    pub fn clear_cancellation_flag(&mut self) {
+        self.cancellation_flag = None;
+    }
+
+    /// Makes maps load only the layers `filter` returns `true` for, such as on a server that only
+    /// needs the collision and object layers of maps. The other layers are skipped without
+    /// decoding their contents, and don't count against [`LoaderOptions::max_layers`].
+    ///
+    /// The filter is also called for group layers, which are skipped along with all the layers
+    /// they contain when rejected. Replaces the filter set before, if any.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{LayerKind, Loader};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// // Groups are kept so that the object layers they contain are loaded too
+    /// loader.set_layer_filter(|layer| matches!(layer.kind, LayerKind::Objects | LayerKind::Group));
+    /// let map = loader.load_tmx_map("assets/tiled_object_groups.tmx")?;
+    /// assert!(map.layers().all(|layer| layer.as_tile_layer().is_none()));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_layer_filter(
+        &mut self,
+        filter: impl Fn(&LayerInfo) -> bool + Send + Sync + 'static,
+    ) {
+        self.layer_filter = Some(LayerFilter(Arc::new(filter)));
+    }
+
+    /// Removes the filter set with [`Loader::set_layer_filter`], so that maps are loaded with all
+    /// their layers.
+    pub fn clear_layer_filter(&mut self) {
+        self.layer_filter = None;
+    }
+
+    /// Sets a function to call each time a tileset has been parsed, before the map or template
+    /// using it is done loading, e.g. to build lookup tables alongside the tileset instead of
+    /// walking it again afterwards. Replaces the one set before, if any.
+    ///
+    /// The function is called for embedded tilesets and for external ones, which includes the
+    /// tilesets loaded by themselves and the ones used by templates. External tilesets that are
+    /// already in the cache aren't parsed again, so the function isn't called for them.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let names = Arc::new(Mutex::new(Vec::new()));
+    /// let mut loader = Loader::new();
+    /// let parsed = names.clone();
+    /// loader.on_tileset_parsed(move |tileset| parsed.lock().unwrap().push(tileset.name.clone()));
+    /// loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// assert_eq!(*names.lock().unwrap(), ["tilesheet"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn on_tileset_parsed(&mut self, callback: impl Fn(&Tileset) + Send + Sync + 'static) {
+        self.observers.tileset = Some(Arc::new(callback));
+    }
+
+    /// Sets a function to call each time a layer of a map has been parsed, before the rest of the
+    /// map is. Replaces the one set before, if any.
+    ///
+    /// Group layers are given to the function once all the layers they contain have been, so the
+    /// function sees layers from the innermost to the outermost. The layers skipped by the
+    /// [layer filter](Loader::set_layer_filter) aren't parsed, so it isn't called for them.
+    pub fn on_layer_parsed(&mut self, callback: impl Fn(&LayerData) + Send + Sync + 'static) {
+        self.observers.layer = Some(Arc::new(callback));
+    }
+
+    /// Sets a function to call each time an object of an object layer of a map has been parsed,
+    /// before the layer it is in is. Replaces the one set before, if any.
+    ///
+    /// The objects of templates and the collision shapes of tiles aren't given to the function.
+    /// Objects using a template are given with the template applied.
+    pub fn on_object_parsed(&mut self, callback: impl Fn(&ObjectData) + Send + Sync + 'static) {
+        self.observers.object = Some(Arc::new(callback));
+    }
+
+    /// Removes the functions set with [`Loader::on_tileset_parsed`],
+    /// [`Loader::on_layer_parsed`] and [`Loader::on_object_parsed`].
+    pub fn clear_parse_observers(&mut self) {
+        self.observers = ParseObservers::default();
+    }
+
+    /// Makes the loader read the properties whose type is `type_name` with `parser`, storing
+    /// them as [`PropertyValue::Custom`](crate::PropertyValue::Custom) instead of failing with
+    /// [`Error::UnknownPropertyType`], e.g. for properties written by Tiled extensions. Replaces
+    /// the parser registered for this type before, if any.
+    ///
+    /// This applies to the types this crate doesn't know only: parsers registered for built-in
+    /// types such as `int` are never used. Values `parser` fails to read are reported as
+    /// [`Error::InvalidPropertyValue`].
+    ///
+    /// ## Example
+    /// ```
+    /// use std::{io::Cursor, net::IpAddr, path::Path};
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+    ///   <properties><property name="server" type="address" value="127.0.0.1"/></properties>
+    /// </map>"#;
+    /// let reader = move |_: &Path| -> std::io::Result<_> { Ok(Cursor::new(map)) };
+    /// let mut loader = Loader::with_reader(reader);
+    /// loader.register_property_type("address", |value: &str| value.parse::<IpAddr>());
+    ///
+    /// let map = loader.load_tmx_map("server.tmx")?;
+    /// if let PropertyValue::Custom(custom) = &map.properties["server"] {
+    ///     assert_eq!(custom.downcast_ref(), Some(&IpAddr::from([127, 0, 0, 1])));
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn register_property_type(
+        &mut self,
+        type_name: impl Into<String>,
+        parser: impl PropertyValueParser + 'static,
+    ) {
+        self.property_parsers
+            .insert(type_name.into(), Arc::new(parser));
+    }
+
+    /// Removes the parsers registered with [`Loader::register_property_type`], so that
+    /// properties of types this crate doesn't know fail to load again.
+    pub fn clear_property_types(&mut self) {
+        self.property_parsers = PropertyParsers::default();
+    }
+
+    /// Returns a reference to the options used by the loader.
+    pub fn options(&self) -> &LoaderOptions {
+        &self.options
+    }
+
+    /// Returns a mutable reference to the options used by the loader.
+    pub fn options_mut(&mut self) -> &mut LoaderOptions {
+        &mut self.options
+    }
+
+    /// Returns how the paths found in files are resolved. See [`PathPolicy`].
+    pub fn path_policy(&self) -> &PathPolicy {
+        &self.path_policy
+    }
+
+    /// Sets how the paths found in files are resolved from now on. See [`PathPolicy`].
+    pub fn set_path_policy(&mut self, policy: PathPolicy) {
+        self.path_policy = policy;
+    }
+
+    /// Releases the memory the loader keeps from one load to the next: The buffers tile data is
+    /// decoded with, and the pool of strings such as property names, which maps and tilesets
+    /// loaded with the same loader share.
+    ///
+    /// These are kept so that loading many maps with the same loader, e.g. when reloading them,
+    /// doesn't allocate them again each time. Clearing them doesn't affect anything loaded, but
+    /// strings loaded afterwards aren't shared with those loaded before. The
+    /// [internal loader cache] is left as is.
+    ///
+    /// [internal loader cache]: Loader::cache()
+    pub fn clear_buffers(&mut self) {
+        self.buffers = ParseBuffers::default();
+    }
+
     /// Consumes the loader and returns its internal [`ResourceCache`] and [`ResourceReader`].
     pub fn into_inner(self) -> (Cache, Reader) {
         (self.cache, self.reader)
@@ -0,0 +1,74 @@
+//! Conversion of object shapes into [lyon](https://github.com/nical/lyon) paths. Requires the
+//! `lyon` feature.
+//!
+//! [`shape_to_path()`] and [`object_to_path()`] turn rectangles, ellipses, polygons and polylines
+//! into [`lyon_path::Path`]s, which can then be tessellated for rendering, hit-tested or combined
+//! with the other algorithms of lyon. Ellipses are made of curves rather than approximated by
+//! polygons. Points and text objects have no path.
+//!
+//! ## Example
+//! ```
+//! use tiled::{lyon::object_to_path, Loader};
+//!
+//! # fn main() -> tiled::Result<()> {
+//! let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+//! let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+//!
+//! for object in layer.objects() {
+//!     if let Some(path) = object_to_path(&object) {
+//!         // The path can be given to a lyon tessellator
+//!         println!("object {} has {} path events", object.id(), path.iter().count());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use lyon_path::{
+    math::{point, vector, Angle, Box2D, Transform},
+    Path, Winding,
+};
+
+use crate::{ObjectData, ObjectShape};
+
+/// Returns the path of `shape`, relative to the position of the object it belongs to and ignoring
+/// the object's rotation, or [`None`] for points, texts and polygonal shapes without points.
+///
+/// Polygons and rectangles are closed paths, while polylines are left open.
+pub fn shape_to_path(shape: &ObjectShape) -> Option<Path> {
+    let mut builder = Path::builder();
+    match shape {
+        ObjectShape::Rect { width, height } => builder.add_rectangle(
+            &Box2D::new(point(0.0, 0.0), point(*width, *height)),
+            Winding::Positive,
+        ),
+        ObjectShape::Ellipse { width, height } => builder.add_ellipse(
+            point(width / 2.0, height / 2.0),
+            vector(width / 2.0, height / 2.0),
+            Angle::zero(),
+            Winding::Positive,
+        ),
+        ObjectShape::Polygon { points } | ObjectShape::Polyline { points } => {
+            let (first, rest) = points.split_first()?;
+            builder.begin(point(first.0, first.1));
+            for (x, y) in rest {
+                builder.line_to(point(*x, *y));
+            }
+            builder.end(matches!(shape, ObjectShape::Polygon { .. }));
+        }
+        ObjectShape::Point(..) | ObjectShape::Text { .. } => return None,
+    }
+    Some(builder.build())
+}
+
+/// Returns the path of the shape of `object`, positioned and rotated like the object, or [`None`]
+/// if the shape has no path. See [`shape_to_path()`] and [`ObjectData::transform()`].
+///
+/// The rectangles of tile objects, which are positioned by their bottom left corner, extend
+/// upwards from the position of the object.
+pub fn object_to_path(object: &ObjectData) -> Option<Path> {
+    let path = shape_to_path(&object.shape)?;
+    let [a, b, c, d, e, f] = object.transform().to_array();
+    let transform = Transform::new(a, b, c, d, e, f);
+    Some(path.transformed(&transform))
+}
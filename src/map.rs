@@ -1,7 +1,6 @@
 //! Structures related to Tiled maps.
 
 use std::{
-    collections::HashMap,
     fmt,
     path::{Path, PathBuf},
     str::FromStr,
@@ -13,12 +12,60 @@ use xml::attribute::OwnedAttribute;
 use crate::{
     error::{Error, Result},
     layers::{LayerData, LayerTag},
+    parse::ParseContext,
     properties::{parse_properties, Color, Properties},
     tileset::Tileset,
+    trace::load_span,
     util::{get_attrs, parse_tag, XmlEventResult},
-    EmbeddedParseResultType, Layer, ResourceCache, ResourceReader,
+    ChunkData, DependencyKind, EmbeddedParseResultType, FlattenedLayer, Layer, LayerType,
+    ParseWarning, ResourceCache, ResourceReader, TileLayer, UnknownXml, Version,
 };
 
+mod compatibility;
+mod content_eq;
+mod content_hash;
+mod depth;
+mod editor_settings;
+mod flood_fill;
+mod gid_lookup;
+mod layer_edit;
+mod layer_lookup;
+mod merge;
+mod nav;
+mod navmesh;
+mod object_lookup;
+mod pending_tilesets;
+#[cfg(feature = "project")]
+mod project_defaults;
+mod query;
+mod reencode;
+mod relocate;
+mod resize;
+mod stats;
+mod template_reapply;
+mod tileset_embed;
+mod tileset_replace;
+mod tileset_usage;
+mod transaction;
+mod validate;
+use gid_lookup::GidTable;
+
+pub use compatibility::*;
+pub use depth::*;
+pub use editor_settings::*;
+pub use merge::*;
+pub use nav::*;
+pub use navmesh::*;
+pub use pending_tilesets::*;
+pub use query::*;
+pub use reencode::*;
+pub use relocate::*;
+pub use resize::*;
+pub use stats::*;
+pub use tileset_embed::*;
+pub use transaction::*;
+pub use validate::*;
+
 pub(crate) struct MapTilesetGid {
     pub first_gid: Gid,
     pub tileset: Arc<Tileset>,
@@ -26,8 +73,10 @@ pub(crate) struct MapTilesetGid {
 
 /// All Tiled map files will be parsed into this. Holds all the layers and tilesets.
 #[derive(PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct Map {
     version: String,
+    tiled_version: Option<Version>,
     /// The path first used in a [`ResourceReader`] to load this map.
     pub source: PathBuf,
     /// The way tiles are laid out in the map.
@@ -73,12 +122,47 @@ pub struct Map {
     infinite: bool,
     /// The type of the map, which is arbitrary and set by the user.
     pub user_type: Option<String>,
+    /// The compression level of the compressed tile layer data of this map, or `None` for the
+    /// default level of the compression method. Also found in the [`Encoding`](crate::Encoding)
+    /// of each layer.
+    pub compression_level: Option<i32>,
+    /// The settings Tiled stores in the map for its own use, or `None` if the map has no
+    /// `<editorsettings>` element.
+    pub editor_settings: Option<EditorSettings>,
+    next_layer_id: u32,
+    next_object_id: u32,
+    warnings: Vec<ParseWarning>,
+    unknown_xml: UnknownXml,
+    #[cfg_attr(feature = "snapshot", serde(skip))]
+    gid_table: GidTable,
 }
 
+/// The attributes of `<map>` that are loaded into a [`Map`].
+const MAP_ATTRIBUTES: &[&str] = &[
+    "backgroundcolor",
+    "compressionlevel",
+    "infinite",
+    "type",
+    "class",
+    "staggeraxis",
+    "staggerindex",
+    "hexsidelength",
+    "nextlayerid",
+    "nextobjectid",
+    "version",
+    "tiledversion",
+    "orientation",
+    "width",
+    "height",
+    "tilewidth",
+    "tileheight",
+];
+
 impl fmt::Debug for Map {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Map")
             .field("version", &self.version)
+            .field("tiled_version", &self.tiled_version)
             .field("orientation", &self.orientation)
             .field("width", &self.width)
             .field("height", &self.height)
@@ -92,6 +176,12 @@ impl fmt::Debug for Map {
             .field("background_color", &self.background_color)
             .field("infinite", &self.infinite)
             .field("user_type", &self.user_type)
+            .field("compression_level", &self.compression_level)
+            .field("editor_settings", &self.editor_settings)
+            .field("next_layer_id", &self.next_layer_id)
+            .field("next_object_id", &self.next_object_id)
+            .field("warnings", &self.warnings)
+            .field("unknown_xml", &self.unknown_xml)
             .finish()
     }
 }
@@ -103,12 +193,114 @@ impl Map {
         self.version.as_ref()
     }
 
+    /// The TMX format version this map was saved to as a [`Version`], or [`None`] if it is not
+    /// made of numbers like `1.10`.
+    pub fn format_version(&self) -> Option<Version> {
+        self.version.parse().ok()
+    }
+
+    /// The version of Tiled that saved this map. Equivalent to the map file's `tiledversion`
+    /// attribute, and [`None`] if it was missing or not a valid [`Version`].
+    pub fn tiled_version(&self) -> Option<Version> {
+        self.tiled_version
+    }
+
     /// Whether this map is infinite. An infinite map has no fixed size and can grow in all
     /// directions. Its layer data is stored in chunks. This value determines whether the map's
     /// tile layers are [`FiniteTileLayer`](crate::FiniteTileLayer)s or [`crate::InfiniteTileLayer`](crate::InfiniteTileLayer)s.
     pub fn infinite(&self) -> bool {
         self.infinite
     }
+
+    /// Returns the position, in tiles, of the top left corner of the area covered by the tile
+    /// layers of this map, and the size of that area in tiles. For infinite maps, this is the
+    /// smallest rectangle containing all of their chunks.
+    pub(crate) fn tile_bounds(&self) -> ((i32, i32), (u32, u32)) {
+        if !self.infinite {
+            return ((0, 0), (self.width, self.height));
+        }
+
+        fn chunk_bounds<'map>(
+            layers: impl Iterator<Item = Layer<'map>>,
+            bounds: &mut Option<(i32, i32, i32, i32)>,
+        ) {
+            for layer in layers {
+                match layer.layer_type() {
+                    LayerType::Tiles(TileLayer::Infinite(tiles)) => {
+                        for ((x, y), _) in tiles.chunks() {
+                            let (left, top) =
+                                (x * ChunkData::WIDTH as i32, y * ChunkData::HEIGHT as i32);
+                            let (right, bottom) = (
+                                left + ChunkData::WIDTH as i32,
+                                top + ChunkData::HEIGHT as i32,
+                            );
+                            *bounds = Some(match *bounds {
+                                Some((l, t, r, b)) => {
+                                    (l.min(left), t.min(top), r.max(right), b.max(bottom))
+                                }
+                                None => (left, top, right, bottom),
+                            });
+                        }
+                    }
+                    LayerType::Group(group) => chunk_bounds(group.layers(), bounds),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut bounds = None;
+        chunk_bounds(self.layers(), &mut bounds);
+        match bounds {
+            Some((left, top, right, bottom)) => {
+                ((left, top), ((right - left) as u32, (bottom - top) as u32))
+            }
+            None => ((0, 0), (0, 0)),
+        }
+    }
+
+    /// The ID that will be given to the next layer created in this map. Equivalent to the map
+    /// file's `nextlayerid` attribute, or one past the highest layer ID in the map if the
+    /// attribute was not present.
+    pub fn next_layer_id(&self) -> u32 {
+        self.next_layer_id
+    }
+
+    /// The ID that will be given to the next object created in this map. Equivalent to the map
+    /// file's `nextobjectid` attribute, or one past the highest object ID in the map if the
+    /// attribute was not present.
+    pub fn next_object_id(&self) -> u32 {
+        self.next_object_id
+    }
+
+    /// The problems found while loading this map in [lenient mode](crate::LoaderOptions::lenient),
+    /// including the ones found in the tilesets and templates that were loaded along with it.
+    /// Always empty otherwise.
+    ///
+    /// Resources that were already present in the loader's cache are not parsed again, and as
+    /// such their warnings are not repeated here.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// The parts of the map file this crate does not model, if loaded with
+    /// [`LoaderOptions::preserve_unknown`](crate::LoaderOptions::preserve_unknown) set.
+    pub fn unknown_xml(&self) -> &UnknownXml {
+        &self.unknown_xml
+    }
+
+    /// Reserves a new layer ID, unique within this map.
+    pub(crate) fn allocate_layer_id(&mut self) -> u32 {
+        let id = self.next_layer_id;
+        self.next_layer_id += 1;
+        id
+    }
+
+    /// Reserves a new object ID, unique within this map.
+    pub(crate) fn allocate_object_id(&mut self) -> u32 {
+        let id = self.next_object_id;
+        self.next_object_id += 1;
+        id
+    }
 }
 
 impl Map {
@@ -152,6 +344,33 @@ impl Map {
         self.layers.iter().map(move |layer| Layer::new(self, layer))
     }
 
+    /// Returns an iterator over every layer of the map that isn't a group, including those nested
+    /// within groups, in display order, each along with its properties combined with those of
+    /// its groups. See [`GroupLayer::flatten()`](crate::GroupLayer::flatten).
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    /// for flattened in map.flatten_layers().filter(|flattened| flattened.visible) {
+    ///     println!(
+    ///         "Drawing {} at {:?} with opacity {}",
+    ///         flattened.layer.name, flattened.offset, flattened.opacity
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn flatten_layers(&self) -> impl Iterator<Item = FlattenedLayer<'_>> {
+        let mut layers = Vec::new();
+        for layer in self.layers() {
+            FlattenedLayer::root(layer).flatten_into(&mut layers);
+        }
+        layers.into_iter()
+    }
+
     /// Returns the top-level layer that has the specified index, if it exists.
     pub fn get_layer(&self, index: usize) -> Option<Layer> {
         self.layers.get(index).map(|data| Layer::new(self, data))
@@ -165,9 +384,11 @@ impl Map {
         map_path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<Map> {
         let (
             (c, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length),
+            (next_layer_id, next_object_id, tiled_version, compression_level),
             (v, o, w, h, tw, th),
         ) = get_attrs!(
             for v in attrs {
@@ -178,6 +399,10 @@ impl Map {
                 Some("staggeraxis") => stagger_axis ?= v.parse::<StaggerAxis>(),
                 Some("staggerindex") => stagger_index ?= v.parse::<StaggerIndex>(),
                 Some("hexsidelength") => hex_side_length ?= v.parse(),
+                Some("nextlayerid") => next_layer_id ?= v.parse::<u32>(),
+                Some("nextobjectid") => next_object_id ?= v.parse::<u32>(),
+                Some("tiledversion") => tiled_version = v.parse::<Version>().ok(),
+                Some("compressionlevel") => compression_level ?= v.parse::<i32>(),
                 "version" => version = v,
                 "orientation" => orientation ?= v.parse::<Orientation>(),
                 "width" => width ?= v.parse::<u32>(),
@@ -185,44 +410,65 @@ impl Map {
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length), (version, orientation, width, height, tile_width, tile_height))
+            ((colour, infinite, user_type, user_class, stagger_axis, stagger_index, hex_side_length), (next_layer_id, next_object_id, tiled_version, compression_level), (version, orientation, width, height, tile_width, tile_height))
         );
 
+        ctx.check_dimensions(w, h)?;
         let infinite = infinite.unwrap_or(false);
-        let user_type = user_type.or(user_class);
         let stagger_axis = stagger_axis.unwrap_or_default();
         let stagger_index = stagger_index.unwrap_or_default();
+        // Tiled writes -1 for the default level
+        let compression_level = compression_level.filter(|&level| level >= 0);
 
         // We can only parse sequentally, but tilesets are guaranteed to appear before layers.
         // So we can pass in tileset data to layer construction without worrying about unfinished
         // data usage.
         let mut layers = Vec::new();
-        let mut properties = HashMap::new();
+        let mut properties = Properties::new();
         let mut tilesets = Vec::new();
+        let mut editor_settings = None;
 
-        parse_tag!(parser, "map", {
+        let first_warning = ctx.warning_count();
+        let user_type = ctx.class_attribute("map", user_type, user_class);
+        let depth = ctx.depth();
+        parse_tag!(parser, ctx, "map", {
             "tileset" => |attrs: Vec<OwnedAttribute>| {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path,  reader, cache)?;
+                let res = Tileset::parse_xml_in_map(parser, &attrs, map_path,  reader, cache, ctx)?;
                 match res.result_type {
                     EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        let tileset = if let Some(ts) = cache.get_tileset(&tileset_path) {
+                        let span = load_span!("resolve_tileset", path = %tileset_path.display(), cached = tracing::field::Empty);
+                        let cached = cache.get_tileset(&tileset_path);
+                        span.record("cached", cached.is_some());
+                        let tileset = if let Some(ts) = cached {
                             ts
+                        } else if ctx.defer_tilesets() {
+                            ctx.dependencies_mut().add(&tileset_path, DependencyKind::Tileset);
+                            tilesets.push(MapTilesetGid{first_gid: res.first_gid, tileset: Arc::new(Tileset::pending(tileset_path))});
+                            return Ok(());
                         } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path,  reader, cache)?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
+                            let errors = ctx.error_count();
+                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path,  reader, cache, ctx)?);
+                            // Don't cache tilesets that are missing parts because of errors
+                            if ctx.error_count() == errors {
+                                cache.insert_tileset(tileset_path.clone(), tileset.clone());
+                            }
                             tileset
                         };
 
+                        ctx.dependencies_mut().add_tileset(&tileset_path, &tileset);
+                        ctx.resource_loaded(&tileset_path);
                         tilesets.push(MapTilesetGid{first_gid: res.first_gid, tileset});
                     }
                     EmbeddedParseResultType::Embedded { tileset } => {
+                        ctx.dependencies_mut().add_tileset_images(&tileset);
+                        ctx.resource_loaded(map_path);
                         tilesets.push(MapTilesetGid{first_gid: res.first_gid, tileset: Arc::new(tileset)});
                     },
                 };
                 Ok(())
             },
             "layer" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Tiles,
@@ -231,12 +477,13 @@ impl Map {
                     &tilesets,
                     None,
                     reader,
-                    cache
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "imagelayer" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Image,
@@ -245,12 +492,13 @@ impl Map {
                     &tilesets,
                     None,
                     reader,
-                    cache
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "objectgroup" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Objects,
@@ -259,12 +507,13 @@ impl Map {
                     &tilesets,
                     None,
                     reader,
-                    cache
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "group" => |attrs| {
-                layers.push(LayerData::new(
+                layers.extend(LayerData::new(
                     parser,
                     attrs,
                     LayerTag::Group,
@@ -273,21 +522,41 @@ impl Map {
                     &tilesets,
                     None,
                     reader,
-                    cache
+                    cache,
+                    ctx,
                 )?);
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
+                Ok(())
+            },
+            "editorsettings" => |_| {
+                editor_settings = Some(EditorSettings::parse(parser, map_path, ctx)?);
                 Ok(())
             },
         });
 
+        let unknown_xml = ctx.take_unknown_xml("map", &attrs, MAP_ATTRIBUTES, depth)?;
+
+        if compression_level.is_some() {
+            // The level is only stored in the map, but applies to the data of every layer
+            LayerData::set_compression_level(&mut layers, compression_level);
+        }
+
         // We do not need first GIDs any more
+        let gid_table = GidTable::new(tilesets.iter().map(|ts| ts.first_gid.0).collect());
         let tilesets = tilesets.into_iter().map(|ts| ts.tileset).collect();
 
+        // Maps saved with older Tiled versions don't store the next IDs, so we figure them out
+        let next_layer_id = next_layer_id
+            .unwrap_or_else(|| LayerData::max_layer_id(&layers).map_or(1, |id| id + 1));
+        let next_object_id = next_object_id
+            .unwrap_or_else(|| LayerData::max_object_id(&layers).map_or(1, |id| id + 1));
+
         Ok(Map {
             version: v,
+            tiled_version: tiled_version.flatten(),
             source: map_path.to_owned(),
             orientation: o,
             width: w,
@@ -303,6 +572,13 @@ impl Map {
             background_color: c,
             infinite,
             user_type,
+            compression_level,
+            editor_settings,
+            next_layer_id,
+            next_object_id,
+            warnings: ctx.warnings_since(first_warning),
+            unknown_xml,
+            gid_table,
         })
     }
 }
@@ -311,6 +587,7 @@ impl Map {
 // right/down. Only applies to Staggered and Hexagonal map orientations.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum StaggerIndex {
     Even,
     #[default]
@@ -351,6 +628,7 @@ impl FromStr for StaggerIndex {
 // map orientations.
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum StaggerAxis {
     X,
     #[default]
@@ -390,6 +668,7 @@ impl FromStr for StaggerAxis {
 /// Represents the way tiles are laid out in a map.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum Orientation {
     Orthogonal,
     Isometric,
@@ -0,0 +1,217 @@
+//! Detection of the Tiled features used by a [`Map`].
+
+use std::fmt;
+
+use crate::{LayerData, LayerDataType, Map, ObjectShape, Properties, PropertyValue, Version};
+
+/// A feature of the TMX format that older versions of Tiled don't support.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Feature {
+    /// Layers containing other layers.
+    GroupLayers,
+    /// Objects displaying text.
+    TextObjects,
+    /// Maps without a fixed size, stored in chunks.
+    InfiniteMaps,
+    /// Objects marking a single position.
+    PointObjects,
+    /// Custom properties referring to an object.
+    ObjectProperties,
+    /// Layers with a tint color.
+    TintColors,
+    /// Layers with a parallax factor other than 1.
+    ParallaxFactors,
+    /// Wang sets, which replaced the older terrain sets.
+    WangSets,
+    /// Custom properties whose type is a user defined class.
+    ClassProperties,
+    /// Maps, layers or tilesets with a class.
+    Classes,
+}
+
+impl Feature {
+    /// The oldest version of Tiled that supports this feature.
+    pub fn required_version(&self) -> Version {
+        match self {
+            Feature::GroupLayers | Feature::TextObjects => Version::new(1, 0, 0),
+            Feature::InfiniteMaps | Feature::PointObjects => Version::new(1, 1, 0),
+            Feature::ObjectProperties | Feature::TintColors => Version::new(1, 4, 0),
+            Feature::ParallaxFactors | Feature::WangSets => Version::new(1, 5, 0),
+            Feature::ClassProperties => Version::new(1, 8, 0),
+            Feature::Classes => Version::new(1, 9, 0),
+        }
+    }
+}
+
+impl fmt::Display for Feature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Feature::GroupLayers => "group layers",
+            Feature::TextObjects => "text objects",
+            Feature::InfiniteMaps => "infinite maps",
+            Feature::PointObjects => "point objects",
+            Feature::ObjectProperties => "object properties",
+            Feature::TintColors => "layer tint colors",
+            Feature::ParallaxFactors => "layer parallax factors",
+            Feature::WangSets => "Wang sets",
+            Feature::ClassProperties => "class properties",
+            Feature::Classes => "classes on maps, layers and tilesets",
+        };
+        write!(f, "{} (requires Tiled {})", name, self.required_version())
+    }
+}
+
+/// The features used by a [`Map`] and the versions of Tiled they require, as returned by
+/// [`Map::compatibility()`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CompatibilityReport {
+    features: Vec<Feature>,
+}
+
+impl CompatibilityReport {
+    /// The features used, each appearing once, sorted by the version of Tiled they require.
+    #[inline]
+    pub fn features(&self) -> &[Feature] {
+        &self.features
+    }
+
+    /// The oldest version of Tiled that supports every feature used, or [`None`] if no feature that
+    /// requires a specific version is used.
+    pub fn required_version(&self) -> Option<Version> {
+        self.features.iter().map(Feature::required_version).max()
+    }
+
+    /// Returns the features used that `version` of Tiled does not support.
+    pub fn unsupported_by(&self, version: Version) -> impl Iterator<Item = Feature> + '_ {
+        self.features
+            .iter()
+            .copied()
+            .filter(move |feature| feature.required_version() > version)
+    }
+
+    /// Returns true if `version` of Tiled supports every feature used.
+    #[inline]
+    pub fn is_supported_by(&self, version: Version) -> bool {
+        self.unsupported_by(version).next().is_none()
+    }
+
+    fn add(&mut self, feature: Feature) {
+        if !self.features.contains(&feature) {
+            self.features.push(feature);
+        }
+    }
+}
+
+impl Map {
+    /// Reports which of the features used by this map, its tilesets and its objects require a
+    /// specific version of Tiled, e.g. to make sure that a map can still be opened with the
+    /// version of the editor a team has agreed on.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, Version};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    /// let report = map.compatibility();
+    /// for feature in report.unsupported_by(Version::new(0, 18, 0)) {
+    ///     eprintln!("{} uses {}", map.source.display(), feature);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn compatibility(&self) -> CompatibilityReport {
+        let mut report = CompatibilityReport::default();
+
+        if self.infinite {
+            report.add(Feature::InfiniteMaps);
+        }
+        if self.user_type.is_some() {
+            report.add(Feature::Classes);
+        }
+        scan_properties(&self.properties, &mut report);
+
+        for tileset in &self.tilesets {
+            if tileset.user_type.is_some() {
+                report.add(Feature::Classes);
+            }
+            if !tileset.wang_sets.is_empty() {
+                report.add(Feature::WangSets);
+            }
+            scan_properties(&tileset.properties, &mut report);
+            for wang_set in &tileset.wang_sets {
+                scan_properties(&wang_set.properties, &mut report);
+                for color in &wang_set.wang_colors {
+                    scan_properties(&color.properties, &mut report);
+                }
+            }
+            for (_, tile) in tileset.tiles() {
+                scan_properties(&tile.properties, &mut report);
+                if let Some(collision) = &tile.collision {
+                    for object in &collision.objects {
+                        scan_object_shape(&object.shape, &mut report);
+                        scan_properties(&object.properties, &mut report);
+                    }
+                }
+            }
+        }
+
+        scan_layers(&self.layers, &mut report);
+
+        report
+            .features
+            .sort_by_key(|feature| feature.required_version());
+        report
+    }
+}
+
+fn scan_layers(layers: &[LayerData], report: &mut CompatibilityReport) {
+    for layer in layers {
+        if layer.tint_color.is_some() {
+            report.add(Feature::TintColors);
+        }
+        if layer.parallax_x != 1.0 || layer.parallax_y != 1.0 {
+            report.add(Feature::ParallaxFactors);
+        }
+        if layer.user_type.is_some() {
+            report.add(Feature::Classes);
+        }
+        scan_properties(&layer.properties, report);
+
+        match &layer.layer_type {
+            LayerDataType::Objects(data) => {
+                for object in &data.objects {
+                    scan_object_shape(&object.shape, report);
+                    scan_properties(&object.properties, report);
+                }
+            }
+            LayerDataType::Group(data) => {
+                report.add(Feature::GroupLayers);
+                scan_layers(&data.layers, report);
+            }
+            LayerDataType::Tiles(_) | LayerDataType::Image(_) => {}
+        }
+    }
+}
+
+fn scan_object_shape(shape: &ObjectShape, report: &mut CompatibilityReport) {
+    match shape {
+        ObjectShape::Text { .. } => report.add(Feature::TextObjects),
+        ObjectShape::Point(..) => report.add(Feature::PointObjects),
+        _ => {}
+    }
+}
+
+fn scan_properties(properties: &Properties, report: &mut CompatibilityReport) {
+    for value in properties.values() {
+        match value {
+            PropertyValue::ObjectValue(_) => report.add(Feature::ObjectProperties),
+            PropertyValue::ClassValue { properties, .. } => {
+                report.add(Feature::ClassProperties);
+                scan_properties(properties, report);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Comparison of maps and tilesets regardless of where their files are.
+
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{util::normalize_path, LayerData, LayerDataType, Map, Tileset, TilesetLocation};
+
+impl Map {
+    /// Returns true if both maps have the same contents, regardless of where they were loaded
+    /// from, e.g. when comparing a map with a copy of it checked out on another machine or written
+    /// back by this crate.
+    ///
+    /// [`Map::source`] and the sources of tilesets are ignored, while the paths of images are
+    /// compared relative to the directory of the map or tileset that uses them. The
+    /// [warnings](Map::warnings) found while loading the maps are ignored as well.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let map = loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// let mut copy = map.clone();
+    /// copy.source = "/somewhere/else/tiled_base64_external.tmx".into();
+    ///
+    /// assert!(map != copy);
+    /// assert!(map.content_eq(&copy));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn content_eq(&self, other: &Map) -> bool {
+        self.without_paths() == other.without_paths()
+    }
+
+    /// Returns a copy of the map with the paths [`Map::content_eq()`] ignores removed.
+    fn without_paths(&self) -> Map {
+        let directory = parent(&self.source);
+        let mut map = self.clone();
+        map.source = PathBuf::new();
+        map.warnings.clear();
+        if let Some(target) = map
+            .editor_settings
+            .as_mut()
+            .and_then(|settings| settings.export.as_mut())
+            .and_then(|export| export.target.as_mut())
+        {
+            *target = relative_path(target, directory);
+        }
+        for tileset in &mut map.tilesets {
+            *tileset = Arc::new(tileset.without_paths());
+        }
+        layers_without_paths(&mut map.layers, directory);
+        map
+    }
+}
+
+impl Tileset {
+    /// Returns true if both tilesets have the same contents, regardless of where they were loaded
+    /// from. [`Tileset::source`] is ignored, the paths of images are compared relative to the
+    /// directory of the tileset, and the [warnings](Tileset::warnings) found while loading the
+    /// tilesets are ignored too. See [`Map::content_eq()`] for more information.
+    pub fn content_eq(&self, other: &Tileset) -> bool {
+        self.without_paths() == other.without_paths()
+    }
+
+    /// Returns a copy of the tileset with the paths [`Tileset::content_eq()`] ignores removed.
+    fn without_paths(&self) -> Tileset {
+        let directory = parent(&self.source);
+        let mut tileset = self.clone();
+        tileset.source = PathBuf::new();
+        tileset.warnings.clear();
+        if let Some(image) = &mut tileset.image {
+            image.source = relative_path(&image.source, directory);
+        }
+        for tile in tileset.tiles.values_mut() {
+            if let Some(image) = &mut tile.image {
+                image.source = relative_path(&image.source, directory);
+            }
+        }
+        tileset
+    }
+}
+
+/// Removes the paths [`Map::content_eq()`] ignores from `layers`, whose images are relative to
+/// `directory`.
+fn layers_without_paths(layers: &mut [LayerData], directory: &Path) {
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Image(data) => {
+                if let Some(image) = &mut data.image {
+                    image.source = relative_path(&image.source, directory);
+                }
+            }
+            LayerDataType::Objects(data) => {
+                for object in &mut data.objects {
+                    let mut overridden_tile = None;
+                    if let Some(instance) = &mut object.template {
+                        instance.source = relative_path(&instance.source, directory);
+                        overridden_tile = instance.overrides.tile.as_mut();
+                    }
+                    for tile in object.tile.iter_mut().chain(overridden_tile) {
+                        if let TilesetLocation::Template(tileset) = &mut tile.tileset_location {
+                            *tileset = Arc::new(tileset.without_paths());
+                        }
+                    }
+                }
+            }
+            LayerDataType::Group(data) => layers_without_paths(&mut data.layers, directory),
+            LayerDataType::Tiles(_) => {}
+        }
+    }
+}
+
+pub(super) fn parent(path: &Path) -> &Path {
+    path.parent().unwrap_or_else(|| Path::new(""))
+}
+
+/// Returns `path` relative to `directory`, e.g. `../images/tiles.png` for
+/// `assets/images/tiles.png` relative to `assets/maps`.
+pub(super) fn relative_path(path: &Path, directory: &Path) -> PathBuf {
+    let path = normalize_path(path);
+    let directory = normalize_path(directory);
+    let common = path
+        .components()
+        .zip(directory.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if directory
+        .components()
+        .skip(common)
+        .any(|component| component == Component::ParentDir)
+    {
+        // The directory can't be reached from the path, since its name isn't known
+        return path;
+    }
+
+    let mut relative: PathBuf = directory
+        .components()
+        .skip(common)
+        .map(|_| Component::ParentDir)
+        .collect();
+    relative.extend(path.components().skip(common));
+    relative
+}
@@ -0,0 +1,458 @@
+//! Stable hashes of the contents of maps, layers and tilesets.
+
+use std::path::Path;
+
+use super::content_eq::{parent, relative_path};
+use crate::{
+    Color, Image, Layer, LayerData, LayerDataType, LayerTileData, Map, ObjectData, ObjectShape,
+    Properties, PropertyValue, TileLayerData, Tileset, TilesetLocation,
+};
+
+impl Map {
+    /// Returns a hash of the contents of the map, including its tilesets and layers, e.g. to find
+    /// out which maps of a game have changed since they were last processed, or whether a client
+    /// has the same version of a map as a server.
+    ///
+    /// The hash doesn't depend on how the files were written: Two maps with the same contents have
+    /// the same hash even if their tile data is encoded differently, their attributes are in
+    /// another order or their tilesets are embedded in one and external in the other. Like
+    /// [`Map::content_eq()`], the hash ignores where the files are, the [warnings](Map::warnings)
+    /// found while loading them, the versions of the format and of Tiled they were saved with, the
+    /// next layer and object IDs, and the [unknown XML](crate::UnknownXml) they contain.
+    ///
+    /// The hash is the same across runs and platforms, but may change between versions of this
+    /// crate.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let base64 = loader.load_tmx_map("assets/tiled_base64.tmx")?;
+    /// let csv = loader.load_tmx_map("assets/tiled_csv.tmx")?;
+    ///
+    /// assert_eq!(base64.content_hash(), csv.content_hash());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new(parent(&self.source));
+        hasher.u32(self.orientation as u32);
+        hasher.u32(self.width);
+        hasher.u32(self.height);
+        hasher.u32(self.tile_width);
+        hasher.u32(self.tile_height);
+        hasher.option(&self.hex_side_length, |hasher, length| hasher.i32(*length));
+        hasher.u32(self.stagger_axis as u32);
+        hasher.u32(self.stagger_index as u32);
+        hasher.properties(&self.properties);
+        hasher.option(&self.background_color, ContentHasher::color);
+        hasher.bool(self.infinite);
+        hasher.option(&self.user_type, |hasher, user_type| hasher.str(user_type));
+
+        hasher.len(self.tilesets.len());
+        for tileset in &self.tilesets {
+            hasher.u64(tileset.content_hash());
+        }
+        hasher.len(self.layers.len());
+        for layer in &self.layers {
+            hasher.layer(layer);
+        }
+        hasher.finish()
+    }
+}
+
+impl<'map> Layer<'map> {
+    /// Returns a hash of the contents of the layer, including the layers it contains if it is a
+    /// group, which can be compared with the one of the same layer in another version of the map
+    /// to find out whether it changed. See [`Map::content_hash()`] for more information.
+    ///
+    /// Tiles are hashed with the index of their tileset within the map and their ID in it, so the
+    /// hash doesn't change when one of the tilesets does. Hash the tilesets as well with
+    /// [`Tileset::content_hash()`] to notice these changes.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new(parent(&self.map.source));
+        hasher.layer(self.data);
+        hasher.finish()
+    }
+}
+
+impl Tileset {
+    /// Returns a hash of the contents of the tileset. See [`Map::content_hash()`] for more
+    /// information.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = ContentHasher::new(parent(&self.source));
+        hasher.tileset(self);
+        hasher.finish()
+    }
+}
+
+/// Computes the hashes returned by [`Map::content_hash()`] and the likes using 64-bit FNV-1a,
+/// which unlike [`std::collections::hash_map::DefaultHasher`] is guaranteed to stay the same.
+///
+/// Values are written in a fixed byte order, and collections are prefixed with their length so
+/// that e.g. moving an object from a layer to the next one changes the hash.
+struct ContentHasher<'a> {
+    state: u64,
+    /// The directory the paths of images are made relative to.
+    directory: &'a Path,
+}
+
+impl<'a> ContentHasher<'a> {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    fn new(directory: &'a Path) -> Self {
+        Self {
+            state: Self::OFFSET_BASIS,
+            directory,
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state = (self.state ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.u32(value.to_bits());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.bytes(&[value as u8]);
+    }
+
+    fn len(&mut self, len: usize) {
+        self.u64(len as u64);
+    }
+
+    fn str(&mut self, value: &str) {
+        self.len(value.len());
+        self.bytes(value.as_bytes());
+    }
+
+    fn option<T>(&mut self, value: &Option<T>, hash: impl FnOnce(&mut Self, &T)) {
+        match value {
+            Some(value) => {
+                self.bool(true);
+                hash(self, value);
+            }
+            None => self.bool(false),
+        }
+    }
+
+    fn color(&mut self, color: &Color) {
+        self.bytes(&[color.alpha, color.red, color.green, color.blue]);
+    }
+
+    fn image(&mut self, image: &Image) {
+        let source = relative_path(&image.source, self.directory);
+        self.str(&source.to_string_lossy().replace('\\', "/"));
+        self.i32(image.width);
+        self.i32(image.height);
+        self.option(&image.transparent_colour, Self::color);
+    }
+
+    fn properties(&mut self, properties: &Properties) {
+        let mut properties: Vec<_> = properties.iter().collect();
+        properties.sort_unstable_by_key(|(name, _)| *name);
+        self.len(properties.len());
+        for (name, value) in properties {
+            self.str(name);
+            match value {
+                PropertyValue::BoolValue(value) => {
+                    self.u32(0);
+                    self.bool(*value);
+                }
+                PropertyValue::FloatValue(value) => {
+                    self.u32(1);
+                    self.f32(*value);
+                }
+                PropertyValue::IntValue(value) => {
+                    self.u32(2);
+                    self.i32(*value);
+                }
+                PropertyValue::ColorValue(value) => {
+                    self.u32(3);
+                    self.color(value);
+                }
+                PropertyValue::StringValue(value) => {
+                    self.u32(4);
+                    self.str(value);
+                }
+                PropertyValue::FileValue(value) => {
+                    self.u32(5);
+                    self.str(value);
+                }
+                PropertyValue::ObjectValue(value) => {
+                    self.u32(6);
+                    self.u32(*value);
+                }
+                PropertyValue::ClassValue {
+                    property_type,
+                    properties,
+                } => {
+                    self.u32(7);
+                    self.str(property_type);
+                    self.properties(properties);
+                }
+                PropertyValue::Custom(custom) => {
+                    self.u32(8);
+                    self.str(&custom.type_name);
+                    self.str(&custom.value);
+                }
+            }
+        }
+    }
+
+    fn tileset(&mut self, tileset: &Tileset) {
+        self.str(&tileset.name);
+        self.u32(tileset.tile_width);
+        self.u32(tileset.tile_height);
+        self.u32(tileset.spacing);
+        self.u32(tileset.margin);
+        self.u32(tileset.tilecount);
+        self.u32(tileset.columns);
+        self.i32(tileset.offset_x);
+        self.i32(tileset.offset_y);
+        self.u32(tileset.tile_render_size as u32);
+        self.u32(tileset.fill_mode as u32);
+        self.option(&tileset.image, Self::image);
+
+        let mut tiles: Vec<_> = tileset.tiles.iter().collect();
+        tiles.sort_unstable_by_key(|(id, _)| **id);
+        self.len(tiles.len());
+        for (id, tile) in tiles {
+            self.u32(*id);
+            self.option(&tile.image, Self::image);
+            self.properties(&tile.properties);
+            self.option(&tile.collision, |hasher, collision| {
+                hasher.option(&collision.colour, Self::color);
+                hasher.u32(collision.draw_order as u32);
+                hasher.objects(&collision.objects);
+            });
+            self.option(&tile.animation, |hasher, frames| {
+                hasher.len(frames.len());
+                for frame in frames {
+                    hasher.u32(frame.tile_id);
+                    hasher.u32(frame.duration);
+                }
+            });
+            self.option(&tile.user_type, |hasher, user_type| hasher.str(user_type));
+            self.f32(tile.probability);
+        }
+
+        self.len(tileset.wang_sets.len());
+        for wang_set in &tileset.wang_sets {
+            self.str(&wang_set.name);
+            self.u32(wang_set.wang_set_type as u32);
+            self.option(&wang_set.tile, |hasher, tile| hasher.u32(*tile));
+            self.len(wang_set.wang_colors.len());
+            for color in &wang_set.wang_colors {
+                self.str(&color.name);
+                self.color(&color.color);
+                self.option(&color.tile, |hasher, tile| hasher.u32(*tile));
+                self.f32(color.probability);
+                self.properties(&color.properties);
+            }
+            let mut wang_tiles: Vec<_> = wang_set.wang_tiles.iter().collect();
+            wang_tiles.sort_unstable_by_key(|(id, _)| **id);
+            self.len(wang_tiles.len());
+            for (id, tile) in wang_tiles {
+                self.u32(*id);
+                self.bytes(&tile.wang_id.0);
+            }
+            self.properties(&wang_set.properties);
+        }
+
+        self.properties(&tileset.properties);
+        self.option(&tileset.user_type, |hasher, user_type| {
+            hasher.str(user_type)
+        });
+    }
+
+    fn layer(&mut self, layer: &LayerData) {
+        self.str(&layer.name);
+        self.u32(layer.id);
+        self.bool(layer.visible);
+        self.f32(layer.offset_x);
+        self.f32(layer.offset_y);
+        self.f32(layer.parallax_x);
+        self.f32(layer.parallax_y);
+        self.f32(layer.opacity);
+        self.option(&layer.tint_color, Self::color);
+        self.properties(&layer.properties);
+        self.option(&layer.user_type, |hasher, user_type| hasher.str(user_type));
+
+        match &layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                self.u32(0);
+                self.u32(data.width);
+                self.u32(data.height);
+                // GIDs depend on the tilesets before the one of each tile, so they aren't hashed
+                for i in 0..data.tiles.len() {
+                    self.tile(data.tile_data_at(i).as_ref());
+                }
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                self.u32(1);
+                let mut chunks: Vec<_> = data.chunks.iter().collect();
+                chunks.sort_unstable_by_key(|(position, _)| **position);
+                self.len(chunks.len());
+                for ((x, y), chunk) in chunks {
+                    self.i32(*x);
+                    self.i32(*y);
+                    for tile in chunk.tiles.iter() {
+                        self.tile(tile.as_ref());
+                    }
+                }
+            }
+            LayerDataType::Objects(data) => {
+                self.u32(2);
+                self.option(&data.colour, Self::color);
+                self.u32(data.draw_order as u32);
+                self.objects(&data.objects);
+            }
+            LayerDataType::Image(data) => {
+                self.u32(3);
+                self.option(&data.image, Self::image);
+            }
+            LayerDataType::Group(data) => {
+                self.u32(4);
+                self.len(data.layers.len());
+                for layer in &data.layers {
+                    self.layer(layer);
+                }
+            }
+        }
+    }
+
+    fn tile(&mut self, tile: Option<&LayerTileData>) {
+        match tile {
+            Some(tile) => {
+                self.bool(true);
+                self.len(tile.tileset_index);
+                self.u32(tile.id);
+                self.bytes(&[tile.flip_h as u8, tile.flip_v as u8, tile.flip_d as u8]);
+            }
+            None => self.bool(false),
+        }
+    }
+
+    fn objects(&mut self, objects: &[ObjectData]) {
+        self.len(objects.len());
+        for object in objects {
+            self.u32(object.id);
+            self.option(&object.tile, |hasher, tile| {
+                match &tile.tileset_location {
+                    TilesetLocation::Map(index) => {
+                        hasher.u32(0);
+                        hasher.len(*index);
+                    }
+                    TilesetLocation::Template(tileset) => {
+                        hasher.u32(1);
+                        hasher.u64(tileset.content_hash());
+                    }
+                }
+                hasher.u32(tile.id);
+                hasher.bytes(&[tile.flip_h as u8, tile.flip_v as u8, tile.flip_d as u8]);
+            });
+            self.str(&object.name);
+            self.str(&object.user_type);
+            self.f32(object.x);
+            self.f32(object.y);
+            self.f32(object.rotation);
+            self.bool(object.visible);
+            self.shape(&object.shape);
+            self.properties(&object.properties);
+        }
+    }
+
+    fn shape(&mut self, shape: &ObjectShape) {
+        match shape {
+            ObjectShape::Rect { width, height } => {
+                self.u32(0);
+                self.f32(*width);
+                self.f32(*height);
+            }
+            ObjectShape::Ellipse { width, height } => {
+                self.u32(1);
+                self.f32(*width);
+                self.f32(*height);
+            }
+            ObjectShape::Polyline { points } => {
+                self.u32(2);
+                self.points(points);
+            }
+            ObjectShape::Polygon { points } => {
+                self.u32(3);
+                self.points(points);
+            }
+            ObjectShape::Point(x, y) => {
+                self.u32(4);
+                self.f32(*x);
+                self.f32(*y);
+            }
+            ObjectShape::Text {
+                font_family,
+                pixel_size,
+                wrap,
+                color,
+                bold,
+                italic,
+                underline,
+                strikeout,
+                kerning,
+                halign,
+                valign,
+                text,
+                width,
+                height,
+            } => {
+                self.u32(5);
+                self.str(font_family);
+                self.len(*pixel_size);
+                self.bytes(&[
+                    *wrap as u8,
+                    *bold as u8,
+                    *italic as u8,
+                    *underline as u8,
+                    *strikeout as u8,
+                    *kerning as u8,
+                ]);
+                self.color(color);
+                self.u32(*halign as u32);
+                self.u32(*valign as u32);
+                self.str(text);
+                self.f32(*width);
+                self.f32(*height);
+            }
+        }
+    }
+
+    fn points(&mut self, points: &[(f32, f32)]) {
+        self.len(points.len());
+        for (x, y) in points {
+            self.f32(*x);
+            self.f32(*y);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+//! Sorting tiles and objects in the order they are drawn in.
+
+use std::cmp::Ordering;
+
+use super::nav::is_shifted;
+use crate::{Map, Object, ObjectData, ObjectShape, Orientation, StaggerAxis};
+
+/// What [`Map::depth_key()`] gives the key of: the tile at a position, in tiles, or an object.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthItem<'a> {
+    /// The tile at this position of a tile layer.
+    Tile(i32, i32),
+    /// An object of an object layer.
+    Object(&'a ObjectData),
+}
+
+impl From<(i32, i32)> for DepthItem<'_> {
+    fn from((x, y): (i32, i32)) -> Self {
+        Self::Tile(x, y)
+    }
+}
+
+impl<'a> From<&'a ObjectData> for DepthItem<'a> {
+    fn from(object: &'a ObjectData) -> Self {
+        Self::Object(object)
+    }
+}
+
+impl<'a> From<Object<'a>> for DepthItem<'a> {
+    fn from(object: Object<'a>) -> Self {
+        Self::Object(object.data)
+    }
+}
+
+/// The key tiles and objects are sorted by to be drawn from back to front, as returned by
+/// [`Map::depth_key()`].
+///
+/// Keys are the position on screen, in pixels, of the point items stand on: the bottom of tiles
+/// and of the shapes of objects. They compare by their vertical position first, then by their
+/// horizontal one.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct DepthKey {
+    /// The vertical position on screen, larger for items in front of others.
+    pub y: f32,
+    /// The horizontal position on screen, used to order items with the same depth.
+    pub x: f32,
+}
+
+impl PartialEq for DepthKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for DepthKey {}
+
+impl PartialOrd for DepthKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DepthKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.y.total_cmp(&other.y).then(self.x.total_cmp(&other.x))
+    }
+}
+
+impl Map {
+    /// Returns the key to sort a tile or an object by so that sorting them all draws the ones in
+    /// front last, e.g. to draw the tiles and objects of an isometric scene together. Tiles sort
+    /// in the order Tiled draws them in, whatever the orientation of the map, and objects are
+    /// placed among them according to where they stand.
+    ///
+    /// Objects stand on the bottom of their shape, taking their rotation into account, and tile
+    /// objects on the point they are anchored at, which is the bottom of their tile. Layer
+    /// offsets aren't taken into account.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{DepthItem, Loader};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_template.tmx")?;
+    /// let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    ///
+    /// let mut items: Vec<DepthItem> = objects.object_data().iter().map(DepthItem::from).collect();
+    /// for y in 0..map.height as i32 {
+    ///     for x in 0..map.width as i32 {
+    ///         items.push(DepthItem::Tile(x, y));
+    ///     }
+    /// }
+    /// items.sort_by_key(|item| map.depth_key(*item));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn depth_key<'a>(&self, item: impl Into<DepthItem<'a>>) -> DepthKey {
+        let object = match item.into() {
+            DepthItem::Tile(x, y) => return self.tile_depth_key(x, y),
+            DepthItem::Object(object) => object,
+        };
+        object_points(object)
+            .into_iter()
+            .map(|(x, y)| {
+                if self.orientation == Orientation::Isometric && self.tile_height > 0 {
+                    // Objects of isometric maps are positioned along the axes of the tiles, both
+                    // of which are measured in tile heights
+                    let (tile_x, tile_y) =
+                        (x / self.tile_height as f32, y / self.tile_height as f32);
+                    DepthKey {
+                        y: (tile_x + tile_y) * self.tile_height as f32 / 2.,
+                        x: (tile_x - tile_y) * self.tile_width as f32 / 2.,
+                    }
+                } else {
+                    DepthKey { y, x }
+                }
+            })
+            .max()
+            .unwrap_or(DepthKey {
+                y: object.y,
+                x: object.x,
+            })
+    }
+
+    /// The position on screen of the bottom left corner of the tile at the given position, or of
+    /// its bottom corner in isometric maps.
+    fn tile_depth_key(&self, x: i32, y: i32) -> DepthKey {
+        let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+        let (fx, fy) = (x as f32, y as f32);
+        match self.orientation {
+            Orientation::Orthogonal => DepthKey {
+                y: (fy + 1.) * tile_height,
+                x: fx * tile_width,
+            },
+            Orientation::Isometric => DepthKey {
+                y: (fx + fy + 2.) * tile_height / 2.,
+                x: (fx - fy) * tile_width / 2.,
+            },
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let side = if self.orientation == Orientation::Hexagonal {
+                    self.hex_side_length.unwrap_or(0) as f32
+                } else {
+                    0.
+                };
+                match self.stagger_axis {
+                    StaggerAxis::X => {
+                        let shift = if is_shifted(self.stagger_index, x) {
+                            tile_height / 2.
+                        } else {
+                            0.
+                        };
+                        DepthKey {
+                            y: fy * tile_height + shift + tile_height,
+                            x: fx * (tile_width + side) / 2.,
+                        }
+                    }
+                    StaggerAxis::Y => {
+                        let shift = if is_shifted(self.stagger_index, y) {
+                            tile_width / 2.
+                        } else {
+                            0.
+                        };
+                        DepthKey {
+                            y: fy * (tile_height + side) / 2. + tile_height,
+                            x: fx * tile_width + shift,
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the points the bottom of the object can be at, in the coordinates objects are
+/// positioned with.
+fn object_points(object: &ObjectData) -> Vec<(f32, f32)> {
+    if object.tile.is_some() {
+        return vec![(object.x, object.y)];
+    }
+    let points = match &object.shape {
+        ObjectShape::Rect { width, height }
+        | ObjectShape::Ellipse { width, height }
+        | ObjectShape::Text { width, height, .. } => {
+            vec![(0., 0.), (*width, 0.), (0., *height), (*width, *height)]
+        }
+        ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => points.clone(),
+        ObjectShape::Point(_, _) => vec![(0., 0.)],
+    };
+
+    // Shapes rotate clockwise around the position of the object
+    let (sin, cos) = object.rotation.to_radians().sin_cos();
+    points
+        .into_iter()
+        .map(|(x, y)| (object.x + x * cos - y * sin, object.y + x * sin + y * cos))
+        .collect()
+}
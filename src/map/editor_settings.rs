@@ -0,0 +1,78 @@
+//! The settings Tiled stores in a map for its own use, found in `<editorsettings>`.
+
+use std::path::{Path, PathBuf};
+
+use xml::attribute::OwnedAttribute;
+
+use crate::{
+    parse::ParseContext,
+    util::{get_attrs, parse_tag, XmlEventResult},
+    Error, Result,
+};
+
+use super::content_eq::parent;
+
+/// The editor settings of a [`Map`](crate::Map), which Tiled uses for infinite maps and for the
+/// "Export" action. See [`Map::editor_settings`](crate::Map::editor_settings).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct EditorSettings {
+    /// The width and height, in tiles, of the chunks Tiled stores the layers of infinite maps in,
+    /// or `None` if the map uses the default of 16x16.
+    pub chunk_size: Option<(u32, u32)>,
+    /// Where and how the map was last exported, or `None` if it never was.
+    pub export: Option<ExportSettings>,
+}
+
+/// Where and how Tiled exports a map, as found in its [`EditorSettings`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct ExportSettings {
+    /// The file the map is exported to, relative to the working directory like every other path
+    /// of this crate, or `None` if not set.
+    pub target: Option<PathBuf>,
+    /// The name of the format the map is exported in, such as `json` or `lua`, if set.
+    pub format: Option<String>,
+}
+
+impl EditorSettings {
+    pub(crate) fn parse(
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+        map_path: &Path,
+        ctx: &mut ParseContext,
+    ) -> Result<EditorSettings> {
+        let mut settings = EditorSettings::default();
+        parse_tag!(parser, ctx, "editorsettings", {
+            "chunksize" => |attrs: Vec<OwnedAttribute>| {
+                let (width, height) = get_attrs!(
+                    for v in attrs {
+                        "width" => width ?= v.parse::<u32>(),
+                        "height" => height ?= v.parse::<u32>(),
+                    }
+                    (width, height)
+                );
+                settings.chunk_size = Some((width, height));
+                Ok(())
+            },
+            "export" => |attrs: Vec<OwnedAttribute>| {
+                let (target, format) = get_attrs!(
+                    for v in attrs {
+                        Some("target") => target = v,
+                        Some("format") => format = v,
+                    }
+                    (target, format)
+                );
+                // Export targets are written relative to the map by Tiled, and aren't read by this
+                // crate, so path policies don't apply to them
+                settings.export = Some(ExportSettings {
+                    target: target.map(|target| parent(map_path).join(target)),
+                    format,
+                });
+                Ok(())
+            },
+        });
+        Ok(settings)
+    }
+}
@@ -0,0 +1,101 @@
+//! Filling connected areas of tile layers, like the bucket fill tool of Tiled.
+
+use std::collections::VecDeque;
+
+use super::{layer_edit::find_in, nav::neighbor_offsets};
+use crate::{EditError, EditTransaction, LayerDataType, LayerTileData, TileLayerData};
+
+impl EditTransaction<'_> {
+    /// Replaces the tile at the given position of the tile layer with ID `layer`, along with all
+    /// the tiles connected to it that are the same, with `tile`, or empties them if `tile` is
+    /// [`None`]. Returns the number of tiles changed, which are recorded like
+    /// [`EditTransaction::set_tile()`] does.
+    ///
+    /// Tiles are the same if they have the same tileset, ID and flipping. They are connected
+    /// through the neighbors of each cell, which depend on the orientation of the map as
+    /// described in [`NavGrid`](crate::NavGrid), e.g. the 6 surrounding cells in hexagonal maps.
+    ///
+    /// The fill stays within finite layers. In infinite layers, it stays within the smallest
+    /// rectangle containing the chunks of all the tile layers of the map, and allocates the
+    /// chunks needed to fill the empty cells of that area. Fails if the position is outside of
+    /// these bounds, or for the same reasons as [`EditTransaction::set_tile()`], in which case
+    /// nothing is changed.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{LayerTileData, Loader};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// let layer = map.get_layer(0).unwrap().id();
+    ///
+    /// let mut transaction = map.edit();
+    /// let filled = transaction.flood_fill(layer, 0, 0, Some(LayerTileData::new(0, 5)))?;
+    /// assert!(filled > 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn flood_fill(
+        &mut self,
+        layer: u32,
+        x: i32,
+        y: i32,
+        tile: Option<LayerTileData>,
+    ) -> Result<usize, EditError> {
+        let region = {
+            let map = self.map();
+            let data = match find_in(&map.layers, layer).map(|data| &data.layer_type) {
+                Some(LayerDataType::Tiles(data)) => data,
+                Some(_) => return Err(EditError::WrongLayerType(layer)),
+                None => return Err(EditError::LayerNotFound(layer)),
+            };
+            let ((left, top), (width, height)) = match data {
+                TileLayerData::Finite(data) => ((0, 0), (data.width(), data.height())),
+                TileLayerData::Infinite(_) => map.tile_bounds(),
+            };
+            let index = |x: i32, y: i32| {
+                let (x, y) = (x - left, y - top);
+                if x < 0 || y < 0 || x as u32 >= width || y as u32 >= height {
+                    None
+                } else {
+                    Some(y as usize * width as usize + x as usize)
+                }
+            };
+            let tile_at = |x, y| match data {
+                TileLayerData::Finite(data) => data.get_tile_data(x, y),
+                TileLayerData::Infinite(data) => data.get_tile_data(x, y),
+            };
+
+            let start = index(x, y).ok_or(EditError::OutOfBounds { layer, x, y })?;
+            let target = tile_at(x, y);
+            if target == tile {
+                return Ok(0);
+            }
+
+            let mut visited = vec![false; width as usize * height as usize];
+            visited[start] = true;
+            let mut queue = VecDeque::from([(x, y)]);
+            let mut region = Vec::new();
+            while let Some((x, y)) = queue.pop_front() {
+                region.push((x, y));
+                for (dx, dy) in
+                    neighbor_offsets(map.orientation, map.stagger_axis, map.stagger_index, x, y)
+                {
+                    let (x, y) = (x + dx, y + dy);
+                    if let Some(index) = index(x, y) {
+                        if !visited[index] && tile_at(x, y) == target {
+                            visited[index] = true;
+                            queue.push_back((x, y));
+                        }
+                    }
+                }
+            }
+            region
+        };
+
+        for &(x, y) in &region {
+            self.set_tile(layer, x, y, tile)?;
+        }
+        Ok(region.len())
+    }
+}
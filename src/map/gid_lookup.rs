@@ -0,0 +1,164 @@
+//! Resolving the GIDs of a [`Map`] to the tiles they refer to without searching the tilesets.
+
+use std::sync::OnceLock;
+
+use crate::{canonical_first_gids, Gid, LayerTile, LayerTileData, Map};
+
+/// The first GID of each tileset of a map, along with the range of GIDs of each tileset, which
+/// are computed the first time a GID is resolved.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct GidTable {
+    /// The first GIDs the map file gave to its tilesets, or [`None`] once the tilesets of the map
+    /// have changed, in which case they get the GIDs of a freshly saved map.
+    file_first_gids: Option<Vec<u32>>,
+    resolved: OnceLock<ResolvedGids>,
+}
+
+#[derive(Debug, Clone)]
+struct ResolvedGids {
+    first_gids: Vec<u32>,
+    /// The GIDs belonging to each tileset, sorted and without overlaps.
+    ranges: Vec<GidRange>,
+}
+
+/// The GIDs from `start` up to `end`, excluded, which belong to the tileset at `tileset`.
+#[derive(Debug, Clone, Copy)]
+struct GidRange {
+    start: u64,
+    end: u64,
+    tileset: usize,
+}
+
+impl GidTable {
+    pub(crate) fn new(first_gids: Vec<u32>) -> Self {
+        Self {
+            file_first_gids: Some(first_gids),
+            resolved: OnceLock::new(),
+        }
+    }
+
+    /// Forgets the ranges of GIDs of the tilesets, after the tile counts of tilesets changed.
+    pub(crate) fn clear_table(&mut self) {
+        self.resolved = OnceLock::new();
+    }
+}
+
+/// The table is a cache, which doesn't make maps any different.
+impl PartialEq for GidTable {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Map {
+    /// Returns the tile a GID refers to, along with the flipping bits stored in it, or [`None`]
+    /// for the empty GID 0 and for GIDs past the last tile of the last tileset.
+    ///
+    /// GIDs are numbered as in the map file, such as in tile data decoded separately or in the
+    /// `gid` of tile objects. Once tilesets have been removed or replaced with
+    /// [`Map::prune_unused_tilesets()`] or [`Map::replace_tileset()`], they are numbered like Tiled
+    /// would when saving the map instead.
+    ///
+    /// Tiles of tilesets whose loading was deferred with
+    /// [`LoaderOptions::defer_tilesets`](crate::LoaderOptions::defer_tilesets) are returned too,
+    /// with every GID from the first GID of their tileset up to the next tileset's belonging to
+    /// them until they are resolved.
+    ///
+    /// The first call sorts the ranges of GIDs of the tilesets of the map, so that afterwards this
+    /// is a binary search through a list of a few bytes per tileset, however large the GIDs of
+    /// the map are. Renderers resolving every tile of every frame this way avoid going through
+    /// the tilesets each time.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    ///
+    /// // The sixth tile of the first tileset, flipped horizontally
+    /// let tile = map.tile_for_gid(0x8000_0006).unwrap();
+    /// assert_eq!((tile.tileset_index(), tile.id()), (0, 5));
+    /// assert!(tile.flip_h);
+    /// assert!(map.tile_for_gid(0).is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_for_gid(&self, gid: u32) -> Option<LayerTile<'_>> {
+        let resolved = self.gid_table.resolved.get_or_init(|| {
+            let first_gids = match &self.gid_table.file_first_gids {
+                Some(first_gids) => first_gids.clone(),
+                None => canonical_first_gids(&self.tilesets),
+            };
+            let ranges = build_ranges(&first_gids, self);
+            ResolvedGids { first_gids, ranges }
+        });
+        let data = LayerTileData::from_bits_with(gid, |gid| {
+            let gid = u64::from(gid.0);
+            let range = resolved.ranges
+                [..resolved.ranges.partition_point(|range| range.start <= gid)]
+                .last()
+                .filter(|range| gid < range.end)?;
+            Some((range.tileset, Gid(resolved.first_gids[range.tileset])))
+        })?;
+        Some(LayerTile::new(self, data))
+    }
+
+    /// Forgets the GIDs the map file gave to the tilesets of the map, after they have changed.
+    pub(crate) fn reset_gids(&mut self) {
+        self.gid_table = GidTable::default();
+    }
+}
+
+fn build_ranges(first_gids: &[u32], map: &Map) -> Vec<GidRange> {
+    let mut ranges: Vec<GidRange> = Vec::new();
+    for (index, (&first_gid, tileset)) in first_gids.iter().zip(map.tilesets()).enumerate() {
+        let start = u64::from(first_gid);
+        let end = if tileset.is_pending() {
+            // Pending tilesets get every GID up to the next tileset, and the tile count of a
+            // pending last tileset isn't known, so its GIDs don't end
+            first_gids
+                .get(index + 1)
+                .map_or(u64::MAX, |&next| u64::from(next).max(start))
+        } else {
+            // Image collection tilesets can have tiles with IDs past their tile count
+            let last_id = tileset
+                .tiles()
+                .map(|(id, _)| u64::from(id) + 1)
+                .max()
+                .unwrap_or(0);
+            start + u64::from(tileset.tilecount).max(last_id)
+        };
+        if start >= end {
+            continue;
+        }
+        // Tilesets further in the list take precedence over the previous ones they overlap
+        let mut kept = Vec::with_capacity(ranges.len() + 2);
+        for range in ranges {
+            if range.end <= start || end <= range.start {
+                kept.push(range);
+                continue;
+            }
+            if range.start < start {
+                kept.push(GidRange {
+                    end: start,
+                    ..range
+                });
+            }
+            if end < range.end {
+                kept.push(GidRange {
+                    start: end,
+                    ..range
+                });
+            }
+        }
+        kept.push(GidRange {
+            start,
+            end,
+            tileset: index,
+        });
+        kept.sort_by_key(|range| range.start);
+        ranges = kept;
+    }
+    ranges
+}
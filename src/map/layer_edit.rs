@@ -0,0 +1,190 @@
+//! Editing of the layer hierarchy of a [`Map`].
+
+use std::collections::HashSet;
+
+use crate::{LayerData, LayerDataType, LayerEditError, Map};
+
+type EditResult<T> = std::result::Result<T, LayerEditError>;
+
+impl Map {
+    /// Inserts a layer at position `index` within the group layer with ID `parent`, or within the
+    /// top-level layers of the map if `parent` is [`None`]. Returns the ID of the inserted layer.
+    ///
+    /// Layer IDs are kept unique within the map: If the layer (or any of its children, if it is a
+    /// group) has an ID of 0 or an ID already present in the map, it is given a new one. Layers
+    /// created with [`LayerBuilder`](crate::LayerBuilder) for this map keep their IDs.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{LayerBuilder, Loader};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    /// let group = LayerBuilder::group("decoration", vec![]).build(&mut map);
+    /// let group_id = map.insert_layer(None, 0, group)?;
+    ///
+    /// let layer = LayerBuilder::tiles("flowers").build(&mut map);
+    /// let layer_id = map.insert_layer(Some(group_id), 0, layer)?;
+    ///
+    /// let group = map.get_layer(0).unwrap().as_group_layer().unwrap();
+    /// assert_eq!(group.get_layer(0).unwrap().id(), layer_id);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn insert_layer(
+        &mut self,
+        parent: Option<u32>,
+        index: usize,
+        mut layer: LayerData,
+    ) -> EditResult<u32> {
+        let list_len = self.layer_list_mut(parent)?.len();
+        if index > list_len {
+            return Err(LayerEditError::IndexOutOfBounds(index));
+        }
+
+        self.assign_unique_ids(&mut layer);
+        let id = layer.id;
+        self.layer_list_mut(parent)?.insert(index, layer);
+        Ok(id)
+    }
+
+    /// Appends a layer to the top-level layers of the map, so that it is displayed above all
+    /// others. Returns the ID of the inserted layer.
+    ///
+    /// See [`Map::insert_layer()`] for how layer IDs are handled.
+    pub fn push_layer(&mut self, layer: LayerData) -> u32 {
+        let index = self.layers.len();
+        self.insert_layer(None, index, layer)
+            .expect("the top-level layer list always exists")
+    }
+
+    /// Removes the layer with the specified ID from wherever it is in the map, returning it.
+    pub fn remove_layer(&mut self, id: u32) -> EditResult<LayerData> {
+        remove_from(&mut self.layers, id).ok_or(LayerEditError::LayerNotFound(id))
+    }
+
+    /// Moves the layer with the specified ID to position `index` within the group layer with ID
+    /// `parent`, or within the top-level layers of the map if `parent` is [`None`]. The layer keeps
+    /// its ID.
+    ///
+    /// `index` refers to the position in the target list once the layer has been taken out of its
+    /// current one. Nothing is changed if an error is returned.
+    pub fn move_layer(&mut self, id: u32, parent: Option<u32>, index: usize) -> EditResult<()> {
+        let moved = find_in(&self.layers, id).ok_or(LayerEditError::LayerNotFound(id))?;
+        if let Some(parent) = parent {
+            if parent == id || find_in(children_of(moved), parent).is_some() {
+                return Err(LayerEditError::MoveIntoItself(id));
+            }
+        }
+
+        // Validate the destination before taking the layer out.
+        let mut list_len = self.layer_list(parent)?.len();
+        if self.layer_list(parent)?.iter().any(|layer| layer.id == id) {
+            list_len -= 1;
+        }
+        if index > list_len {
+            return Err(LayerEditError::IndexOutOfBounds(index));
+        }
+
+        let layer = self.remove_layer(id)?;
+        self.layer_list_mut(parent)?.insert(index, layer);
+        Ok(())
+    }
+
+    fn layer_list(&self, parent: Option<u32>) -> EditResult<&Vec<LayerData>> {
+        match parent {
+            None => Ok(&self.layers),
+            Some(parent) => match find_in(&self.layers, parent) {
+                Some(LayerData {
+                    layer_type: LayerDataType::Group(group),
+                    ..
+                }) => Ok(&group.layers),
+                Some(_) => Err(LayerEditError::NotAGroup(parent)),
+                None => Err(LayerEditError::LayerNotFound(parent)),
+            },
+        }
+    }
+
+    fn layer_list_mut(&mut self, parent: Option<u32>) -> EditResult<&mut Vec<LayerData>> {
+        match parent {
+            None => Ok(&mut self.layers),
+            Some(parent) => match find_in_mut(&mut self.layers, parent) {
+                Some(LayerData {
+                    layer_type: LayerDataType::Group(group),
+                    ..
+                }) => Ok(&mut group.layers),
+                Some(_) => Err(LayerEditError::NotAGroup(parent)),
+                None => Err(LayerEditError::LayerNotFound(parent)),
+            },
+        }
+    }
+
+    fn assign_unique_ids(&mut self, layer: &mut LayerData) {
+        fn collect_ids(layers: &[LayerData], ids: &mut HashSet<u32>) {
+            for layer in layers {
+                ids.insert(layer.id);
+                collect_ids(children_of(layer), ids);
+            }
+        }
+
+        fn assign(map: &mut Map, layer: &mut LayerData, ids: &mut HashSet<u32>) {
+            if layer.id == 0 || ids.contains(&layer.id) {
+                layer.id = map.allocate_layer_id();
+            } else if layer.id >= map.next_layer_id {
+                map.next_layer_id = layer.id + 1;
+            }
+            ids.insert(layer.id);
+
+            if let LayerDataType::Group(group) = &mut layer.layer_type {
+                for child in &mut group.layers {
+                    assign(map, child, ids);
+                }
+            }
+        }
+
+        let mut ids = HashSet::new();
+        collect_ids(&self.layers, &mut ids);
+        assign(self, layer, &mut ids);
+    }
+}
+
+fn children_of(layer: &LayerData) -> &[LayerData] {
+    match &layer.layer_type {
+        LayerDataType::Group(group) => &group.layers,
+        _ => &[],
+    }
+}
+
+pub(crate) fn find_in(layers: &[LayerData], id: u32) -> Option<&LayerData> {
+    layers.iter().find_map(|layer| {
+        if layer.id == id {
+            Some(layer)
+        } else {
+            find_in(children_of(layer), id)
+        }
+    })
+}
+
+pub(crate) fn find_in_mut(layers: &mut [LayerData], id: u32) -> Option<&mut LayerData> {
+    layers.iter_mut().find_map(|layer| {
+        if layer.id == id {
+            Some(layer)
+        } else if let LayerDataType::Group(group) = &mut layer.layer_type {
+            find_in_mut(&mut group.layers, id)
+        } else {
+            None
+        }
+    })
+}
+
+fn remove_from(layers: &mut Vec<LayerData>, id: u32) -> Option<LayerData> {
+    if let Some(index) = layers.iter().position(|layer| layer.id == id) {
+        return Some(layers.remove(index));
+    }
+    layers
+        .iter_mut()
+        .find_map(|layer| match &mut layer.layer_type {
+            LayerDataType::Group(group) => remove_from(&mut group.layers, id),
+            _ => None,
+        })
+}
@@ -0,0 +1,89 @@
+//! Finding the layers of a [`Map`] by ID, by name or by class.
+
+use crate::{Layer, LayerData, LayerDataType, Map};
+
+use super::layer_edit::find_in;
+
+impl Map {
+    /// Returns the layer with the ID given, looking within group layers too.
+    ///
+    /// Unlike indices, IDs don't change when layers are reordered, so they are a more robust way
+    /// of referring to layers.
+    pub fn layer_by_id(&self, id: u32) -> Option<Layer<'_>> {
+        find_in(&self.layers, id).map(|data| Layer::new(self, data))
+    }
+
+    /// Returns the layer found by following `path`, a list of layer names separated by slashes,
+    /// from the top-level layers of the map through nested group layers, such as
+    /// `World/Decoration/Foreground` for the `Foreground` layer of the `Decoration` group within
+    /// the `World` group.
+    ///
+    /// If several layers at some level have the same name, they are tried in display order and the
+    /// first that leads to a layer is returned. Layers whose name contains a slash can't be found
+    /// this way.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    ///
+    /// let layer = map.layer_by_path("group-2/group-3/tile-3").unwrap();
+    /// assert_eq!(map.layer_by_id(layer.id()).unwrap().name, "tile-3");
+    /// assert!(map.layer_by_path("group-2/tile-3").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layer_by_path(&self, path: &str) -> Option<Layer<'_>> {
+        let names: Vec<&str> = path.split('/').collect();
+        find_by_path(&self.layers, &names).map(|data| Layer::new(self, data))
+    }
+
+    /// Returns an iterator over the layers whose [type](LayerData::user_type), called class since
+    /// Tiled 1.9, is `class`, including group layers and the layers within them, in display order.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_group_layers.tmx")?;
+    /// for layer in map.layers_with_class("collision") {
+    ///     println!("Building colliders for {}", layer.name);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layers_with_class<'map>(
+        &'map self,
+        class: &str,
+    ) -> impl Iterator<Item = Layer<'map>> + 'map {
+        let mut layers = Vec::new();
+        collect_with_class(&self.layers, class, &mut layers);
+        layers.into_iter().map(move |data| Layer::new(self, data))
+    }
+}
+
+fn collect_with_class<'a>(layers: &'a [LayerData], class: &str, found: &mut Vec<&'a LayerData>) {
+    for layer in layers {
+        if layer.user_type.as_deref() == Some(class) {
+            found.push(layer);
+        }
+        if let LayerDataType::Group(group) = &layer.layer_type {
+            collect_with_class(&group.layers, class, found);
+        }
+    }
+}
+
+fn find_by_path<'a>(layers: &'a [LayerData], names: &[&str]) -> Option<&'a LayerData> {
+    let (name, rest) = names.split_first()?;
+    layers
+        .iter()
+        .filter(|layer| layer.name == *name)
+        .find_map(|layer| match (&layer.layer_type, rest) {
+            (_, []) => Some(layer),
+            (LayerDataType::Group(group), rest) => find_by_path(&group.layers, rest),
+            _ => None,
+        })
+}
@@ -0,0 +1,290 @@
+//! Copying the contents of a map into another one.
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    ChunkData, FiniteTileLayerData, InfiniteTileLayerData, LayerData, LayerDataType, LayerTileData,
+    Map, ObjectShape, Properties, PropertyValue, TileLayerData, Tileset, TilesetLocation,
+};
+
+/// Where [`Map::merge_at()`] places the contents of the merged map.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MergeOffset {
+    /// Move the tiles of the merged map by this amount of tiles, and its objects and image
+    /// layers by the matching amount of pixels.
+    Tiles(i32, i32),
+    /// Add this amount of pixels to the offset of each merged layer, leaving its contents where
+    /// they are. Useful when the maps aren't aligned on tiles, like maps of a world can be.
+    Pixels(f32, f32),
+}
+
+impl Map {
+    /// Copies the layers of `other`, along with the objects and tilesets they use, into this
+    /// map, above all of its layers. Returns the IDs the top-level layers of `other` were given.
+    ///
+    /// The merged contents are placed according to `offset`. Their tile layers become finite or
+    /// infinite like the ones of this map; in finite maps, the tiles that end up outside of the
+    /// map are left out.
+    ///
+    /// Tilesets already in this map are shared instead of being added again, and the tiles of the
+    /// merged layers are encoded with the GIDs of the resulting list. Layers and objects are given
+    /// IDs that are unused in this map, and object properties referring to the merged objects
+    /// follow them. Merged layers and tilesets whose names are already taken by different ones in
+    /// this map are renamed by appending a number, as in `ground 2`.
+    ///
+    /// The properties of `other` itself aren't merged.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, MergeOffset};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let mut map = loader.load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")?;
+    /// let other = loader.load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")?;
+    ///
+    /// // Place a copy of the map to the right of itself
+    /// let ids = map.merge_at(&other, MergeOffset::Tiles(100, 0));
+    /// assert_eq!(ids.len(), other.layers().len());
+    /// assert_eq!(map.tilesets().len(), other.tilesets().len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn merge_at(&mut self, other: &Map, offset: MergeOffset) -> Vec<u32> {
+        let tileset_indices: Vec<usize> = other
+            .tilesets
+            .iter()
+            .map(|tileset| self.merge_tileset(tileset))
+            .collect();
+        self.reset_gids();
+
+        let (tile_offset, pixel_offset) = match offset {
+            MergeOffset::Tiles(x, y) => ((x, y), self.pixel_offset(x, y)),
+            MergeOffset::Pixels(_, _) => ((0, 0), (0., 0.)),
+        };
+        let context = MergeContext {
+            tileset_indices,
+            tile_offset,
+            pixel_offset,
+        };
+
+        let mut names = HashSet::new();
+        collect_names(&self.layers, &mut names);
+        let mut object_ids = HashMap::new();
+        let mut layers = other.layers.clone();
+        for layer in &mut layers {
+            self.merge_layer(layer, &context, &mut names, &mut object_ids);
+        }
+        remap_object_properties(&mut layers, &object_ids);
+
+        layers
+            .into_iter()
+            .map(|mut layer| {
+                if let MergeOffset::Pixels(x, y) = offset {
+                    layer.offset_x += x;
+                    layer.offset_y += y;
+                }
+                self.push_layer(layer)
+            })
+            .collect()
+    }
+
+    /// Returns the index of `tileset` within the tilesets of the map, adding it if it isn't
+    /// there already.
+    fn merge_tileset(&mut self, tileset: &Arc<Tileset>) -> usize {
+        if let Some(index) = self
+            .tilesets
+            .iter()
+            .position(|existing| Arc::ptr_eq(existing, tileset) || existing == tileset)
+        {
+            return index;
+        }
+
+        let names: HashSet<String> = self.tilesets.iter().map(|t| t.name.clone()).collect();
+        let tileset = if names.contains(&tileset.name) {
+            let mut renamed = Tileset::clone(tileset);
+            renamed.name = unique_name(&tileset.name, &names);
+            Arc::new(renamed)
+        } else {
+            tileset.clone()
+        };
+        self.tilesets.push(tileset);
+        self.tilesets.len() - 1
+    }
+
+    /// Prepares a layer of another map to be part of this one.
+    fn merge_layer(
+        &mut self,
+        layer: &mut LayerData,
+        context: &MergeContext,
+        names: &mut HashSet<String>,
+        object_ids: &mut HashMap<u32, u32>,
+    ) {
+        if names.contains(&layer.name) {
+            layer.name = unique_name(&layer.name, names);
+        }
+        names.insert(layer.name.clone());
+        // Given a new ID once added to the map
+        layer.id = 0;
+
+        let (offset_x, offset_y) = context.pixel_offset;
+        match &mut layer.layer_type {
+            LayerDataType::Tiles(data) => *data = self.merge_tiles(data, context),
+            LayerDataType::Objects(data) => {
+                for object in &mut data.objects {
+                    let id = self.allocate_object_id();
+                    object_ids.insert(object.id, id);
+                    object.id = id;
+                    object.x += offset_x;
+                    object.y += offset_y;
+                    if let ObjectShape::Point(x, y) = &mut object.shape {
+                        *x += offset_x;
+                        *y += offset_y;
+                    }
+
+                    let overridden_tile = object
+                        .template
+                        .as_mut()
+                        .and_then(|instance| instance.overrides.tile.as_mut());
+                    for tile in object.tile.iter_mut().chain(overridden_tile) {
+                        if let TilesetLocation::Map(index) = &mut tile.tileset_location {
+                            *index = context.tileset_indices[*index];
+                        }
+                    }
+                }
+            }
+            LayerDataType::Image(_) => {
+                layer.offset_x += offset_x;
+                layer.offset_y += offset_y;
+            }
+            LayerDataType::Group(data) => {
+                for child in &mut data.layers {
+                    self.merge_layer(child, context, names, object_ids);
+                }
+            }
+        }
+    }
+
+    /// Returns the tiles of a tile layer of another map moved into this one, stored like the tile
+    /// layers of this map.
+    fn merge_tiles(&self, data: &TileLayerData, context: &MergeContext) -> TileLayerData {
+        let (offset_x, offset_y) = context.tile_offset;
+        let mut tiles = Vec::new();
+        let mut push = |x: i32, y: i32, tile: LayerTileData| {
+            let tile = LayerTileData {
+                tileset_index: context.tileset_indices[tile.tileset_index],
+                ..tile
+            };
+            tiles.push((x + offset_x, y + offset_y, tile));
+        };
+        let encoding = match data {
+            TileLayerData::Finite(data) => {
+                for index in 0..data.tiles.len() {
+                    if let Some(tile) = data.tile_data_at(index) {
+                        let width = data.width as usize;
+                        push((index % width) as i32, (index / width) as i32, tile);
+                    }
+                }
+                data.encoding
+            }
+            TileLayerData::Infinite(data) => {
+                for (&(chunk_x, chunk_y), chunk) in &data.chunks {
+                    for (index, tile) in chunk.tiles.iter().enumerate() {
+                        if let Some(tile) = tile {
+                            let width = ChunkData::WIDTH as usize;
+                            push(
+                                chunk_x * ChunkData::WIDTH as i32 + (index % width) as i32,
+                                chunk_y * ChunkData::HEIGHT as i32 + (index / width) as i32,
+                                *tile,
+                            );
+                        }
+                    }
+                }
+                data.encoding
+            }
+        };
+
+        if self.infinite {
+            let mut merged = InfiniteTileLayerData {
+                chunks: HashMap::new(),
+                encoding,
+            };
+            for (x, y, tile) in tiles {
+                merged.set_tile_data(x, y, Some(tile));
+            }
+            TileLayerData::Infinite(merged)
+        } else {
+            let mut merged = FiniteTileLayerData::empty(self.width, self.height, self);
+            merged.encoding = encoding;
+            for (x, y, tile) in tiles {
+                if x >= 0 && y >= 0 && (x as u32) < self.width && (y as u32) < self.height {
+                    let index = y as usize * self.width as usize + x as usize;
+                    merged.set_tile_data_at(index, Some(tile), &self.tilesets);
+                }
+            }
+            TileLayerData::Finite(merged)
+        }
+    }
+}
+
+/// What is needed to move the layers of another map into a map.
+struct MergeContext {
+    /// The index within the tilesets of the map of each tileset of the other map.
+    tileset_indices: Vec<usize>,
+    tile_offset: (i32, i32),
+    pixel_offset: (f32, f32),
+}
+
+fn collect_names(layers: &[LayerData], names: &mut HashSet<String>) {
+    for layer in layers {
+        names.insert(layer.name.clone());
+        if let LayerDataType::Group(group) = &layer.layer_type {
+            collect_names(&group.layers, names);
+        }
+    }
+}
+
+/// Returns `name` followed by the first number from 2 onwards that makes it not in `taken`.
+fn unique_name(name: &str, taken: &HashSet<String>) -> String {
+    (2..)
+        .map(|number| format!("{} {}", name, number))
+        .find(|candidate| !taken.contains(candidate))
+        .expect("there are always names left")
+}
+
+/// Makes the object properties of `layers` and of their objects refer to the new IDs of the
+/// objects they referred to.
+fn remap_object_properties(layers: &mut [LayerData], object_ids: &HashMap<u32, u32>) {
+    fn remap(properties: &mut Properties, object_ids: &HashMap<u32, u32>) {
+        for value in properties.values_mut() {
+            match value {
+                PropertyValue::ObjectValue(id) => {
+                    if let Some(new_id) = object_ids.get(id) {
+                        *id = *new_id;
+                    }
+                }
+                PropertyValue::ClassValue { properties, .. } => remap(properties, object_ids),
+                _ => {}
+            }
+        }
+    }
+
+    for layer in layers {
+        remap(&mut layer.properties, object_ids);
+        match &mut layer.layer_type {
+            LayerDataType::Objects(data) => {
+                for object in &mut data.objects {
+                    remap(&mut object.properties, object_ids);
+                    if let Some(instance) = &mut object.template {
+                        remap(&mut instance.overrides.properties, object_ids);
+                    }
+                }
+            }
+            LayerDataType::Group(data) => remap_object_properties(&mut data.layers, object_ids),
+            LayerDataType::Tiles(_) | LayerDataType::Image(_) => {}
+        }
+    }
+}
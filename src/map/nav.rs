@@ -0,0 +1,308 @@
+//! Walkability grids built from the tiles of a [`Map`], and pathfinding over them.
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{Layer, LayerTile, LayerType, Map, Orientation, StaggerAxis, StaggerIndex, TileLayer};
+
+/// Which cells of a map can be walked on, as returned by [`Map::to_nav_grid()`].
+///
+/// Cells are addressed with the same coordinates as the tiles of the map's tile layers. The
+/// neighbors of a cell, i.e. the cells that can be reached from it in a single step, depend on the
+/// orientation of the map:
+///
+/// - In orthogonal and isometric maps, they are the 4 cells sharing an edge with it.
+/// - In hexagonal maps, they are the 6 surrounding cells.
+/// - In staggered maps, they are the 4 cells diagonally adjacent to it in the staggered rows or
+///   columns, which are the ones sharing an edge with it on screen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavGrid {
+    origin: (i32, i32),
+    width: u32,
+    height: u32,
+    walkable: Vec<bool>,
+    orientation: Orientation,
+    stagger_axis: StaggerAxis,
+    stagger_index: StaggerIndex,
+}
+
+impl NavGrid {
+    /// The position of the top left cell of the grid. Always `(0, 0)` unless the grid was built
+    /// from an infinite map, in which case it covers the smallest area containing all chunks.
+    #[inline]
+    pub fn origin(&self) -> (i32, i32) {
+        self.origin
+    }
+
+    /// The width of the grid, in cells.
+    #[inline]
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the grid, in cells.
+    #[inline]
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Returns true if the cell at the given position can be walked on. Cells outside of the grid
+    /// never can.
+    pub fn is_walkable(&self, x: i32, y: i32) -> bool {
+        self.index(x, y).is_some_and(|index| self.walkable[index])
+    }
+
+    /// Changes whether the cell at the given position can be walked on, e.g. to account for
+    /// obstacles that aren't part of the map. Does nothing if the cell is outside of the grid.
+    pub fn set_walkable(&mut self, x: i32, y: i32, walkable: bool) {
+        if let Some(index) = self.index(x, y) {
+            self.walkable[index] = walkable;
+        }
+    }
+
+    /// Returns the walkable neighbors of the cell at the given position.
+    pub fn neighbors(&self, x: i32, y: i32) -> impl Iterator<Item = (i32, i32)> + '_ {
+        self.neighbor_offsets(x, y)
+            .into_iter()
+            .map(move |(dx, dy)| (x + dx, y + dy))
+            .filter(move |&(x, y)| self.is_walkable(x, y))
+    }
+
+    /// Finds one of the shortest paths between two cells with the A* algorithm, moving only
+    /// between walkable neighbors. Every step has the same cost.
+    ///
+    /// Returns the cells of the path, including both `start` and `goal`, or [`None`] if either
+    /// isn't walkable or if no path exists.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// // Only empty cells can be walked on
+    /// let grid = map.to_nav_grid(|tiles| tiles.is_empty());
+    ///
+    /// if let Some(path) = grid.find_path((20, 20), (30, 25)) {
+    ///     println!("{} steps", path.len() - 1);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn find_path(&self, start: (i32, i32), goal: (i32, i32)) -> Option<Vec<(i32, i32)>> {
+        if !self.is_walkable(start.0, start.1) || !self.is_walkable(goal.0, goal.1) {
+            return None;
+        }
+
+        let mut open = BinaryHeap::new();
+        let mut came_from = HashMap::new();
+        let mut costs = HashMap::new();
+        costs.insert(start, 0u32);
+        open.push(Reverse((self.distance(start, goal), 0u32, start)));
+
+        while let Some(Reverse((_, cost, cell))) = open.pop() {
+            if cell == goal {
+                let mut path = vec![cell];
+                let mut cell = cell;
+                while let Some(&previous) = came_from.get(&cell) {
+                    path.push(previous);
+                    cell = previous;
+                }
+                path.reverse();
+                return Some(path);
+            }
+            if costs.get(&cell).is_some_and(|&best| cost > best) {
+                continue;
+            }
+
+            for neighbor in self.neighbors(cell.0, cell.1) {
+                let neighbor_cost = cost + 1;
+                if costs
+                    .get(&neighbor)
+                    .is_none_or(|&best| neighbor_cost < best)
+                {
+                    costs.insert(neighbor, neighbor_cost);
+                    came_from.insert(neighbor, cell);
+                    open.push(Reverse((
+                        neighbor_cost + self.distance(neighbor, goal),
+                        neighbor_cost,
+                        neighbor,
+                    )));
+                }
+            }
+        }
+
+        None
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        let (x, y) = (x - self.origin.0, y - self.origin.1);
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return None;
+        }
+        Some(y as usize * self.width as usize + x as usize)
+    }
+
+    fn is_shifted(&self, position: i32) -> bool {
+        is_shifted(self.stagger_index, position)
+    }
+
+    fn neighbor_offsets(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        neighbor_offsets(
+            self.orientation,
+            self.stagger_axis,
+            self.stagger_index,
+            x,
+            y,
+        )
+    }
+
+    /// The smallest number of steps between two cells if there were no obstacles, which never
+    /// overestimates the length of a path as A* requires.
+    fn distance(&self, from: (i32, i32), to: (i32, i32)) -> u32 {
+        if let Orientation::Orthogonal | Orientation::Isometric = self.orientation {
+            return from.0.abs_diff(to.0) + from.1.abs_diff(to.1);
+        }
+
+        // Work with (along, across) the stagger axis, so that both axes are handled the same way
+        let swap = |(x, y): (i32, i32)| match self.stagger_axis {
+            StaggerAxis::Y => (x, y),
+            StaggerAxis::X => (y, x),
+        };
+        let (from, to) = (swap(from), swap(to));
+        let shift = |(_, row): (i32, i32)| self.is_shifted(row) as i32;
+
+        match self.orientation {
+            Orientation::Staggered => {
+                // Staggered cells are diamonds, which form an isometric grid once rotated
+                let u = |cell: (i32, i32)| 2 * cell.0 + shift(cell) + cell.1;
+                let v = |cell: (i32, i32)| cell.1 - 2 * cell.0 - shift(cell);
+                (u(from).abs_diff(u(to)) + v(from).abs_diff(v(to))) / 2
+            }
+            _ => {
+                // Convert to cube coordinates, in which hexagonal distances are simple
+                let q = |(x, row): (i32, i32)| match self.stagger_index {
+                    StaggerIndex::Odd => x - row.div_euclid(2),
+                    StaggerIndex::Even => x - (row + 1).div_euclid(2),
+                };
+                let (dq, dr) = (q(to) - q(from), to.1 - from.1);
+                (dq.unsigned_abs() + dr.unsigned_abs() + (dq + dr).unsigned_abs()) / 2
+            }
+        }
+    }
+}
+
+/// Returns true if the row (or column, depending on the stagger axis) at the given position is
+/// shifted by half a tile in staggered and hexagonal maps.
+pub(crate) fn is_shifted(stagger_index: StaggerIndex, position: i32) -> bool {
+    let odd = position.rem_euclid(2) == 1;
+    match stagger_index {
+        StaggerIndex::Odd => odd,
+        StaggerIndex::Even => !odd,
+    }
+}
+
+/// Returns the offsets from the cell at the given position to its neighbors in a map laid out as
+/// given, which are described in [`NavGrid`].
+pub(crate) fn neighbor_offsets(
+    orientation: Orientation,
+    stagger_axis: StaggerAxis,
+    stagger_index: StaggerIndex,
+    x: i32,
+    y: i32,
+) -> Vec<(i32, i32)> {
+    let staggered = match orientation {
+        Orientation::Orthogonal | Orientation::Isometric => {
+            return vec![(0, -1), (1, 0), (0, 1), (-1, 0)];
+        }
+        Orientation::Staggered => true,
+        Orientation::Hexagonal => false,
+    };
+
+    // Offsets along and across the stagger axis, i.e. (x, y) when rows are staggered
+    let shifted = match stagger_axis {
+        StaggerAxis::Y => is_shifted(stagger_index, y),
+        StaggerAxis::X => is_shifted(stagger_index, x),
+    };
+    let side = if shifted { 0 } else { -1 };
+    let mut offsets = vec![(side, -1), (side + 1, -1), (side, 1), (side + 1, 1)];
+    if !staggered {
+        offsets.extend([(-1, 0), (1, 0)]);
+    }
+
+    match stagger_axis {
+        StaggerAxis::Y => offsets,
+        StaggerAxis::X => offsets.into_iter().map(|(a, b)| (b, a)).collect(),
+    }
+}
+
+impl Map {
+    /// Builds a grid telling which cells of this map can be walked on, to find paths through it
+    /// with [`NavGrid::find_path()`].
+    ///
+    /// `predicate` is called once for each cell with the tiles the map's tile layers contain at
+    /// that position, from the bottommost layer to the topmost one, including the ones in group
+    /// layers. It returns whether the cell can be walked on, usually by looking at the custom
+    /// properties of the tiles or of their tilesets.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// let grid = map.to_nav_grid(|tiles| {
+    ///     !tiles.iter().any(|tile| {
+    ///         tile.get_tile().is_some_and(|tile| {
+    ///             tile.properties.get("solid") == Some(&PropertyValue::BoolValue(true))
+    ///         })
+    ///     })
+    /// });
+    /// assert_eq!(grid.width(), map.width);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_nav_grid(&self, mut predicate: impl FnMut(&[LayerTile]) -> bool) -> NavGrid {
+        fn collect_tile_layers<'map>(
+            layers: impl Iterator<Item = Layer<'map>>,
+            tile_layers: &mut Vec<TileLayer<'map>>,
+        ) {
+            for layer in layers {
+                match layer.layer_type() {
+                    LayerType::Tiles(tiles) => tile_layers.push(tiles),
+                    LayerType::Group(group) => collect_tile_layers(group.layers(), tile_layers),
+                    _ => {}
+                }
+            }
+        }
+        let mut layers = Vec::new();
+        collect_tile_layers(self.layers(), &mut layers);
+
+        let (origin, (width, height)) = self.tile_bounds();
+        let mut walkable = Vec::with_capacity(width as usize * height as usize);
+        let mut tiles = Vec::with_capacity(layers.len());
+        for y in 0..height as i32 {
+            for x in 0..width as i32 {
+                tiles.clear();
+                tiles.extend(
+                    layers
+                        .iter()
+                        .filter_map(|layer| layer.get_tile(x + origin.0, y + origin.1)),
+                );
+                walkable.push(predicate(&tiles));
+            }
+        }
+
+        NavGrid {
+            origin,
+            width,
+            height,
+            walkable,
+            orientation: self.orientation,
+            stagger_axis: self.stagger_axis,
+            stagger_index: self.stagger_index,
+        }
+    }
+}
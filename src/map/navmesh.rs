@@ -0,0 +1,409 @@
+//! Navigation meshes covering the parts of a [`Map`] that aren't blocked by collision shapes.
+
+use std::collections::HashMap;
+
+use crate::{outline::tile_collisions, Layer, LayerType, Map, TileLayer};
+
+/// Options for [`Map::to_nav_mesh()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct NavMeshSettings {
+    /// The number of cells each tile is split into along both axes when looking for obstacles. A
+    /// cell is blocked if its center lies within an obstacle, so higher values follow the shape of
+    /// obstacles more closely, at the cost of a mesh with more triangles.
+    ///
+    /// 2 by default.
+    pub resolution: u32,
+    /// Whether the collision shapes of the tiles placed in tile layers are obstacles.
+    ///
+    /// Enabled by default.
+    pub tile_obstacles: bool,
+    /// Whether the objects of object layers are obstacles.
+    ///
+    /// Enabled by default.
+    pub object_obstacles: bool,
+    /// If set, only the objects with this class are obstacles.
+    ///
+    /// Unset by default.
+    pub obstacle_class: Option<String>,
+}
+
+impl Default for NavMeshSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 2,
+            tile_obstacles: true,
+            object_obstacles: true,
+            obstacle_class: None,
+        }
+    }
+}
+
+/// A triangulation of the walkable area of a map, as returned by [`Map::to_nav_mesh()`].
+///
+/// Coordinates are in pixels, with the same origin as the map's tiles.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct NavMesh {
+    /// The vertices of the triangles.
+    pub vertices: Vec<(f32, f32)>,
+    /// The triangles covering the walkable area, as indices into [`NavMesh::vertices`].
+    pub triangles: Vec<[u32; 3]>,
+    /// The pairs of triangles that can be walked between, i.e. whose edges overlap.
+    pub links: Vec<NavLink>,
+}
+
+/// Two triangles of a [`NavMesh`] that can be walked between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NavLink {
+    /// The indices of both triangles within [`NavMesh::triangles`].
+    pub triangles: [u32; 2],
+    /// The segment shared by the edges of both triangles, which must be crossed to go from one to
+    /// the other.
+    pub portal: [(f32, f32); 2],
+}
+
+impl NavMesh {
+    /// Returns the indices of the triangles that can be reached from the given one in one step.
+    pub fn neighbors(&self, triangle: u32) -> impl Iterator<Item = u32> + '_ {
+        self.links
+            .iter()
+            .filter_map(move |link| match link.triangles {
+                [a, b] if a == triangle => Some(b),
+                [a, b] if b == triangle => Some(a),
+                _ => None,
+            })
+    }
+
+    /// Returns the index of a triangle containing the given point, if any.
+    pub fn triangle_at(&self, (x, y): (f32, f32)) -> Option<u32> {
+        let side = |(ax, ay): (f32, f32), (bx, by): (f32, f32)| {
+            (bx - ax) * (y - ay) - (by - ay) * (x - ax)
+        };
+        self.triangles
+            .iter()
+            .position(|triangle| {
+                let [a, b, c] = triangle.map(|index| self.vertices[index as usize]);
+                let sides = [side(a, b), side(b, c), side(c, a)];
+                sides.iter().all(|&side| side >= 0.0) || sides.iter().all(|&side| side <= 0.0)
+            })
+            .map(|index| index as u32)
+    }
+}
+
+impl Map {
+    /// Builds a navigation mesh covering the area of this map that isn't blocked by obstacles, such
+    /// as the collision shapes of tiles and the shapes of objects.
+    ///
+    /// The walkable area is found on a grid of cells, as configured by
+    /// [`NavMeshSettings::resolution`], and then covered by as few rectangles as possible, each
+    /// split into two triangles. Points, polylines and texts are never obstacles, since they have
+    /// no area. Tiles are placed as in orthogonal maps regardless of the map's orientation.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, NavMeshSettings};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+    /// let mesh = map.to_nav_mesh(&NavMeshSettings::default());
+    ///
+    /// if let Some(triangle) = mesh.triangle_at((4.0, 4.0)) {
+    ///     for neighbor in mesh.neighbors(triangle) {
+    ///         println!("{:?} leads to {:?}", triangle, neighbor);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_nav_mesh(&self, settings: &NavMeshSettings) -> NavMesh {
+        let resolution = settings.resolution.max(1);
+        let ((left, top), (width, height)) = self.tile_bounds();
+        let grid = CellGrid {
+            origin: (
+                (left * self.tile_width as i32) as f32,
+                (top * self.tile_height as i32) as f32,
+            ),
+            cell_size: (
+                self.tile_width as f32 / resolution as f32,
+                self.tile_height as f32 / resolution as f32,
+            ),
+            width: width * resolution,
+            height: height * resolution,
+        };
+
+        let mut obstacles = Vec::new();
+        collect_obstacles(self, settings, self.layers(), (0.0, 0.0), &mut obstacles);
+        let mut blocked = vec![false; grid.width as usize * grid.height as usize];
+        for obstacle in &obstacles {
+            grid.block(obstacle, &mut blocked);
+        }
+
+        grid.mesh(&blocked)
+    }
+}
+
+fn collect_obstacles<'map>(
+    map: &'map Map,
+    settings: &NavMeshSettings,
+    layers: impl Iterator<Item = Layer<'map>>,
+    offset: (f32, f32),
+    obstacles: &mut Vec<Vec<(f32, f32)>>,
+) {
+    for layer in layers {
+        let offset = (offset.0 + layer.offset_x, offset.1 + layer.offset_y);
+        let mut add = |points: Vec<(f32, f32)>| {
+            obstacles.push(
+                points
+                    .into_iter()
+                    .map(|(x, y)| (x + offset.0, y + offset.1))
+                    .collect(),
+            )
+        };
+
+        match layer.layer_type() {
+            LayerType::Tiles(tiles) if settings.tile_obstacles => {
+                let ((left, top), (width, height)) = map.tile_bounds();
+                for y in top..top + height as i32 {
+                    for x in left..left + width as i32 {
+                        let tile = match &tiles {
+                            TileLayer::Finite(tiles) => tiles.get_tile_data(x, y),
+                            TileLayer::Infinite(tiles) => tiles.get_tile_data(x, y),
+                        };
+                        if let Some(tile) = tile {
+                            for collision in tile_collisions(map, &tile, x, y) {
+                                if collision.outline.closed {
+                                    add(collision.outline.points);
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            LayerType::Objects(objects) if settings.object_obstacles => {
+                for object in objects.objects() {
+                    if let Some(class) = &settings.obstacle_class {
+                        if &object.user_type != class {
+                            continue;
+                        }
+                    }
+                    let outline = match object.shape.outline() {
+                        Some(outline) if outline.closed => outline,
+                        _ => continue,
+                    };
+                    // Tile objects are positioned by their bottom left corner
+                    let top = match (&object.shape, object.get_tile()) {
+                        (crate::ObjectShape::Rect { height, .. }, Some(_)) => -height,
+                        _ => 0.0,
+                    };
+                    let (sin, cos) = object.rotation.to_radians().sin_cos();
+                    add(outline
+                        .map(|(x, y)| {
+                            let y = y + top;
+                            (object.x + x * cos - y * sin, object.y + x * sin + y * cos)
+                        })
+                        .points);
+                }
+            }
+            LayerType::Group(group) => {
+                collect_obstacles(map, settings, group.layers(), offset, obstacles)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The grid of cells the walkable area is computed on.
+struct CellGrid {
+    origin: (f32, f32),
+    cell_size: (f32, f32),
+    width: u32,
+    height: u32,
+}
+
+impl CellGrid {
+    /// Marks the cells whose center lies within a polygon as blocked.
+    fn block(&self, polygon: &[(f32, f32)], blocked: &mut [bool]) {
+        if polygon.len() < 3 {
+            return;
+        }
+        let (mut min, mut max) = (polygon[0], polygon[0]);
+        for &(x, y) in polygon {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+
+        let cell = |value: f32, origin: f32, size: f32, count: u32| {
+            (((value - origin) / size).floor().max(0.0) as u32).min(count)
+        };
+        let (left, right) = (
+            cell(min.0, self.origin.0, self.cell_size.0, self.width),
+            cell(
+                max.0,
+                self.origin.0,
+                self.cell_size.0,
+                self.width.saturating_sub(1),
+            ),
+        );
+        let (top, bottom) = (
+            cell(min.1, self.origin.1, self.cell_size.1, self.height),
+            cell(
+                max.1,
+                self.origin.1,
+                self.cell_size.1,
+                self.height.saturating_sub(1),
+            ),
+        );
+        for y in top..=bottom.min(self.height.saturating_sub(1)) {
+            for x in left..=right {
+                if x >= self.width {
+                    break;
+                }
+                let center = (
+                    self.origin.0 + (x as f32 + 0.5) * self.cell_size.0,
+                    self.origin.1 + (y as f32 + 0.5) * self.cell_size.1,
+                );
+                if contains(polygon, center) {
+                    blocked[(y * self.width + x) as usize] = true;
+                }
+            }
+        }
+    }
+
+    /// Covers the cells that aren't blocked with rectangles, and turns them into a mesh.
+    fn mesh(&self, blocked: &[bool]) -> NavMesh {
+        let (width, height) = (self.width as usize, self.height as usize);
+        // The rectangle each cell belongs to
+        let mut cell_rects: Vec<Option<u32>> = vec![None; blocked.len()];
+        let mut rects = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if blocked[y * width + x] || cell_rects[y * width + x].is_some() {
+                    continue;
+                }
+                let free = |x: usize, y: usize| {
+                    !blocked[y * width + x] && cell_rects[y * width + x].is_none()
+                };
+                let mut right = x + 1;
+                while right < width && free(right, y) {
+                    right += 1;
+                }
+                let mut bottom = y + 1;
+                while bottom < height && (x..right).all(|x| free(x, bottom)) {
+                    bottom += 1;
+                }
+
+                let rect = rects.len() as u32;
+                for cell_y in y..bottom {
+                    for cell_x in x..right {
+                        cell_rects[cell_y * width + cell_x] = Some(rect);
+                    }
+                }
+                rects.push((x as u32, y as u32, right as u32, bottom as u32));
+            }
+        }
+
+        let mut mesh = NavMesh::default();
+        let mut vertices = HashMap::new();
+        let mut vertex = |mesh: &mut NavMesh, corner: (u32, u32)| {
+            *vertices.entry(corner).or_insert_with(|| {
+                mesh.vertices.push(self.point(corner));
+                mesh.vertices.len() as u32 - 1
+            })
+        };
+        // Each rectangle is split along the diagonal going from its top left corner to its bottom
+        // right one. The lower triangle holds its left and bottom edges, the upper one its top and
+        // right edges.
+        for (index, &(left, top, right, bottom)) in rects.iter().enumerate() {
+            let top_left = vertex(&mut mesh, (left, top));
+            let top_right = vertex(&mut mesh, (right, top));
+            let bottom_right = vertex(&mut mesh, (right, bottom));
+            let bottom_left = vertex(&mut mesh, (left, bottom));
+            mesh.triangles.push([top_left, bottom_right, bottom_left]);
+            mesh.triangles.push([top_left, top_right, bottom_right]);
+            let index = index as u32;
+            mesh.links.push(NavLink {
+                triangles: [2 * index, 2 * index + 1],
+                portal: [self.point((left, top)), self.point((right, bottom))],
+            });
+        }
+
+        // The extent of the boundary shared by two rectangles, along with whether they are next to
+        // each other horizontally
+        let mut shared: HashMap<(u32, u32, bool), (u32, u32, u32)> = HashMap::new();
+        for y in 0..height {
+            for x in 0..width {
+                let rect = match cell_rects[y * width + x] {
+                    Some(rect) => rect,
+                    None => continue,
+                };
+                if let Some(Some(other)) = (x + 1 < width).then(|| cell_rects[y * width + x + 1]) {
+                    if other != rect {
+                        let extent = shared.entry((rect, other, true)).or_insert((
+                            x as u32 + 1,
+                            y as u32,
+                            y as u32 + 1,
+                        ));
+                        extent.2 = y as u32 + 1;
+                    }
+                }
+                if let Some(Some(other)) = (y + 1 < height).then(|| cell_rects[(y + 1) * width + x])
+                {
+                    if other != rect {
+                        let extent = shared.entry((rect, other, false)).or_insert((
+                            y as u32 + 1,
+                            x as u32,
+                            x as u32 + 1,
+                        ));
+                        extent.2 = x as u32 + 1;
+                    }
+                }
+            }
+        }
+
+        let mut shared: Vec<_> = shared.into_iter().collect();
+        shared.sort_unstable_by_key(|&(key, _)| key);
+        for ((first, second, horizontal), (position, start, end)) in shared {
+            let (triangles, portal) = if horizontal {
+                // The right edge of the first rectangle against the left edge of the second one
+                (
+                    [2 * first + 1, 2 * second],
+                    [(position, start), (position, end)],
+                )
+            } else {
+                // The bottom edge of the first rectangle against the top edge of the second one
+                (
+                    [2 * first, 2 * second + 1],
+                    [(start, position), (end, position)],
+                )
+            };
+            mesh.links.push(NavLink {
+                triangles,
+                portal: portal.map(|corner| self.point(corner)),
+            });
+        }
+
+        mesh
+    }
+
+    /// Returns the position in pixels of a corner of the cells.
+    fn point(&self, (x, y): (u32, u32)) -> (f32, f32) {
+        (
+            self.origin.0 + x as f32 * self.cell_size.0,
+            self.origin.1 + y as f32 * self.cell_size.1,
+        )
+    }
+}
+
+/// Returns true if a point lies within a polygon, using the even-odd rule.
+fn contains(polygon: &[(f32, f32)], (x, y): (f32, f32)) -> bool {
+    let mut inside = false;
+    let mut previous = polygon[polygon.len() - 1];
+    for &point in polygon {
+        let ((ax, ay), (bx, by)) = (previous, point);
+        if (ay > y) != (by > y) && x < ax + (y - ay) / (by - ay) * (bx - ax) {
+            inside = !inside;
+        }
+        previous = point;
+    }
+    inside
+}
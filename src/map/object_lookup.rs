@@ -0,0 +1,48 @@
+//! Finding the objects of a [`Map`] by ID, such as the ones object properties refer to.
+
+use crate::{LayerData, LayerDataType, Map, Object, ObjectData, PropertyValue};
+
+impl Map {
+    /// Returns the object with the ID given, searching every object layer of the map including
+    /// the ones within group layers.
+    pub fn object_by_id(&self, id: u32) -> Option<Object<'_>> {
+        find_object(&self.layers, id).map(|data| Object::new(self, data))
+    }
+
+    /// Returns the object an [object property](PropertyValue::ObjectValue) of this map, or of one
+    /// of its layers or objects, refers to. Returns [`None`] for other properties, for
+    /// object properties that aren't set, which refer to the ID 0, and for objects that were
+    /// removed since the property was set.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_property.tmx")?;
+    /// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    /// let object = layer.get_object(0).unwrap();
+    ///
+    /// let target = map
+    ///     .resolve_object_property(&object.properties["object property"])
+    ///     .unwrap();
+    /// assert_eq!(target.id(), 3);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_object_property(&self, value: &PropertyValue) -> Option<Object<'_>> {
+        match value {
+            PropertyValue::ObjectValue(0) => None,
+            PropertyValue::ObjectValue(id) => self.object_by_id(*id),
+            _ => None,
+        }
+    }
+}
+
+fn find_object(layers: &[LayerData], id: u32) -> Option<&ObjectData> {
+    layers.iter().find_map(|layer| match &layer.layer_type {
+        LayerDataType::Objects(data) => data.objects.iter().find(|object| object.id == id),
+        LayerDataType::Group(data) => find_object(&data.layers, id),
+        _ => None,
+    })
+}
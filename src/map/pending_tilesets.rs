@@ -0,0 +1,88 @@
+//! Loading the external tilesets of maps loaded with
+//! [`LoaderOptions::defer_tilesets`](crate::LoaderOptions::defer_tilesets) set.
+
+use std::path::Path;
+
+use crate::{Loader, Map, ResourceCache, ResourceReader, Result, Tileset};
+
+/// The tileset a tile refers to, as returned by [`LayerTile::tileset_status()`].
+///
+/// [`LayerTile::tileset_status()`]: crate::LayerTile::tileset_status
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TilesetStatus<'map> {
+    /// The tileset has been loaded.
+    Loaded(&'map Tileset),
+    /// Loading the tileset was deferred; it is read from this path by
+    /// [`Map::resolve_tilesets()`].
+    PendingTileset(&'map Path),
+}
+
+impl<'map> TilesetStatus<'map> {
+    pub(crate) fn new(tileset: &'map Tileset) -> Self {
+        if tileset.is_pending() {
+            Self::PendingTileset(&tileset.source)
+        } else {
+            Self::Loaded(tileset)
+        }
+    }
+}
+
+impl Map {
+    /// Returns the index and path of each of the tilesets of the map whose loading was deferred,
+    /// in the order of [`Map::tilesets()`].
+    pub fn pending_tilesets(&self) -> impl Iterator<Item = (usize, &Path)> {
+        self.tilesets
+            .iter()
+            .enumerate()
+            .filter(|(_, tileset)| tileset.is_pending())
+            .map(|(index, tileset)| (index, tileset.source.as_path()))
+    }
+
+    /// Loads the tilesets of the map whose loading was deferred with
+    /// [`LoaderOptions::defer_tilesets`](crate::LoaderOptions::defer_tilesets), using the cache
+    /// and reader of `loader`, and puts them in place of their placeholders. Tilesets that aren't
+    /// in the cache yet are added to it.
+    ///
+    /// If a tileset fails to load, its error is returned and it stays pending, along with the
+    /// ones after it, so that this can be called again later. Does nothing if no tileset is
+    /// pending.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, TilesetStatus};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// loader.options_mut().defer_tilesets = true;
+    /// let mut map = loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
+    ///
+    /// let tile = map.tile_for_gid(1).unwrap();
+    /// assert!(matches!(tile.tileset_status(), TilesetStatus::PendingTileset(_)));
+    ///
+    /// map.resolve_tilesets(&mut loader)?;
+    /// assert_eq!(map.pending_tilesets().count(), 0);
+    /// assert_eq!(map.tilesets()[0].name, "tilesheet");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_tilesets<Cache: ResourceCache, Reader: ResourceReader>(
+        &mut self,
+        loader: &mut Loader<Cache, Reader>,
+    ) -> Result<()> {
+        let mut result = Ok(());
+        for index in 0..self.tilesets.len() {
+            if !self.tilesets[index].is_pending() {
+                continue;
+            }
+            match loader.load_cached_tileset(&self.tilesets[index].source) {
+                Ok(tileset) => self.tilesets[index] = tileset,
+                Err(err) => {
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+        self.gid_table.clear_table();
+        result
+    }
+}
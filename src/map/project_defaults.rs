@@ -0,0 +1,71 @@
+//! Filling in the members of classes defined by a [`Project`] that a map leaves to their default.
+
+use std::sync::Arc;
+
+use crate::{LayerData, LayerDataType, Map, ObjectData, Project, Tileset};
+
+impl Map {
+    /// Fills in the default values of the members of their class that the map, its layers, its
+    /// objects and the tiles of its tilesets don't set, along with those of the class properties
+    /// they contain. See [`Project::apply_class_defaults()`].
+    ///
+    /// Tilesets shared with other maps through a [`ResourceCache`](crate::ResourceCache) are
+    /// copied before being modified, so maps loaded without applying the defaults are unaffected.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, PropertyValue};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let project = loader.load_project("assets/project/project.tiled-project")?;
+    /// let mut map = loader.load_tmx_map("assets/project/spawns.tmx")?;
+    /// map.apply_project_defaults(&project);
+    ///
+    /// let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    /// let spawn = layer.get_object(0).unwrap();
+    /// assert_eq!(spawn.properties["count"], PropertyValue::IntValue(1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn apply_project_defaults(&mut self, project: &Project) {
+        project.apply_class_defaults(self.user_type.as_deref(), "map", &mut self.properties);
+        apply_to_layers(project, &mut self.layers);
+        for tileset in &mut self.tilesets {
+            Arc::make_mut(tileset).apply_project_defaults(project);
+        }
+    }
+}
+
+impl Tileset {
+    /// Fills in the default values of the members of their class that the tileset, its tiles and
+    /// the objects of their collision shapes don't set, along with those of the class properties
+    /// they contain. See [`Project::apply_class_defaults()`].
+    pub fn apply_project_defaults(&mut self, project: &Project) {
+        project.apply_class_defaults(self.user_type.as_deref(), "tileset", &mut self.properties);
+        for tile in self.tiles.values_mut() {
+            project.apply_class_defaults(tile.user_type.as_deref(), "tile", &mut tile.properties);
+            if let Some(collision) = &mut tile.collision {
+                apply_to_objects(project, &mut collision.objects);
+            }
+        }
+    }
+}
+
+fn apply_to_layers(project: &Project, layers: &mut [LayerData]) {
+    for layer in layers {
+        project.apply_class_defaults(layer.user_type.as_deref(), "layer", &mut layer.properties);
+        match &mut layer.layer_type {
+            LayerDataType::Objects(data) => apply_to_objects(project, &mut data.objects),
+            LayerDataType::Group(data) => apply_to_layers(project, &mut data.layers),
+            LayerDataType::Tiles(_) | LayerDataType::Image(_) => {}
+        }
+    }
+}
+
+fn apply_to_objects(project: &Project, objects: &mut [ObjectData]) {
+    for object in objects {
+        let class = object.class().map(str::to_owned);
+        project.apply_class_defaults(class.as_deref(), "object", &mut object.properties);
+    }
+}
@@ -0,0 +1,194 @@
+//! Finding the layers and objects of a map that match a set of conditions.
+
+use crate::{Layer, LayerKind, LayerType, Map, Object, Properties, PropertyValue};
+
+/// A search through the layers and objects of a map, created with [`Map::query()`].
+///
+/// Conditions are added with the methods of the query, and all of them must hold for a layer or
+/// an object to match. [`MapQuery::run()`] then returns every match, going through the layers in
+/// display order, including the layers within groups, and through the objects of each object layer
+/// right after the layer itself.
+#[derive(Debug, Clone)]
+#[must_use = "queries do nothing until they are run"]
+pub struct MapQuery<'map> {
+    map: &'map Map,
+    kinds: Vec<LayerKind>,
+    targets: QueryTargets,
+    name: Option<String>,
+    class: Option<String>,
+    properties: Vec<(String, Option<PropertyValue>)>,
+}
+
+/// What a [`MapQuery`] can match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryTargets {
+    All,
+    Layers,
+    Objects,
+}
+
+/// A layer or an object matched by a [`MapQuery`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QueryMatch<'map> {
+    /// A matching layer.
+    Layer(Layer<'map>),
+    /// A matching object, along with the layer it is in.
+    Object(Layer<'map>, Object<'map>),
+}
+
+impl<'map> QueryMatch<'map> {
+    /// The matching layer, or [`None`] if an object matched.
+    pub fn as_layer(&self) -> Option<Layer<'map>> {
+        match self {
+            QueryMatch::Layer(layer) => Some(*layer),
+            QueryMatch::Object(..) => None,
+        }
+    }
+
+    /// The matching object, or [`None`] if a layer matched.
+    pub fn as_object(&self) -> Option<Object<'map>> {
+        match self {
+            QueryMatch::Layer(_) => None,
+            QueryMatch::Object(_, object) => Some(*object),
+        }
+    }
+}
+
+impl Map {
+    /// Starts a search through the layers and objects of the map, replacing the loops usually
+    /// written to find them by class, by name or by property.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{LayerKind, Loader, PropertyValue};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_template.tmx")?;
+    ///
+    /// // The objects of object layers that inherit `property` from their template
+    /// let objects: Vec<_> = map
+    ///     .query()
+    ///     .layers(LayerKind::Objects)
+    ///     .only_objects()
+    ///     .with_property("property", PropertyValue::IntValue(1))
+    ///     .run()
+    ///     .into_iter()
+    ///     .filter_map(|found| found.as_object())
+    ///     .collect();
+    /// assert_eq!(objects.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query(&self) -> MapQuery<'_> {
+        MapQuery {
+            map: self,
+            kinds: Vec::new(),
+            targets: QueryTargets::All,
+            name: None,
+            class: None,
+            properties: Vec::new(),
+        }
+    }
+}
+
+impl<'map> MapQuery<'map> {
+    /// Only searches the layers of the kind given, and the objects within them. Can be called
+    /// several times to search layers of several kinds. The layers within groups are searched
+    /// whether groups are searched themselves or not.
+    pub fn layers(mut self, kind: LayerKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Only matches objects, and not the layers they are in.
+    pub fn only_objects(mut self) -> Self {
+        self.targets = QueryTargets::Objects;
+        self
+    }
+
+    /// Only matches layers, and not the objects within them.
+    pub fn only_layers(mut self) -> Self {
+        self.targets = QueryTargets::Layers;
+        self
+    }
+
+    /// Only matches layers and objects with the name given.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Only matches layers and objects of the class given, which older versions of Tiled called
+    /// their type.
+    pub fn class(mut self, class: impl Into<String>) -> Self {
+        self.class = Some(class.into());
+        self
+    }
+
+    /// Only matches layers and objects with a property called `name` equal to `value`.
+    pub fn with_property(mut self, name: impl Into<String>, value: PropertyValue) -> Self {
+        self.properties.push((name.into(), Some(value)));
+        self
+    }
+
+    /// Only matches layers and objects with a property called `name`, whatever its value.
+    pub fn has_property(mut self, name: impl Into<String>) -> Self {
+        self.properties.push((name.into(), None));
+        self
+    }
+
+    /// Returns every layer and object matching the query.
+    pub fn run(&self) -> Vec<QueryMatch<'map>> {
+        let mut found = Vec::new();
+        self.search(self.map.layers(), &mut found);
+        found
+    }
+
+    fn search(&self, layers: impl Iterator<Item = Layer<'map>>, found: &mut Vec<QueryMatch<'map>>) {
+        for layer in layers {
+            let kind = match layer.layer_type() {
+                LayerType::Tiles(_) => LayerKind::Tiles,
+                LayerType::Objects(_) => LayerKind::Objects,
+                LayerType::Image(_) => LayerKind::Image,
+                LayerType::Group(_) => LayerKind::Group,
+            };
+            let searched = self.kinds.is_empty() || self.kinds.contains(&kind);
+
+            if searched
+                && self.targets != QueryTargets::Objects
+                && self.matches(&layer.name, layer.user_type.as_deref(), &layer.properties)
+            {
+                found.push(QueryMatch::Layer(layer));
+            }
+            match layer.layer_type() {
+                LayerType::Objects(objects) if searched && self.targets != QueryTargets::Layers => {
+                    found.extend(
+                        objects
+                            .objects()
+                            .filter(|object| {
+                                self.matches(&object.name, object.class(), &object.properties)
+                            })
+                            .map(|object| QueryMatch::Object(layer, object)),
+                    );
+                }
+                LayerType::Group(group) => self.search(group.layers(), found),
+                _ => {}
+            }
+        }
+    }
+
+    fn matches(&self, name: &str, class: Option<&str>, properties: &Properties) -> bool {
+        self.name.as_deref().is_none_or(|expected| name == expected)
+            && self
+                .class
+                .as_deref()
+                .is_none_or(|expected| class == Some(expected))
+            && self.properties.iter().all(|(name, value)| {
+                match (properties.get(name.as_str()), value) {
+                    (Some(found), Some(expected)) => found == expected,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                }
+            })
+    }
+}
@@ -0,0 +1,121 @@
+//! Changing how the tile layers of a [`Map`] are encoded.
+
+use crate::{
+    canonical_first_gids, ChunkData, DataFormat, Encoding, LayerData, LayerDataType, Map,
+    TileLayerData,
+};
+
+/// The size of the tile data of a layer before and after [`Map::reencode_layers()`], part of a
+/// [`ReencodeReport`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ReencodedLayer {
+    /// The ID of the tile layer.
+    pub id: u32,
+    /// The size in bytes of the tile data with the previous encoding of the layer.
+    pub before: usize,
+    /// The size in bytes of the tile data with the new encoding.
+    pub after: usize,
+}
+
+/// The sizes of the tile layers re-encoded by [`Map::reencode_layers()`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub struct ReencodeReport {
+    /// Every tile layer of the map, including those within groups, in the order they appear in.
+    pub layers: Vec<ReencodedLayer>,
+}
+
+impl ReencodeReport {
+    /// The size in bytes of the tile data of all layers with their previous encodings.
+    pub fn before(&self) -> usize {
+        self.layers.iter().map(|layer| layer.before).sum()
+    }
+
+    /// The size in bytes of the tile data of all layers with the new encoding.
+    pub fn after(&self) -> usize {
+        self.layers.iter().map(|layer| layer.after).sum()
+    }
+}
+
+impl Map {
+    /// Sets the encoding of every tile layer of the map, including those within groups, to
+    /// `encoding`, e.g. to ship maps whose tile data is compressed with zstd rather than stored as
+    /// CSV. The [compression level](Map::compression_level) of the map becomes the one of
+    /// `encoding`, since TMX files store a single level.
+    ///
+    /// Returns the size of the tile data of each layer with its previous encoding and with the
+    /// new one. Sizes are those of the text of the `data` elements as Tiled writes them, without
+    /// the indentation; infinite layers are measured as if written in chunks of
+    /// [`ChunkData::WIDTH`] by [`ChunkData::HEIGHT`] tiles.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Encoding, Loader};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+    /// let report = map.reencode_layers(Encoding::Base64Zstd { level: Some(19) });
+    ///
+    /// println!("Tile data went from {} to {} bytes", report.before(), report.after());
+    /// assert!(report.after() < report.before());
+    /// assert_eq!(map.compression_level, Some(19));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reencode_layers(&mut self, encoding: Encoding) -> ReencodeReport {
+        let first_gids = canonical_first_gids(&self.tilesets);
+        let mut report = ReencodeReport::default();
+        reencode(&mut self.layers, encoding, &first_gids, &mut report);
+        self.compression_level = encoding.level();
+        report
+    }
+}
+
+fn reencode(
+    layers: &mut [LayerData],
+    encoding: Encoding,
+    first_gids: &[u32],
+    report: &mut ReencodeReport,
+) {
+    for layer in layers {
+        let (before, after) = match &mut layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                let tiles = data.bits(first_gids);
+                let size = |encoding: Encoding| {
+                    encoding
+                        .encode(&tiles, data.width, &DataFormat::default())
+                        .len()
+                };
+                let sizes = (size(data.encoding), size(encoding));
+                data.encoding = encoding;
+                sizes
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                let mut sizes = (0, 0);
+                for chunk in data.chunks.values() {
+                    let tiles = chunk.bits(first_gids);
+                    let size = |encoding: Encoding| {
+                        encoding
+                            .encode(&tiles, ChunkData::WIDTH, &DataFormat::default())
+                            .len()
+                    };
+                    sizes.0 += size(data.encoding);
+                    sizes.1 += size(encoding);
+                }
+                data.encoding = encoding;
+                sizes
+            }
+            LayerDataType::Group(group) => {
+                reencode(&mut group.layers, encoding, first_gids, report);
+                continue;
+            }
+            _ => continue,
+        };
+        report.layers.push(ReencodedLayer {
+            id: layer.id,
+            before,
+            after,
+        });
+    }
+}
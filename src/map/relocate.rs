@@ -0,0 +1,235 @@
+//! Moving a map to another location, rebasing the paths of the files it references.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Component, Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    util::normalize_path, DependencyKind, LayerData, LayerDataType, Map, Tileset, TilesetLocation,
+};
+
+use super::content_eq::{parent, relative_path};
+
+/// What [`Map::relocate()`] does with the external tilesets and images a map references.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[non_exhaustive]
+pub enum ResourceHandling {
+    /// Keep referencing the files where they are. Their paths are left untouched, so that they
+    /// are written relative to the new location of the map.
+    #[default]
+    Reference,
+    /// Copy the files next to the new location of the map, keeping their layout relative to the
+    /// map. Files outside of the directory of the map are copied into the new directory itself.
+    Copy,
+}
+
+/// A file [`Map::relocate()`] moved the references of, which must be copied for the relocated map
+/// to load.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct ResourceCopy {
+    /// The path of the file the map referenced before being relocated.
+    pub from: PathBuf,
+    /// The path the map now references the file as.
+    pub to: PathBuf,
+    /// What the file contains.
+    pub kind: DependencyKind,
+}
+
+impl ResourceCopy {
+    /// Copies the file, creating the directories it goes into if needed.
+    pub fn perform(&self) -> io::Result<()> {
+        if let Some(directory) = self.to.parent() {
+            fs::create_dir_all(directory)?;
+        }
+        fs::copy(&self.from, &self.to).map(|_| ())
+    }
+}
+
+impl Map {
+    /// Moves the map to `path`, as saving it there would, rebasing the files it references
+    /// according to `handling`, and returns the files that must be copied for the map to load
+    /// from its new location.
+    ///
+    /// [`Map::source`] and the sources of embedded tilesets are set to `path`. With
+    /// [`ResourceHandling::Reference`], the other paths are kept, since they are relative to the
+    /// working directory and not to the map; [`Map::relative_reference()`] gives the path to
+    /// write for them. With [`ResourceHandling::Copy`], the paths of external tilesets, of
+    /// images and of the tilesets of templates are rewritten to point next to `path`, and nothing
+    /// is copied until [`ResourceCopy::perform()`] is called.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::{Loader, ResourceHandling};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// let copies = map.relocate("export/level.tmx", ResourceHandling::Copy);
+    ///
+    /// assert_eq!(map.source, Path::new("export/level.tmx"));
+    /// assert_eq!(map.tilesets()[0].source, Path::new("export/tilesheet.tsx"));
+    /// assert!(copies.iter().any(|copy| copy.from == Path::new("assets/tilesheet.tsx")));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn relocate(
+        &mut self,
+        path: impl Into<PathBuf>,
+        handling: ResourceHandling,
+    ) -> Vec<ResourceCopy> {
+        let old_source = std::mem::replace(&mut self.source, path.into());
+        let directories = (
+            parent(&old_source).to_owned(),
+            parent(&self.source).to_owned(),
+        );
+        let mut rebaser = Rebaser {
+            handling,
+            targets: HashMap::new(),
+            used: HashSet::new(),
+            copies: Vec::new(),
+        };
+
+        for tileset in &mut self.tilesets {
+            let tileset = Arc::make_mut(tileset);
+            if tileset.source == old_source {
+                tileset.source = self.source.clone();
+                rebaser.tileset_images(tileset, &directories);
+            } else {
+                rebaser.tileset(tileset, &directories);
+            }
+        }
+        rebaser.layers(&mut self.layers, &directories);
+
+        rebaser.copies
+    }
+
+    /// Returns the path a file used by the map should be referenced as from the map file, i.e.
+    /// `path` relative to the directory of [`Map::source`], such as `../tilesets/forest.tsx` for
+    /// `assets/tilesets/forest.tsx` used by `assets/maps/level.tmx`.
+    ///
+    /// `path` is returned normalized but otherwise unchanged if it can't be made relative, e.g.
+    /// because the map path has more `..` components than it.
+    pub fn relative_reference(&self, path: &Path) -> PathBuf {
+        relative_path(path, parent(&self.source))
+    }
+}
+
+/// The directory paths were relative to before relocating a map, and the one they are rebased
+/// onto.
+type Directories = (PathBuf, PathBuf);
+
+/// Rewrites the paths of the files used by a relocated map.
+struct Rebaser {
+    handling: ResourceHandling,
+    /// The new path of each file already rebased, by normalized old path, so that files used in several places are only
+    /// copied once.
+    targets: HashMap<PathBuf, PathBuf>,
+    used: HashSet<PathBuf>,
+    copies: Vec<ResourceCopy>,
+}
+
+impl Rebaser {
+    /// Returns the path `path` is referenced as after relocating the map.
+    fn rebase(
+        &mut self,
+        path: &Path,
+        (old_directory, new_directory): &Directories,
+        kind: DependencyKind,
+    ) -> PathBuf {
+        if self.handling == ResourceHandling::Reference {
+            return path.to_owned();
+        }
+        let path = normalize_path(path);
+        if let Some(target) = self.targets.get(&path) {
+            return target.clone();
+        }
+
+        let relative = relative_path(&path, old_directory);
+        let escapes = relative
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+        let mut target = if escapes {
+            new_directory.join(path.file_name().unwrap_or(path.as_os_str()))
+        } else {
+            new_directory.join(relative)
+        };
+        target = normalize_path(&target);
+        // Files gathered from different directories may have the same name
+        if self.used.contains(&target) {
+            let stem = target
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let extension = target
+                .extension()
+                .map(|extension| format!(".{}", extension.to_string_lossy()))
+                .unwrap_or_default();
+            let original = target.clone();
+            target = (2..)
+                .map(|index| original.with_file_name(format!("{}-{}{}", stem, index, extension)))
+                .find(|candidate| !self.used.contains(candidate))
+                .unwrap();
+        }
+
+        self.used.insert(target.clone());
+        self.targets.insert(path.clone(), target.clone());
+        self.copies.push(ResourceCopy {
+            from: path,
+            to: target.clone(),
+            kind,
+        });
+        target
+    }
+
+    /// Rebases an external tileset and the images it uses. The images are kept at the same place
+    /// relative to the tileset.
+    fn tileset(&mut self, tileset: &mut Tileset, directories: &Directories) {
+        let old_directory = parent(&tileset.source).to_owned();
+        tileset.source = self.rebase(&tileset.source, directories, DependencyKind::Tileset);
+        let new_directory = parent(&tileset.source).to_owned();
+        self.tileset_images(tileset, &(old_directory, new_directory));
+    }
+
+    fn tileset_images(&mut self, tileset: &mut Tileset, directories: &Directories) {
+        if let Some(image) = &mut tileset.image {
+            image.source = self.rebase(&image.source, directories, DependencyKind::Image);
+        }
+        for tile in tileset.tiles.values_mut() {
+            if let Some(image) = &mut tile.image {
+                image.source = self.rebase(&image.source, directories, DependencyKind::Image);
+            }
+        }
+    }
+
+    fn layers(&mut self, layers: &mut [LayerData], directories: &Directories) {
+        for layer in layers {
+            match &mut layer.layer_type {
+                LayerDataType::Image(data) => {
+                    if let Some(image) = &mut data.image {
+                        image.source =
+                            self.rebase(&image.source, directories, DependencyKind::Image);
+                    }
+                }
+                LayerDataType::Objects(data) => {
+                    for object in &mut data.objects {
+                        let overridden_tile = object
+                            .template
+                            .as_mut()
+                            .and_then(|instance| instance.overrides.tile.as_mut());
+                        for tile in object.tile.iter_mut().chain(overridden_tile) {
+                            if let TilesetLocation::Template(tileset) = &mut tile.tileset_location {
+                                self.tileset(Arc::make_mut(tileset), directories);
+                            }
+                        }
+                    }
+                }
+                LayerDataType::Group(data) => self.layers(&mut data.layers, directories),
+                LayerDataType::Tiles(_) => {}
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Changing the size of maps, like the Resize Map dialog of Tiled.
+
+use crate::{
+    ChunkData, InfiniteTileLayerData, LayerData, LayerDataType, Map, ObjectShape, Orientation,
+    StaggerAxis, TileLayerData,
+};
+
+/// Which part of a map stays in place when resizing it with [`Map::resize()`], the rest of the
+/// map growing or shrinking around it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[allow(missing_docs)]
+pub enum ResizeAnchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl ResizeAnchor {
+    /// How many halves of the change in size the contents of the map move by, horizontally and
+    /// vertically.
+    fn halves(self) -> (i64, i64) {
+        match self {
+            ResizeAnchor::TopLeft => (0, 0),
+            ResizeAnchor::Top => (1, 0),
+            ResizeAnchor::TopRight => (2, 0),
+            ResizeAnchor::Left => (0, 1),
+            ResizeAnchor::Center => (1, 1),
+            ResizeAnchor::Right => (2, 1),
+            ResizeAnchor::BottomLeft => (0, 2),
+            ResizeAnchor::Bottom => (1, 2),
+            ResizeAnchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+/// An area of a map, in tiles, as given to [`Map::crop()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct MapArea {
+    /// The horizontal position of the left edge of the area.
+    pub x: i32,
+    /// The vertical position of the top edge of the area.
+    pub y: i32,
+    /// The width of the area.
+    pub width: u32,
+    /// The height of the area.
+    pub height: u32,
+}
+
+impl Map {
+    /// Changes the size of the map to `width` by `height` tiles, keeping the part of the map
+    /// given by `anchor` in place, as the Resize Map dialog of Tiled does. This is
+    /// [`Map::crop()`] with the area the old map is moved into.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, ResizeAnchor};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    /// let corner = map.get_layer(0).unwrap().as_tile_layer().unwrap().get_tile(0, 0).map(|tile| tile.id());
+    ///
+    /// // Add two columns to the left of the map
+    /// let (width, height) = (map.width + 2, map.height);
+    /// map.resize(width, height, ResizeAnchor::Right);
+    ///
+    /// let tiles = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    /// assert_eq!(tiles.width(), Some(width));
+    /// assert_eq!(tiles.get_tile(2, 0).map(|tile| tile.id()), corner);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resize(&mut self, width: u32, height: u32, anchor: ResizeAnchor) {
+        let (halves_x, halves_y) = anchor.halves();
+        let offset = |new: u32, old: u32, halves: i64| {
+            ((i64::from(new) - i64::from(old)) * halves / 2) as i32
+        };
+        self.crop(MapArea {
+            x: -offset(width, self.width, halves_x),
+            y: -offset(height, self.height, halves_y),
+            width,
+            height,
+        });
+    }
+
+    /// Reduces the map to the area `rect`, which may also extend past its edges to grow it, and
+    /// moves the contents of the map so that the top left corner of `rect` becomes its origin.
+    ///
+    /// The tiles of all tile layers are moved, and those outside of the new area are removed,
+    /// including in infinite maps whose chunks are rebuilt to cover only the tiles left. Objects
+    /// and image layers are moved by the matching amount of pixels, and are kept even if they end
+    /// up outside of the map. On staggered and hexagonal maps, moving by an odd number of rows or
+    /// columns along the stagger axis also changes which of them are shifted.
+    pub fn crop(&mut self, rect: MapArea) {
+        let pixel_offset = self.pixel_offset(-rect.x, -rect.y);
+        crop_layers(&mut self.layers, rect, pixel_offset);
+        self.width = rect.width;
+        self.height = rect.height;
+    }
+
+    /// The amount of pixels objects move by when the tiles of the map move by the given amount
+    /// of tiles.
+    pub(crate) fn pixel_offset(&self, x: i32, y: i32) -> (f32, f32) {
+        let (x, y) = (x as f32, y as f32);
+        let (tile_width, tile_height) = (self.tile_width as f32, self.tile_height as f32);
+        match self.orientation {
+            Orientation::Orthogonal => (x * tile_width, y * tile_height),
+            // Objects of isometric maps are positioned along the axes of the tiles, both of which
+            // are measured in tile heights
+            Orientation::Isometric => (x * tile_height, y * tile_height),
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let side = if self.orientation == Orientation::Hexagonal {
+                    self.hex_side_length.unwrap_or(0) as f32
+                } else {
+                    0.
+                };
+                match self.stagger_axis {
+                    StaggerAxis::X => (x * (tile_width + side) / 2., y * tile_height),
+                    StaggerAxis::Y => (x * tile_width, y * (tile_height + side) / 2.),
+                }
+            }
+        }
+    }
+}
+
+fn crop_layers(layers: &mut [LayerData], rect: MapArea, (offset_x, offset_y): (f32, f32)) {
+    let contains =
+        |x: i32, y: i32| x >= 0 && y >= 0 && (x as u32) < rect.width && (y as u32) < rect.height;
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                let mut tiles = vec![0; rect.width as usize * rect.height as usize];
+                for y in 0..rect.height {
+                    for x in 0..rect.width {
+                        let (old_x, old_y) = (x as i32 + rect.x, y as i32 + rect.y);
+                        if old_x >= 0
+                            && old_y >= 0
+                            && (old_x as u32) < data.width
+                            && (old_y as u32) < data.height
+                        {
+                            tiles[y as usize * rect.width as usize + x as usize] =
+                                data.tiles[old_y as usize * data.width as usize + old_x as usize];
+                        }
+                    }
+                }
+                data.tiles = tiles;
+                data.width = rect.width;
+                data.height = rect.height;
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                let mut cropped = InfiniteTileLayerData {
+                    chunks: Default::default(),
+                    encoding: data.encoding,
+                };
+                for (&(chunk_x, chunk_y), chunk) in &data.chunks {
+                    for (index, tile) in chunk.tiles.iter().enumerate() {
+                        let x = chunk_x * ChunkData::WIDTH as i32 - rect.x
+                            + (index % ChunkData::WIDTH as usize) as i32;
+                        let y = chunk_y * ChunkData::HEIGHT as i32 - rect.y
+                            + (index / ChunkData::WIDTH as usize) as i32;
+                        if tile.is_some() && contains(x, y) {
+                            cropped.set_tile_data(x, y, *tile);
+                        }
+                    }
+                }
+                *data = cropped;
+            }
+            LayerDataType::Objects(data) => {
+                for object in &mut data.objects {
+                    object.x += offset_x;
+                    object.y += offset_y;
+                    if let ObjectShape::Point(x, y) = &mut object.shape {
+                        *x += offset_x;
+                        *y += offset_y;
+                    }
+                }
+            }
+            LayerDataType::Image(_) => {
+                layer.offset_x += offset_x;
+                layer.offset_y += offset_y;
+            }
+            LayerDataType::Group(data) => crop_layers(&mut data.layers, rect, (offset_x, offset_y)),
+        }
+    }
+}
@@ -0,0 +1,203 @@
+//! Statistics about the contents of a [`Map`].
+
+use std::{collections::HashMap, mem::size_of};
+
+use crate::{
+    ChunkData, FiniteTileLayerData, InfiniteTileLayerData, LayerData, LayerDataType, LayerTileData,
+    Map, ObjectData, ObjectShape, Properties, PropertyValue, TileId, TileLayerData,
+};
+
+/// Statistics about the contents of a [`Map`], as returned by [`Map::stats()`].
+#[derive(Debug, Clone, PartialEq, Default)]
+#[non_exhaustive]
+pub struct MapStats {
+    /// How many times each tile is placed in the map's tile layers, keyed by the index of its
+    /// tileset within [`Map::tilesets()`] and its ID within that tileset, which is what the GID of
+    /// a tile refers to. Tiles that aren't used don't appear.
+    pub tile_usage: HashMap<(usize, TileId), usize>,
+    /// The number of objects of each class, in all object layers. Objects without a class are
+    /// counted under the empty string.
+    pub objects_by_class: HashMap<String, usize>,
+    /// Statistics about each layer of the map, including the ones within group layers, in the
+    /// order they appear in the file.
+    pub layers: Vec<LayerStats>,
+}
+
+impl MapStats {
+    /// The approximate amount of memory used by the layers of the map, in bytes.
+    pub fn layer_memory(&self) -> usize {
+        self.layers.iter().map(|layer| layer.memory).sum()
+    }
+}
+
+/// Statistics about a single layer of a [`Map`], part of [`MapStats`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LayerStats {
+    /// The ID of the layer.
+    pub id: u32,
+    /// The name of the layer.
+    pub name: String,
+    /// The number of cells of the layer if it is a tile layer, or 0 otherwise. For infinite
+    /// layers, these are the cells of the chunks the layer contains.
+    pub cells: usize,
+    /// The number of cells of the layer that contain a tile.
+    pub tiles: usize,
+    /// The number of objects in the layer if it is an object layer, or 0 otherwise.
+    pub objects: usize,
+    /// The approximate amount of memory used by the layer, in bytes. Doesn't include the layers
+    /// within it if it is a group layer, which have their own statistics.
+    pub memory: usize,
+}
+
+impl LayerStats {
+    /// The proportion of the layer's cells that are empty, between 0 and 1, or [`None`] if it has
+    /// no cells.
+    pub fn empty_cell_ratio(&self) -> Option<f32> {
+        if self.cells == 0 {
+            None
+        } else {
+            Some((self.cells - self.tiles) as f32 / self.cells as f32)
+        }
+    }
+}
+
+impl Map {
+    /// Gathers statistics about the contents of this map, such as how often each tile is used or
+    /// how much memory each layer takes, e.g. to find tiles that could be removed from a tileset.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_zlib.tmx")?;
+    /// let stats = map.stats();
+    ///
+    /// for layer in &stats.layers {
+    ///     if let Some(ratio) = layer.empty_cell_ratio() {
+    ///         println!("{}: {}% empty", layer.name, ratio * 100.0);
+    ///     }
+    /// }
+    /// println!("{} different tiles used", stats.tile_usage.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn stats(&self) -> MapStats {
+        let mut stats = MapStats::default();
+        collect_layer_stats(&self.layers, &mut stats);
+        stats
+    }
+}
+
+fn collect_layer_stats(layers: &[LayerData], stats: &mut MapStats) {
+    for layer in layers {
+        let mut layer_stats = LayerStats {
+            id: layer.id,
+            name: layer.name.clone(),
+            cells: 0,
+            tiles: 0,
+            objects: 0,
+            memory: size_of::<LayerData>()
+                + layer.name.capacity()
+                + layer.user_type.as_ref().map_or(0, String::capacity)
+                + properties_memory(&layer.properties),
+        };
+        let tile_usage = &mut stats.tile_usage;
+        let mut count_tile = |tile: Option<LayerTileData>, layer_stats: &mut LayerStats| {
+            if let Some(tile) = tile {
+                layer_stats.tiles += 1;
+                *tile_usage
+                    .entry((tile.tileset_index(), tile.id()))
+                    .or_insert(0) += 1;
+            }
+        };
+
+        match &layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                layer_stats.cells = data.tiles.len();
+                layer_stats.memory += finite_memory(data);
+                for index in 0..data.tiles.len() {
+                    count_tile(data.tile_data_at(index), &mut layer_stats);
+                }
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                layer_stats.cells = data.chunks.len() * ChunkData::TILE_COUNT;
+                layer_stats.memory += infinite_memory(data);
+                for (_, chunk) in data.chunk_data() {
+                    for y in 0..ChunkData::HEIGHT as i32 {
+                        for x in 0..ChunkData::WIDTH as i32 {
+                            count_tile(chunk.get_tile_data(x, y), &mut layer_stats);
+                        }
+                    }
+                }
+            }
+            LayerDataType::Objects(data) => {
+                layer_stats.objects = data.objects.len();
+                for object in &data.objects {
+                    layer_stats.memory += object_memory(object);
+                    *stats
+                        .objects_by_class
+                        .entry(object.user_type.clone())
+                        .or_insert(0) += 1;
+                }
+            }
+            LayerDataType::Image(data) => {
+                if let Some(image) = &data.image {
+                    layer_stats.memory += image.source.as_os_str().len();
+                }
+            }
+            LayerDataType::Group(_) => {}
+        }
+
+        stats.layers.push(layer_stats);
+        if let LayerDataType::Group(data) = &layer.layer_type {
+            collect_layer_stats(&data.layers, stats);
+        }
+    }
+}
+
+fn finite_memory(data: &FiniteTileLayerData) -> usize {
+    (data.tiles.capacity() + data.first_gids.capacity()) * size_of::<u32>()
+}
+
+fn infinite_memory(data: &InfiniteTileLayerData) -> usize {
+    // Chunks are boxed, so both the entry of the map and the chunk itself count
+    data.chunks.capacity() * size_of::<((i32, i32), ChunkData)>()
+        + data.chunks.len() * ChunkData::TILE_COUNT * size_of::<Option<LayerTileData>>()
+}
+
+fn object_memory(object: &ObjectData) -> usize {
+    let shape = match &object.shape {
+        ObjectShape::Polygon { points } | ObjectShape::Polyline { points } => {
+            points.capacity() * size_of::<(f32, f32)>()
+        }
+        ObjectShape::Text {
+            font_family, text, ..
+        } => font_family.capacity() + text.capacity(),
+        _ => 0,
+    };
+    size_of::<ObjectData>()
+        + object.name.capacity()
+        + object.user_type.capacity()
+        + shape
+        + properties_memory(&object.properties)
+}
+
+fn properties_memory(properties: &Properties) -> usize {
+    // Property names are shared, so they aren't counted
+    properties.capacity() * size_of::<(std::sync::Arc<str>, PropertyValue)>()
+        + properties
+            .values()
+            .map(|value| match value {
+                PropertyValue::StringValue(value) | PropertyValue::FileValue(value) => {
+                    value.capacity()
+                }
+                PropertyValue::ClassValue {
+                    property_type,
+                    properties,
+                } => property_type.capacity() + properties_memory(properties),
+                _ => 0,
+            })
+            .sum::<usize>()
+}
@@ -0,0 +1,70 @@
+//! Updating the objects of a map after one of their templates changed.
+
+use std::path::Path;
+
+use crate::{util::normalize_path, LayerData, LayerDataType, Map, Template};
+
+impl Map {
+    /// Resolves all the objects of the map instantiated from the template at `template_path`
+    /// again from `template`, e.g. after the template file changed on disk, and returns how many
+    /// objects were updated.
+    ///
+    /// Objects keep the values they set themselves in the map, such as their position and the
+    /// properties they override, and take all others from the new template, as if the map had
+    /// been loaded again. `template_path` is compared to the paths the templates of objects were
+    /// loaded from once both are normalized.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tiled::{Loader, ResourceCache, Template};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let mut map = loader.load_tmx_map("assets/tiled_object_template.tmx")?;
+    ///
+    /// // Usually loaded again from the modified file
+    /// let template = loader.cache().get_template("assets/tiled_object_template.tx").unwrap();
+    /// let mut template = Template::clone(&template);
+    /// template.object.visible = false;
+    ///
+    /// assert!(map.reapply_template("assets/tiled_object_template.tx", &template) > 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reapply_template(
+        &mut self,
+        template_path: impl AsRef<Path>,
+        template: &Template,
+    ) -> usize {
+        let template_path = normalize_path(template_path.as_ref());
+        reapply_in(&mut self.layers, &template_path, template)
+    }
+}
+
+fn reapply_in(layers: &mut [LayerData], template_path: &Path, template: &Template) -> usize {
+    let mut updated = 0;
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Objects(data) => {
+                for object in &mut data.objects {
+                    let instance = match object.template.take() {
+                        Some(instance) => instance,
+                        None => continue,
+                    };
+                    if normalize_path(&instance.source) == template_path {
+                        object.apply_template(template, &instance.overrides);
+                        updated += 1;
+                    }
+                    object.template = Some(instance);
+                }
+                data.objects_changed();
+            }
+            LayerDataType::Group(data) => {
+                updated += reapply_in(&mut data.layers, template_path, template)
+            }
+            LayerDataType::Tiles(_) | LayerDataType::Image(_) => {}
+        }
+    }
+    updated
+}
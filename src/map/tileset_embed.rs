@@ -0,0 +1,134 @@
+//! Embedding external tilesets into a [`Map`] and exporting embedded ones.
+
+use std::{path::PathBuf, sync::Arc};
+
+use crate::{EmbedTilesetError, Map};
+
+/// How a tileset of a map is stored, chosen for each tileset with [`Map::set_tileset_storage()`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TilesetStorage {
+    /// The tileset is embedded in the map file.
+    Embedded,
+    /// The map references an existing `.tsx` file at the given path, which holds the tileset.
+    Reference(PathBuf),
+    /// The map references a new `.tsx` file at the given path, which the tileset must be written
+    /// to alongside the map.
+    Export(PathBuf),
+}
+
+impl Map {
+    /// Returns whether the tileset at `index` within [`Map::tilesets()`] is embedded in the map
+    /// rather than stored in a `.tsx` file of its own, or `None` if there is no such tileset.
+    ///
+    /// Embedded tilesets are those whose [`Tileset::source`](crate::Tileset::source) is the
+    /// [source of the map](Map::source).
+    pub fn is_tileset_embedded(&self, index: usize) -> Option<bool> {
+        self.tilesets
+            .get(index)
+            .map(|tileset| tileset.source == self.source)
+    }
+
+    /// Sets how the tileset at `index` within [`Map::tilesets()`] is stored, so that pipelines can
+    /// enforce their preferred layout whatever the layout of the maps they are given.
+    ///
+    /// Unlike [`Map::embed_tileset()`] and [`Map::export_tileset()`], this succeeds if the tileset
+    /// is already stored as requested. [`TilesetStorage::Reference`] and
+    /// [`TilesetStorage::Export`] both make the map reference the path given; no file is read or
+    /// written. Returns true if the tileset must be written to a file of its own, i.e. for
+    /// [`TilesetStorage::Export`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, TilesetStorage};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+    ///
+    /// // Ship every tileset in a file of its own
+    /// for index in 0..map.tilesets().len() {
+    ///     let path = format!("assets/tilesets/{}.tsx", map.tilesets()[index].name);
+    ///     map.set_tileset_storage(index, TilesetStorage::Export(path.into()))?;
+    /// }
+    /// assert_eq!(map.is_tileset_embedded(0), Some(false));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_tileset_storage(
+        &mut self,
+        index: usize,
+        storage: TilesetStorage,
+    ) -> Result<bool, EmbedTilesetError> {
+        if index >= self.tilesets.len() {
+            return Err(EmbedTilesetError::TilesetNotFound(index));
+        }
+        let (source, export) = match storage {
+            TilesetStorage::Embedded => (self.source.clone(), false),
+            TilesetStorage::Reference(path) | TilesetStorage::Export(path)
+                if path == self.source =>
+            {
+                return Err(EmbedTilesetError::PathIsMap(path))
+            }
+            TilesetStorage::Reference(path) => (path, false),
+            TilesetStorage::Export(path) => (path, true),
+        };
+        if self.tilesets[index].source != source {
+            Arc::make_mut(&mut self.tilesets[index]).source = source;
+        }
+        Ok(export)
+    }
+
+    /// Embeds the external tileset at `index` within [`Map::tilesets()`] into the map, like the
+    /// "Embed Tileset" action of Tiled. The map no longer references the `.tsx` file the tileset
+    /// was loaded from and stores the tileset itself instead.
+    ///
+    /// The paths of the images of the tileset don't need rewriting, since they are relative to
+    /// the working directory rather than to the tileset file.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// assert_eq!(map.is_tileset_embedded(0), Some(false));
+    ///
+    /// map.embed_tileset(0)?;
+    /// assert_eq!(map.is_tileset_embedded(0), Some(true));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn embed_tileset(&mut self, index: usize) -> Result<(), EmbedTilesetError> {
+        match self.is_tileset_embedded(index) {
+            None => return Err(EmbedTilesetError::TilesetNotFound(index)),
+            Some(true) => return Err(EmbedTilesetError::AlreadyEmbedded(index)),
+            Some(false) => {}
+        }
+        Arc::make_mut(&mut self.tilesets[index]).source = self.source.clone();
+        Ok(())
+    }
+
+    /// Turns the tileset embedded in the map at `index` within [`Map::tilesets()`] into an
+    /// external tileset stored at `path`, like the "Export Tileset As" action of Tiled. The map
+    /// references the tileset by that path from then on.
+    ///
+    /// This doesn't write the tileset file, which must be stored at `path` for the map to load
+    /// once saved. Fails if the tileset isn't embedded or if `path` is the path of the map.
+    pub fn export_tileset(
+        &mut self,
+        index: usize,
+        path: impl Into<PathBuf>,
+    ) -> Result<(), EmbedTilesetError> {
+        match self.is_tileset_embedded(index) {
+            None => return Err(EmbedTilesetError::TilesetNotFound(index)),
+            Some(false) => return Err(EmbedTilesetError::AlreadyExternal(index)),
+            Some(true) => {}
+        }
+        let path = path.into();
+        if path == self.source {
+            return Err(EmbedTilesetError::PathIsMap(path));
+        }
+        Arc::make_mut(&mut self.tilesets[index]).source = path;
+        Ok(())
+    }
+}
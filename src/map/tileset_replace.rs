@@ -0,0 +1,169 @@
+//! Replacement of the tilesets of a loaded [`Map`].
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::{
+    canonical_first_gids, LayerData, LayerDataType, Map, ReplaceTilesetError, TileId,
+    TileLayerData, Tileset, TilesetLocation,
+};
+
+impl Map {
+    /// Replaces the tileset at `index` within [`Map::tilesets()`] with another one, e.g. after its
+    /// file has changed on disk. Tiles and tile objects using the tileset keep their IDs and use
+    /// the new tileset from then on. Returns the tileset that was replaced.
+    ///
+    /// Fails without changing the map if any of these tiles doesn't exist in the new tileset. Use
+    /// [`Map::replace_tileset_remapped()`] if the IDs of the tiles have changed.
+    ///
+    /// ## Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let mut loader = Loader::new();
+    /// let mut map = loader.load_tmx_map("assets/tiled_base64_external.tmx")?;
+    ///
+    /// // Later on, once the tileset has been edited
+    /// let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx")?;
+    /// let old = map.replace_tileset(0, Arc::new(tileset))?;
+    /// assert_eq!(old.name, map.tilesets()[0].name);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn replace_tileset(
+        &mut self,
+        index: usize,
+        tileset: Arc<Tileset>,
+    ) -> Result<Arc<Tileset>, ReplaceTilesetError> {
+        self.replace_tileset_remapped(index, tileset, |id| id)
+    }
+
+    /// Replaces the tileset at `index` within [`Map::tilesets()`] with another one, like
+    /// [`Map::replace_tileset()`], giving every tile and tile object that uses it the ID returned
+    /// by `remap` for its current ID. Returns the tileset that was replaced.
+    ///
+    /// `remap` is called once for each different tile ID in use. Fails without changing the map if
+    /// any of the resulting IDs doesn't exist in the new tileset.
+    pub fn replace_tileset_remapped(
+        &mut self,
+        index: usize,
+        tileset: Arc<Tileset>,
+        mut remap: impl FnMut(TileId) -> TileId,
+    ) -> Result<Arc<Tileset>, ReplaceTilesetError> {
+        if index >= self.tilesets.len() {
+            return Err(ReplaceTilesetError::TilesetNotFound(index));
+        }
+
+        let mut new_ids = BTreeMap::new();
+        visit_tile_ids(&self.layers, index, &mut |id| {
+            new_ids.entry(id).or_insert_with(|| remap(id));
+        });
+        if let Some((&id, &new_id)) = new_ids
+            .iter()
+            .find(|(_, &new_id)| tileset.get_tile(new_id).is_none())
+        {
+            return Err(ReplaceTilesetError::MissingTile { id, new_id });
+        }
+
+        let old = std::mem::replace(&mut self.tilesets[index], tileset);
+        self.reset_gids();
+        if new_ids.iter().any(|(id, new_id)| id != new_id) {
+            // The new IDs may not fit between the GIDs finite layers give to this tileset and the
+            // next, so these layers get the GIDs of a freshly saved map
+            let first_gids = canonical_first_gids(&self.tilesets);
+            remap_tile_ids(&mut self.layers, index, &new_ids, &first_gids);
+        }
+        Ok(old)
+    }
+}
+
+/// Calls `visit` with the ID of every tile of the tileset at `index` used by `layers`.
+fn visit_tile_ids(layers: &[LayerData], index: usize, visit: &mut impl FnMut(TileId)) {
+    for layer in layers {
+        match &layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                for i in 0..data.tiles.len() {
+                    match data.tile_data_at(i) {
+                        Some(tile) if tile.tileset_index == index => visit(tile.id),
+                        _ => {}
+                    }
+                }
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                for chunk in data.chunks.values() {
+                    for tile in chunk.tiles.iter().flatten() {
+                        if tile.tileset_index == index {
+                            visit(tile.id);
+                        }
+                    }
+                }
+            }
+            LayerDataType::Objects(data) => {
+                for tile in data
+                    .objects
+                    .iter()
+                    .filter_map(|object| object.tile.as_ref())
+                {
+                    if matches!(tile.tileset_location, TilesetLocation::Map(i) if i == index) {
+                        visit(tile.id);
+                    }
+                }
+            }
+            LayerDataType::Group(data) => visit_tile_ids(&data.layers, index, visit),
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
+
+/// Gives the tiles of the tileset at `index` used by `layers` their ID from `new_ids`, encoding
+/// finite layers again using `first_gids`.
+fn remap_tile_ids(
+    layers: &mut [LayerData],
+    index: usize,
+    new_ids: &BTreeMap<TileId, TileId>,
+    first_gids: &[u32],
+) {
+    let remap = |id: &mut TileId| {
+        if let Some(new_id) = new_ids.get(id) {
+            *id = *new_id;
+        }
+    };
+
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                data.reencode(first_gids.to_vec(), |mut tile| {
+                    if tile.tileset_index == index {
+                        remap(&mut tile.id);
+                    }
+                    tile
+                });
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                for chunk in data.chunks.values_mut() {
+                    for tile in chunk.tiles.iter_mut().flatten() {
+                        if tile.tileset_index == index {
+                            remap(&mut tile.id);
+                        }
+                    }
+                }
+            }
+            LayerDataType::Objects(data) => {
+                for tile in data
+                    .objects
+                    .iter_mut()
+                    .filter_map(|object| object.tile.as_mut())
+                {
+                    if matches!(tile.tileset_location, TilesetLocation::Map(i) if i == index) {
+                        remap(&mut tile.id);
+                    }
+                }
+            }
+            LayerDataType::Group(data) => {
+                remap_tile_ids(&mut data.layers, index, new_ids, first_gids)
+            }
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
@@ -0,0 +1,146 @@
+//! Detection and removal of the tilesets of a [`Map`] that nothing uses.
+
+use std::sync::Arc;
+
+use crate::{LayerData, LayerDataType, Map, TileLayerData, Tileset, TilesetLocation};
+
+impl Map {
+    /// Returns the indices within [`Map::tilesets()`] of the tilesets that are used by the tiles of
+    /// this map's tile layers or by its tile objects, in increasing order.
+    ///
+    /// Tile objects that come from a template and use the template's tileset don't count, since
+    /// that tileset isn't one of the map's.
+    pub fn used_tilesets(&self) -> Vec<usize> {
+        let mut used = vec![false; self.tilesets.len()];
+        visit_tileset_indices(&self.layers, &mut |index| {
+            if let Some(used) = used.get_mut(index) {
+                *used = true;
+            }
+        });
+        used.iter()
+            .enumerate()
+            .filter_map(|(index, &used)| used.then_some(index))
+            .collect()
+    }
+
+    /// Removes the tilesets nothing in this map uses, as reported by [`Map::used_tilesets()`], and
+    /// updates the tiles of every layer and object to refer to the remaining ones. This makes maps
+    /// smaller once saved and avoids loading tilesets for nothing.
+    ///
+    /// Returns the removed tilesets, in the order they were in.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut map = Loader::new().load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")?;
+    /// for tileset in map.prune_unused_tilesets() {
+    ///     println!("removed {}", tileset.name);
+    /// }
+    /// assert_eq!(map.used_tilesets().len(), map.tilesets().len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn prune_unused_tilesets(&mut self) -> Vec<Arc<Tileset>> {
+        let used = self.used_tilesets();
+        if used.len() == self.tilesets.len() {
+            return Vec::new();
+        }
+
+        // The new index of each tileset, if it is kept
+        let mut new_indices = vec![None; self.tilesets.len()];
+        for (new_index, &index) in used.iter().enumerate() {
+            new_indices[index] = Some(new_index);
+        }
+
+        let mut removed = Vec::new();
+        let tilesets = std::mem::take(&mut self.tilesets);
+        for (index, tileset) in tilesets.into_iter().enumerate() {
+            match new_indices[index] {
+                Some(_) => self.tilesets.push(tileset),
+                None => removed.push(tileset),
+            }
+        }
+
+        remap_tileset_indices(&mut self.layers, &new_indices);
+        self.reset_gids();
+
+        removed
+    }
+}
+
+/// Calls `visit` with the index of every map tileset used by the tiles and objects of `layers`.
+fn visit_tileset_indices(layers: &[LayerData], visit: &mut impl FnMut(usize)) {
+    for layer in layers {
+        match &layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                for index in 0..data.tiles.len() {
+                    if let Some(tile) = data.tile_data_at(index) {
+                        visit(tile.tileset_index());
+                    }
+                }
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                for chunk in data.chunks.values() {
+                    for tile in chunk.tiles.iter().flatten() {
+                        visit(tile.tileset_index());
+                    }
+                }
+            }
+            LayerDataType::Objects(data) => {
+                for object in &data.objects {
+                    if let Some(TilesetLocation::Map(index)) =
+                        object.tile.as_ref().map(|tile| &tile.tileset_location)
+                    {
+                        visit(*index);
+                    }
+                }
+            }
+            LayerDataType::Group(data) => visit_tileset_indices(&data.layers, visit),
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
+
+/// Makes the tiles and objects of `layers` refer to the new index of their tileset. Indices that
+/// don't refer to any tileset are left as they are.
+fn remap_tileset_indices(layers: &mut [LayerData], new_indices: &[Option<usize>]) {
+    let remap = |index: &mut usize| {
+        if let Some(Some(new_index)) = new_indices.get(*index) {
+            *index = *new_index;
+        }
+    };
+
+    for layer in layers {
+        match &mut layer.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                // Tiles only store their GID, so removing the first GID of the removed tilesets is
+                // enough: no tile uses them, so every GID still belongs to the same tileset
+                let mut index = 0;
+                data.first_gids.retain(|_| {
+                    index += 1;
+                    new_indices.get(index - 1).is_none_or(Option::is_some)
+                });
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                for chunk in data.chunks.values_mut() {
+                    for tile in chunk.tiles.iter_mut().flatten() {
+                        remap(&mut tile.tileset_index);
+                    }
+                }
+            }
+            LayerDataType::Objects(data) => {
+                for object in &mut data.objects {
+                    if let Some(TilesetLocation::Map(index)) =
+                        object.tile.as_mut().map(|tile| &mut tile.tileset_location)
+                    {
+                        remap(index);
+                    }
+                }
+            }
+            LayerDataType::Group(data) => remap_tileset_indices(&mut data.layers, new_indices),
+            LayerDataType::Image(_) => {}
+        }
+    }
+}
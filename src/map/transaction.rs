@@ -0,0 +1,383 @@
+//! Recorded edits of the contents of a [`Map`], which can be undone and redone.
+
+use std::sync::Arc;
+
+use super::layer_edit::find_in_mut;
+use crate::{
+    EditError, LayerData, LayerDataType, LayerTileData, Map, ObjectData, Properties, PropertyValue,
+    TileLayerData,
+};
+
+type EditResult<T> = std::result::Result<T, EditError>;
+
+/// What a property changed by an [`EditTransaction`] belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyTarget {
+    /// The map itself.
+    Map,
+    /// The layer with the given ID.
+    Layer(u32),
+    /// The object with the given ID.
+    Object(u32),
+}
+
+/// A single change made by an [`EditTransaction`], along with what it replaced.
+#[derive(Debug, Clone, PartialEq)]
+enum Change {
+    Tile {
+        layer: u32,
+        x: i32,
+        y: i32,
+        old: Option<LayerTileData>,
+        new: Option<LayerTileData>,
+    },
+    AddObject {
+        layer: u32,
+        object: ObjectData,
+    },
+    RemoveObject {
+        layer: u32,
+        index: usize,
+        object: ObjectData,
+    },
+    Property {
+        target: PropertyTarget,
+        name: Arc<str>,
+        old: Option<PropertyValue>,
+        new: Option<PropertyValue>,
+    },
+}
+
+/// Edits the contents of a map while recording every change made, so that they can be rolled
+/// back or kept as an [`EditRecord`] to be undone and redone later on, e.g. to give the tools of
+/// an editor an undo stack. Created with [`Map::edit()`].
+///
+/// Changes are applied to the map straight away. Each one either succeeds or fails with an
+/// [`EditError`] without changing anything. Dropping the transaction keeps the changes made so
+/// far, without recording them.
+///
+/// ## Example
+/// ```
+/// use tiled::{LayerTileData, Loader, PropertyTarget, PropertyValue};
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut map = Loader::new().load_tmx_map("assets/tiled_base64.tmx")?;
+/// let layer = map.get_layer(0).unwrap().id();
+///
+/// let mut transaction = map.edit();
+/// transaction.set_tile(layer, 0, 0, Some(LayerTileData::new(0, 5)))?;
+/// transaction.set_property(
+///     PropertyTarget::Map,
+///     "edited",
+///     Some(PropertyValue::BoolValue(true)),
+/// )?;
+/// let record = transaction.commit();
+///
+/// record.undo(&mut map)?;
+/// let tile = map.get_layer(0).unwrap().as_tile_layer().unwrap().get_tile(0, 0);
+/// assert_ne!(tile.map(|tile| tile.id()), Some(5));
+///
+/// record.redo(&mut map)?;
+/// assert!(map.properties.contains_key("edited"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct EditTransaction<'map> {
+    map: &'map mut Map,
+    changes: Vec<Change>,
+}
+
+/// The changes made by a committed [`EditTransaction`], in the order they were made.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EditRecord {
+    changes: Vec<Change>,
+}
+
+impl Map {
+    /// Starts editing the contents of the map, recording the changes made so that they can be
+    /// undone. See [`EditTransaction`] for more information.
+    pub fn edit(&mut self) -> EditTransaction<'_> {
+        EditTransaction {
+            map: self,
+            changes: Vec::new(),
+        }
+    }
+}
+
+impl<'map> EditTransaction<'map> {
+    /// The map being edited, with the changes made so far.
+    #[inline]
+    pub fn map(&self) -> &Map {
+        self.map
+    }
+
+    /// Places a tile at the given position of the tile layer with ID `layer`, or empties it if
+    /// `tile` is [`None`]. Returns the tile that was there before.
+    ///
+    /// Infinite layers grow as needed. Fails if the tile isn't part of its tileset, or if the
+    /// position is outside of a finite layer.
+    pub fn set_tile(
+        &mut self,
+        layer: u32,
+        x: i32,
+        y: i32,
+        tile: Option<LayerTileData>,
+    ) -> EditResult<Option<LayerTileData>> {
+        let old = self.map.write_tile(layer, x, y, tile)?;
+        self.changes.push(Change::Tile {
+            layer,
+            x,
+            y,
+            old,
+            new: tile,
+        });
+        Ok(old)
+    }
+
+    /// Adds an object above all others in the object layer with ID `layer`. Returns the ID of the
+    /// object.
+    ///
+    /// Objects with an ID of 0 are given a new one. Fails if another object of the map already
+    /// has the object's ID; Objects created with [`ObjectBuilder`](crate::ObjectBuilder) for this
+    /// map have unique IDs.
+    pub fn add_object(&mut self, layer: u32, mut object: ObjectData) -> EditResult<u32> {
+        if object.id == 0 {
+            object.id = self.map.allocate_object_id();
+        } else if object.id >= self.map.next_object_id {
+            self.map.next_object_id = object.id + 1;
+        }
+        let id = object.id;
+        self.map.insert_object(layer, None, object.clone())?;
+        self.changes.push(Change::AddObject { layer, object });
+        Ok(id)
+    }
+
+    /// Removes the object with ID `id` from wherever it is in the map, returning it.
+    pub fn remove_object(&mut self, id: u32) -> EditResult<ObjectData> {
+        let (layer, index, object) = self.map.take_object(id)?;
+        self.changes.push(Change::RemoveObject {
+            layer,
+            index,
+            object: object.clone(),
+        });
+        Ok(object)
+    }
+
+    /// Sets the custom property `name` of `target` to `value`, or removes it if `value` is
+    /// [`None`]. Returns the previous value of the property.
+    pub fn set_property(
+        &mut self,
+        target: PropertyTarget,
+        name: impl Into<Arc<str>>,
+        value: Option<PropertyValue>,
+    ) -> EditResult<Option<PropertyValue>> {
+        let name = name.into();
+        let old = self.map.write_property(target, &name, value.clone())?;
+        self.changes.push(Change::Property {
+            target,
+            name,
+            old: old.clone(),
+            new: value,
+        });
+        Ok(old)
+    }
+
+    /// Stops editing the map, returning the changes made so that they can be undone.
+    pub fn commit(self) -> EditRecord {
+        EditRecord {
+            changes: self.changes,
+        }
+    }
+
+    /// Undoes every change made by this transaction, leaving the map as it was when it started
+    /// apart from the object IDs it reserved, which Tiled doesn't give out again either.
+    pub fn rollback(self) {
+        for change in self.changes.iter().rev() {
+            // The transaction borrows the map mutably, so nothing else can have changed it since
+            undo(self.map, change).expect("changes made by a transaction can always be undone");
+        }
+    }
+}
+
+impl EditRecord {
+    /// Returns true if no changes were recorded.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The number of changes recorded.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Undoes the recorded changes, from the last one to the first, giving back to everything
+    /// that was changed its previous contents.
+    ///
+    /// The map should be in the state the transaction left it in, e.g. after undoing the records
+    /// committed after this one. Otherwise, this fails at the first change that can't be undone,
+    /// such as one made on a layer that was removed since, and the changes after it stay undone.
+    pub fn undo(&self, map: &mut Map) -> EditResult<()> {
+        self.changes
+            .iter()
+            .rev()
+            .try_for_each(|change| undo(map, change))
+    }
+
+    /// Makes the recorded changes again, e.g. after undoing them with [`EditRecord::undo()`].
+    ///
+    /// Like [`EditRecord::undo()`], this fails at the first change that can't be made, in which
+    /// case the changes before it stay made.
+    pub fn redo(&self, map: &mut Map) -> EditResult<()> {
+        self.changes.iter().try_for_each(|change| redo(map, change))
+    }
+}
+
+fn undo(map: &mut Map, change: &Change) -> EditResult<()> {
+    match change {
+        Change::Tile {
+            layer, x, y, old, ..
+        } => map.write_tile(*layer, *x, *y, *old).map(drop),
+        Change::AddObject { object, .. } => map.take_object(object.id).map(drop),
+        Change::RemoveObject {
+            layer,
+            index,
+            object,
+        } => map.insert_object(*layer, Some(*index), object.clone()),
+        Change::Property {
+            target, name, old, ..
+        } => map.write_property(*target, name, old.clone()).map(drop),
+    }
+}
+
+fn redo(map: &mut Map, change: &Change) -> EditResult<()> {
+    match change {
+        Change::Tile {
+            layer, x, y, new, ..
+        } => map.write_tile(*layer, *x, *y, *new).map(drop),
+        Change::AddObject { layer, object } => map.insert_object(*layer, None, object.clone()),
+        Change::RemoveObject { object, .. } => map.take_object(object.id).map(drop),
+        Change::Property {
+            target, name, new, ..
+        } => map.write_property(*target, name, new.clone()).map(drop),
+    }
+}
+
+impl Map {
+    fn write_tile(
+        &mut self,
+        layer: u32,
+        x: i32,
+        y: i32,
+        tile: Option<LayerTileData>,
+    ) -> EditResult<Option<LayerTileData>> {
+        if let Some(tile) = tile {
+            let tileset = self
+                .tilesets
+                .get(tile.tileset_index)
+                .ok_or(EditError::TilesetNotFound(tile.tileset_index))?;
+            if tileset.get_tile(tile.id).is_none() {
+                return Err(EditError::TileNotFound {
+                    tileset_index: tile.tileset_index,
+                    id: tile.id,
+                });
+            }
+        }
+
+        let data = find_in_mut(&mut self.layers, layer).ok_or(EditError::LayerNotFound(layer))?;
+        match &mut data.layer_type {
+            LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                if x < 0 || y < 0 || x as u32 >= data.width || y as u32 >= data.height {
+                    return Err(EditError::OutOfBounds { layer, x, y });
+                }
+                let index = x as usize + y as usize * data.width as usize;
+                Ok(data.set_tile_data_at(index, tile, &self.tilesets))
+            }
+            LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                Ok(data.set_tile_data(x, y, tile))
+            }
+            _ => Err(EditError::WrongLayerType(layer)),
+        }
+    }
+
+    /// Inserts an object at `index` within the object layer with ID `layer`, or at its end if
+    /// `index` is [`None`] or past it.
+    fn insert_object(
+        &mut self,
+        layer: u32,
+        index: Option<usize>,
+        object: ObjectData,
+    ) -> EditResult<()> {
+        if find_object(&mut self.layers, object.id).is_some() {
+            return Err(EditError::DuplicateObject(object.id));
+        }
+        let data = find_in_mut(&mut self.layers, layer).ok_or(EditError::LayerNotFound(layer))?;
+        match &mut data.layer_type {
+            LayerDataType::Objects(data) => {
+                let index = index.map_or(data.objects.len(), |index| index.min(data.objects.len()));
+                data.objects.insert(index, object);
+                data.objects_changed();
+                Ok(())
+            }
+            _ => Err(EditError::WrongLayerType(layer)),
+        }
+    }
+
+    /// Removes the object with ID `id`, returning the ID of its layer, its index within it and
+    /// the object itself.
+    fn take_object(&mut self, id: u32) -> EditResult<(u32, usize, ObjectData)> {
+        fn take_from(layers: &mut [LayerData], id: u32) -> Option<(u32, usize, ObjectData)> {
+            layers
+                .iter_mut()
+                .find_map(|layer| match &mut layer.layer_type {
+                    LayerDataType::Objects(data) => {
+                        let index = data.objects.iter().position(|object| object.id == id)?;
+                        let object = data.objects.remove(index);
+                        data.objects_changed();
+                        Some((layer.id, index, object))
+                    }
+                    LayerDataType::Group(data) => take_from(&mut data.layers, id),
+                    _ => None,
+                })
+        }
+
+        take_from(&mut self.layers, id).ok_or(EditError::ObjectNotFound(id))
+    }
+
+    fn write_property(
+        &mut self,
+        target: PropertyTarget,
+        name: &Arc<str>,
+        value: Option<PropertyValue>,
+    ) -> EditResult<Option<PropertyValue>> {
+        let properties: &mut Properties = match target {
+            PropertyTarget::Map => &mut self.properties,
+            PropertyTarget::Layer(id) => {
+                &mut find_in_mut(&mut self.layers, id)
+                    .ok_or(EditError::LayerNotFound(id))?
+                    .properties
+            }
+            PropertyTarget::Object(id) => {
+                &mut find_object(&mut self.layers, id)
+                    .ok_or(EditError::ObjectNotFound(id))?
+                    .properties
+            }
+        };
+        Ok(match value {
+            Some(value) => properties.insert(name.clone(), value),
+            None => properties.shift_remove(name),
+        })
+    }
+}
+
+fn find_object(layers: &mut [LayerData], id: u32) -> Option<&mut ObjectData> {
+    layers
+        .iter_mut()
+        .find_map(|layer| match &mut layer.layer_type {
+            LayerDataType::Objects(data) => data.objects.iter_mut().find(|object| object.id == id),
+            LayerDataType::Group(data) => find_object(&mut data.layers, id),
+            _ => None,
+        })
+}
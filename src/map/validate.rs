@@ -0,0 +1,404 @@
+//! Sanity checks over the contents of a [`Map`].
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    ChunkData, LayerData, LayerDataType, LayerTileData, Map, ObjectData, ObjectShape,
+    ResourceReader, TileId, TileLayerData, TilesetLocation,
+};
+
+/// Pixel coordinates past this value cannot be represented exactly by an `f32` anymore.
+const MAX_EXACT_PIXEL_COORDINATE: i64 = 1 << f32::MANTISSA_DIGITS;
+
+/// A problem found in a [`Map`] by [`Map::validate()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// A tile layer contains a tile that does not exist in the tileset it refers to. This happens
+    /// when its GID is not covered by any of the map's tilesets.
+    MissingTile {
+        /// The ID of the layer containing the tile.
+        layer_id: u32,
+        /// The position of the tile within the layer.
+        x: i32,
+        /// The position of the tile within the layer.
+        y: i32,
+        /// The index of the tileset the tile refers to, within the map's tilesets.
+        tileset_index: usize,
+        /// The local ID of the tile that does not exist.
+        tile_id: TileId,
+    },
+    /// A tile object refers to a tile that does not exist in its tileset, which is either one of
+    /// the map's or the one of the object's template.
+    MissingObjectTile {
+        /// The ID of the object.
+        object_id: u32,
+        /// The local ID of the tile that does not exist.
+        tile_id: TileId,
+    },
+    /// An object was instantiated from a template that can no longer be read, e.g. because it was
+    /// moved or deleted after the map was loaded, so that the map can't be loaded again. Only
+    /// reported by [`Map::validate_with_reader()`].
+    MissingTemplate {
+        /// The ID of the object.
+        object_id: u32,
+        /// The path of the template, as given by [`ObjectData::template_source()`].
+        path: PathBuf,
+    },
+    /// A rectangle, ellipse or text object that isn't a tile object has no area, or a polyline or
+    /// polygon doesn't have enough points to be drawn.
+    ZeroSizedObject {
+        /// The ID of the object.
+        object_id: u32,
+    },
+    /// A finite tile layer holds a different amount of tiles than its size implies.
+    TileCountMismatch {
+        /// The ID of the layer.
+        layer_id: u32,
+        /// The amount of tiles the layer should contain, i.e. its width times its height.
+        expected: usize,
+        /// The amount of tiles the layer actually contains.
+        found: usize,
+    },
+    /// A chunk of an infinite tile layer lies so far away from the origin that the pixel
+    /// coordinates of its tiles can no longer be represented exactly as `f32`.
+    ChunkOutOfRange {
+        /// The ID of the layer containing the chunk.
+        layer_id: u32,
+        /// The position of the chunk, in chunks.
+        x: i32,
+        /// The position of the chunk, in chunks.
+        y: i32,
+    },
+    /// A Wang set or one of its colors refers to a tile that does not exist in its tileset.
+    MissingWangTile {
+        /// The index of the tileset containing the Wang set, within the map's tilesets.
+        tileset_index: usize,
+        /// The index of the Wang set within the tileset.
+        wang_set: usize,
+        /// The local ID of the tile that does not exist.
+        tile_id: TileId,
+    },
+    /// A Wang tile uses a color that is not defined in its Wang set.
+    InvalidWangColor {
+        /// The index of the tileset containing the Wang set, within the map's tilesets.
+        tileset_index: usize,
+        /// The index of the Wang set within the tileset.
+        wang_set: usize,
+        /// The local ID of the Wang tile.
+        tile_id: TileId,
+        /// The color index used, starting from 1.
+        color: u8,
+    },
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationIssue::MissingTile {
+                layer_id,
+                x,
+                y,
+                tileset_index,
+                tile_id,
+            } => write!(
+                f,
+                "Tile at ({}, {}) in layer {} refers to tile {} which does not exist in tileset {}",
+                x, y, layer_id, tile_id, tileset_index
+            ),
+            ValidationIssue::MissingObjectTile { object_id, tile_id } => write!(
+                f,
+                "Object {} refers to tile {} which does not exist in its tileset",
+                object_id, tile_id
+            ),
+            ValidationIssue::MissingTemplate { object_id, path } => write!(
+                f,
+                "Object {} was instantiated from template {} which does not exist",
+                object_id,
+                path.display()
+            ),
+            ValidationIssue::ZeroSizedObject { object_id } => {
+                write!(f, "Object {} has no size", object_id)
+            }
+            ValidationIssue::TileCountMismatch {
+                layer_id,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Layer {} should contain {} tiles, but contains {}",
+                layer_id, expected, found
+            ),
+            ValidationIssue::ChunkOutOfRange { layer_id, x, y } => write!(
+                f,
+                "Chunk at ({}, {}) in layer {} is too far away from the origin",
+                x, y, layer_id
+            ),
+            ValidationIssue::MissingWangTile {
+                tileset_index,
+                wang_set,
+                tile_id,
+            } => write!(
+                f,
+                "Wang set {} of tileset {} refers to tile {} which does not exist",
+                wang_set, tileset_index, tile_id
+            ),
+            ValidationIssue::InvalidWangColor {
+                tileset_index,
+                wang_set,
+                tile_id,
+                color,
+            } => write!(
+                f,
+                "Wang tile {} in Wang set {} of tileset {} uses undefined color {}",
+                tile_id, wang_set, tileset_index, color
+            ),
+        }
+    }
+}
+
+impl Map {
+    /// Checks the map for inconsistencies that Tiled and this crate tolerate, but which are most
+    /// likely mistakes in the assets, such as tiles that do not exist in their tileset or objects
+    /// without a size. Returns every issue found; An empty list means that the map is fine.
+    ///
+    /// Problems that prevent a map from loading at all, such as missing tileset or template files,
+    /// are reported as an [`Error`](crate::Error) by the [`Loader`](crate::Loader) instead. Use
+    /// [`Map::validate_with_reader()`] to also check that templates still exist.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// for issue in map.validate() {
+    ///     eprintln!("{}: {}", map.source.display(), issue);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        self.validate_tilesets(&mut issues);
+        self.validate_layers(&self.layers, &mut None, &mut issues);
+        issues
+    }
+
+    /// Checks the map like [`Map::validate()`] does, and also reports the objects whose template
+    /// can't be read by `reader` anymore as [`ValidationIssue::MissingTemplate`]. Use the same
+    /// kind of reader the map was loaded with, since templates are read from the paths the loader
+    /// resolved them to.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{FilesystemResourceReader, Loader};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_template.tmx")?;
+    /// assert!(map
+    ///     .validate_with_reader(&mut FilesystemResourceReader::new())
+    ///     .is_empty());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate_with_reader(&self, reader: &mut impl ResourceReader) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        self.validate_tilesets(&mut issues);
+        let mut checked = HashMap::new();
+        let mut template_exists = |path: &Path| {
+            *checked
+                .entry(path.to_owned())
+                .or_insert_with(|| reader.read_from(path).is_ok())
+        };
+        self.validate_layers(&self.layers, &mut Some(&mut template_exists), &mut issues);
+        issues
+    }
+
+    fn validate_tilesets(&self, issues: &mut Vec<ValidationIssue>) {
+        for (tileset_index, tileset) in self.tilesets.iter().enumerate() {
+            for (wang_set_index, wang_set) in tileset.wang_sets.iter().enumerate() {
+                let mut referenced_tiles: Vec<TileId> = wang_set
+                    .tile
+                    .into_iter()
+                    .chain(wang_set.wang_colors.iter().filter_map(|color| color.tile))
+                    .chain(wang_set.wang_tiles.keys().copied())
+                    .filter(|id| tileset.get_tile(*id).is_none())
+                    .collect();
+                referenced_tiles.sort_unstable();
+                referenced_tiles.dedup();
+                issues.extend(referenced_tiles.into_iter().map(|tile_id| {
+                    ValidationIssue::MissingWangTile {
+                        tileset_index,
+                        wang_set: wang_set_index,
+                        tile_id,
+                    }
+                }));
+
+                let color_count = wang_set.wang_colors.len();
+                let mut wang_tiles: Vec<_> = wang_set.wang_tiles.iter().collect();
+                wang_tiles.sort_unstable_by_key(|(id, _)| **id);
+                for (tile_id, wang_tile) in wang_tiles {
+                    if let Some(&color) = wang_tile
+                        .wang_id
+                        .0
+                        .iter()
+                        .find(|color| **color as usize > color_count)
+                    {
+                        issues.push(ValidationIssue::InvalidWangColor {
+                            tileset_index,
+                            wang_set: wang_set_index,
+                            tile_id: *tile_id,
+                            color,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// `template_exists` tells whether the template at a path exists, if templates are checked.
+    fn validate_layers(
+        &self,
+        layers: &[LayerData],
+        template_exists: &mut Option<&mut dyn FnMut(&Path) -> bool>,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        for layer in layers {
+            match &layer.layer_type {
+                LayerDataType::Tiles(TileLayerData::Finite(data)) => {
+                    let expected = data.width as usize * data.height as usize;
+                    if data.tiles.len() != expected {
+                        issues.push(ValidationIssue::TileCountMismatch {
+                            layer_id: layer.id,
+                            expected,
+                            found: data.tiles.len(),
+                        });
+                    }
+                    for index in 0..data.tiles.len() {
+                        if let Some(tile) = data.tile_data_at(index) {
+                            let x = (index % data.width.max(1) as usize) as i32;
+                            let y = (index / data.width.max(1) as usize) as i32;
+                            self.validate_layer_tile(layer.id, x, y, &tile, issues);
+                        }
+                    }
+                }
+                LayerDataType::Tiles(TileLayerData::Infinite(data)) => {
+                    let mut chunks: Vec<_> = data.chunk_data().collect();
+                    chunks.sort_unstable_by_key(|(pos, _)| (pos.1, pos.0));
+                    for ((chunk_x, chunk_y), chunk) in chunks {
+                        if !self.chunk_in_range(chunk_x, chunk_y) {
+                            issues.push(ValidationIssue::ChunkOutOfRange {
+                                layer_id: layer.id,
+                                x: chunk_x,
+                                y: chunk_y,
+                            });
+                        }
+                        for y in 0..ChunkData::HEIGHT as i32 {
+                            for x in 0..ChunkData::WIDTH as i32 {
+                                if let Some(tile) = chunk.get_tile_data(x, y) {
+                                    self.validate_layer_tile(
+                                        layer.id,
+                                        chunk_x * ChunkData::WIDTH as i32 + x,
+                                        chunk_y * ChunkData::HEIGHT as i32 + y,
+                                        &tile,
+                                        issues,
+                                    );
+                                }
+                            }
+                        }
+                    }
+                }
+                LayerDataType::Objects(data) => {
+                    for object in &data.objects {
+                        self.validate_object(object, template_exists, issues);
+                    }
+                }
+                LayerDataType::Image(_) => {}
+                LayerDataType::Group(data) => {
+                    self.validate_layers(&data.layers, template_exists, issues)
+                }
+            }
+        }
+    }
+
+    fn validate_layer_tile(
+        &self,
+        layer_id: u32,
+        x: i32,
+        y: i32,
+        tile: &LayerTileData,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        let tileset_index = tile.tileset_index();
+        if self.tilesets[tileset_index].get_tile(tile.id()).is_none() {
+            issues.push(ValidationIssue::MissingTile {
+                layer_id,
+                x,
+                y,
+                tileset_index,
+                tile_id: tile.id(),
+            });
+        }
+    }
+
+    fn validate_object(
+        &self,
+        object: &ObjectData,
+        template_exists: &mut Option<&mut dyn FnMut(&Path) -> bool>,
+        issues: &mut Vec<ValidationIssue>,
+    ) {
+        if let (Some(path), Some(template_exists)) = (object.template_source(), template_exists) {
+            if !template_exists(path) {
+                issues.push(ValidationIssue::MissingTemplate {
+                    object_id: object.id,
+                    path: path.to_owned(),
+                });
+            }
+        }
+
+        if let Some(tile) = &object.tile {
+            let tileset = match tile.tileset_location() {
+                TilesetLocation::Map(index) => &self.tilesets[*index],
+                TilesetLocation::Template(tileset) => tileset,
+            };
+            if tileset.get_tile(tile.id()).is_none() {
+                issues.push(ValidationIssue::MissingObjectTile {
+                    object_id: object.id,
+                    tile_id: tile.id(),
+                });
+            }
+        }
+
+        // Tile objects without a size are drawn using the size of their tile instead.
+        let zero_sized = object.tile.is_none()
+            && match &object.shape {
+                ObjectShape::Rect { width, height }
+                | ObjectShape::Ellipse { width, height }
+                | ObjectShape::Text { width, height, .. } => *width <= 0. || *height <= 0.,
+                ObjectShape::Polyline { points } => points.len() < 2,
+                ObjectShape::Polygon { points } => points.len() < 3,
+                ObjectShape::Point(..) => false,
+            };
+        if zero_sized {
+            issues.push(ValidationIssue::ZeroSizedObject {
+                object_id: object.id,
+            });
+        }
+    }
+
+    fn chunk_in_range(&self, chunk_x: i32, chunk_y: i32) -> bool {
+        let in_range = |chunk: i32, chunk_size: u32, tile_size: u32| {
+            let size = chunk_size as i64 * tile_size as i64;
+            let start = chunk as i64 * size;
+            start.abs() <= MAX_EXACT_PIXEL_COORDINATE
+                && (start + size).abs() <= MAX_EXACT_PIXEL_COORDINATE
+        };
+        in_range(chunk_x, ChunkData::WIDTH, self.tile_width)
+            && in_range(chunk_y, ChunkData::HEIGHT, self.tile_height)
+    }
+}
@@ -1,19 +1,36 @@
-use std::{collections::HashMap, path::Path, sync::Arc};
+use std::{path::Path, sync::Arc};
 
 use xml::attribute::OwnedAttribute;
 
 use crate::{
+    dependencies::DependencyKind,
     error::{Error, Result},
+    parse::ParseContext,
     properties::{parse_properties, Properties},
     template::Template,
+    trace::load_span,
     util::{get_attrs, map_wrapper, parse_tag, XmlEventResult},
-    Color, Gid, MapTilesetGid, ResourceCache, ResourceReader, Tile, TileId, Tileset,
+    Color, Gid, MapTilesetGid, ResourceCache, ResourceReader, Tile, TileId, Tileset, UnknownXml,
 };
 
+mod bounds;
+mod builder;
+mod instance;
+mod sampling;
+mod text;
+mod transform;
+pub use bounds::*;
+pub use builder::*;
+pub(crate) use instance::TemplateInstance;
+pub use instance::TemplateOverrides;
+pub use text::*;
+pub use transform::*;
+
 /// The location of the tileset this tile is in
 ///
 /// Tilesets can be contained within either a map or a template.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum TilesetLocation {
     /// Index into the Map's tileset list, guaranteed to be a valid index of the map tileset container.
     Map(usize),
@@ -23,11 +40,12 @@ pub enum TilesetLocation {
 
 /// Stores the internal tile gid about a layer tile, along with how it is flipped.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectTileData {
     /// A valid TilesetLocation that points to a tileset that **may or may not contain** this tile.
-    tileset_location: TilesetLocation,
+    pub(crate) tileset_location: TilesetLocation,
     /// The local ID of the tile in the tileset it's in.
-    id: TileId,
+    pub(crate) id: TileId,
     /// Whether this tile is flipped on its Y axis (horizontally).
     pub flip_h: bool,
     /// Whether this tile is flipped on its X axis (vertically).
@@ -119,6 +137,7 @@ impl<'map> ObjectTile<'map> {
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum ObjectShape {
     Rect {
         width: f32,
@@ -157,6 +176,7 @@ pub enum ObjectShape {
 /// The horizontal alignment of an [`ObjectShape::Text`].
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum HorizontalAlignment {
     #[default]
     Left,
@@ -168,6 +188,7 @@ pub enum HorizontalAlignment {
 /// The vertical alignment of an [`ObjectShape::Text`].
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum VerticalAlignment {
     #[default]
     Top,
@@ -179,12 +200,15 @@ pub enum VerticalAlignment {
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-object).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct ObjectData {
-    id: u32,
-    tile: Option<ObjectTileData>,
+    pub(crate) id: u32,
+    pub(crate) tile: Option<ObjectTileData>,
     /// The name of the object, which is arbitrary and set by the user.
     pub name: String,
-    /// The type of the object, which is arbitrary and set by the user.
+    /// The class of the object, which is arbitrary and set by the user, or an empty string if it
+    /// has none. Loaded from the `class` attribute, or from the `type` attribute that Tiled used
+    /// before version 1.9; see [`ObjectData::class()`].
     pub user_type: String,
     /// The X coordinate of this object in pixels.
     pub x: f32,
@@ -198,8 +222,17 @@ pub struct ObjectData {
     pub shape: ObjectShape,
     /// The object's custom properties as set by the user.
     pub properties: Properties,
+    pub(crate) unknown_xml: UnknownXml,
+    /// The template the object was instantiated from, if any.
+    pub(crate) template: Option<Box<TemplateInstance>>,
 }
 
+/// The attributes of `<object>` that are loaded into an [`ObjectData`].
+const OBJECT_ATTRIBUTES: &[&str] = &[
+    "id", "gid", "name", "type", "class", "width", "height", "visible", "rotation", "template",
+    "x", "y",
+];
+
 impl ObjectData {
     /// ID of the object, which is unique per map since Tiled 0.11.
     ///
@@ -209,16 +242,38 @@ impl ObjectData {
         self.id
     }
 
+    /// The class of the object, or `None` if it has none. Objects saved by Tiled 1.9 and later
+    /// store their class in a `class` attribute while older ones use `type`, and both end up
+    /// here, so this works with files of any version.
+    #[inline]
+    pub fn class(&self) -> Option<&str> {
+        Some(self.user_type.as_str()).filter(|class| !class.is_empty())
+    }
+
+    /// Returns true if the object has the given class, whichever attribute it was loaded from.
+    #[inline]
+    pub fn is_class(&self, class: &str) -> bool {
+        !class.is_empty() && self.user_type == class
+    }
+
     /// Returns the data of the tile that this object is referencing, if it exists.
     #[inline]
     pub fn tile_data(&self) -> Option<ObjectTileData> {
         self.tile.clone()
     }
+
+    /// The parts of the object's XML this crate does not model, if loaded with
+    /// [`LoaderOptions::preserve_unknown`](crate::LoaderOptions::preserve_unknown) set.
+    #[inline]
+    pub fn unknown_xml(&self) -> &UnknownXml {
+        &self.unknown_xml
+    }
 }
 
 impl ObjectData {
     /// If it is known that the object has no tile images in it (i.e. collision data)
     /// then we can pass in [`None`] as the tilesets
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
@@ -228,8 +283,10 @@ impl ObjectData {
         base_path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<ObjectData> {
-        let (id, tile, mut n, mut t, c, mut w, mut h, mut v, mut r, template, x, y) = get_attrs!(
+        ctx.count_object()?;
+        let (id, tile, n, t, c, w, h, v, r, template, x, y) = get_attrs!(
             for v in attrs {
                 Some("id") => id ?= v.parse(),
                 Some("gid") => tile ?= v.parse::<u32>(),
@@ -246,59 +303,56 @@ impl ObjectData {
             }
             (id, tile, name, user_type, user_class, width, height, visible, rotation, template, x, y)
         );
+        // Merged before applying the template, which an object of either version may override
+        let t = ctx.class_attribute("object", t, c);
         let x = x.unwrap_or(0.);
         let y = y.unwrap_or(0.);
-        let mut tile = tile.and_then(|bits| {
+        let tile = tile.and_then(|bits| {
             ObjectTileData::from_bits(bits, tilesets?, for_tileset.as_ref().cloned())
         });
         // If the template attribute is there, we need to go fetch the template file
         let template = template
             .map(|template_path: String| {
-                let template_path = base_path.join(Path::new(&template_path));
+                let template_path = ctx.resolve_path(base_path, &template_path)?;
 
                 // Check the cache to see if this template exists
-                let template = if let Some(templ) = cache.get_template(&template_path) {
+                let span = load_span!("resolve_template", path = %template_path.display(), cached = tracing::field::Empty);
+                let cached = cache.get_template(&template_path);
+                span.record("cached", cached.is_some());
+                let template = if let Some(templ) = cached {
                     templ
                 } else {
-                    let template = Template::parse_template(&template_path, reader, cache)?;
-                    // Insert it into the cache
-                    cache.insert_template(&template_path, template.clone());
+                    let errors = ctx.error_count();
+                    let template = Template::parse_template(&template_path, reader, cache, ctx)?;
+                    // Insert it into the cache, unless it is missing parts because of errors
+                    if ctx.error_count() == errors {
+                        cache.insert_template(&template_path, template.clone());
+                    }
                     template
                 };
-
-                // The template sets the default values for the object
-                let obj = &template.object;
-                v.get_or_insert(obj.visible);
-                r.get_or_insert(obj.rotation);
-                n.get_or_insert_with(|| obj.name.clone());
-                t.get_or_insert_with(|| obj.user_type.clone());
-                if let Some(templ_tile) = &obj.tile {
-                    tile.get_or_insert_with(|| templ_tile.clone());
-                }
-                match &obj.shape {
-                    ObjectShape::Rect { width, height }
-                    | ObjectShape::Ellipse { width, height }
-                    | ObjectShape::Text { width, height, .. } => {
-                        w.get_or_insert(*width);
-                        h.get_or_insert(*height);
+                // Templates that were cached haven't recorded their tileset
+                let dependencies = ctx.dependencies_mut();
+                dependencies.add(&template_path, DependencyKind::Template);
+                match &template.tileset {
+                    Some(tileset) if tileset.source != template.source => {
+                        dependencies.add_tileset(&tileset.source, tileset)
                     }
-                    _ => {}
+                    Some(tileset) => dependencies.add_tileset_images(tileset),
+                    None => {}
                 }
+                ctx.resource_loaded(&template_path);
                 Ok(template)
             })
             .transpose()?;
 
-        let visible = v.unwrap_or(true);
         let width = w.unwrap_or(0f32);
         let height = h.unwrap_or(0f32);
-        let rotation = r.unwrap_or(0f32);
         let id = id.unwrap_or(0u32);
-        let name = n.unwrap_or_default();
-        let user_type: String = t.or(c).unwrap_or_default();
         let mut shape = None;
-        let mut properties = HashMap::new();
+        let mut properties = Properties::new();
 
-        parse_tag!(parser, "object", {
+        let depth = ctx.depth();
+        parse_tag!(parser, ctx, "object", {
             "ellipse" => |_| {
                 shape = Some(ObjectShape::Ellipse {
                     width,
@@ -323,77 +377,56 @@ impl ObjectData {
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
         });
+        let unknown_xml = ctx.take_unknown_xml("object", &attrs, OBJECT_ATTRIBUTES, depth)?;
 
-        if let Some(templ) = template {
-            shape.get_or_insert_with(|| {
-                // Inherit the shape from the template but use the size and
-                // position from the object where relevant
-                match &templ.object.shape {
-                    ObjectShape::Rect { .. } => ObjectShape::Rect { width, height },
-                    ObjectShape::Ellipse { .. } => ObjectShape::Ellipse { width, height },
-                    ObjectShape::Point(_, _) => ObjectShape::Point(x, y),
-                    ObjectShape::Text {
-                        font_family,
-                        pixel_size,
-                        wrap,
-                        color,
-                        bold,
-                        italic,
-                        underline,
-                        strikeout,
-                        kerning,
-                        halign,
-                        valign,
-                        text,
-                        width: _,
-                        height: _,
-                    } => ObjectShape::Text {
-                        font_family: font_family.clone(),
-                        pixel_size: pixel_size.clone(),
-                        wrap: wrap.clone(),
-                        color: color.clone(),
-                        bold: bold.clone(),
-                        italic: italic.clone(),
-                        underline: underline.clone(),
-                        strikeout: strikeout.clone(),
-                        kerning: kerning.clone(),
-                        halign: halign.clone(),
-                        valign: valign.clone(),
-                        text: text.clone(),
-                        width,
-                        height,
-                    },
-                    shape => shape.clone(),
-                }
-            });
-
-            // Possibly copy properties from the template into the object
-            // Any that already exist in the object's map don't get copied over
-            for (k, v) in &templ.object.properties {
-                if !properties.contains_key(k) {
-                    properties.insert(k.clone(), v.clone());
-                }
-            }
-        }
-
-        let shape = shape.unwrap_or(ObjectShape::Rect { width, height });
-
-        Ok(ObjectData {
+        let mut object = ObjectData {
             id,
-            tile,
-            name,
-            user_type,
+            tile: None,
+            name: String::new(),
+            user_type: String::new(),
             x,
             y,
-            rotation,
-            visible,
-            shape,
-            properties,
-        })
+            rotation: 0.,
+            visible: true,
+            shape: ObjectShape::Rect { width, height },
+            properties: Properties::new(),
+            unknown_xml,
+            template: None,
+        };
+        match template {
+            Some(template) => {
+                let overrides = TemplateOverrides {
+                    name: n,
+                    user_type: t,
+                    visible: v,
+                    rotation: r,
+                    width: w,
+                    height: h,
+                    tile,
+                    shape,
+                    properties,
+                };
+                object.apply_template(&template, &overrides);
+                object.template = Some(Box::new(TemplateInstance {
+                    source: template.source.clone(),
+                    overrides,
+                }));
+            }
+            None => {
+                object.tile = tile;
+                object.name = n.unwrap_or_default();
+                object.user_type = t.unwrap_or_default();
+                object.visible = v.unwrap_or(true);
+                object.rotation = r.unwrap_or(0f32);
+                object.shape = shape.unwrap_or(ObjectShape::Rect { width, height });
+                object.properties = properties;
+            }
+        }
+        Ok(object)
     }
 }
 
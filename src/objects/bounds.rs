@@ -0,0 +1,187 @@
+//! The area objects cover on screen.
+
+use crate::{Map, ObjectData, ObjectShape, ObjectTransform, Orientation};
+
+/// An axis-aligned rectangle, in pixels, as returned by [`ObjectData::world_aabb()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    /// The horizontal position of the left edge.
+    pub min_x: f32,
+    /// The vertical position of the top edge.
+    pub min_y: f32,
+    /// The horizontal position of the right edge.
+    pub max_x: f32,
+    /// The vertical position of the bottom edge.
+    pub max_y: f32,
+}
+
+impl Aabb {
+    /// The width of the rectangle.
+    #[inline]
+    pub fn width(&self) -> f32 {
+        self.max_x - self.min_x
+    }
+
+    /// The height of the rectangle.
+    #[inline]
+    pub fn height(&self) -> f32 {
+        self.max_y - self.min_y
+    }
+
+    /// Returns true if the rectangles overlap, including when they only share an edge.
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min_x <= other.max_x
+            && other.min_x <= self.max_x
+            && self.min_y <= other.max_y
+            && other.min_y <= self.max_y
+    }
+
+    /// The smallest rectangle containing all the points given, or [`None`] if there are none.
+    fn around(points: impl IntoIterator<Item = (f32, f32)>) -> Option<Aabb> {
+        points.into_iter().fold(None, |aabb, (x, y)| {
+            Some(match aabb {
+                Some(Aabb {
+                    min_x,
+                    min_y,
+                    max_x,
+                    max_y,
+                }) => Aabb {
+                    min_x: min_x.min(x),
+                    min_y: min_y.min(y),
+                    max_x: max_x.max(x),
+                    max_y: max_y.max(y),
+                },
+                None => Aabb {
+                    min_x: x,
+                    min_y: y,
+                    max_x: x,
+                    max_y: y,
+                },
+            })
+        })
+    }
+}
+
+impl ObjectData {
+    /// Returns the smallest rectangle containing the object as `map` displays it, in pixels
+    /// relative to the top left corner of the map, e.g. for culling or for a collision
+    /// broadphase.
+    ///
+    /// The rotation of objects is taken into account, and rotated ellipses get their exact
+    /// bounds. Tile objects are displayed as their tile scaled to the size of the object and
+    /// anchored at its position by the bottom left corner of the image, or its bottom center on
+    /// isometric maps, where other shapes are projected onto the isometric grid the way Tiled
+    /// draws them. Points have an empty rectangle. The offsets of the layer of the object and of
+    /// the groups it is in aren't included.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_template.tmx")?;
+    /// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    ///
+    /// // A 32x32 tile object anchored at (0, 32) by its bottom left corner
+    /// let object = layer.get_object(1).unwrap();
+    /// let aabb = object.world_aabb(&map);
+    /// assert_eq!((aabb.min_x, aabb.min_y, aabb.max_x, aabb.max_y), (0., 0., 32., 32.));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn world_aabb(&self, map: &Map) -> Aabb {
+        let projection = projection(map);
+        let position = projection.apply((self.x, self.y));
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+
+        let aabb = match (&self.shape, &self.tile) {
+            (ObjectShape::Rect { width, height }, Some(_)) => {
+                // Tile images are drawn upright on screen, rotated around their anchor
+                let left = if map.orientation == Orientation::Isometric {
+                    -width / 2.
+                } else {
+                    0.
+                };
+                let transform = ObjectTransform {
+                    a: cos,
+                    b: sin,
+                    c: -sin,
+                    d: cos,
+                    e: position.0,
+                    f: position.1,
+                };
+                Aabb::around(
+                    [
+                        (left, -height),
+                        (left + width, -height),
+                        (left + width, 0.),
+                        (left, 0.),
+                    ]
+                    .map(|point| transform.apply(point)),
+                )
+            }
+            (ObjectShape::Ellipse { width, height }, _) => {
+                // The unit circle, scaled to the ellipse and placed on screen; each half extent of
+                // the result is the length of a row of its linear part
+                let transform = ObjectTransform {
+                    a: width / 2.,
+                    d: height / 2.,
+                    e: width / 2.,
+                    f: height / 2.,
+                    ..ObjectTransform::IDENTITY
+                }
+                .then(&self.transform())
+                .then(&projection);
+                let (half_width, half_height) = (
+                    transform.a.hypot(transform.c),
+                    transform.b.hypot(transform.d),
+                );
+                Some(Aabb {
+                    min_x: transform.e - half_width,
+                    min_y: transform.f - half_height,
+                    max_x: transform.e + half_width,
+                    max_y: transform.f + half_height,
+                })
+            }
+            (ObjectShape::Rect { width, height }, None)
+            | (ObjectShape::Text { width, height, .. }, _) => {
+                let transform = self.transform().then(&projection);
+                Aabb::around(
+                    [(0., 0.), (*width, 0.), (*width, *height), (0., *height)]
+                        .map(|point| transform.apply(point)),
+                )
+            }
+            (ObjectShape::Polygon { points } | ObjectShape::Polyline { points }, _) => {
+                let transform = self.transform().then(&projection);
+                Aabb::around(points.iter().map(|&point| transform.apply(point)))
+            }
+            (ObjectShape::Point(..), _) => None,
+        };
+        aabb.unwrap_or(Aabb {
+            min_x: position.0,
+            min_y: position.1,
+            max_x: position.0,
+            max_y: position.1,
+        })
+    }
+}
+
+/// The transformation from the coordinates of objects to the pixels of the map on screen, which
+/// are the same except on isometric maps.
+fn projection(map: &Map) -> ObjectTransform {
+    if map.orientation != Orientation::Isometric || map.tile_height == 0 {
+        return ObjectTransform::IDENTITY;
+    }
+    // Objects are positioned along the axes of the tiles, both of which are measured in tile
+    // heights, and the top corner of the tile at (0, 0) is at the middle of the top of the map
+    let (tile_width, tile_height) = (map.tile_width as f32, map.tile_height as f32);
+    let ratio = tile_width / tile_height / 2.;
+    ObjectTransform {
+        a: ratio,
+        b: 0.5,
+        c: -ratio,
+        d: 0.5,
+        e: map.height as f32 * tile_width / 2.,
+        f: 0.,
+    }
+}
@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use crate::{Map, ObjectData, ObjectShape, Properties, PropertyValue, UnknownXml};
+
+/// A builder used to create new [`ObjectData`] from code.
+///
+/// Objects are given an ID when built, which is reserved from the map they are going to be added
+/// to, so that it is unique within it just like Tiled would do.
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, ObjectBuilder, ObjectShape, PropertyValue};
+///
+/// # fn main() -> tiled::Result<()> {
+/// let mut map = Loader::new().load_tmx_map("assets/tiled_object_groups.tmx")?;
+/// let next_id = map.next_object_id();
+///
+/// let object = ObjectBuilder::new(ObjectShape::Point(0., 0.))
+///     .name("player_start")
+///     .user_type("spawn")
+///     .position(64., 32.)
+///     .property("facing", PropertyValue::StringValue("left".to_owned()))
+///     .build(&mut map);
+///
+/// assert_eq!(object.id(), next_id);
+/// assert_eq!(map.next_object_id(), next_id + 1);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ObjectBuilder {
+    shape: ObjectShape,
+    name: String,
+    user_type: String,
+    x: f32,
+    y: f32,
+    rotation: f32,
+    visible: bool,
+    properties: Properties,
+}
+
+impl ObjectBuilder {
+    /// Creates a new builder for a visible object with the given shape, placed at the origin.
+    pub fn new(shape: ObjectShape) -> Self {
+        Self {
+            shape,
+            name: String::new(),
+            user_type: String::new(),
+            x: 0.,
+            y: 0.,
+            rotation: 0.,
+            visible: true,
+            properties: Properties::new(),
+        }
+    }
+
+    /// Sets the name of the object.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Sets the type (also known as class) of the object.
+    pub fn user_type(mut self, user_type: impl Into<String>) -> Self {
+        self.user_type = user_type.into();
+        self
+    }
+
+    /// Sets the position of the object, in pixels.
+    pub fn position(mut self, x: f32, y: f32) -> Self {
+        self.x = x;
+        self.y = y;
+        self
+    }
+
+    /// Sets the clockwise rotation of the object around its position, in degrees.
+    pub fn rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Sets whether the object is shown or hidden.
+    pub fn visible(mut self, visible: bool) -> Self {
+        self.visible = visible;
+        self
+    }
+
+    /// Sets a custom property of the object.
+    pub fn property(mut self, name: impl Into<Arc<str>>, value: PropertyValue) -> Self {
+        self.properties.insert(name.into(), value);
+        self
+    }
+
+    /// Builds the object, giving it the next free object ID of `map`.
+    pub fn build(self, map: &mut Map) -> ObjectData {
+        ObjectData {
+            id: map.allocate_object_id(),
+            tile: None,
+            name: self.name,
+            user_type: self.user_type,
+            x: self.x,
+            y: self.y,
+            rotation: self.rotation,
+            visible: self.visible,
+            shape: self.shape,
+            properties: self.properties,
+            unknown_xml: UnknownXml::default(),
+            template: None,
+        }
+    }
+}
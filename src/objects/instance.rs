@@ -0,0 +1,150 @@
+//! Objects instantiated from templates, which are resolved again when their template changes.
+
+use std::path::{Path, PathBuf};
+
+use crate::{ObjectData, ObjectShape, ObjectTileData, Properties, PropertiesExt, Template};
+
+/// The values an object instantiated from a template sets itself, which take precedence over the
+/// ones of the template, as returned by [`ObjectData::template_overrides()`].
+///
+/// Fields are [`None`] when the object inherits them from its template, and should be left out
+/// when writing the object back to a map so that it keeps following the template. The position
+/// and ID of objects always belong to them and are not listed.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub struct TemplateOverrides {
+    /// The name of the object, if it doesn't use the one of the template.
+    pub name: Option<String>,
+    /// The class of the object, if it doesn't use the one of the template.
+    pub user_type: Option<String>,
+    /// Whether the object is visible, if it doesn't use the visibility of the template.
+    pub visible: Option<bool>,
+    /// The rotation of the object, if it doesn't use the one of the template.
+    pub rotation: Option<f32>,
+    /// The width of the object, if it doesn't use the one of the template.
+    pub width: Option<f32>,
+    /// The height of the object, if it doesn't use the one of the template.
+    pub height: Option<f32>,
+    /// The tile of the object, if it doesn't use the one of the template.
+    pub tile: Option<ObjectTileData>,
+    /// The shape of the object as given by its own shape element, such as `<ellipse>`, if it
+    /// doesn't use the shape of the template. Its size is the one of the object as a whole.
+    pub shape: Option<ObjectShape>,
+    /// The properties the object sets itself. Class properties only have the members the object
+    /// sets, while the others come from the template.
+    pub properties: Properties,
+}
+
+/// The template an object was instantiated from, along with what the object overrides.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct TemplateInstance {
+    /// The path the template was loaded from.
+    pub source: PathBuf,
+    pub overrides: TemplateOverrides,
+}
+
+impl ObjectData {
+    /// The path of the template the object was instantiated from, if any.
+    #[inline]
+    pub fn template_source(&self) -> Option<&Path> {
+        self.template
+            .as_ref()
+            .map(|instance| instance.source.as_path())
+    }
+
+    /// The values the object sets itself if it was instantiated from a template, telling which of
+    /// its fields and properties are inherited from the template and which are specific to it.
+    /// [`None`] for objects that don't use a template, all of whose values are their own.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_object_template.tmx")?;
+    /// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    ///
+    /// // The size of this object is its own, while its tile comes from the template
+    /// let object = layer.get_object(2).unwrap();
+    /// let overrides = object.template_overrides().unwrap();
+    /// assert_eq!((overrides.width, overrides.height), (Some(64.), Some(32.)));
+    /// assert!(overrides.tile.is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn template_overrides(&self) -> Option<&TemplateOverrides> {
+        self.template.as_ref().map(|instance| &instance.overrides)
+    }
+
+    /// Sets the fields of the object to the ones of `template`, replaced by `overrides`, leaving
+    /// its ID and position as they are.
+    pub(crate) fn apply_template(&mut self, template: &Template, overrides: &TemplateOverrides) {
+        let object = &template.object;
+        let template_size = match &object.shape {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => Some((*width, *height)),
+            _ => None,
+        };
+        let width = overrides
+            .width
+            .or(template_size.map(|(width, _)| width))
+            .unwrap_or(0.);
+        let height = overrides
+            .height
+            .or(template_size.map(|(_, height)| height))
+            .unwrap_or(0.);
+
+        self.name = overrides
+            .name
+            .clone()
+            .unwrap_or_else(|| object.name.clone());
+        self.user_type = overrides
+            .user_type
+            .clone()
+            .unwrap_or_else(|| object.user_type.clone());
+        self.visible = overrides.visible.unwrap_or(object.visible);
+        self.rotation = overrides.rotation.unwrap_or(object.rotation);
+        self.tile = overrides.tile.clone().or_else(|| object.tile.clone());
+
+        // The shape of the template, or the one of the object, takes the size and position of the
+        // object where relevant
+        let mut shape = overrides
+            .shape
+            .clone()
+            .unwrap_or_else(|| object.shape.clone());
+        match &mut shape {
+            ObjectShape::Rect {
+                width: w,
+                height: h,
+            }
+            | ObjectShape::Ellipse {
+                width: w,
+                height: h,
+            }
+            | ObjectShape::Text {
+                width: w,
+                height: h,
+                ..
+            } => {
+                *w = width;
+                *h = height;
+            }
+            ObjectShape::Point(x, y) if overrides.shape.is_none() => {
+                *x = self.x;
+                *y = self.y;
+            }
+            _ => {}
+        }
+        self.shape = shape;
+
+        // Inherit the properties of the template, overridden by those of the object including
+        // individual members of class properties
+        let mut properties = object.properties.clone();
+        properties.merge(&overrides.properties);
+        self.properties = properties;
+    }
+}
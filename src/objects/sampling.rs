@@ -0,0 +1,84 @@
+//! Measuring and sampling the paths made by polylines and polygons.
+
+use crate::ObjectShape;
+
+impl ObjectShape {
+    /// Returns the line segments making up a polyline or polygon, as pairs of start and end points
+    /// relative to the position of the object, or [`None`] for other shapes. Polygons include the
+    /// segment going from their last point back to the first one.
+    ///
+    /// Use [`ObjectData::transform()`](crate::ObjectData::transform) to take the rotation of the
+    /// object into account.
+    pub fn segments(&self) -> Option<impl Iterator<Item = ((f32, f32), (f32, f32))> + '_> {
+        let (points, closed) = match self {
+            ObjectShape::Polyline { points } => (points, false),
+            ObjectShape::Polygon { points } => (points, points.len() > 1),
+            _ => return None,
+        };
+        let closing = points.last().zip(points.first()).filter(|_| closed);
+        Some(
+            points
+                .windows(2)
+                .map(|pair| (pair[0], pair[1]))
+                .chain(closing.map(|(last, first)| (*last, *first))),
+        )
+    }
+
+    /// Returns the length in pixels of a polyline, or of the outline of a polygon, or [`None`] for
+    /// other shapes.
+    pub fn length(&self) -> Option<f32> {
+        Some(self.segments()?.map(segment_length).sum())
+    }
+
+    /// Returns the point at `distance` pixels along a polyline or the outline of a polygon from
+    /// its first point, relative to the position of the object, or [`None`] for other shapes and
+    /// for polylines and polygons without points.
+    ///
+    /// This lets patrol paths and camera rails drawn in Tiled be followed at a constant speed.
+    /// Distances beyond either end of a polyline are clamped to the end, while polygons are
+    /// closed loops, so distances wrap around them.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::ObjectShape;
+    ///
+    /// let rail = ObjectShape::Polyline {
+    ///     points: vec![(0., 0.), (10., 0.), (10., 5.)],
+    /// };
+    /// assert_eq!(rail.length(), Some(15.));
+    /// assert_eq!(rail.point_at_distance(12.), Some((10., 2.)));
+    /// assert_eq!(rail.point_at_distance(20.), Some((10., 5.)));
+    /// ```
+    pub fn point_at_distance(&self, distance: f32) -> Option<(f32, f32)> {
+        let first = match self {
+            ObjectShape::Polyline { points } | ObjectShape::Polygon { points } => {
+                *points.first()?
+            }
+            _ => return None,
+        };
+        let length = self.length()?;
+        let mut remaining = match self {
+            ObjectShape::Polygon { .. } if length > 0. => distance.rem_euclid(length),
+            _ => distance.clamp(0., length),
+        };
+
+        let mut last = first;
+        for (start, end) in self.segments()? {
+            let segment = segment_length((start, end));
+            if remaining <= segment && segment > 0. {
+                let ratio = remaining / segment;
+                return Some((
+                    start.0 + (end.0 - start.0) * ratio,
+                    start.1 + (end.1 - start.1) * ratio,
+                ));
+            }
+            remaining -= segment;
+            last = end;
+        }
+        Some(last)
+    }
+}
+
+fn segment_length(((x1, y1), (x2, y2)): ((f32, f32), (f32, f32))) -> f32 {
+    (x2 - x1).hypot(y2 - y1)
+}
@@ -0,0 +1,147 @@
+//! Laying out the text of text objects.
+
+use crate::{HorizontalAlignment, ObjectShape, VerticalAlignment};
+
+/// A piece of text positioned within a text object, as returned by
+/// [`ObjectShape::layout_text()`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct TextRun {
+    /// The text to draw.
+    pub text: String,
+    /// The X coordinate of the left edge of the text, in pixels, relative to the object.
+    pub x: f32,
+    /// The Y coordinate of the top of the line the text is on, in pixels, relative to the object.
+    /// Add the ascent of the font to get the baseline.
+    pub y: f32,
+    /// The width of the text, as measured by the callback.
+    pub width: f32,
+}
+
+/// A line of text, made of the words it contains.
+struct Line<'a> {
+    words: Vec<&'a str>,
+    /// Whether the line ends its paragraph, in which case it isn't justified.
+    last: bool,
+}
+
+impl ObjectShape {
+    /// Lays out the text of an [`ObjectShape::Text`] like Tiled draws it, and returns the runs of
+    /// text to draw, or [`None`] for other shapes.
+    ///
+    /// `measure` returns the width in pixels of the string it is given, drawn with the font
+    /// described by the shape: its family, pixel size, boldness, italics and kerning. Lines are
+    /// `line_height` pixels apart, which is the line spacing of that font.
+    ///
+    /// Lines are broken at newlines and, if the text wraps, between words that would exceed the
+    /// width of the object; words longer than that are left overflowing. Each line gives one run
+    /// positioned according to the horizontal alignment, except for justified lines, which give
+    /// one run per word. Justified text is aligned left on the last line of each paragraph. The
+    /// lines are then aligned vertically within the height of the object, and may overflow it.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_text_object.tmx")?;
+    /// let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    /// let object = layer.get_object(0).unwrap();
+    ///
+    /// // A monospace font whose characters are 8 pixels wide
+    /// let runs = object
+    ///     .shape
+    ///     .layout_text(16., |text| text.chars().count() as f32 * 8.)
+    ///     .unwrap();
+    ///
+    /// // The text is centered at the bottom of the object
+    /// assert_eq!(runs[0].text, "Test");
+    /// assert_eq!(runs[0].x * 2. + runs[0].width, 87.7188);
+    /// assert_eq!(runs[0].y + 16., 21.7969);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn layout_text(
+        &self,
+        line_height: f32,
+        mut measure: impl FnMut(&str) -> f32,
+    ) -> Option<Vec<TextRun>> {
+        let (wrap, halign, valign, text, width, height) = match self {
+            ObjectShape::Text {
+                wrap,
+                halign,
+                valign,
+                text,
+                width,
+                height,
+                ..
+            } => (*wrap, *halign, *valign, text, *width, *height),
+            _ => return None,
+        };
+
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            let words: Vec<&str> = paragraph.split(' ').filter(|w| !w.is_empty()).collect();
+            let mut line = Vec::new();
+            for word in words {
+                line.push(word);
+                if wrap && line.len() > 1 && measure(&line.join(" ")) > width {
+                    line.pop();
+                    lines.push(Line {
+                        words: std::mem::replace(&mut line, vec![word]),
+                        last: false,
+                    });
+                }
+            }
+            lines.push(Line {
+                words: line,
+                last: true,
+            });
+        }
+
+        let total_height = lines.len() as f32 * line_height;
+        let top = match valign {
+            VerticalAlignment::Top => 0.,
+            VerticalAlignment::Center => (height - total_height) / 2.,
+            VerticalAlignment::Bottom => height - total_height,
+        };
+
+        let mut runs = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            let y = top + index as f32 * line_height;
+            if line.words.is_empty() {
+                continue;
+            }
+            if halign == HorizontalAlignment::Justify && !line.last && line.words.len() > 1 {
+                let widths: Vec<f32> = line.words.iter().map(|word| measure(word)).collect();
+                let gap = (width - widths.iter().sum::<f32>()) / (line.words.len() - 1) as f32;
+                let mut x = 0.;
+                for (word, word_width) in line.words.iter().zip(widths) {
+                    runs.push(TextRun {
+                        text: (*word).to_owned(),
+                        x,
+                        y,
+                        width: word_width,
+                    });
+                    x += word_width + gap;
+                }
+                continue;
+            }
+
+            let text = line.words.join(" ");
+            let line_width = measure(&text);
+            let x = match halign {
+                HorizontalAlignment::Left | HorizontalAlignment::Justify => 0.,
+                HorizontalAlignment::Center => (width - line_width) / 2.,
+                HorizontalAlignment::Right => width - line_width,
+            };
+            runs.push(TextRun {
+                text,
+                x,
+                y,
+                width: line_width,
+            });
+        }
+        Some(runs)
+    }
+}
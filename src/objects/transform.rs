@@ -0,0 +1,145 @@
+//! The placement of objects within their layer.
+
+use crate::{ObjectData, ObjectShape};
+
+/// A 2D affine transformation, which maps a point `(x, y)` to
+/// `(a * x + c * y + e, b * x + d * y + f)` like an SVG `matrix(a, b, c, d, e, f)`. Returned by
+/// [`ObjectData::transform()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ObjectTransform {
+    /// The horizontal scaling and rotation component applied to X coordinates.
+    pub a: f32,
+    /// The vertical skewing and rotation component applied to X coordinates.
+    pub b: f32,
+    /// The horizontal skewing and rotation component applied to Y coordinates.
+    pub c: f32,
+    /// The vertical scaling and rotation component applied to Y coordinates.
+    pub d: f32,
+    /// The horizontal translation.
+    pub e: f32,
+    /// The vertical translation.
+    pub f: f32,
+}
+
+impl ObjectTransform {
+    /// The transformation that leaves points untouched.
+    pub const IDENTITY: ObjectTransform = ObjectTransform {
+        a: 1.0,
+        b: 0.0,
+        c: 0.0,
+        d: 1.0,
+        e: 0.0,
+        f: 0.0,
+    };
+
+    /// Returns the transformation that applies `self` and then `other`.
+    pub fn then(&self, other: &ObjectTransform) -> ObjectTransform {
+        ObjectTransform {
+            a: other.a * self.a + other.c * self.b,
+            b: other.b * self.a + other.d * self.b,
+            c: other.a * self.c + other.c * self.d,
+            d: other.b * self.c + other.d * self.d,
+            e: other.a * self.e + other.c * self.f + other.e,
+            f: other.b * self.e + other.d * self.f + other.f,
+        }
+    }
+
+    /// Transforms a point.
+    #[inline]
+    pub fn apply(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// The components of the transformation as `[a, b, c, d, e, f]`.
+    #[inline]
+    pub fn to_array(&self) -> [f32; 6] {
+        [self.a, self.b, self.c, self.d, self.e, self.f]
+    }
+}
+
+impl Default for ObjectTransform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl ObjectData {
+    /// Returns the transformation from the coordinates of the object's shape to those of its
+    /// layer, which accounts for the position of the object and for its rotation around that
+    /// position, clockwise in degrees.
+    ///
+    /// Shapes are laid out with their top left corner at the origin, except for polygons and
+    /// polylines, whose points are already relative to the position of the object. Tile objects
+    /// are positioned by their bottom left corner instead, which Tiled uses for tiles on
+    /// orthogonal maps, so their transformation also moves them up by their height. The offsets of
+    /// the layer and of the groups it is in aren't included.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+    /// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    /// let object = layer.get_object(0).unwrap();
+    ///
+    /// // The top left corner of a rectangle is the position of its object
+    /// assert_eq!(object.transform().apply((0.0, 0.0)), (object.x, object.y));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn transform(&self) -> ObjectTransform {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let rotation = ObjectTransform {
+            a: cos,
+            b: sin,
+            c: -sin,
+            d: cos,
+            e: self.x,
+            f: self.y,
+        };
+        match (&self.shape, &self.tile) {
+            (ObjectShape::Rect { height, .. }, Some(_)) => ObjectTransform {
+                f: -height,
+                ..ObjectTransform::IDENTITY
+            }
+            .then(&rotation),
+            _ => rotation,
+        }
+    }
+
+    /// Returns the corners of the bounds of the object's shape in the coordinates of its layer,
+    /// clockwise from the top left one before rotation, or [`None`] for points. See
+    /// [`ObjectData::transform()`].
+    ///
+    /// The bounds of polygons and polylines are the smallest rectangle containing their points.
+    pub fn world_corners(&self) -> Option<[(f32, f32); 4]> {
+        let (left, top, right, bottom) = match &self.shape {
+            ObjectShape::Rect { width, height }
+            | ObjectShape::Ellipse { width, height }
+            | ObjectShape::Text { width, height, .. } => (0.0, 0.0, *width, *height),
+            ObjectShape::Polygon { points } | ObjectShape::Polyline { points } => {
+                if points.is_empty() {
+                    return None;
+                }
+                points.iter().fold(
+                    (f32::MAX, f32::MAX, f32::MIN, f32::MIN),
+                    |(left, top, right, bottom), &(x, y)| {
+                        (left.min(x), top.min(y), right.max(x), bottom.max(y))
+                    },
+                )
+            }
+            ObjectShape::Point(..) => return None,
+        };
+        let transform = self.transform();
+        Some([
+            transform.apply((left, top)),
+            transform.apply((right, top)),
+            transform.apply((right, bottom)),
+            transform.apply((left, bottom)),
+        ])
+    }
+}
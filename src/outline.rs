@@ -0,0 +1,132 @@
+//! Polygonal outlines of object shapes, used to turn collision data into geometry.
+
+use crate::{LayerTileData, Map, ObjectData, ObjectShape, Tile};
+
+/// The number of sides of the polygons ellipses are approximated with.
+const ELLIPSE_SIDES: u32 = 16;
+
+/// The points making up the outline of a shape.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Outline {
+    pub points: Vec<(f32, f32)>,
+    /// Whether the last point connects back to the first one, i.e. whether the outline
+    /// encloses an area instead of being a polyline.
+    pub closed: bool,
+}
+
+impl Outline {
+    /// Applies a transformation to every point.
+    pub fn map(mut self, mut transform: impl FnMut((f32, f32)) -> (f32, f32)) -> Self {
+        for point in &mut self.points {
+            *point = transform(*point);
+        }
+        self
+    }
+}
+
+impl ObjectShape {
+    /// Returns the outline of this shape relative to the position of its object, ignoring the
+    /// object's rotation, or [`None`] for points and texts. Ellipses are approximated by polygons.
+    pub(crate) fn outline(&self) -> Option<Outline> {
+        let (points, closed) = match self {
+            ObjectShape::Rect { width, height } => (
+                vec![(0.0, 0.0), (*width, 0.0), (*width, *height), (0.0, *height)],
+                true,
+            ),
+            ObjectShape::Ellipse { width, height } => (
+                (0..ELLIPSE_SIDES)
+                    .map(|i| {
+                        let angle = i as f32 / ELLIPSE_SIDES as f32 * std::f32::consts::TAU;
+                        (
+                            width / 2.0 * (1.0 + angle.cos()),
+                            height / 2.0 * (1.0 + angle.sin()),
+                        )
+                    })
+                    .collect(),
+                true,
+            ),
+            ObjectShape::Polygon { points } => (points.clone(), true),
+            ObjectShape::Polyline { points } => (points.clone(), false),
+            ObjectShape::Point(..) | ObjectShape::Text { .. } => return None,
+        };
+        Some(Outline { points, closed })
+    }
+}
+
+impl ObjectData {
+    /// Returns the outline of this object's shape, positioned and rotated like the object.
+    ///
+    /// Tile objects, which are positioned by their bottom left corner, are not taken into
+    /// account, as this is meant for collision shapes.
+    pub(crate) fn outline(&self) -> Option<Outline> {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        Some(
+            self.shape
+                .outline()?
+                .map(|(x, y)| (self.x + x * cos - y * sin, self.y + x * sin + y * cos)),
+        )
+    }
+}
+
+/// A collision shape of a tile placed in a tile layer.
+pub(crate) struct TileCollision<'map> {
+    /// The tile the collision shape belongs to.
+    #[cfg_attr(not(feature = "rapier"), allow(dead_code))]
+    pub tile: Tile<'map>,
+    /// The collision shape.
+    #[cfg_attr(not(feature = "rapier"), allow(dead_code))]
+    pub object: &'map ObjectData,
+    /// The outline of the collision shape, in the pixel coordinates of the map, flipped like the
+    /// tile.
+    pub outline: Outline,
+}
+
+/// Returns the collision shapes of a tile placed at the given position of a tile layer of an
+/// orthogonal map, with their outline.
+pub(crate) fn tile_collisions<'map>(
+    map: &'map Map,
+    tile_data: &LayerTileData,
+    x: i32,
+    y: i32,
+) -> Vec<TileCollision<'map>> {
+    let tileset = &map.tilesets()[tile_data.tileset_index()];
+    let (tile, collision) = match tileset.get_tile(tile_data.id()) {
+        Some(tile) => match &tile.data.collision {
+            Some(collision) => (tile, collision),
+            None => return Vec::new(),
+        },
+        None => return Vec::new(),
+    };
+    let (width, height) = match tileset.tile_rect(tile_data.id()) {
+        Some(rect) => (rect.width as f32, rect.height as f32),
+        None => return Vec::new(),
+    };
+
+    // Tiles are aligned to the bottom left corner of their cell
+    let origin = (
+        (x * map.tile_width as i32 + tileset.offset_x) as f32,
+        ((y + 1) * map.tile_height as i32 + tileset.offset_y) as f32 - height,
+    );
+    let transform = |(px, py): (f32, f32)| {
+        let (mut px, mut py) = if tile_data.flip_d { (py, px) } else { (px, py) };
+        if tile_data.flip_h {
+            px = width - px;
+        }
+        if tile_data.flip_v {
+            py = height - py;
+        }
+        (origin.0 + px, origin.1 + py)
+    };
+
+    collision
+        .object_data()
+        .iter()
+        .filter_map(|object| {
+            Some(TileCollision {
+                tile: Tile::new(tileset, tile.data),
+                object,
+                outline: object.outline()?.map(transform),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,20 @@
+use super::Interner;
+
+/// Everything a [`Loader`](crate::Loader) keeps from one load to the next so that loading map
+/// after map, e.g. when reloading them, doesn't allocate it all over again.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ParseBuffers {
+    pub interner: Interner,
+    pub decode: DecodeBuffers,
+}
+
+/// Scratch space for decoding tile data.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DecodeBuffers {
+    /// Base64-decoded tile data, compressed or not.
+    pub base64: Vec<u8>,
+    /// Decompressed tile data.
+    pub decompressed: Vec<u8>,
+    /// The GIDs of the decoded tiles, along with their flipping bits.
+    pub tiles: Vec<u32>,
+}
@@ -0,0 +1,761 @@
+use std::{
+    cell::Cell,
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use xml::{attribute::OwnedAttribute, reader::XmlEvent};
+
+use crate::{
+    dependencies::Dependencies,
+    parse::{DecodeBuffers, ParseBuffers},
+    util::{normalize_path, XmlEventResult},
+    Error, LayerData, LayerFilter, LayerInfo, LoaderOptions, ObjectData, ParseWarning,
+    ParseWarningKind, PathBase, PathPolicy, PropertyParsers, PropertyValue, RawElement, Result,
+    Tileset, UnknownXml,
+};
+
+/// Every element that appears in the TMX and TSX formats, including the ones this crate ignores.
+/// Only elements not in this list are reported as unknown.
+const KNOWN_ELEMENTS: &[&str] = &[
+    "animation",
+    "chunk",
+    "chunksize",
+    "data",
+    "editorsettings",
+    "ellipse",
+    "export",
+    "frame",
+    "grid",
+    "group",
+    "image",
+    "imagelayer",
+    "layer",
+    "map",
+    "object",
+    "objectgroup",
+    "point",
+    "polygon",
+    "polyline",
+    "properties",
+    "property",
+    "template",
+    "terrain",
+    "terraintypes",
+    "text",
+    "tile",
+    "tileoffset",
+    "tileset",
+    "transformations",
+    "wangcolor",
+    "wangset",
+    "wangsets",
+    "wangtile",
+];
+
+/// Attributes of the TMX and TSX formats that this crate ignores, which aren't unknown in
+/// [strict mode](LoaderOptions::strict) even though they are preserved as unknown XML.
+const IGNORED_ATTRIBUTES: &[&str] = &[
+    "backgroundcolor",
+    "color",
+    "height",
+    "locked",
+    "objectalignment",
+    "parallaxoriginx",
+    "parallaxoriginy",
+    "renderorder",
+    "width",
+    "x",
+    "y",
+];
+
+/// Elements that Tiled has replaced with others, which are errors in
+/// [strict mode](LoaderOptions::strict).
+const DEPRECATED_ELEMENTS: &[&str] = &["terrain", "terraintypes"];
+
+/// Attributes that Tiled has replaced with others, which are errors in
+/// [strict mode](LoaderOptions::strict).
+const DEPRECATED_ATTRIBUTES: &[&str] = &["terrain"];
+
+/// Elements that are never preserved as unknown XML because the elements inside them are parsed
+/// as if they were children of their parent instead.
+const TRANSPARENT_ELEMENTS: &[&str] = &["wangsets"];
+
+/// Where the parser of the file currently being read is at. Kept up to date by
+/// [`PositionedEvents`](crate::parse::xml::PositionedEvents).
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct Cursor {
+    /// The amount of elements that have been opened but not closed yet.
+    pub depth: usize,
+    /// The line of the last event read, starting from 0.
+    pub row: u64,
+    /// The column of the last event read, starting from 0.
+    pub column: u64,
+}
+
+/// The state of [`ParseContext`] that belongs to the file currently being parsed.
+#[derive(Default)]
+pub(crate) struct FileState {
+    id: usize,
+    path: PathBuf,
+    cursor: Rc<Cell<Cursor>>,
+}
+
+/// An element preserved by [`ParseContext::unknown_element`], waiting to be claimed by the
+/// element it belongs to.
+struct PendingElement {
+    file: usize,
+    /// The depth of the parent of the element.
+    depth: usize,
+    element: RawElement,
+}
+
+/// A function called each time a resource is loaded, given to
+/// [`Loader::on_resource_loaded`](crate::Loader::on_resource_loaded).
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(pub Arc<ProgressFn>);
+
+type ProgressFn = dyn Fn(&Path, usize, usize) + Send + Sync;
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// The functions called each time a tileset, layer or object has been parsed, given to
+/// [`Loader::on_tileset_parsed`](crate::Loader::on_tileset_parsed) and the like.
+#[derive(Clone, Default)]
+pub(crate) struct ParseObservers {
+    pub tileset: Option<Arc<ObserverFn<Tileset>>>,
+    pub layer: Option<Arc<ObserverFn<LayerData>>>,
+    pub object: Option<Arc<ObserverFn<ObjectData>>>,
+}
+
+type ObserverFn<T> = dyn Fn(&T) + Send + Sync;
+
+impl std::fmt::Debug for ParseObservers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ParseObservers")
+            .field("tileset", &self.tileset.is_some())
+            .field("layer", &self.layer.is_some())
+            .field("object", &self.object.is_some())
+            .finish()
+    }
+}
+
+/// The resources loaded so far by a load operation that reports its progress.
+struct Progress {
+    callback: ProgressCallback,
+    loaded: usize,
+    estimate: usize,
+}
+
+/// State shared by everything parsed during a single load operation of a [`Loader`](crate::Loader).
+pub(crate) struct ParseContext {
+    options: LoaderOptions,
+    path_policy: PathPolicy,
+    file: FileState,
+    warnings: Vec<ParseWarning>,
+    errors: Vec<Error>,
+    files_entered: usize,
+    /// The normalized paths of the files being parsed, from the one being loaded to the one that
+    /// is currently parsed, which is referenced by the previous one.
+    open_files: Vec<PathBuf>,
+    unknown_elements: Vec<PendingElement>,
+    buffers: ParseBuffers,
+    dependencies: Dependencies,
+    progress: Option<Progress>,
+    cancellation_flag: Option<Arc<AtomicBool>>,
+    layer_filter: Option<LayerFilter>,
+    observers: ParseObservers,
+    property_parsers: PropertyParsers,
+    /// The amounts counted against the limits of [`LoaderOptions`].
+    decompressed_bytes: usize,
+    layers: usize,
+    objects: usize,
+}
+
+impl ParseContext {
+    pub fn new(options: LoaderOptions, buffers: ParseBuffers) -> Self {
+        Self {
+            options,
+            path_policy: PathPolicy::default(),
+            buffers,
+            file: FileState::default(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            files_entered: 0,
+            open_files: Vec::new(),
+            unknown_elements: Vec::new(),
+            dependencies: Dependencies::default(),
+            progress: None,
+            cancellation_flag: None,
+            layer_filter: None,
+            observers: ParseObservers::default(),
+            property_parsers: PropertyParsers::default(),
+            decompressed_bytes: 0,
+            layers: 0,
+            objects: 0,
+        }
+    }
+
+    pub fn set_path_policy(&mut self, policy: PathPolicy) {
+        self.path_policy = policy;
+    }
+
+    /// Resolves a path found in a file whose directory is `directory` according to the
+    /// [`PathPolicy`] of the loader.
+    pub fn resolve_path(&self, directory: &Path, reference: &str) -> Result<PathBuf> {
+        let reference = if self.path_policy.normalize_separators {
+            reference.replace('\\', "/")
+        } else {
+            reference.to_owned()
+        };
+        let path = match &self.path_policy.base {
+            PathBase::File => directory.join(reference),
+            PathBase::AssetRoot(root) => root.join(reference),
+        };
+        if self.path_policy.allow_escape {
+            return Ok(path);
+        }
+
+        let root = match &self.path_policy.base {
+            PathBase::File => self
+                .open_files
+                .first()
+                .and_then(|file| file.parent())
+                .map_or_else(PathBuf::new, Path::to_owned),
+            PathBase::AssetRoot(root) => normalize_path(root),
+        };
+        let normalized = normalize_path(&path);
+        // Every path starts with the empty root of a map loaded without a directory, so absolute
+        // paths have to be rejected explicitly when the root is relative
+        let escapes = (is_absolute(&normalized) && !is_absolute(&root))
+            || match normalized.strip_prefix(normalize_path(&root)) {
+                Ok(relative) => relative
+                    .components()
+                    .next()
+                    .is_some_and(|component| component == Component::ParentDir),
+                Err(_) => true,
+            };
+        if escapes {
+            Err(Error::PathEscapesRoot { path, root })
+        } else {
+            Ok(path)
+        }
+    }
+
+    /// Makes [`Self::check_cancelled`] fail once `flag` is set.
+    pub fn set_cancellation_flag(&mut self, flag: Option<Arc<AtomicBool>>) {
+        self.cancellation_flag = flag;
+    }
+
+    /// The flag set by [`Self::set_cancellation_flag`], for work done away from the context.
+    #[cfg(feature = "rayon")]
+    #[inline]
+    pub fn cancellation_flag(&self) -> Option<&AtomicBool> {
+        self.cancellation_flag.as_deref()
+    }
+
+    /// Returns [`Error::Cancelled`] if loading should stop. Called regularly while parsing.
+    #[inline]
+    pub fn check_cancelled(&self) -> Result<()> {
+        match &self.cancellation_flag {
+            Some(flag) if flag.load(Ordering::Relaxed) => Err(Error::Cancelled),
+            _ => Ok(()),
+        }
+    }
+
+    /// The most bytes of tile data that may be decompressed, if limited.
+    pub fn max_decompressed_bytes(&self) -> Option<usize> {
+        self.options.max_decompressed_bytes
+    }
+
+    /// How many more bytes of tile data may be decompressed, if limited.
+    pub fn decompression_budget(&self) -> Option<usize> {
+        self.options
+            .max_decompressed_bytes
+            .map(|limit| limit.saturating_sub(self.decompressed_bytes))
+    }
+
+    /// Counts `bytes` of decompressed tile data against
+    /// [`LoaderOptions::max_decompressed_bytes`].
+    pub fn decompressed(&mut self, bytes: usize) -> Result<()> {
+        self.decompressed_bytes += bytes;
+        match self.options.max_decompressed_bytes {
+            Some(limit) if self.decompressed_bytes > limit => {
+                Err(Error::DecompressedSizeExceeded { limit })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks the size of a map, tile layer or chunk against
+    /// [`LoaderOptions::max_map_dimensions`].
+    pub fn check_dimensions(&self, width: u32, height: u32) -> Result<()> {
+        match self.options.max_map_dimensions {
+            Some(limit) if width > limit || height > limit => Err(Error::MapTooLarge {
+                width,
+                height,
+                limit,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts a layer against [`LoaderOptions::max_layers`].
+    pub fn count_layer(&mut self) -> Result<()> {
+        self.layers += 1;
+        match self.options.max_layers {
+            Some(limit) if self.layers > limit => Err(Error::TooManyLayers { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Counts an object against [`LoaderOptions::max_objects`].
+    pub fn count_object(&mut self) -> Result<()> {
+        self.objects += 1;
+        match self.options.max_objects {
+            Some(limit) if self.objects > limit => Err(Error::TooManyObjects { limit }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Makes the context call `callback` each time [`Self::resource_loaded`] is.
+    pub fn set_progress_callback(&mut self, callback: Option<ProgressCallback>) {
+        self.progress = callback.map(|callback| Progress {
+            callback,
+            loaded: 0,
+            estimate: 0,
+        });
+    }
+
+    /// Whether resources loaded are reported to a callback, in which case
+    /// [`Self::estimate_resources`] should be called if possible.
+    #[inline]
+    pub fn reports_progress(&self) -> bool {
+        self.progress.is_some()
+    }
+
+    /// Adds `count` to the number of resources that are expected to be loaded.
+    pub fn estimate_resources(&mut self, count: usize) {
+        if let Some(progress) = &mut self.progress {
+            progress.estimate += count;
+        }
+    }
+
+    /// Reports that a tileset, template or layer read from the file at `path` has been loaded.
+    pub fn resource_loaded(&mut self, path: &Path) {
+        if let Some(progress) = &mut self.progress {
+            let index = progress.loaded;
+            progress.loaded += 1;
+            // Tilesets used by templates aren't part of the estimate
+            progress.estimate = progress.estimate.max(progress.loaded);
+            (progress.callback.0)(path, index, progress.estimate);
+        }
+    }
+
+    /// Returns the strings interned and the buffers used by this context, so that they can be
+    /// reused by the next one.
+    pub fn into_buffers(self) -> ParseBuffers {
+        self.buffers
+    }
+
+    /// Lends the buffers to decode tile data with, which should be given back with
+    /// [`Self::return_decode_buffers`] once done.
+    pub fn take_decode_buffers(&mut self) -> DecodeBuffers {
+        std::mem::take(&mut self.buffers.decode)
+    }
+
+    /// Gives back buffers taken with [`Self::take_decode_buffers`].
+    pub fn return_decode_buffers(&mut self, buffers: DecodeBuffers) {
+        self.buffers.decode = buffers;
+    }
+
+    /// Returns a pooled copy of a string that is likely to appear many times, such as the name of
+    /// a property.
+    #[inline]
+    pub fn intern(&mut self, string: &str) -> Arc<str> {
+        self.buffers.interner.intern(string)
+    }
+
+    /// The files referenced by what has been parsed so far.
+    #[inline]
+    pub fn dependencies_mut(&mut self) -> &mut Dependencies {
+        &mut self.dependencies
+    }
+
+    pub fn take_dependencies(&mut self) -> Dependencies {
+        std::mem::take(&mut self.dependencies)
+    }
+
+    /// Whether parts of the formats that this crate doesn't fully understand should be errors.
+    #[inline]
+    pub fn strict(&self) -> bool {
+        self.options.strict
+    }
+
+    /// Makes [`Self::filter_layer`] use `filter`.
+    pub fn set_layer_filter(&mut self, filter: Option<LayerFilter>) {
+        self.layer_filter = filter;
+    }
+
+    /// Whether the layer described by `info` should be loaded.
+    #[inline]
+    pub fn filter_layer(&self, info: &LayerInfo) -> bool {
+        self.layer_filter
+            .as_ref()
+            .is_none_or(|LayerFilter(filter)| filter(info))
+    }
+
+    /// Makes the context call the functions of `observers` each time [`Self::tileset_parsed`],
+    /// [`Self::layer_parsed`] or [`Self::object_parsed`] is.
+    pub fn set_observers(&mut self, observers: ParseObservers) {
+        self.observers = observers;
+    }
+
+    /// Reports that a tileset, embedded or external, has been parsed.
+    pub fn tileset_parsed(&self, tileset: &Tileset) {
+        if let Some(observer) = &self.observers.tileset {
+            observer(tileset);
+        }
+    }
+
+    /// Reports that a layer of a map has been parsed, along with all the layers it contains.
+    pub fn layer_parsed(&self, layer: &LayerData) {
+        if let Some(observer) = &self.observers.layer {
+            observer(layer);
+        }
+    }
+
+    /// Reports that an object of an object layer of a map has been parsed.
+    pub fn object_parsed(&self, object: &ObjectData) {
+        if let Some(observer) = &self.observers.object {
+            observer(object);
+        }
+    }
+
+    pub fn set_property_parsers(&mut self, parsers: PropertyParsers) {
+        self.property_parsers = parsers;
+    }
+
+    /// Reads a property of a type that isn't built in with the parser registered for it, failing
+    /// with [`Error::UnknownPropertyType`] if there is none.
+    pub fn parse_custom_property(&self, type_name: String, value: &str) -> Result<PropertyValue> {
+        self.property_parsers.parse(type_name, value)
+    }
+
+    /// Whether the external tilesets of maps should be left for later if they aren't cached.
+    #[inline]
+    pub fn defer_tilesets(&self) -> bool {
+        self.options.defer_tilesets
+    }
+
+    /// Whether images should be left out of what is loaded.
+    #[inline]
+    pub fn skip_images(&self) -> bool {
+        self.options.skip_images
+    }
+
+    /// Whether problems that don't prevent the file from being understood should be recorded as
+    /// warnings rather than returned as errors.
+    #[inline]
+    pub fn lenient(&self) -> bool {
+        self.options.lenient
+    }
+
+    /// Returns [`Error::CircularReference`] if the file at `path` is already being parsed, in which
+    /// case parsing it would reference it again forever.
+    pub fn check_circular(&self, path: &Path) -> Result<()> {
+        let path = normalize_path(path);
+        match self.open_files.iter().position(|open| *open == path) {
+            Some(start) => {
+                let mut cycle = self.open_files[start..].to_vec();
+                cycle.push(path);
+                Err(Error::CircularReference { cycle })
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Sets the file being parsed, along with the cursor of its parser. Returns the state of the
+    /// previous file so that it can be restored with [`Self::leave_file`] once done.
+    pub fn enter_file(&mut self, path: &Path, cursor: Rc<Cell<Cursor>>) -> FileState {
+        self.files_entered += 1;
+        self.open_files.push(normalize_path(path));
+        std::mem::replace(
+            &mut self.file,
+            FileState {
+                id: self.files_entered,
+                path: path.to_owned(),
+                cursor,
+            },
+        )
+    }
+
+    pub fn leave_file(&mut self, previous: FileState) {
+        // Elements nobody claimed belong to parts of the file that don't keep them
+        let id = self.file.id;
+        self.unknown_elements.retain(|pending| pending.file != id);
+        self.open_files.pop();
+        self.file = previous;
+    }
+
+    pub fn warn(&mut self, kind: ParseWarningKind) {
+        self.warnings.push(ParseWarning {
+            path: self.file.path.clone(),
+            kind,
+        });
+    }
+
+    /// Merges the `type` and `class` attributes of an element into its class, since Tiled 1.9
+    /// renamed the former to the latter. Like in Tiled, `class` takes precedence, and elements
+    /// that have both with different values are reported in lenient mode.
+    pub fn class_attribute(
+        &mut self,
+        element: &str,
+        user_type: Option<String>,
+        class: Option<String>,
+    ) -> Option<String> {
+        match (user_type, class) {
+            (Some(user_type), Some(class)) => {
+                if user_type != class && self.lenient() {
+                    self.warn(ParseWarningKind::ConflictingClass {
+                        element: element.to_owned(),
+                        user_type,
+                        class: class.clone(),
+                    });
+                }
+                Some(class)
+            }
+            (user_type, class) => class.or(user_type),
+        }
+    }
+
+    /// The amount of warnings recorded so far, to be used along with [`Self::warnings_since`].
+    #[inline]
+    pub fn warning_count(&self) -> usize {
+        self.warnings.len()
+    }
+
+    pub fn warnings_since(&self, start: usize) -> Vec<ParseWarning> {
+        self.warnings[start..].to_vec()
+    }
+
+    /// The amount of errors recorded so far by [`Self::recover`].
+    #[inline]
+    pub fn error_count(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Returns all errors recorded by [`Self::recover`].
+    pub fn take_errors(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// The amount of elements currently open in the file being parsed.
+    #[inline]
+    pub fn depth(&self) -> usize {
+        self.file.cursor.get().depth
+    }
+
+    /// Attaches the path of the file being parsed and the current position of its parser to an
+    /// error, unless it already has a location.
+    pub fn locate(&self, err: Error) -> Error {
+        match err {
+            Error::Located { .. } | Error::Multiple(_) | Error::Cancelled => err,
+            err => {
+                let cursor = self.file.cursor.get();
+                Error::Located {
+                    path: self.file.path.clone(),
+                    line: cursor.row + 1,
+                    column: cursor.column + 1,
+                    err: Box::new(err),
+                }
+            }
+        }
+    }
+
+    /// Called when parsing a child element failed with `err`. `depth` is the depth the parser was
+    /// at right after the child element had been opened.
+    ///
+    /// If errors are to be collected, the error is recorded and the rest of the element is skipped
+    /// so that parsing can continue with its siblings. Otherwise, the error is returned.
+    pub fn recover(
+        &mut self,
+        err: Error,
+        depth: usize,
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+    ) -> Result<()> {
+        if !self.options.collect_errors
+            || matches!(err, Error::XmlDecodingError(_) | Error::Cancelled)
+            // Going on would only find the limit exceeded again
+            || matches!(
+                err.inner(),
+                Error::DecompressedSizeExceeded { .. }
+                    | Error::TooManyLayers { .. }
+                    | Error::TooManyObjects { .. }
+            )
+        {
+            return Err(err);
+        }
+
+        let err = self.locate(err);
+        self.errors.push(err);
+        while self.depth() >= depth {
+            match parser.next() {
+                Some(Ok(XmlEvent::EndDocument)) | None => {
+                    return Err(Error::PrematureEnd(
+                        "Document ended before we expected.".to_string(),
+                    ))
+                }
+                Some(Err(err)) => return Err(Error::XmlDecodingError(err)),
+                Some(Ok(_)) => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Called for elements that the parser of `parent` does not handle. In lenient mode, elements
+    /// that are not part of the formats Tiled uses are recorded as a warning and skipped along
+    /// with their contents. If unknown XML is to be preserved, the element is read and kept until
+    /// claimed with [`Self::take_unknown_xml`]. Otherwise nothing is done.
+    pub fn unknown_element(
+        &mut self,
+        name: String,
+        attributes: &[OwnedAttribute],
+        parent: &str,
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+    ) -> Result<()> {
+        let unknown_to_tiled = !KNOWN_ELEMENTS.contains(&name.as_str());
+        if self.strict() {
+            if DEPRECATED_ELEMENTS.contains(&name.as_str()) {
+                return Err(Error::DeprecatedFeature {
+                    description: format!("{} elements", name),
+                });
+            }
+            if unknown_to_tiled {
+                return Err(Error::UnknownElement {
+                    name,
+                    parent: parent.to_owned(),
+                });
+            }
+        }
+        if self.lenient() && unknown_to_tiled {
+            self.warn(ParseWarningKind::UnknownElement {
+                name: name.clone(),
+                parent: parent.to_owned(),
+            });
+        }
+
+        if self.options.preserve_unknown && !TRANSPARENT_ELEMENTS.contains(&name.as_str()) {
+            let element = RawElement::read(name, attributes, parser)?;
+            self.unknown_elements.push(PendingElement {
+                file: self.file.id,
+                depth: self.depth(),
+                element,
+            });
+            return Ok(());
+        }
+
+        if !self.lenient() || !unknown_to_tiled {
+            return Ok(());
+        }
+        self.skip_element(parser)
+    }
+
+    /// Skips the contents of the element that was just opened, up to and including its end.
+    pub fn skip_element(&self, parser: &mut impl Iterator<Item = XmlEventResult>) -> Result<()> {
+        let mut depth = 0usize;
+        for next in parser {
+            match next.map_err(Error::XmlDecodingError)? {
+                XmlEvent::StartElement { .. } => depth += 1,
+                XmlEvent::EndElement { .. } if depth == 0 => return Ok(()),
+                XmlEvent::EndElement { .. } => depth -= 1,
+                XmlEvent::EndDocument => break,
+                _ => {}
+            }
+        }
+        Err(Error::PrematureEnd(
+            "Document ended before we expected.".to_string(),
+        ))
+    }
+
+    /// Collects the XML of an element that isn't modeled by this crate: Its attributes not in
+    /// `known`, and the children preserved by [`Self::unknown_element`] since it was opened.
+    /// `depth` is the depth the parser was at right after the element had been opened.
+    pub fn take_unknown_xml(
+        &mut self,
+        element: &str,
+        attributes: &[OwnedAttribute],
+        known: &[&str],
+        depth: usize,
+    ) -> Result<UnknownXml> {
+        Ok(UnknownXml {
+            attributes: self.unknown_attributes(element, attributes, known)?,
+            elements: self.take_unknown_elements(depth),
+        })
+    }
+
+    /// Returns the attributes of `element` not in `known` if unknown XML is to be preserved, or
+    /// nothing otherwise. In strict mode, fails if any of them isn't part of the formats or is
+    /// deprecated.
+    pub fn unknown_attributes(
+        &self,
+        element: &str,
+        attributes: &[OwnedAttribute],
+        known: &[&str],
+    ) -> Result<Vec<(String, String)>> {
+        if self.strict() {
+            for attribute in attributes {
+                let name = attribute.name.local_name.as_str();
+                if DEPRECATED_ATTRIBUTES.contains(&name) {
+                    return Err(Error::DeprecatedFeature {
+                        description: format!("the {} attribute of {} elements", name, element),
+                    });
+                }
+                if !known.contains(&name) && !IGNORED_ATTRIBUTES.contains(&name) {
+                    return Err(Error::UnknownAttribute {
+                        name: name.to_owned(),
+                        element: element.to_owned(),
+                    });
+                }
+            }
+        }
+        if self.options.preserve_unknown {
+            Ok(UnknownXml::attributes_except(attributes, known))
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    /// Returns the children preserved by [`Self::unknown_element`] since the element at `depth`
+    /// was opened. Children preserved for its descendants that were never claimed are discarded.
+    pub fn take_unknown_elements(&mut self, depth: usize) -> Vec<RawElement> {
+        let id = self.file.id;
+        let start = self
+            .unknown_elements
+            .iter()
+            .rposition(|pending| pending.file != id || pending.depth < depth)
+            .map_or(0, |last_outside| last_outside + 1);
+        self.unknown_elements
+            .drain(start..)
+            .filter(|pending| pending.depth == depth)
+            .map(|pending| pending.element)
+            .collect()
+    }
+}
+
+/// Returns true if `path` starts at the root of the filesystem or at a drive, even if it isn't
+/// fully absolute on Windows, like `\maps` or `C:maps`.
+fn is_absolute(path: &Path) -> bool {
+    matches!(
+        path.components().next(),
+        Some(Component::RootDir | Component::Prefix(_))
+    )
+}
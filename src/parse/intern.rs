@@ -0,0 +1,23 @@
+use std::{collections::HashSet, sync::Arc};
+
+/// A pool of strings, used so that strings that appear over and over again in the files loaded by
+/// a [`Loader`](crate::Loader), such as property names and tile classes, share the same
+/// allocation.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Interner {
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Returns the pooled copy of `string`, adding it to the pool if it isn't there yet.
+    pub fn intern(&mut self, string: &str) -> Arc<str> {
+        match self.strings.get(string) {
+            Some(interned) => interned.clone(),
+            None => {
+                let interned: Arc<str> = Arc::from(string);
+                self.strings.insert(interned.clone());
+                interned
+            }
+        }
+    }
+}
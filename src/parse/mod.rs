@@ -1 +1,7 @@
+mod buffers;
+pub(crate) use buffers::*;
+mod context;
+pub(crate) use context::*;
+mod intern;
+pub(crate) use intern::*;
 pub mod xml;
@@ -0,0 +1,93 @@
+use std::{cell::Cell, io::Read, path::Path, rc::Rc};
+
+use xml::{attribute::OwnedAttribute, common::Position, reader::XmlEvent, EventReader};
+
+use crate::{
+    parse::{Cursor, ParseContext},
+    trace::{CountingReader, LoadSpan},
+    util::XmlEventResult,
+    Error, Result,
+};
+
+/// An iterator over the events of an [`EventReader`] that keeps track of where the reader is at
+/// through a [`Cursor`], which is shared with the [`ParseContext`].
+pub(crate) struct PositionedEvents<R: Read> {
+    reader: EventReader<R>,
+    cursor: Rc<Cell<Cursor>>,
+    finished: bool,
+}
+
+impl<R: Read> PositionedEvents<R> {
+    pub fn new(reader: EventReader<R>) -> Self {
+        Self {
+            reader,
+            cursor: Rc::default(),
+            finished: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for PositionedEvents<R> {
+    type Item = XmlEventResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let event = self.reader.next();
+
+        let mut cursor = self.cursor.get();
+        match &event {
+            Ok(XmlEvent::StartElement { .. }) => cursor.depth += 1,
+            Ok(XmlEvent::EndElement { .. }) => cursor.depth = cursor.depth.saturating_sub(1),
+            Ok(XmlEvent::EndDocument) | Err(_) => self.finished = true,
+            _ => {}
+        }
+        let position = self.reader.position();
+        cursor.row = position.row;
+        cursor.column = position.column;
+        self.cursor.set(cursor);
+
+        Some(event)
+    }
+}
+
+/// Reads `file` until the `root` element is found and then calls `parse` with its attributes,
+/// which is expected to consume the element. Any error returned is given the location in the file
+/// where it happened, and the number of bytes read from `file` is recorded in `span`.
+pub(crate) fn parse_root_element<R: Read, T>(
+    file: R,
+    path: &Path,
+    root: &str,
+    span: &LoadSpan,
+    ctx: &mut ParseContext,
+    parse: impl FnOnce(
+        &mut PositionedEvents<CountingReader<R>>,
+        Vec<OwnedAttribute>,
+        &mut ParseContext,
+    ) -> Result<T>,
+) -> Result<T> {
+    ctx.check_cancelled()?;
+    ctx.check_circular(path)?;
+    let mut parser = PositionedEvents::new(EventReader::new(CountingReader::new(file)));
+    let previous_file = ctx.enter_file(path, parser.cursor.clone());
+    let result = loop {
+        match parser.next() {
+            Some(Ok(XmlEvent::StartElement {
+                name, attributes, ..
+            })) if name.local_name == root => break parse(&mut parser, attributes, ctx),
+            Some(Ok(XmlEvent::EndDocument)) | None => {
+                break Err(Error::PrematureEnd(format!(
+                    "Document ended before the {} element was parsed",
+                    root
+                )))
+            }
+            Some(Err(err)) => break Err(Error::XmlDecodingError(err)),
+            Some(Ok(_)) => {}
+        }
+    };
+    let result = result.map_err(|err| ctx.locate(err));
+    ctx.leave_file(previous_file);
+    span.record("bytes", parser.reader.source().count());
+    result
+}
@@ -1,44 +1,79 @@
-use std::path::Path;
+use std::{
+    io::{Cursor, Read},
+    path::Path,
+};
 
-use xml::{reader::XmlEvent, EventReader};
+use crate::{
+    parse::ParseContext, trace::load_span, Error, Map, ResourceCache, ResourceReader, Result,
+};
 
-use crate::{Error, Map, ResourceCache, ResourceReader, Result};
+use super::parse_root_element;
 
 pub fn parse_map(
     path: &Path,
     reader: &mut impl ResourceReader,
     cache: &mut impl ResourceCache,
+    ctx: &mut ParseContext,
 ) -> Result<Map> {
-    let mut parser =
-        EventReader::new(
-            reader
-                .read_from(path)
-                .map_err(|err| Error::ResourceLoadingError {
-                    path: path.to_owned(),
-                    err: Box::new(err),
-                })?,
-        );
-    loop {
-        match parser.next().map_err(Error::XmlDecodingError)? {
-            XmlEvent::StartElement {
-                name, attributes, ..
-            } => {
-                if name.local_name == "map" {
-                    return Map::parse_xml(
-                        &mut parser.into_iter(),
-                        attributes,
-                        path,
-                        reader,
-                        cache,
-                    );
-                }
-            }
-            XmlEvent::EndDocument => {
-                return Err(Error::PrematureEnd(
-                    "Document ended before map was parsed".to_string(),
-                ))
-            }
-            _ => {}
-        }
+    let file = reader
+        .read_from(path)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    parse_map_from(file, path, reader, cache, ctx)
+}
+
+/// Parses a map read from `file` instead of from `reader`, which is still used to read the files
+/// it references relative to `path`.
+pub fn parse_map_from(
+    mut file: impl Read,
+    path: &Path,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+    ctx: &mut ParseContext,
+) -> Result<Map> {
+    let span = load_span!("parse_map", path = %path.display());
+    if !ctx.reports_progress() {
+        return parse_root_element(file, path, "map", &span, ctx, |parser, attributes, ctx| {
+            Map::parse_xml(parser, attributes, path, reader, cache, ctx)
+        });
     }
+
+    // Skim through the file first to know roughly how many resources it is made of
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    ctx.estimate_resources(estimate_resources(&data));
+    parse_root_element(
+        Cursor::new(data),
+        path,
+        "map",
+        &span,
+        ctx,
+        |parser, attributes, ctx| Map::parse_xml(parser, attributes, path, reader, cache, ctx),
+    )
+}
+
+/// Counts the tilesets, layers and template references in the XML of a map.
+fn estimate_resources(data: &[u8]) -> usize {
+    const RESOURCES: &[&[u8]] = &[
+        b"<tileset",
+        b"<layer",
+        b"<imagelayer",
+        b"<objectgroup",
+        b"<group",
+        b" template=",
+    ];
+    RESOURCES
+        .iter()
+        .map(|resource| {
+            data.windows(resource.len())
+                .filter(|window| window == resource)
+                .count()
+        })
+        .sum()
 }
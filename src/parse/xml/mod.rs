@@ -1,3 +1,5 @@
+mod events;
+pub(crate) use events::*;
 mod map;
 pub use map::*;
 mod tileset;
@@ -1,42 +1,44 @@
-use std::path::Path;
+use std::{io::Read, path::Path};
 
-use xml::{reader::XmlEvent, EventReader};
+use crate::{
+    parse::ParseContext, trace::load_span, Error, ResourceCache, ResourceReader, Result, Tileset,
+};
 
-use crate::{Error, ResourceCache, ResourceReader, Result, Tileset};
+use super::parse_root_element;
 
 pub fn parse_tileset(
     path: &Path,
     reader: &mut impl ResourceReader,
     cache: &mut impl ResourceCache,
+    ctx: &mut ParseContext,
 ) -> Result<Tileset> {
-    let mut tileset_parser =
-        EventReader::new(
-            reader
-                .read_from(path)
-                .map_err(|err| Error::ResourceLoadingError {
-                    path: path.to_owned(),
-                    err: Box::new(err),
-                })?,
-        );
-    loop {
-        match tileset_parser.next().map_err(Error::XmlDecodingError)? {
-            XmlEvent::StartElement {
-                name, attributes, ..
-            } if name.local_name == "tileset" => {
-                return Tileset::parse_external_tileset(
-                    &mut tileset_parser.into_iter(),
-                    &attributes,
-                    path,
-                    reader,
-                    cache,
-                );
-            }
-            XmlEvent::EndDocument => {
-                return Err(Error::PrematureEnd(
-                    "Tileset Document ended before map was parsed".to_string(),
-                ))
-            }
-            _ => {}
-        }
-    }
+    let file = reader
+        .read_from(path)
+        .map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        })?;
+    parse_tileset_from(file, path, reader, cache, ctx)
+}
+
+/// Parses a tileset read from `file` instead of from `reader`, which is still used to read the
+/// files it references relative to `path`.
+pub fn parse_tileset_from(
+    file: impl Read,
+    path: &Path,
+    reader: &mut impl ResourceReader,
+    cache: &mut impl ResourceCache,
+    ctx: &mut ParseContext,
+) -> Result<Tileset> {
+    let span = load_span!("parse_tileset", path = %path.display());
+    parse_root_element(
+        file,
+        path,
+        "tileset",
+        &span,
+        ctx,
+        |parser, attributes, ctx| {
+            Tileset::parse_external_tileset(parser, &attributes, path, reader, cache, ctx)
+        },
+    )
 }
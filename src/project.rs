@@ -0,0 +1,418 @@
+//! Loading of Tiled [projects](https://doc.mapeditor.org/en/stable/manual/projects/) and of the
+//! [custom types](https://doc.mapeditor.org/en/stable/manual/custom-properties/#custom-types)
+//! they define. Requires the `project` feature.
+
+use std::{
+    io::Read,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{Error, Loader, Properties, PropertyValue, ResourceCache, ResourceReader, Result};
+
+/// A project, as loaded from a `.tiled-project` file with [`Loader::load_project()`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct Project {
+    /// The path first used in a [`ResourceReader`] to load this project.
+    pub source: PathBuf,
+    /// The folders shown in the project view of Tiled, relative to the directory of the project.
+    pub folders: Vec<String>,
+    /// The custom property types defined by the project, in the order they appear in the file.
+    pub property_types: Vec<PropertyType>,
+}
+
+/// A custom property type defined by a [`Project`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum PropertyType {
+    /// A class, which groups several members.
+    Class(ClassType),
+    /// An enum, which restricts a property to a set of values.
+    Enum(EnumType),
+}
+
+impl PropertyType {
+    /// The name of the type, which properties and classes of objects, layers and tiles refer to.
+    pub fn name(&self) -> &str {
+        match self {
+            PropertyType::Class(class) => &class.name,
+            PropertyType::Enum(enum_type) => &enum_type.name,
+        }
+    }
+}
+
+/// A custom class, as defined by a [`Project`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ClassType {
+    /// The unique ID of the type within the project.
+    pub id: u32,
+    /// The name of the class.
+    pub name: String,
+    /// The members of the class, in the order Tiled shows them in.
+    pub members: Vec<ClassMember>,
+    /// What the class can be used for, with the names Tiled gives them: `property`, `map`,
+    /// `layer`, `object`, `tile`, `tileset`, `wangcolor`, `wangset` and `project`. Empty for
+    /// projects written before Tiled 1.9, where classes could be used for anything.
+    pub use_as: Vec<String>,
+}
+
+impl ClassType {
+    /// Returns whether the class can be used for `usage`, one of the names listed in
+    /// [`ClassType::use_as`].
+    pub fn is_usable_as(&self, usage: &str) -> bool {
+        self.use_as.is_empty() || self.use_as.iter().any(|used_as| used_as == usage)
+    }
+
+    /// Returns the member with the name given.
+    pub fn member(&self, name: &str) -> Option<&ClassMember> {
+        self.members.iter().find(|member| &*member.name == name)
+    }
+}
+
+/// A member of a [`ClassType`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ClassMember {
+    /// The name of the member.
+    pub name: Arc<str>,
+    /// The default value of the member. Members that are themselves classes only hold the members
+    /// whose default differs from the one of their class.
+    pub value: PropertyValue,
+    /// The name of the custom type of the member, for members that are classes or enums.
+    pub property_type: Option<String>,
+}
+
+/// A custom enum, as defined by a [`Project`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct EnumType {
+    /// The unique ID of the type within the project.
+    pub id: u32,
+    /// The name of the enum.
+    pub name: String,
+    /// The values of the enum.
+    pub values: Vec<String>,
+    /// How properties of this type store their value.
+    pub storage_type: EnumStorageType,
+    /// Whether properties of this type can hold several values at once, which are then separated
+    /// by commas, or stored as bit flags by index.
+    pub values_as_flags: bool,
+}
+
+/// How the properties of an [`EnumType`] store their value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EnumStorageType {
+    /// The name of the value, in a [`PropertyValue::StringValue`].
+    #[default]
+    String,
+    /// The index of the value, in a [`PropertyValue::IntValue`].
+    Int,
+}
+
+impl Project {
+    /// Parses a project from the contents of a `.tiled-project` file. `path` is the path the file
+    /// was read from.
+    pub fn parse(path: impl AsRef<Path>, json: &[u8]) -> Result<Project> {
+        let invalid = |description: String| Error::InvalidProject { description };
+        let raw: RawProject =
+            serde_json::from_slice(json).map_err(|err| invalid(err.to_string()))?;
+
+        let mut property_types = Vec::with_capacity(raw.property_types.len());
+        for raw_type in &raw.property_types {
+            property_types.push(match raw_type.kind.as_str() {
+                "class" => PropertyType::Class(ClassType {
+                    id: raw_type.id,
+                    name: raw_type.name.clone(),
+                    members: raw_type
+                        .members
+                        .iter()
+                        .map(|member| {
+                            Ok(ClassMember {
+                                name: member.name.as_str().into(),
+                                value: member_value(
+                                    &raw.property_types,
+                                    &member.kind,
+                                    member.property_type.as_deref(),
+                                    &member.value,
+                                )
+                                .map_err(|err| {
+                                    invalid(format!(
+                                        "member `{}` of class `{}`: {}",
+                                        member.name, raw_type.name, err
+                                    ))
+                                })?,
+                                property_type: member.property_type.clone(),
+                            })
+                        })
+                        .collect::<Result<_>>()?,
+                    use_as: raw_type.use_as.clone(),
+                }),
+                "enum" => PropertyType::Enum(EnumType {
+                    id: raw_type.id,
+                    name: raw_type.name.clone(),
+                    values: raw_type.values.clone(),
+                    storage_type: match raw_type.storage_type.as_deref() {
+                        None | Some("string") => EnumStorageType::String,
+                        Some("int") => EnumStorageType::Int,
+                        Some(other) => {
+                            return Err(invalid(format!(
+                                "unknown storage type `{}` of enum `{}`",
+                                other, raw_type.name
+                            )))
+                        }
+                    },
+                    values_as_flags: raw_type.values_as_flags,
+                }),
+                other => {
+                    return Err(invalid(format!(
+                        "unknown kind `{}` of property type `{}`",
+                        other, raw_type.name
+                    )))
+                }
+            });
+        }
+
+        Ok(Project {
+            source: path.as_ref().to_owned(),
+            folders: raw.folders,
+            property_types,
+        })
+    }
+
+    /// Returns the custom type with the name given.
+    pub fn property_type(&self, name: &str) -> Option<&PropertyType> {
+        self.property_types
+            .iter()
+            .find(|property_type| property_type.name() == name)
+    }
+
+    /// Returns the custom class with the name given.
+    pub fn class(&self, name: &str) -> Option<&ClassType> {
+        self.property_types
+            .iter()
+            .find_map(|property_type| match property_type {
+                PropertyType::Class(class) if class.name == name => Some(class),
+                _ => None,
+            })
+    }
+
+    /// Returns the custom enum with the name given.
+    pub fn enum_type(&self, name: &str) -> Option<&EnumType> {
+        self.property_types
+            .iter()
+            .find_map(|property_type| match property_type {
+                PropertyType::Enum(enum_type) if enum_type.name == name => Some(enum_type),
+                _ => None,
+            })
+    }
+}
+
+impl Project {
+    /// Fills in `properties` with the default values of the members of `class` that they don't
+    /// set, if the project defines such a class that can be used for `usage`, one of the names
+    /// listed in [`ClassType::use_as`]. This is how Tiled evaluates the properties of anything
+    /// that has a class, since it only saves the members that differ from their default.
+    ///
+    /// The members of class properties within `properties` are filled in the same way, from the
+    /// defaults given by the class that contains them first and then from their own class.
+    /// Use [`Map::apply_project_defaults()`](crate::Map::apply_project_defaults) to do this for
+    /// everything in a map.
+    pub fn apply_class_defaults(
+        &self,
+        class: Option<&str>,
+        usage: &str,
+        properties: &mut Properties,
+    ) {
+        let mut classes = Vec::new();
+        match class
+            .and_then(|name| self.class(name))
+            .filter(|class| class.is_usable_as(usage))
+        {
+            Some(class) => self.fill_class(class, properties, &mut classes),
+            None => self.fill_members(properties, &mut classes),
+        }
+    }
+
+    /// Fills in the members of `class` missing from `properties`. `classes` are the classes being
+    /// filled in already, which classes containing themselves would otherwise fill in forever.
+    fn fill_class<'a>(
+        &'a self,
+        class: &'a ClassType,
+        properties: &mut Properties,
+        classes: &mut Vec<&'a str>,
+    ) {
+        if classes.contains(&class.name.as_str()) {
+            return;
+        }
+        classes.push(&class.name);
+        for member in &class.members {
+            fill_value(properties, &member.name, &member.value);
+        }
+        self.fill_members(properties, classes);
+        classes.pop();
+    }
+
+    /// Fills in the members of the class properties within `properties` from their class.
+    fn fill_members<'a>(&'a self, properties: &mut Properties, classes: &mut Vec<&'a str>) {
+        for value in properties.values_mut() {
+            if let PropertyValue::ClassValue {
+                property_type,
+                properties,
+            } = value
+            {
+                if let Some(class) = self.class(property_type) {
+                    self.fill_class(class, properties, classes);
+                }
+            }
+        }
+    }
+}
+
+/// Sets the property `name` to `default` if it isn't set, or fills in the members missing from it
+/// if both are class properties of the same class.
+fn fill_value(properties: &mut Properties, name: &Arc<str>, default: &PropertyValue) {
+    match (properties.get_mut(name), default) {
+        (None, _) => {
+            properties.insert(name.clone(), default.clone());
+        }
+        (
+            Some(PropertyValue::ClassValue {
+                property_type,
+                properties: members,
+            }),
+            PropertyValue::ClassValue {
+                property_type: default_type,
+                properties: defaults,
+            },
+        ) if property_type == default_type => {
+            for (name, default) in defaults {
+                fill_value(members, name, default);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
+    /// Parses a `.tiled-project` file.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let project = Loader::new().load_project("assets/project/project.tiled-project")?;
+    /// let spawn = project.class("Spawn").unwrap();
+    /// assert!(spawn.is_usable_as("object"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_project(&mut self, path: impl AsRef<Path>) -> Result<Project> {
+        let path = path.as_ref();
+        let mut file =
+            self.reader_mut()
+                .read_from(path)
+                .map_err(|err| Error::ResourceLoadingError {
+                    path: path.to_owned(),
+                    err: Box::new(err),
+                })?;
+        let mut json = Vec::new();
+        file.read_to_end(&mut json)
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err: Box::new(err),
+            })?;
+        Project::parse(path, &json)
+    }
+}
+
+/// Converts the JSON value of a class member into a property value. Members that are classes only
+/// hold the members whose value differs from their default, whose types are found in the
+/// definition of their class.
+fn member_value(
+    types: &[RawPropertyType],
+    kind: &str,
+    property_type: Option<&str>,
+    value: &Value,
+) -> Result<PropertyValue> {
+    if kind == "class" {
+        let class = property_type.and_then(|name| {
+            types
+                .iter()
+                .find(|raw_type| raw_type.kind == "class" && raw_type.name == name)
+        });
+        let mut properties = Properties::new();
+        if let (Some(class), Value::Object(values)) = (class, value) {
+            for (name, value) in values {
+                if let Some(member) = class.members.iter().find(|member| &member.name == name) {
+                    properties.insert(
+                        name.as_str().into(),
+                        member_value(types, &member.kind, member.property_type.as_deref(), value)?,
+                    );
+                }
+            }
+        }
+        return Ok(PropertyValue::ClassValue {
+            property_type: property_type.unwrap_or_default().to_owned(),
+            properties,
+        });
+    }
+
+    let value = match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        value => value.to_string(),
+    };
+    let value = match kind {
+        // Colors that aren't set are written as empty strings
+        "color" if value.is_empty() => PropertyValue::new(kind.to_owned(), "#00000000".to_owned()),
+        _ => PropertyValue::new(kind.to_owned(), value),
+    };
+    value.map_err(|(err, _)| err)
+}
+
+/// The contents of a `.tiled-project` file. Fields Tiled doesn't always write have default
+/// values, and unknown fields are ignored.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawProject {
+    #[serde(default)]
+    folders: Vec<String>,
+    #[serde(default)]
+    property_types: Vec<RawPropertyType>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawPropertyType {
+    #[serde(default)]
+    id: u32,
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    members: Vec<RawClassMember>,
+    #[serde(default)]
+    use_as: Vec<String>,
+    #[serde(default)]
+    values: Vec<String>,
+    storage_type: Option<String>,
+    #[serde(default)]
+    values_as_flags: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawClassMember {
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    property_type: Option<String>,
+    #[serde(default)]
+    value: Value,
+}
@@ -1,15 +1,48 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
 
 use xml::{attribute::OwnedAttribute, reader::XmlEvent};
 
 use crate::{
     error::{Error, Result},
+    parse::ParseContext,
     util::{get_attrs, parse_tag, XmlEventResult},
+    Map, ParseWarningKind, Tileset,
 };
 
+mod custom;
+#[cfg(feature = "serde")]
+mod de;
+mod diff;
+mod from;
+
+pub use custom::*;
+#[cfg(feature = "serde")]
+pub use de::*;
+pub use diff::*;
+pub use from::*;
+
 /// Represents a RGBA color with 8-bit depth on each channel.
+///
+/// Tiled writes colors as `#AARRGGBB`, or `#RRGGBB` for opaque ones, with the alpha channel
+/// first. The conversions of this type make the order of the channels explicit in their name, so
+/// that colors can be handed to renderers expecting another order without guessing.
+///
+/// ## Example
+/// ```
+/// use tiled::Color;
+///
+/// let color: Color = "#80ff0000".parse().unwrap();
+/// assert_eq!(color.to_argb_u32(), 0x80ff0000);
+/// assert_eq!(color.to_rgba_u32(), 0xff000080);
+/// assert_eq!(color.to_rgba_f32(), [1.0, 0.0, 0.0, 128.0 / 255.0]);
+/// ```
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub alpha: u8,
     pub red: u8,
@@ -17,6 +50,53 @@ pub struct Color {
     pub blue: u8,
 }
 
+impl Color {
+    /// Returns the channels of the color as `[red, green, blue, alpha]` floating point values
+    /// between 0 and 1, which is what most graphics APIs take.
+    pub fn to_rgba_f32(&self) -> [f32; 4] {
+        [self.red, self.green, self.blue, self.alpha].map(|channel| channel as f32 / 255.0)
+    }
+
+    /// Returns the color packed as `0xRRGGBBAA`.
+    pub fn to_rgba_u32(&self) -> u32 {
+        u32::from_be_bytes([self.red, self.green, self.blue, self.alpha])
+    }
+
+    /// Returns the color packed as `0xAARRGGBB`, the order Tiled writes colors in.
+    pub fn to_argb_u32(&self) -> u32 {
+        u32::from_be_bytes([self.alpha, self.red, self.green, self.blue])
+    }
+
+    /// Creates a color from one packed as `0xRRGGBBAA`.
+    pub fn from_rgba_u32(rgba: u32) -> Color {
+        let [red, green, blue, alpha] = rgba.to_be_bytes();
+        Color {
+            alpha,
+            red,
+            green,
+            blue,
+        }
+    }
+
+    /// Creates a color from one packed as `0xAARRGGBB`, the order Tiled writes colors in.
+    pub fn from_argb_u32(argb: u32) -> Color {
+        let [alpha, red, green, blue] = argb.to_be_bytes();
+        Color {
+            alpha,
+            red,
+            green,
+            blue,
+        }
+    }
+}
+
+impl From<Color> for [f32; 4] {
+    /// Converts the color with [`Color::to_rgba_f32()`].
+    fn from(color: Color) -> Self {
+        color.to_rgba_f32()
+    }
+}
+
 impl FromStr for Color {
     type Err = ();
 
@@ -65,6 +145,7 @@ impl FromStr for Color {
 ///
 /// Also read the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tmx-properties).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum PropertyValue {
     /// A boolean value. Corresponds to the `bool` property type.
     BoolValue(bool),
@@ -90,58 +171,121 @@ pub enum PropertyValue {
         /// A set of properties.
         properties: Properties,
     },
+    /// A value of a type this crate doesn't know, read by the parser registered for it with
+    /// [`Loader::register_property_type()`](crate::Loader::register_property_type).
+    Custom(CustomValue),
 }
 
 impl PropertyValue {
-    fn new(property_type: String, value: String) -> Result<PropertyValue> {
+    /// Creates a property of a type built into Tiled from its value, handing the value back along
+    /// with the error if it can't be, e.g. to parse it as a custom type instead.
+    pub(crate) fn new(
+        property_type: String,
+        value: String,
+    ) -> std::result::Result<PropertyValue, (Error, String)> {
+        fn invalid(description: String, value: String) -> (Error, String) {
+            (Error::InvalidPropertyValue { description }, value)
+        }
+
         // Check the property type against the value.
         match property_type.as_str() {
             "bool" => match value.parse() {
                 Ok(val) => Ok(PropertyValue::BoolValue(val)),
-                Err(err) => Err(Error::InvalidPropertyValue {
-                    description: err.to_string(),
-                }),
+                Err(err) => Err(invalid(err.to_string(), value)),
             },
             "float" => match value.parse() {
                 Ok(val) => Ok(PropertyValue::FloatValue(val)),
-                Err(err) => Err(Error::InvalidPropertyValue {
-                    description: err.to_string(),
-                }),
+                Err(err) => Err(invalid(err.to_string(), value)),
             },
             "int" => match value.parse() {
                 Ok(val) => Ok(PropertyValue::IntValue(val)),
-                Err(err) => Err(Error::InvalidPropertyValue {
-                    description: err.to_string(),
-                }),
+                Err(err) => Err(invalid(err.to_string(), value)),
+            },
+            "color" if value.len() > 1 => match Color::from_str(&value) {
+                Ok(color) => Ok(PropertyValue::ColorValue(color)),
+                Err(()) => Err(invalid("Couldn't parse color".to_string(), value)),
             },
-            "color" if value.len() > 1 => Color::from_str(&value)
-                .map(PropertyValue::ColorValue)
-                .map_err(|_| Error::InvalidPropertyValue {
-                    description: "Couldn't parse color".to_string(),
-                }),
             "string" => Ok(PropertyValue::StringValue(value)),
             "object" => match value.parse() {
                 Ok(val) => Ok(PropertyValue::ObjectValue(val)),
-                Err(err) => Err(Error::InvalidPropertyValue {
-                    description: err.to_string(),
-                }),
+                Err(err) => Err(invalid(err.to_string(), value)),
             },
             "file" => Ok(PropertyValue::FileValue(value)),
-            _ => Err(Error::UnknownPropertyType {
-                type_name: property_type,
-            }),
+            _ => Err((
+                Error::UnknownPropertyType {
+                    type_name: property_type,
+                },
+                value,
+            )),
+        }
+    }
+}
+
+impl PropertyValue {
+    /// Returns the path a [file property](PropertyValue::FileValue) refers to, given the path of
+    /// the file the property was loaded from, such as [`Map::source`] or [`Tileset::source`].
+    /// Returns [`None`] for other properties and for file properties that aren't set.
+    ///
+    /// File properties are stored relative to the file that defines them, while the returned path
+    /// is relative to the working directory like every other path of this crate, so that it can
+    /// be given to a [`ResourceReader`](crate::ResourceReader).
+    ///
+    /// ## Example
+    /// ```
+    /// use std::path::Path;
+    /// use tiled::PropertyValue;
+    ///
+    /// let value = PropertyValue::FileValue("../sounds/door.ogg".to_owned());
+    /// assert_eq!(
+    ///     value.resolve_file(Path::new("assets/maps/level.tmx")),
+    ///     Some(Path::new("assets/maps/../sounds/door.ogg").to_owned())
+    /// );
+    /// ```
+    pub fn resolve_file(&self, source: &Path) -> Option<PathBuf> {
+        match self {
+            PropertyValue::FileValue(path) if !path.is_empty() => {
+                Some(source.parent().unwrap_or_else(|| Path::new("")).join(path))
+            }
+            _ => None,
         }
     }
 }
 
+impl Map {
+    /// Returns the path a [file property](PropertyValue::FileValue) of this map, or of one of its
+    /// layers or objects, refers to. See [`PropertyValue::resolve_file()`].
+    ///
+    /// Properties of tilesets and of their tiles are relative to the tileset file instead, and must
+    /// be resolved with [`Tileset::resolve_file_property()`].
+    pub fn resolve_file_property(&self, value: &PropertyValue) -> Option<PathBuf> {
+        value.resolve_file(&self.source)
+    }
+}
+
+impl Tileset {
+    /// Returns the path a [file property](PropertyValue::FileValue) of this tileset, or of one of
+    /// its tiles, refers to. See [`PropertyValue::resolve_file()`].
+    pub fn resolve_file_property(&self, value: &PropertyValue) -> Option<PathBuf> {
+        value.resolve_file(&self.source)
+    }
+}
+
 /// A custom property container.
-pub type Properties = HashMap<String, PropertyValue>;
+///
+/// Property names are shared between all the properties loaded by the same
+/// [`Loader`](crate::Loader) that have the same name, which saves memory in maps where the same
+/// properties are used many times.
+///
+/// Properties are kept in the order they were written in the file, the same order Tiled shows
+/// them in, and new properties are added last.
+pub type Properties = indexmap::IndexMap<Arc<str>, PropertyValue>;
 
 pub(crate) fn parse_properties(
     parser: &mut impl Iterator<Item = XmlEventResult>,
+    ctx: &mut ParseContext,
 ) -> Result<Properties> {
-    let mut p = HashMap::new();
-    parse_tag!(parser, "properties", {
+    let mut p = Properties::new();
+    parse_tag!(parser, ctx, "properties", {
         "property" => |attrs:Vec<OwnedAttribute>| {
             let (t, v_attr, k, p_t) = get_attrs!(
                 for attr in attrs {
@@ -158,11 +302,11 @@ pub(crate) fn parse_properties(
                 // element. Only the actually set members are saved. When no members have been set
                 // the properties element is left out entirely.
                 let properties = if has_properties_tag_next(parser) {
-                    parse_properties(parser)?
+                    parse_properties(parser, ctx)?
                 } else {
-                    HashMap::new()
+                    Properties::new()
                 };
-                p.insert(k, PropertyValue::ClassValue {
+                p.insert(ctx.intern(&k), PropertyValue::ClassValue {
                     property_type: p_t.unwrap_or_default(),
                     properties,
                 });
@@ -182,7 +326,27 @@ pub(crate) fn parse_properties(
                 }
             };
 
-            p.insert(k, PropertyValue::new(t, v)?);
+            // The value is only kept when it couldn't be parsed, in case it is stored as a string
+            let value = match PropertyValue::new(t, v) {
+                Ok(value) => Ok(value),
+                Err((Error::UnknownPropertyType { type_name }, v)) => {
+                    ctx.parse_custom_property(type_name, &v).map_err(|err| (err, v))
+                }
+                Err(err) => Err(err),
+            };
+            match value {
+                Ok(value) => {
+                    p.insert(ctx.intern(&k), value);
+                }
+                Err((Error::UnknownPropertyType { type_name }, v)) if ctx.lenient() => {
+                    ctx.warn(ParseWarningKind::UnknownPropertyType { name: k.clone(), type_name });
+                    p.insert(ctx.intern(&k), PropertyValue::StringValue(v));
+                }
+                Err((Error::InvalidPropertyValue { description }, _)) if ctx.lenient() => {
+                    ctx.warn(ParseWarningKind::InvalidPropertyValue { name: k, description });
+                }
+                Err((err, _)) => return Err(err),
+            }
             Ok(())
         },
     });
@@ -0,0 +1,122 @@
+//! Properties of types this crate doesn't know, read by the parsers registered with
+//! [`Loader::register_property_type()`](crate::Loader::register_property_type).
+
+use std::{any::Any, collections::HashMap, fmt, sync::Arc};
+
+use crate::{Error, PropertyValue, Result};
+
+/// Reads the value of properties of a type this crate doesn't know, registered with
+/// [`Loader::register_property_type()`](crate::Loader::register_property_type).
+///
+/// Implemented for the functions taking the value of a property as written in the file and
+/// returning the value to store, or an error describing why the value is invalid.
+pub trait PropertyValueParser: Send + Sync {
+    /// Reads `value`, the `value` attribute of a property of the type the parser was registered
+    /// for, failing with a description of the problem if it is invalid.
+    fn parse(&self, value: &str) -> std::result::Result<Box<dyn Any + Send + Sync>, String>;
+}
+
+impl<F, T, E> PropertyValueParser for F
+where
+    F: Fn(&str) -> std::result::Result<T, E> + Send + Sync,
+    T: Any + Send + Sync,
+    E: fmt::Display,
+{
+    fn parse(&self, value: &str) -> std::result::Result<Box<dyn Any + Send + Sync>, String> {
+        self(value)
+            .map(|value| Box::new(value) as Box<dyn Any + Send + Sync>)
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// The value of a property of a type this crate doesn't know, as stored in
+/// [`PropertyValue::Custom`].
+///
+/// Along with the value the [`PropertyValueParser`] of the type returned, the type name and the
+/// value as written in the file are kept, so that the property can be saved again. Two custom
+/// values are equal if these are.
+#[derive(Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub struct CustomValue {
+    /// The `type` attribute of the property.
+    pub type_name: String,
+    /// The `value` attribute of the property.
+    pub value: String,
+    #[cfg_attr(feature = "snapshot", serde(skip, default = "CustomValue::no_data"))]
+    data: Arc<dyn Any + Send + Sync>,
+}
+
+impl CustomValue {
+    /// Creates a custom value read from `value` by the parser of `type_name`.
+    pub fn new(
+        type_name: impl Into<String>,
+        value: impl Into<String>,
+        data: impl Any + Send + Sync,
+    ) -> Self {
+        Self {
+            type_name: type_name.into(),
+            value: value.into(),
+            data: Arc::new(data),
+        }
+    }
+
+    /// Returns the value the parser of the type returned if it is a `T`.
+    ///
+    /// Values deserialized from a snapshot weren't read by a parser, so this always returns
+    /// [`None`] for them.
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.data.downcast_ref()
+    }
+
+    #[cfg(feature = "snapshot")]
+    fn no_data() -> Arc<dyn Any + Send + Sync> {
+        Arc::new(())
+    }
+}
+
+impl PartialEq for CustomValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.type_name == other.type_name && self.value == other.value
+    }
+}
+
+impl fmt::Debug for CustomValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomValue")
+            .field("type_name", &self.type_name)
+            .field("value", &self.value)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The parsers registered with a [`Loader`](crate::Loader), by the type name they read.
+#[derive(Clone, Default)]
+pub(crate) struct PropertyParsers(HashMap<String, Arc<dyn PropertyValueParser>>);
+
+impl PropertyParsers {
+    pub fn insert(&mut self, type_name: String, parser: Arc<dyn PropertyValueParser>) {
+        self.0.insert(type_name, parser);
+    }
+
+    /// Reads a property of an unknown type with the parser registered for it, failing with
+    /// [`Error::UnknownPropertyType`] if there is none.
+    pub fn parse(&self, type_name: String, value: &str) -> Result<PropertyValue> {
+        match self.0.get(&type_name) {
+            Some(parser) => match parser.parse(value) {
+                Ok(data) => Ok(PropertyValue::Custom(CustomValue {
+                    type_name,
+                    value: value.to_owned(),
+                    data: Arc::from(data),
+                })),
+                Err(description) => Err(Error::InvalidPropertyValue { description }),
+            },
+            None => Err(Error::UnknownPropertyType { type_name }),
+        }
+    }
+}
+
+impl fmt::Debug for PropertyParsers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
@@ -0,0 +1,204 @@
+//! Deserializing [`Properties`] into user types with serde. Requires the `serde` feature.
+
+use std::{fmt::Display, sync::Arc};
+
+use serde::de::{
+    self, value::BorrowedStrDeserializer, DeserializeSeed, Deserializer, IntoDeserializer,
+    MapAccess, Visitor,
+};
+
+use super::from::within_property;
+use crate::{Error, Properties, PropertyValue, Result};
+
+/// Extracts a set of [`Properties`] into any type implementing [`serde::Deserialize`]. Requires
+/// the `serde` feature.
+///
+/// Properties are deserialized like a map from their names to their values, so structs deriving
+/// `Deserialize` get their fields from the properties of the same name. Values map to serde types
+/// as follows:
+/// - `bool`, `int` and `float` properties give the corresponding numbers, which can be read into
+///   any numeric type they fit in.
+/// - `string` and `file` properties give strings, which can also be read into enums with unit
+///   variants of the same name. `int` properties can be read into such enums as well, by the
+///   index of the variant, which is how Tiled stores enums whose values are saved as numbers.
+/// - `color` properties give `#AARRGGBB` strings, or `0xAARRGGBB` numbers when read into a `u32`.
+/// - `object` properties give the ID of the object, or nothing when read into an [`Option`] if
+///   the property is unset.
+/// - `class` properties give nested maps of their members.
+///
+/// Properties that are missing can be read into [`Option`]s, or given defaults with
+/// `#[serde(default)]`. Errors are reported as [`Error::InvalidPropertyValue`], prefixed by the
+/// names of the properties leading to the offending value.
+///
+/// ## Example
+/// ```
+/// use serde::Deserialize;
+/// use tiled::{DeserializeProperties, Loader};
+///
+/// #[derive(Deserialize)]
+/// struct Settings {
+///     #[serde(rename = "class property")]
+///     class: TestType,
+///     speed: Option<f32>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct TestType {
+///     test_property_1: u8,
+/// }
+///
+/// # fn main() -> tiled::Result<()> {
+/// let map = Loader::new().load_tmx_map("assets/tiled_class_property.tmx")?;
+/// let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+/// let object = layer.get_object(0).unwrap();
+///
+/// let settings: Settings = object.properties.deserialize()?;
+/// assert_eq!(settings.class.test_property_1, 3);
+/// assert_eq!(settings.speed, None);
+/// # Ok(())
+/// # }
+/// ```
+pub trait DeserializeProperties {
+    /// Deserializes the properties into a `T`. See [`DeserializeProperties`].
+    fn deserialize<'de, T: de::Deserialize<'de>>(&'de self) -> Result<T>;
+}
+
+impl DeserializeProperties for Properties {
+    fn deserialize<'de, T: de::Deserialize<'de>>(&'de self) -> Result<T> {
+        T::deserialize(PropertiesDeserializer(self))
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Error::InvalidPropertyValue {
+            description: msg.to_string(),
+        }
+    }
+}
+
+struct PropertiesDeserializer<'de>(&'de Properties);
+
+impl<'de> Deserializer<'de> for PropertiesDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(PropertiesAccess {
+            properties: self.0.iter(),
+            value: None,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier
+        ignored_any
+    }
+}
+
+type PropertiesIter<'de> = indexmap::map::Iter<'de, Arc<str>, PropertyValue>;
+
+struct PropertiesAccess<'de> {
+    properties: PropertiesIter<'de>,
+    /// The property whose name was last returned, and whose value is to be deserialized next.
+    value: Option<(&'de str, &'de PropertyValue)>,
+}
+
+impl<'de> MapAccess<'de> for PropertiesAccess<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.properties.next() {
+            Some((name, value)) => {
+                self.value = Some((name, value));
+                seed.deserialize(BorrowedStrDeserializer::new(name))
+                    .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        match self.value.take() {
+            Some((name, value)) => seed
+                .deserialize(ValueDeserializer(value))
+                .map_err(|err| within_property(name, err)),
+            None => Err(de::Error::custom(
+                "value requested before its property name",
+            )),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.properties.len())
+    }
+}
+
+struct ValueDeserializer<'de>(&'de PropertyValue);
+
+impl<'de> Deserializer<'de> for ValueDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            PropertyValue::BoolValue(value) => visitor.visit_bool(*value),
+            PropertyValue::FloatValue(value) => visitor.visit_f32(*value),
+            PropertyValue::IntValue(value) => visitor.visit_i32(*value),
+            PropertyValue::ColorValue(color) => {
+                visitor.visit_string(format!("#{:08x}", color.to_argb_u32()))
+            }
+            PropertyValue::StringValue(value) | PropertyValue::FileValue(value) => {
+                visitor.visit_borrowed_str(value)
+            }
+            PropertyValue::ObjectValue(id) => visitor.visit_u32(*id),
+            PropertyValue::ClassValue { properties, .. } => {
+                PropertiesDeserializer(properties).deserialize_any(visitor)
+            }
+            PropertyValue::Custom(custom) => visitor.visit_borrowed_str(&custom.value),
+        }
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            PropertyValue::ColorValue(color) => visitor.visit_u32(color.to_argb_u32()),
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0 {
+            PropertyValue::ObjectValue(0) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.0 {
+            PropertyValue::StringValue(value) => {
+                visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(value.as_str()))
+            }
+            PropertyValue::IntValue(index) if *index >= 0 => {
+                visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(*index as u32))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u64 u128 f32 f64 char str string bytes byte_buf unit
+        unit_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
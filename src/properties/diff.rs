@@ -0,0 +1,175 @@
+//! Comparing and merging sets of [`Properties`].
+
+use std::sync::Arc;
+
+use crate::{Properties, PropertyValue};
+
+/// A difference between two sets of properties, as returned by [`PropertiesExt::diff()`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyChange<'a> {
+    /// The property is only in the other set.
+    Added {
+        /// The name of the property.
+        name: &'a Arc<str>,
+        /// The value of the property in the other set.
+        value: &'a PropertyValue,
+    },
+    /// The property is only in the original set.
+    Removed {
+        /// The name of the property.
+        name: &'a Arc<str>,
+        /// The value of the property in the original set.
+        value: &'a PropertyValue,
+    },
+    /// The property is in both sets, with different values.
+    Changed {
+        /// The name of the property.
+        name: &'a Arc<str>,
+        /// The value of the property in the original set.
+        old: &'a PropertyValue,
+        /// The value of the property in the other set.
+        new: &'a PropertyValue,
+    },
+}
+
+impl<'a> PropertyChange<'a> {
+    /// The name of the property that differs.
+    pub fn name(&self) -> &'a Arc<str> {
+        match self {
+            PropertyChange::Added { name, .. }
+            | PropertyChange::Removed { name, .. }
+            | PropertyChange::Changed { name, .. } => name,
+        }
+    }
+}
+
+/// A property whose value was replaced by one of another type while merging properties, as
+/// returned by [`PropertiesExt::merge()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyConflict {
+    /// The names leading to the property, starting with the property of the set merged into and
+    /// followed by the members of the class properties it is in, if any.
+    pub path: Vec<Arc<str>>,
+    /// The value that was replaced.
+    pub replaced: PropertyValue,
+}
+
+/// Operations on whole sets of [`Properties`].
+pub trait PropertiesExt {
+    /// Returns the properties that differ between `self` and `other`, sorted by name. Properties
+    /// with the same value in both sets are left out.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Properties, PropertiesExt, PropertyChange, PropertyValue};
+    ///
+    /// let mut old = Properties::new();
+    /// old.insert("health".into(), PropertyValue::IntValue(10));
+    /// old.insert("name".into(), PropertyValue::StringValue("goblin".to_owned()));
+    /// let mut new = old.clone();
+    /// new.insert("health".into(), PropertyValue::IntValue(20));
+    ///
+    /// let changes = old.diff(&new);
+    /// assert_eq!(changes.len(), 1);
+    /// assert!(matches!(
+    ///     changes[0],
+    ///     PropertyChange::Changed {
+    ///         new: PropertyValue::IntValue(20),
+    ///         ..
+    ///     }
+    /// ));
+    /// ```
+    fn diff<'a>(&'a self, other: &'a Properties) -> Vec<PropertyChange<'a>>;
+
+    /// Sets every property of `overrides` in `self`, and returns the properties whose value was
+    /// replaced by one of another type.
+    ///
+    /// Class properties of the same class are merged member by member, like Tiled does when an
+    /// object overrides some of the members of a class property of its template. Values of other
+    /// types, and class properties of another class, replace the existing value; those whose type
+    /// differs are reported as conflicts.
+    fn merge(&mut self, overrides: &Properties) -> Vec<PropertyConflict>;
+}
+
+impl PropertiesExt for Properties {
+    fn diff<'a>(&'a self, other: &'a Properties) -> Vec<PropertyChange<'a>> {
+        let mut changes: Vec<_> = self
+            .iter()
+            .filter_map(|(name, old)| match other.get(name) {
+                None => Some(PropertyChange::Removed { name, value: old }),
+                Some(new) if new != old => Some(PropertyChange::Changed { name, old, new }),
+                Some(_) => None,
+            })
+            .chain(
+                other
+                    .iter()
+                    .filter(|(name, _)| !self.contains_key(*name))
+                    .map(|(name, value)| PropertyChange::Added { name, value }),
+            )
+            .collect();
+        changes.sort_by(|a, b| a.name().cmp(b.name()));
+        changes
+    }
+
+    fn merge(&mut self, overrides: &Properties) -> Vec<PropertyConflict> {
+        let mut conflicts = Vec::new();
+        merge_into(self, overrides, &mut Vec::new(), &mut conflicts);
+        conflicts
+    }
+}
+
+fn merge_into(
+    properties: &mut Properties,
+    overrides: &Properties,
+    path: &mut Vec<Arc<str>>,
+    conflicts: &mut Vec<PropertyConflict>,
+) {
+    for (name, value) in overrides {
+        let existing = match properties.get_mut(name) {
+            Some(existing) => existing,
+            None => {
+                properties.insert(name.clone(), value.clone());
+                continue;
+            }
+        };
+        path.push(name.clone());
+        match (&mut *existing, value) {
+            (
+                PropertyValue::ClassValue {
+                    property_type,
+                    properties: members,
+                },
+                PropertyValue::ClassValue {
+                    property_type: override_type,
+                    properties: override_members,
+                },
+            ) if property_type == override_type => {
+                merge_into(members, override_members, path, conflicts)
+            }
+            (existing, value) => {
+                let replaced = std::mem::replace(existing, value.clone());
+                if !same_type(&replaced, value) {
+                    conflicts.push(PropertyConflict {
+                        path: path.clone(),
+                        replaced,
+                    });
+                }
+            }
+        }
+        path.pop();
+    }
+}
+
+fn same_type(a: &PropertyValue, b: &PropertyValue) -> bool {
+    match (a, b) {
+        (
+            PropertyValue::ClassValue {
+                property_type: a, ..
+            },
+            PropertyValue::ClassValue {
+                property_type: b, ..
+            },
+        ) => a == b,
+        _ => std::mem::discriminant(a) == std::mem::discriminant(b),
+    }
+}
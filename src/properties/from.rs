@@ -0,0 +1,128 @@
+//! Reading properties into Rust types, such as the ones generated from the custom types of a
+//! project.
+
+use crate::{Color, Error, Properties, PropertyValue, Result};
+
+/// A type that can be read from the value of a single property.
+///
+/// Implemented for the types matching each kind of property: [`bool`], [`i32`], [`f32`], which
+/// also accepts `int` properties, [`String`] for `string` and `file` properties, [`Color`] and
+/// [`u32`] for the IDs `object` properties refer to.
+pub trait FromPropertyValue: Sized {
+    /// Reads the value, failing with [`Error::InvalidPropertyValue`] if it doesn't have the
+    /// expected type.
+    fn from_property_value(value: &PropertyValue) -> Result<Self>;
+
+    /// Replaces `self` with the value of the property `name` if `properties` has it.
+    fn update_from_property(&mut self, properties: &Properties, name: &str) -> Result<()> {
+        if let Some(value) = properties.get(name) {
+            *self = Self::from_property_value(value).map_err(|err| within_property(name, err))?;
+        }
+        Ok(())
+    }
+}
+
+/// A type that can be read from a set of properties, such as the members of a class property or
+/// the properties of an object of a class.
+///
+/// Starting from the default value of the type, the properties that are set replace the fields
+/// they correspond to, which is how Tiled evaluates classes since it only saves the members that
+/// differ from their default. With the `project` feature, `tiled::codegen` generates types
+/// implementing this trait from the custom classes of a project.
+pub trait FromProperties: Default {
+    /// Replaces the fields of `self` that `properties` set.
+    fn update_from_properties(&mut self, properties: &Properties) -> Result<()>;
+
+    /// Reads a value from the default of the type and the fields that `properties` set.
+    fn from_properties(properties: &Properties) -> Result<Self> {
+        let mut value = Self::default();
+        value.update_from_properties(properties)?;
+        Ok(value)
+    }
+
+    /// Replaces the fields of `self` that the members of the class property `name` set, if
+    /// `properties` has it.
+    fn update_from_class_property(&mut self, properties: &Properties, name: &str) -> Result<()> {
+        match properties.get(name) {
+            Some(PropertyValue::ClassValue { properties, .. }) => self
+                .update_from_properties(properties)
+                .map_err(|err| within_property(name, err)),
+            Some(_) => Err(within_property(name, expected("class"))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Prefixes the description of an error that occurred while reading a property with the name of
+/// the property.
+pub(crate) fn within_property(name: &str, err: Error) -> Error {
+    match err {
+        Error::InvalidPropertyValue { description } => Error::InvalidPropertyValue {
+            description: format!("property `{}`: {}", name, description),
+        },
+        err => err,
+    }
+}
+
+fn expected(property_type: &str) -> Error {
+    Error::InvalidPropertyValue {
+        description: format!("expected a value of type {}", property_type),
+    }
+}
+
+impl FromPropertyValue for bool {
+    fn from_property_value(value: &PropertyValue) -> Result<Self> {
+        match value {
+            PropertyValue::BoolValue(value) => Ok(*value),
+            _ => Err(expected("bool")),
+        }
+    }
+}
+
+impl FromPropertyValue for i32 {
+    fn from_property_value(value: &PropertyValue) -> Result<Self> {
+        match value {
+            PropertyValue::IntValue(value) => Ok(*value),
+            _ => Err(expected("int")),
+        }
+    }
+}
+
+impl FromPropertyValue for f32 {
+    fn from_property_value(value: &PropertyValue) -> Result<Self> {
+        match value {
+            PropertyValue::FloatValue(value) => Ok(*value),
+            PropertyValue::IntValue(value) => Ok(*value as f32),
+            _ => Err(expected("float")),
+        }
+    }
+}
+
+impl FromPropertyValue for String {
+    fn from_property_value(value: &PropertyValue) -> Result<Self> {
+        match value {
+            PropertyValue::StringValue(value) | PropertyValue::FileValue(value) => {
+                Ok(value.clone())
+            }
+            _ => Err(expected("string")),
+        }
+    }
+}
+
+impl FromPropertyValue for Color {
+    fn from_property_value(value: &PropertyValue) -> Result<Self> {
+        match value {
+            PropertyValue::ColorValue(value) => Ok(*value),
+            _ => Err(expected("color")),
+        }
+    }
+}
+
+impl FromPropertyValue for u32 {
+    fn from_property_value(value: &PropertyValue) -> Result<Self> {
+        match value {
+            PropertyValue::ObjectValue(value) => Ok(*value),
+            _ => Err(expected("object")),
+        }
+    }
+}
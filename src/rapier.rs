@@ -0,0 +1,422 @@
+//! Integration with the [Rapier](https://rapier.rs) physics engine. Requires the `rapier` feature.
+//!
+//! [`map_to_colliders()`] builds the static geometry of a map as Rapier rigid bodies and colliders:
+//!
+//! - The collision shapes of the tiles placed in tile layers become colliders attached to a
+//!   single fixed rigid body, [`MapColliders::tile_body`]. Tile flips are taken into account.
+//! - Each object of object layers becomes a fixed rigid body, positioned and rotated like the
+//!   object, with a collider matching its shape.
+//!
+//! Points and text objects don't have a collider, since they have no area. Coordinates are the
+//! same as in Tiled, divided by [`ColliderSettings::pixels_per_meter`], so the Y axis points
+//! downwards and gravity should be positive.
+//!
+//! ## Example
+//! ```
+//! use tiled::{
+//!     rapier::{map_to_colliders, ColliderSettings, ColliderSource},
+//!     Loader,
+//! };
+//!
+//! # fn main() -> tiled::Result<()> {
+//! let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx")?;
+//!
+//! let mut settings = ColliderSettings::default();
+//! settings.pixels_per_meter = 32.0;
+//! let colliders = map_to_colliders(&map, &settings);
+//!
+//! // `colliders.bodies` and `colliders.colliders` can then be given to a physics pipeline
+//! for (handle, collider) in colliders.colliders.iter() {
+//!     if let Some(ColliderSource::Object { object_id, .. }) = colliders.source(handle) {
+//!         println!("object {} is at {:?}", object_id, collider.translation());
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use rapier2d::prelude::{
+    ColliderBuilder, ColliderHandle, ColliderSet, RigidBodyBuilder, RigidBodyHandle, RigidBodySet,
+    Vector,
+};
+
+use crate::{
+    outline::{tile_collisions, Outline, TileCollision},
+    Layer, LayerTileData, LayerType, Map, Object, ObjectShape, Properties, PropertyValue, TileId,
+    TileLayer,
+};
+
+/// Options for [`map_to_colliders()`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct ColliderSettings {
+    /// The number of pixels that make up a meter, by which every coordinate is divided.
+    ///
+    /// 1 by default.
+    pub pixels_per_meter: f32,
+    /// Whether to create colliders from the collision shapes of tiles.
+    ///
+    /// Enabled by default.
+    pub tiles: bool,
+    /// Whether to create colliders from objects.
+    ///
+    /// Enabled by default.
+    pub objects: bool,
+    /// Which objects and tile collision shapes get a collider.
+    ///
+    /// [`ColliderFilter::All`] by default.
+    pub filter: ColliderFilter,
+    /// If set, the objects and tile collision shapes that have a boolean custom property with
+    /// this name set to `true` become sensors, which detect contacts without blocking movement.
+    ///
+    /// Unset by default.
+    pub sensor_property: Option<String>,
+}
+
+impl Default for ColliderSettings {
+    fn default() -> Self {
+        Self {
+            pixels_per_meter: 1.0,
+            tiles: true,
+            objects: true,
+            filter: ColliderFilter::All,
+            sensor_property: None,
+        }
+    }
+}
+
+/// Selects the objects and tile collision shapes [`map_to_colliders()`] creates colliders for.
+///
+/// The class and properties of a tile collision shape are its own, falling back to the ones of
+/// its tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColliderFilter {
+    /// Every object and collision shape.
+    All,
+    /// Only the ones whose class is one of these.
+    Classes(Vec<String>),
+    /// Only the ones with a boolean custom property with this name set to `true`.
+    Property(String),
+}
+
+/// What a collider created by [`map_to_colliders()`] was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColliderSource {
+    /// A collision shape of a tile placed in a tile layer.
+    Tile {
+        /// The ID of the tile layer.
+        layer_id: u32,
+        /// The position of the tile within the layer, in tiles.
+        x: i32,
+        /// The position of the tile within the layer, in tiles.
+        y: i32,
+        /// The index of the tile's tileset within the map's tilesets.
+        tileset_index: usize,
+        /// The ID of the tile within its tileset.
+        tile_id: TileId,
+        /// The ID of the collision shape within the tile's collision objects.
+        shape_id: u32,
+    },
+    /// An object of an object layer.
+    Object {
+        /// The ID of the object layer.
+        layer_id: u32,
+        /// The ID of the object.
+        object_id: u32,
+    },
+}
+
+/// The rigid bodies and colliders built by [`map_to_colliders()`].
+#[derive(Clone, Default)]
+pub struct MapColliders {
+    /// The rigid bodies, which are all fixed.
+    pub bodies: RigidBodySet,
+    /// The colliders, each attached to one of [`MapColliders::bodies`].
+    pub colliders: ColliderSet,
+    /// The body holding the colliders of tiles, if any were created.
+    pub tile_body: Option<RigidBodyHandle>,
+    sources: HashMap<ColliderHandle, ColliderSource>,
+    object_bodies: HashMap<u32, RigidBodyHandle>,
+}
+
+impl MapColliders {
+    /// Returns what the collider with the given handle was built from.
+    #[inline]
+    pub fn source(&self, handle: ColliderHandle) -> Option<&ColliderSource> {
+        self.sources.get(&handle)
+    }
+
+    /// Returns every collider created along with what it was built from.
+    pub fn sources(&self) -> impl ExactSizeIterator<Item = (ColliderHandle, &ColliderSource)> {
+        self.sources
+            .iter()
+            .map(|(handle, source)| (*handle, source))
+    }
+
+    /// Returns the rigid body created for the object with the given ID.
+    #[inline]
+    pub fn object_body(&self, object_id: u32) -> Option<RigidBodyHandle> {
+        self.object_bodies.get(&object_id).copied()
+    }
+}
+
+impl std::fmt::Debug for MapColliders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MapColliders")
+            .field("bodies", &self.bodies.len())
+            .field("colliders", &self.colliders.len())
+            .field("tile_body", &self.tile_body)
+            .field("sources", &self.sources)
+            .finish()
+    }
+}
+
+/// Builds rigid bodies and colliders from the tiles and objects of a map. See the
+/// [module documentation](self) for what is converted and how.
+pub fn map_to_colliders(map: &Map, settings: &ColliderSettings) -> MapColliders {
+    let mut builder = Builder {
+        map,
+        settings,
+        result: MapColliders::default(),
+    };
+    builder.add_layers(map.layers(), (0.0, 0.0));
+    builder.result
+}
+
+struct Builder<'map, 's> {
+    map: &'map Map,
+    settings: &'s ColliderSettings,
+    result: MapColliders,
+}
+
+impl<'map> Builder<'map, '_> {
+    fn add_layers(&mut self, layers: impl Iterator<Item = Layer<'map>>, offset: (f32, f32)) {
+        for layer in layers {
+            let offset = (offset.0 + layer.offset_x, offset.1 + layer.offset_y);
+            match layer.layer_type() {
+                LayerType::Tiles(tiles) if self.settings.tiles => {
+                    self.add_tile_layer(layer.id(), &tiles, offset)
+                }
+                LayerType::Objects(objects) if self.settings.objects => {
+                    for object in objects.objects() {
+                        self.add_object(layer.id(), &object, offset);
+                    }
+                }
+                LayerType::Group(group) => self.add_layers(group.layers(), offset),
+                _ => {}
+            }
+        }
+    }
+
+    fn add_tile_layer(&mut self, layer_id: u32, tiles: &TileLayer, offset: (f32, f32)) {
+        match tiles {
+            TileLayer::Finite(tiles) => {
+                for y in 0..tiles.height() as i32 {
+                    for x in 0..tiles.width() as i32 {
+                        if let Some(tile) = tiles.get_tile_data(x, y) {
+                            self.add_tile(layer_id, x, y, &tile, offset);
+                        }
+                    }
+                }
+            }
+            TileLayer::Infinite(tiles) => {
+                for ((chunk_x, chunk_y), chunk) in tiles.chunks() {
+                    for y in 0..crate::ChunkData::HEIGHT as i32 {
+                        for x in 0..crate::ChunkData::WIDTH as i32 {
+                            if let Some(tile) = chunk.get_tile_data(x, y) {
+                                self.add_tile(
+                                    layer_id,
+                                    chunk_x * crate::ChunkData::WIDTH as i32 + x,
+                                    chunk_y * crate::ChunkData::HEIGHT as i32 + y,
+                                    &tile,
+                                    offset,
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn add_tile(
+        &mut self,
+        layer_id: u32,
+        x: i32,
+        y: i32,
+        tile_data: &LayerTileData,
+        offset: (f32, f32),
+    ) {
+        let scale = self.settings.pixels_per_meter;
+        for TileCollision {
+            tile,
+            object,
+            outline,
+        } in tile_collisions(self.map, tile_data, x, y)
+        {
+            let class = if object.user_type.is_empty() {
+                tile.user_type.as_deref()
+            } else {
+                Some(object.user_type.as_str())
+            };
+            let properties = [&object.properties, &tile.properties];
+            if !self.accepts(class, &properties) {
+                continue;
+            }
+
+            let outline =
+                outline.map(|(px, py)| ((px + offset.0) / scale, (py + offset.1) / scale));
+            let collider = match polygon_collider(outline) {
+                Some(collider) => collider.sensor(self.is_sensor(&properties)),
+                None => continue,
+            };
+
+            let body = match self.result.tile_body {
+                Some(body) => body,
+                None => {
+                    let body = self.result.bodies.insert(RigidBodyBuilder::fixed());
+                    self.result.tile_body = Some(body);
+                    body
+                }
+            };
+            self.insert_collider(
+                collider,
+                body,
+                ColliderSource::Tile {
+                    layer_id,
+                    x,
+                    y,
+                    tileset_index: tile_data.tileset_index(),
+                    tile_id: tile_data.id(),
+                    shape_id: object.id(),
+                },
+            );
+        }
+    }
+
+    fn add_object(&mut self, layer_id: u32, object: &Object, offset: (f32, f32)) {
+        let properties = [&object.properties];
+        let class = if object.user_type.is_empty() {
+            None
+        } else {
+            Some(object.user_type.as_str())
+        };
+        if !self.accepts(class, &properties) {
+            return;
+        }
+
+        let scale = self.settings.pixels_per_meter;
+        let collider = match &object.shape {
+            ObjectShape::Rect { width, height } => {
+                // Tile objects are positioned by their bottom left corner
+                let top = if object.get_tile().is_some() {
+                    -height
+                } else {
+                    0.0
+                };
+                ColliderBuilder::cuboid(width / 2.0 / scale, height / 2.0 / scale)
+                    .translation(self.vector(width / 2.0, top + height / 2.0))
+            }
+            ObjectShape::Ellipse { width, height } if width == height => {
+                ColliderBuilder::ball(width / 2.0 / scale)
+                    .translation(self.vector(width / 2.0, height / 2.0))
+            }
+            shape => match shape.outline().and_then(|outline| {
+                polygon_collider(outline.map(|(px, py)| (px / scale, py / scale)))
+            }) {
+                Some(collider) => collider,
+                None => return,
+            },
+        };
+
+        let body = self.result.bodies.insert(
+            RigidBodyBuilder::fixed()
+                .translation(self.vector(object.x + offset.0, object.y + offset.1))
+                .rotation(object.rotation.to_radians()),
+        );
+        self.result.object_bodies.insert(object.id(), body);
+        self.insert_collider(
+            collider.sensor(self.is_sensor(&properties)),
+            body,
+            ColliderSource::Object {
+                layer_id,
+                object_id: object.id(),
+            },
+        );
+    }
+
+    fn insert_collider(
+        &mut self,
+        collider: ColliderBuilder,
+        body: RigidBodyHandle,
+        source: ColliderSource,
+    ) {
+        let result = &mut self.result;
+        let handle = result
+            .colliders
+            .insert_with_parent(collider, body, &mut result.bodies);
+        result.sources.insert(handle, source);
+    }
+
+    fn vector(&self, x: f32, y: f32) -> Vector {
+        Vector::new(
+            x / self.settings.pixels_per_meter,
+            y / self.settings.pixels_per_meter,
+        )
+    }
+
+    /// Checks the filter of the settings against a class and the properties given, the first
+    /// ones taking precedence.
+    fn accepts(&self, class: Option<&str>, properties: &[&Properties]) -> bool {
+        match &self.settings.filter {
+            ColliderFilter::All => true,
+            ColliderFilter::Classes(classes) => {
+                class.is_some_and(|class| classes.iter().any(|c| c == class))
+            }
+            ColliderFilter::Property(name) => is_set(name, properties),
+        }
+    }
+
+    fn is_sensor(&self, properties: &[&Properties]) -> bool {
+        self.settings
+            .sensor_property
+            .as_ref()
+            .is_some_and(|name| is_set(name, properties))
+    }
+}
+
+/// Returns true if the first of the properties given that has a boolean property with this name
+/// sets it to `true`.
+fn is_set(name: &str, properties: &[&Properties]) -> bool {
+    properties
+        .iter()
+        .find_map(|properties| match properties.get(name) {
+            Some(PropertyValue::BoolValue(value)) => Some(*value),
+            _ => None,
+        })
+        .unwrap_or(false)
+}
+
+/// Builds a collider from an outline, decomposing closed ones into convex parts.
+fn polygon_collider(outline: Outline) -> Option<ColliderBuilder> {
+    let points: Vec<Vector> = outline
+        .points
+        .into_iter()
+        .map(|(x, y)| Vector::new(x, y))
+        .collect();
+    if points.len() < 2 {
+        return None;
+    }
+    if !outline.closed {
+        return Some(ColliderBuilder::polyline(points, None));
+    }
+    if points.len() < 3 {
+        return None;
+    }
+
+    let count = points.len() as u32;
+    let indices: Vec<[u32; 2]> = (0..count).map(|i| [i, (i + 1) % count]).collect();
+    Some(ColliderBuilder::convex_decomposition(&points, &indices))
+}
@@ -0,0 +1,116 @@
+//! Unprocessed XML data, preserved for forward compatibility.
+
+use xml::{attribute::OwnedAttribute, reader::XmlEvent};
+
+use crate::{util::XmlEventResult, Error, Result};
+
+/// An XML element that is kept as is, because this crate does not know what it represents.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawElement {
+    /// The name of the element.
+    pub name: String,
+    /// The attributes of the element, as name and value pairs in the order they appeared in.
+    pub attributes: Vec<(String, String)>,
+    /// The contents of the element, in the order they appeared in. Whitespace between elements is
+    /// not preserved.
+    pub children: Vec<RawNode>,
+}
+
+/// Part of the contents of a [`RawElement`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub enum RawNode {
+    /// A child element.
+    Element(RawElement),
+    /// Text, with any entities resolved.
+    Text(String),
+}
+
+/// The attributes and child elements of an element that this crate does not model. Only filled
+/// when loading with [`LoaderOptions::preserve_unknown`](crate::LoaderOptions::preserve_unknown)
+/// set, and empty otherwise.
+///
+/// This includes both things that Tiled itself doesn't know about, like data added by newer
+/// versions of it, and things this crate does not load, such as a map's `renderorder` attribute
+/// or a tileset's `<transformations>` element, so that they can be written back without any loss.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnknownXml {
+    /// The attributes that aren't modeled, as name and value pairs in the order they appeared in.
+    pub attributes: Vec<(String, String)>,
+    /// The child elements that aren't modeled, in the order they appeared in.
+    pub elements: Vec<RawElement>,
+}
+
+impl UnknownXml {
+    /// Returns true if there are no unknown attributes nor elements.
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.elements.is_empty()
+    }
+
+    /// Returns the value of the unknown attribute with the given name, if present.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr_name, _)| attr_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the attributes in `attrs` whose names are not in `known`.
+    pub(crate) fn attributes_except(
+        attrs: &[OwnedAttribute],
+        known: &[&str],
+    ) -> Vec<(String, String)> {
+        attrs
+            .iter()
+            .filter(|attr| !known.contains(&attr.name.local_name.as_str()))
+            .map(|attr| (attr.name.local_name.clone(), attr.value.clone()))
+            .collect()
+    }
+}
+
+impl RawElement {
+    /// Reads the rest of an element whose start has just been read from `parser`, including its
+    /// end.
+    pub(crate) fn read(
+        name: String,
+        attrs: &[OwnedAttribute],
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+    ) -> Result<RawElement> {
+        let mut stack = vec![RawElement {
+            name,
+            attributes: UnknownXml::attributes_except(attrs, &[]),
+            children: Vec::new(),
+        }];
+
+        for next in parser {
+            match next.map_err(Error::XmlDecodingError)? {
+                XmlEvent::StartElement {
+                    name, attributes, ..
+                } => stack.push(RawElement {
+                    name: name.local_name,
+                    attributes: UnknownXml::attributes_except(&attributes, &[]),
+                    children: Vec::new(),
+                }),
+                XmlEvent::EndElement { .. } => {
+                    let element = stack.pop().expect("stack always contains the root element");
+                    match stack.last_mut() {
+                        Some(parent) => parent.children.push(RawNode::Element(element)),
+                        None => return Ok(element),
+                    }
+                }
+                XmlEvent::Characters(text) | XmlEvent::CData(text) => {
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(RawNode::Text(text));
+                    }
+                }
+                XmlEvent::EndDocument => break,
+                _ => {}
+            }
+        }
+        Err(Error::PrematureEnd(
+            "Document ended before we expected.".to_string(),
+        ))
+    }
+}
@@ -1,5 +1,11 @@
 use std::io::BufReader;
-use std::{fs::File, io::Read, path::Path};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::Error;
 
 /// A trait defining types that can load data from a [`ResourcePath`](crate::ResourcePath).
 ///
@@ -35,6 +41,48 @@ pub trait ResourceReader {
 
     /// Try to return a reader object from a path into the resources filesystem.
     fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error>;
+
+    /// Lists the files and directories directly in the directory at `path`, sorted by path, or
+    /// returns [`None`] if the reader can't list directories, which is the default.
+    ///
+    /// Used by [`Loader::load_all_tmx_in_dir()`](crate::Loader::load_all_tmx_in_dir) to find
+    /// the maps to load.
+    fn list_directory(
+        &mut self,
+        _path: &Path,
+    ) -> Option<std::result::Result<Vec<DirectoryEntry>, Self::Error>> {
+        None
+    }
+}
+
+/// A file or directory found by [`ResourceReader::list_directory()`].
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DirectoryEntry {
+    /// The path of the entry, which is the path of the directory joined with its name.
+    pub path: PathBuf,
+    /// Whether the entry is a directory rather than a file.
+    pub is_directory: bool,
+}
+
+/// Lists the directory at `path` with `reader`, failing with [`Error::ResourceLoadingError`] if
+/// it can't be listed, including when the reader doesn't support listing directories.
+pub(crate) fn list_directory(
+    reader: &mut impl ResourceReader,
+    path: &Path,
+) -> crate::Result<Vec<DirectoryEntry>> {
+    match reader.list_directory(path) {
+        Some(entries) => entries.map_err(|err| Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(err),
+        }),
+        None => Err(Error::ResourceLoadingError {
+            path: path.to_owned(),
+            err: Box::new(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                "the resource reader can't list directories",
+            )),
+        }),
+    }
 }
 
 /// A [`ResourceReader`] that reads from [`File`] handles.
@@ -56,6 +104,29 @@ impl ResourceReader for FilesystemResourceReader {
         let file = File::open(path)?;
         Ok(BufReader::new(file))
     }
+
+    fn list_directory(
+        &mut self,
+        path: &Path,
+    ) -> Option<std::result::Result<Vec<DirectoryEntry>, Self::Error>> {
+        let list = || {
+            let directory = match path {
+                path if path == Path::new("") => Path::new("."),
+                path => path,
+            };
+            let mut entries = Vec::new();
+            for entry in std::fs::read_dir(directory)? {
+                let entry = entry?;
+                entries.push(DirectoryEntry {
+                    path: path.join(entry.file_name()),
+                    is_directory: entry.file_type()?.is_dir(),
+                });
+            }
+            entries.sort_unstable();
+            Ok(entries)
+        };
+        Some(list())
+    }
 }
 
 impl<T, R, E> ResourceReader for T
@@ -72,3 +143,229 @@ where
         self(path)
     }
 }
+
+/// The object-safe part of [`ResourceReader`] used by [`ChainedResourceReader`] to hold readers
+/// of different types.
+trait DynResourceReader {
+    fn read_boxed(
+        &mut self,
+        path: &Path,
+    ) -> Result<Box<dyn Read>, Box<dyn std::error::Error + Send + Sync>>;
+
+    fn list_boxed(
+        &mut self,
+        path: &Path,
+    ) -> Option<Result<Vec<DirectoryEntry>, Box<dyn std::error::Error + Send + Sync>>>;
+}
+
+impl<T> DynResourceReader for T
+where
+    T: ResourceReader,
+    T::Resource: 'static,
+{
+    fn read_boxed(
+        &mut self,
+        path: &Path,
+    ) -> Result<Box<dyn Read>, Box<dyn std::error::Error + Send + Sync>> {
+        match self.read_from(path) {
+            Ok(resource) => Ok(Box::new(resource)),
+            Err(err) => Err(Box::new(err)),
+        }
+    }
+
+    fn list_boxed(
+        &mut self,
+        path: &Path,
+    ) -> Option<Result<Vec<DirectoryEntry>, Box<dyn std::error::Error + Send + Sync>>> {
+        self.list_directory(path)
+            .map(|entries| entries.map_err(|err| Box::new(err) as _))
+    }
+}
+
+/// A [`ResourceReader`] that tries several readers in order, reading each file with the first
+/// one that succeeds, e.g. to let the files of a mod folder override the ones of the base game
+/// and fall back to defaults embedded in the executable.
+///
+/// ## Example
+/// ```
+/// use std::io::{Cursor, ErrorKind};
+/// use std::path::Path;
+/// use tiled::{ChainedResourceReader, FilesystemResourceReader, Loader};
+///
+/// # fn main() -> tiled::Result<()> {
+/// let reader = ChainedResourceReader::new()
+///     .with(|path: &Path| -> std::io::Result<_> {
+///         if path == Path::new("assets/tilesheet.tsx") {
+///             // A modded version of the tileset
+///             Ok(Cursor::new(&include_bytes!("../assets/tilesheet.tsx")[..]))
+///         } else {
+///             Err(ErrorKind::NotFound.into())
+///         }
+///     })
+///     .with(FilesystemResourceReader::new());
+///
+/// let map = Loader::with_reader(reader).load_tmx_map("assets/tiled_base64_external.tmx")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct ChainedResourceReader {
+    readers: Vec<Box<dyn DynResourceReader>>,
+}
+
+impl std::fmt::Debug for ChainedResourceReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChainedResourceReader")
+            .field("readers", &self.readers.len())
+            .finish()
+    }
+}
+
+impl ChainedResourceReader {
+    /// Creates a reader without any readers to try, which fails to read every file until some
+    /// are added.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a reader to try after the ones added before.
+    pub fn with<R>(mut self, reader: R) -> Self
+    where
+        R: ResourceReader + 'static,
+        R::Resource: 'static,
+    {
+        self.push(reader);
+        self
+    }
+
+    /// Adds a reader to try after the ones added before.
+    pub fn push<R>(&mut self, reader: R)
+    where
+        R: ResourceReader + 'static,
+        R::Resource: 'static,
+    {
+        self.readers.push(Box::new(reader));
+    }
+
+    /// The number of readers tried.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.readers.len()
+    }
+
+    /// Returns true if no readers have been added.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.readers.is_empty()
+    }
+}
+
+impl ResourceReader for ChainedResourceReader {
+    type Resource = Box<dyn Read>;
+    type Error = ChainedReadError;
+
+    fn read_from(&mut self, path: &Path) -> std::result::Result<Self::Resource, Self::Error> {
+        let mut errors = Vec::new();
+        for reader in &mut self.readers {
+            match reader.read_boxed(path) {
+                Ok(resource) => return Ok(resource),
+                Err(err) => errors.push(err),
+            }
+        }
+        Err(ChainedReadError { errors })
+    }
+
+    /// Lists the entries every reader that can list directories finds, so that the files of
+    /// each are found. Fails only if all of these readers fail.
+    fn list_directory(
+        &mut self,
+        path: &Path,
+    ) -> Option<std::result::Result<Vec<DirectoryEntry>, Self::Error>> {
+        let mut entries = Vec::new();
+        let mut errors = Vec::new();
+        let mut listed = false;
+        for reader in &mut self.readers {
+            match reader.list_boxed(path) {
+                Some(Ok(found)) => {
+                    listed = true;
+                    entries.extend(found);
+                }
+                Some(Err(err)) => errors.push(err),
+                None => {}
+            }
+        }
+        if listed {
+            entries.sort_unstable();
+            entries.dedup_by(|a, b| a.path == b.path);
+            Some(Ok(entries))
+        } else if errors.is_empty() {
+            None
+        } else {
+            Some(Err(ChainedReadError { errors }))
+        }
+    }
+}
+
+/// The error returned by a [`ChainedResourceReader`] when none of its readers could read a file.
+///
+/// It displays as its [most relevant](ChainedReadError::most_relevant) error, which is also its
+/// [source](std::error::Error::source).
+#[derive(Debug)]
+pub struct ChainedReadError {
+    errors: Vec<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl ChainedReadError {
+    /// The errors returned by each reader, in the order the readers were tried.
+    pub fn errors(&self) -> &[Box<dyn std::error::Error + Send + Sync>] {
+        &self.errors
+    }
+
+    /// The error that best explains why the file couldn't be read: The first one that doesn't
+    /// just say the file doesn't exist, since a reader failing otherwise, e.g. because it was
+    /// denied access to the file, most likely had the file the others didn't have. If every
+    /// reader said the file doesn't exist, this is the error of the first one.
+    ///
+    /// Errors are recognized as saying the file doesn't exist if they are [`std::io::Error`]s of
+    /// kind [`NotFound`](std::io::ErrorKind::NotFound), or 404 statuses returned by the
+    /// `HttpResourceReader` of the `http` feature. Returns [`None`] if there were no readers to
+    /// try.
+    pub fn most_relevant(&self) -> Option<&(dyn std::error::Error + Send + Sync + 'static)> {
+        self.errors
+            .iter()
+            .find(|err| !is_not_found(err.as_ref()))
+            .or_else(|| self.errors.first())
+            .map(|err| err.as_ref())
+    }
+}
+
+impl std::fmt::Display for ChainedReadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.most_relevant() {
+            Some(err) => write!(f, "{}", err),
+            None => write!(f, "No readers to read the file with"),
+        }
+    }
+}
+
+impl std::error::Error for ChainedReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.most_relevant()
+            .map(|err| err as &(dyn std::error::Error + 'static))
+    }
+}
+
+fn is_not_found(err: &(dyn std::error::Error + Send + Sync + 'static)) -> bool {
+    if let Some(err) = err.downcast_ref::<std::io::Error>() {
+        return err.kind() == std::io::ErrorKind::NotFound;
+    }
+    if let Some(err) = err.downcast_ref::<ChainedReadError>() {
+        return err.most_relevant().is_none_or(is_not_found);
+    }
+    #[cfg(feature = "http")]
+    if let Some(crate::HttpError::Status { code: 404, .. }) = err.downcast_ref::<crate::HttpError>()
+    {
+        return true;
+    }
+    false
+}
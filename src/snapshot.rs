@@ -0,0 +1,94 @@
+//! A compact binary encoding of [`Map`]s. Requires the `snapshot` feature.
+
+use crate::{Error, Map, Result, SnapshotError};
+
+/// The bytes every snapshot starts with.
+const MAGIC: &[u8; 4] = b"TMXS";
+
+/// The version of the snapshot format. Snapshots are not self-describing, so starting with the
+/// first release that includes them, this must be increased whenever the layout of any of the
+/// serialized types changes, and the snapshots of `test_snapshot_format_version` regenerated.
+/// Until then it stays at 1, whatever changes the unreleased layout goes through.
+const FORMAT_VERSION: u32 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + std::mem::size_of::<u32>();
+
+impl Map {
+    /// Encodes this map, along with its tilesets, into a compact binary snapshot that can be
+    /// loaded back with [`Map::from_snapshot()`] much faster than parsing the original files.
+    ///
+    /// This is intended for shipping preprocessed maps along with a game. Snapshots can only be
+    /// read by versions of this crate that use the same snapshot format, so they should be
+    /// regenerated from the original files when updating it.
+    ///
+    /// Tilesets shared between maps, or between a map and the templates it uses, are stored in
+    /// every snapshot that uses them.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Loader, Map};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let map = Loader::new().load_tmx_map("assets/tiled_base64_external.tmx")?;
+    /// let snapshot = map.to_snapshot();
+    ///
+    /// let loaded = Map::from_snapshot(&snapshot)?;
+    /// assert_eq!(loaded.layers().len(), map.layers().len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn to_snapshot(&self) -> Vec<u8> {
+        let mut snapshot = Vec::with_capacity(HEADER_LEN);
+        snapshot.extend_from_slice(MAGIC);
+        snapshot.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        postcard::to_extend(self, snapshot).expect("maps can always be serialized")
+    }
+
+    /// Decodes a map from a snapshot made with [`Map::to_snapshot()`].
+    ///
+    /// Fails with [`Error::InvalidSnapshot`] if the data isn't a snapshot, or if it was made by a
+    /// version of this crate with a different snapshot format.
+    pub fn from_snapshot(snapshot: &[u8]) -> Result<Map> {
+        if snapshot.len() < HEADER_LEN || &snapshot[..MAGIC.len()] != MAGIC {
+            return Err(Error::InvalidSnapshot(SnapshotError::InvalidHeader));
+        }
+
+        let mut version = [0; 4];
+        version.copy_from_slice(&snapshot[MAGIC.len()..HEADER_LEN]);
+        let version = u32::from_le_bytes(version);
+        if version != FORMAT_VERSION {
+            return Err(Error::InvalidSnapshot(SnapshotError::UnsupportedVersion {
+                found: version,
+                supported: FORMAT_VERSION,
+            }));
+        }
+
+        postcard::from_bytes(&snapshot[HEADER_LEN..])
+            .map_err(|err| Error::InvalidSnapshot(SnapshotError::Decoding(err.to_string())))
+    }
+}
+
+/// Serializes the boxed tile array of a [`ChunkData`](crate::ChunkData), which serde doesn't
+/// support because of its length.
+pub(crate) mod chunk_tiles {
+    use std::convert::TryInto;
+
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use crate::{ChunkData, LayerTileData};
+
+    type Tiles = Box<[Option<LayerTileData>; ChunkData::TILE_COUNT]>;
+
+    pub fn serialize<S: Serializer>(tiles: &Tiles, serializer: S) -> Result<S::Ok, S::Error> {
+        tiles[..].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Tiles, D::Error> {
+        let tiles = Vec::<Option<LayerTileData>>::deserialize(deserializer)?;
+        let len = tiles.len();
+        tiles
+            .into_boxed_slice()
+            .try_into()
+            .map_err(|_| D::Error::invalid_length(len, &"a full chunk of tiles"))
+    }
+}
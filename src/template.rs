@@ -1,14 +1,17 @@
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use xml::EventReader;
-use xml::{attribute::OwnedAttribute, reader::XmlEvent};
+use xml::attribute::OwnedAttribute;
 
 use crate::{
-    util::*, EmbeddedParseResultType, Error, MapTilesetGid, ObjectData, ResourceCache,
-    ResourceReader, Result, Tileset,
+    parse::ParseContext, trace::load_span, util::*, EmbeddedParseResultType, Error, Map,
+    MapTilesetGid, ObjectData, ObjectShape, Properties, ResourceCache, ResourceReader, Result,
+    TemplateInstance, TemplateOverrides, Tileset, UnknownXml,
 };
 
+mod library;
+pub use library::*;
+
 /// A template, consisting of an object and a tileset
 ///
 /// Templates define a tileset and object data to use for an object that can be shared between multiple objects and
@@ -24,11 +27,61 @@ pub struct Template {
 }
 
 impl Template {
+    /// Creates an object instantiated from this template at the given position, in pixels, like
+    /// Tiled does when placing the template, giving it the next free object ID of `map`. The
+    /// object can then be added to a layer with
+    /// [`EditTransaction::add_object()`](crate::EditTransaction::add_object), and follows changes
+    /// to the template applied with [`Map::reapply_template()`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let mut map = loader.load_tmx_map("assets/tiled_object_groups.tmx")?;
+    /// let template = loader.load_tx_template("assets/tiled_object_template.tx")?;
+    ///
+    /// let object = template.instantiate(&mut map, 64., 32.);
+    /// assert_eq!((object.x, object.y), (64., 32.));
+    /// assert!(object.tile_data().is_some());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn instantiate(&self, map: &mut Map, x: f32, y: f32) -> ObjectData {
+        let overrides = TemplateOverrides::default();
+        let mut object = ObjectData {
+            id: map.allocate_object_id(),
+            tile: None,
+            name: String::new(),
+            user_type: String::new(),
+            x,
+            y,
+            rotation: 0.,
+            visible: true,
+            shape: ObjectShape::Rect {
+                width: 0.,
+                height: 0.,
+            },
+            properties: Properties::new(),
+            unknown_xml: UnknownXml::default(),
+            template: None,
+        };
+        object.apply_template(self, &overrides);
+        object.template = Some(Box::new(TemplateInstance {
+            source: self.source.clone(),
+            overrides,
+        }));
+        object
+    }
+
     pub(crate) fn parse_template(
         path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<Arc<Template>> {
+        let span = load_span!("parse_template", path = %path.display());
         // Open the template file
         let file = reader
             .read_from(path)
@@ -37,30 +90,14 @@ impl Template {
                 err: Box::new(err),
             })?;
 
-        let mut template_parser = EventReader::new(file);
-        loop {
-            match template_parser.next().map_err(Error::XmlDecodingError)? {
-                XmlEvent::StartElement {
-                    name,
-                    attributes: _,
-                    ..
-                } if name.local_name == "template" => {
-                    let template = Self::parse_external_template(
-                        &mut template_parser.into_iter(),
-                        path,
-                        reader,
-                        cache,
-                    )?;
-                    return Ok(template);
-                }
-                XmlEvent::EndDocument => {
-                    return Err(Error::PrematureEnd(
-                        "Template Document ended before template element was parsed".to_string(),
-                    ))
-                }
-                _ => {}
-            }
-        }
+        crate::parse::xml::parse_root_element(
+            file,
+            path,
+            "template",
+            &span,
+            ctx,
+            |parser, _, ctx| Self::parse_external_template(parser, path, reader, cache, ctx),
+        )
     }
 
     fn parse_external_template(
@@ -68,29 +105,42 @@ impl Template {
         template_path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<Arc<Template>> {
         let mut object = Option::None;
         let mut tileset = None;
         let mut tileset_gid: Vec<MapTilesetGid> = vec![];
 
-        parse_tag!(parser, "template", {
+        parse_tag!(parser, ctx, "template", {
             "object" => |attrs| {
-                object = Some(ObjectData::new(parser, attrs, Some(&tileset_gid), tileset.clone(), template_path.parent().ok_or(Error::PathIsNotFile)?, reader, cache)?);
+                object = Some(ObjectData::new(parser, attrs, Some(&tileset_gid), tileset.clone(), template_path.parent().ok_or(Error::PathIsNotFile)?, reader, cache, ctx)?);
                 Ok(())
             },
             "tileset" => |attrs: Vec<OwnedAttribute>| {
-                let res = Tileset::parse_xml_in_map(parser, &attrs, template_path, reader, cache)?;
+                let res = Tileset::parse_xml_in_map(parser, &attrs, template_path, reader, cache, ctx)?;
                 match res.result_type {
                     EmbeddedParseResultType::ExternalReference { tileset_path } => {
-                        tileset = Some(if let Some(ts) = cache.get_tileset(&tileset_path) {
+                        let span = load_span!("resolve_tileset", path = %tileset_path.display(), cached = tracing::field::Empty);
+                        let cached = cache.get_tileset(&tileset_path);
+                        span.record("cached", cached.is_some());
+                        let resolved = if let Some(ts) = cached {
                             ts
                         } else {
-                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, reader, cache)?);
-                            cache.insert_tileset(tileset_path.clone(), tileset.clone());
+                            let errors = ctx.error_count();
+                            let tileset = Arc::new(crate::parse::xml::parse_tileset(&tileset_path, reader, cache, ctx)?);
+                            // Don't cache tilesets that are missing parts because of errors
+                            if ctx.error_count() == errors {
+                                cache.insert_tileset(tileset_path.clone(), tileset.clone());
+                            }
                             tileset
-                        });
+                        };
+                        ctx.dependencies_mut().add_tileset(&tileset_path, &resolved);
+                        ctx.resource_loaded(&tileset_path);
+                        tileset = Some(resolved);
                     }
                     EmbeddedParseResultType::Embedded { tileset: embedded_tileset } => {
+                        ctx.dependencies_mut().add_tileset_images(&embedded_tileset);
+                        ctx.resource_loaded(template_path);
                         tileset = Some(Arc::new(embedded_tileset));
                     },
                 };
@@ -0,0 +1,121 @@
+//! A registry of the templates a game instantiates objects from at runtime.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    util::normalize_path, Loader, Map, ObjectData, ResourceCache, ResourceReader, Result, Template,
+};
+
+/// A collection of loaded templates, looked up by path or name, e.g. to place enemies or pickups
+/// defined as templates in Tiled while a game is running.
+///
+/// Templates are keyed by their normalized path. Their name is the name of their file without
+/// its extension, which is how Tiled lists them.
+///
+/// ## Example
+/// ```
+/// use tiled::{Loader, TemplateLibrary};
+///
+/// # fn main() -> tiled::Result<()> {
+/// let mut loader = Loader::new();
+/// let mut map = loader.load_tmx_map("assets/templates/example.tmx")?;
+///
+/// // The templates the map uses, along with any other loaded explicitly
+/// let mut library = TemplateLibrary::from_cache(loader.cache());
+/// library.load(&mut loader, "assets/tiled_object_template.tx")?;
+///
+/// assert!(library.get("assets/templates/edge.tx").is_some());
+/// let object = library.spawn("simple_figure", &mut map, 48., 96.).unwrap();
+/// assert_eq!(object.name, library.get_by_name("simple_figure").unwrap().object.name);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TemplateLibrary {
+    templates: BTreeMap<PathBuf, Arc<Template>>,
+}
+
+impl TemplateLibrary {
+    /// Creates an empty library.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a library with all the templates in `cache`, such as the ones used by the maps
+    /// loaded so far.
+    pub fn from_cache(cache: &impl ResourceCache) -> Self {
+        let mut library = Self::new();
+        for (path, template) in cache.templates() {
+            library.insert(path, template.clone());
+        }
+        library
+    }
+
+    /// Loads the template at `path` with `loader`, or takes it from the cache of `loader`, and
+    /// adds it to the library, replacing the one with the same path if any.
+    pub fn load<Cache: ResourceCache, Reader: ResourceReader>(
+        &mut self,
+        loader: &mut Loader<Cache, Reader>,
+        path: impl AsRef<Path>,
+    ) -> Result<Arc<Template>> {
+        let template = loader.load_tx_template(path.as_ref())?;
+        self.insert(path, template.clone());
+        Ok(template)
+    }
+
+    /// Adds a template to the library under `path`, returning the one it replaces, if any.
+    pub fn insert(
+        &mut self,
+        path: impl AsRef<Path>,
+        template: Arc<Template>,
+    ) -> Option<Arc<Template>> {
+        self.templates
+            .insert(normalize_path(path.as_ref()), template)
+    }
+
+    /// Removes the template at `path` from the library, returning it.
+    pub fn remove(&mut self, path: impl AsRef<Path>) -> Option<Arc<Template>> {
+        self.templates.remove(&normalize_path(path.as_ref()))
+    }
+
+    /// Returns the template at `path`.
+    pub fn get(&self, path: impl AsRef<Path>) -> Option<&Arc<Template>> {
+        self.templates.get(&normalize_path(path.as_ref()))
+    }
+
+    /// Returns the template named `name`, or the first one by path if several templates in
+    /// different directories have this name.
+    pub fn get_by_name(&self, name: &str) -> Option<&Arc<Template>> {
+        self.iter()
+            .find(|(path, _)| path.file_stem().is_some_and(|stem| stem == name))
+            .map(|(_, template)| template)
+    }
+
+    /// Returns the templates of the library along with their path, sorted by path.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&Path, &Arc<Template>)> {
+        self.templates
+            .iter()
+            .map(|(path, template)| (path.as_path(), template))
+    }
+
+    /// Returns the number of templates in the library.
+    pub fn len(&self) -> usize {
+        self.templates.len()
+    }
+
+    /// Returns true if the library has no templates.
+    pub fn is_empty(&self) -> bool {
+        self.templates.is_empty()
+    }
+
+    /// Creates an object instantiated from the template named `name` at the given position, with
+    /// the next free object ID of `map`. See [`Template::instantiate()`].
+    pub fn spawn(&self, name: &str, map: &mut Map, x: f32, y: f32) -> Option<ObjectData> {
+        self.get_by_name(name)
+            .map(|template| template.instantiate(map, x, y))
+    }
+}
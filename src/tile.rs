@@ -1,4 +1,4 @@
-use std::{collections::HashMap, path::Path};
+use std::{path::Path, sync::Arc};
 
 use xml::attribute::OwnedAttribute;
 
@@ -7,16 +7,18 @@ use crate::{
     error::Error,
     image::Image,
     layers::ObjectLayerData,
+    parse::ParseContext,
     properties::{parse_properties, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
-    ResourceCache, ResourceReader, Result, Tileset,
+    ResourceCache, ResourceReader, Result, Tileset, UnknownXml,
 };
 
 /// A tile ID, local to a tileset.
 pub type TileId = u32;
 
 /// Raw data belonging to a tile.
-#[derive(Debug, PartialEq, Clone, Default)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct TileData {
     /// The image of the tile. Only set when the tile is part of an "image collection" tileset.
     pub image: Option<Image>,
@@ -27,9 +29,18 @@ pub struct TileData {
     /// The animation frames of this tile.
     pub animation: Option<Vec<Frame>>,
     /// The type of this tile.
-    pub user_type: Option<String>,
-    /// The probability of this tile.
+    ///
+    /// Like property names, types are shared between all the tiles loaded by the same
+    /// [`Loader`](crate::Loader) that have the same type.
+    pub user_type: Option<Arc<str>>,
+    /// The probability of this tile being picked when painting randomly, relative to the other
+    /// tiles. Defaults to 1 when loading. See [`Tileset::random_tile_with_class()`].
+    ///
+    /// [`Tileset::random_tile_with_class()`]: crate::Tileset::random_tile_with_class
     pub probability: f32,
+    /// The parts of the tile's XML this crate does not model, if loaded with
+    /// [`LoaderOptions::preserve_unknown`](crate::LoaderOptions::preserve_unknown) set.
+    pub unknown_xml: UnknownXml,
 }
 
 /// Points to a tile belonging to a tileset.
@@ -59,6 +70,21 @@ impl<'tileset> std::ops::Deref for Tile<'tileset> {
     }
 }
 
+impl Default for TileData {
+    /// The data of a tile that has nothing but the default probability of 1.
+    fn default() -> Self {
+        Self {
+            image: None,
+            properties: Properties::default(),
+            collision: None,
+            animation: None,
+            user_type: None,
+            probability: 1.,
+            unknown_xml: UnknownXml::default(),
+        }
+    }
+}
+
 impl TileData {
     pub(crate) fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
@@ -66,6 +92,7 @@ impl TileData {
         path_relative_to: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<(TileId, TileData)> {
         let ((user_type, user_class, probability), id) = get_attrs!(
             for v in attrs {
@@ -76,24 +103,30 @@ impl TileData {
             }
             ((user_type, user_class, probability), id)
         );
-        let user_type = user_type.or(user_class);
+        let user_type = ctx
+            .class_attribute("tile", user_type, user_class)
+            .map(|user_type| ctx.intern(&user_type));
         let mut image = Option::None;
-        let mut properties = HashMap::new();
+        let mut properties = Properties::new();
         let mut objectgroup = None;
         let mut animation = None;
-        parse_tag!(parser, "tile", {
+        let depth = ctx.depth();
+        parse_tag!(parser, ctx, "tile", {
             "image" => |attrs| {
-                image = Some(Image::new(parser, attrs, path_relative_to)?);
+                let tile_image = Image::new(parser, attrs, path_relative_to, ctx)?;
+                if !ctx.skip_images() {
+                    image = Some(tile_image);
+                }
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
             "objectgroup" => |attrs| {
                 // Tile objects are not allowed within tile object groups, so we can pass None as the
                 // tilesets vector
-                objectgroup = Some(ObjectLayerData::new(parser, attrs, None, None, path_relative_to, reader, cache)?.0);
+                objectgroup = Some(ObjectLayerData::new(parser, attrs, None, None, path_relative_to, reader, cache, ctx)?.0);
                 Ok(())
             },
             "animation" => |_| {
@@ -101,6 +134,12 @@ impl TileData {
                 Ok(())
             },
         });
+        let unknown_xml = ctx.take_unknown_xml(
+            "tile",
+            &attrs,
+            &["type", "class", "probability", "id"],
+            depth,
+        )?;
         Ok((
             id,
             TileData {
@@ -110,6 +149,7 @@ impl TileData {
                 animation,
                 user_type,
                 probability: probability.unwrap_or(1.0),
+                unknown_xml,
             },
         ))
     }
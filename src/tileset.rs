@@ -7,8 +7,15 @@ use crate::error::{Error, Result};
 use crate::image::Image;
 use crate::properties::{parse_properties, Properties};
 use crate::tile::TileData;
-use crate::{util::*, Gid, InvalidTilesetError, ResourceCache, ResourceReader, Tile, TileId};
-
+use crate::{
+    parse::ParseContext, util::*, Gid, InvalidTilesetError, ParseWarning, ResourceCache,
+    ResourceReader, Tile, TileId, UnknownXml, Version,
+};
+
+mod atlas;
+pub use atlas::*;
+mod builder;
+pub use builder::*;
 mod wangset;
 pub use wangset::*;
 
@@ -16,6 +23,7 @@ pub use wangset::*;
 ///
 /// Also see the [TMX docs](https://doc.mapeditor.org/en/stable/reference/tmx-map-format/#tileset).
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tileset {
     /// The path first used in a [`ResourceReader`] to load this tileset.
     ///
@@ -50,6 +58,11 @@ pub struct Tileset {
     pub offset_x: i32,
     /// The y-offset to be used when drawing tiles of this tileset.
     pub offset_y: i32,
+    /// The size tiles of this tileset are drawn at in tile layers. See [`Tileset::draw_rect()`].
+    pub tile_render_size: TileRenderSize,
+    /// How tiles are scaled when drawn at the size of the map grid. See
+    /// [`Tileset::draw_rect()`].
+    pub fill_mode: FillMode,
 
     /// A tileset can either:
     /// * have a single spritesheet `image` in `tileset` ("regular" tileset);
@@ -61,7 +74,7 @@ pub struct Tileset {
     pub image: Option<Image>,
 
     /// All the tiles present in this tileset, indexed by their local IDs.
-    tiles: HashMap<TileId, TileData>,
+    pub(crate) tiles: HashMap<TileId, TileData>,
 
     /// All the wangsets present in this tileset.
     pub wang_sets: Vec<WangSet>,
@@ -71,6 +84,61 @@ pub struct Tileset {
 
     /// The custom tileset type, arbitrarily set by the user.
     pub user_type: Option<String>,
+
+    pub(crate) warnings: Vec<ParseWarning>,
+
+    pub(crate) version: Option<Version>,
+    pub(crate) tiled_version: Option<Version>,
+
+    pub(crate) unknown_xml: UnknownXml,
+
+    /// Whether this is a placeholder for an external tileset that hasn't been loaded yet.
+    #[cfg_attr(feature = "snapshot", serde(default))]
+    pub(crate) pending: bool,
+}
+
+/// The attributes of `<tileset>` that are loaded into a [`Tileset`], or into the map or template
+/// containing it.
+const TILESET_ATTRIBUTES: &[&str] = &[
+    "spacing",
+    "margin",
+    "columns",
+    "name",
+    "type",
+    "class",
+    "tilecount",
+    "firstgid",
+    "tilewidth",
+    "tileheight",
+    "version",
+    "tiledversion",
+    "tilerendersize",
+    "fillmode",
+];
+
+/// The size the tiles of a [`Tileset`] are drawn at in tile layers, added in Tiled 1.9.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub enum TileRenderSize {
+    /// Tiles are drawn at their own size.
+    #[default]
+    Tile,
+    /// Tiles are drawn at the size of the cells of the map grid, scaled according to the
+    /// [`FillMode`] of the tileset.
+    Grid,
+}
+
+/// How the tiles of a [`Tileset`] drawn at the size of the map grid are scaled, added in
+/// Tiled 1.9.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillMode {
+    /// Tiles are stretched to fill their cell.
+    #[default]
+    Stretch,
+    /// Tiles are scaled as much as they can without changing their aspect ratio nor leaving their
+    /// cell, and centered within it.
+    PreserveAspectFit,
 }
 
 pub(crate) enum EmbeddedParseResultType {
@@ -93,11 +161,50 @@ struct TilesetProperties {
     user_type: Option<String>,
     tile_width: u32,
     tile_height: u32,
+    version: Option<Version>,
+    tiled_version: Option<Version>,
     /// The root all non-absolute paths contained within the tileset are relative to.
     root_path: PathBuf,
 }
 
 impl Tileset {
+    /// The problems found while loading this tileset in
+    /// [lenient mode](crate::LoaderOptions::lenient). Always empty otherwise.
+    #[inline]
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// The TSX format version this tileset was saved to, if its `version` attribute is present and
+    /// a valid [`Version`]. Tiled only stores it in tilesets saved to their own file.
+    #[inline]
+    pub fn format_version(&self) -> Option<Version> {
+        self.version
+    }
+
+    /// The version of Tiled that saved this tileset, if its `tiledversion` attribute is present
+    /// and a valid [`Version`]. Tiled only stores it in tilesets saved to their own file.
+    #[inline]
+    pub fn tiled_version(&self) -> Option<Version> {
+        self.tiled_version
+    }
+
+    /// The parts of the tileset's XML this crate does not model, if loaded with
+    /// [`LoaderOptions::preserve_unknown`](crate::LoaderOptions::preserve_unknown) set.
+    #[inline]
+    pub fn unknown_xml(&self) -> &UnknownXml {
+        &self.unknown_xml
+    }
+
+    /// Whether this tileset is a placeholder for an external tileset whose loading was deferred
+    /// with [`LoaderOptions::defer_tilesets`](crate::LoaderOptions::defer_tilesets), until
+    /// [`Map::resolve_tilesets()`](crate::Map::resolve_tilesets) loads it. Placeholders have no
+    /// tiles and their [`source`](Self::source) is the path of the tileset.
+    #[inline]
+    pub fn is_pending(&self) -> bool {
+        self.pending
+    }
+
     /// Gets the tile with the specified ID from the tileset.
     #[inline]
     pub fn get_tile(&self, id: TileId) -> Option<Tile> {
@@ -111,19 +218,239 @@ impl Tileset {
             .iter()
             .map(move |(id, data)| (*id, Tile::new(self, data)))
     }
+
+    /// Picks a random tile among those whose [type](TileData::user_type), called class since
+    /// Tiled 1.9, is `class`, weighted by their [probability](TileData::probability), like the
+    /// random mode of the stamp brush of Tiled does. Returns [`None`] if no tile with that class
+    /// has a positive probability.
+    ///
+    /// `random` must return numbers uniformly distributed between 0 (inclusive) and 1
+    /// (exclusive), such as `rand::random::<f32>`, which lets any random number generator be
+    /// used. Tiles are considered in order of ID, so that seeded generators always pick the same
+    /// tiles.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::{Image, TilesetBuilder};
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let image = Image {
+    ///     source: "decoration.png".into(),
+    ///     width: 64,
+    ///     height: 32,
+    ///     transparent_colour: None,
+    /// };
+    /// let tileset = TilesetBuilder::new("decoration", image, 32, 32)
+    ///     .tile_user_type(0, "flower")
+    ///     .tile_user_type(1, "flower")
+    ///     .tile_probability(1, 3.)
+    ///     .build()?;
+    ///
+    /// // Tile 1 covers the last three quarters of the range
+    /// let (id, _) = tileset.random_tile_with_class(|| 0.5, "flower").unwrap();
+    /// assert_eq!(id, 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn random_tile_with_class(
+        &self,
+        mut random: impl FnMut() -> f32,
+        class: &str,
+    ) -> Option<(TileId, Tile<'_>)> {
+        let mut candidates: Vec<(TileId, &TileData)> = self
+            .tiles
+            .iter()
+            .filter(|(_, data)| data.user_type.as_deref() == Some(class) && data.probability > 0.)
+            .map(|(id, data)| (*id, data))
+            .collect();
+        candidates.sort_unstable_by_key(|(id, _)| *id);
+
+        let total: f32 = candidates.iter().map(|(_, data)| data.probability).sum();
+        let mut target = random() * total;
+        let last = candidates.last().copied()?;
+        let (id, data) = candidates
+            .into_iter()
+            .find(|(_, data)| {
+                target -= data.probability;
+                target < 0.
+            })
+            // Rounding errors may leave a sliver past the last tile
+            .unwrap_or(last);
+        Some((id, Tile::new(self, data)))
+    }
+
+    /// Returns the rectangle (in pixels) that the tile with the specified ID occupies within the
+    /// image it is drawn from, or [`None`] if the tileset has no such tile.
+    ///
+    /// For regular tilesets, this is the tile's cell within [the tileset image](Self::image),
+    /// taking [margin](Self::margin), [spacing](Self::spacing) and [columns](Self::columns) into
+    /// account. For image collection tilesets, the rectangle covers the whole image of the tile.
+    ///
+    /// ## Note
+    /// The [tile offset](Self::offset_x) is not part of the source rectangle, since it does not
+    /// change which pixels are sampled. Add [`Tileset::tile_draw_offset()`] to the position the
+    /// tile is drawn at instead, or use [`Tileset::draw_rect()`], which already does.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, TileRect};
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    ///
+    /// // The tileset uses 32x32 tiles with no margin nor spacing and 14 columns.
+    /// assert_eq!(
+    ///     tileset.tile_rect(15),
+    ///     Some(TileRect { x: 32, y: 32, width: 32, height: 32 })
+    /// );
+    /// assert_eq!(tileset.tile_rect(tileset.tilecount), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_rect(&self, id: TileId) -> Option<TileRect> {
+        if self.image.is_none() {
+            let image = self.tiles.get(&id)?.image.as_ref()?;
+            return Some(TileRect {
+                x: 0,
+                y: 0,
+                width: image.width as u32,
+                height: image.height as u32,
+            });
+        }
+
+        if id >= self.tilecount || self.columns == 0 {
+            return None;
+        }
+
+        let column = id % self.columns;
+        let row = id / self.columns;
+        Some(TileRect {
+            x: self.margin + column * (self.tile_width + self.spacing),
+            y: self.margin + row * (self.tile_height + self.spacing),
+            width: self.tile_width,
+            height: self.tile_height,
+        })
+    }
+
+    /// Returns the offset in pixels, as `(x, y)`, to add to the position tiles of this tileset are
+    /// drawn at, which isn't included in their [source rectangle](Self::tile_rect).
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tiled_tile_render_size.tsx")?;
+    /// assert_eq!(tileset.tile_draw_offset(), (1, 2));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn tile_draw_offset(&self) -> (i32, i32) {
+        (self.offset_x, self.offset_y)
+    }
+
+    /// Returns the rectangle, as `(x, y, width, height)` in pixels, that the tile with the
+    /// specified ID is drawn in when placed in the map grid cell `cell`, given in the same way, or
+    /// [`None`] if the tileset has no such tile.
+    ///
+    /// Like in Tiled, tiles drawn at their own [size](TileRenderSize::Tile) are aligned with the
+    /// bottom-left corner of their cell, while tiles drawn at the [size of the
+    /// grid](TileRenderSize::Grid) are scaled according to the [fill mode](Self::fill_mode). The
+    /// [tile offset](Self::offset_x) is then applied.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::Loader;
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    ///
+    /// // A 32x32 tile in a 16x16 cell overflows it upwards
+    /// assert_eq!(
+    ///     tileset.draw_rect(0, (64., 64., 16., 16.)),
+    ///     Some((64., 48., 32., 32.))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn draw_rect(
+        &self,
+        id: TileId,
+        cell: (f32, f32, f32, f32),
+    ) -> Option<(f32, f32, f32, f32)> {
+        let rect = self.tile_rect(id)?;
+        let (x, y, width, height) = cell;
+        let (tile_width, tile_height) = (rect.width as f32, rect.height as f32);
+        let (x, y, width, height) = match (self.tile_render_size, self.fill_mode) {
+            (TileRenderSize::Tile, _) => (x, y + height - tile_height, tile_width, tile_height),
+            (TileRenderSize::Grid, FillMode::Stretch) => (x, y, width, height),
+            (TileRenderSize::Grid, FillMode::PreserveAspectFit) => {
+                let scale = (width / tile_width).min(height / tile_height);
+                let (scaled_width, scaled_height) = (tile_width * scale, tile_height * scale);
+                (
+                    x + (width - scaled_width) / 2.,
+                    y + (height - scaled_height) / 2.,
+                    scaled_width,
+                    scaled_height,
+                )
+            }
+        };
+        let (offset_x, offset_y) = self.tile_draw_offset();
+        Some((x + offset_x as f32, y + offset_y as f32, width, height))
+    }
+}
+
+/// A rectangle in pixels within an image, as returned by [`Tileset::tile_rect()`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TileRect {
+    /// The X coordinate of the rectangle's top-left corner.
+    pub x: u32,
+    /// The Y coordinate of the rectangle's top-left corner.
+    pub y: u32,
+    /// The width of the rectangle.
+    pub width: u32,
+    /// The height of the rectangle.
+    pub height: u32,
 }
 
 impl Tileset {
+    /// Creates the placeholder of the external tileset at `source`, which is loaded later.
+    pub(crate) fn pending(source: PathBuf) -> Self {
+        Self {
+            source,
+            name: String::new(),
+            tile_width: 0,
+            tile_height: 0,
+            spacing: 0,
+            margin: 0,
+            tilecount: 0,
+            columns: 0,
+            offset_x: 0,
+            offset_y: 0,
+            tile_render_size: TileRenderSize::default(),
+            fill_mode: FillMode::default(),
+            image: None,
+            tiles: HashMap::new(),
+            wang_sets: Vec::new(),
+            properties: Properties::new(),
+            user_type: None,
+            warnings: Vec::new(),
+            version: None,
+            tiled_version: None,
+            unknown_xml: UnknownXml::default(),
+            pending: true,
+        }
+    }
+
     pub(crate) fn parse_xml_in_map(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: &[OwnedAttribute],
         path: &Path, // Template or Map file
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<EmbeddedParseResult> {
-        Tileset::parse_xml_embedded(parser, attrs, path, reader, cache).or_else(|err| {
+        Tileset::parse_xml_embedded(parser, attrs, path, reader, cache, ctx).or_else(|err| {
             if matches!(err, Error::MalformedAttributes(_)) {
-                Tileset::parse_xml_reference(attrs, path)
+                Tileset::parse_xml_reference(attrs, path, ctx)
             } else {
                 Err(err)
             }
@@ -136,12 +463,16 @@ impl Tileset {
         path: &Path, // Template or Map file
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<EmbeddedParseResult> {
         let (
             (spacing, margin, columns, name, user_type, user_class),
             (tilecount, first_gid, tile_width, tile_height),
+            (version, tiled_version),
         ) = get_attrs!(
            for v in attrs {
+            Some("version") => version = v.parse::<Version>().ok(),
+            Some("tiledversion") => tiled_version = v.parse::<Version>().ok(),
             Some("spacing") => spacing ?= v.parse(),
             Some("margin") => margin ?= v.parse(),
             Some("columns") => columns ?= v.parse(),
@@ -154,27 +485,32 @@ impl Tileset {
             "tilewidth" => tile_width ?= v.parse::<u32>(),
             "tileheight" => tile_height ?= v.parse::<u32>(),
            }
-           ((spacing, margin, columns, name, user_type, user_class), (tilecount, first_gid, tile_width, tile_height))
+           ((spacing, margin, columns, name, user_type, user_class), (tilecount, first_gid, tile_width, tile_height), (version, tiled_version))
         );
 
+        let user_type = ctx.class_attribute("tileset", user_type, user_class);
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
 
         Self::finish_parsing_xml(
             parser,
+            attrs,
             path.to_owned(),
             TilesetProperties {
                 spacing,
                 margin,
                 name: name.unwrap_or_default(),
-                user_type: user_type.or(user_class),
+                user_type,
                 root_path,
                 columns,
                 tilecount,
                 tile_height,
                 tile_width,
+                version: version.flatten(),
+                tiled_version: tiled_version.flatten(),
             },
             reader,
             cache,
+            ctx,
         )
         .map(|tileset| EmbeddedParseResult {
             first_gid,
@@ -185,6 +521,7 @@ impl Tileset {
     fn parse_xml_reference(
         attrs: &[OwnedAttribute],
         map_path: &Path,
+        ctx: &ParseContext,
     ) -> Result<EmbeddedParseResult> {
         let (first_gid, source) = get_attrs!(
             for v in attrs {
@@ -194,7 +531,8 @@ impl Tileset {
             (first_gid, source)
         );
 
-        let tileset_path = map_path.parent().ok_or(Error::PathIsNotFile)?.join(source);
+        let tileset_path =
+            ctx.resolve_path(map_path.parent().ok_or(Error::PathIsNotFile)?, &source)?;
 
         Ok(EmbeddedParseResult {
             first_gid,
@@ -208,12 +546,16 @@ impl Tileset {
         path: &Path,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<Tileset> {
         let (
             (spacing, margin, columns, name, user_type, user_class),
             (tilecount, tile_width, tile_height),
+            (version, tiled_version),
         ) = get_attrs!(
             for v in attrs {
+                Some("version") => version = v.parse::<Version>().ok(),
+                Some("tiledversion") => tiled_version = v.parse::<Version>().ok(),
                 Some("spacing") => spacing ?= v.parse(),
                 Some("margin") => margin ?= v.parse(),
                 Some("columns") => columns ?= v.parse(),
@@ -225,46 +567,77 @@ impl Tileset {
                 "tilewidth" => tile_width ?= v.parse::<u32>(),
                 "tileheight" => tile_height ?= v.parse::<u32>(),
             }
-            ((spacing, margin, columns, name, user_type, user_class), (tilecount, tile_width, tile_height))
+            ((spacing, margin, columns, name, user_type, user_class), (tilecount, tile_width, tile_height), (version, tiled_version))
         );
 
+        let user_type = ctx.class_attribute("tileset", user_type, user_class);
         let root_path = path.parent().ok_or(Error::PathIsNotFile)?.to_owned();
 
         Self::finish_parsing_xml(
             parser,
+            attrs,
             path.to_owned(),
             TilesetProperties {
                 spacing,
                 margin,
                 name: name.unwrap_or_default(),
-                user_type: user_type.or(user_class),
+                user_type,
                 root_path,
                 columns,
                 tilecount,
                 tile_height,
                 tile_width,
+                version: version.flatten(),
+                tiled_version: tiled_version.flatten(),
             },
             reader,
             cache,
+            ctx,
         )
     }
 
     fn finish_parsing_xml(
         parser: &mut impl Iterator<Item = XmlEventResult>,
+        attrs: &[OwnedAttribute],
         container_path: PathBuf,
         prop: TilesetProperties,
         reader: &mut impl ResourceReader,
         cache: &mut impl ResourceCache,
+        ctx: &mut ParseContext,
     ) -> Result<Tileset> {
         let mut image = Option::None;
         let mut tiles = HashMap::with_capacity(prop.tilecount as usize);
-        let mut properties = HashMap::new();
+        let mut properties = Properties::new();
         let mut wang_sets = Vec::new();
         let mut offset = (0i32, 0i32);
 
-        parse_tag!(parser, "tileset", {
+        let (tile_render_size, fill_mode) = get_attrs!(
+            for v in attrs {
+                Some("tilerendersize") => tile_render_size = match v.as_str() {
+                    "tile" => TileRenderSize::Tile,
+                    "grid" => TileRenderSize::Grid,
+                    _ => return Err(Error::MalformedAttributes(
+                        "`tilerendersize` attribute did not contain a valid value of 'tile' or 'grid'"
+                            .to_string(),
+                    )),
+                },
+                Some("fillmode") => fill_mode = match v.as_str() {
+                    "stretch" => FillMode::Stretch,
+                    "preserve-aspect-fit" => FillMode::PreserveAspectFit,
+                    _ => return Err(Error::MalformedAttributes(
+                        "`fillmode` attribute did not contain a valid value of 'stretch' or 'preserve-aspect-fit'"
+                            .to_string(),
+                    )),
+                },
+            }
+            (tile_render_size, fill_mode)
+        );
+
+        let first_warning = ctx.warning_count();
+        let depth = ctx.depth();
+        parse_tag!(parser, ctx, "tileset", {
             "image" => |attrs| {
-                image = Some(Image::new(parser, attrs, &prop.root_path)?);
+                image = Some(Image::new(parser, attrs, &prop.root_path, ctx)?);
                 Ok(())
             },
             "tileoffset" => |attrs| {
@@ -272,20 +645,21 @@ impl Tileset {
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
             "tile" => |attrs| {
-                let (id, tile) = TileData::new(parser, attrs, &prop.root_path, reader, cache)?;
+                let (id, tile) = TileData::new(parser, attrs, &prop.root_path, reader, cache, ctx)?;
                 tiles.insert(id, tile);
                 Ok(())
             },
             "wangset" => |attrs| {
-                let set = WangSet::new(parser, attrs)?;
+                let set = WangSet::parse(parser, attrs, ctx)?;
                 wang_sets.push(set);
                 Ok(())
             },
         });
+        let unknown_xml = ctx.take_unknown_xml("tileset", attrs, TILESET_ATTRIBUTES, depth)?;
 
         // A tileset is considered an image collection tileset if there is no image attribute (because its tiles do).
         let is_image_collection_tileset = image.is_none();
@@ -308,8 +682,11 @@ impl Tileset {
             .columns
             .map(Ok)
             .unwrap_or_else(|| Self::calculate_columns(&image, prop.tile_width, margin, spacing))?;
+        if ctx.skip_images() {
+            image = None;
+        }
 
-        Ok(Tileset {
+        let tileset = Tileset {
             source: container_path,
             name: prop.name,
             user_type: prop.user_type,
@@ -320,12 +697,21 @@ impl Tileset {
             columns,
             offset_x: offset.0,
             offset_y: offset.1,
+            tile_render_size: tile_render_size.unwrap_or_default(),
+            fill_mode: fill_mode.unwrap_or_default(),
             tilecount: prop.tilecount,
             image,
             tiles,
             wang_sets,
             properties,
-        })
+            warnings: ctx.warnings_since(first_warning),
+            version: prop.version,
+            tiled_version: prop.tiled_version,
+            unknown_xml,
+            pending: false,
+        };
+        ctx.tileset_parsed(&tileset);
+        Ok(tileset)
     }
 
     fn calculate_columns(
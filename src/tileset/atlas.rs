@@ -0,0 +1,99 @@
+use crate::{TileId, TileRect, Tileset};
+
+/// Normalized texture coordinates of a tile within its tileset texture, as returned by
+/// [`Tileset::uv_table()`].
+///
+/// `(0, 0)` is the top-left corner of the texture, and `(1, 1)` is the bottom-right one.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct TileUv {
+    /// The horizontal coordinate of the left edge of the tile.
+    pub u_min: f32,
+    /// The vertical coordinate of the top edge of the tile.
+    pub v_min: f32,
+    /// The horizontal coordinate of the right edge of the tile.
+    pub u_max: f32,
+    /// The vertical coordinate of the bottom edge of the tile.
+    pub v_max: f32,
+}
+
+/// Adjustments applied when computing a [`Tileset::uv_table()`], used to avoid neighbouring tiles
+/// bleeding into each other when sampling the texture on the GPU.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum UvPadding {
+    /// Use the exact edges of each tile.
+    #[default]
+    None,
+    /// Move each edge half a texel towards the center of the tile, so that samples never fall on
+    /// the texels of a neighbouring tile.
+    HalfTexel,
+    /// The texture has been regenerated with each tile's border pixels repeated outwards by the
+    /// given amount of pixels, which means tiles are spaced further apart than what the tileset
+    /// describes. The texture size given must be the one of the extruded texture.
+    Extruded(u32),
+}
+
+impl Tileset {
+    /// Computes the normalized texture coordinates of every tile in this tileset, indexed by tile
+    /// ID, given the size in pixels of the texture the tileset image was uploaded to.
+    ///
+    /// Returns an empty table for image collection tilesets, since their tiles do not share a
+    /// texture.
+    ///
+    /// ## Example
+    /// ```
+    /// # use tiled::{Loader, UvPadding};
+    /// # fn main() -> tiled::Result<()> {
+    /// let tileset = Loader::new().load_tsx_tileset("assets/tilesheet.tsx")?;
+    /// let image = tileset.image.as_ref().unwrap();
+    ///
+    /// let uvs = tileset.uv_table(image.width as u32, image.height as u32, UvPadding::HalfTexel);
+    /// assert_eq!(uvs.len(), tileset.tilecount as usize);
+    /// assert_eq!(uvs[0].u_min, 0.5 / image.width as f32);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn uv_table(
+        &self,
+        texture_width: u32,
+        texture_height: u32,
+        padding: UvPadding,
+    ) -> Vec<TileUv> {
+        if self.image.is_none() || texture_width == 0 || texture_height == 0 {
+            return Vec::new();
+        }
+
+        (0..self.tilecount)
+            .filter_map(|id| self.padded_tile_rect(id, padding))
+            .map(|rect| {
+                let inset = match padding {
+                    UvPadding::HalfTexel => 0.5,
+                    _ => 0.,
+                };
+                TileUv {
+                    u_min: (rect.x as f32 + inset) / texture_width as f32,
+                    v_min: (rect.y as f32 + inset) / texture_height as f32,
+                    u_max: ((rect.x + rect.width) as f32 - inset) / texture_width as f32,
+                    v_max: ((rect.y + rect.height) as f32 - inset) / texture_height as f32,
+                }
+            })
+            .collect()
+    }
+
+    fn padded_tile_rect(&self, id: TileId, padding: UvPadding) -> Option<TileRect> {
+        let rect = self.tile_rect(id)?;
+        match padding {
+            UvPadding::Extruded(extrusion) => {
+                // Each tile gains `extrusion` pixels on every side, which pushes every following
+                // column and row further away.
+                let column = id % self.columns;
+                let row = id / self.columns;
+                Some(TileRect {
+                    x: rect.x + extrusion * (2 * column + 1),
+                    y: rect.y + extrusion * (2 * row + 1),
+                    ..rect
+                })
+            }
+            _ => Some(rect),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use crate::{
+    DrawOrder, Error, FillMode, Frame, Image, InvalidTilesetError, ObjectData, ObjectLayerData,
+    ObjectShape, Properties, PropertyValue, Result, TileData, TileId, TileRenderSize, Tileset,
+    UnknownXml,
+};
+
+/// A builder used to create [`Tileset`]s from code rather than loading them from a file, e.g. for
+/// procedurally generated tilesheets.
+///
+/// The resulting tileset is a regular one, i.e. it uses a single image that is split into tiles
+/// using the given tile size, margin and spacing.
+///
+/// ## Example
+/// ```
+/// use std::{path::PathBuf, sync::Arc};
+/// use tiled::{Frame, Image, Loader, ObjectShape, ResourceCache, TilesetBuilder};
+///
+/// # fn main() -> tiled::Result<()> {
+/// let image = Image {
+///     source: PathBuf::from("generated/terrain.png"),
+///     width: 64,
+///     height: 32,
+///     transparent_colour: None,
+/// };
+///
+/// let tileset = TilesetBuilder::new("terrain", image, 32, 32)
+///     .source("generated/terrain.tsx")
+///     .tile_animation(0, vec![Frame { tile_id: 0, duration: 100 }, Frame { tile_id: 1, duration: 100 }])
+///     .tile_collision(1, 0., 0., ObjectShape::Rect { width: 32., height: 16. })
+///     .build()?;
+///
+/// assert_eq!(tileset.tilecount, 2);
+/// assert!(tileset.get_tile(1).unwrap().collision.is_some());
+///
+/// // Maps loaded afterwards that reference "generated/terrain.tsx" will use this tileset.
+/// let mut loader = Loader::new();
+/// loader.cache_mut().insert_tileset("generated/terrain.tsx", Arc::new(tileset));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TilesetBuilder {
+    source: PathBuf,
+    name: String,
+    user_type: Option<String>,
+    image: Image,
+    tile_width: u32,
+    tile_height: u32,
+    margin: u32,
+    spacing: u32,
+    offset: (i32, i32),
+    tile_render_size: TileRenderSize,
+    fill_mode: FillMode,
+    properties: Properties,
+    tiles: HashMap<TileId, TileData>,
+}
+
+impl TilesetBuilder {
+    /// Creates a new builder for a tileset with the given name, which splits `image` into tiles of
+    /// the given size.
+    pub fn new(name: impl Into<String>, image: Image, tile_width: u32, tile_height: u32) -> Self {
+        Self {
+            source: PathBuf::new(),
+            name: name.into(),
+            user_type: None,
+            image,
+            tile_width,
+            tile_height,
+            margin: 0,
+            spacing: 0,
+            offset: (0, 0),
+            tile_render_size: TileRenderSize::Tile,
+            fill_mode: FillMode::Stretch,
+            properties: Properties::new(),
+            tiles: HashMap::new(),
+        }
+    }
+
+    /// Sets the [source](Tileset::source) of the tileset, which is empty by default. Use the same
+    /// path the tileset is inserted into a [`ResourceCache`](crate::ResourceCache) with.
+    pub fn source(mut self, source: impl Into<PathBuf>) -> Self {
+        self.source = source.into();
+        self
+    }
+
+    /// Sets the custom [type](Tileset::user_type) of the tileset.
+    pub fn user_type(mut self, user_type: impl Into<String>) -> Self {
+        self.user_type = Some(user_type.into());
+        self
+    }
+
+    /// Sets the margin around the tiles in the image, in pixels.
+    pub fn margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    /// Sets the spacing between the tiles in the image, in pixels.
+    pub fn spacing(mut self, spacing: u32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Sets the offset in pixels to be used when drawing tiles of this tileset.
+    pub fn offset(mut self, x: i32, y: i32) -> Self {
+        self.offset = (x, y);
+        self
+    }
+
+    /// Sets the [size](Tileset::tile_render_size) tiles are drawn at in tile layers.
+    pub fn tile_render_size(mut self, tile_render_size: TileRenderSize) -> Self {
+        self.tile_render_size = tile_render_size;
+        self
+    }
+
+    /// Sets how tiles are [scaled](Tileset::fill_mode) when drawn at the size of the map grid.
+    pub fn fill_mode(mut self, fill_mode: FillMode) -> Self {
+        self.fill_mode = fill_mode;
+        self
+    }
+
+    /// Sets a custom property of the tileset.
+    pub fn property(mut self, name: impl Into<Arc<str>>, value: PropertyValue) -> Self {
+        self.properties.insert(name.into(), value);
+        self
+    }
+
+    /// Replaces all data belonging to a tile.
+    pub fn tile(mut self, id: TileId, data: TileData) -> Self {
+        self.tiles.insert(id, data);
+        self
+    }
+
+    /// Sets a custom property of a tile.
+    pub fn tile_property(
+        mut self,
+        id: TileId,
+        name: impl Into<Arc<str>>,
+        value: PropertyValue,
+    ) -> Self {
+        self.tile_mut(id).properties.insert(name.into(), value);
+        self
+    }
+
+    /// Sets the custom type of a tile.
+    pub fn tile_user_type(mut self, id: TileId, user_type: impl Into<Arc<str>>) -> Self {
+        self.tile_mut(id).user_type = Some(user_type.into());
+        self
+    }
+
+    /// Sets the probability of a tile, relative to the other tiles, of being picked when painting
+    /// randomly. Tiles have a probability of 1 unless set.
+    pub fn tile_probability(mut self, id: TileId, probability: f32) -> Self {
+        self.tile_mut(id).probability = probability;
+        self
+    }
+
+    /// Sets the animation frames of a tile.
+    pub fn tile_animation(mut self, id: TileId, frames: Vec<Frame>) -> Self {
+        self.tile_mut(id).animation = Some(frames);
+        self
+    }
+
+    /// Adds a collision shape to a tile, positioned in pixels relative to the tile's top-left
+    /// corner.
+    pub fn tile_collision(mut self, id: TileId, x: f32, y: f32, shape: ObjectShape) -> Self {
+        let collision = self
+            .tile_mut(id)
+            .collision
+            .get_or_insert_with(|| ObjectLayerData {
+                objects: Vec::new(),
+                colour: None,
+                draw_order: DrawOrder::Index,
+                name_index: Default::default(),
+            });
+        let object_id = collision.objects.len() as u32 + 1;
+        collision.objects.push(ObjectData {
+            id: object_id,
+            tile: None,
+            name: String::new(),
+            user_type: String::new(),
+            x,
+            y,
+            rotation: 0.,
+            visible: true,
+            shape,
+            properties: Properties::new(),
+            unknown_xml: UnknownXml::default(),
+            template: None,
+        });
+        collision.objects_changed();
+        self
+    }
+
+    fn tile_mut(&mut self, id: TileId) -> &mut TileData {
+        self.tiles.entry(id).or_default()
+    }
+
+    /// Builds the tileset, calculating the amount of columns and tiles from the image size.
+    ///
+    /// Fails with [`InvalidTilesetError::InvalidTileDimensions`] if the tile width or height is 0.
+    pub fn build(self) -> Result<Tileset> {
+        if self.tile_width == 0 || self.tile_height == 0 {
+            return Err(Error::InvalidTileset(
+                InvalidTilesetError::InvalidTileDimensions,
+            ));
+        }
+
+        let fit = |size: i32, tile_size: u32| {
+            (size.max(0) as u32 + self.spacing).saturating_sub(self.margin)
+                / (tile_size + self.spacing)
+        };
+        let columns = fit(self.image.width, self.tile_width);
+        let rows = fit(self.image.height, self.tile_height);
+        let tilecount = columns * rows;
+
+        let mut tiles = self.tiles;
+        for id in 0..tilecount {
+            tiles.entry(id).or_default();
+        }
+
+        Ok(Tileset {
+            source: self.source,
+            name: self.name,
+            tile_width: self.tile_width,
+            tile_height: self.tile_height,
+            spacing: self.spacing,
+            margin: self.margin,
+            tilecount,
+            columns,
+            offset_x: self.offset.0,
+            offset_y: self.offset.1,
+            tile_render_size: self.tile_render_size,
+            fill_mode: self.fill_mode,
+            image: Some(self.image),
+            tiles,
+            wang_sets: Vec::new(),
+            properties: self.properties,
+            user_type: self.user_type,
+            warnings: Vec::new(),
+            version: None,
+            tiled_version: None,
+            unknown_xml: UnknownXml::default(),
+            pending: false,
+        })
+    }
+}
@@ -4,9 +4,10 @@ use xml::attribute::OwnedAttribute;
 
 use crate::{
     error::Error,
+    parse::ParseContext,
     properties::{parse_properties, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
-    Result, TileId,
+    LoaderOptions, Result, TileId,
 };
 
 mod wang_color;
@@ -17,6 +18,7 @@ pub use wang_tile::*;
 /// Wang set's terrain brush connection type.
 #[derive(Debug, PartialEq, Clone, Copy)]
 #[allow(missing_docs)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub enum WangSetType {
     Corner,
     Edge,
@@ -31,6 +33,7 @@ impl Default for WangSetType {
 
 /// Raw data belonging to a WangSet.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangSet {
     /// The name of the Wang set.
     pub name: String,
@@ -51,6 +54,18 @@ impl WangSet {
     pub fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
+    ) -> Result<WangSet> {
+        Self::parse(
+            parser,
+            attrs,
+            &mut ParseContext::new(LoaderOptions::default(), Default::default()),
+        )
+    }
+
+    pub(crate) fn parse(
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+        attrs: Vec<OwnedAttribute>,
+        ctx: &mut ParseContext,
     ) -> Result<WangSet> {
         // Get common data
         let (name, wang_set_type, tile) = get_attrs!(
@@ -72,10 +87,10 @@ impl WangSet {
         // Gather variable data
         let mut wang_colors = Vec::new();
         let mut wang_tiles = HashMap::new();
-        let mut properties = HashMap::new();
-        parse_tag!(parser, "wangset", {
+        let mut properties = Properties::new();
+        parse_tag!(parser, ctx, "wangset", {
             "wangcolor" => |attrs| {
-                let color = WangColor::new(parser, attrs)?;
+                let color = WangColor::parse(parser, attrs, ctx)?;
                 wang_colors.push(color);
                 Ok(())
             },
@@ -85,7 +100,7 @@ impl WangSet {
                 Ok(())
             },
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
         });
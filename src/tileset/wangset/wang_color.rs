@@ -1,16 +1,16 @@
-use std::collections::HashMap;
-
 use xml::attribute::OwnedAttribute;
 
 use crate::{
     error::Error,
+    parse::ParseContext,
     properties::{parse_properties, Color, Properties},
     util::{get_attrs, parse_tag, XmlEventResult},
-    Result, TileId,
+    LoaderOptions, Result, TileId,
 };
 
 /// Stores the data of the Wang color.
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangColor {
     /// The name of this color.
     pub name: String,
@@ -29,6 +29,18 @@ impl WangColor {
     pub fn new(
         parser: &mut impl Iterator<Item = XmlEventResult>,
         attrs: Vec<OwnedAttribute>,
+    ) -> Result<WangColor> {
+        Self::parse(
+            parser,
+            attrs,
+            &mut ParseContext::new(LoaderOptions::default(), Default::default()),
+        )
+    }
+
+    pub(crate) fn parse(
+        parser: &mut impl Iterator<Item = XmlEventResult>,
+        attrs: Vec<OwnedAttribute>,
+        ctx: &mut ParseContext,
     ) -> Result<WangColor> {
         // Get common data
         let (name, color, tile, probability) = get_attrs!(
@@ -44,10 +56,10 @@ impl WangColor {
         let tile = if tile >= 0 { Some(tile as u32) } else { None };
 
         // Gather variable data
-        let mut properties = HashMap::new();
-        parse_tag!(parser, "wangcolor", {
+        let mut properties = Properties::new();
+        parse_tag!(parser, ctx, "wangcolor", {
             "properties" => |_| {
-                properties = parse_properties(parser)?;
+                properties = parse_properties(parser, ctx)?;
                 Ok(())
             },
         });
@@ -10,6 +10,7 @@ use crate::{
 
 /// The Wang ID, stored as an array of 8 u8 values.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangId(pub [u8; 8]);
 
 impl FromStr for WangId {
@@ -37,6 +38,7 @@ impl FromStr for WangId {
 
 /// Stores the Wang ID.
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
 pub struct WangTile {
     #[allow(missing_docs)]
     pub wang_id: WangId,
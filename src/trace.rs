@@ -0,0 +1,94 @@
+//! Instrumentation of loads with [`tracing`](https://docs.rs/tracing) spans, enabled by the
+//! `tracing` feature. Without it, everything in here does nothing.
+
+use std::io::Read;
+
+/// Creates a [`LoadSpan`] and enters it. Takes the name of the span followed by its fields, using
+/// the syntax of [`tracing::span!`](https://docs.rs/tracing/latest/tracing/macro.span.html).
+/// Every span also has a `bytes` field and an `elapsed_us` one, which holds the time spent in the
+/// span in microseconds.
+///
+/// The fields aren't evaluated when the `tracing` feature is disabled.
+#[cfg(feature = "tracing")]
+macro_rules! load_span {
+    ($name:literal $(, $($fields:tt)*)?) => {
+        $crate::trace::LoadSpan::enter(tracing::debug_span!(
+            $name,
+            $($($fields)*,)?
+            bytes = tracing::field::Empty,
+            elapsed_us = tracing::field::Empty
+        ))
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! load_span {
+    ($($tokens:tt)*) => {
+        $crate::trace::LoadSpan
+    };
+}
+
+pub(crate) use load_span;
+
+/// A span entered by [`load_span!`], which records the time spent in it once dropped.
+#[cfg(feature = "tracing")]
+pub(crate) struct LoadSpan {
+    span: tracing::span::EnteredSpan,
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "tracing")]
+impl LoadSpan {
+    pub fn enter(span: tracing::Span) -> Self {
+        Self {
+            span: span.entered(),
+            start: std::time::Instant::now(),
+        }
+    }
+
+    /// Records the value of a field declared when creating the span.
+    pub fn record(&self, field: &str, value: impl tracing::Value) {
+        self.span.record(field, value);
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Drop for LoadSpan {
+    fn drop(&mut self) {
+        self.span
+            .record("elapsed_us", self.start.elapsed().as_micros() as u64);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct LoadSpan;
+
+#[cfg(not(feature = "tracing"))]
+impl LoadSpan {
+    #[inline]
+    pub fn record<T>(&self, _field: &str, _value: T) {}
+}
+
+/// A reader that counts the bytes read from it.
+pub(crate) struct CountingReader<R> {
+    inner: R,
+    count: usize,
+}
+
+impl<R> CountingReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read;
+        Ok(read)
+    }
+}
@@ -179,16 +179,46 @@ pub(crate) use handle_attr_branches;
 
 /// Goes through the children of the tag and will call the correct function for
 /// that child. Closes the tag.
+///
+/// If a [`ParseContext`](crate::parse::ParseContext) is passed after the parser, children that
+/// fail to parse are handed to [`ParseContext::recover`](crate::parse::ParseContext::recover), and
+/// children that aren't handled to
+/// [`ParseContext::unknown_element`](crate::parse::ParseContext::unknown_element).
 macro_rules! parse_tag {
     ($parser:expr, $close_tag:expr, {$($open_tag:expr => $open_method:expr),* $(,)*}) => {
+        $crate::util::parse_tag!(@impl $parser, $close_tag,
+            {$($open_tag => |attributes| $open_method(attributes)?),*},
+            |_, _| {}
+        )
+    };
+
+    ($parser:expr, $ctx:expr, $close_tag:expr, {$($open_tag:expr => $open_method:expr),* $(,)*}) => {
+        $crate::util::parse_tag!(@impl $parser, $close_tag,
+            {$($open_tag => |attributes| {
+                let depth = $ctx.depth();
+                let result = $open_method(attributes);
+                if let Err(err) = result {
+                    $ctx.recover(err, depth, $parser)?;
+                }
+            }),*},
+            |name, attributes| $ctx.unknown_element(name.local_name, &attributes, $close_tag, $parser)?
+        )
+    };
+
+    (@impl $parser:expr, $close_tag:expr,
+        {$($open_tag:expr => |$attributes:ident| $on_open:expr),*},
+        |$unknown_name:pat_param, $unknown_attributes:pat_param| $on_unknown:expr
+    ) => {
         while let Some(next) = $parser.next() {
             match next.map_err(Error::XmlDecodingError)? {
                 #[allow(unused_variables)]
                 $(
-                    xml::reader::XmlEvent::StartElement {name, attributes, ..}
-                        if name.local_name == $open_tag => $open_method(attributes)?,
+                    xml::reader::XmlEvent::StartElement {name, attributes: $attributes, ..}
+                        if name.local_name == $open_tag => $on_open,
                 )*
 
+                #[allow(unused_variables)]
+                xml::reader::XmlEvent::StartElement {name: $unknown_name, attributes: $unknown_attributes, ..} => $on_unknown,
 
                 xml::reader::XmlEvent::EndElement {name, ..} => if name.local_name == $close_tag {
                     break;
@@ -267,3 +297,26 @@ pub fn floor_div(a: i32, b: i32) -> i32 {
         d - ((a < 0) ^ (b < 0)) as i32
     }
 }
+
+/// Resolves the `.` and `..` components of a path built by joining paths found in files. Leading
+/// `..` components are kept.
+pub(crate) fn normalize_path(path: &std::path::Path) -> std::path::PathBuf {
+    use std::path::{Component, PathBuf};
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    normalized.components().next_back(),
+                    Some(Component::Normal(_))
+                ) =>
+            {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,87 @@
+use std::{fmt, str::FromStr};
+
+/// A version number, such as the one of the TMX format a file was saved in or of the Tiled
+/// release that saved it.
+///
+/// Versions are ordered by their components, so they can be compared directly:
+/// ```
+/// use tiled::Version;
+///
+/// let version: Version = "1.10".parse().unwrap();
+/// assert_eq!(version, Version::new(1, 10, 0));
+/// assert!(version > Version::new(1, 9, 2));
+/// ```
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Default)]
+#[cfg_attr(feature = "snapshot", derive(serde::Serialize, serde::Deserialize))]
+pub struct Version {
+    /// The first component of the version.
+    pub major: u32,
+    /// The second component of the version.
+    pub minor: u32,
+    /// The third component of the version, 0 if not present.
+    pub patch: u32,
+}
+
+impl Version {
+    /// Creates a new version from its components.
+    #[inline]
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+}
+
+impl FromStr for Version {
+    type Err = VersionParseError;
+
+    /// Parses versions made of two or three numbers separated by dots, such as `1.10` or `1.10.2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || VersionParseError {
+            str_found: s.to_owned(),
+        };
+
+        let mut components = s.split('.').map(|component| component.parse::<u32>());
+        let mut next = || {
+            components
+                .next()
+                .map(|component| component.map_err(|_| error()))
+        };
+        let major = next().ok_or_else(error)??;
+        let minor = next().ok_or_else(error)??;
+        let patch = next().transpose()?.unwrap_or(0);
+        if next().is_some() {
+            return Err(error());
+        }
+
+        Ok(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+#[derive(Debug)]
+/// An error arising from trying to parse a [`Version`] that is not valid.
+pub struct VersionParseError {
+    /// The invalid string found.
+    pub str_found: String,
+}
+
+impl fmt::Display for VersionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to parse version, expected numbers separated by dots like `1.10.2` but got `{}` \
+            instead",
+            self.str_found
+        )
+    }
+}
+
+impl std::error::Error for VersionParseError {}
@@ -0,0 +1,320 @@
+//! Hot reloading of maps from the filesystem. Requires the `watch` feature.
+//!
+//! A [`MapWatcher`] loads a map and uses [`notify`] to watch the file it was loaded from as well
+//! as every file it depends on: Its external tilesets, the templates of its objects and the images
+//! of its tilesets and image layers. Whenever some of these change, the map is loaded again,
+//! reading only the files that changed anew, and a [`WatchEvent`] is emitted for each of them.
+//!
+//! ## Example
+//! ```no_run
+//! use tiled::watch::{MapWatcher, WatchEvent};
+//!
+//! # fn main() -> Result<(), tiled::watch::WatchError> {
+//! let mut watcher = MapWatcher::new("assets/tiled_base64_external.tmx")?;
+//! loop {
+//!     for event in watcher.wait() {
+//!         match event {
+//!             WatchEvent::TilesetChanged { index } => {
+//!                 println!("reloaded {}", watcher.map().tilesets()[index].name)
+//!             }
+//!             WatchEvent::ImageChanged(path) => println!("{} needs uploading", path.display()),
+//!             WatchEvent::Error(err) => eprintln!("{}", err),
+//!             _ => {}
+//!         }
+//!     }
+//!     // Draw `watcher.map()`...
+//! }
+//! # }
+//! ```
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+};
+
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::{dependencies::DependencyKind, Error, Loader, Map, ResourceCache};
+
+/// Errors that can occur while watching a map with a [`MapWatcher`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WatchError {
+    /// The map could not be loaded. When reloading, the previous version of the map is kept.
+    Loading(Error),
+    /// The files of the map could not be watched.
+    Watching(notify::Error),
+}
+
+impl fmt::Display for WatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WatchError::Loading(err) => write!(f, "Could not load the watched map: {}", err),
+            WatchError::Watching(err) => write!(f, "Could not watch the files of the map: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for WatchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WatchError::Loading(err) => Some(err),
+            WatchError::Watching(err) => Some(err),
+        }
+    }
+}
+
+/// A change noticed by a [`MapWatcher`]. Paths are the ones the map refers to files with, which
+/// are relative to the working directory if the map's path was.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum WatchEvent {
+    /// The map file itself changed, and the map was loaded again.
+    MapChanged,
+    /// The tileset at this index of [`Map::tilesets()`] changed, and the map was loaded again.
+    TilesetChanged {
+        /// The index of the tileset in the map that was loaded again.
+        index: usize,
+    },
+    /// A template used by objects of the map changed, and the map was loaded again.
+    TemplateChanged(PathBuf),
+    /// An image used by a tileset or an image layer of the map changed. Images aren't loaded by
+    /// this crate, so the map stays the same; the image needs to be loaded again by whatever uses
+    /// it.
+    ImageChanged(PathBuf),
+    /// The map could not be loaded again, or its files could not be watched anymore.
+    Error(WatchError),
+}
+
+/// Loads a map and loads it again whenever it or a file it depends on changes. See the
+/// [module documentation](self) for more information.
+#[derive(Debug)]
+pub struct MapWatcher {
+    loader: Loader,
+    path: PathBuf,
+    map: Map,
+    /// The files the map depends on along with their original path, by their path as returned by
+    /// `normalize`.
+    dependencies: HashMap<PathBuf, (PathBuf, DependencyKind)>,
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    /// The directories containing the map and its dependencies. Directories are watched instead
+    /// of files because many editors save files by replacing them.
+    watched: HashSet<PathBuf>,
+    /// The directories that should be watched but couldn't be, e.g. because they don't exist yet.
+    /// Watching them is tried again whenever changes are handled.
+    unwatched: HashSet<PathBuf>,
+}
+
+impl MapWatcher {
+    /// Loads the map at `path` with a new [`Loader`] and starts watching it.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, WatchError> {
+        Self::with_loader(Loader::new(), path)
+    }
+
+    /// Loads the map at `path` with the loader given, which is used to load it again afterwards,
+    /// and starts watching it.
+    pub fn with_loader(mut loader: Loader, path: impl AsRef<Path>) -> Result<Self, WatchError> {
+        let path = path.as_ref().to_owned();
+        let (map, dependencies) = loader
+            .load_tmx_map_with_dependencies(&path)
+            .map_err(WatchError::Loading)?;
+
+        let (sender, events) = channel();
+        let watcher = notify::recommended_watcher(sender).map_err(WatchError::Watching)?;
+        let mut map_watcher = Self {
+            loader,
+            path,
+            map,
+            dependencies: HashMap::new(),
+            watcher,
+            events,
+            watched: HashSet::new(),
+            unwatched: HashSet::new(),
+        };
+        map_watcher.set_dependencies(dependencies.iter())?;
+        Ok(map_watcher)
+    }
+
+    /// The current version of the map.
+    #[inline]
+    pub fn map(&self) -> &Map {
+        &self.map
+    }
+
+    /// The path of the map being watched.
+    #[inline]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the loader used to load the map.
+    #[inline]
+    pub fn loader(&self) -> &Loader {
+        &self.loader
+    }
+
+    /// Handles the changes that happened since the last call without waiting for any, loading the
+    /// map again if needed. Returns what changed, which is empty if nothing did.
+    pub fn poll(&mut self) -> Vec<WatchEvent> {
+        let events: Vec<_> = self.events.try_iter().collect();
+        self.handle(events)
+    }
+
+    /// Waits until the map or one of its dependencies changes, then handles the change like
+    /// [`MapWatcher::poll()`] does.
+    pub fn wait(&mut self) -> Vec<WatchEvent> {
+        loop {
+            let first = match self.events.recv() {
+                Ok(event) => event,
+                // The watcher holds the sender, so this shouldn't happen
+                Err(_) => return Vec::new(),
+            };
+            let events = std::iter::once(first)
+                .chain(self.events.try_iter())
+                .collect();
+            let changes = self.handle(events);
+            if !changes.is_empty() {
+                return changes;
+            }
+        }
+    }
+
+    fn handle(&mut self, events: Vec<notify::Result<notify::Event>>) -> Vec<WatchEvent> {
+        // Failures were reported when the directories were first watched
+        let _ = self.watch_pending();
+
+        let mut changes = Vec::new();
+        let mut changed = HashSet::new();
+        for event in events {
+            match event {
+                Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                    changed.extend(event.paths.iter().map(|path| normalize(path)));
+                }
+                Ok(_) => {}
+                Err(err) => changes.push(WatchEvent::Error(WatchError::Watching(err))),
+            }
+        }
+
+        let map_changed = changed.contains(&normalize(&self.path));
+        let mut changed_dependencies: Vec<_> = changed
+            .iter()
+            .filter_map(|path| self.dependencies.get(path))
+            .cloned()
+            .collect();
+        changed_dependencies.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut reload = map_changed;
+        let mut changed_tilesets = Vec::new();
+        for (path, kind) in changed_dependencies {
+            match kind {
+                // Templates using a changed tileset are evicted along with it
+                DependencyKind::Tileset => {
+                    self.loader.cache_mut().invalidate(&path);
+                    changed_tilesets.push(path);
+                    reload = true;
+                }
+                DependencyKind::Template => {
+                    self.loader.cache_mut().invalidate(&path);
+                    changes.push(WatchEvent::TemplateChanged(path));
+                    reload = true;
+                }
+                DependencyKind::Image => changes.push(WatchEvent::ImageChanged(path)),
+            }
+        }
+
+        if !reload {
+            return changes;
+        }
+        match self.loader.load_tmx_map_with_dependencies(&self.path) {
+            Ok((map, dependencies)) => {
+                self.map = map;
+                if let Err(err) = self.set_dependencies(dependencies.iter()) {
+                    changes.push(WatchEvent::Error(err));
+                }
+            }
+            Err(err) => {
+                changes.push(WatchEvent::Error(WatchError::Loading(err)));
+                return changes;
+            }
+        }
+
+        if map_changed {
+            changes.insert(0, WatchEvent::MapChanged);
+        }
+        for (index, tileset) in self.map.tilesets().iter().enumerate() {
+            if changed_tilesets.contains(&tileset.source) {
+                changes.push(WatchEvent::TilesetChanged { index });
+            }
+        }
+        changes
+    }
+
+    /// Replaces the dependencies of the map and watches the directories they are in.
+    fn set_dependencies<'a>(
+        &mut self,
+        dependencies: impl Iterator<Item = (&'a Path, DependencyKind)>,
+    ) -> Result<(), WatchError> {
+        self.dependencies = dependencies
+            .map(|(path, kind)| (normalize(path), (path.to_owned(), kind)))
+            .collect();
+
+        let directories: HashSet<PathBuf> = std::iter::once(normalize(&self.path))
+            .chain(self.dependencies.keys().cloned())
+            .filter_map(|path| path.parent().map(Path::to_owned))
+            .collect();
+        for directory in self.watched.difference(&directories) {
+            // The directory may not exist anymore, in which case it isn't watched anyway
+            let _ = self.watcher.unwatch(directory);
+        }
+        self.watched
+            .retain(|directory| directories.contains(directory));
+        self.unwatched = directories
+            .into_iter()
+            .filter(|directory| !self.watched.contains(directory))
+            .collect();
+        self.watch_pending()
+    }
+
+    /// Tries to watch the directories that couldn't be watched yet, returning the last error.
+    fn watch_pending(&mut self) -> Result<(), WatchError> {
+        let mut result = Ok(());
+        let mut newly_watched = false;
+        for directory in std::mem::take(&mut self.unwatched) {
+            match self.watcher.watch(&directory, RecursiveMode::NonRecursive) {
+                Ok(()) => {
+                    self.watched.insert(directory);
+                    newly_watched = true;
+                }
+                Err(err) => {
+                    self.unwatched.insert(directory);
+                    result = Err(WatchError::Watching(err));
+                }
+            }
+        }
+        // Paths in directories that didn't exist couldn't be normalized before
+        if newly_watched {
+            self.dependencies = std::mem::take(&mut self.dependencies)
+                .into_values()
+                .map(|(path, kind)| (normalize(&path), (path, kind)))
+                .collect();
+        }
+        result
+    }
+}
+
+/// Returns the absolute path of a file with symbolic links resolved, so that the paths of events
+/// can be compared with the ones of the map. The file itself may not exist, since it may have
+/// just been removed or renamed.
+fn normalize(path: &Path) -> PathBuf {
+    let directory = match path.parent() {
+        Some(directory) if directory != Path::new("") => directory,
+        _ => Path::new("."),
+    };
+    match (directory.canonicalize(), path.file_name()) {
+        (Ok(directory), Some(name)) => directory.join(name),
+        _ => path.to_owned(),
+    }
+}
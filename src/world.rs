@@ -0,0 +1,1213 @@
+//! Loading of Tiled [worlds](https://doc.mapeditor.org/en/stable/manual/worlds/). Requires the
+//! `world` feature.
+
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+    fmt,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+use xml::{attribute::OwnedAttribute, reader::XmlEvent, EventReader};
+
+use crate::{
+    reader::list_directory, Error, FilesystemResourceReader, Layer, LayerTile, LayerType, Loader,
+    Map, Object, Orientation, PropertyValue, ResourceCache, ResourceReader, Result, StaggerAxis,
+    StaggerIndex,
+};
+
+/// A world, which places several maps next to each other, as loaded from a `.world` file with
+/// [`Loader::load_world()`].
+///
+/// Maps are either listed explicitly in [`World::maps`], or found by matching the names of the
+/// files in the directory of the world against [`World::patterns`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct World {
+    /// The path first used in a [`ResourceReader`] to load this world.
+    pub source: PathBuf,
+    /// The maps listed explicitly in the world, in the order they appear in the file.
+    pub maps: Vec<WorldMap>,
+    /// The patterns used to place the maps whose file name matches them.
+    pub patterns: Vec<WorldPattern>,
+    /// Whether Tiled should only show the maps next to the one being edited.
+    pub only_show_adjacent_maps: bool,
+    /// The value of the `type` field of the file, which is `world` for files written by Tiled.
+    pub world_type: Option<String>,
+    /// The fields of the file that aren't part of the format, which tools other than Tiled may
+    /// have added, by name.
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A map placed in a [`World`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct WorldMap {
+    /// The path of the map file, relative to the directory of the world.
+    pub filename: String,
+    /// The X coordinate of the top-left corner of the map in the world, in pixels.
+    pub x: i32,
+    /// The Y coordinate of the top-left corner of the map in the world, in pixels.
+    pub y: i32,
+    /// The width of the map in pixels, if known.
+    pub width: Option<u32>,
+    /// The height of the map in pixels, if known.
+    pub height: Option<u32>,
+    /// The fields of the entry of the map in the world file that aren't part of the format,
+    /// which tools other than Tiled may have added, by name. Always empty for maps placed by a
+    /// [pattern](World::patterns).
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// A tile of a map in a [`World`] found at a position in pixels of the world, as returned by
+/// [`World::tile_at()`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct WorldTile<'map> {
+    /// The index of the map in [`World::maps`], which is also its index in the maps given to
+    /// [`World::tile_at()`].
+    pub map_index: usize,
+    /// The map the position is in.
+    pub map: &'map Map,
+    /// The X coordinate of the tile in the tile layers of the map.
+    pub x: i32,
+    /// The Y coordinate of the tile in the tile layers of the map.
+    pub y: i32,
+}
+
+/// An object of a map in a [`World`], as returned by [`World::resolve_object_property()`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct WorldObject<'map> {
+    /// The index of the map the object is in, within [`World::maps`].
+    pub map_index: usize,
+    /// The object.
+    pub object: Object<'map>,
+    /// The X coordinate of the object in the world, in pixels.
+    pub x: f32,
+    /// The Y coordinate of the object in the world, in pixels.
+    pub y: f32,
+}
+
+/// An index of the objects of all the maps of a [`World`], created with [`World::objects()`].
+///
+/// Object IDs are only unique within a map, so objects are identified by the index of their map
+/// in [`World::maps`] along with their ID. Objects are kept in the order of their maps, then in
+/// the order of the layers of each map, including the layers within groups, then in the order of
+/// each layer.
+#[derive(Debug, Clone)]
+pub struct WorldObjects<'map> {
+    objects: Vec<WorldObject<'map>>,
+    /// The indices in `objects` of the objects with each ID, in the order of their maps.
+    by_id: HashMap<u32, Vec<usize>>,
+    /// The indices in `objects` of the objects of each class.
+    by_class: HashMap<&'map str, Vec<usize>>,
+}
+
+impl<'map> WorldObjects<'map> {
+    /// Returns the object with the ID given in the map at `map_index` of [`World::maps`].
+    pub fn get(&self, map_index: usize, id: u32) -> Option<WorldObject<'map>> {
+        self.with_id(id)
+            .find(|object| object.map_index == map_index)
+    }
+
+    /// Returns the objects with the ID given in any of the maps, in the order of their maps.
+    pub fn with_id(&self, id: u32) -> impl Iterator<Item = WorldObject<'map>> + '_ {
+        self.indices(self.by_id.get(&id))
+    }
+
+    /// Returns the objects of the class given in any of the maps, in order.
+    pub fn with_class(&self, class: &str) -> impl Iterator<Item = WorldObject<'map>> + '_ {
+        self.indices(self.by_class.get(class))
+    }
+
+    /// Returns the object an [object property](PropertyValue::ObjectValue) found in the map at
+    /// `map_index` refers to.
+    ///
+    /// The object is looked up in the map the property was found in first. If it has no object
+    /// with that ID, and a single other map does, the property is taken to refer to the object
+    /// of that map, as if the reference pointed across maps. Returns [`None`] if several other
+    /// maps have an object with that ID, since the reference is then ambiguous, as well as for
+    /// the properties [`Map::resolve_object_property()`] would return [`None`] for.
+    pub fn resolve_object_property(
+        &self,
+        map_index: usize,
+        value: &PropertyValue,
+    ) -> Option<WorldObject<'map>> {
+        let id = match value {
+            PropertyValue::ObjectValue(0) => return None,
+            PropertyValue::ObjectValue(id) => *id,
+            _ => return None,
+        };
+        if let Some(object) = self.get(map_index, id) {
+            return Some(object);
+        }
+        let mut others = self.with_id(id);
+        match (others.next(), others.next()) {
+            (Some(object), None) => Some(object),
+            _ => None,
+        }
+    }
+
+    /// Returns every object of the world, in order.
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = WorldObject<'map>> + '_ {
+        self.objects.iter().copied()
+    }
+
+    /// The amount of objects in all the maps.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Returns true if none of the maps has objects.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    fn indices<'a>(
+        &'a self,
+        indices: Option<&'a Vec<usize>>,
+    ) -> impl Iterator<Item = WorldObject<'map>> + 'a {
+        indices
+            .into_iter()
+            .flatten()
+            .map(move |&index| self.objects[index])
+    }
+
+    fn add_layers(
+        &mut self,
+        map_index: usize,
+        origin: (f32, f32),
+        layers: impl Iterator<Item = Layer<'map>>,
+    ) {
+        for layer in layers {
+            match layer.layer_type() {
+                LayerType::Objects(layer) => {
+                    for object in layer.objects() {
+                        let index = self.objects.len();
+                        self.by_id.entry(object.id()).or_default().push(index);
+                        if let Some(class) = object.data.class() {
+                            self.by_class.entry(class).or_default().push(index);
+                        }
+                        self.objects.push(WorldObject {
+                            map_index,
+                            object,
+                            x: origin.0 + object.x,
+                            y: origin.1 + object.y,
+                        });
+                    }
+                }
+                LayerType::Group(group) => self.add_layers(map_index, origin, group.layers()),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<'map> WorldTile<'map> {
+    /// Returns the tile at this position in the layer at `layer_index` of [`Map::layers()`], or
+    /// [`None`] if the layer isn't a tile layer or has no tile there.
+    pub fn get_tile(&self, layer_index: usize) -> Option<LayerTile<'map>> {
+        self.map
+            .get_layer(layer_index)?
+            .as_tile_layer()?
+            .get_tile(self.x, self.y)
+    }
+
+    /// Returns the tiles at this position in the tile layers at the top of the layer hierarchy
+    /// of the map, i.e. not in groups, along with their layer, from the bottom layer up.
+    pub fn tiles(&self) -> impl Iterator<Item = (Layer<'map>, LayerTile<'map>)> + 'map {
+        let (x, y) = (self.x, self.y);
+        self.map.layers().filter_map(move |layer| {
+            let tile = layer.as_tile_layer()?.get_tile(x, y)?;
+            Some((layer, tile))
+        })
+    }
+}
+
+/// A probable authoring mistake found in a [`World`] by [`World::validate()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum WorldIssue {
+    /// Two maps of the world cover the same area.
+    OverlappingMaps {
+        /// The file name of the map listed or matched first.
+        first: String,
+        /// The file name of the map listed or matched second.
+        second: String,
+    },
+    /// A map file is listed more than once in [`World::maps`].
+    DuplicateMap {
+        /// The file name of the map, as listed.
+        filename: String,
+    },
+    /// A pattern doesn't match any of the files in the directory of the world.
+    UnusedPattern {
+        /// The index of the pattern in [`World::patterns`].
+        index: usize,
+    },
+    /// The directory of the world couldn't be listed to match patterns against its files.
+    UnreadableDirectory {
+        /// The description of the error that occurred.
+        description: String,
+    },
+}
+
+impl fmt::Display for WorldIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WorldIssue::OverlappingMaps { first, second } => {
+                write!(f, "Maps {} and {} overlap", first, second)
+            }
+            WorldIssue::DuplicateMap { filename } => {
+                write!(f, "Map {} is listed more than once", filename)
+            }
+            WorldIssue::UnusedPattern { index } => write!(
+                f,
+                "Pattern {} doesn't match any file in the directory of the world",
+                index
+            ),
+            WorldIssue::UnreadableDirectory { description } => write!(
+                f,
+                "The directory of the world couldn't be listed: {}",
+                description
+            ),
+        }
+    }
+}
+
+/// A pattern that places the maps whose file name matches it in a [`World`].
+///
+/// The regular expression must have two capture groups, which match the X and Y indices of the
+/// map. The map is then placed at the indices multiplied by [`WorldPattern::multiplier_x`] and
+/// [`WorldPattern::multiplier_y`], plus [`WorldPattern::offset_x`] and
+/// [`WorldPattern::offset_y`].
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub struct WorldPattern {
+    /// The regular expression the file name of maps is matched against.
+    pub regexp: Regex,
+    /// The horizontal distance between the maps, in pixels.
+    pub multiplier_x: i32,
+    /// The vertical distance between the maps, in pixels.
+    pub multiplier_y: i32,
+    /// The X coordinate of the map with an X index of 0, in pixels.
+    pub offset_x: i32,
+    /// The Y coordinate of the map with a Y index of 0, in pixels.
+    pub offset_y: i32,
+    /// The width of the maps in pixels, if the file specifies it.
+    pub map_width: Option<u32>,
+    /// The height of the maps in pixels, if the file specifies it.
+    pub map_height: Option<u32>,
+}
+
+impl WorldPattern {
+    /// Creates a pattern matching the file names following `format`, in which `{x}` and `{y}`
+    /// stand for the X and Y indices of the maps, e.g. `"map_{x}_{y}.tmx"`. Maps are placed next
+    /// to each other like Tiled does, each index counting maps of `map_width` by `map_height`
+    /// pixels from the origin of the world.
+    ///
+    /// Fails with [`Error::InvalidWorld`] unless `format` contains `{x}` followed by `{y}`
+    /// exactly once each.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::WorldPattern;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let pattern = WorldPattern::from_format("map_{x}_{y}.tmx", 640, 480)?;
+    /// assert_eq!(pattern.regexp.as_str(), r"^map_(-?\d+)_(-?\d+)\.tmx$");
+    ///
+    /// let map = pattern.match_filename("map_2_-1.tmx").unwrap();
+    /// assert_eq!((map.x, map.y, map.width), (1280, -480, Some(640)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_format(format: &str, map_width: u32, map_height: u32) -> Result<WorldPattern> {
+        let invalid = |description: &str| Error::InvalidWorld {
+            description: format!("invalid pattern format {:?}: {}", format, description),
+        };
+        let (before_x, after_x) = split_once_exactly(format, "{x}")
+            .ok_or_else(|| invalid("it must contain {x} exactly once"))?;
+        let (between, after_y) = split_once_exactly(after_x, "{y}")
+            .filter(|_| !before_x.contains("{y}"))
+            .ok_or_else(|| invalid("it must contain {y} exactly once, after {x}"))?;
+        let multiplier =
+            |size: u32| i32::try_from(size).map_err(|_| invalid("the size of the maps is too big"));
+
+        let index = r"(-?\d+)";
+        let regexp = format!(
+            "^{}{}{}{}{}$",
+            regex::escape(before_x),
+            index,
+            regex::escape(between),
+            index,
+            regex::escape(after_y)
+        );
+        Ok(WorldPattern {
+            regexp: Regex::new(&regexp).map_err(|err| invalid(&err.to_string()))?,
+            multiplier_x: multiplier(map_width)?,
+            multiplier_y: multiplier(map_height)?,
+            offset_x: 0,
+            offset_y: 0,
+            map_width: Some(map_width),
+            map_height: Some(map_height),
+        })
+    }
+
+    /// Returns the place of the map with the given file name if the name matches this pattern.
+    ///
+    /// `filename` is only the name of the file, without any directory: Tiled only matches the
+    /// files in the directory of the world against its patterns. Use [`World::match_path()`] to
+    /// match a path instead.
+    pub fn match_filename(&self, filename: &str) -> Option<WorldMap> {
+        let captures = self.regexp.captures(filename)?;
+        if captures.len() != 3 {
+            return None;
+        }
+        let x: i32 = captures.get(1)?.as_str().parse().ok()?;
+        let y: i32 = captures.get(2)?.as_str().parse().ok()?;
+        Some(WorldMap {
+            filename: filename.to_owned(),
+            x: x.checked_mul(self.multiplier_x)?
+                .checked_add(self.offset_x)?,
+            y: y.checked_mul(self.multiplier_y)?
+                .checked_add(self.offset_y)?,
+            width: self.map_width,
+            height: self.map_height,
+            extra: serde_json::Map::new(),
+        })
+    }
+}
+
+impl World {
+    /// The directory of the world, which the file names of its maps are relative to.
+    pub fn directory(&self) -> &Path {
+        self.source.parent().unwrap_or_else(|| Path::new(""))
+    }
+
+    /// Returns the place of the map at `path` if the first pattern it matches places it, or
+    /// [`None`] if it matches no pattern.
+    ///
+    /// Like Tiled, only the maps in the directory of the world are matched: Paths in other
+    /// directories, including subdirectories, never match. `path` is compared with
+    /// [`World::directory()`] as is, so both should be relative to the same directory.
+    /// Use [`WorldPattern::match_filename()`] to match file names regardless of their directory.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let world = Loader::new().load_world("assets/world/world_pattern.world")?;
+    ///
+    /// let map = world.match_path("assets/world/map-x04-y02.tmx").unwrap();
+    /// assert_eq!((map.x, map.y), (4 * 640, 2 * 480));
+    /// assert!(world.match_path("assets/map-x04-y02.tmx").is_none());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn match_path(&self, path: impl AsRef<Path>) -> Option<WorldMap> {
+        let path = path.as_ref();
+        if path.parent().unwrap_or_else(|| Path::new("")) != self.directory() {
+            return None;
+        }
+        let filename = path.file_name()?.to_str()?;
+        self.patterns
+            .iter()
+            .find_map(|pattern| pattern.match_filename(filename))
+    }
+
+    /// Returns the maps of the world: The ones it lists, followed by the ones among `filenames`
+    /// that match one of its patterns, as [`World::match_path()`] would place them. File names
+    /// are relative to [`World::directory()`], and those for which a map is listed are skipped.
+    pub fn maps_with<'a>(&self, filenames: impl IntoIterator<Item = &'a str>) -> Vec<WorldMap> {
+        let mut maps = self.maps.clone();
+        for filename in filenames {
+            if maps.iter().any(|map| map.filename == filename) {
+                continue;
+            }
+            if let Some(map) = self.match_path(self.directory().join(filename)) {
+                maps.push(map);
+            }
+        }
+        maps
+    }
+
+    /// Returns the maps of the world: The ones it lists, followed by the ones whose file in
+    /// [`World::directory()`] matches one of its patterns, sorted by file name. The directory is
+    /// listed with [`ResourceReader::list_directory()`].
+    ///
+    /// Like Tiled, only the files directly in the directory are matched, not the ones in its
+    /// subdirectories, which [`World::discover_maps_recursive()`] searches as well. Fails with
+    /// [`Error::ResourceLoadingError`] if the directory can't be listed, including when `reader`
+    /// doesn't support listing directories; Use [`World::maps_with()`] to match file names found
+    /// another way then.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let world = loader.load_world("assets/world/world_basic.world")?;
+    /// for world_map in world.discover_maps(loader.reader_mut())? {
+    ///     let map = loader.load_tmx_map(world.directory().join(&world_map.filename))?;
+    ///     println!("{} layers at {}, {}", map.layers().len(), world_map.x, world_map.y);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn discover_maps(&self, reader: &mut impl ResourceReader) -> Result<Vec<WorldMap>> {
+        self.discover(reader, false)
+    }
+
+    /// Returns the maps of the world like [`World::discover_maps()`], also searching the
+    /// subdirectories of [`World::directory()`] for files matching its patterns, which Tiled
+    /// doesn't do.
+    ///
+    /// Patterns are matched against the names of the files, and the [`WorldMap::filename`] of
+    /// maps found in subdirectories is their path relative to the directory of the world, with
+    /// `/` separators. Maps are sorted by that path.
+    pub fn discover_maps_recursive(
+        &self,
+        reader: &mut impl ResourceReader,
+    ) -> Result<Vec<WorldMap>> {
+        self.discover(reader, true)
+    }
+
+    fn discover(&self, reader: &mut impl ResourceReader, recursive: bool) -> Result<Vec<WorldMap>> {
+        let mut maps = self.maps.clone();
+        if self.patterns.is_empty() {
+            return Ok(maps);
+        }
+        let filenames = self.filenames(reader, recursive)?;
+
+        for filename in filenames {
+            if maps.iter().any(|map| map.filename == filename) {
+                continue;
+            }
+            let name = filename.rsplit('/').next().unwrap_or(&filename);
+            if let Some(mut map) = self
+                .patterns
+                .iter()
+                .find_map(|pattern| pattern.match_filename(name))
+            {
+                map.filename = filename;
+                maps.push(map);
+            }
+        }
+        Ok(maps)
+    }
+
+    /// Lists the paths of the files in the directory of the world, and in its subdirectories if
+    /// `recursive`, relative to it and with `/` separators, sorted.
+    fn filenames(&self, reader: &mut impl ResourceReader, recursive: bool) -> Result<Vec<String>> {
+        let mut filenames = Vec::new();
+        let mut directories = vec![self.directory().to_owned()];
+        while let Some(directory) = directories.pop() {
+            for entry in list_directory(reader, &directory)? {
+                if entry.is_directory {
+                    if recursive {
+                        directories.push(entry.path);
+                    }
+                    continue;
+                }
+                let relative = match entry.path.strip_prefix(self.directory()) {
+                    Ok(relative) => relative,
+                    Err(_) => continue,
+                };
+                let components: Option<Vec<&str>> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_str())
+                    .collect();
+                if let Some(components) = components {
+                    filenames.push(components.join("/"));
+                }
+            }
+        }
+        filenames.sort_unstable();
+        Ok(filenames)
+    }
+
+    /// Fills in the [`WorldMap::width`] and [`WorldMap::height`] of the maps in [`World::maps`]
+    /// that lack them with the size of the map in pixels, so that where each map ends is known.
+    ///
+    /// Only the `map` element of finite maps is read, which tells their size. Infinite maps are
+    /// loaded entirely, their size being the one of the area their chunks cover.
+    ///
+    /// Maps placed by patterns can be resolved by adding them to [`World::maps`] first.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let mut world = loader.load_world("assets/world/world_sizes.world")?;
+    /// assert_eq!(world.maps[0].width, None);
+    ///
+    /// world.resolve_sizes(&mut loader)?;
+    /// assert_eq!(world.maps[0].width, Some(3200));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn resolve_sizes<Cache: ResourceCache, Reader: ResourceReader>(
+        &mut self,
+        loader: &mut Loader<Cache, Reader>,
+    ) -> Result<()> {
+        let directory = self.directory().to_owned();
+        for map in &mut self.maps {
+            if map.width.is_some() && map.height.is_some() {
+                continue;
+            }
+            let (width, height) = loader.map_pixel_size(&directory.join(&map.filename))?;
+            map.width.get_or_insert(width);
+            map.height.get_or_insert(height);
+        }
+        Ok(())
+    }
+
+    /// Checks the world for mistakes that Tiled tolerates: Maps covering the same area, maps listed
+    /// more than once and patterns that match none of the files in [`World::directory()`], which
+    /// is read from the filesystem. Returns every issue found; An empty list means that the world
+    /// is fine.
+    ///
+    /// Maps whose size is unknown can't overlap others; Use [`World::resolve_sizes()`] first to
+    /// check them as well.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let world = Loader::new().load_world("assets/world/world_basic.world")?;
+    /// for issue in world.validate() {
+    ///     eprintln!("{}: {}", world.source.display(), issue);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn validate(&self) -> Vec<WorldIssue> {
+        let mut issues = Vec::new();
+
+        let mut listed = HashSet::new();
+        for map in &self.maps {
+            if !listed.insert(&map.filename) {
+                issues.push(WorldIssue::DuplicateMap {
+                    filename: map.filename.clone(),
+                });
+            }
+        }
+
+        let mut filenames = Vec::new();
+        if !self.patterns.is_empty() {
+            match self.filenames(&mut FilesystemResourceReader::new(), false) {
+                Ok(names) => {
+                    filenames = names;
+                    for (index, pattern) in self.patterns.iter().enumerate() {
+                        if !filenames
+                            .iter()
+                            .any(|filename| pattern.match_filename(filename).is_some())
+                        {
+                            issues.push(WorldIssue::UnusedPattern { index });
+                        }
+                    }
+                }
+                Err(err) => issues.push(WorldIssue::UnreadableDirectory {
+                    description: match err {
+                        Error::ResourceLoadingError { err, .. } => err.to_string(),
+                        err => err.to_string(),
+                    },
+                }),
+            }
+        }
+
+        let maps = self.maps_with(filenames.iter().map(String::as_str));
+        let rectangles: Vec<_> = maps
+            .iter()
+            .filter_map(|map| {
+                let (width, height) = (map.width?, map.height?);
+                let (x, y) = (i64::from(map.x), i64::from(map.y));
+                Some((map, x, y, x + i64::from(width), y + i64::from(height)))
+            })
+            .collect();
+        for (i, &(first, left, top, right, bottom)) in rectangles.iter().enumerate() {
+            for &(second, other_left, other_top, other_right, other_bottom) in &rectangles[i + 1..]
+            {
+                if first.filename != second.filename
+                    && left < other_right
+                    && other_left < right
+                    && top < other_bottom
+                    && other_top < bottom
+                {
+                    issues.push(WorldIssue::OverlappingMaps {
+                        first: first.filename.clone(),
+                        second: second.filename.clone(),
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    /// Returns the map containing the given position in pixels of the world and the coordinates of
+    /// the tile there, or [`None`] if no map contains the position.
+    ///
+    /// `maps` are the maps of [`World::maps`] once loaded, in the same order. The size of maps
+    /// whose [`WorldMap::width`] or [`WorldMap::height`] is unknown is computed from the map, the
+    /// area covered by its chunks being placed at the position of infinite maps. If maps overlap,
+    /// the first one containing the position is used.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let world = loader.load_world("assets/world/world_basic.world")?;
+    /// let maps = world
+    ///     .maps
+    ///     .iter()
+    ///     .map(|map| loader.load_tmx_map(world.directory().join(&map.filename)))
+    ///     .collect::<tiled::Result<Vec<_>>>()?;
+    ///
+    /// let tile = world.tile_at(&maps, 3200 + 40, 70).unwrap();
+    /// assert_eq!((tile.map_index, tile.x, tile.y), (1, 1, 2));
+    /// let solid = tile.tiles().any(|(_, tile)| tile.id() != 0);
+    /// # let _ = solid;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn tile_at<'map>(
+        &self,
+        maps: &'map [Map],
+        world_x: i32,
+        world_y: i32,
+    ) -> Option<WorldTile<'map>> {
+        self.maps
+            .iter()
+            .zip(maps)
+            .enumerate()
+            .find_map(|(map_index, (world_map, map))| {
+                let header = MapHeader::from_map(map);
+                let (width, height) = header.pixel_size();
+                let width = world_map.width.unwrap_or(width);
+                let height = world_map.height.unwrap_or(height);
+                let x = world_x.checked_sub(world_map.x)?;
+                let y = world_y.checked_sub(world_map.y)?;
+                if x < 0 || y < 0 || x as u32 >= width || y as u32 >= height {
+                    return None;
+                }
+                let ((origin_x, origin_y), _) = map.tile_bounds();
+                let (tile_x, tile_y) = header.pixel_to_tile(x, y)?;
+                Some(WorldTile {
+                    map_index,
+                    map,
+                    x: tile_x + origin_x,
+                    y: tile_y + origin_y,
+                })
+            })
+    }
+
+    /// Returns the object an [object property](crate::PropertyValue::ObjectValue) found in the map
+    /// at `map_index` of `maps` refers to, along with its position in the world. `maps` are the
+    /// maps listed in [`World::maps`], in the same order. See [`Map::resolve_object_property()`].
+    ///
+    /// Object IDs are only unique within a map, so the object is looked up in the map the property
+    /// was found in. The position of the object is that of its map in the world plus its own,
+    /// without the offsets of the layers it is in.
+    pub fn resolve_object_property<'map>(
+        &self,
+        maps: &'map [Map],
+        map_index: usize,
+        value: &PropertyValue,
+    ) -> Option<WorldObject<'map>> {
+        let world_map = self.maps.get(map_index)?;
+        let object = maps.get(map_index)?.resolve_object_property(value)?;
+        Some(WorldObject {
+            map_index,
+            object,
+            x: world_map.x as f32 + object.x,
+            y: world_map.y as f32 + object.y,
+        })
+    }
+
+    /// Indexes the objects of `maps`, which are the maps listed in [`World::maps`] once loaded, in
+    /// the same order, so that they can be looked up across maps. The positions of the objects
+    /// are those of their map in the world plus their own, as with
+    /// [`World::resolve_object_property()`].
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let world = loader.load_world("assets/world/world_basic.world")?;
+    /// let maps = world
+    ///     .maps
+    ///     .iter()
+    ///     .map(|map| loader.load_tmx_map(world.directory().join(&map.filename)))
+    ///     .collect::<tiled::Result<Vec<_>>>()?;
+    ///
+    /// let objects = world.objects(&maps);
+    /// for spawn in objects.with_class("spawn") {
+    ///     println!("spawn point at ({}, {})", spawn.x, spawn.y);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn objects<'map>(&self, maps: &'map [Map]) -> WorldObjects<'map> {
+        let mut objects = WorldObjects {
+            objects: Vec::new(),
+            by_id: HashMap::new(),
+            by_class: HashMap::new(),
+        };
+        for (map_index, (world_map, map)) in self.maps.iter().zip(maps).enumerate() {
+            let origin = (world_map.x as f32, world_map.y as f32);
+            objects.add_layers(map_index, origin, map.layers());
+        }
+        objects
+    }
+
+    /// Parses a world from the contents of a `.world` file. `path` is the path the file was read
+    /// from, which the maps of the world are relative to.
+    pub fn parse(path: impl AsRef<Path>, json: &[u8]) -> Result<World> {
+        let invalid = |description: String| Error::InvalidWorld { description };
+        let raw: RawWorld = serde_json::from_slice(json).map_err(|err| invalid(err.to_string()))?;
+
+        let patterns = raw
+            .patterns
+            .into_iter()
+            .map(|pattern| {
+                let regexp = Regex::new(&pattern.regexp)
+                    .map_err(|err| invalid(format!("invalid pattern: {}", err)))?;
+                Ok(WorldPattern {
+                    regexp,
+                    multiplier_x: pattern.multiplier_x,
+                    multiplier_y: pattern.multiplier_y,
+                    offset_x: pattern.offset_x,
+                    offset_y: pattern.offset_y,
+                    map_width: pattern.map_width,
+                    map_height: pattern.map_height,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(World {
+            source: path.as_ref().to_owned(),
+            maps: raw
+                .maps
+                .into_iter()
+                .map(|map| WorldMap {
+                    filename: map.file_name,
+                    x: map.x,
+                    y: map.y,
+                    width: map.width,
+                    height: map.height,
+                    extra: map.extra,
+                })
+                .collect(),
+            patterns,
+            only_show_adjacent_maps: raw.only_show_adjacent_maps,
+            world_type: raw.world_type,
+            extra: raw.extra,
+        })
+    }
+}
+
+impl<Cache: ResourceCache, Reader: ResourceReader> Loader<Cache, Reader> {
+    /// Parses a `.world` file. The maps of the world aren't loaded, use
+    /// [`Loader::load_tmx_map()`] for each of them as needed.
+    ///
+    /// ## Example
+    /// ```
+    /// use tiled::Loader;
+    ///
+    /// # fn main() -> tiled::Result<()> {
+    /// let mut loader = Loader::new();
+    /// let world = loader.load_world("assets/world/world_basic.world")?;
+    /// for world_map in &world.maps {
+    ///     let map = loader.load_tmx_map(world.directory().join(&world_map.filename))?;
+    ///     println!("{} layers at {}, {}", map.layers().len(), world_map.x, world_map.y);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn load_world(&mut self, path: impl AsRef<Path>) -> Result<World> {
+        let path = path.as_ref();
+        let mut file =
+            self.reader_mut()
+                .read_from(path)
+                .map_err(|err| Error::ResourceLoadingError {
+                    path: path.to_owned(),
+                    err: Box::new(err),
+                })?;
+        let mut json = Vec::new();
+        file.read_to_end(&mut json)
+            .map_err(|err| Error::ResourceLoadingError {
+                path: path.to_owned(),
+                err: Box::new(err),
+            })?;
+        World::parse(path, &json)
+    }
+
+    /// Returns the size in pixels of the map at `path`, reading as little of it as possible.
+    fn map_pixel_size(&mut self, path: &Path) -> Result<(u32, u32)> {
+        let file =
+            self.reader_mut()
+                .read_from(path)
+                .map_err(|err| Error::ResourceLoadingError {
+                    path: path.to_owned(),
+                    err: Box::new(err),
+                })?;
+        let attributes = EventReader::new(file)
+            .into_iter()
+            .find_map(|event| match event {
+                Ok(XmlEvent::StartElement {
+                    name, attributes, ..
+                }) if name.local_name == "map" => Some(Ok(attributes)),
+                Ok(_) => None,
+                Err(err) => Some(Err(Error::XmlDecodingError(err))),
+            })
+            .transpose()?
+            .ok_or_else(|| Error::PrematureEnd("Document ended before the map element".into()))?;
+        let header = MapHeader::new(&attributes)?;
+
+        if header.infinite {
+            let map = self.load_tmx_map(path)?;
+            return Ok(MapHeader::from_map(&map).pixel_size());
+        }
+        Ok(header.pixel_size())
+    }
+}
+
+/// The attributes of the `map` element of a map file that the placement of its tiles depends on.
+#[derive(Clone, Copy)]
+struct MapHeader {
+    orientation: Orientation,
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    stagger_axis: StaggerAxis,
+    stagger_index: StaggerIndex,
+    hex_side_length: u32,
+    infinite: bool,
+}
+
+impl MapHeader {
+    fn new(attributes: &[OwnedAttribute]) -> Result<Self> {
+        let attribute = |name: &str| {
+            attributes
+                .iter()
+                .find(|attribute| attribute.name.local_name == name)
+                .map(|attribute| attribute.value.as_str())
+        };
+        let number = |name: &str| -> Result<u32> {
+            attribute(name).map_or(Ok(0), |value| {
+                value.parse().map_err(|_| {
+                    Error::MalformedAttributes(format!("map {} must be an integer", name))
+                })
+            })
+        };
+        let malformed = |description: &str| Error::MalformedAttributes(description.to_owned());
+
+        Ok(MapHeader {
+            orientation: attribute("orientation")
+                .ok_or_else(|| malformed("map must have an orientation"))?
+                .parse()
+                .map_err(|_| malformed("map orientation is invalid"))?,
+            width: number("width")?,
+            height: number("height")?,
+            tile_width: number("tilewidth")?,
+            tile_height: number("tileheight")?,
+            stagger_axis: attribute("staggeraxis")
+                .map_or(Ok(StaggerAxis::default()), str::parse)
+                .map_err(|_| malformed("map stagger axis is invalid"))?,
+            stagger_index: attribute("staggerindex")
+                .map_or(Ok(StaggerIndex::default()), str::parse)
+                .map_err(|_| malformed("map stagger index is invalid"))?,
+            hex_side_length: number("hexsidelength")?,
+            infinite: attribute("infinite") == Some("1"),
+        })
+    }
+
+    /// The header of a loaded map, sized like the area covered by its chunks if it is infinite.
+    fn from_map(map: &Map) -> Self {
+        let (_, (width, height)) = map.tile_bounds();
+        MapHeader {
+            orientation: map.orientation,
+            width,
+            height,
+            tile_width: map.tile_width,
+            tile_height: map.tile_height,
+            stagger_axis: map.stagger_axis,
+            stagger_index: map.stagger_index,
+            hex_side_length: map.hex_side_length.unwrap_or(0).max(0) as u32,
+            infinite: map.infinite(),
+        }
+    }
+
+    /// The size of the map in pixels, computed the way Tiled does.
+    fn pixel_size(&self) -> (u32, u32) {
+        let (width, height) = (self.width, self.height);
+        match self.orientation {
+            Orientation::Orthogonal => (width * self.tile_width, height * self.tile_height),
+            Orientation::Isometric => {
+                let side = width + height;
+                (side * self.tile_width / 2, side * self.tile_height / 2)
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                let hex = HexGeometry::new(self);
+                match self.stagger_axis {
+                    StaggerAxis::X => {
+                        let mut size = (
+                            width * hex.column_width + hex.side_offset_x,
+                            height * (hex.tile_height + hex.side_length_y),
+                        );
+                        if width > 1 {
+                            size.1 += hex.row_height;
+                        }
+                        size
+                    }
+                    StaggerAxis::Y => {
+                        let mut size = (
+                            width * (hex.tile_width + hex.side_length_x),
+                            height * hex.row_height + hex.side_offset_y,
+                        );
+                        if height > 1 {
+                            size.0 += hex.column_width;
+                        }
+                        size
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the coordinates of the tile at the given position in pixels, relative to the top
+    /// left corner of the map, computed the way Tiled does.
+    fn pixel_to_tile(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        if self.tile_width == 0 || self.tile_height == 0 {
+            return None;
+        }
+        let (tile_width, tile_height) = (self.tile_width as i32, self.tile_height as i32);
+        match self.orientation {
+            Orientation::Orthogonal => Some((x.div_euclid(tile_width), y.div_euclid(tile_height))),
+            Orientation::Isometric => {
+                // The top corner of the tile at (0, 0) is at the middle of the map's top edge
+                let x = f64::from(x) - f64::from(self.height * self.tile_width) / 2.0;
+                let tile_x = x / f64::from(tile_width);
+                let tile_y = f64::from(y) / f64::from(tile_height);
+                Some((
+                    (tile_y + tile_x).floor() as i32,
+                    (tile_y - tile_x).floor() as i32,
+                ))
+            }
+            Orientation::Staggered | Orientation::Hexagonal => {
+                Some(HexGeometry::new(self).pixel_to_tile(x, y))
+            }
+        }
+    }
+}
+
+/// The dimensions Tiled lays out the tiles of staggered and hexagonal maps with. Staggered maps
+/// are laid out like hexagonal maps whose hexagons have sides of length 0.
+struct HexGeometry {
+    staggered: bool,
+    stagger_x: bool,
+    stagger_even: bool,
+    tile_width: u32,
+    tile_height: u32,
+    side_length_x: u32,
+    side_length_y: u32,
+    side_offset_x: u32,
+    side_offset_y: u32,
+    column_width: u32,
+    row_height: u32,
+}
+
+impl HexGeometry {
+    fn new(header: &MapHeader) -> Self {
+        let staggered = header.orientation == Orientation::Staggered;
+        let stagger_x = header.stagger_axis == StaggerAxis::X;
+        let side_length = if staggered { 0 } else { header.hex_side_length };
+        let tile_width = header.tile_width & !1;
+        let tile_height = header.tile_height & !1;
+        let (side_length_x, side_length_y) = if stagger_x {
+            (side_length, 0)
+        } else {
+            (0, side_length)
+        };
+        let side_offset_x = tile_width.saturating_sub(side_length_x) / 2;
+        let side_offset_y = tile_height.saturating_sub(side_length_y) / 2;
+        HexGeometry {
+            staggered,
+            stagger_x,
+            stagger_even: header.stagger_index == StaggerIndex::Even,
+            tile_width,
+            tile_height,
+            side_length_x,
+            side_length_y,
+            side_offset_x,
+            side_offset_y,
+            column_width: side_offset_x + side_length_x,
+            row_height: side_offset_y + side_length_y,
+        }
+    }
+
+    fn pixel_to_tile(&self, x: i32, y: i32) -> (i32, i32) {
+        let (mut x, mut y) = (f64::from(x), f64::from(y));
+        if self.stagger_x {
+            x -= f64::from(if self.stagger_even {
+                self.tile_width
+            } else {
+                self.side_offset_x
+            });
+        } else {
+            y -= f64::from(if self.stagger_even {
+                self.tile_height
+            } else {
+                self.side_offset_y
+            });
+        }
+
+        // The tiles are found in blocks of two columns or rows, each containing one whole tile
+        // and parts of the three tiles staggered around it
+        let block_width = f64::from(self.column_width * 2).max(1.0);
+        let block_height = f64::from(self.row_height * 2).max(1.0);
+        let block_x = (x / block_width).floor();
+        let block_y = (y / block_height).floor();
+        let (x, y) = (x - block_x * block_width, y - block_y * block_height);
+        let (mut tile_x, mut tile_y) = (block_x as i32, block_y as i32);
+        let stagger_coordinate = if self.stagger_x {
+            &mut tile_x
+        } else {
+            &mut tile_y
+        };
+        *stagger_coordinate = *stagger_coordinate * 2 + i32::from(self.stagger_even);
+
+        let (column_width, row_height) = (f64::from(self.column_width), f64::from(self.row_height));
+        let (centers, offsets) = if self.stagger_x {
+            let left = f64::from(self.side_length_x / 2);
+            let (center_x, center_y) = (left + column_width, f64::from(self.tile_height / 2));
+            (
+                [
+                    (left, center_y),
+                    (center_x, center_y - row_height),
+                    (center_x, center_y + row_height),
+                    (center_x + column_width, center_y),
+                ],
+                [(0, 0), (1, -1), (1, 0), (2, 0)],
+            )
+        } else {
+            let top = f64::from(self.side_length_y / 2);
+            let (center_x, center_y) = (f64::from(self.tile_width / 2), top + row_height);
+            (
+                [
+                    (center_x, top),
+                    (center_x - column_width, center_y),
+                    (center_x + column_width, center_y),
+                    (center_x, center_y + row_height),
+                ],
+                [(0, 0), (-1, 1), (0, 1), (0, 2)],
+            )
+        };
+        // Hexagons are the tiles whose center is the nearest, while the diamonds of staggered
+        // maps are the ones whose center is the nearest when stretched into squares
+        let (half_width, half_height) = (
+            f64::from(self.tile_width.max(2) / 2),
+            f64::from(self.tile_height.max(2) / 2),
+        );
+        let distance = |(center_x, center_y): (f64, f64)| {
+            if self.staggered {
+                (x - center_x).abs() / half_width + (y - center_y).abs() / half_height
+            } else {
+                (x - center_x).powi(2) + (y - center_y).powi(2)
+            }
+        };
+        let nearest = (1..4).fold(0, |nearest, i| {
+            if distance(centers[i]) < distance(centers[nearest]) {
+                i
+            } else {
+                nearest
+            }
+        });
+        (tile_x + offsets[nearest].0, tile_y + offsets[nearest].1)
+    }
+}
+
+/// Splits `text` around `separator` if it appears exactly once.
+fn split_once_exactly<'a>(text: &'a str, separator: &str) -> Option<(&'a str, &'a str)> {
+    text.split_once(separator)
+        .filter(|(_, after)| !after.contains(separator))
+}
+
+/// The contents of a `.world` file. Fields Tiled doesn't always write have default values, and
+/// unknown fields are kept as they are.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawWorld {
+    #[serde(default)]
+    maps: Vec<RawWorldMap>,
+    #[serde(default)]
+    patterns: Vec<RawWorldPattern>,
+    #[serde(default)]
+    only_show_adjacent_maps: bool,
+    #[serde(rename = "type")]
+    world_type: Option<String>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawWorldMap {
+    file_name: String,
+    #[serde(default, deserialize_with = "int")]
+    x: i32,
+    #[serde(default, deserialize_with = "int")]
+    y: i32,
+    #[serde(default, deserialize_with = "size")]
+    width: Option<u32>,
+    #[serde(default, deserialize_with = "size")]
+    height: Option<u32>,
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RawWorldPattern {
+    regexp: String,
+    #[serde(default, deserialize_with = "int")]
+    multiplier_x: i32,
+    #[serde(default, deserialize_with = "int")]
+    multiplier_y: i32,
+    #[serde(default, deserialize_with = "int")]
+    offset_x: i32,
+    #[serde(default, deserialize_with = "int")]
+    offset_y: i32,
+    #[serde(default, deserialize_with = "size")]
+    map_width: Option<u32>,
+    #[serde(default, deserialize_with = "size")]
+    map_height: Option<u32>,
+}
+
+/// Reads a coordinate, which may have been written as a floating point number by other tools.
+fn int<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<i32, D::Error> {
+    f64::deserialize(deserializer).map(|value| value.round() as i32)
+}
+
+/// Reads a size that may be missing or null, and may have been written as a floating point
+/// number.
+fn size<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<u32>, D::Error> {
+    Option::<f64>::deserialize(deserializer).map(|value| value.map(|value| value.round() as u32))
+}
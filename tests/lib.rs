@@ -1,8 +1,17 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use tiled::{
-    Color, FiniteTileLayer, HorizontalAlignment, LayerType, Loader, Map, ObjectShape,
-    PropertyValue, ResourceCache, TileLayer, TilesetLocation, VerticalAlignment, WangId,
+    ChainedReadError, ChainedResourceReader, Color, DataFormat, DependencyKind, DrawOrder,
+    EditError, EmbedTilesetError, Encoding, Error, Feature, FilesystemResourceReader, FillMode,
+    FiniteTileLayer, HorizontalAlignment, Image, LayerBuilder, LayerEditError, LayerTileData,
+    LayerType, Loader, Map, ObjectBuilder, ObjectShape, ObjectTransform, ParseWarningKind,
+    PathBase, PathPolicy, Properties, PropertiesExt, PropertyChange, PropertyConflict,
+    PropertyTarget, PropertyValue, RawElement, RawNode, ReplaceTilesetError, ResourceCache,
+    ResourceHandling, TileLayer, TileRect, TileRenderSize, TilesetBuilder, TilesetLocation,
+    TilesetStorage, UvPadding, ValidationIssue, Version, VerticalAlignment, WangId,
 };
 
 fn as_finite<'map>(data: TileLayer<'map>) -> FiniteTileLayer<'map> {
@@ -616,3 +625,4703 @@ fn test_text_object() {
         _ => panic!(),
     };
 }
+
+#[test]
+fn test_uv_table_extrusion() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap();
+
+    // 14 columns and 6 rows of 32x32 tiles, each extruded by 1 pixel on every side.
+    let (width, height) = (14 * 34, 6 * 34);
+    let uvs = tileset.uv_table(width, height, UvPadding::Extruded(1));
+    assert_eq!(uvs.len(), 84);
+
+    let uv = uvs[15];
+    assert_eq!(uv.u_min, 35. / width as f32);
+    assert_eq!(uv.v_min, 35. / height as f32);
+    assert_eq!(uv.u_max, 67. / width as f32);
+    assert_eq!(uv.v_max, 67. / height as f32);
+}
+
+#[test]
+fn test_next_ids() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    assert_eq!(map.next_layer_id(), 3);
+    assert_eq!(map.next_object_id(), 5);
+
+    let object = ObjectBuilder::new(ObjectShape::Point(0., 0.)).build(&mut map);
+    let layer = LayerBuilder::objects("objects", vec![object]).build(&mut map);
+    assert_eq!(layer.id(), 3);
+    assert_eq!(map.next_layer_id(), 4);
+    assert_eq!(map.next_object_id(), 6);
+}
+
+#[test]
+fn test_layer_editing() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let ids = |map: &Map| map.layers().map(|layer| layer.id()).collect::<Vec<_>>();
+    let original = ids(&map);
+
+    let group = LayerBuilder::group("group", vec![]).build(&mut map);
+    let group_id = map.push_layer(group);
+    map.move_layer(original[0], Some(group_id), 0).unwrap();
+    assert_eq!(ids(&map).len(), original.len());
+    assert_eq!(
+        map.move_layer(group_id, Some(group_id), 0),
+        Err(LayerEditError::MoveIntoItself(group_id))
+    );
+    let layer = LayerBuilder::tiles("x").build(&mut map);
+    assert_eq!(
+        map.insert_layer(Some(original[0]), 0, layer),
+        Err(LayerEditError::NotAGroup(original[0]))
+    );
+
+    // Reinserting a copy of an existing layer gives it a new ID
+    let copy = map.remove_layer(original[0]).unwrap();
+    let copy_id = map.push_layer(copy.clone());
+    assert_eq!(copy_id, original[0]);
+    assert_ne!(map.push_layer(copy), copy_id);
+    assert!(map.remove_layer(12345).is_err());
+}
+
+#[test]
+fn test_content_eq() {
+    let absolute = std::env::current_dir().unwrap().join("assets");
+    let mut loader = Loader::new();
+    let map = loader
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    let moved = Loader::new()
+        .load_tmx_map(absolute.join("tiled_image_layers.tmx"))
+        .unwrap();
+    assert!(map != moved);
+    assert!(map.content_eq(&moved));
+
+    let map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let mut moved = Loader::new()
+        .load_tmx_map(absolute.join("tiled_object_template.tmx"))
+        .unwrap();
+    assert!(map.content_eq(&moved));
+    moved
+        .properties
+        .insert("changed".into(), PropertyValue::BoolValue(true));
+    assert!(!map.content_eq(&moved));
+
+    let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    let mut moved = Loader::new()
+        .load_tsx_tileset(absolute.join("tilesheet.tsx"))
+        .unwrap();
+    assert!(tileset != moved && tileset.content_eq(&moved));
+    moved.image.as_mut().unwrap().source = absolute.join("other.png");
+    assert!(!tileset.content_eq(&moved));
+}
+
+#[test]
+fn test_content_hash() {
+    let mut loader = Loader::new();
+    let base64 = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    for path in [
+        "assets/tiled_csv.tmx",
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_gzip.tmx",
+    ] {
+        let map = loader.load_tmx_map(path).unwrap();
+        assert_eq!(map.content_hash(), base64.content_hash(), "{}", path);
+    }
+
+    let absolute = std::env::current_dir().unwrap().join("assets");
+    let map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let mut moved = Loader::new()
+        .load_tmx_map(absolute.join("tiled_object_template.tmx"))
+        .unwrap();
+    assert_eq!(map.content_hash(), moved.content_hash());
+    let layer_hashes: Vec<_> = moved.layers().map(|layer| layer.content_hash()).collect();
+    moved
+        .properties
+        .insert("changed".into(), PropertyValue::BoolValue(true));
+    assert_ne!(map.content_hash(), moved.content_hash());
+    assert_eq!(
+        moved
+            .layers()
+            .map(|layer| layer.content_hash())
+            .collect::<Vec<_>>(),
+        layer_hashes
+    );
+
+    let group = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let hashes: Vec<_> = group.layers().map(|layer| layer.content_hash()).collect();
+    let mut unique = hashes.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), hashes.len());
+
+    let tileset = loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap();
+    let mut moved = Loader::new()
+        .load_tsx_tileset(absolute.join("tilesheet.tsx"))
+        .unwrap();
+    assert_eq!(tileset.content_hash(), moved.content_hash());
+    moved.spacing += 1;
+    assert_ne!(tileset.content_hash(), moved.content_hash());
+}
+
+#[test]
+fn test_edit_transaction() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let original = map.clone();
+    let tile_layer = map.get_layer(0).unwrap().id();
+    let object_layer = map.get_layer(1).unwrap().id();
+    let object = map
+        .get_layer(1)
+        .unwrap()
+        .as_object_layer()
+        .unwrap()
+        .get_object(0)
+        .unwrap()
+        .id();
+    let point = ObjectBuilder::new(ObjectShape::Point(1., 2.)).build(&mut map);
+
+    let mut transaction = map.edit();
+    let tile = LayerTileData::new(0, 3);
+    assert!(transaction.set_tile(tile_layer, 2, 1, Some(tile)).is_ok());
+    assert_eq!(
+        transaction.set_tile(tile_layer, -1, 0, Some(tile)),
+        Err(EditError::OutOfBounds {
+            layer: tile_layer,
+            x: -1,
+            y: 0
+        })
+    );
+    assert_eq!(
+        transaction.set_tile(object_layer, 0, 0, None),
+        Err(EditError::WrongLayerType(object_layer))
+    );
+    let point = transaction.add_object(object_layer, point).unwrap();
+    assert!(transaction.remove_object(object).is_ok());
+    assert_eq!(
+        transaction.remove_object(object),
+        Err(EditError::ObjectNotFound(object))
+    );
+    transaction
+        .set_property(
+            PropertyTarget::Object(point),
+            "spawn",
+            Some(PropertyValue::BoolValue(true)),
+        )
+        .unwrap();
+    transaction
+        .set_property(PropertyTarget::Layer(tile_layer), "edited", None)
+        .unwrap();
+    let record = transaction.commit();
+    assert_eq!(record.len(), 5);
+
+    let edited = map.clone();
+    let tiles = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(tiles.get_tile(2, 1).unwrap().id(), 3);
+    record.undo(&mut map).unwrap();
+    assert_eq!(map.content_hash(), original.content_hash());
+    record.redo(&mut map).unwrap();
+    assert_eq!(map, edited);
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let original = map.clone();
+    let layer = map.get_layer(0).unwrap().id();
+    let mut transaction = map.edit();
+    transaction
+        .set_tile(layer, -1000, 1000, Some(LayerTileData::new(0, 1)))
+        .unwrap();
+    assert_eq!(
+        transaction
+            .map()
+            .get_layer(0)
+            .unwrap()
+            .as_tile_layer()
+            .unwrap()
+            .get_tile(-1000, 1000)
+            .unwrap()
+            .id(),
+        1
+    );
+    transaction.rollback();
+    assert_eq!(map, original);
+}
+
+#[test]
+fn test_load_from_slice() {
+    let mut loader = Loader::new();
+    let data = std::fs::read("assets/tiled_base64_external.tmx").unwrap();
+    let map = loader
+        .load_tmx_map_from_slice(&data, "assets/downloaded.tmx")
+        .unwrap();
+    assert_eq!(map.source, PathBuf::from("assets/downloaded.tmx"));
+    assert!(map.content_eq(
+        &loader
+            .load_tmx_map("assets/tiled_base64_external.tmx")
+            .unwrap()
+    ));
+
+    // Dependencies are resolved relative to the path given
+    match Loader::new().load_tmx_map_from_slice(&data, "elsewhere/downloaded.tmx") {
+        Err(err) => match err.inner() {
+            Error::ResourceLoadingError { path, .. } => {
+                assert_eq!(path, &PathBuf::from("elsewhere/tilesheet.tsx"))
+            }
+            err => panic!("unexpected error: {}", err),
+        },
+        Ok(_) => panic!("the tileset shouldn't have been found"),
+    }
+
+    let data = std::fs::read("assets/tilesheet.tsx").unwrap();
+    let tileset = loader
+        .load_tsx_tileset_from_slice(&data, "assets/tilesheet.tsx")
+        .unwrap();
+    assert_eq!(
+        tileset,
+        loader.load_tsx_tileset("assets/tilesheet.tsx").unwrap()
+    );
+}
+
+#[test]
+fn test_chained_resource_reader() {
+    use std::io::{Cursor, ErrorKind};
+    use std::path::Path;
+
+    let modded = |path: &Path| -> std::io::Result<_> {
+        match path.to_str() {
+            Some("assets/tilesheet.tsx") => Ok(Cursor::new(
+                br#"<tileset name="modded" tilewidth="32" tileheight="32" tilecount="84" columns="14">
+                    <image source="tilesheet.png" width="448" height="192"/>
+                </tileset>"#,
+            )),
+            Some("assets/locked.tmx") => Err(ErrorKind::PermissionDenied.into()),
+            _ => Err(ErrorKind::NotFound.into()),
+        }
+    };
+    let reader = ChainedResourceReader::new()
+        .with(modded)
+        .with(FilesystemResourceReader::new());
+    assert_eq!(reader.len(), 2);
+    let mut loader = Loader::with_reader(reader);
+
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(map.tilesets()[0].name, "modded");
+
+    // The filesystem doesn't have the file either, but the first reader's error explains more
+    for (path, kind) in [
+        ("assets/locked.tmx", ErrorKind::PermissionDenied),
+        ("assets/missing.tmx", ErrorKind::NotFound),
+    ] {
+        match loader.load_tmx_map(path).unwrap_err() {
+            Error::ResourceLoadingError { err, .. } => {
+                let err = err.downcast_ref::<ChainedReadError>().unwrap();
+                assert_eq!(err.errors().len(), 2);
+                let relevant = err.most_relevant().unwrap();
+                assert_eq!(
+                    relevant.downcast_ref::<std::io::Error>().unwrap().kind(),
+                    kind
+                );
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+}
+
+#[test]
+fn test_path_policy() {
+    let mut loader = Loader::new();
+    assert!(loader
+        .load_tmx_map("assets/path_policy/root_relative.tmx")
+        .is_err());
+    let mut policy = PathPolicy::default();
+    policy.base = PathBase::AssetRoot("assets".into());
+    loader.set_path_policy(policy.clone());
+    let map = loader
+        .load_tmx_map("assets/path_policy/root_relative.tmx")
+        .unwrap();
+    assert_eq!(
+        map.tilesets()[0].source,
+        PathBuf::from("assets/tilesheet.tsx")
+    );
+
+    // Paths may not leave the asset root, or the directory of the map by default
+    policy.allow_escape = false;
+    loader.set_path_policy(policy);
+    assert!(loader
+        .load_tmx_map("assets/path_policy/root_relative.tmx")
+        .is_ok());
+    let mut policy = PathPolicy::default();
+    policy.allow_escape = false;
+    loader.set_path_policy(policy);
+    match loader
+        .load_tmx_map("assets/folder/tiled_relative_paths.tmx")
+        .unwrap_err()
+        .inner()
+    {
+        Error::PathEscapesRoot { path, root } => {
+            assert_eq!(path, &PathBuf::from("assets/folder/../tilesheet.tsx"));
+            assert_eq!(root, &PathBuf::from("assets/folder"));
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .is_ok());
+
+    // Including for maps that aren't in a directory
+    let absolute = std::fs::canonicalize("assets/tilesheet.tsx").unwrap();
+    for reference in [absolute.to_str().unwrap(), "../tilesheet.tsx"] {
+        let map = format!(
+            r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" source="{}"/>
+</map>"#,
+            reference
+        );
+        match loader
+            .load_tmx_map_from_slice(map.as_bytes(), "map.tmx")
+            .unwrap_err()
+            .inner()
+        {
+            Error::PathEscapesRoot { path, root } => {
+                assert_eq!(path, &Path::new("").join(reference));
+                assert_eq!(root, &PathBuf::new());
+            }
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    let mut loader = Loader::new();
+    assert!(loader
+        .load_tmx_map("assets/path_policy/backslashes.tmx")
+        .is_err());
+    let mut policy = PathPolicy::default();
+    policy.normalize_separators = true;
+    loader.set_path_policy(policy);
+    let map = loader
+        .load_tmx_map("assets/path_policy/backslashes.tmx")
+        .unwrap();
+    assert_eq!(
+        map.tilesets()[0].source,
+        PathBuf::from("assets/path_policy/../tilesheet.tsx")
+    );
+}
+
+#[test]
+fn test_validate() {
+    let mut loader = Loader::new();
+    for path in [
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_object_groups.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_text_object.tmx",
+    ] {
+        let map = loader.load_tmx_map(path).unwrap();
+        assert_eq!(map.validate(), vec![], "{}", path);
+    }
+
+    let mut map = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    let object = ObjectBuilder::new(ObjectShape::Rect {
+        width: 0.,
+        height: 16.,
+    })
+    .build(&mut map);
+    let object_id = object.id();
+    let layer = LayerBuilder::objects("objects", vec![object]).build(&mut map);
+    map.push_layer(layer);
+    assert_eq!(
+        map.validate(),
+        vec![ValidationIssue::ZeroSizedObject { object_id }]
+    );
+
+    // Templates are only checked when given a reader, which is the one the map was loaded with
+    let mut moved = |path: &Path| match path.strip_prefix("moved") {
+        Ok(path) => std::fs::File::open(Path::new("assets").join(path)),
+        Err(_) => std::fs::File::open(path),
+    };
+    let data = std::fs::read("assets/tiled_object_template.tmx").unwrap();
+    let map = Loader::with_reader(moved)
+        .load_tmx_map_from_slice(&data, "moved/tiled_object_template.tmx")
+        .unwrap();
+    assert_eq!(map.validate(), vec![]);
+    assert_eq!(map.validate_with_reader(&mut moved), vec![]);
+
+    // Which doesn't have them anymore
+    let mut removed = |path: &Path| match path.extension() {
+        Some(extension) if extension == "tx" => Err(std::io::ErrorKind::NotFound.into()),
+        _ => moved(path),
+    };
+    let path = PathBuf::from("moved/tiled_object_template.tx");
+    assert_eq!(
+        map.validate_with_reader(&mut removed),
+        vec![
+            ValidationIssue::MissingTemplate {
+                object_id: 1,
+                path: path.clone()
+            },
+            ValidationIssue::MissingTemplate { object_id: 3, path }
+        ]
+    );
+}
+
+#[test]
+fn test_lenient_parsing() {
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <futurefeature><layer id="9"/></futurefeature>
+ <properties>
+  <property name="shiny" type="quaternion" value="1,0,0,0"/>
+  <property name="hp" type="int" value="lots"/>
+  <property name="name" value="test"/>
+ </properties>
+ <layer id="1" name="Tile Layer 1" width="1" height="1">
+  <data encoding="csv">0</data>
+ </layer>
+</map>"#;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(MAP)) };
+
+    assert!(Loader::with_reader(reader).load_tmx_map("map.tmx").is_err());
+
+    let mut loader = Loader::with_reader(reader);
+    loader.options_mut().lenient = true;
+    let map = loader.load_tmx_map("map.tmx").unwrap();
+    assert_eq!(map.layers().len(), 1);
+    assert_eq!(
+        map.properties.get("shiny"),
+        Some(&PropertyValue::StringValue("1,0,0,0".to_owned()))
+    );
+    assert!(!map.properties.contains_key("hp"));
+    assert_eq!(
+        map.warnings()
+            .iter()
+            .map(|warning| &warning.kind)
+            .collect::<Vec<_>>(),
+        [
+            &ParseWarningKind::UnknownElement {
+                name: "futurefeature".to_owned(),
+                parent: "map".to_owned()
+            },
+            &ParseWarningKind::UnknownPropertyType {
+                name: "shiny".to_owned(),
+                type_name: "quaternion".to_owned()
+            },
+            &ParseWarningKind::InvalidPropertyValue {
+                name: "hp".to_owned(),
+                description: "invalid digit found in string".to_owned()
+            },
+        ]
+    );
+    assert!(map
+        .warnings()
+        .iter()
+        .all(|warning| warning.path == std::path::Path::new("map.tmx")));
+}
+
+#[test]
+fn test_strict_parsing() {
+    let mut loader = Loader::new();
+    loader.options_mut().strict = true;
+    loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let err = loader.load_tmx_map("assets/tiled_xml.tmx").unwrap_err();
+    assert!(matches!(err.inner(), Error::DeprecatedFeature { .. }));
+
+    let load = |map: &'static str| {
+        let reader =
+            move |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(map)) };
+        let mut loader = Loader::with_reader(reader);
+        loader.options_mut().strict = true;
+        loader.options_mut().lenient = true;
+        loader
+            .load_tmx_map("map.tmx")
+            .map(|_| ())
+            .map_err(|err| err.inner().to_string())
+    };
+    assert_eq!(
+        load(
+            r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" renderorder="right-down" glow="1"/>"#
+        ),
+        Err("Unknown attribute glow found in map".to_owned())
+    );
+    assert_eq!(
+        load(
+            r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32"><futurefeature/></map>"#
+        ),
+        Err("Unknown element futurefeature found in map".to_owned())
+    );
+    assert_eq!(
+        load(
+            r#"<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="terrains" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+  <tile id="0" terrain="0,0,0,0"/>
+ </tileset>
+</map>"#
+        ),
+        Err("Deprecated feature used: the terrain attribute of tile elements".to_owned())
+    );
+}
+
+#[test]
+fn test_error_location() {
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" source="tileset.tsx"/>
+</map>"#;
+    const TILESET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+ <image source="tiles.png" width="32" height="32"/>
+ <tile id="0" probability="very"/>
+</tileset>"#;
+    let reader = |path: &std::path::Path| -> std::io::Result<_> {
+        match path.file_name().and_then(|name| name.to_str()) {
+            Some("map.tmx") => Ok(std::io::Cursor::new(MAP)),
+            Some("tileset.tsx") => Ok(std::io::Cursor::new(TILESET)),
+            _ => Err(std::io::ErrorKind::NotFound.into()),
+        }
+    };
+
+    let err = Loader::with_reader(reader)
+        .load_tmx_map("assets/map.tmx")
+        .unwrap_err();
+    assert_eq!(
+        err.location(),
+        Some((std::path::Path::new("assets/tileset.tsx"), 4, 2))
+    );
+    assert!(matches!(err.inner(), Error::MalformedAttributes(_)));
+}
+
+#[test]
+fn test_collect_errors() {
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <layer id="1" name="broken" width="wide" height="1">
+  <data encoding="csv">0</data>
+ </layer>
+ <objectgroup id="2" name="objects">
+  <object id="1" x="left"/>
+  <object id="2" x="0" y="0"/>
+  <object id="3" rotation="up">
+   <point/>
+  </object>
+ </objectgroup>
+</map>"#;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(MAP)) };
+
+    let err = Loader::with_reader(reader)
+        .load_tmx_map("map.tmx")
+        .unwrap_err();
+    assert_eq!(err.location().map(|(_, line, _)| line), Some(3));
+
+    let mut loader = Loader::with_reader(reader);
+    loader.options_mut().collect_errors = true;
+    let errors = match loader.load_tmx_map("map.tmx") {
+        Err(Error::Multiple(errors)) => errors,
+        result => panic!("expected multiple errors, got {:?}", result),
+    };
+    assert_eq!(
+        errors
+            .iter()
+            .map(|err| err.location().unwrap().1)
+            .collect::<Vec<_>>(),
+        [3, 7, 9]
+    );
+    assert!(errors
+        .iter()
+        .all(|err| matches!(err.inner(), Error::MalformedAttributes(_))));
+}
+
+#[test]
+fn test_preserve_unknown_xml() {
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="1" height="1" tilewidth="32" tileheight="32">
+ <futuresettings>
+  <export target="out.json" format="json"/>
+ </futuresettings>
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1" editorhint="collapsed">
+  <image source="tiles.png" width="32" height="32"/>
+  <wangsets>
+   <wangset name="set" type="corner" tile="-1">
+    <wangcolor name="grass" color="#00ff00" tile="-1" probability="1"/>
+   </wangset>
+  </wangsets>
+  <tile id="0" futureattribute="1"/>
+ </tileset>
+ <objectgroup id="1" name="Objects" locked="1">
+  <object id="1" x="0" y="0" futureattribute="2">
+   <futureshape radius="3">some <b>text</b></futureshape>
+  </object>
+ </objectgroup>
+</map>"##;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(MAP)) };
+
+    let map = Loader::with_reader(reader).load_tmx_map("map.tmx").unwrap();
+    assert!(map.unknown_xml().is_empty());
+
+    let mut loader = Loader::with_reader(reader);
+    loader.options_mut().preserve_unknown = true;
+    let map = loader.load_tmx_map("map.tmx").unwrap();
+
+    assert_eq!(
+        map.unknown_xml().attribute("renderorder"),
+        Some("right-down")
+    );
+    assert_eq!(map.unknown_xml().attributes.len(), 1);
+    let future_settings = &map.unknown_xml().elements;
+    assert_eq!(future_settings.len(), 1);
+    assert_eq!(
+        future_settings[0].children,
+        [RawNode::Element(RawElement {
+            name: "export".to_owned(),
+            attributes: vec![
+                ("target".to_owned(), "out.json".to_owned()),
+                ("format".to_owned(), "json".to_owned()),
+            ],
+            children: Vec::new(),
+        })]
+    );
+
+    let tileset = &map.tilesets()[0];
+    assert_eq!(
+        tileset.unknown_xml().attribute("editorhint"),
+        Some("collapsed")
+    );
+    assert!(tileset.unknown_xml().elements.is_empty());
+    assert_eq!(tileset.wang_sets.len(), 1);
+    assert_eq!(
+        tileset
+            .get_tile(0)
+            .unwrap()
+            .unknown_xml
+            .attribute("futureattribute"),
+        Some("1")
+    );
+
+    let layer = map.get_layer(0).unwrap();
+    assert_eq!(layer.unknown_xml().attribute("locked"), Some("1"));
+    let object = layer.as_object_layer().unwrap().get_object(0).unwrap();
+    assert_eq!(object.unknown_xml().attribute("futureattribute"), Some("2"));
+    assert_eq!(
+        object.unknown_xml().elements,
+        [RawElement {
+            name: "futureshape".to_owned(),
+            attributes: vec![("radius".to_owned(), "3".to_owned())],
+            children: vec![
+                RawNode::Text("some ".to_owned()),
+                RawNode::Element(RawElement {
+                    name: "b".to_owned(),
+                    attributes: Vec::new(),
+                    children: vec![RawNode::Text("text".to_owned())],
+                }),
+            ],
+        }]
+    );
+}
+
+#[test]
+fn test_compatibility() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_csv_wangsets.tmx")
+        .unwrap();
+    assert_eq!(map.format_version(), Some(Version::new(1, 8, 0)));
+    assert_eq!(map.tiled_version(), Some(Version::new(1, 8, 5)));
+    let tileset = &map.tilesets()[0];
+    assert_eq!(tileset.format_version(), Some(Version::new(1, 8, 0)));
+    assert_eq!(tileset.tiled_version(), Some(Version::new(1, 8, 5)));
+
+    let report = map.compatibility();
+    assert_eq!(report.features(), [Feature::WangSets]);
+    assert_eq!(report.required_version(), Some(Version::new(1, 5, 0)));
+    assert!(report.is_supported_by(Version::new(1, 5, 0)));
+    assert_eq!(
+        report
+            .unsupported_by("1.4.3".parse().unwrap())
+            .collect::<Vec<_>>(),
+        [Feature::WangSets]
+    );
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert!(map
+        .compatibility()
+        .features()
+        .contains(&Feature::GroupLayers));
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_parallax.tmx")
+        .unwrap();
+    assert!(map
+        .compatibility()
+        .features()
+        .contains(&Feature::ParallaxFactors));
+
+    assert!("1".parse::<Version>().is_err());
+    assert!("1.2.3.4".parse::<Version>().is_err());
+    assert!("1.x".parse::<Version>().is_err());
+}
+
+#[cfg(feature = "snapshot")]
+#[test]
+fn test_snapshot_round_trip() {
+    use tiled::SnapshotError;
+
+    for path in [
+        "assets/tiled_base64_external.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_class_property.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        let snapshot = map.to_snapshot();
+        let loaded = Map::from_snapshot(&snapshot).unwrap();
+
+        assert_eq!(loaded.source, map.source);
+        assert_eq!(loaded.properties, map.properties);
+        assert_eq!(loaded.tilesets(), map.tilesets());
+        assert_eq!(loaded.layers().len(), map.layers().len());
+        for (loaded_layer, layer) in loaded.layers().zip(map.layers()) {
+            assert_eq!(*loaded_layer, *layer);
+        }
+    }
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let mut snapshot = map.to_snapshot();
+    assert!(matches!(
+        Map::from_snapshot(&snapshot[..3]),
+        Err(Error::InvalidSnapshot(SnapshotError::InvalidHeader))
+    ));
+    assert!(matches!(
+        Map::from_snapshot(&snapshot[..snapshot.len() / 2]),
+        Err(Error::InvalidSnapshot(SnapshotError::Decoding(_)))
+    ));
+    snapshot[4] += 1;
+    assert!(matches!(
+        Map::from_snapshot(&snapshot),
+        Err(Error::InvalidSnapshot(
+            SnapshotError::UnsupportedVersion { .. }
+        ))
+    ));
+}
+
+/// The snapshots made by the current format version must still decode to the maps they were made
+/// from, which fails if the layout of a serialized type changed without increasing the format
+/// version. After increasing it, run this test with `TILED_UPDATE_SNAPSHOTS=1` to write the
+/// snapshots of the new version next to those of the previous ones.
+#[cfg(feature = "snapshot")]
+#[test]
+fn test_snapshot_format_version() {
+    for path in [
+        "assets/tiled_base64_external.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+        "assets/tiled_object_template.tmx",
+        "assets/tiled_csv_wangsets.tmx",
+        "assets/tiled_class_property.tmx",
+        "assets/tiled_group_layers.tmx",
+        "assets/tiled_image_layers.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        let snapshot = map.to_snapshot();
+        let version = u32::from_le_bytes([snapshot[4], snapshot[5], snapshot[6], snapshot[7]]);
+        let stem = Path::new(path).file_stem().unwrap().to_str().unwrap();
+        let saved = PathBuf::from(format!("assets/snapshots/v{}/{}.snapshot", version, stem));
+        if std::env::var_os("TILED_UPDATE_SNAPSHOTS").is_some() && !saved.exists() {
+            std::fs::create_dir_all(saved.parent().unwrap()).unwrap();
+            std::fs::write(&saved, &snapshot).unwrap();
+        }
+
+        let saved = std::fs::read(&saved).unwrap_or_else(|_| {
+            panic!(
+                "no snapshot of {} for format version {}, run with TILED_UPDATE_SNAPSHOTS=1",
+                path, version
+            )
+        });
+        let loaded = Map::from_snapshot(&saved).unwrap_or_else(|err| {
+            panic!(
+                "the snapshot format changed, increase its version: {}: {}",
+                path, err
+            )
+        });
+        let message = "the snapshot format changed, increase its version";
+        assert_eq!(loaded.source, map.source, "{}", message);
+        assert_eq!(loaded.properties, map.properties, "{}", message);
+        assert_eq!(loaded.tilesets(), map.tilesets(), "{}", message);
+        assert_eq!(loaded.layers().len(), map.layers().len(), "{}", message);
+        for (loaded_layer, layer) in loaded.layers().zip(map.layers()) {
+            assert_eq!(*loaded_layer, *layer, "{}", message);
+        }
+    }
+}
+
+#[cfg(feature = "ldtk")]
+#[test]
+fn test_ldtk_export() {
+    use tiled::export::ldtk::{to_ldtk, LdtkWarning};
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let project = to_ldtk(&map);
+    assert_eq!(
+        project.warnings,
+        [LdtkWarning::LayerProperties { layer_id: 1 }]
+    );
+
+    let json = &project.json;
+    assert_eq!(json["defs"]["tilesets"].as_array().unwrap().len(), 1);
+    assert_eq!(json["defs"]["tilesets"][0]["tileGridSize"], 32);
+    let level = &json["levels"][0];
+    assert_eq!(level["pxWid"], 3200);
+    assert_eq!(level["bgColor"], "#ff00ff");
+
+    // LDtk lists layers from top to bottom
+    let layer = level["layerInstances"].as_array().unwrap().last().unwrap();
+    assert_eq!(layer["__type"], "Tiles");
+    let tile = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tile = tile.get_tile(0, 0).unwrap();
+    let grid_tile = &layer["gridTiles"][0];
+    assert_eq!(grid_tile["t"], tile.id());
+    assert_eq!(grid_tile["px"], serde_json::json!([0, 0]));
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let project = to_ldtk(&map);
+    let level = &project.json["levels"][0];
+    assert_eq!(level["pxWid"].as_u64().unwrap() % (16 * 32), 0);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    let project = to_ldtk(&map);
+    let entities = project.json["levels"][0]["layerInstances"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|layer| layer["__type"] == "Entities")
+        .unwrap()["entityInstances"]
+        .as_array()
+        .unwrap();
+    assert_eq!(entities.len(), 2);
+    assert_eq!(entities[1]["px"], serde_json::json!([32, 32]));
+    let field = &entities[0]["fieldInstances"][0];
+    assert_eq!(field["__identifier"], "object_property");
+    assert_eq!(field["__type"], "Int");
+    assert_eq!(field["__value"], 3);
+    let entity_defs = project.json["defs"]["entities"].as_array().unwrap();
+    assert_eq!(entity_defs.len(), 1);
+    assert_eq!(entity_defs[0]["fieldDefs"].as_array().unwrap().len(), 1);
+}
+
+#[cfg(feature = "godot")]
+#[test]
+fn test_godot_export() {
+    use tiled::export::godot::{to_godot, GodotOptions};
+
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let export = to_godot(&map, &GodotOptions::default());
+    assert!(export.warnings.is_empty());
+    assert!(export.tile_set.is_none());
+    assert!(export.scene.starts_with("[gd_scene load_steps=4 format=3]"));
+    assert!(export
+        .scene
+        .contains("[ext_resource type=\"Texture2D\" path=\"res://assets/tilesheet.png\""));
+    assert!(export
+        .scene
+        .contains("[node name=\"Tile Layer 1\" type=\"TileMapLayer\" parent=\".\"]"));
+    assert!(export.scene.contains("metadata/prop2 = \"some text\""));
+    assert!(export
+        .scene
+        .contains("[node name=\"Object4\" type=\"Node2D\" parent=\"Object group\"]"));
+    assert!(export.scene.contains("metadata/tiled_shape = \"polygon\""));
+
+    let mut options = GodotOptions::default();
+    options.tile_set_path = Some("res://tilesheet.tres".to_owned());
+    let export = to_godot(&map, &options);
+    let tile_set = export.tile_set.unwrap();
+    assert!(tile_set.starts_with("[gd_resource type=\"TileSet\" load_steps=3 format=3]"));
+    assert!(tile_set.contains("sources/0 = SubResource(\"TileSetAtlasSource_1\")"));
+    assert!(export
+        .scene
+        .contains("[ext_resource type=\"TileSet\" path=\"res://tilesheet.tres\""));
+    assert!(!export.scene.contains("TileSetAtlasSource"));
+}
+
+#[cfg(feature = "rapier")]
+#[test]
+fn test_rapier_colliders() {
+    use tiled::rapier::{map_to_colliders, ColliderFilter, ColliderSettings, ColliderSource};
+
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let mut settings = ColliderSettings::default();
+    settings.pixels_per_meter = 2.0;
+    let colliders = map_to_colliders(&map, &settings);
+    assert!(colliders.tile_body.is_none());
+    assert_eq!(colliders.colliders.len(), 4);
+    assert_eq!(colliders.bodies.len(), 4);
+
+    let body = colliders.object_body(1).unwrap();
+    let translation = colliders.bodies[body].translation();
+    assert_eq!((translation.x, translation.y), (7.0, 4.5));
+    let (handle, collider) = colliders
+        .colliders
+        .iter()
+        .find(|(_, collider)| collider.parent() == Some(body))
+        .unwrap();
+    assert_eq!(
+        colliders.source(handle),
+        Some(&ColliderSource::Object {
+            layer_id: 2,
+            object_id: 1
+        })
+    );
+    let cuboid = collider.shape().as_cuboid().unwrap();
+    assert_eq!(
+        (cuboid.half_extents.x, cuboid.half_extents.y),
+        (71.25, 33.75)
+    );
+
+    settings.filter = ColliderFilter::Classes(vec!["Nothing".to_owned()]);
+    let colliders = map_to_colliders(&map, &settings);
+    assert_eq!(colliders.colliders.len(), 0);
+}
+
+#[test]
+fn test_nav_grid() {
+    use tiled::{Orientation, StaggerAxis, StaggerIndex};
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib.tmx")
+        .unwrap();
+    let mut grid = map.to_nav_grid(|_| true);
+    assert_eq!((grid.width(), grid.height()), (map.width, map.height));
+    assert!(grid.is_walkable(0, 0));
+    assert!(!grid.is_walkable(-1, 0));
+
+    // A wall with a single gap at (5, 9)
+    for y in 0..9 {
+        grid.set_walkable(5, y, false);
+    }
+    let path = grid.find_path((0, 0), (10, 0)).unwrap();
+    assert_eq!(path.first(), Some(&(0, 0)));
+    assert_eq!(path.last(), Some(&(10, 0)));
+    assert!(path.contains(&(5, 9)));
+    assert_eq!(path.len(), 29);
+    grid.set_walkable(5, 9, false);
+    for y in 10..map.height as i32 {
+        grid.set_walkable(5, y, false);
+    }
+    assert_eq!(grid.find_path((0, 0), (10, 0)), None);
+
+    let grid = map.to_nav_grid(|tiles| tiles.is_empty());
+    assert!(!grid.is_walkable(0, 0));
+    assert_eq!(grid.find_path((0, 0), (1, 0)), None);
+
+    map.orientation = Orientation::Hexagonal;
+    map.stagger_axis = StaggerAxis::Y;
+    map.stagger_index = StaggerIndex::Odd;
+    let grid = map.to_nav_grid(|_| true);
+    let mut neighbors: Vec<_> = grid.neighbors(2, 2).collect();
+    neighbors.sort();
+    assert_eq!(neighbors, [(1, 1), (1, 2), (1, 3), (2, 1), (2, 3), (3, 2)]);
+    let mut neighbors: Vec<_> = grid.neighbors(2, 3).collect();
+    neighbors.sort();
+    assert_eq!(neighbors, [(1, 3), (2, 2), (2, 4), (3, 2), (3, 3), (3, 4)]);
+    assert_eq!(grid.find_path((0, 0), (3, 6)).unwrap().len(), 7);
+
+    map.orientation = Orientation::Staggered;
+    map.stagger_axis = StaggerAxis::X;
+    let grid = map.to_nav_grid(|_| true);
+    let mut neighbors: Vec<_> = grid.neighbors(3, 3).collect();
+    neighbors.sort();
+    assert_eq!(neighbors, [(2, 3), (2, 4), (4, 3), (4, 4)]);
+    // Cells of the same column only touch by their corners
+    assert_eq!(grid.find_path((3, 3), (3, 5)).unwrap().len(), 5);
+}
+
+#[test]
+fn test_nav_mesh() {
+    use tiled::NavMeshSettings;
+
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    // Both objects block a tile, leaving two tiles only touching by their corners
+    let mesh = map.to_nav_mesh(&NavMeshSettings::default());
+    assert_eq!(mesh.triangles.len(), 4);
+    assert_eq!(mesh.links.len(), 2);
+    assert_eq!(mesh.triangle_at((16.0, 16.0)), None);
+    let triangle = mesh.triangle_at((48.0, 16.0)).unwrap();
+    assert_eq!(mesh.neighbors(triangle).count(), 1);
+
+    let mut settings = NavMeshSettings::default();
+    settings.object_obstacles = false;
+    let mesh = map.to_nav_mesh(&settings);
+    assert_eq!(
+        mesh.vertices,
+        [(0.0, 0.0), (64.0, 0.0), (64.0, 64.0), (0.0, 64.0)]
+    );
+    assert_eq!(mesh.triangles.len(), 2);
+
+    let wall = ObjectBuilder::new(ObjectShape::Rect {
+        width: 32.,
+        height: 32.,
+    })
+    .user_type("wall")
+    .build(&mut map);
+    let walls = LayerBuilder::objects("walls", vec![wall]).build(&mut map);
+    map.push_layer(walls);
+    let mut settings = NavMeshSettings::default();
+    settings.resolution = 1;
+    settings.obstacle_class = Some("wall".to_owned());
+    let mesh = map.to_nav_mesh(&settings);
+    assert_eq!(mesh.triangles.len(), 4);
+    assert_eq!(mesh.links.len(), 3);
+    let link = mesh.links.last().unwrap();
+    assert_eq!(link.triangles, [3, 0]);
+    assert_eq!(link.portal, [(32.0, 32.0), (32.0, 64.0)]);
+}
+
+#[cfg(feature = "lyon")]
+#[test]
+fn test_lyon_paths() {
+    use lyon_path::{math::point, PathEvent};
+    use tiled::lyon::{object_to_path, shape_to_path};
+
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let ends = |object: usize| -> Vec<bool> {
+        object_to_path(&layer.get_object(object).unwrap())
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                PathEvent::End { close, .. } => Some(close),
+                _ => None,
+            })
+            .collect()
+    };
+    // Every shape is a single sub-path, only the polyline being open
+    assert_eq!(ends(0), [true]);
+    assert_eq!(ends(1), [true]);
+    assert_eq!(ends(2), [false]);
+    assert_eq!(ends(3), [true]);
+
+    let rect = object_to_path(&layer.get_object(0).unwrap()).unwrap();
+    let corners: Vec<_> = rect
+        .iter()
+        .filter_map(|event| match event {
+            PathEvent::Begin { at } => Some(at),
+            PathEvent::Line { to, .. } => Some(to),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(corners.len(), 4);
+    assert!(corners.contains(&point(14.0, 9.0)));
+    assert!(corners.contains(&point(299.0, 144.0)));
+
+    let ellipse = shape_to_path(&layer.get_object(1).unwrap().shape).unwrap();
+    assert!(ellipse.iter().any(|event| !matches!(
+        event,
+        PathEvent::Begin { .. } | PathEvent::Line { .. } | PathEvent::End { .. }
+    )));
+    assert!(shape_to_path(&ObjectShape::Point(1.0, 2.0)).is_none());
+    assert!(shape_to_path(&ObjectShape::Polygon { points: Vec::new() }).is_none());
+
+    // Rotated objects turn around their position
+    let mut object: tiled::ObjectData = (*layer.get_object(0).unwrap()).clone();
+    object.rotation = 90.0;
+    let rotated = object_to_path(&object).unwrap();
+    let far = rotated
+        .iter()
+        .filter_map(|event| match event {
+            PathEvent::Line { to, .. } => Some(to),
+            _ => None,
+        })
+        .find(|at| (at.x - (14.0 - 135.0)).abs() < 1e-3 && (at.y - (9.0 + 285.0)).abs() < 1e-3);
+    assert!(far.is_some());
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_spans() {
+    use std::sync::Mutex;
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    /// The name of each span created, along with the names of the fields recorded in it.
+    type Spans = Arc<Mutex<Vec<(&'static str, Vec<String>)>>>;
+
+    #[derive(Default)]
+    struct Collector(Spans);
+
+    struct Fields<'a>(&'a mut Vec<String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, _: &dyn std::fmt::Debug) {
+            self.0.push(field.name().to_owned());
+        }
+    }
+
+    impl Subscriber for Collector {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            let mut spans = self.0.lock().unwrap();
+            spans.push((span.metadata().name(), Vec::new()));
+            span::Id::from_u64(spans.len() as u64)
+        }
+        fn record(&self, span: &span::Id, values: &span::Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            values.record(&mut Fields(&mut spans[span.into_u64() as usize - 1].1));
+        }
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+        fn event(&self, _: &Event<'_>) {}
+        fn enter(&self, _: &span::Id) {}
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    let collector = Collector::default();
+    let spans = collector.0.clone();
+    tracing::subscriber::with_default(collector, || {
+        Loader::new()
+            .load_tmx_map("assets/tiled_base64_zlib.tmx")
+            .unwrap();
+    });
+
+    let spans = spans.lock().unwrap();
+    let fields = |name| {
+        spans
+            .iter()
+            .find(|(span, _)| *span == name)
+            .map(|(_, fields)| fields.clone())
+    };
+    assert_eq!(
+        fields("parse_map"),
+        Some(vec!["bytes".to_owned(), "elapsed_us".to_owned()])
+    );
+    assert_eq!(
+        fields("decode_tile_data"),
+        Some(vec![
+            "bytes".to_owned(),
+            "tiles".to_owned(),
+            "elapsed_us".to_owned()
+        ])
+    );
+}
+
+#[test]
+fn test_interned_property_names() {
+    let key = |map: &Map, index| {
+        map.get_layer(index)
+            .unwrap()
+            .properties
+            .keys()
+            .find(|key| &***key == "key")
+            .unwrap()
+            .clone()
+    };
+
+    let mut loader = Loader::new();
+    let first = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let second = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert!(Arc::ptr_eq(&key(&first, 0), &key(&first, 1)));
+    assert!(Arc::ptr_eq(&key(&first, 0), &key(&second, 2)));
+
+    let other = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert!(!Arc::ptr_eq(&key(&first, 0), &key(&other, 0)));
+}
+
+#[test]
+fn test_finite_tile_data() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_flipped.tmx")
+        .unwrap();
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let tile = layer.get_tile_data(1, 1).unwrap();
+    assert_eq!(tile, *layer.get_tile(1, 1).unwrap());
+    assert_eq!((tile.tileset_index(), tile.flip_d), (0, true));
+    assert_eq!(layer.get_tile_data(-1, 0), None);
+    assert_eq!(layer.get_tile_data(0, layer.height() as i32), None);
+
+    let layer = LayerBuilder::tiles("empty").build(&mut map);
+    let id = map.push_layer(layer);
+    let layer = map.layers().find(|layer| layer.id() == id).unwrap();
+    let layer = as_finite(layer.as_tile_layer().unwrap());
+    assert_eq!((layer.width(), layer.height()), (map.width, map.height));
+    assert_eq!(layer.get_tile(0, 0), None);
+}
+
+#[test]
+fn test_map_stats() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    let stats = map.stats();
+    assert!(stats.tile_usage.is_empty());
+    assert_eq!(stats.objects_by_class.get(""), Some(&2));
+    assert_eq!(stats.layers.len(), 2);
+    assert_eq!(stats.layers[0].cells, 4);
+    assert_eq!(stats.layers[0].empty_cell_ratio(), Some(1.0));
+    assert_eq!(stats.layers[1].objects, 2);
+    assert_eq!(stats.layers[1].empty_cell_ratio(), None);
+    assert!(stats.layers.iter().all(|layer| layer.memory > 0));
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_flipped.tmx")
+        .unwrap();
+    let stats = map.stats();
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    let tile = layer.get_tile_data(0, 0).unwrap();
+    assert_eq!(stats.tile_usage.get(&(0, tile.id())), Some(&4));
+    assert_eq!(stats.layers[0].tiles, 4);
+}
+
+#[test]
+fn test_prune_unused_tilesets() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_unused_tileset.tmx")
+        .unwrap();
+    assert_eq!(map.used_tilesets(), [1]);
+    let removed = map.prune_unused_tilesets();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].name, "unused");
+    assert_eq!(map.tilesets().len(), 1);
+    assert_eq!(map.used_tilesets(), [0]);
+    assert!(map.prune_unused_tilesets().is_empty());
+
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tile = layer.get_tile(1, 0).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id(), tile.flip_h), (0, 1, true));
+    assert_eq!(tile.get_tileset().name, "tilesheet");
+    assert_eq!(layer.get_tile(0, 0).unwrap().id(), 0);
+    let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let object_tile = objects.get_object(0).unwrap().get_tile().unwrap();
+    assert_eq!(object_tile.tileset_location(), &TilesetLocation::Map(0));
+    assert_eq!(object_tile.id(), 2);
+}
+
+#[test]
+fn test_replace_tileset() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_unused_tileset.tmx")
+        .unwrap();
+    let image = Image {
+        source: PathBuf::from("small.png"),
+        width: 64,
+        height: 32,
+        transparent_colour: None,
+    };
+    let small = Arc::new(TilesetBuilder::new("small", image, 32, 32).build().unwrap());
+
+    assert_eq!(
+        map.replace_tileset(2, small.clone()),
+        Err(ReplaceTilesetError::TilesetNotFound(2))
+    );
+    // Nothing uses the first tileset
+    assert_eq!(
+        map.replace_tileset(0, small.clone()).unwrap().name,
+        "unused"
+    );
+    // The object uses tile 2, which the small tileset doesn't have
+    assert_eq!(
+        map.replace_tileset(1, small.clone()),
+        Err(ReplaceTilesetError::MissingTile { id: 2, new_id: 2 })
+    );
+    assert_eq!(map.tilesets()[1].name, "tilesheet");
+
+    let old = map
+        .replace_tileset_remapped(1, small, |id| 1 - id % 2)
+        .unwrap();
+    assert_eq!(old.name, "tilesheet");
+    assert_eq!(map.tilesets()[1].name, "small");
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tile = layer.get_tile(0, 0).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (1, 1));
+    let tile = layer.get_tile(1, 0).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id(), tile.flip_h), (1, 0, true));
+    assert!(layer.get_tile(0, 1).is_none());
+    let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(objects.get_object(0).unwrap().get_tile().unwrap().id(), 1);
+}
+
+#[test]
+fn test_dependencies() {
+    let mut loader = Loader::new();
+    let expected = [
+        ("assets/tiled_object_template.tx", DependencyKind::Template),
+        ("assets/tilesheet.png", DependencyKind::Image),
+        ("assets/tilesheet.tsx", DependencyKind::Tileset),
+        ("assets/tilesheet_template.tsx", DependencyKind::Tileset),
+    ];
+    // The second time, the tilesets and the template come from the cache
+    for _ in 0..2 {
+        let dependencies = loader
+            .dependencies("assets/tiled_object_template.tmx")
+            .unwrap();
+        let dependencies: Vec<_> = dependencies
+            .iter()
+            .map(|dependency| (dependency.path.to_str().unwrap(), dependency.kind))
+            .collect();
+        assert_eq!(dependencies, expected);
+    }
+
+    let dependencies = loader
+        .dependencies("assets/tiled_image_layers.tmx")
+        .unwrap();
+    assert_eq!(dependencies.len(), 1);
+    assert_eq!(dependencies[0].path, PathBuf::from("assets/tilesheet.png"));
+}
+
+#[test]
+fn test_progress_callback() {
+    use std::sync::Mutex;
+
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let mut loader = Loader::new();
+    let recorded = calls.clone();
+    loader.on_resource_loaded(move |path, index, total_estimate| {
+        recorded
+            .lock()
+            .unwrap()
+            .push((path.to_owned(), index, total_estimate));
+    });
+    loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+
+    let calls = calls.lock().unwrap();
+    let map_path = PathBuf::from("assets/tiled_group_layers.tmx");
+    assert_eq!(calls.len(), 7);
+    assert_eq!(calls[0].0, PathBuf::from("assets/tilesheet.tsx"));
+    for (i, (path, index, total_estimate)) in calls.iter().enumerate() {
+        assert_eq!((*index, *total_estimate), (i, 7));
+        if i > 0 {
+            assert_eq!(path, &map_path);
+        }
+    }
+
+    loader.clear_resource_loaded();
+    loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(calls.len(), 7);
+}
+
+#[test]
+fn test_cancel_loading() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let mut loader = Loader::new();
+    loader.options_mut().collect_errors = true;
+    loader.set_cancellation_flag(cancelled.clone());
+    // Cancel once the tileset has been loaded, before the layers are
+    let flag = cancelled.clone();
+    loader.on_resource_loaded(move |_, _, _| flag.store(true, Ordering::Relaxed));
+    let result = loader.load_tmx_map("assets/tiled_group_layers.tmx");
+    assert!(matches!(result, Err(Error::Cancelled)));
+
+    cancelled.store(false, Ordering::Relaxed);
+    loader.clear_resource_loaded();
+    assert!(loader.load_tmx_map("assets/tiled_group_layers.tmx").is_ok());
+}
+
+#[test]
+fn test_loader_limits() {
+    let load = |path: &str, set_limit: fn(&mut tiled::LoaderOptions)| {
+        let mut loader = Loader::new();
+        loader.options_mut().collect_errors = true;
+        set_limit(loader.options_mut());
+        loader.load_tmx_map(path)
+    };
+
+    let err = load("assets/tiled_base64_zlib.tmx", |options| {
+        options.max_decompressed_bytes = Some(1000)
+    })
+    .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedSizeExceeded { limit: 1000 }
+    ));
+    let err = load("assets/tiled_base64.tmx", |options| {
+        options.max_map_dimensions = Some(50)
+    })
+    .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::MapTooLarge {
+            width: 100,
+            height: 100,
+            limit: 50
+        }
+    ));
+    let err = load("assets/tiled_group_layers.tmx", |options| {
+        options.max_layers = Some(2)
+    })
+    .unwrap_err();
+    assert!(matches!(err.inner(), Error::TooManyLayers { limit: 2 }));
+    let err = load("assets/tiled_object_template.tmx", |options| {
+        options.max_objects = Some(1)
+    })
+    .unwrap_err();
+    assert!(matches!(err.inner(), Error::TooManyObjects { limit: 1 }));
+
+    let map = load("assets/tiled_base64_zlib.tmx", |options| {
+        options.max_decompressed_bytes = Some(100 * 100 * 4);
+        options.max_map_dimensions = Some(100);
+        options.max_layers = Some(2);
+        // The tiles of its tileset have collision shapes
+        options.max_objects = Some(4);
+    })
+    .unwrap();
+    assert_eq!(map.layers().len(), 2);
+}
+
+#[test]
+fn test_circular_reference() {
+    let err = Loader::new()
+        .load_tsx_tileset("assets/circular/solid.tsx")
+        .unwrap_err();
+    match err.inner() {
+        Error::CircularReference { cycle } => assert_eq!(
+            cycle,
+            &[
+                PathBuf::from("assets/circular/solid.tsx"),
+                PathBuf::from("assets/circular/solid.tx"),
+                PathBuf::from("assets/circular/solid.tsx")
+            ]
+        ),
+        err => panic!("unexpected error: {}", err),
+    }
+
+    // Paths are compared once normalized
+    let err = Loader::new()
+        .load_tmx_map("assets/circular/itself.tmx")
+        .unwrap_err();
+    let itself = PathBuf::from("assets/circular/itself.tx");
+    assert!(
+        matches!(err.inner(), Error::CircularReference { cycle } if cycle == &[itself.clone(), itself])
+    );
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn test_map_watcher() {
+    use std::time::{Duration, Instant};
+    use tiled::watch::{MapWatcher, WatchEvent};
+
+    let dir = std::env::temp_dir().join(format!("tiled-watch-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("assets/tiled_base64_external.tmx", dir.join("map.tmx")).unwrap();
+    let tileset = std::fs::read_to_string("assets/tilesheet.tsx").unwrap();
+    std::fs::write(dir.join("tilesheet.tsx"), &tileset).unwrap();
+
+    let mut watcher = MapWatcher::new(dir.join("map.tmx")).unwrap();
+    assert_eq!(watcher.map().tilesets()[0].name, "tilesheet");
+    assert!(watcher.poll().is_empty());
+
+    let renamed = tileset.replace(r#"name="tilesheet""#, r#"name="renamed""#);
+    // Replace the file at once like editors do, so that it is never read half-written
+    std::fs::write(dir.join("tilesheet.tsx.tmp"), renamed).unwrap();
+    std::fs::rename(dir.join("tilesheet.tsx.tmp"), dir.join("tilesheet.tsx")).unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut events = Vec::new();
+    while events.is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+        events = watcher.poll();
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(
+        events.as_slice(),
+        [WatchEvent::TilesetChanged { index: 0 }]
+    ));
+    assert_eq!(watcher.map().tilesets()[0].name, "renamed");
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn test_map_watcher_missing_directory() {
+    use std::time::{Duration, Instant};
+    use tiled::watch::{MapWatcher, WatchError, WatchEvent};
+
+    let dir = std::env::temp_dir().join(format!("tiled-watch-missing-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::copy("assets/tiled_base64_external.tmx", dir.join("map.tmx")).unwrap();
+    let tileset = std::fs::read_to_string("assets/tilesheet.tsx").unwrap();
+    std::fs::write(dir.join("tilesheet.tsx"), &tileset).unwrap();
+    let mut watcher = MapWatcher::new(dir.join("map.tmx")).unwrap();
+
+    let poll = |watcher: &mut MapWatcher| {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut events = Vec::new();
+        while events.is_empty() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(50));
+            events = watcher.poll();
+        }
+        events
+    };
+
+    // The image is in a directory that doesn't exist yet, so it can't be watched at first
+    let moved = tileset.replace(
+        r#"source="tilesheet.png""#,
+        r#"source="images/tilesheet.png""#,
+    );
+    std::fs::write(dir.join("tilesheet.tsx.tmp"), moved).unwrap();
+    std::fs::rename(dir.join("tilesheet.tsx.tmp"), dir.join("tilesheet.tsx")).unwrap();
+    let events = poll(&mut watcher);
+
+    // Until it is created
+    std::fs::create_dir(dir.join("images")).unwrap();
+    assert!(watcher.poll().is_empty());
+    std::fs::copy("assets/tilesheet.png", dir.join("images/tilesheet.png")).unwrap();
+    let image_events = poll(&mut watcher);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(
+        events.as_slice(),
+        [
+            WatchEvent::Error(WatchError::Watching(_)),
+            WatchEvent::TilesetChanged { index: 0 }
+        ]
+    ));
+    assert!(matches!(
+        image_events.as_slice(),
+        [WatchEvent::ImageChanged(path)] if path == &dir.join("images/tilesheet.png")
+    ));
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world() {
+    use tiled::World;
+
+    let mut loader = Loader::new();
+    let world = loader.load_world("assets/world/world_basic.world").unwrap();
+    assert!(world.only_show_adjacent_maps);
+    assert_eq!(world.world_type.as_deref(), Some("world"));
+    assert!(world.patterns.is_empty());
+    assert_eq!(world.maps.len(), 2);
+    assert_eq!(world.maps[1].filename, "../tiled_base64_zlib.tmx");
+    assert_eq!((world.maps[1].x, world.maps[1].y), (3200, 0));
+    assert_eq!(world.maps[1].width, Some(3200));
+    for world_map in &world.maps {
+        loader
+            .load_tmx_map(world.directory().join(&world_map.filename))
+            .unwrap();
+    }
+
+    let world = loader
+        .load_world("assets/world/world_pattern.world")
+        .unwrap();
+    assert!(!world.only_show_adjacent_maps);
+    assert!(world.maps.is_empty());
+    let map = world.match_path("assets/world/map-x01-y03.tmx").unwrap();
+    assert_eq!(map.filename, "map-x01-y03.tmx");
+    assert_eq!(
+        (map.x, map.y, map.width, map.height),
+        (640, 1440, Some(640), Some(480))
+    );
+    let map = world.patterns[1]
+        .match_filename("overworld-x-1-y2.tmx")
+        .unwrap();
+    assert_eq!((map.x, map.y, map.width), (-6400, 6560, None));
+    // Only maps in the directory of the world match its patterns
+    assert!(world
+        .match_path("assets/world/maps/map-x01-y03.tmx")
+        .is_none());
+    assert!(world.match_path("map-x01-y03.tmx").is_none());
+    assert!(world.match_path("assets/world/map.tmx").is_none());
+
+    let maps = world.maps_with(["map-x00-y00.tmx", "readme.txt", "overworld-x0-y0.tmx"]);
+    assert_eq!(maps.len(), 2);
+    assert_eq!((maps[1].x, maps[1].y), (-3200, 160));
+
+    let err = World::parse("bad.world", br#"{"patterns": [{"regexp": "("}]}"#).unwrap_err();
+    assert!(matches!(err, Error::InvalidWorld { .. }));
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_resolve_sizes() {
+    let mut loader = Loader::new();
+    let mut world = loader.load_world("assets/world/world_sizes.world").unwrap();
+    world.resolve_sizes(&mut loader).unwrap();
+    let sizes: Vec<_> = world
+        .maps
+        .iter()
+        .map(|map| (map.width, map.height))
+        .collect();
+    assert_eq!(sizes[0], (Some(3200), Some(3200)));
+    // Infinite maps are as big as the area covered by their chunks
+    assert_eq!(sizes[1], (Some(2048), Some(2048)));
+    // Sizes given by the world are kept
+    assert_eq!(sizes[2], (Some(100), Some(72)));
+
+    world.maps[0].filename = "missing.tmx".to_owned();
+    world.maps[0].width = None;
+    assert!(world.resolve_sizes(&mut loader).is_err());
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_pattern_from_format() {
+    use tiled::WorldPattern;
+
+    let pattern = WorldPattern::from_format("maps/level[{x}]-{y}.tmx", 320, 240).unwrap();
+    let map = pattern.match_filename("maps/level[3]--2.tmx").unwrap();
+    assert_eq!(
+        (map.x, map.y, map.width, map.height),
+        (960, -480, Some(320), Some(240))
+    );
+    assert!(pattern.match_filename("maps/level[3]-2.tmx.bak").is_none());
+    assert!(pattern.match_filename("maps/levelx3]-2.tmx").is_none());
+
+    for format in [
+        "map.tmx",
+        "{x}.tmx",
+        "{y}_{x}.tmx",
+        "{x}_{y}_{x}.tmx",
+        "{x}{y}{y}",
+    ] {
+        assert!(matches!(
+            WorldPattern::from_format(format, 320, 240),
+            Err(Error::InvalidWorld { .. })
+        ));
+    }
+    assert!(WorldPattern::from_format("{x}_{y}", u32::MAX, 240).is_err());
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_discover_maps() {
+    use tiled::FilesystemResourceReader;
+
+    let mut loader = Loader::new();
+    let world = loader.load_world("assets/world/grid/grid.world").unwrap();
+    let places = |maps: &[tiled::WorldMap]| -> Vec<(String, i32, i32)> {
+        maps.iter()
+            .map(|map| (map.filename.clone(), map.x, map.y))
+            .collect()
+    };
+    let maps = world.discover_maps(loader.reader_mut()).unwrap();
+    // Listed maps aren't placed by patterns, and subdirectories aren't searched
+    assert_eq!(
+        places(&maps),
+        [
+            ("grid-1-0.tmx".to_owned(), 100, 100),
+            ("grid-0-0.tmx".to_owned(), 0, 0),
+            ("grid-0-1.tmx".to_owned(), 0, 32)
+        ]
+    );
+    assert_eq!(world.validate(), vec![]);
+    let maps = world
+        .discover_maps_recursive(&mut FilesystemResourceReader::new())
+        .unwrap();
+    assert_eq!(
+        places(&maps),
+        [
+            ("grid-1-0.tmx".to_owned(), 100, 100),
+            ("grid-0-0.tmx".to_owned(), 0, 0),
+            ("grid-0-1.tmx".to_owned(), 0, 32),
+            ("nested/grid-2-0.tmx".to_owned(), 64, 0)
+        ]
+    );
+
+    // Readers that can't list directories, even when the directory exists
+    let mut reader = |path: &std::path::Path| std::fs::File::open(path);
+    let err = world.discover_maps(&mut reader).unwrap_err();
+    assert!(matches!(err, Error::ResourceLoadingError { .. }));
+
+    // Directories listed by another reader
+    struct Listing;
+    impl tiled::ResourceReader for Listing {
+        type Resource = std::io::Empty;
+        type Error = std::io::Error;
+
+        fn read_from(&mut self, _: &std::path::Path) -> std::io::Result<std::io::Empty> {
+            Ok(std::io::empty())
+        }
+
+        fn list_directory(
+            &mut self,
+            path: &std::path::Path,
+        ) -> Option<std::io::Result<Vec<tiled::DirectoryEntry>>> {
+            Some(Ok(["grid-3-1.tmx", "notes.txt"]
+                .iter()
+                .map(|name| tiled::DirectoryEntry {
+                    path: path.join(name),
+                    is_directory: false,
+                })
+                .collect()))
+        }
+    }
+    let maps = world.discover_maps(&mut Listing).unwrap();
+    assert_eq!(
+        places(&maps),
+        [
+            ("grid-1-0.tmx".to_owned(), 100, 100),
+            ("grid-3-1.tmx".to_owned(), 96, 32)
+        ]
+    );
+
+    let mut world = world;
+    world.source = PathBuf::from("assets/world/missing/grid.world");
+    assert!(world
+        .discover_maps(&mut FilesystemResourceReader::new())
+        .is_err());
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_validate() {
+    use tiled::WorldIssue;
+
+    let mut loader = Loader::new();
+    let world = loader.load_world("assets/world/world_basic.world").unwrap();
+    assert_eq!(world.validate(), vec![]);
+    let world = loader
+        .load_world("assets/world/world_pattern.world")
+        .unwrap();
+    assert_eq!(
+        world.validate(),
+        vec![
+            WorldIssue::UnusedPattern { index: 0 },
+            WorldIssue::UnusedPattern { index: 1 }
+        ]
+    );
+
+    let world = loader
+        .load_world("assets/world/world_invalid.world")
+        .unwrap();
+    assert_eq!(
+        world.validate(),
+        vec![
+            WorldIssue::DuplicateMap {
+                filename: "../tiled_base64.tmx".to_owned()
+            },
+            WorldIssue::UnusedPattern { index: 0 },
+            WorldIssue::OverlappingMaps {
+                first: "../tiled_base64.tmx".to_owned(),
+                second: "../tiled_base64_zlib.tmx".to_owned()
+            },
+            WorldIssue::OverlappingMaps {
+                first: "../tiled_base64_zlib.tmx".to_owned(),
+                second: "../tiled_base64.tmx".to_owned()
+            },
+        ]
+    );
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_tile_at() {
+    let mut loader = Loader::new();
+    let world = loader.load_world("assets/world/world_sizes.world").unwrap();
+    let maps: Vec<_> = world
+        .maps
+        .iter()
+        .map(|map| {
+            loader
+                .load_tmx_map(world.directory().join(&map.filename))
+                .unwrap()
+        })
+        .collect();
+    let position = |x, y| {
+        world
+            .tile_at(&maps, x, y)
+            .map(|tile| (tile.map_index, tile.x, tile.y))
+    };
+
+    assert_eq!(position(0, 0), Some((0, 0, 0)));
+    assert_eq!(position(3199, 95), Some((0, 99, 2)));
+    let tile = world.tile_at(&maps, 100, 70).unwrap();
+    assert_eq!(tile.get_tile(0).unwrap().id(), 34);
+    assert_eq!(tile.tiles().count(), 1);
+    // The chunks of infinite maps start at the position of the map
+    assert_eq!(position(3200, 0), Some((1, -32, 0)));
+    assert_eq!(position(3200 + 2047, 2047), Some((1, 31, 63)));
+    assert_eq!(position(3200 + 2048, 0), None);
+    // Odd rows of this hexagonal map are shifted right by half a hexagon
+    assert_eq!(position(16, 3200 + 14), Some((2, 0, 0)));
+    assert_eq!(position(32, 3200 + 36), Some((2, 0, 1)));
+    assert_eq!(position(4, 3200 + 40), Some((2, -1, 1)));
+    assert_eq!(position(40, 3200 + 60), Some((2, 1, 2)));
+    // The width given by the world is used over the one of the map
+    assert_eq!(position(120, 3200 + 10), None);
+    assert_eq!(position(-1, 0), None);
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn test_http_resource_reader() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use tiled::{HttpError, HttpResourceReader};
+
+    // Serves the files of the assets directory, one request per connection
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = String::new();
+            BufReader::new(&stream).read_line(&mut request).unwrap();
+            let path = request.split(' ').nth(1).unwrap_or("/");
+            let response = match path.strip_prefix("/assets/") {
+                Some(path) if !path.contains("..") => {
+                    std::fs::read(format!("assets/{}", path)).ok()
+                }
+                _ => None,
+            };
+            let (status, body) = match response {
+                Some(body) => ("200 OK", body),
+                None => ("404 Not Found", Vec::new()),
+            };
+            let header = format!(
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                body.len()
+            );
+            let _ = stream.write_all(header.as_bytes());
+            let _ = stream.write_all(&body);
+        }
+    });
+
+    let reader = HttpResourceReader::new(format!("http://{}/assets", address));
+    let mut loader = Loader::with_reader(reader.clone());
+    let map = loader.load_tmx_map("tiled_base64_external.tmx").unwrap();
+    assert!(map.content_eq(
+        &Loader::new()
+            .load_tmx_map("assets/tiled_base64_external.tmx")
+            .unwrap()
+    ));
+
+    match loader.load_tmx_map("missing.tmx").unwrap_err() {
+        Error::ResourceLoadingError { err, .. } => match err.downcast_ref::<HttpError>() {
+            Some(HttpError::Status { code, .. }) => assert_eq!(*code, 404),
+            _ => panic!("unexpected error: {}", err),
+        },
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(matches!(
+        reader.url("../secret.tmx".as_ref()),
+        Err(HttpError::InvalidPath(_))
+    ));
+    assert_eq!(
+        reader.url("maps/./level 1.tmx".as_ref()).unwrap(),
+        format!("http://{}/assets/maps/level%201.tmx", address)
+    );
+}
+
+#[test]
+fn test_relocate() {
+    let mut loader = Loader::new();
+    let mut map = loader
+        .load_tmx_map("assets/folder/tiled_relative_paths.tmx")
+        .unwrap();
+    let original = map.clone();
+
+    let copies = map.relocate("export/maps/level.tmx", ResourceHandling::Reference);
+    assert!(copies.is_empty());
+    assert_eq!(map.source, PathBuf::from("export/maps/level.tmx"));
+    assert_eq!(map.tilesets()[0].source, original.tilesets()[0].source);
+    assert_eq!(
+        map.relative_reference(&map.tilesets()[0].source),
+        PathBuf::from("../../assets/tilesheet.tsx")
+    );
+
+    let mut map = original.clone();
+    let mut copies = map.relocate("export/level.tmx", ResourceHandling::Copy);
+    copies.sort_unstable_by(|a, b| a.from.cmp(&b.from));
+    assert_eq!(
+        copies
+            .iter()
+            .map(|copy| (copy.from.as_path(), copy.to.as_path(), copy.kind))
+            .collect::<Vec<_>>(),
+        [
+            (
+                "assets/tilesheet.png".as_ref(),
+                "export/tilesheet.png".as_ref(),
+                DependencyKind::Image
+            ),
+            (
+                "assets/tilesheet.tsx".as_ref(),
+                "export/tilesheet.tsx".as_ref(),
+                DependencyKind::Tileset
+            ),
+        ]
+    );
+    assert_eq!(
+        map.relative_reference(&map.tilesets()[0].source),
+        PathBuf::from("tilesheet.tsx")
+    );
+    assert_eq!(
+        map.tilesets()[0].image.as_ref().unwrap().source,
+        PathBuf::from("export/tilesheet.png")
+    );
+}
+
+#[test]
+fn test_embed_and_export_tileset() {
+    let mut loader = Loader::new();
+    let mut map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    let tileset = map.tilesets()[0].clone();
+
+    assert_eq!(map.is_tileset_embedded(0), Some(false));
+    assert_eq!(map.is_tileset_embedded(1), None);
+    assert_eq!(
+        map.export_tileset(0, "assets/other.tsx"),
+        Err(EmbedTilesetError::AlreadyExternal(0))
+    );
+
+    map.embed_tileset(0).unwrap();
+    assert_eq!(map.is_tileset_embedded(0), Some(true));
+    assert_eq!(map.tilesets()[0].source, map.source);
+    assert!(map.tilesets()[0].content_eq(&tileset));
+    assert_eq!(
+        map.embed_tileset(0),
+        Err(EmbedTilesetError::AlreadyEmbedded(0))
+    );
+    assert_eq!(
+        map.embed_tileset(1),
+        Err(EmbedTilesetError::TilesetNotFound(1))
+    );
+
+    let map_path = map.source.clone();
+    assert_eq!(
+        map.export_tileset(0, map_path.clone()),
+        Err(EmbedTilesetError::PathIsMap(map_path))
+    );
+    map.export_tileset(0, "assets/tilesheet.tsx").unwrap();
+    assert_eq!(map.is_tileset_embedded(0), Some(false));
+    assert_eq!(*map.tilesets()[0], *tileset);
+}
+
+#[test]
+fn test_tileset_storage() {
+    let mut loader = Loader::new();
+    let mut map = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+
+    assert_eq!(
+        map.set_tileset_storage(0, TilesetStorage::Embedded),
+        Ok(false)
+    );
+    assert_eq!(map.is_tileset_embedded(0), Some(true));
+    assert_eq!(
+        map.set_tileset_storage(0, TilesetStorage::Export(map.source.clone())),
+        Err(EmbedTilesetError::PathIsMap(map.source.clone()))
+    );
+    assert_eq!(
+        map.set_tileset_storage(0, TilesetStorage::Export("assets/new.tsx".into())),
+        Ok(true)
+    );
+    assert_eq!(map.tilesets()[0].source, PathBuf::from("assets/new.tsx"));
+    assert_eq!(
+        map.set_tileset_storage(0, TilesetStorage::Reference("assets/tilesheet.tsx".into())),
+        Ok(false)
+    );
+    assert_eq!(
+        map.tilesets()[0].source,
+        PathBuf::from("assets/tilesheet.tsx")
+    );
+    assert_eq!(
+        map.set_tileset_storage(0, TilesetStorage::Embedded),
+        Ok(false)
+    );
+    assert_eq!(map.is_tileset_embedded(0), Some(true));
+    assert_eq!(
+        map.set_tileset_storage(1, TilesetStorage::Embedded),
+        Err(EmbedTilesetError::TilesetNotFound(1))
+    );
+}
+
+#[test]
+fn test_reencode_layers() {
+    let mut loader = Loader::new();
+    let ldtk = loader.load_tmx_map("assets/ldk_tiled_export.tmx").unwrap();
+    assert_eq!(ldtk.compression_level, Some(0));
+    let zlib = loader.load_tmx_map("assets/tiled_base64_zlib.tmx").unwrap();
+    assert_eq!(zlib.compression_level, None);
+    let layer = as_finite(zlib.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(layer.encoding(), Encoding::Base64Zlib { level: None });
+
+    let mut map = loader.load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let original = map.clone();
+    let report = map.reencode_layers(Encoding::Base64Zstd { level: Some(19) });
+    assert_eq!(report.layers.len(), 1);
+    assert_eq!(report.layers[0].id, 1);
+    assert!(report.after() < report.before());
+    assert_eq!(map.compression_level, Some(19));
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    assert_eq!(layer.encoding(), Encoding::Base64Zstd { level: Some(19) });
+    for y in 0..layer.height() as i32 {
+        for x in 0..layer.width() as i32 {
+            let original = as_finite(original.get_layer(0).unwrap().as_tile_layer().unwrap());
+            assert_eq!(layer.get_tile_data(x, y), original.get_tile_data(x, y));
+        }
+    }
+
+    // Going back reports the sizes the other way around
+    let back = map.reencode_layers(Encoding::Csv);
+    assert_eq!(back.before(), report.after());
+    assert_eq!(back.after(), report.before());
+    assert_eq!(map.compression_level, None);
+
+    let mut infinite = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let same = infinite.reencode_layers(Encoding::Base64Zlib { level: None });
+    assert!(!same.layers.is_empty());
+    assert_eq!(same.before(), same.after());
+}
+
+#[test]
+fn test_encode_tile_data() {
+    let mut loader = Loader::new();
+    let map = loader.load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+
+    // The default format gives the text Tiled wrote
+    let file = std::fs::read_to_string("assets/tiled_csv.tmx")
+        .unwrap()
+        .replace("\r\n", "\n");
+    let start = file.find("<data encoding=\"csv\">").unwrap() + "<data encoding=\"csv\">".len();
+    let end = start + file[start..].find("</data>").unwrap();
+    assert_eq!(
+        layer.encode(&DataFormat::default()).trim(),
+        file[start..end].trim()
+    );
+
+    let mut format = DataFormat::default();
+    format.csv_tiles_per_line = Some(7);
+    let csv = layer.encode(&format);
+    assert!(csv.trim().lines().all(|line| line.split(',').count() <= 8));
+
+    let mut map = map.clone();
+    map.reencode_layers(Encoding::Base64Zlib { level: Some(9) });
+    let layer = as_finite(map.get_layer(0).unwrap().as_tile_layer().unwrap());
+    format.base64_line_length = Some(76);
+    let wrapped = layer.encode(&format);
+    assert!(wrapped.trim().lines().all(|line| line.len() <= 76));
+    assert_eq!(
+        wrapped.split_whitespace().collect::<String>(),
+        layer.encode(&DataFormat::default())
+    );
+
+    // Chunks come out in the same order whatever the order they are stored in
+    let infinite = |loader: &mut Loader| {
+        let map = loader
+            .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+            .unwrap();
+        match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+            TileLayer::Infinite(layer) => layer.encode(&DataFormat::default()),
+            TileLayer::Finite(_) => panic!("Not an infinite tile layer"),
+        }
+    };
+    let chunks = infinite(&mut loader);
+    assert!(chunks.len() > 1);
+    assert!(chunks
+        .windows(2)
+        .all(|pair| (pair[0].0 .1, pair[0].0 .0) < (pair[1].0 .1, pair[1].0 .0)));
+    assert_eq!(chunks, infinite(&mut Loader::new()));
+}
+
+#[test]
+fn test_image_layer_repeat() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap().as_image_layer().unwrap();
+    assert!(!layer.repeat_x);
+    assert!(layer.repeat_y);
+
+    // The 448x192 image is repeated vertically only
+    let positions: Vec<_> = layer
+        .draw_positions((10.0, 0.0), (0.0, -200.0, 100.0, 400.0))
+        .collect();
+    assert_eq!(
+        positions,
+        [(10.0, -384.0), (10.0, -192.0), (10.0, 0.0), (10.0, 192.0)]
+    );
+    assert_eq!(
+        layer
+            .draw_positions((10.0, 0.0), (500.0, 0.0, 100.0, 100.0))
+            .count(),
+        0
+    );
+    let empty = map.get_layer(0).unwrap().as_image_layer().unwrap();
+    assert_eq!(
+        empty
+            .draw_positions((0.0, 0.0), (0.0, 0.0, 100.0, 100.0))
+            .count(),
+        0
+    );
+
+    let image = layer.image.clone();
+    let layer = LayerBuilder::image("tiled", image)
+        .repeat(true, true)
+        .build(&mut map);
+    map.push_layer(layer);
+    let layer = map.get_layer(2).unwrap().as_image_layer().unwrap();
+    assert!(layer.repeat_x && layer.repeat_y);
+}
+
+#[test]
+fn test_flatten_layers() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let names: Vec<_> = map
+        .flatten_layers()
+        .map(|flattened| flattened.layer.name.clone())
+        .collect();
+    assert_eq!(names, ["tile-1", "tile-2", "tile-3"]);
+
+    let inner_tile = LayerBuilder::tiles("inner")
+        .offset(1., 2.)
+        .opacity(0.5)
+        .parallax(0.5, 1.)
+        .tint_color(Color {
+            alpha: 255,
+            red: 255,
+            green: 0,
+            blue: 255,
+        })
+        .build(&mut map);
+    let inner = LayerBuilder::group("inner group", vec![inner_tile])
+        .offset(10., 20.)
+        .parallax(0.5, 0.5)
+        .visible(false)
+        .build(&mut map);
+    let hidden_tile = LayerBuilder::tiles("hidden").build(&mut map);
+    let outer = LayerBuilder::group("outer group", vec![inner, hidden_tile])
+        .offset(100., 200.)
+        .opacity(0.5)
+        .tint_color(Color {
+            alpha: 255,
+            red: 255,
+            green: 255,
+            blue: 0,
+        })
+        .build(&mut map);
+    map.push_layer(outer);
+
+    let group = match map.get_layer(3).unwrap().layer_type() {
+        LayerType::Group(group) => group,
+        _ => panic!("Not a group layer"),
+    };
+    let flattened: Vec<_> = group.flatten().collect();
+    assert_eq!(flattened.len(), 2);
+    // The outer group itself isn't included
+    assert_eq!(flattened[0].layer.name, "inner");
+    assert_eq!(flattened[0].offset, (11., 22.));
+    assert_eq!(flattened[0].opacity, 0.5);
+    assert_eq!(flattened[0].parallax, (0.25, 0.5));
+    assert!(!flattened[0].visible);
+    assert_eq!(flattened[1].layer.name, "hidden");
+    assert!(flattened[1].visible);
+
+    let flattened = map.flatten_layers().last().unwrap();
+    assert_eq!(flattened.layer.name, "hidden");
+    assert_eq!(flattened.offset, (100., 200.));
+    let inner = map.flatten_layers().nth(3).unwrap();
+    assert_eq!(inner.offset, (111., 222.));
+    assert_eq!(inner.opacity, 0.25);
+    assert_eq!(
+        inner.tint_color,
+        Some(Color {
+            alpha: 255,
+            red: 255,
+            green: 0,
+            blue: 0,
+        })
+    );
+}
+
+#[test]
+fn test_layer_lookup() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(map.layer_by_id(5).unwrap().name, "tile-2");
+    assert_eq!(map.layer_by_id(8).unwrap().name, "group-3");
+    assert!(map.layer_by_id(2).is_none());
+
+    assert_eq!(map.layer_by_path("tile-1").unwrap().id(), 1);
+    assert_eq!(map.layer_by_path("group-1/tile-2").unwrap().id(), 5);
+    assert_eq!(map.layer_by_path("group-2/group-3").unwrap().id(), 8);
+    assert!(map.layer_by_path("tile-1/tile-2").is_none());
+    assert!(map.layer_by_path("group-1/").is_none());
+    assert!(map.layer_by_path("").is_none());
+
+    // Layers sharing their name with an earlier one are still reachable
+    let tile = LayerBuilder::tiles("tile-4").build(&mut map);
+    let group = LayerBuilder::group("group-1", vec![tile]).build(&mut map);
+    let id = map.push_layer(group);
+    let layer = map.layer_by_path("group-1/tile-4").unwrap();
+    assert_eq!(map.layer_by_id(id).unwrap().name, "group-1");
+    assert_eq!(layer.name, "tile-4");
+}
+
+#[test]
+fn test_layers_with_class() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(map.layers_with_class("collision").count(), 0);
+
+    let walls = LayerBuilder::tiles("walls")
+        .user_type("collision")
+        .build(&mut map);
+    let floor = LayerBuilder::tiles("floor").build(&mut map);
+    let group = LayerBuilder::group("level", vec![walls, floor])
+        .user_type("collision")
+        .build(&mut map);
+    map.push_layer(group);
+    let water = LayerBuilder::tiles("water")
+        .user_type("collision")
+        .build(&mut map);
+    map.push_layer(water);
+
+    let names: Vec<_> = map
+        .layers_with_class("collision")
+        .map(|layer| layer.name.clone())
+        .collect();
+    assert_eq!(names, ["level", "walls", "water"]);
+}
+
+#[test]
+fn test_random_tile_with_class() {
+    let image = Image {
+        source: "decoration.png".into(),
+        width: 128,
+        height: 32,
+        transparent_colour: None,
+    };
+    let tileset = TilesetBuilder::new("decoration", image, 32, 32)
+        .tile_user_type(0, "flower")
+        .tile_user_type(1, "flower")
+        .tile_probability(1, 0.)
+        .tile_user_type(2, "flower")
+        .tile_probability(2, 3.)
+        .tile_user_type(3, "rock")
+        .build()
+        .unwrap();
+
+    assert!(tileset.random_tile_with_class(|| 0.5, "tree").is_none());
+    assert_eq!(tileset.random_tile_with_class(|| 0.5, "rock").unwrap().0, 3);
+
+    // Tile 0 covers the first quarter, tile 2 the rest, tile 1 has no chance
+    let mut picks = [0; 4];
+    for step in 0..100 {
+        let (id, tile) = tileset
+            .random_tile_with_class(|| step as f32 / 100., "flower")
+            .unwrap();
+        assert_eq!(tile.user_type.as_deref(), Some("flower"));
+        picks[id as usize] += 1;
+    }
+    assert_eq!(picks, [25, 0, 75, 0]);
+    assert_eq!(
+        tileset
+            .random_tile_with_class(|| 0.999_999_9, "flower")
+            .unwrap()
+            .0,
+        2
+    );
+
+    let loaded = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap();
+    assert!(loaded.tiles().all(|(_, tile)| tile.probability == 1.));
+}
+
+#[test]
+fn test_tile_rect() {
+    let image = Image {
+        source: "spaced.png".into(),
+        width: 77,
+        height: 39,
+        transparent_colour: None,
+    };
+    let tileset = TilesetBuilder::new("spaced", image, 16, 16)
+        .margin(2)
+        .spacing(3)
+        .offset(4, -5)
+        .build()
+        .unwrap();
+    assert_eq!((tileset.columns, tileset.tilecount), (4, 8));
+    assert_eq!(
+        tileset.tile_rect(0),
+        Some(TileRect {
+            x: 2,
+            y: 2,
+            width: 16,
+            height: 16
+        })
+    );
+    // The last column of the second row
+    assert_eq!(
+        tileset.tile_rect(7),
+        Some(TileRect {
+            x: 59,
+            y: 21,
+            width: 16,
+            height: 16
+        })
+    );
+    assert_eq!(tileset.tile_rect(8), None);
+
+    // The offset only moves where tiles are drawn
+    assert_eq!(tileset.tile_draw_offset(), (4, -5));
+    assert_eq!(
+        tileset.draw_rect(7, (32., 32., 16., 16.)),
+        Some((36., 27., 16., 16.))
+    );
+}
+
+#[test]
+fn test_tile_render_size() {
+    let tileset = Loader::new()
+        .load_tsx_tileset("assets/tiled_tile_render_size.tsx")
+        .unwrap();
+    assert_eq!(tileset.tile_render_size, TileRenderSize::Grid);
+    assert_eq!(tileset.fill_mode, FillMode::PreserveAspectFit);
+    // A 32x16 tile fit into a 16x16 cell, then offset
+    assert_eq!(
+        tileset.draw_rect(0, (0., 0., 16., 16.)),
+        Some((1., 6., 16., 8.))
+    );
+    assert_eq!(tileset.draw_rect(4, (0., 0., 16., 16.)), None);
+
+    let defaults = Loader::new()
+        .load_tsx_tileset("assets/tilesheet.tsx")
+        .unwrap();
+    assert_eq!(defaults.tile_render_size, TileRenderSize::Tile);
+    assert_eq!(defaults.fill_mode, FillMode::Stretch);
+
+    let image = Image {
+        source: "wide.png".into(),
+        width: 64,
+        height: 32,
+        transparent_colour: None,
+    };
+    let builder = TilesetBuilder::new("wide", image, 32, 16);
+    let tile = builder.clone().build().unwrap();
+    assert_eq!(
+        tile.draw_rect(1, (0., 0., 16., 16.)),
+        Some((0., 0., 32., 16.))
+    );
+    let stretch = builder
+        .tile_render_size(TileRenderSize::Grid)
+        .fill_mode(FillMode::Stretch)
+        .build()
+        .unwrap();
+    assert_eq!(
+        stretch.draw_rect(1, (16., 16., 16., 16.)),
+        Some((16., 16., 16., 16.))
+    );
+}
+
+#[test]
+fn test_editor_settings() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let settings = map.editor_settings.as_ref().unwrap();
+    assert_eq!(settings.chunk_size, Some((32, 32)));
+    assert_eq!(settings.export, None);
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    assert_eq!(map.editor_settings, None);
+
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <editorsettings>
+  <export target="../export/level.json" format="json"/>
+ </editorsettings>
+</map>"##;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(MAP)) };
+    let map = Loader::with_reader(reader)
+        .load_tmx_map("maps/level.tmx")
+        .unwrap();
+    let export = map.editor_settings.unwrap().export.unwrap();
+    assert_eq!(
+        export.target,
+        Some(PathBuf::from("maps/../export/level.json"))
+    );
+    assert_eq!(export.format.as_deref(), Some("json"));
+}
+
+#[test]
+fn test_object_class() {
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <objectgroup id="1" name="Objects">
+  <object id="1" type="door" x="0" y="0"/>
+  <object id="2" class="door" x="0" y="0"/>
+  <object id="3" type="door" class="gate" x="0" y="0"/>
+  <object id="4" type="door" class="door" x="0" y="0"/>
+  <object id="5" x="0" y="0"/>
+  <object id="6" class="gate" template="door.tx"/>
+ </objectgroup>
+</map>"##;
+    const TEMPLATE: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<template>
+ <object type="door" width="32" height="32"/>
+</template>"##;
+    let reader = |path: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(if path.ends_with("door.tx") {
+            TEMPLATE
+        } else {
+            MAP
+        }))
+    };
+
+    let mut loader = Loader::with_reader(reader);
+    loader.options_mut().lenient = true;
+    let map = loader.load_tmx_map("map.tmx").unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let classes: Vec<_> = layer
+        .objects()
+        .map(|object| object.class().map(str::to_owned))
+        .collect();
+    assert_eq!(
+        classes.iter().map(Option::as_deref).collect::<Vec<_>>(),
+        [
+            Some("door"),
+            Some("door"),
+            Some("gate"),
+            Some("door"),
+            None,
+            Some("gate")
+        ]
+    );
+    assert!(layer.get_object(0).unwrap().is_class("door"));
+    assert!(!layer.get_object(4).unwrap().is_class(""));
+
+    // Only the object with two different classes is reported
+    assert_eq!(map.warnings().len(), 1);
+    assert_eq!(
+        map.warnings()[0].kind,
+        ParseWarningKind::ConflictingClass {
+            element: "object".to_owned(),
+            user_type: "door".to_owned(),
+            class: "gate".to_owned(),
+        }
+    );
+    let map = Loader::with_reader(reader).load_tmx_map("map.tmx").unwrap();
+    assert!(map.warnings().is_empty());
+
+    // The other elements with a class prefer it over their type the same way
+    const CLASSES: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32" type="level" class="area">
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1" type="level" class="area">
+  <image source="tiles.png" width="32" height="32"/>
+  <tile id="0" type="level" class="area"/>
+ </tileset>
+ <objectgroup id="1" name="Objects" type="level" class="area"/>
+</map>"##;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(CLASSES)) };
+    let mut loader = Loader::with_reader(reader);
+    loader.options_mut().lenient = true;
+    let map = loader.load_tmx_map("map.tmx").unwrap();
+    assert_eq!(map.user_type.as_deref(), Some("area"));
+    assert_eq!(map.tilesets()[0].user_type.as_deref(), Some("area"));
+    assert_eq!(
+        map.tilesets()[0].get_tile(0).unwrap().user_type.as_deref(),
+        Some("area")
+    );
+    assert_eq!(map.get_layer(0).unwrap().user_type.as_deref(), Some("area"));
+    let elements: Vec<_> = map
+        .warnings()
+        .iter()
+        .map(|warning| match &warning.kind {
+            ParseWarningKind::ConflictingClass { element, .. } => element.as_str(),
+            kind => panic!("unexpected warning: {}", kind),
+        })
+        .collect();
+    assert_eq!(elements, ["map", "tileset", "tile", "objectgroup"]);
+}
+
+#[test]
+fn test_layout_text() {
+    let text = |halign, valign, wrap, text: &str| ObjectShape::Text {
+        font_family: "sans-serif".to_owned(),
+        pixel_size: 16,
+        wrap,
+        color: Color {
+            alpha: 255,
+            red: 0,
+            green: 0,
+            blue: 0,
+        },
+        bold: false,
+        italic: false,
+        underline: false,
+        strikeout: false,
+        kerning: true,
+        halign,
+        valign,
+        text: text.to_owned(),
+        width: 100.,
+        height: 60.,
+    };
+    // Every character is 10 pixels wide
+    let measure = |text: &str| text.chars().count() as f32 * 10.;
+    let runs = |shape: ObjectShape| -> Vec<(String, f32, f32)> {
+        shape
+            .layout_text(20., measure)
+            .unwrap()
+            .into_iter()
+            .map(|run| (run.text, run.x, run.y))
+            .collect()
+    };
+
+    assert_eq!(
+        runs(text(
+            HorizontalAlignment::Left,
+            VerticalAlignment::Top,
+            false,
+            "a long line of text\nb"
+        )),
+        [
+            ("a long line of text".to_owned(), 0., 0.),
+            ("b".to_owned(), 0., 20.)
+        ]
+    );
+    assert_eq!(
+        runs(text(
+            HorizontalAlignment::Center,
+            VerticalAlignment::Center,
+            true,
+            "one two three"
+        )),
+        [
+            ("one two".to_owned(), 15., 10.),
+            ("three".to_owned(), 25., 30.)
+        ]
+    );
+    // Words longer than the object overflow it on a line of their own
+    assert_eq!(
+        runs(text(
+            HorizontalAlignment::Right,
+            VerticalAlignment::Bottom,
+            true,
+            "a extraordinary b"
+        )),
+        [
+            ("a".to_owned(), 90., 0.),
+            ("extraordinary".to_owned(), -30., 20.),
+            ("b".to_owned(), 90., 40.)
+        ]
+    );
+    assert_eq!(
+        runs(text(
+            HorizontalAlignment::Justify,
+            VerticalAlignment::Top,
+            true,
+            "ab cd ef gh"
+        )),
+        [
+            ("ab".to_owned(), 0., 0.),
+            ("cd".to_owned(), 40., 0.),
+            ("ef".to_owned(), 80., 0.),
+            ("gh".to_owned(), 0., 20.)
+        ]
+    );
+    assert!(ObjectShape::Point(0., 0.)
+        .layout_text(20., measure)
+        .is_none());
+}
+
+#[test]
+fn test_object_transform() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+
+    // Tile objects extend upwards from their position
+    let tile = layer.get_object(1).unwrap();
+    assert_eq!(
+        tile.world_corners(),
+        Some([(0., 0.), (32., 0.), (32., 32.), (0., 32.)])
+    );
+
+    // And turn around their bottom left corner
+    let mut rotated: tiled::ObjectData = (*tile).clone();
+    rotated.rotation = 90.;
+    let corners = rotated.world_corners().unwrap();
+    let expected = [(32., 32.), (32., 64.), (0., 64.), (0., 32.)];
+    for (corner, expected) in corners.iter().zip(expected) {
+        assert!((corner.0 - expected.0).abs() < 1e-4 && (corner.1 - expected.1).abs() < 1e-4);
+    }
+
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let rect = layer.get_object(0).unwrap();
+    assert_eq!(rect.transform().to_array(), [1., 0., 0., 1., 14., 9.]);
+    assert_eq!(rect.world_corners().unwrap()[2], (299., 144.));
+    // Polygons are bounded by their points
+    let polygon = layer.get_object(3).unwrap();
+    assert_eq!(
+        polygon.world_corners(),
+        Some([(424., 35.), (638., 35.), (638., 212.), (424., 212.)])
+    );
+
+    let transform = rect.transform().then(&ObjectTransform::IDENTITY);
+    assert_eq!(transform, rect.transform());
+}
+
+#[test]
+fn test_shape_sampling() {
+    let map = Loader::new().load_tmx_map("assets/tiled_csv.tmx").unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let polyline = &layer.get_object(2).unwrap().shape;
+    assert_eq!(polyline.segments().unwrap().count(), 5);
+    assert_eq!(
+        polyline.segments().unwrap().next(),
+        Some(((0., 0.), (-111., -63.)))
+    );
+    assert_eq!(polyline.point_at_distance(-1.), Some((0., 0.)));
+    assert_eq!(polyline.point_at_distance(1e6), Some((-6., -6.)));
+    assert!(layer.get_object(0).unwrap().shape.length().is_none());
+
+    let square = ObjectShape::Polygon {
+        points: vec![(0., 0.), (4., 0.), (4., 4.), (0., 4.)],
+    };
+    let segments: Vec<_> = square.segments().unwrap().collect();
+    assert_eq!(segments.len(), 4);
+    assert_eq!(segments[3], ((0., 4.), (0., 0.)));
+    assert_eq!(square.length(), Some(16.));
+    assert_eq!(square.point_at_distance(6.), Some((4., 2.)));
+    // Polygons are loops
+    assert_eq!(square.point_at_distance(18.), Some((2., 0.)));
+    assert_eq!(square.point_at_distance(-2.), Some((0., 2.)));
+
+    let point = ObjectShape::Polyline {
+        points: vec![(3., 3.)],
+    };
+    assert_eq!(point.length(), Some(0.));
+    assert_eq!(point.point_at_distance(5.), Some((3., 3.)));
+    assert_eq!(
+        ObjectShape::Polygon { points: Vec::new() }.point_at_distance(0.),
+        None
+    );
+}
+
+#[test]
+fn test_objects_in_draw_order() {
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <objectgroup id="1" name="Sorted">
+  <object id="1" x="0" y="20"/>
+  <object id="2" x="0" y="10"/>
+  <object id="3" x="5" y="20"/>
+  <object id="4" x="0" y="-5"/>
+ </objectgroup>
+ <objectgroup id="2" name="Unsorted" draworder="index">
+  <object id="5" x="0" y="20"/>
+  <object id="6" x="0" y="10"/>
+ </objectgroup>
+</map>"##;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(MAP)) };
+    let map = Loader::with_reader(reader).load_tmx_map("map.tmx").unwrap();
+    let ids = |index: usize| -> Vec<u32> {
+        map.get_layer(index)
+            .unwrap()
+            .as_object_layer()
+            .unwrap()
+            .objects_in_draw_order()
+            .iter()
+            .map(|object| object.id())
+            .collect()
+    };
+
+    let sorted = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    assert_eq!(sorted.draw_order, DrawOrder::TopDown);
+    // Objects at the same height keep their order
+    assert_eq!(ids(0), [4, 2, 1, 3]);
+    let unsorted = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(unsorted.draw_order, DrawOrder::Index);
+    assert_eq!(ids(1), [5, 6]);
+
+    const INVALID: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <objectgroup id="1" name="Objects" draworder="random"/>
+</map>"##;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(INVALID)) };
+    let err = Loader::with_reader(reader)
+        .load_tmx_map("map.tmx")
+        .unwrap_err();
+    assert!(matches!(err.inner(), Error::MalformedAttributes(_)));
+}
+
+#[test]
+fn test_resolve_object_property() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_property.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let first = layer.get_object(0).unwrap();
+    let second = layer.get_object(1).unwrap();
+
+    let target = map
+        .resolve_object_property(&first.properties["object property"])
+        .unwrap();
+    assert_eq!(target.id(), second.id());
+    assert_eq!((target.x, target.y), (32., 32.));
+    // Unset object properties refer to no object
+    assert!(map
+        .resolve_object_property(&second.properties["object property"])
+        .is_none());
+    assert!(map
+        .resolve_object_property(&PropertyValue::ObjectValue(42))
+        .is_none());
+    assert!(map
+        .resolve_object_property(&PropertyValue::IntValue(3))
+        .is_none());
+    assert_eq!(map.object_by_id(2).unwrap().id(), 2);
+
+    #[cfg(feature = "world")]
+    {
+        let world = tiled::World::parse(
+            "assets/world/objects.world",
+            br#"{"maps": [{"fileName": "../tiled_object_property.tmx", "x": 100, "y": 200}]}"#,
+        )
+        .unwrap();
+        let maps = [map.clone()];
+        let target = world
+            .resolve_object_property(&maps, 0, &first.properties["object property"])
+            .unwrap();
+        assert_eq!(
+            (target.map_index, target.object.id(), target.x, target.y),
+            (0, 3, 132., 232.)
+        );
+        assert!(world
+            .resolve_object_property(&maps, 1, &first.properties["object property"])
+            .is_none());
+    }
+}
+
+#[test]
+fn test_resolve_file_property() {
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <properties>
+  <property name="music" type="file" value="../music/theme.ogg"/>
+  <property name="unset" type="file" value=""/>
+ </properties>
+ <tileset firstgid="1" source="../tilesets/tiles.tsx"/>
+</map>"##;
+    const TILESET: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<tileset version="1.10" name="tiles" tilewidth="32" tileheight="32" tilecount="1" columns="1">
+ <image source="tiles.png" width="32" height="32"/>
+ <tile id="0">
+  <properties>
+   <property name="sound" type="file" value="sounds/step.ogg"/>
+  </properties>
+ </tile>
+</tileset>"##;
+    let reader = |path: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(if path.ends_with("tiles.tsx") {
+            TILESET
+        } else {
+            MAP
+        }))
+    };
+    let map = Loader::with_reader(reader)
+        .load_tmx_map("assets/maps/level.tmx")
+        .unwrap();
+
+    assert_eq!(
+        map.resolve_file_property(&map.properties["music"]),
+        Some(PathBuf::from("assets/maps/../music/theme.ogg"))
+    );
+    assert_eq!(map.resolve_file_property(&map.properties["unset"]), None);
+    assert_eq!(
+        map.resolve_file_property(&PropertyValue::StringValue("music.ogg".to_owned())),
+        None
+    );
+
+    let tileset = &map.tilesets()[0];
+    let tile = tileset.get_tile(0).unwrap();
+    assert_eq!(
+        tileset.resolve_file_property(&tile.properties["sound"]),
+        Some(PathBuf::from("assets/maps/../tilesets/sounds/step.ogg"))
+    );
+}
+
+#[test]
+fn test_color_conversions() {
+    let color: Color = "#40102030".parse().unwrap();
+    assert_eq!(color.to_argb_u32(), 0x40102030);
+    assert_eq!(color.to_rgba_u32(), 0x10203040);
+    assert_eq!(Color::from_argb_u32(0x40102030), color);
+    assert_eq!(Color::from_rgba_u32(0x10203040), color);
+    let [red, green, blue, alpha]: [f32; 4] = color.into();
+    assert_eq!(
+        (red, green, blue, alpha),
+        (16. / 255., 32. / 255., 48. / 255., 64. / 255.)
+    );
+
+    // Colors without alpha are opaque
+    let opaque: Color = "#ffffff".parse().unwrap();
+    assert_eq!(opaque.to_rgba_u32(), u32::MAX);
+    assert_eq!(opaque.to_rgba_f32(), [1.; 4]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_deserialize_properties() {
+    use serde::Deserialize;
+    use tiled::DeserializeProperties;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    enum Kind {
+        Walker,
+        Flyer,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Stats {
+        health: u16,
+        #[serde(default)]
+        armor: f64,
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Spawn<'a> {
+        name: &'a str,
+        kind: Kind,
+        layer_kind: Kind,
+        speed: f32,
+        tint: u32,
+        target: Option<u32>,
+        owner: Option<u32>,
+        stats: Stats,
+        missing: Option<bool>,
+    }
+
+    let reader = |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+ <properties>
+  <property name="name" value="goblin"/>
+  <property name="kind" propertytype="Kind" value="Flyer"/>
+  <property name="layer_kind" type="int" propertytype="Kind" value="0"/>
+  <property name="speed" type="int" value="2"/>
+  <property name="tint" type="color" value="#80ff0000"/>
+  <property name="target" type="object" value="0"/>
+  <property name="owner" type="object" value="7"/>
+  <property name="stats" type="class" propertytype="Stats">
+   <properties>
+    <property name="health" type="int" value="30"/>
+   </properties>
+  </property>
+  <property name="unused" type="bool" value="true"/>
+ </properties>
+</map>"##,
+        ))
+    };
+    let map = Loader::with_reader(reader)
+        .load_tmx_map("spawn.tmx")
+        .unwrap();
+
+    let spawn: Spawn = map.properties.deserialize().unwrap();
+    assert_eq!(
+        spawn,
+        Spawn {
+            name: "goblin",
+            kind: Kind::Flyer,
+            layer_kind: Kind::Walker,
+            speed: 2.,
+            tint: 0x80ff0000,
+            target: None,
+            owner: Some(7),
+            stats: Stats {
+                health: 30,
+                armor: 0.
+            },
+            missing: None,
+        }
+    );
+
+    // Errors name the property they come from
+    #[derive(Debug, Deserialize)]
+    struct Wrong {
+        #[allow(dead_code)]
+        stats: std::collections::HashMap<String, bool>,
+    }
+    match map.properties.deserialize::<Wrong>() {
+        Err(Error::InvalidPropertyValue { description }) => {
+            assert!(description.starts_with("property `stats`: property `health`: "))
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_properties_preserve_order() {
+    let reader = |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(
+            r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="1" height="1" tilewidth="32" tileheight="32" infinite="0">
+ <properties>
+  <property name="zeta" value="1"/>
+  <property name="alpha" value="2"/>
+  <property name="nested" type="class" propertytype="Nested">
+   <properties>
+    <property name="second" type="int" value="2"/>
+    <property name="first" type="int" value="1"/>
+   </properties>
+  </property>
+  <property name="mu" value="3"/>
+ </properties>
+</map>"##,
+        ))
+    };
+    let mut map = Loader::with_reader(reader)
+        .load_tmx_map("ordered.tmx")
+        .unwrap();
+
+    let names: Vec<&str> = map.properties.keys().map(|name| &**name).collect();
+    assert_eq!(names, ["zeta", "alpha", "nested", "mu"]);
+    match &map.properties["nested"] {
+        PropertyValue::ClassValue { properties, .. } => {
+            let names: Vec<&str> = properties.keys().map(|name| &**name).collect();
+            assert_eq!(names, ["second", "first"]);
+        }
+        other => panic!("unexpected property value: {:?}", other),
+    }
+
+    // New properties are added last, and removing one keeps the order of the others
+    map.properties
+        .insert("beta".into(), PropertyValue::BoolValue(true));
+    map.properties.shift_remove("alpha");
+    let names: Vec<&str> = map.properties.keys().map(|name| &**name).collect();
+    assert_eq!(names, ["zeta", "nested", "mu", "beta"]);
+}
+
+#[test]
+fn test_properties_diff_and_merge() {
+    let class = |members: &[(&str, PropertyValue)]| PropertyValue::ClassValue {
+        property_type: "Stats".to_owned(),
+        properties: members
+            .iter()
+            .map(|(name, value)| (Arc::from(*name), value.clone()))
+            .collect(),
+    };
+    let mut base = Properties::new();
+    base.insert(
+        "name".into(),
+        PropertyValue::StringValue("goblin".to_owned()),
+    );
+    base.insert("speed".into(), PropertyValue::FloatValue(1.));
+    base.insert(
+        "stats".into(),
+        class(&[
+            ("health", PropertyValue::IntValue(10)),
+            ("armor", PropertyValue::IntValue(2)),
+        ]),
+    );
+    let mut overrides = Properties::new();
+    overrides.insert("speed".into(), PropertyValue::IntValue(2));
+    overrides.insert("flying".into(), PropertyValue::BoolValue(true));
+    overrides.insert(
+        "stats".into(),
+        class(&[("health", PropertyValue::IntValue(30))]),
+    );
+
+    let changes: Vec<_> = base
+        .diff(&overrides)
+        .iter()
+        .map(|change| match change {
+            PropertyChange::Added { name, .. } => format!("+{}", name),
+            PropertyChange::Removed { name, .. } => format!("-{}", name),
+            PropertyChange::Changed { name, .. } => format!("~{}", name),
+        })
+        .collect();
+    assert_eq!(changes, ["+flying", "-name", "~speed", "~stats"]);
+    assert!(base.diff(&base.clone()).is_empty());
+
+    let mut merged = base.clone();
+    let conflicts = merged.merge(&overrides);
+    assert_eq!(
+        conflicts,
+        [PropertyConflict {
+            path: vec!["speed".into()],
+            replaced: PropertyValue::FloatValue(1.),
+        }]
+    );
+    assert_eq!(merged["name"], base["name"]);
+    assert_eq!(merged["speed"], PropertyValue::IntValue(2));
+    assert_eq!(merged["flying"], PropertyValue::BoolValue(true));
+    assert_eq!(
+        merged["stats"],
+        class(&[
+            ("health", PropertyValue::IntValue(30)),
+            ("armor", PropertyValue::IntValue(2)),
+        ])
+    );
+
+    // Objects override the members of class properties of their template individually
+    const MAP: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <objectgroup id="1" name="Objects">
+  <object id="1" template="goblin.tx">
+   <properties>
+    <property name="stats" type="class" propertytype="Stats">
+     <properties>
+      <property name="health" type="int" value="30"/>
+     </properties>
+    </property>
+   </properties>
+  </object>
+ </objectgroup>
+</map>"##;
+    const TEMPLATE: &str = r##"<?xml version="1.0" encoding="UTF-8"?>
+<template>
+ <object width="32" height="32">
+  <properties>
+   <property name="stats" type="class" propertytype="Stats">
+    <properties>
+     <property name="armor" type="int" value="2"/>
+     <property name="health" type="int" value="10"/>
+    </properties>
+   </property>
+  </properties>
+ </object>
+</template>"##;
+    let reader = |path: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(if path.ends_with("goblin.tx") {
+            TEMPLATE
+        } else {
+            MAP
+        }))
+    };
+    let map = Loader::with_reader(reader).load_tmx_map("map.tmx").unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    assert_eq!(
+        layer.get_object(0).unwrap().properties["stats"],
+        merged["stats"]
+    );
+}
+
+#[cfg(feature = "project")]
+#[test]
+fn test_apply_project_defaults() {
+    use tiled::{EnumStorageType, Project, PropertyType};
+
+    let mut loader = Loader::new();
+    let project = loader
+        .load_project("assets/project/project.tiled-project")
+        .unwrap();
+    assert_eq!(project.property_types.len(), 4);
+    match project.property_type("Kind") {
+        Some(PropertyType::Enum(kind)) => {
+            assert_eq!(kind.values, ["Walker", "Flyer"]);
+            assert_eq!(kind.storage_type, EnumStorageType::String);
+        }
+        other => panic!("unexpected property type: {:?}", other),
+    }
+    let spawn = project.class("Spawn").unwrap();
+    assert!(spawn.is_usable_as("tile") && !spawn.is_usable_as("layer"));
+    assert_eq!(
+        spawn.member("kind").unwrap().property_type.as_deref(),
+        Some("Kind")
+    );
+
+    let stats = |armor: f32, health: i32| PropertyValue::ClassValue {
+        property_type: "Stats".to_owned(),
+        properties: vec![
+            (Arc::from("armor"), PropertyValue::FloatValue(armor)),
+            (Arc::from("health"), PropertyValue::IntValue(health)),
+        ]
+        .into_iter()
+        .collect(),
+    };
+
+    let mut map = loader.load_tmx_map("assets/project/spawns.tmx").unwrap();
+    map.apply_project_defaults(&project);
+    let layer = map.get_layer(0).unwrap();
+    assert!(layer.properties.is_empty());
+    let layer = layer.as_object_layer().unwrap();
+
+    let first = layer.get_object(0).unwrap();
+    assert_eq!(first.properties.len(), 7);
+    assert_eq!(
+        first.properties["kind"],
+        PropertyValue::StringValue("Flyer".to_owned())
+    );
+    assert_eq!(first.properties["count"], PropertyValue::IntValue(1));
+    // Class members take the default of the class containing them before the one of their own
+    assert_eq!(first.properties["stats"], stats(0.5, 50));
+    assert_eq!(first.properties["target"], PropertyValue::ObjectValue(0));
+    assert_eq!(
+        first.properties["tint"],
+        PropertyValue::ColorValue(Color::from_argb_u32(0))
+    );
+
+    let second = layer.get_object(1).unwrap();
+    assert_eq!(second.properties["count"], PropertyValue::IntValue(3));
+    assert_eq!(second.properties["stats"], stats(0., 50));
+    assert!(layer.get_object(2).unwrap().properties.is_empty());
+
+    match Project::parse(
+        "broken.tiled-project",
+        br#"{"propertyTypes": [{"name": "A"}]}"#,
+    ) {
+        Err(Error::InvalidProject { .. }) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[cfg(feature = "project")]
+#[test]
+fn test_codegen() {
+    mod generated {
+        include!("../assets/project/project.rs");
+    }
+    use generated::{Kind, Layers, Spawn, Stats};
+    use tiled::FromProperties;
+
+    let mut loader = Loader::new();
+    let project = loader
+        .load_project("assets/project/project.tiled-project")
+        .unwrap();
+    assert_eq!(
+        tiled::codegen::generate_rust(&project),
+        include_str!("../assets/project/project.rs")
+    );
+
+    let map = loader.load_tmx_map("assets/project/spawns.tmx").unwrap();
+    let layer = map.get_layer(0).unwrap().as_object_layer().unwrap();
+    let first = Spawn::from_properties(&layer.get_object(0).unwrap().properties).unwrap();
+    assert_eq!(
+        first,
+        Spawn {
+            kind: Kind::Flyer,
+            stats: Stats {
+                armor: 0.5,
+                health: 50
+            },
+            ..Spawn::default()
+        }
+    );
+    assert!(first.layers.contains(Layers::GROUND) && !first.layers.contains(Layers::WATER));
+    let second = Spawn::from_properties(&layer.get_object(1).unwrap().properties).unwrap();
+    assert_eq!(second.count, 3);
+    assert_eq!(second.stats.health, 50);
+
+    let mut properties = tiled::Properties::new();
+    properties.insert(
+        "kind".into(),
+        PropertyValue::StringValue("Swimmer".to_owned()),
+    );
+    match Spawn::from_properties(&properties) {
+        Err(Error::InvalidPropertyValue { description }) => assert_eq!(
+            description,
+            "property `kind`: unknown value `Swimmer` of enum `Kind`"
+        ),
+        other => panic!("unexpected result: {:?}", other),
+    }
+}
+
+#[test]
+fn test_tile_for_gid() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_unused_tileset.tmx")
+        .unwrap();
+    assert!(map.tile_for_gid(0).is_none());
+    let tile = map.tile_for_gid(1).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (0, 0));
+    let tile = map.tile_for_gid(0x4000_0000 | 88).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id(), tile.flip_v), (1, 3, true));
+    assert!(map.tile_for_gid(85 + 84).is_none());
+
+    // Without the unused tileset, the tilesheet starts at GID 1
+    map.prune_unused_tilesets();
+    let tile = map.tile_for_gid(4).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (0, 3));
+    assert!(map.tile_for_gid(88).is_none());
+}
+
+#[test]
+fn test_tile_for_gid_large_gids() {
+    // Neither the first GIDs nor the tile IDs make the lookup allocate per GID
+    let map = Loader::new()
+        .load_tmx_map_from_slice(
+            br#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="images" tilewidth="32" tileheight="32" tilecount="1" columns="0">
+  <tile id="4294967295">
+   <image source="tile.png" width="32" height="32"/>
+  </tile>
+ </tileset>
+ <tileset firstgid="200000000" name="tiles" tilewidth="32" tileheight="32" tilecount="8" columns="8">
+  <image source="tiles.png" width="256" height="32"/>
+ </tileset>
+ <layer id="1" name="Tile Layer 1" width="1" height="1">
+  <data encoding="csv">200000003</data>
+ </layer>
+</map>"#,
+            "assets/large_gids.tmx",
+        )
+        .unwrap();
+    let tile = map.tile_for_gid(200_000_003).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (1, 3));
+    // The tiles of the image collection reach past the first GID of the next tileset, which
+    // takes precedence over them
+    let tile = map.tile_for_gid(199_999_999).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (0, 199_999_998));
+    let tile = map.tile_for_gid(200_000_008).unwrap();
+    assert_eq!((tile.tileset_index(), tile.id()), (0, 200_000_007));
+    assert!(map.tile_for_gid(0).is_none());
+}
+
+#[test]
+fn test_tile_data_decoding() {
+    fn map_with_data(width: usize, data: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="{w}" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="8" columns="8">
+  <image source="tiles.png" width="256" height="32"/>
+ </tileset>
+ <layer id="1" name="Tile Layer 1" width="{w}" height="1">
+  {data}
+ </layer>
+</map>"#,
+            w = width,
+            data = data
+        )
+    }
+    let reader = |path: &std::path::Path| -> std::io::Result<_> {
+        let map = match path.to_str().unwrap() {
+            "csv.tmx" => map_with_data(
+                5,
+                "<data encoding=\"csv\">\n1,2147483650,\n3, 4 ,5\n</data>",
+            ),
+            "base64.tmx" => map_with_data(
+                5,
+                "<data encoding=\"base64\">\n   AQAAAAIAAIADAAAABAAAAAUAAAA=\n  </data>",
+            ),
+            "base64_four.tmx" => map_with_data(
+                4,
+                "<data encoding=\"base64\">AQAAAAIAAAADAAAABAAAAA==</data>",
+            ),
+            "bad_csv.tmx" => map_with_data(5, "<data encoding=\"csv\">1,2,,4,5</data>"),
+            "bad_base64.tmx" => map_with_data(
+                4,
+                "<data encoding=\"base64\">AQAAAAIAAAADAAAABAAAAA=</data>",
+            ),
+            _ => return Err(std::io::ErrorKind::NotFound.into()),
+        };
+        Ok(std::io::Cursor::new(map))
+    };
+    let mut loader = Loader::with_reader(reader);
+
+    let tiles = |map: &Map| {
+        let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+        (0..layer.width().unwrap() as i32)
+            .map(|x| {
+                let tile = layer.get_tile(x, 0).unwrap();
+                (tile.id(), tile.flip_h)
+            })
+            .collect::<Vec<_>>()
+    };
+    let csv = loader.load_tmx_map("csv.tmx").unwrap();
+    let expected = vec![(0, false), (1, true), (2, false), (3, false), (4, false)];
+    assert_eq!(tiles(&csv), expected);
+    let base64 = loader.load_tmx_map("base64.tmx").unwrap();
+    assert_eq!(tiles(&base64), expected);
+    let base64 = loader.load_tmx_map("base64_four.tmx").unwrap();
+    assert_eq!(
+        tiles(&base64),
+        vec![(0, false), (1, false), (2, false), (3, false)]
+    );
+
+    let err = loader.load_tmx_map("bad_csv.tmx").unwrap_err();
+    assert!(matches!(err.inner(), Error::CsvDecodingError(_)));
+    let err = loader.load_tmx_map("bad_base64.tmx").unwrap_err();
+    assert!(matches!(err.inner(), Error::Base64DecodingError(_)));
+}
+
+#[test]
+fn test_chunk_decoding_order() {
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="32" tileheight="32" infinite="1">
+ <layer id="1" name="Tile Layer 1" width="2" height="1">
+  <data encoding="csv">
+   <chunk x="0" y="0" width="2" height="1">0,0</chunk>
+   <chunk x="16" y="0" width="2" height="1">0</chunk>
+   <chunk x="32" y="0" width="2" height="1">0</chunk>
+  </data>
+ </layer>
+</map>"#;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(MAP)) };
+
+    let err = Loader::with_reader(reader)
+        .load_tmx_map("map.tmx")
+        .unwrap_err();
+    assert!(matches!(err.inner(), Error::InvalidTileFound));
+
+    // Chunks are added in the order of the file, even when decoded in parallel
+    let mut loader = Loader::with_reader(reader);
+    loader.options_mut().lenient = true;
+    let map = loader.load_tmx_map("map.tmx").unwrap();
+    assert_eq!(
+        map.warnings()
+            .iter()
+            .map(|warning| &warning.kind)
+            .collect::<Vec<_>>(),
+        [
+            &ParseWarningKind::IncompleteChunk { x: 16, y: 0 },
+            &ParseWarningKind::IncompleteChunk { x: 32, y: 0 }
+        ]
+    );
+
+    let mut loader = Loader::new();
+    loader.options_mut().max_decompressed_bytes = Some(1000);
+    let err = loader
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedSizeExceeded { limit: 1000 }
+    ));
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_parallel_chunk_decompression_limit() {
+    use base64::Engine;
+    use std::io::Write;
+
+    // 16 chunks of 16 by 16 tiles, decompressing to 1024 bytes each
+    let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&[0; 1024]).unwrap();
+    let data = base64::engine::general_purpose::STANDARD.encode(encoder.finish().unwrap());
+    let chunks: String = (0..16)
+        .map(|i| {
+            format!(
+                r#"<chunk x="{}" y="0" width="16" height="16">{}</chunk>"#,
+                i * 16,
+                data
+            )
+        })
+        .collect();
+    let map = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="16" height="16" tilewidth="32" tileheight="32" infinite="1">
+ <layer id="1" name="Tile Layer 1" width="16" height="16">
+  <data encoding="base64" compression="zlib">{}</data>
+ </layer>
+</map>"#,
+        chunks
+    );
+    let load = |limit: usize| {
+        let mut loader = Loader::new();
+        loader.options_mut().lenient = true;
+        loader.options_mut().max_decompressed_bytes = Some(limit);
+        loader.load_tmx_map_from_slice(map.as_bytes(), "assets/chunks.tmx")
+    };
+
+    assert!(load(16 * 1024).is_ok());
+    // Every chunk fits within the limit, but not all of them together. Chunks cut short by the
+    // shared budget fail with the limit rather than being kept as incomplete chunks.
+    let err = load(4000).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedSizeExceeded { limit: 4000 }
+    ));
+    let err = load(16 * 1024 - 1).unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::DecompressedSizeExceeded { .. }
+    ));
+}
+
+#[test]
+fn test_loader_clear_buffers() {
+    let key = |map: &Map| {
+        map.get_layer(0)
+            .unwrap()
+            .properties
+            .keys()
+            .find(|key| &***key == "key")
+            .unwrap()
+            .clone()
+    };
+
+    let mut loader = Loader::new();
+    let first = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let zlib = loader.load_tmx_map("assets/tiled_base64_zlib.tmx").unwrap();
+    // Decoding reuses the buffers of the previous loads without mixing up their contents
+    assert_eq!(
+        loader.load_tmx_map("assets/tiled_base64_zlib.tmx").unwrap(),
+        zlib
+    );
+    assert_eq!(
+        loader.load_tmx_map("assets/tiled_base64.tmx").unwrap(),
+        Loader::new()
+            .load_tmx_map("assets/tiled_base64.tmx")
+            .unwrap()
+    );
+
+    loader.clear_buffers();
+    let second = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(second, first);
+    assert!(!Arc::ptr_eq(&key(&first), &key(&second)));
+    assert_eq!(
+        loader.load_tmx_map("assets/tiled_base64_zlib.tmx").unwrap(),
+        zlib
+    );
+}
+
+#[test]
+fn test_load_all_tmx_in_dir() {
+    let maps = Loader::new().load_all_tmx_in_dir("assets/world").unwrap();
+    assert_eq!(
+        maps.keys().map(PathBuf::as_path).collect::<Vec<_>>(),
+        [
+            std::path::Path::new("assets/world/grid/grid-0-0.tmx"),
+            std::path::Path::new("assets/world/grid/grid-0-1.tmx"),
+            std::path::Path::new("assets/world/grid/grid-1-0.tmx"),
+            std::path::Path::new("assets/world/grid/nested/grid-2-0.tmx"),
+            std::path::Path::new("assets/world/hexagonal.tmx"),
+        ]
+    );
+    assert_eq!(
+        maps[std::path::Path::new("assets/world/hexagonal.tmx")].orientation,
+        tiled::Orientation::Hexagonal
+    );
+
+    // Readers that can't list directories can't find maps either
+    let reader = |path: &std::path::Path| -> std::io::Result<_> { std::fs::File::open(path) };
+    let err = Loader::with_reader(reader)
+        .load_all_tmx_in_dir("assets/world")
+        .unwrap_err();
+    match err {
+        Error::ResourceLoadingError { path, err } => {
+            assert_eq!(path, std::path::Path::new("assets/world"));
+            let err = err.downcast_ref::<std::io::Error>().unwrap();
+            assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+
+    // Chained readers list the directories of the readers that can
+    let reader = ChainedResourceReader::new()
+        .with(reader)
+        .with(FilesystemResourceReader::new());
+    let chained = Loader::with_reader(reader)
+        .load_all_tmx_in_dir("assets/world/grid/nested")
+        .unwrap();
+    assert_eq!(chained.len(), 1);
+}
+
+#[test]
+fn test_cache_stats_and_invalidation() {
+    let mut loader = Loader::new();
+    loader.load_tmx_map("assets/templates/example.tmx").unwrap();
+    let stats = loader.cache().stats();
+    assert!(stats.misses > 0);
+    loader.load_tmx_map("assets/templates/example.tmx").unwrap();
+    let reloaded = loader.cache().stats();
+    assert_eq!(reloaded.misses, stats.misses);
+    assert!(reloaded.hits > stats.hits);
+
+    // The template using the tileset goes along with it, but not the other ones
+    let template_count = loader.cache().templates.len();
+    loader
+        .cache_mut()
+        .invalidate("assets/templates/simple_figure.tsx");
+    let cache = loader.cache();
+    assert!(cache
+        .get_tileset("assets/templates/simple_figure.tsx")
+        .is_none());
+    assert!(cache
+        .get_tileset("assets/templates/grass_walls.tsx")
+        .is_some());
+    assert!(cache
+        .get_template("assets/templates/simple_figure.tx")
+        .is_none());
+    assert_eq!(cache.templates.len(), template_count - 1);
+
+    loader
+        .cache_mut()
+        .retain(|_, resource| matches!(resource, tiled::CachedResource::Template(_)));
+    assert!(loader.cache().tilesets.is_empty());
+    assert_eq!(loader.cache().templates.len(), template_count - 1);
+}
+
+#[test]
+fn test_insert_generated_tileset() {
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="1" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" source="../generated/./atlas.tsx"/>
+ <layer id="1" name="Tile Layer 1" width="2" height="1">
+  <data encoding="csv">1,2</data>
+ </layer>
+</map>"#;
+    let reader = |path: &std::path::Path| -> std::io::Result<_> {
+        if path == std::path::Path::new("maps/level.tmx") {
+            Ok(std::io::Cursor::new(MAP))
+        } else {
+            Err(std::io::ErrorKind::NotFound.into())
+        }
+    };
+    let image = Image {
+        source: PathBuf::from("generated/atlas.png"),
+        width: 64,
+        height: 32,
+        transparent_colour: None,
+    };
+    let atlas = Arc::new(
+        TilesetBuilder::new("atlas", image, 32, 32)
+            .source("generated/atlas.tsx")
+            .build()
+            .unwrap(),
+    );
+
+    let mut loader = Loader::with_reader(reader);
+    // Without the tileset, the map refers to a file that doesn't exist
+    assert!(loader.load_tmx_map("maps/level.tmx").is_err());
+
+    loader
+        .cache_mut()
+        .insert_tileset("generated/atlas.tsx", atlas.clone());
+    let map = loader.load_tmx_map("maps/level.tmx").unwrap();
+    assert!(Arc::ptr_eq(&map.tilesets()[0], &atlas));
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(layer.get_tile(1, 0).unwrap().id(), 1);
+}
+
+#[test]
+fn test_cache_iteration() {
+    fn cached_paths<C: ResourceCache>(cache: &C) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        let mut tilesets: Vec<_> = cache.tilesets().map(|(path, _)| path.to_owned()).collect();
+        let mut templates: Vec<_> = cache.templates().map(|(path, _)| path.to_owned()).collect();
+        tilesets.sort();
+        templates.sort();
+        (tilesets, templates)
+    }
+
+    let mut loader = Loader::new();
+    assert_eq!(cached_paths(loader.cache()), (vec![], vec![]));
+    loader.load_tmx_map("assets/templates/example.tmx").unwrap();
+    let (tilesets, templates) = cached_paths(loader.cache());
+    assert_eq!(
+        tilesets,
+        [
+            PathBuf::from("assets/templates/grass_walls.tsx"),
+            PathBuf::from("assets/templates/simple_figure.tsx")
+        ]
+    );
+    assert_eq!(templates.len(), 3);
+    assert!(loader
+        .cache()
+        .tilesets()
+        .all(|(path, tileset)| tileset.source == path));
+}
+
+#[test]
+fn test_skip_images() {
+    let mut loader = Loader::new();
+    loader.options_mut().skip_images = true;
+    let map = loader
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    let tileset = &map.tilesets()[0];
+    assert!(tileset.image.is_none());
+    assert_eq!((tileset.tilecount, tileset.columns), (84, 14));
+    let layer = map.get_layer(1).unwrap().as_image_layer().unwrap();
+    assert!(layer.image.is_none());
+    assert!(!loader
+        .dependencies("assets/tiled_image_layers.tmx")
+        .unwrap()
+        .iter()
+        .any(|dependency| dependency.kind == DependencyKind::Image));
+
+    let with_images = Loader::new()
+        .load_tmx_map("assets/tiled_image_layers.tmx")
+        .unwrap();
+    assert!(with_images.tilesets()[0].image.is_some());
+    assert!(with_images
+        .get_layer(1)
+        .unwrap()
+        .as_image_layer()
+        .unwrap()
+        .image
+        .is_some());
+}
+
+#[test]
+fn test_layer_filter() {
+    fn layer_names<'map>(layers: impl Iterator<Item = tiled::Layer<'map>>) -> Vec<String> {
+        layers.map(|layer| layer.name.clone()).collect()
+    }
+
+    let mut loader = Loader::new();
+    loader.set_layer_filter(|layer| layer.name != "tile-2" && layer.name != "group-2");
+    let map = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(layer_names(map.layers()), ["tile-1", "group-1"]);
+    let group = map.get_layer(1).unwrap();
+    assert!(group.properties.contains_key("key"));
+    assert_eq!(group.as_group_layer().unwrap().layers().len(), 0);
+
+    loader.set_layer_filter(|layer| layer.kind != tiled::LayerKind::Tiles);
+    let map = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(layer_names(map.layers()), ["group-1", "group-2"]);
+    let group = map.get_layer(1).unwrap().as_group_layer().unwrap();
+    assert_eq!(layer_names(group.layers()), ["group-3"]);
+
+    loader.clear_layer_filter();
+    let map = loader
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    assert_eq!(map.layers().len(), 3);
+}
+
+#[test]
+fn test_deferred_tilesets() {
+    let mut loader = Loader::new();
+    loader.options_mut().defer_tilesets = true;
+    let mut map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_none());
+    assert_eq!(
+        map.pending_tilesets().collect::<Vec<_>>(),
+        [(0, std::path::Path::new("assets/tilesheet.tsx"))]
+    );
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tile = layer.get_tile(0, 0).unwrap();
+    assert_eq!(
+        tile.tileset_status(),
+        tiled::TilesetStatus::PendingTileset(std::path::Path::new("assets/tilesheet.tsx"))
+    );
+    assert!(tile.get_tile().is_none());
+    let id = tile.id();
+
+    map.resolve_tilesets(&mut loader).unwrap();
+    assert_eq!(map.pending_tilesets().count(), 0);
+    let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    let tile = layer.get_tile(0, 0).unwrap();
+    assert!(
+        matches!(tile.tileset_status(), tiled::TilesetStatus::Loaded(tileset) if tileset.name == "tilesheet")
+    );
+    assert_eq!(tile.id(), id);
+    assert!(loader.cache().get_tileset("assets/tilesheet.tsx").is_some());
+    assert_eq!(
+        map,
+        Loader::new()
+            .load_tmx_map("assets/tiled_base64_external.tmx")
+            .unwrap()
+    );
+
+    // Tilesets already cached aren't deferred
+    let map = loader
+        .load_tmx_map("assets/tiled_base64_external.tmx")
+        .unwrap();
+    assert_eq!(map.pending_tilesets().count(), 0);
+}
+
+#[test]
+fn test_reapply_template() {
+    let mut loader = Loader::new();
+    let mut map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let original = loader
+        .cache()
+        .get_template("assets/tiled_object_template.tx")
+        .unwrap();
+    let mut template = tiled::Template::clone(&original);
+    template.object.name = "changed".to_owned();
+    template.object.visible = false;
+    template.object.shape = ObjectShape::Rect {
+        width: 16.,
+        height: 8.,
+    };
+    template
+        .object
+        .properties
+        .insert("added".into(), PropertyValue::BoolValue(true));
+
+    assert_eq!(
+        map.reapply_template("assets/./tiled_object_template.tx", &template),
+        2
+    );
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let objects: Vec<_> = layer.objects().collect();
+    assert_eq!(objects[0].name, "changed");
+    assert!(!objects[0].visible);
+    assert_eq!((objects[0].x, objects[0].y), (32., 32.));
+    assert_eq!(
+        objects[0].shape,
+        ObjectShape::Rect {
+            width: 16.,
+            height: 8.
+        }
+    );
+    assert_eq!(
+        objects[0].properties.get("added"),
+        Some(&PropertyValue::BoolValue(true))
+    );
+    assert_eq!(
+        objects[0].properties.get("property"),
+        Some(&PropertyValue::IntValue(1))
+    );
+    // The second object isn't templated, the third overrides the size of the template
+    assert!(objects[1].visible);
+    assert_eq!(
+        objects[2].shape,
+        ObjectShape::Rect {
+            width: 64.,
+            height: 32.
+        }
+    );
+
+    assert_eq!(map.reapply_template("assets/other.tx", &template), 0);
+    map.reapply_template("assets/tiled_object_template.tx", &original);
+    assert_eq!(
+        map,
+        Loader::new()
+            .load_tmx_map("assets/tiled_object_template.tmx")
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_template_library() {
+    let mut loader = Loader::new();
+    let mut map = loader.load_tmx_map("assets/templates/example.tmx").unwrap();
+    let mut library = tiled::TemplateLibrary::from_cache(loader.cache());
+    assert_eq!(library.len(), 3);
+    library
+        .load(&mut loader, "assets/./tiled_object_template.tx")
+        .unwrap();
+    assert_eq!(
+        library
+            .iter()
+            .map(|(path, _)| path.to_owned())
+            .collect::<Vec<_>>(),
+        [
+            PathBuf::from("assets/templates/corner.tx"),
+            PathBuf::from("assets/templates/edge.tx"),
+            PathBuf::from("assets/templates/simple_figure.tx"),
+            PathBuf::from("assets/tiled_object_template.tx"),
+        ]
+    );
+    assert!(Arc::ptr_eq(
+        library.get("assets/tiled_object_template.tx").unwrap(),
+        library.get_by_name("tiled_object_template").unwrap()
+    ));
+    assert!(library.get_by_name("missing").is_none());
+
+    let next_id = map.next_object_id();
+    let object = library
+        .spawn("tiled_object_template", &mut map, 10., 20.)
+        .unwrap();
+    assert_eq!(object.id(), next_id);
+    assert_eq!((object.x, object.y), (10., 20.));
+    assert_eq!(
+        object.properties.get("property"),
+        Some(&PropertyValue::IntValue(1))
+    );
+    assert_eq!(
+        object.shape,
+        ObjectShape::Rect {
+            width: 32.,
+            height: 32.
+        }
+    );
+    assert!(library.spawn("missing", &mut map, 0., 0.).is_none());
+
+    assert!(library.remove("assets/templates/edge.tx").is_some());
+    assert_eq!(library.len(), 3);
+}
+
+#[test]
+fn test_template_overrides() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let objects: Vec<_> = layer.objects().collect();
+
+    assert_eq!(
+        objects[0].template_source(),
+        Some(std::path::Path::new("assets/tiled_object_template.tx"))
+    );
+    let overrides = objects[0].template_overrides().unwrap();
+    assert_eq!(overrides, &tiled::TemplateOverrides::default());
+    assert!(objects[0].properties.contains_key("property"));
+
+    assert!(objects[1].template_source().is_none());
+    assert!(objects[1].template_overrides().is_none());
+
+    let overrides = objects[2].template_overrides().unwrap();
+    assert_eq!((overrides.width, overrides.height), (Some(64.), Some(32.)));
+    assert!(overrides.name.is_none() && overrides.tile.is_none() && overrides.shape.is_none());
+}
+
+#[test]
+fn test_flood_fill() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64.tmx")
+        .unwrap();
+    let layer = map.get_layer(0).unwrap().id();
+    let (width, height) = (map.width as i32, map.height as i32);
+
+    // Clear the layer, then split it with a wall of tiles at x = 2
+    let mut transaction = map.edit();
+    for y in 0..height {
+        for x in 0..width {
+            let tile = if x == 2 {
+                Some(LayerTileData::new(0, 1))
+            } else {
+                None
+            };
+            transaction.set_tile(layer, x, y, tile).unwrap();
+        }
+    }
+    transaction.commit();
+    let original = map.clone();
+
+    let tile = LayerTileData::new(0, 5);
+    let mut transaction = map.edit();
+    assert_eq!(
+        transaction.flood_fill(layer, 0, 0, Some(tile)),
+        Ok(2 * height as usize)
+    );
+    assert_eq!(transaction.flood_fill(layer, 1, 3, Some(tile)), Ok(0));
+    assert_eq!(
+        transaction.flood_fill(layer, width, 0, Some(tile)),
+        Err(EditError::OutOfBounds {
+            layer,
+            x: width,
+            y: 0
+        })
+    );
+    let record = transaction.commit();
+    assert_eq!(record.len(), 2 * height as usize);
+
+    let tiles = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(tiles.get_tile(1, height - 1).unwrap().id(), 5);
+    assert_eq!(tiles.get_tile(2, 0).unwrap().id(), 1);
+    assert!(tiles.get_tile(3, 0).is_none());
+    record.undo(&mut map).unwrap();
+    assert_eq!(map, original);
+}
+
+#[test]
+fn test_map_resize() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let original = map.clone();
+    let tile_at = |map: &tiled::Map, x, y| {
+        map.get_layer(0)
+            .unwrap()
+            .as_tile_layer()
+            .unwrap()
+            .get_tile(x, y)
+            .map(|tile| tile.id())
+    };
+    let object = |map: &tiled::Map| {
+        let object = map
+            .get_layer(1)
+            .unwrap()
+            .as_object_layer()
+            .unwrap()
+            .get_object(0)
+            .unwrap();
+        (object.x, object.y)
+    };
+
+    // Grow by 4 tiles in each direction around the center, then shrink back
+    map.resize(map.width + 8, map.height + 8, tiled::ResizeAnchor::Center);
+    assert_eq!(
+        (map.width, map.height),
+        (original.width + 8, original.height + 8)
+    );
+    assert_eq!(tile_at(&map, 4, 4), tile_at(&original, 0, 0));
+    assert_eq!(tile_at(&map, 0, 0), None);
+    let (x, y) = object(&original);
+    assert_eq!(
+        object(&map),
+        (
+            x + 4. * map.tile_width as f32,
+            y + 4. * map.tile_height as f32
+        )
+    );
+    map.crop(tiled::MapArea {
+        x: 4,
+        y: 4,
+        width: original.width,
+        height: original.height,
+    });
+    assert_eq!(map, original);
+
+    // Cropping an infinite map keeps only the tiles within the area
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_base64_zlib_infinite.tmx")
+        .unwrap();
+    let original = map.clone();
+    let area = tiled::MapArea {
+        x: -10,
+        y: 5,
+        width: 20,
+        height: 3,
+    };
+    map.crop(area);
+    let tiles = match map.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        tiled::TileLayer::Infinite(tiles) => tiles,
+        _ => panic!("expected an infinite layer"),
+    };
+    let before = match original.get_layer(0).unwrap().as_tile_layer().unwrap() {
+        tiled::TileLayer::Infinite(tiles) => tiles,
+        _ => panic!("expected an infinite layer"),
+    };
+    for y in -20..20 {
+        for x in -20..40 {
+            let inside = (0..20).contains(&x) && (0..3).contains(&y);
+            let expected = if inside {
+                before.get_tile_data(x - 10, y + 5)
+            } else {
+                None
+            };
+            assert_eq!(tiles.get_tile_data(x, y), expected);
+        }
+    }
+    assert!(tiles.chunks().len() <= 2);
+}
+
+#[test]
+fn test_map_merge() {
+    let mut loader = Loader::new();
+    let mut map = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let other = loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let layer_count = map.layers().len();
+
+    let ids = map.merge_at(&other, tiled::MergeOffset::Tiles(1, 2));
+    assert_eq!(ids.len(), other.layers().len());
+    assert_eq!(map.layers().len(), layer_count * 2);
+    assert_eq!(map.tilesets().len(), other.tilesets().len());
+
+    let merged = map.get_layer(layer_count).unwrap();
+    assert_eq!(merged.id(), ids[0]);
+    assert_eq!(
+        merged.name,
+        format!("{} 2", other.get_layer(0).unwrap().name)
+    );
+    let tile_at = |layer: tiled::Layer, x, y| {
+        layer
+            .as_tile_layer()
+            .unwrap()
+            .get_tile(x, y)
+            .map(|tile| tile.id())
+    };
+    assert_eq!(
+        tile_at(merged, 1, 2),
+        tile_at(other.get_layer(0).unwrap(), 0, 0)
+    );
+
+    // Merged objects get new IDs and move by the matching amount of pixels
+    let objects = map
+        .get_layer(layer_count + 1)
+        .unwrap()
+        .as_object_layer()
+        .unwrap();
+    let original = other.get_layer(1).unwrap().as_object_layer().unwrap();
+    let mut object_ids: Vec<u32> = map
+        .layers()
+        .filter_map(|layer| layer.as_object_layer())
+        .flat_map(|layer| {
+            layer
+                .objects()
+                .map(|object| object.id())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    object_ids.sort_unstable();
+    object_ids.dedup();
+    assert_eq!(object_ids.len(), 2 * original.objects().len());
+    for (object, original) in objects.objects().zip(original.objects()) {
+        assert_ne!(object.id(), original.id());
+        assert_eq!(object.x, original.x + map.tile_width as f32);
+        assert_eq!(object.y, original.y + 2. * map.tile_height as f32);
+        assert_eq!(object.tile_data(), original.tile_data());
+    }
+
+    // Tilesets that aren't in the map yet are added, and tiles use them
+    let other = loader.load_tmx_map("assets/tiled_base64.tmx").unwrap();
+    map.merge_at(&other, tiled::MergeOffset::Pixels(8., 0.));
+    assert_eq!(map.tilesets().len(), 2);
+    assert_eq!(map.tilesets()[1].name, "tilesheet 2");
+    let merged = map.get_layer(2 * layer_count).unwrap();
+    assert_eq!(merged.offset_x, other.get_layer(0).unwrap().offset_x + 8.);
+    let tiles = match merged.as_tile_layer().unwrap() {
+        tiled::TileLayer::Finite(tiles) => tiles,
+        _ => panic!("expected a finite layer"),
+    };
+    let tile = (0..tiles.height() as i32)
+        .flat_map(|y| (0..tiles.width() as i32).map(move |x| (x, y)))
+        .find_map(|(x, y)| tiles.get_tile_data(x, y))
+        .unwrap();
+    assert_eq!(tile.tileset_index(), 1);
+}
+
+#[test]
+fn test_depth_key() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let object = |map: &tiled::Map, index| {
+        let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+        tiled::ObjectData::clone(&layer.get_object(index).unwrap())
+    };
+
+    // The tile object stands on the bottom of the tile at (0, 0), and rects on their bottom edge
+    let tile_object = object(&map, 1);
+    assert_eq!(map.depth_key(&tile_object), map.depth_key((0, 0)));
+    assert!(map.depth_key(&tile_object) < map.depth_key((0, 1)));
+    let mut rect = ObjectBuilder::new(ObjectShape::Rect {
+        width: 32.,
+        height: 32.,
+    })
+    .build(&mut map);
+    rect.y = 32.;
+    assert_eq!(map.depth_key(&rect).y, map.depth_key((0, 1)).y);
+    rect.rotation = 90.;
+    assert!((map.depth_key(&rect).y - 64.).abs() < 1e-3);
+
+    map.orientation = tiled::Orientation::Isometric;
+    let mut tiles = vec![(1, 1), (0, 1), (1, 0), (0, 0)];
+    tiles.sort_by_key(|&tile| map.depth_key(tile));
+    assert_eq!(tiles, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+    assert_eq!(map.depth_key((1, 0)).y, map.depth_key((0, 1)).y);
+
+    // Objects are positioned in tile heights along the axes of isometric tiles
+    let mut tile_object = tile_object;
+    tile_object.x = 32.;
+    assert_eq!(map.depth_key(&tile_object), map.depth_key((0, 0)));
+    assert!(map.depth_key(&tile_object) < map.depth_key((1, 0)));
+}
+
+#[test]
+fn test_object_by_name() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let layer = map.get_layer(1).unwrap().id();
+    let spawn_ids = |map: &tiled::Map| -> Vec<u32> {
+        let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+        objects
+            .objects_by_name("spawn")
+            .map(|object| object.id())
+            .collect()
+    };
+    assert!(spawn_ids(&map).is_empty());
+
+    // The index follows the objects added to and removed from the layer
+    let objects: Vec<_> = (0..3)
+        .map(|x| {
+            ObjectBuilder::new(ObjectShape::Point(x as f32, 0.))
+                .name("spawn")
+                .build(&mut map)
+        })
+        .collect();
+    let mut transaction = map.edit();
+    let ids: Vec<u32> = objects
+        .into_iter()
+        .map(|object| transaction.add_object(layer, object).unwrap())
+        .collect();
+    transaction.commit();
+    assert_eq!(spawn_ids(&map), ids);
+
+    let mut transaction = map.edit();
+    transaction.remove_object(ids[0]).unwrap();
+    transaction.commit();
+    assert_eq!(spawn_ids(&map), &ids[1..]);
+    let objects = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    assert_eq!(objects.object_by_name("spawn").unwrap().id(), ids[1]);
+    assert!(objects.object_by_name("missing").is_none());
+}
+
+#[test]
+fn test_world_aabb() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let bounds = |aabb: tiled::Aabb| {
+        [aabb.min_x, aabb.min_y, aabb.max_x, aabb.max_y].map(|value| value.round())
+    };
+
+    // Rotated ellipses get their exact bounds rather than those of their rotated box
+    let mut ellipse = ObjectBuilder::new(ObjectShape::Ellipse {
+        width: 64.,
+        height: 32.,
+    })
+    .position(100., 100.)
+    .build(&mut map);
+    assert_eq!(bounds(ellipse.world_aabb(&map)), [100., 100., 164., 132.]);
+    ellipse.rotation = 90.;
+    assert_eq!(bounds(ellipse.world_aabb(&map)), [68., 100., 100., 164.]);
+
+    let polygon = ObjectBuilder::new(ObjectShape::Polygon {
+        points: vec![(0., 0.), (10., -5.), (-3., 7.)],
+    })
+    .position(50., 50.)
+    .build(&mut map);
+    assert_eq!(bounds(polygon.world_aabb(&map)), [47., 45., 60., 57.]);
+
+    // The 32x32 tile object at (0, 32) is anchored by its bottom, and centered on isometric maps
+    let layer = map.get_layer(1).unwrap().as_object_layer().unwrap();
+    let tile_object = tiled::ObjectData::clone(&layer.get_object(1).unwrap());
+    assert_eq!(bounds(tile_object.world_aabb(&map)), [0., 0., 32., 32.]);
+    map.orientation = tiled::Orientation::Isometric;
+    let (x, y) = (
+        (map.height as f32 - 1.) * map.tile_width as f32 / 2.,
+        map.tile_height as f32 / 2.,
+    );
+    assert_eq!(
+        bounds(tile_object.world_aabb(&map)),
+        [x - 16., y - 32., x + 16., y].map(|value| value.round())
+    );
+}
+
+#[test]
+fn test_effective_properties() {
+    let mut map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let outer = map.layer_by_path("group-2").unwrap().id();
+    let inner = map.layer_by_path("group-2/group-3").unwrap().id();
+    let layer = tiled::LayerBuilder::objects("triggers", vec![]).build(&mut map);
+    let layer = map.insert_layer(Some(inner), 0, layer).unwrap();
+
+    let mut transaction = map.edit();
+    transaction
+        .set_property(
+            PropertyTarget::Layer(outer),
+            "collision",
+            Some(PropertyValue::BoolValue(true)),
+        )
+        .unwrap();
+    transaction.commit();
+
+    // The new layer inherits `collision` from the outer group and `key` from the inner one
+    let properties = map.layer_by_id(layer).unwrap().effective_properties();
+    assert_eq!(properties.len(), 2);
+    assert_eq!(properties["collision"], PropertyValue::BoolValue(true));
+    assert_eq!(
+        properties["key"],
+        PropertyValue::StringValue("value6".to_owned())
+    );
+
+    // Layers outside of the groups don't inherit anything
+    let top = map.get_layer(0).unwrap();
+    assert_eq!(top.effective_properties(), top.properties);
+}
+
+#[test]
+fn test_map_query() {
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_group_layers.tmx")
+        .unwrap();
+    let names = |query: tiled::MapQuery| -> Vec<String> {
+        query
+            .run()
+            .into_iter()
+            .map(|found| match found {
+                tiled::QueryMatch::Layer(layer) => layer.name.clone(),
+                tiled::QueryMatch::Object(_, object) => object.name.clone(),
+            })
+            .collect()
+    };
+
+    // Layers within groups are searched even if groups aren't
+    let value = |value: &str| PropertyValue::StringValue(value.to_owned());
+    assert_eq!(
+        names(map.query().with_property("key", value("value3"))),
+        ["tile-3"]
+    );
+    assert_eq!(
+        names(
+            map.query()
+                .layers(tiled::LayerKind::Group)
+                .has_property("key")
+        ),
+        ["group-1", "group-2", "group-3"]
+    );
+    assert!(names(map.query().layers(tiled::LayerKind::Tiles).name("group-2")).is_empty());
+    assert!(names(map.query().only_objects().has_property("key")).is_empty());
+
+    let map = Loader::new()
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    let found = map
+        .query()
+        .with_property("property", PropertyValue::IntValue(1))
+        .run();
+    assert_eq!(found.len(), 2);
+    assert!(found
+        .iter()
+        .all(|found| found.as_object().is_some() && found.as_layer().is_none()));
+}
+
+#[test]
+fn test_parse_observers() {
+    let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut loader = Loader::new();
+    let tilesets = events.clone();
+    loader.on_tileset_parsed(move |tileset| {
+        tilesets
+            .lock()
+            .unwrap()
+            .push(format!("tileset {}", tileset.source.display()))
+    });
+    let layers = events.clone();
+    loader
+        .on_layer_parsed(move |layer| layers.lock().unwrap().push(format!("layer {}", layer.id())));
+    let objects = events.clone();
+    loader.on_object_parsed(move |object| {
+        let property = object.properties.get("property").cloned();
+        objects
+            .lock()
+            .unwrap()
+            .push(format!("object {} {:?}", object.id(), property))
+    });
+    loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+
+    // Objects come before their layer, with templates applied
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "tileset assets/tilesheet.tsx",
+            "layer 1",
+            "tileset assets/tilesheet_template.tsx",
+            "object 1 Some(IntValue(1))",
+            "object 2 None",
+            "object 3 Some(IntValue(1))",
+            "layer 2",
+        ]
+    );
+
+    // Cached tilesets and templates aren't parsed again
+    events.lock().unwrap().clear();
+    loader.clear_parse_observers();
+    loader
+        .load_tmx_map("assets/tiled_object_template.tmx")
+        .unwrap();
+    assert!(events.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_custom_property_types() {
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="1" height="1" tilewidth="32" tileheight="32">
+ <properties>
+  <property name="spin" type="quaternion" value="1,0,0,0"/>
+  <property name="hp" type="int" value="3"/>
+ </properties>
+</map>"#;
+    let reader = |_: &std::path::Path| -> std::io::Result<_> { Ok(std::io::Cursor::new(MAP)) };
+    let parse_quaternion = |value: &str| -> Result<Vec<f32>, std::num::ParseFloatError> {
+        value.split(',').map(str::parse).collect()
+    };
+
+    let mut loader = Loader::with_reader(reader);
+    loader.register_property_type("quaternion", parse_quaternion);
+    // Built-in types can't be replaced
+    loader.register_property_type("int", |_: &str| Err::<(), _>("unused"));
+    let map = loader.load_tmx_map("map.tmx").unwrap();
+    assert_eq!(map.properties["hp"], PropertyValue::IntValue(3));
+    let custom = match &map.properties["spin"] {
+        PropertyValue::Custom(custom) => custom,
+        value => panic!("expected a custom value, got {:?}", value),
+    };
+    assert_eq!(custom.type_name, "quaternion");
+    assert_eq!(custom.value, "1,0,0,0");
+    assert_eq!(custom.downcast_ref(), Some(&vec![1f32, 0., 0., 0.]));
+    assert_eq!(custom.downcast_ref::<String>(), None);
+    assert_eq!(
+        map.properties["spin"],
+        PropertyValue::Custom(tiled::CustomValue::new("quaternion", "1,0,0,0", ()))
+    );
+
+    // Values the parser rejects are invalid
+    let mut loader = Loader::with_reader(reader);
+    loader.register_property_type("quaternion", |_: &str| Err::<(), _>("not a quaternion"));
+    let err = loader.load_tmx_map("map.tmx").unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::InvalidPropertyValue { description } if description == "not a quaternion"
+    ));
+
+    loader.clear_property_types();
+    let err = loader.load_tmx_map("map.tmx").unwrap_err();
+    assert!(matches!(
+        err.inner(),
+        Error::UnknownPropertyType { type_name } if type_name == "quaternion"
+    ));
+}
+
+#[test]
+fn test_error_categories() {
+    use tiled::ErrorCategory;
+
+    let err = Loader::new()
+        .load_tmx_map("assets/missing.tmx")
+        .unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::Io);
+    assert_eq!(err.path(), Some(std::path::Path::new("assets/missing.tmx")));
+
+    let reader = |_: &std::path::Path| -> std::io::Result<_> {
+        Ok(std::io::Cursor::new(
+            "<map version=\"1.10\">\n  <layer/>\n</map>",
+        ))
+    };
+    let err = Loader::with_reader(reader)
+        .load_tmx_map("map.tmx")
+        .unwrap_err();
+    // Located errors are categorized by the error they wrap
+    assert!(matches!(err, Error::Located { .. }));
+    assert_eq!(err.category(), ErrorCategory::Syntax);
+    assert_eq!(err.code(), "E201");
+    assert_eq!(err.code(), err.inner().code());
+    assert_eq!(err.path(), Some(std::path::Path::new("map.tmx")));
+
+    let errors = Error::Multiple(vec![
+        Error::UnknownPropertyType {
+            type_name: "quaternion".to_owned(),
+        },
+        Error::InvalidTileFound,
+    ]);
+    assert_eq!(errors.category(), ErrorCategory::Unsupported);
+    assert_eq!(errors.code(), "E402");
+    assert_eq!(Error::Multiple(Vec::new()).code(), "E000");
+    assert_eq!(Error::InvalidTileFound.path(), None);
+}
+
+#[test]
+fn test_try_load_tmx_map() {
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="32" tileheight="32">
+ <tileset firstgid="1" name="tiles" tilewidth="32" tileheight="32" tilecount="4" columns="2">
+  <image source="tiles.png" width="64" height="64"/>
+ </tileset>
+ <tileset firstgid="5" source="missing.tsx"/>
+ <layer id="1" name="ground" width="2" height="2">
+  <data encoding="csv">1,2,3,4</data>
+ </layer>
+ <layer id="2" name="broken" width="2" height="2">
+  <data encoding="csv">1,oops,3,4</data>
+ </layer>
+ <objectgroup id="3" name="objects">
+  <object id="1" x="0" y="0" width="8" height="8"/>
+ </objectgroup>
+</map>"#;
+    let reader = |path: &std::path::Path| -> std::io::Result<_> {
+        match path.to_str() {
+            Some("map.tmx") => Ok(std::io::Cursor::new(MAP)),
+            _ => Err(std::io::ErrorKind::NotFound.into()),
+        }
+    };
+    let mut loader = Loader::with_reader(reader);
+    assert!(loader.load_tmx_map("map.tmx").is_err());
+
+    // The broken tileset is left out, along with the tiles of the broken layer
+    let (map, diagnostics) = loader.try_load_tmx_map("map.tmx");
+    let map = map.unwrap();
+    assert_eq!(map.tilesets().len(), 1);
+    let names: Vec<_> = map.layers().map(|layer| layer.name.clone()).collect();
+    assert_eq!(names, ["ground", "broken", "objects"]);
+    let ground = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+    assert_eq!(ground.get_tile(1, 1).map(|tile| tile.id()), Some(3));
+    let broken = map.get_layer(1).unwrap().as_tile_layer().unwrap();
+    assert!(broken.get_tile(0, 0).is_none());
+    assert_eq!(diagnostics.len(), 2);
+    assert!(diagnostics.iter().all(|diagnostic| diagnostic.is_error()));
+    assert_eq!(diagnostics[1].path(), Some(std::path::Path::new("map.tmx")));
+    assert_eq!(diagnostics[1].position().map(|(line, _)| line), Some(11));
+
+    // The options of the loader are left as they were
+    assert!(!loader.options().collect_errors);
+
+    let (map, diagnostics) = loader.try_load_tmx_map("missing.tmx");
+    assert!(map.is_none());
+    assert_eq!(diagnostics.len(), 1);
+    assert!(matches!(
+        &diagnostics[0],
+        tiled::Diagnostic::Error(Error::ResourceLoadingError { .. })
+    ));
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_extra_fields() {
+    use serde_json::json;
+    use tiled::World;
+
+    let world = World::parse(
+        "game.world",
+        br#"{
+            "maps": [
+                {"fileName": "a.tmx", "x": 0, "y": 0, "region": "north", "music": {"track": 3}},
+                {"fileName": "b.tmx", "x": 320.0, "y": 0}
+            ],
+            "type": "world",
+            "version": 2
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(world.extra.len(), 1);
+    assert_eq!(world.extra["version"], json!(2));
+    assert_eq!(world.maps[0].extra["region"], json!("north"));
+    assert_eq!(world.maps[0].extra["music"], json!({"track": 3}));
+    // Known fields aren't extras
+    assert!(world.maps[1].extra.is_empty());
+    assert_eq!(world.maps[1].x, 320);
+}
+
+#[cfg(feature = "world")]
+#[test]
+fn test_world_objects() {
+    use tiled::World;
+
+    let map = |objects: &str| {
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" renderorder="right-down" width="4" height="4" tilewidth="16" tileheight="16" infinite="0" nextlayerid="3" nextobjectid="4">
+ <group id="1" name="group">
+  <objectgroup id="2" name="objects">{}</objectgroup>
+ </group>
+</map>"#,
+            objects
+        );
+        Loader::new()
+            .load_tmx_map_from_slice(xml.as_bytes(), "assets/world.tmx")
+            .unwrap()
+    };
+    let maps = [
+        map(r#"<object id="1" class="spawn" x="8" y="8"/>
+            <object id="2" x="0" y="0"><properties><property name="door" type="object" value="3"/></properties></object>"#),
+        map(r#"<object id="1" class="spawn" x="4" y="0"/>
+            <object id="3" class="door" x="16" y="16"/>"#),
+    ];
+    let world = World::parse(
+        "assets/game.world",
+        br#"{"maps": [
+            {"fileName": "a.tmx", "x": 0, "y": 0},
+            {"fileName": "b.tmx", "x": 64, "y": 32}
+        ]}"#,
+    )
+    .unwrap();
+
+    let objects = world.objects(&maps);
+    assert_eq!(objects.len(), 4);
+    let spawns: Vec<_> = objects
+        .with_class("spawn")
+        .map(|spawn| (spawn.map_index, spawn.x, spawn.y))
+        .collect();
+    assert_eq!(spawns, [(0, 8., 8.), (1, 68., 32.)]);
+    assert_eq!(objects.with_id(1).count(), 2);
+    assert_eq!(objects.get(1, 1).unwrap().x, 68.);
+    assert!(objects.get(0, 3).is_none());
+    assert_eq!(objects.with_class("boss").count(), 0);
+
+    // The door isn't in the map of the property, but only one other map has an object 3
+    let source = objects.get(0, 2).unwrap();
+    let door = objects
+        .resolve_object_property(0, &source.object.properties["door"])
+        .unwrap();
+    assert_eq!((door.map_index, door.object.id()), (1, 3));
+    // Objects of the same map come first, and ambiguous references resolve to nothing
+    let spawn = objects.resolve_object_property(1, &tiled::PropertyValue::ObjectValue(1));
+    assert_eq!(spawn.unwrap().map_index, 1);
+    let maps = [maps[0].clone(), maps[1].clone(), maps[1].clone()];
+    let world = World::parse(
+        "assets/game.world",
+        br#"{"maps": [
+            {"fileName": "a.tmx", "x": 0, "y": 0},
+            {"fileName": "b.tmx", "x": 64, "y": 0},
+            {"fileName": "c.tmx", "x": 128, "y": 0}
+        ]}"#,
+    )
+    .unwrap();
+    let objects = world.objects(&maps);
+    assert!(objects
+        .resolve_object_property(0, &source.object.properties["door"])
+        .is_none());
+}
+
+#[test]
+fn test_occupancy_bitset() {
+    for path in [
+        "assets/tiled_base64_zlib.tmx",
+        "assets/tiled_base64_zlib_infinite.tmx",
+    ] {
+        let map = Loader::new().load_tmx_map(path).unwrap();
+        let layer = map.get_layer(0).unwrap().as_tile_layer().unwrap();
+        let occupied = layer.occupancy_bitset();
+        let (left, top) = occupied.origin();
+        let mut count = 0;
+        for y in top..top + occupied.height() as i32 {
+            for x in left..left + occupied.width() as i32 {
+                assert_eq!(occupied.is_occupied(x, y), layer.get_tile(x, y).is_some());
+                count += layer.get_tile(x, y).is_some() as usize;
+            }
+        }
+        assert!(count > 0);
+        assert_eq!(occupied.count(), count);
+        assert_eq!(occupied.iter().count(), count);
+        assert!(occupied.iter().all(|(x, y)| layer.get_tile(x, y).is_some()));
+        assert!(!occupied.is_occupied(left - 1, top));
+        assert_eq!(occupied.row(top).unwrap().len(), occupied.words_per_row());
+        assert!(occupied.row(top + occupied.height() as i32).is_none());
+
+        let (x, y) = occupied.iter().next().unwrap();
+        let id = layer.get_tile(x, y).unwrap().id();
+        let same = layer.occupancy_bitset_filtered(|tile| tile.id() == id);
+        assert!(same.is_occupied(x, y));
+        assert!(same
+            .iter()
+            .all(|(x, y)| layer.get_tile(x, y).unwrap().id() == id));
+        let none = layer.occupancy_bitset_filtered(|_| false);
+        assert_eq!((none.count(), none.width()), (0, occupied.width()));
+    }
+}
+
+#[cfg(all(feature = "bevy", feature = "world"))]
+#[test]
+fn test_bevy_world_loader() {
+    use bevy_app::{App, TaskPoolPlugin};
+    use bevy_asset::{
+        io::{
+            memory::{Dir, MemoryAssetReader},
+            AssetSourceBuilder, AssetSourceId,
+        },
+        AssetApp, AssetPlugin, AssetServer, Assets, Handle,
+    };
+    use std::path::Path;
+    use tiled::bevy::{TiledMap, TiledPlugin, TiledWorld};
+
+    let dir = Dir::default();
+    dir.insert_asset_text(
+        Path::new("worlds/overworld.world"),
+        r#"{"maps": [
+            {"fileName": "../maps/a.tmx", "x": 0, "y": 0},
+            {"fileName": "../maps/b.tmx", "x": 320, "y": 0}
+        ], "type": "world"}"#,
+    );
+    for (name, width) in [("maps/a.tmx", 10), ("maps/b.tmx", 20)] {
+        dir.insert_asset_text(
+            Path::new(name),
+            &format!(
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="{}" height="10" tilewidth="32" tileheight="32" infinite="0"/>"#,
+                width
+            ),
+        );
+    }
+    let mut app = App::new();
+    let root = dir.clone();
+    app.register_asset_source(
+        AssetSourceId::Default,
+        AssetSourceBuilder::new(move || Box::new(MemoryAssetReader { root: root.clone() })),
+    )
+    .add_plugins((
+        TaskPoolPlugin::default(),
+        AssetPlugin {
+            watch_for_changes_override: Some(false),
+            use_asset_processor_override: Some(false),
+            ..Default::default()
+        },
+        TiledPlugin,
+    ));
+
+    let handle: Handle<TiledWorld> = app
+        .world()
+        .resource::<AssetServer>()
+        .load("worlds/overworld.world");
+    let mut loaded = false;
+    for _ in 0..10000 {
+        app.update();
+        let server = app.world().resource::<AssetServer>();
+        assert!(!server.load_state(&handle).is_failed());
+        if server.is_loaded_with_dependencies(&handle) {
+            loaded = true;
+            break;
+        }
+    }
+    assert!(loaded);
+
+    let worlds = app.world().resource::<Assets<TiledWorld>>();
+    let world = worlds.get(&handle).unwrap();
+    assert_eq!(world.world_type.as_deref(), Some("world"));
+    assert_eq!(world.maps.len(), 2);
+    let maps = app.world().resource::<Assets<TiledMap>>();
+    let widths: Vec<u32> = world
+        .maps
+        .iter()
+        .map(|map| maps.get(map).unwrap().width)
+        .collect();
+    assert_eq!(widths, [10, 20]);
+}